@@ -0,0 +1,142 @@
+mod report;
+mod workload;
+
+use clap::Parser;
+use duva::domains::query_io::QueryIO;
+use duva::prelude::{anyhow, tokio};
+use duva_client::DuvaClient;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use report::Stats;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use workload::{Mix, Op, Workload};
+
+#[derive(Parser)]
+#[command(name = "duva-benchmark", about = "Load generator for a duva cluster")]
+#[clap(disable_help_flag = true)]
+struct Args {
+    #[arg(short, long, default_value = "127.0.0.1")]
+    host: String,
+    #[arg(short, long, default_value = "6000")]
+    port: u16,
+    #[arg(short = 'a', long)]
+    pass: Option<String>,
+    /// Concurrent client sessions
+    #[arg(short, long, default_value = "50")]
+    clients: usize,
+    /// Requests in total
+    #[arg(short = 'n', long, default_value = "100000")]
+    requests: u64,
+    /// Requests each client sends in a single round trip
+    #[arg(short = 'P', long, default_value = "1")]
+    pipeline: u64,
+    /// Keys picked at random from, as `key:<0..keyspace>`
+    #[arg(short = 'r', long, default_value = "10000")]
+    keyspace: u64,
+    /// Size of the values SET writes, in bytes
+    #[arg(short = 'd', long, default_value = "3")]
+    data_size: usize,
+    /// Share of the load each command takes
+    #[arg(short = 't', long, default_value = "set=1,get=1")]
+    mix: Mix,
+    /// Keys each MGET reads
+    #[arg(long, default_value = "10")]
+    mget_keys: usize,
+    /// Print help
+    #[arg(long, action = clap::ArgAction::HelpLong)]
+    help: Option<bool>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    anyhow::ensure!(args.clients > 0 && args.pipeline > 0, "clients and pipeline must be above 0");
+    anyhow::ensure!(args.keyspace > 0, "keyspace must be above 0");
+
+    let address = format!("{}:{}", args.host, args.port);
+    let workload = Arc::new(Workload {
+        mix: args.mix,
+        keyspace: args.keyspace,
+        value: "x".repeat(args.data_size),
+        mget_keys: args.mget_keys.max(1),
+    });
+    // * Clients take their next pipeline from what is left, so they finish about together
+    let remaining = Arc::new(AtomicU64::new(args.requests));
+
+    let mut clients = Vec::with_capacity(args.clients);
+    for _ in 0..args.clients {
+        clients.push(DuvaClient::connect_with_password(&address, args.pass.clone()).await?);
+    }
+
+    let started = Instant::now();
+    let tasks: Vec<_> = clients
+        .into_iter()
+        .map(|client| {
+            tokio::spawn(run_client(client, workload.clone(), remaining.clone(), args.pipeline))
+        })
+        .collect();
+    let mut stats: BTreeMap<Op, Stats> = BTreeMap::new();
+    for task in tasks {
+        for (op, client_stats) in task.await?? {
+            stats.entry(op).or_default().merge(client_stats);
+        }
+    }
+    let elapsed = started.elapsed();
+
+    for (op, op_stats) in stats {
+        println!("{}", op_stats.report(op.name(), elapsed));
+    }
+    println!(
+        "{} requests in {:.2} seconds over {} clients with pipeline {}: {:.2} requests per second",
+        args.requests,
+        elapsed.as_secs_f64(),
+        args.clients,
+        args.pipeline,
+        args.requests as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
+
+async fn run_client(
+    mut client: DuvaClient,
+    workload: Arc<Workload>,
+    remaining: Arc<AtomicU64>,
+    pipeline: u64,
+) -> anyhow::Result<BTreeMap<Op, Stats>> {
+    let mut stats: BTreeMap<Op, Stats> = BTreeMap::new();
+    let mut rng = StdRng::from_os_rng();
+    loop {
+        let Ok(left) = remaining.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+            (left > 0).then(|| left - pipeline.min(left))
+        }) else {
+            return Ok(stats);
+        };
+        let batch = pipeline.min(left);
+
+        let ops: Vec<Op> = (0..batch).map(|_| workload.mix.pick(&mut rng)).collect();
+        let commands: Vec<Vec<String>> =
+            ops.iter().map(|op| workload.command(*op, &mut rng)).collect();
+        let commands: Vec<Vec<&str>> =
+            commands.iter().map(|command| command.iter().map(String::as_str).collect()).collect();
+        let commands: Vec<&[&str]> = commands.iter().map(Vec::as_slice).collect();
+
+        // * Each request of a pipeline takes as long as the round trip that carried it
+        let sent = Instant::now();
+        let replies = client.pipeline(&commands).await;
+        let latency = sent.elapsed();
+        for (i, op) in ops.into_iter().enumerate() {
+            let failed = match &replies {
+                | Ok(replies) => matches!(replies[i], QueryIO::Err(_)),
+                | Err(_) => true,
+            };
+            stats.entry(op).or_default().record(latency, failed);
+        }
+        if replies.is_err() {
+            // * Gives a failing cluster a moment instead of spinning through the remaining requests
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// Latencies of the requests of one command, and how many of them failed.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+impl Stats {
+    pub(crate) fn record(&mut self, latency: Duration, failed: bool) {
+        self.latencies.push(latency);
+        self.errors += failed as u64;
+    }
+
+    pub(crate) fn merge(&mut self, other: Stats) {
+        self.latencies.extend(other.latencies);
+        self.errors += other.errors;
+    }
+
+    pub(crate) fn requests(&self) -> usize {
+        self.latencies.len()
+    }
+
+    pub(crate) fn report(mut self, name: &str, elapsed: Duration) -> String {
+        self.latencies.sort_unstable();
+        let ms = |latency: Duration| latency.as_secs_f64() * 1000.0;
+        let percentile = |p: f64| ms(percentile(&self.latencies, p));
+        format!(
+            "====== {name} ======\n  \
+             {} requests completed in {:.2} seconds, {} errors\n  \
+             {:.2} requests per second\n  \
+             latency (ms): p50={:.3} p95={:.3} p99={:.3} p99.9={:.3} max={:.3}\n",
+            self.requests(),
+            elapsed.as_secs_f64(),
+            self.errors,
+            self.requests() as f64 / elapsed.as_secs_f64(),
+            percentile(50.0),
+            percentile(95.0),
+            percentile(99.0),
+            percentile(99.9),
+            percentile(100.0),
+        )
+    }
+}
+
+// * Nearest-rank percentile of latencies sorted in ascending order
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_takes_nearest_rank() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&latencies, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&latencies, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&latencies, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_report_counts_requests_and_errors() {
+        let mut stats = Stats::default();
+        stats.record(Duration::from_millis(2), false);
+        let mut other = Stats::default();
+        other.record(Duration::from_millis(4), true);
+        stats.merge(other);
+
+        let report = stats.report("GET", Duration::from_secs(1));
+        assert!(report.contains("2 requests completed in 1.00 seconds, 1 errors"), "{report}");
+        assert!(report.contains("2.00 requests per second"), "{report}");
+        assert!(report.contains("p50=2.000") && report.contains("max=4.000"), "{report}");
+    }
+}
@@ -0,0 +1,148 @@
+use duva::prelude::anyhow;
+use rand::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Op {
+    Set,
+    Get,
+    Incr,
+    MGet,
+}
+
+impl Op {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            | Op::Set => "SET",
+            | Op::Get => "GET",
+            | Op::Incr => "INCR",
+            | Op::MGet => "MGET",
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_uppercase().as_str() {
+            | "SET" => Ok(Op::Set),
+            | "GET" => Ok(Op::Get),
+            | "INCR" => Ok(Op::Incr),
+            | "MGET" => Ok(Op::MGet),
+            | other => Err(anyhow::anyhow!("unknown command '{other}' in the mix")),
+        }
+    }
+}
+
+/// Share of the load each command takes, given as `set=1,get=8,incr=1`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Mix(Vec<(Op, u32)>);
+
+impl Mix {
+    pub(crate) fn pick(&self, rng: &mut impl Rng) -> Op {
+        let total: u32 = self.0.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.random_range(0..total);
+        for (op, weight) in &self.0 {
+            if roll < *weight {
+                return *op;
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is below the total weight")
+    }
+}
+
+impl FromStr for Mix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut ops = Vec::new();
+        for part in s.split(',').filter(|part| !part.is_empty()) {
+            let (op, weight) = part.split_once('=').unwrap_or((part, "1"));
+            let op: Op = op.trim().parse()?;
+            if ops.iter().any(|(seen, _)| *seen == op) {
+                anyhow::bail!("'{}' is given more than once in the mix", op.name());
+            }
+            let weight: u32 = weight.trim().parse()?;
+            if weight > 0 {
+                ops.push((op, weight));
+            }
+        }
+        if ops.is_empty() {
+            anyhow::bail!("the mix needs at least one command with a weight above 0");
+        }
+        ops.sort();
+        Ok(Self(ops))
+    }
+}
+
+pub(crate) struct Workload {
+    pub(crate) mix: Mix,
+    // * Keys are picked at random from this many, so that the load spreads over partitions
+    pub(crate) keyspace: u64,
+    pub(crate) value: String,
+    pub(crate) mget_keys: usize,
+}
+
+impl Workload {
+    pub(crate) fn command(&self, op: Op, rng: &mut impl Rng) -> Vec<String> {
+        let mut index = || rng.random_range(0..self.keyspace);
+        match op {
+            | Op::Set => vec!["SET".into(), format!("key:{}", index()), self.value.clone()],
+            | Op::Get => vec!["GET".into(), format!("key:{}", index())],
+            // * Counters live apart from the values SET writes, which are not numbers
+            | Op::Incr => vec!["INCR".into(), format!("counter:{}", index())],
+            | Op::MGet => std::iter::once("MGET".into())
+                .chain((0..self.mget_keys).map(|_| format!("key:{}", index())))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_parses_weights() {
+        let mix: Mix = "get=8,set=2,incr".parse().unwrap();
+        assert_eq!(mix, Mix(vec![(Op::Set, 2), (Op::Get, 8), (Op::Incr, 1)]));
+    }
+
+    #[test]
+    fn test_mix_drops_commands_weighted_zero() {
+        let mix: Mix = "set=1,mget=0".parse().unwrap();
+        assert_eq!(mix, Mix(vec![(Op::Set, 1)]));
+        assert!("mget=0".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn test_mix_rejects_unknown_and_repeated_commands() {
+        assert!("set=1,del=1".parse::<Mix>().is_err());
+        assert!("set=1,set=2".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn test_mix_picks_only_weighted_commands() {
+        let mix: Mix = "get=3,incr=1".parse().unwrap();
+        let mut rng = rand::rng();
+        let picked: Vec<Op> = (0..1000).map(|_| mix.pick(&mut rng)).collect();
+        assert!(picked.iter().all(|op| matches!(op, Op::Get | Op::Incr)));
+        assert!(picked.contains(&Op::Get) && picked.contains(&Op::Incr));
+    }
+
+    #[test]
+    fn test_workload_builds_commands_within_keyspace() {
+        let workload = Workload {
+            mix: "set".parse().unwrap(),
+            keyspace: 1,
+            value: "xxx".into(),
+            mget_keys: 3,
+        };
+        let mut rng = rand::rng();
+        assert_eq!(workload.command(Op::Set, &mut rng), ["SET", "key:0", "xxx"]);
+        assert_eq!(workload.command(Op::Incr, &mut rng), ["INCR", "counter:0"]);
+        assert_eq!(workload.command(Op::MGet, &mut rng), ["MGET", "key:0", "key:0", "key:0"]);
+    }
+}
@@ -29,11 +29,15 @@ pub struct Broker {
     pub(crate) request_id: u64,
     pub(crate) topology: Topology,
     pub(crate) read_kill_switch: Option<tokio::sync::oneshot::Sender<()>>,
+    // * Last password AUTH succeeded with, sent along when reconnecting to another node
+    pub(crate) password: Option<String>,
 }
 
 impl Broker {
     pub(crate) async fn run(mut self) {
         let mut queue = InputQueue::default();
+        // * Set once MONITOR is acknowledged; the server then streams commands unprompted
+        let mut monitoring = false;
         while let Some(msg) = self.rx.recv().await {
             match msg {
                 | BrokerMessage::FromServer(Ok(QueryIO::TopologyChange(topology))) => {
@@ -42,8 +46,20 @@ impl Broker {
 
                 | BrokerMessage::FromServer(Ok(query_io)) => {
                     let Some(input) = queue.pop() else {
+                        if let (true, QueryIO::SimpleString(line)) = (monitoring, query_io) {
+                            println!("{}", String::from_utf8_lossy(&line));
+                        }
                         continue;
                     };
+                    if matches!(input.kind, ClientAction::Monitor) {
+                        monitoring = matches!(query_io, QueryIO::SimpleString(_));
+                    }
+
+                    if let (ClientAction::Auth { password, .. }, QueryIO::SimpleString(_)) =
+                        (&input.kind, &query_io)
+                    {
+                        self.password = Some(password.clone());
+                    }
 
                     if let Some(index) = self.extract_req_id(&input.kind, &query_io) {
                         self.request_id = index;
@@ -98,7 +114,8 @@ impl Broker {
             // * Current rule: s:value-idx:index_num
             | QueryIO::SimpleString(v) => {
                 let s = String::from_utf8_lossy(v);
-                IndexedValueCodec::decode_index(s).filter(|&id| id > self.request_id)
+                // * An id bumped past an error may already match the index this write landed at
+                IndexedValueCodec::decode_index(s).map(|idx| idx.max(self.request_id + 1))
             },
             | QueryIO::Err(_) => Some(self.request_id + 1),
             | _ => None,
@@ -114,6 +131,9 @@ impl Broker {
 
         stream.serialized_write(auth_request.unwrap_or_default()).await.unwrap(); // client_id not exist
         let auth_response: AuthResponse = stream.deserialized_read().await?;
+        if let Some(rejection) = auth_response.rejection {
+            return Err(IoError::Custom(rejection));
+        }
         let (r, w) = stream.into_split();
         Ok((ServerStreamReader(r), ServerStreamWriter(w), auth_response))
     }
@@ -137,6 +157,7 @@ impl Broker {
             let auth_req = AuthRequest {
                 client_id: Some(self.client_id.to_string()),
                 request_id: self.request_id,
+                password: self.password.clone(),
             };
             let Ok((r, w, auth_response)) = Self::authenticate(node, Some(auth_req)).await else {
                 continue;
@@ -8,10 +8,17 @@ pub(crate) struct Cli {
     port: u16,
     #[arg(short, long, default_value = "127.0.0.1")]
     host: String,
+    // * Authenticates the connection, as AUTH would
+    #[arg(short = 'a', long)]
+    pass: Option<String>,
 }
 
 impl Cli {
     pub(crate) fn address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    pub(crate) fn password(&self) -> Option<String> {
+        self.pass.clone()
+    }
 }
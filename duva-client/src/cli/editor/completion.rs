@@ -15,21 +15,55 @@ pub(crate) static COMMANDS: &[&str] = &[
     "append",
     "cluster",
     "ping",
+    "hello",
+    "auth",
+    "readonly",
+    "readwrite",
+    "wait",
     "keys",
     "info",
     "exists",
     "del",
+    "unlink",
     "incr",
     "incrby",
     "decr",
     "decrby",
     "ttl",
+    "cas",
+    "lock",
+    "unlock",
+    "lease",
+    "batch",
+    "client",
+    "command",
+    "shutdown",
     // subcommands
     "cluster info",
     "cluster nodes",
+    "cluster shards",
+    "cluster subscribe",
     "cluster forget",
     "cluster meet",
     "cluster reshard",
+    "cluster migrate",
+    "cluster failover",
+    "cluster leave",
+    "lease grant",
+    "lease keepalive",
+    "lease attach",
+    "lease revoke",
+    "client id",
+    "client list",
+    "client kill",
+    "client getname",
+    "client setname",
+    "client pause",
+    "client unpause",
+    "command info",
+    "command docs",
+    "command count",
+    "command getkeys",
     "info replication",
     "replicaof",
 ];
@@ -84,7 +118,10 @@ impl Completer for DuvaHinter {
             | "cluster" => {
                 if previous_words.len() == 1 {
                     // Suggest subcommands for cluster that start with current_prefix
-                    let subcommands = ["info", "nodes", "forget", "meet", "reshard"];
+                    let subcommands = [
+                        "info", "nodes", "shards", "forget", "meet", "reshard", "migrate",
+                        "failover", "leave",
+                    ];
                     candidates.extend(
                         subcommands
                             .iter()
@@ -93,16 +130,48 @@ impl Completer for DuvaHinter {
                     );
                 } else if previous_words.len() == 2 {
                     let subcommand = previous_words[1].to_lowercase();
-                    if subcommand == "forget" || subcommand == "meet" {
+                    if subcommand == "forget" || subcommand == "meet" || subcommand == "failover" {
                         // Suggest "node" for cluster forget
                         candidates.push(new_pair!("node"));
+                    } else if subcommand == "reshard" && "status".starts_with(current_prefix) {
+                        candidates.push(new_pair!("status"));
                     }
                 }
             },
+            | "command" => {
+                if previous_words.len() == 1 {
+                    let subcommands = ["info", "docs", "count", "getkeys"];
+                    candidates.extend(
+                        subcommands
+                            .iter()
+                            .filter(|s| s.starts_with(current_prefix))
+                            .map(|s| new_pair!(s)),
+                    );
+                }
+            },
+            | "lease" => {
+                if previous_words.len() == 1 {
+                    let subcommands = ["grant", "keepalive", "attach", "revoke"];
+                    candidates.extend(
+                        subcommands
+                            .iter()
+                            .filter(|s| s.starts_with(current_prefix))
+                            .map(|s| new_pair!(s)),
+                    );
+                } else if previous_words.len() == 2 {
+                    if previous_words[1].eq_ignore_ascii_case("grant") {
+                        candidates.push(new_pair!("ttl"));
+                    } else {
+                        candidates.push(new_pair!("id"));
+                    }
+                } else if previous_words[1].eq_ignore_ascii_case("attach") {
+                    candidates.push(new_pair!("key"));
+                }
+            },
             | "info" => {
                 if previous_words.len() == 1 {
                     // Suggest subcommands for info that start with current_prefix
-                    let subcommands = ["replication", "section"];
+                    let subcommands = ["replication", "stats", "all", "section"];
                     candidates.extend(
                         subcommands
                             .iter()
@@ -137,7 +206,34 @@ impl Completer for DuvaHinter {
                     }
                 }
             },
-            | "exists" | "del" | "mget" => {
+            | "cas" => {
+                if previous_words.len() == 1 {
+                    candidates.push(new_pair!("key"));
+                } else if previous_words.len() == 2 {
+                    candidates.push(new_pair!("expected"));
+                } else if previous_words.len() == 3 {
+                    candidates.push(new_pair!("value"));
+                }
+            },
+            | "lock" | "unlock" => {
+                if previous_words.len() == 1 {
+                    candidates.push(new_pair!("key"));
+                } else if previous_words.len() == 2 {
+                    if command == "lock" {
+                        candidates.push(new_pair!("ttl"));
+                    } else {
+                        candidates.push(new_pair!("token"));
+                    }
+                }
+            },
+            | "batch" => {
+                // * Start of the batch or of an operation following ";"
+                if previous_words.len() == 1 || previous_words.last() == Some(&";") {
+                    candidates.push(new_pair!("set"));
+                    candidates.push(new_pair!("del"));
+                }
+            },
+            | "exists" | "del" | "unlink" | "mget" => {
                 if !previous_words.is_empty() {
                     // Suggest "key" for these commands
                     candidates.push(new_pair!("key"));
@@ -147,6 +243,8 @@ impl Completer for DuvaHinter {
                 if previous_words.len() == 1 {
                     // Suggest "index" after get key
                     candidates.push(new_pair!("key"));
+                } else if previous_words.len() == 2 && command == "get" {
+                    candidates.push(new_pair!("linearizable"));
                 }
             },
             | "keys" => {
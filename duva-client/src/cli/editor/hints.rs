@@ -111,7 +111,7 @@ impl Hinter for DuvaHinter {
                 })
                 .collect::<Vec<_>>();
 
-        matching_hints.sort_by(|a, b| a.display.len().cmp(&b.display.len()));
+        matching_hints.sort_by_key(|a| a.display.len());
         matching_hints.into_iter().next()
     }
 }
@@ -119,6 +119,7 @@ impl Hinter for DuvaHinter {
 pub(crate) fn default_hints() -> HashSet<CommandHint> {
     let mut set = HashSet::new();
     set.insert(CommandHint::new("get key", "get "));
+    set.insert(CommandHint::new("get key [linearizable]", "get "));
     set.insert(CommandHint::new("set key value", "set "));
     set.insert(CommandHint::new("set key value [px expr]", "set "));
     set.insert(CommandHint::new("append key value", "append "));
@@ -126,20 +127,52 @@ pub(crate) fn default_hints() -> HashSet<CommandHint> {
     set.insert(CommandHint::new("incrby key value", "incrby "));
     set.insert(CommandHint::new("decr key", "decr "));
     set.insert(CommandHint::new("decrby key value", "decrby "));
+    set.insert(CommandHint::new("cas key expected value", "cas "));
+    set.insert(CommandHint::new("lock key ttl", "lock "));
+    set.insert(CommandHint::new("unlock key token", "unlock "));
+    set.insert(CommandHint::new("lease grant ttl", "lease "));
+    set.insert(CommandHint::new("lease keepalive id", "lease "));
+    set.insert(CommandHint::new("lease attach id key [key ...]", "lease "));
+    set.insert(CommandHint::new("lease revoke id", "lease "));
+    set.insert(CommandHint::new("batch command [arg ...] [; command [arg ...] ...]", "batch "));
     set.insert(CommandHint::new("cluster info", "cluster "));
     set.insert(CommandHint::new("cluster nodes", "cluster "));
+    set.insert(CommandHint::new("cluster shards", "cluster "));
+    set.insert(CommandHint::new("cluster subscribe", "cluster "));
     set.insert(CommandHint::new("cluster forget node", "cluster "));
-    set.insert(CommandHint::new("cluster reshard", "cluster "));
+    set.insert(CommandHint::new("cluster reshard [status]", "cluster "));
+    set.insert(CommandHint::new("cluster migrate prefix|start-end to replid", "cluster "));
     set.insert(CommandHint::new("cluster meet node [lazy|eager]", "cluster "));
+    set.insert(CommandHint::new("cluster failover [node]", "cluster "));
+    set.insert(CommandHint::new("cluster leave", "cluster "));
+    set.insert(CommandHint::new("client id", "client "));
+    set.insert(CommandHint::new("client list", "client "));
+    set.insert(CommandHint::new("client kill addr", "client "));
+    set.insert(CommandHint::new("client kill [id client-id] [addr addr]", "client "));
+    set.insert(CommandHint::new("client getname", "client "));
+    set.insert(CommandHint::new("client setname name", "client "));
+    set.insert(CommandHint::new("client pause timeout [write|all]", "client "));
+    set.insert(CommandHint::new("client unpause", "client "));
+    set.insert(CommandHint::new("command info [command ...]", "command "));
+    set.insert(CommandHint::new("command docs [command ...]", "command "));
+    set.insert(CommandHint::new("command count", "command "));
+    set.insert(CommandHint::new("command getkeys command [arg ...]", "command "));
     set.insert(CommandHint::new("ping", ""));
+    set.insert(CommandHint::new("hello [protover [AUTH username password]]", "hello "));
+    set.insert(CommandHint::new("auth [username] password", "auth "));
+    set.insert(CommandHint::new("readonly", ""));
+    set.insert(CommandHint::new("readwrite", ""));
+    set.insert(CommandHint::new("wait numreplicas timeout", "wait "));
     set.insert(CommandHint::new("keys pattern", "keys "));
     set.insert(CommandHint::new("info [section]", ""));
     set.insert(CommandHint::new("info replication", ""));
     set.insert(CommandHint::new("exists key [key ...]", "exists "));
     set.insert(CommandHint::new("mget key [key ...]", "mget "));
     set.insert(CommandHint::new("del key [key ...]", "del "));
+    set.insert(CommandHint::new("unlink key [key ...]", "unlink "));
     set.insert(CommandHint::new("ttl key", "ttl "));
     set.insert(CommandHint::new("replicaof host port", "replicaof "));
+    set.insert(CommandHint::new("shutdown [nosave|save]", "shutdown "));
 
     set
 }
@@ -170,14 +203,34 @@ pub(crate) fn dynamic_hints() -> HashMap<&'static str, Vec<DynamicHint>> {
     map.insert("append", vec![hint!("key value", 0), hint!("value", 1)]);
     map.insert("incrby", vec![hint!("key increment", 0), hint!("increment", 1)]);
     map.insert("decrby", vec![hint!("key decrement", 0), hint!("decrement", 1)]);
+    map.insert("lock", vec![hint!("key ttl", 0), hint!("ttl", 1)]);
+    map.insert("unlock", vec![hint!("key token", 0), hint!("token", 1)]);
+    map.insert("wait", vec![hint!("numreplicas timeout", 0), hint!("timeout", 1)]);
+    map.insert(
+        "cas",
+        vec![hint!("key expected value", 0), hint!("expected value", 1), hint!("value", 2)],
+    );
+    map.insert(
+        "batch",
+        vec![
+            hint!("command [arg ...] [; command [arg ...] ...]", 0),
+            hint!("[arg ...] [; command [arg ...] ...]", 1),
+        ],
+    );
 
     map.insert("cluster forget", vec![hint!("node", 0)]);
+    map.insert("cluster failover", vec![hint!("[node]", 0)]);
     map.insert("cluster meet", vec![hint!("node [lazy|eager]", 0), hint!("[lazy|eager]", 1)]);
     map.insert("keys", vec![hint!("pattern", 0)]);
-    map.insert("get", vec![hint!("key", 0)]);
+    map.insert("shutdown", vec![hint!("[nosave|save]", 0)]);
+    map.insert("get", vec![hint!("key [linearizable]", 0), hint!("[linearizable]", 1)]);
     map.insert("exists", vec![hint!("key [key ...]", 0, repeat), hint!("[key ...]", 1, repeat)]);
     map.insert("del", vec![hint!("key [key ...]", 0, repeat), hint!("[key ...]", 1, repeat)]);
-    map.insert("mget", vec![hint!("key [key ...]", 0, repeat), hint!("[key ...]", 1, repeat)]);
+    map.insert("unlink", vec![hint!("key [key ...]", 0, repeat), hint!("[key ...]", 1, repeat)]);
+    map.insert(
+        "mget",
+        vec![hint!("key [key ...] [linearizable]", 0, repeat), hint!("[key ...]", 1, repeat)],
+    );
     map.insert("replicaof", vec![hint!("host port", 0), hint!("port", 1)]);
 
     map
@@ -4,6 +4,7 @@ use clap::Parser;
 use duva::{
     prelude::{
         anyhow,
+        bytes::Bytes,
         tokio::{self, sync::oneshot},
     },
     presentation::clients::request::extract_action,
@@ -21,7 +22,8 @@ async fn main() -> anyhow::Result<()> {
     clear_and_make_ascii_art();
 
     let cli = cli::Cli::parse();
-    let mut controller = ClientController::new(editor::create(), &cli.address()).await?;
+    let mut controller =
+        ClientController::new(editor::create(), &cli.address(), cli.password()).await?;
 
     loop {
         let readline = controller.target.readline(PROMPT).unwrap_or_else(|_| std::process::exit(0));
@@ -38,7 +40,9 @@ async fn main() -> anyhow::Result<()> {
         // and the rest are arguments
         let (cmd, args) = separate_command_and_args(args);
 
-        match extract_action(cmd, &args) {
+        let arg_bytes: Vec<Bytes> =
+            args.iter().map(|arg| Bytes::copy_from_slice(arg.as_bytes())).collect();
+        match extract_action(cmd, &arg_bytes) {
             | Ok(input) => {
                 let (tx, rx) = oneshot::channel();
                 let input = Input::new(input, tx);
@@ -57,6 +61,7 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[cfg_attr(test, allow(clippy::needless_return))]
 fn clear_and_make_ascii_art() {
     let is_test_env = std::env::var("DUVA_ENV").unwrap_or_default() == "test";
     if is_test_env {
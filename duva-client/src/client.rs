@@ -0,0 +1,377 @@
+use duva::domains::TSerdeReadWrite;
+use duva::domains::caches::cache_manager::IndexedValueCodec;
+use duva::domains::cluster_actors::replication::ReplicationId;
+use duva::domains::interface::TRead;
+use duva::domains::peers::identifier::TPeerAddress;
+use duva::domains::query_io::QueryIO;
+use duva::prelude::anyhow::{self, Context};
+use duva::prelude::bytes::Bytes;
+use duva::prelude::tokio::io::AsyncWriteExt;
+use duva::prelude::tokio::net::TcpStream;
+use duva::prelude::tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use duva::prelude::tokio::{self, time::Duration};
+use duva::prelude::{AuthRequest, AuthResponse, LEADER_HEARTBEAT_INTERVAL_MAX, Topology};
+use duva::presentation::clients::request::{ClientAction, extract_action};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+// * Redirections followed, and failovers waited out, before a command is given up on
+const MAX_ATTEMPTS: usize = 5;
+
+/// Async client for a duva cluster. Commands go straight to the leader of the partition their
+/// keys belong to, going by the hash ring of the topology the nodes push on every change.
+/// `MOVED` and `ASK` redirections are followed, and commands that are safe to send again, reads
+/// and the writes the server deduplicates by request id, are retried on another node when a
+/// leader goes away.
+pub struct DuvaClient {
+    // * Session the connections to every node share, set by the first handshake
+    client_id: Option<String>,
+    request_id: u64,
+    password: Option<String>,
+    topology: Topology,
+    // * Node commands without keys are sent to
+    home: String,
+    connections: HashMap<String, Connection>,
+}
+
+struct Prepared {
+    action: ClientAction,
+    frame: Vec<u8>,
+}
+
+enum Redirect<'a> {
+    Moved(&'a str),
+    Ask(&'a str),
+    // * Held back or cut off by a leadership change, to be sent again once it settles
+    TryAgain,
+}
+
+impl DuvaClient {
+    pub async fn connect(addr: &str) -> anyhow::Result<Self> {
+        Self::connect_with_password(addr, None).await
+    }
+
+    pub async fn connect_with_password(
+        addr: &str,
+        password: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let home = addr.bind_addr()?;
+        let mut client = Self {
+            client_id: None,
+            request_id: 0,
+            password,
+            topology: Topology::default(),
+            home: home.clone(),
+            connections: HashMap::new(),
+        };
+        client.open(&home).await?;
+        Ok(client)
+    }
+
+    /// Topology the client routes by, as last pushed by the cluster.
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Runs a command given as it would be typed, e.g. `["SET", "foo", "bar"]`. Errors the
+    /// server replies with are returned as errors.
+    pub async fn execute(&mut self, args: &[&str]) -> anyhow::Result<QueryIO> {
+        let prepared = self.prepare(args)?;
+        match self.run(&prepared).await? {
+            | QueryIO::Err(err) => Err(anyhow::anyhow!(String::from_utf8_lossy(&err).into_owned())),
+            | reply => Ok(reply),
+        }
+    }
+
+    /// Runs the commands with a single round trip to each node they are routed to, and returns
+    /// their replies in order. Errors the server replies with are returned as `QueryIO::Err`.
+    pub async fn pipeline(&mut self, commands: &[&[&str]]) -> anyhow::Result<Vec<QueryIO>> {
+        let prepared =
+            commands.iter().map(|args| self.prepare(args)).collect::<anyhow::Result<Vec<_>>>()?;
+        let mut by_node: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, command) in prepared.iter().enumerate() {
+            by_node.entry(self.route(&command.action)).or_default().push(i);
+        }
+
+        let mut replies: Vec<Option<QueryIO>> = vec![None; prepared.len()];
+        for (node, indices) in by_node {
+            if self.open_if_absent(&node).await.is_err() {
+                continue;
+            }
+            let connection = self.connections.get_mut(&node).unwrap();
+            let frames: Vec<u8> =
+                indices.iter().flat_map(|&i| prepared[i].frame.iter().copied()).collect();
+            let mut broken = connection.send(&frames).await.is_err();
+            for &i in &indices {
+                if broken {
+                    break;
+                }
+                match connection.reply(&mut self.topology).await {
+                    | Ok(reply) => replies[i] = Some(reply),
+                    | Err(_) => broken = true,
+                }
+            }
+            if broken {
+                self.connections.remove(&node);
+            }
+        }
+
+        // * Commands that were redirected, or cut off by a failover, are sent again one by one
+        let mut results = Vec::with_capacity(prepared.len());
+        for (command, reply) in prepared.iter().zip(replies) {
+            let reply = match reply {
+                | Some(reply) if redirect(&reply).is_none() => reply,
+                | Some(_) => self.run(command).await?,
+                | None if is_retryable(&command.action) => self.run(command).await?,
+                | None => anyhow::bail!("connection lost before the reply to {:?}", command.action),
+            };
+            results.push(reply);
+        }
+        Ok(results)
+    }
+
+    pub async fn get(&mut self, key: &str) -> anyhow::Result<Option<String>> {
+        match self.execute(&["GET", key]).await? {
+            | QueryIO::Null => Ok(None),
+            | QueryIO::BulkString(value) => Ok(Some(String::from_utf8(value.to_vec())?)),
+            | reply => Err(anyhow::anyhow!("unexpected reply to GET: {reply:?}")),
+        }
+    }
+
+    pub async fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.execute(&["SET", key, value]).await?;
+        Ok(())
+    }
+
+    /// Returns how many of the keys there were.
+    pub async fn del(&mut self, keys: &[&str]) -> anyhow::Result<u64> {
+        let args: Vec<&str> = std::iter::once("DEL").chain(keys.iter().copied()).collect();
+        match self.execute(&args).await? {
+            | QueryIO::SimpleString(count) => Ok(std::str::from_utf8(&count)?.parse()?),
+            | reply => Err(anyhow::anyhow!("unexpected reply to DEL: {reply:?}")),
+        }
+    }
+
+    /// Returns the value after the increment.
+    pub async fn incr(&mut self, key: &str) -> anyhow::Result<i64> {
+        match self.execute(&["INCR", key]).await? {
+            | QueryIO::SimpleString(value) => {
+                IndexedValueCodec::decode_value(String::from_utf8_lossy(&value))
+                    .context("unexpected reply to INCR")
+            },
+            | reply => Err(anyhow::anyhow!("unexpected reply to INCR: {reply:?}")),
+        }
+    }
+
+    /// Turns the client into a stream of the shards of the cluster, as `CLUSTER SUBSCRIBE` pushes
+    /// them: the current ones first, then again every time they change.
+    pub async fn subscribe_shards(mut self) -> anyhow::Result<ShardSubscription> {
+        let prepared = self.prepare(&["CLUSTER", "SUBSCRIBE"])?;
+        let home = self.home.clone();
+        self.open_if_absent(&home).await?;
+        let mut connection = self.connections.remove(&home).unwrap();
+        connection.send(&prepared.frame).await?;
+        Ok(ShardSubscription { connection, topology: self.topology })
+    }
+
+    fn prepare(&mut self, args: &[&str]) -> anyhow::Result<Prepared> {
+        let (name, rest) = args.split_first().context("empty command")?;
+        let rest: Vec<Bytes> =
+            rest.iter().map(|arg| Bytes::copy_from_slice(arg.as_bytes())).collect();
+        let action = extract_action(name, &rest)?;
+        // * A write keeps its request id when it is sent again, so that it is only applied once
+        if action.consensus_required() {
+            self.request_id += 1;
+        }
+        let frame = QueryIO::SessionRequest {
+            request_id: self.request_id,
+            value: args.iter().map(|arg| QueryIO::BulkString(arg.to_string().into())).collect(),
+        }
+        .serialize()
+        .to_vec();
+        Ok(Prepared { action, frame })
+    }
+
+    async fn run(&mut self, command: &Prepared) -> anyhow::Result<QueryIO> {
+        let mut target = self.route(&command.action);
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let reply = match self.open_if_absent(&target).await {
+                | Ok(()) => {
+                    let connection = self.connections.get_mut(&target).unwrap();
+                    match connection.send(&command.frame).await {
+                        | Ok(()) => connection.reply(&mut self.topology).await,
+                        | Err(err) => Err(err),
+                    }
+                },
+                | Err(err) => Err(err),
+            };
+
+            match reply {
+                | Ok(reply) if attempts < MAX_ATTEMPTS => match redirect(&reply) {
+                    | Some(Redirect::Moved(to) | Redirect::Ask(to)) => {
+                        let to = to.to_string();
+                        target = self.resolve(&to).await;
+                    },
+                    | Some(Redirect::TryAgain) if is_retryable(&command.action) => {
+                        self.refresh_topology().await;
+                        target = self.route(&command.action);
+                    },
+                    | _ => return Ok(reply),
+                },
+                | Ok(reply) => return Ok(reply),
+                | Err(_) if attempts < MAX_ATTEMPTS && is_retryable(&command.action) => {
+                    self.connections.remove(&target);
+                    self.refresh_topology().await;
+                    target = self.route(&command.action);
+                },
+                | Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn route(&self, action: &ClientAction) -> String {
+        action
+            .all_keys()
+            .first()
+            .and_then(|key| self.topology.leader_for_key(key))
+            .map(|leader| leader.0.clone())
+            .unwrap_or_else(|| self.home.clone())
+    }
+
+    // * Redirections name either a node or the replication id of a partition
+    async fn resolve(&mut self, to: &str) -> String {
+        if let Ok(addr) = to.bind_addr() {
+            return addr;
+        }
+        let replid = ReplicationId::Key(to.to_string());
+        if self.topology.leader_of(&replid).is_none() {
+            self.refresh_topology().await;
+        }
+        self.topology.leader_of(&replid).map(|leader| leader.0.clone()).unwrap_or(self.home.clone())
+    }
+
+    async fn open_if_absent(&mut self, addr: &str) -> anyhow::Result<()> {
+        if !self.connections.contains_key(addr) {
+            self.open(addr).await?;
+        }
+        Ok(())
+    }
+
+    async fn open(&mut self, addr: &str) -> anyhow::Result<AuthResponse> {
+        let auth_request = AuthRequest {
+            client_id: self.client_id.clone(),
+            request_id: self.request_id,
+            password: self.password.clone(),
+        };
+        let (connection, response) = Connection::open(addr, auth_request).await?;
+        if self.client_id.is_none() {
+            self.client_id = Some(response.client_id.clone());
+            self.request_id = response.request_id;
+        }
+        self.topology = response.topology.clone();
+        self.connections.insert(addr.to_string(), connection);
+        Ok(response)
+    }
+
+    /// Waits out an election, then takes the topology from the first node that answers, which
+    /// becomes the node commands without keys are sent to.
+    async fn refresh_topology(&mut self) {
+        tokio::time::sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX)).await;
+        let mut candidates = vec![self.home.clone()];
+        candidates.extend(self.topology.connected_peers.iter().map(|peer| peer.0.clone()));
+        for addr in candidates {
+            self.connections.remove(&addr);
+            if let Ok(response) = self.open(&addr).await
+                && response.connected_to_leader
+            {
+                self.home = addr;
+                return;
+            }
+        }
+    }
+}
+
+fn redirect(reply: &QueryIO) -> Option<Redirect<'_>> {
+    let QueryIO::Err(err) = reply else { return None };
+    let err = std::str::from_utf8(err).ok()?;
+    err.strip_prefix("MOVED ")
+        .map(Redirect::Moved)
+        .or_else(|| err.strip_prefix("ASK ").map(Redirect::Ask))
+        .or_else(|| err.starts_with("TRYAGAIN").then_some(Redirect::TryAgain))
+}
+
+// * Writes are deduplicated by request id, so sending them again never applies them twice
+fn is_retryable(action: &ClientAction) -> bool {
+    action.consensus_required() || action.is_keyspace_read()
+}
+
+/// Shards pushed to a client that subscribed to them, see [`DuvaClient::subscribe_shards`].
+pub struct ShardSubscription {
+    connection: Connection,
+    topology: Topology,
+}
+
+impl ShardSubscription {
+    /// Next set of shards, each given the way `CLUSTER SHARDS` lists them.
+    pub async fn next(&mut self) -> anyhow::Result<Vec<String>> {
+        loop {
+            let items = match self.connection.reply(&mut self.topology).await? {
+                | QueryIO::Push(items) | QueryIO::Array(items) => items,
+                | QueryIO::Err(err) => {
+                    return Err(anyhow::anyhow!(String::from_utf8_lossy(&err).into_owned()));
+                },
+                | _ => continue,
+            };
+            let [QueryIO::BulkString(kind), QueryIO::Array(shards)] = &items[..] else { continue };
+            if kind.as_ref() != b"topology" {
+                continue;
+            }
+            return shards
+                .iter()
+                .map(|shard| match shard {
+                    | QueryIO::BulkString(line) => Ok(String::from_utf8(line.to_vec())?),
+                    | other => Err(anyhow::anyhow!("unexpected shard {other:?}")),
+                })
+                .collect();
+        }
+    }
+}
+
+struct Connection {
+    r: OwnedReadHalf,
+    w: OwnedWriteHalf,
+    // * Values read off the socket that have yet to be handed out
+    received: VecDeque<QueryIO>,
+}
+
+impl Connection {
+    async fn open(addr: &str, auth_request: AuthRequest) -> anyhow::Result<(Self, AuthResponse)> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.serialized_write(auth_request).await?;
+        let response: AuthResponse = stream.deserialized_read().await?;
+        if let Some(rejection) = response.rejection {
+            anyhow::bail!(rejection);
+        }
+        let (r, w) = stream.into_split();
+        Ok((Self { r, w, received: VecDeque::new() }, response))
+    }
+
+    async fn send(&mut self, frames: &[u8]) -> anyhow::Result<()> {
+        self.w.write_all(frames).await?;
+        Ok(())
+    }
+
+    /// Next reply, updating `topology` with the topology changes pushed in between.
+    async fn reply(&mut self, topology: &mut Topology) -> anyhow::Result<QueryIO> {
+        loop {
+            while let Some(value) = self.received.pop_front() {
+                match value {
+                    | QueryIO::TopologyChange(pushed) => *topology = pushed,
+                    | reply => return Ok(reply),
+                }
+            }
+            self.received.extend(self.r.read_values().await?);
+        }
+    }
+}
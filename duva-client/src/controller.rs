@@ -5,6 +5,7 @@ use crate::broker::BrokerMessage;
 
 use duva::domains::caches::cache_manager::IndexedValueCodec;
 use duva::domains::query_io::QueryIO;
+use duva::prelude::AuthRequest;
 use duva::prelude::anyhow;
 use duva::prelude::bytes::Bytes;
 use duva::prelude::tokio;
@@ -18,8 +19,13 @@ pub struct ClientController<T> {
 }
 
 impl<T> ClientController<T> {
-    pub async fn new(editor: T, server_addr: &str) -> anyhow::Result<Self> {
-        let (r, w, auth_response) = Broker::authenticate(server_addr, None).await?;
+    pub async fn new(
+        editor: T,
+        server_addr: &str,
+        password: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let auth_request = AuthRequest { password: password.clone(), ..Default::default() };
+        let (r, w, auth_response) = Broker::authenticate(server_addr, Some(auth_request)).await?;
 
         let (broker_tx, rx) = tokio::sync::mpsc::channel::<BrokerMessage>(100);
 
@@ -32,6 +38,7 @@ impl<T> ClientController<T> {
 
             topology: auth_response.topology,
             read_kill_switch: Some(r.run(broker_tx.clone())),
+            password,
         };
         tokio::spawn(broker.run());
         Ok(Self { broker_tx, target: editor })
@@ -41,22 +48,45 @@ impl<T> ClientController<T> {
         use ClientAction::*;
         match kind {
             | Ping
+            | Auth { .. }
             | Get { .. }
+            | Dump { .. }
             | IndexGet { .. }
             | Echo { .. }
             | Config { .. }
-            | Info
+            | ConfigSet { .. }
+            | BgSave
+            | Shutdown { .. }
+            | Import { .. }
+            | Export { .. }
+            | Info { .. }
             | ClusterForget { .. }
             | Role
+            | ReadOnly
+            | ReadWrite
+            | Monitor
+            | ClientSetName(_)
+            | ClientGetName
+            | ClientList
+            | ClientKill { legacy: true, .. }
+            | ClientPause { .. }
+            | ClientUnpause
             | ReplicaOf { .. }
-            | ClusterInfo => match query_io {
+            | ClusterInfo
+            | ClusterReshardStatus
+            | ClusterConsensus
+            | DebugWalVerify
+            | DebugBigKeys
+            | MemoryStats
+            | MemoryEvictionPool
+            | LatencyHistogram { .. } => match query_io {
                 | QueryIO::Null => Response::Null,
                 | QueryIO::SimpleString(value) => Response::String(value),
                 | QueryIO::BulkString(value) => Response::String(value),
                 | QueryIO::Err(value) => Response::Error(value),
                 | _err => Response::FormatError,
             },
-            | Delete { .. } | Exists { .. } => {
+            | Delete { .. } | Unlink { .. } | Exists { .. } => {
                 let QueryIO::SimpleString(value) = query_io else {
                     return Response::FormatError;
                 };
@@ -68,30 +98,59 @@ impl<T> ClientController<T> {
                     },
                 }
             },
-            | Incr { .. } | Decr { .. } | Ttl { .. } | IncrBy { .. } | DecrBy { .. } => {
-                match query_io {
-                    | QueryIO::SimpleString(value) => {
-                        let s = String::from_utf8_lossy(&value);
-                        let s: Option<i64> = IndexedValueCodec::decode_value(s);
-                        Response::Integer(s.unwrap().to_string().into())
-                    },
-                    | QueryIO::Err(value) => Response::Error(value),
-                    | QueryIO::BulkString(value) => Response::Integer(value),
-                    | _ => Response::FormatError,
-                }
+            | ObjectIdleTime { .. } | ObjectFreq { .. } => match query_io {
+                | QueryIO::Null => Response::Null,
+                | QueryIO::SimpleString(value) => Response::Integer(value),
+                | QueryIO::Err(value) => Response::Error(value),
+                | _ => Response::FormatError,
             },
-            | Save => {
-                let QueryIO::Null = query_io else {
-                    return Response::FormatError;
-                };
-                Response::Null
+            | Wait { .. }
+            | LatencyReset
+            | CommandCount
+            | ClusterBanListClear(_)
+            | ClientId
+            | ClientKill { legacy: false, .. } => match query_io {
+                | QueryIO::SimpleString(value) => Response::Integer(value),
+                | QueryIO::Err(value) => Response::Error(value),
+                | _ => Response::FormatError,
             },
-            | Set { .. } | SetWithExpiry { .. } => match query_io {
+            | Incr { .. }
+            | Decr { .. }
+            | Ttl { .. }
+            | IncrBy { .. }
+            | DecrBy { .. }
+            | Cas { .. }
+            | Lock { .. }
+            | Unlock { .. }
+            | LeaseGrant { .. }
+            | LeaseKeepAlive { .. }
+            | LeaseAttach { .. }
+            | LeaseRevoke { .. }
+            | Batch { .. } => match query_io {
+                | QueryIO::SimpleString(value) => {
+                    let s = String::from_utf8_lossy(&value);
+                    let s: Option<i64> = IndexedValueCodec::decode_value(s);
+                    Response::Integer(s.unwrap().to_string().into())
+                },
+                | QueryIO::Err(value) => Response::Error(value),
+                | QueryIO::BulkString(value) => Response::Integer(value),
+                | _ => Response::FormatError,
+            },
+            | Save => match query_io {
+                | QueryIO::Null => Response::Null,
+                | QueryIO::Err(value) => Response::Error(value),
+                | _ => Response::FormatError,
+            },
+            | Set { .. } | SetWithExpiry { .. } | Restore { .. } => match query_io {
                 | QueryIO::SimpleString(_) => Response::String("OK".into()),
                 | QueryIO::Err(value) => Response::Error(value),
                 | _ => Response::FormatError,
             },
-            | ClusterMeet { .. } | ClusterReshard => match query_io {
+            | ClusterMeet { .. }
+            | ClusterReshard
+            | ClusterMigrate { .. }
+            | ClusterFailover { .. }
+            | ClusterLeave => match query_io {
                 | QueryIO::Null => Response::String("OK".into()),
                 | QueryIO::Err(value) => Response::Error(value),
                 | _ => Response::FormatError,
@@ -101,9 +160,11 @@ impl<T> ClientController<T> {
                 | QueryIO::Err(value) => Response::Error(value),
                 | _ => Response::FormatError,
             },
-            | Keys { .. } | MGet { .. } => {
-                let QueryIO::Array(value) = query_io else {
-                    return Response::FormatError;
+            | Keys { .. } | MGet { .. } | CommandGetKeys { .. } => {
+                let value = match query_io {
+                    | QueryIO::Array(value) => value,
+                    | QueryIO::Err(value) => return Response::Error(value),
+                    | _ => return Response::FormatError,
                 };
                 let mut keys = Vec::new();
                 for (i, item) in value.into_iter().enumerate() {
@@ -116,7 +177,50 @@ impl<T> ClientController<T> {
                 }
                 Response::Array(keys)
             },
-            | ClusterNodes => {
+            | Hello { .. } => {
+                let entries = match query_io {
+                    | QueryIO::Map(entries) => entries,
+                    // * RESP2 connections receive the map flattened into key-value pairs
+                    | QueryIO::Array(items) => {
+                        let mut items = items.into_iter();
+                        let mut entries = Vec::new();
+                        while let (Some(k), Some(v)) = (items.next(), items.next()) {
+                            entries.push((k, v));
+                        }
+                        entries
+                    },
+                    | QueryIO::Err(value) => return Response::Error(value),
+                    | _ => return Response::FormatError,
+                };
+                let mut fields = Vec::new();
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    let QueryIO::BulkString(key) = key else {
+                        return Response::FormatError;
+                    };
+                    let value = match value {
+                        | QueryIO::BulkString(value) => {
+                            format!("\"{}\"", String::from_utf8_lossy(&value))
+                        },
+                        | QueryIO::Integer(value) => format!("(integer) {value}"),
+                        | _ => return Response::FormatError,
+                    };
+                    fields.push(Response::String(
+                        format!("{}# \"{}\" => {value}", i + 1, String::from_utf8_lossy(&key))
+                            .into(),
+                    ));
+                }
+                Response::Array(fields)
+            },
+            | CommandInfo { .. } | CommandDocs { .. } | ClusterSubscribe => match query_io {
+                | QueryIO::Err(value) => Response::Error(value),
+                | query_io => match nested_lines(query_io) {
+                    | Some(lines) => Response::Array(
+                        lines.into_iter().map(|line| Response::String(line.into())).collect(),
+                    ),
+                    | None => Response::FormatError,
+                },
+            },
+            | ClusterNodes | ClusterShards | ClusterHistory(_) | ClusterBanList => {
                 let QueryIO::Array(value) = query_io else {
                     return Response::FormatError;
                 };
@@ -137,6 +241,44 @@ impl<T> ClientController<T> {
     }
 }
 
+/// Lays out nested replies the way redis-cli does, one line per scalar.
+fn nested_lines(query_io: QueryIO) -> Option<Vec<String>> {
+    let entries: Vec<(String, QueryIO)> = match query_io {
+        | QueryIO::Null => return Some(vec!["(nil)".to_string()]),
+        | QueryIO::SimpleString(value) => {
+            return Some(vec![String::from_utf8_lossy(&value).into_owned()]);
+        },
+        | QueryIO::BulkString(value) => {
+            return Some(vec![format!("\"{}\"", String::from_utf8_lossy(&value))]);
+        },
+        | QueryIO::Integer(value) => return Some(vec![format!("(integer) {value}")]),
+        | QueryIO::Array(items) if items.is_empty() => {
+            return Some(vec!["(empty array)".to_string()]);
+        },
+        | QueryIO::Array(items) => {
+            items.into_iter().enumerate().map(|(i, item)| (format!("{}) ", i + 1), item)).collect()
+        },
+        | QueryIO::Map(entries) => entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, (key, value))| {
+                let key = nested_lines(key)?.join(" ");
+                Some((format!("{}# {key} => ", i + 1), value))
+            })
+            .collect::<Option<_>>()?,
+        | _ => return None,
+    };
+
+    let mut lines = Vec::new();
+    for (prefix, item) in entries {
+        let indent = " ".repeat(prefix.len());
+        for (i, line) in nested_lines(item)?.into_iter().enumerate() {
+            lines.push(if i == 0 { format!("{prefix}{line}") } else { format!("{indent}{line}") });
+        }
+    }
+    Some(lines)
+}
+
 enum Response {
     Null,
     FormatError,
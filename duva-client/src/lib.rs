@@ -1,3 +1,6 @@
 pub mod broker;
+pub mod client;
 pub mod command;
 pub mod controller;
+
+pub use client::{DuvaClient, ShardSubscription};
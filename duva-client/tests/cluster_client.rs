@@ -0,0 +1,136 @@
+use duva::domains::query_io::QueryIO;
+use duva::prelude::anyhow;
+use duva::prelude::tokio::{self, time::Duration};
+use duva_client::DuvaClient;
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+
+/// A `duva` server process, killed and cleaned up after on drop.
+struct Server {
+    process: Child,
+    port: u16,
+    dir: std::path::PathBuf,
+}
+
+impl Server {
+    fn spawn() -> Self {
+        {
+            static ONCE: std::sync::Once = std::sync::Once::new();
+            ONCE.call_once(|| {
+                let mut command = Command::new("cargo");
+                command.args(["build", "-p", "duva"]);
+                command.stdout(Stdio::null()).stderr(Stdio::null()).status().unwrap();
+            });
+        }
+        let port = loop {
+            let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+            // * Peers connect on port + 10000
+            if port < 55000 {
+                break port;
+            }
+        };
+        let dir = std::env::temp_dir().join(format!("duva-client-test-{port}"));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = std::env::current_dir().unwrap().parent().unwrap().join("target/debug/duva");
+        let process = Command::new(path)
+            .args(["--port", &port.to_string(), "--dir", dir.to_str().unwrap()])
+            .args(["--tpp", dir.join("duva.tp").to_str().unwrap()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        Self { process, port, dir }
+    }
+
+    fn bind_addr(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+
+    async fn connect(&self) -> anyhow::Result<DuvaClient> {
+        for _ in 0..100 {
+            if let Ok(mut client) = DuvaClient::connect(&self.bind_addr()).await
+                && client.execute(&["PING"]).await.is_ok()
+            {
+                return Ok(client);
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        anyhow::bail!("server on {} did not start", self.port)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[tokio::test]
+async fn test_client_runs_typed_commands_and_pipelines() -> anyhow::Result<()> {
+    // GIVEN
+    let server = Server::spawn();
+    let mut client = server.connect().await?;
+
+    // WHEN
+    client.set("foo", "bar").await?;
+    let replies = client
+        .pipeline(&[&["SET", "a", "1"], &["INCR", "a"], &["GET", "a"], &["GET", "missing"]])
+        .await?;
+
+    // THEN
+    assert_eq!(client.get("foo").await?.as_deref(), Some("bar"));
+    assert_eq!(replies.len(), 4);
+    assert_eq!(replies[2], QueryIO::BulkString("2".into()));
+    assert_eq!(replies[3], QueryIO::Null);
+    assert_eq!(client.incr("a").await?, 3);
+    assert_eq!(client.del(&["foo", "missing"]).await?, 1);
+    assert_eq!(client.get("foo").await?, None);
+    assert!(client.execute(&["INCR", "a", "extra"]).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_routes_keys_to_the_leader_of_their_partition() -> anyhow::Result<()> {
+    // GIVEN
+    let server = Server::spawn();
+    let server2 = Server::spawn();
+    let mut client = server.connect().await?;
+    let mut client2 = server2.connect().await?;
+    // * Each node takes writes again once it has handed off the keys it no longer owns
+    let keys: Vec<String> = (0..20).map(|i| format!("key{i}")).collect();
+    for key in &keys {
+        client.set(key, "stale").await?;
+        client2.set(key, "stale").await?;
+    }
+    client.execute(&["CLUSTER", "MEET", &server2.bind_addr(), "eager"]).await?;
+
+    // WHEN - the rebalanced ring reaches the client
+    let leaders = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            client.execute(&["PING"]).await?;
+            let leaders: std::collections::HashSet<_> = keys
+                .iter()
+                .filter_map(|key| client.topology().leader_for_key(key.as_bytes()).cloned())
+                .collect();
+            if leaders.len() == 2 {
+                return anyhow::Ok(leaders);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await??;
+
+    // THEN - writes land on both partitions without surfacing redirections
+    let sets: Vec<Vec<&str>> = keys.iter().map(|key| vec!["SET", key, key]).collect();
+    let sets: Vec<&[&str]> = sets.iter().map(Vec::as_slice).collect();
+    let replies = client.pipeline(&sets).await?;
+    assert!(replies.iter().all(|reply| !matches!(reply, QueryIO::Err(_))), "{replies:?}");
+    for key in &keys {
+        assert_eq!(client.get(key).await?.as_deref(), Some(key.as_str()));
+    }
+    assert!(leaders.iter().any(|leader| leader.0 == server2.bind_addr()));
+    Ok(())
+}
@@ -0,0 +1,7 @@
+// * protox compiles the service definition in Rust, so building needs no protoc install
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let descriptors = protox::compile(["proto/duva.proto"], ["proto"])?;
+    tonic_prost_build::configure().compile_fds(descriptors)?;
+    println!("cargo:rerun-if-changed=proto/duva.proto");
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use duva::domains::caches::cache_manager::IndexedValueCodec;
+use duva::domains::query_io::QueryIO;
+use duva::prelude::anyhow;
+use duva::prelude::tokio::{self, sync::Mutex, sync::mpsc};
+use duva_client::DuvaClient;
+use pb::kv_server::Kv;
+use pb::watch_server::Watch;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+pub mod pb {
+    tonic::include_proto!("duva.v1");
+}
+
+/// Serves the `duva.v1` gRPC services in front of a duva cluster, by way of a [`DuvaClient`]
+/// connected to one of its nodes.
+pub struct Gateway {
+    address: String,
+    password: Option<String>,
+    // * Calls share one client session, and so one request id sequence for writes
+    client: Mutex<DuvaClient>,
+}
+
+impl Gateway {
+    pub async fn connect(address: &str, password: Option<String>) -> anyhow::Result<Self> {
+        let client = DuvaClient::connect_with_password(address, password.clone()).await?;
+        Ok(Self { address: address.to_string(), password, client: Mutex::new(client) })
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<QueryIO, Status> {
+        self.client.lock().await.execute(args).await.map_err(into_status)
+    }
+}
+
+#[tonic::async_trait]
+impl Kv for Gateway {
+    async fn get(
+        &self,
+        request: Request<pb::GetRequest>,
+    ) -> Result<Response<pb::GetResponse>, Status> {
+        let value = self.client.lock().await.get(&request.into_inner().key).await;
+        Ok(Response::new(pb::GetResponse { value: value.map_err(into_status)? }))
+    }
+
+    async fn set(
+        &self,
+        request: Request<pb::SetRequest>,
+    ) -> Result<Response<pb::SetResponse>, Status> {
+        let args = set_args(&request.into_inner());
+        self.execute(&args.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+        Ok(Response::new(pb::SetResponse {}))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<pb::DeleteRequest>,
+    ) -> Result<Response<pb::DeleteResponse>, Status> {
+        let keys = request.into_inner().keys;
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let deleted = self.client.lock().await.del(&keys).await.map_err(into_status)?;
+        Ok(Response::new(pb::DeleteResponse { deleted }))
+    }
+
+    async fn incr_by(
+        &self,
+        request: Request<pb::IncrByRequest>,
+    ) -> Result<Response<pb::IncrByResponse>, Status> {
+        let pb::IncrByRequest { key, delta } = request.into_inner();
+        let reply = self.execute(&["INCRBY", &key, &delta.to_string()]).await?;
+        Ok(Response::new(pb::IncrByResponse { value: decode_value(reply)? }))
+    }
+
+    async fn compare_and_swap(
+        &self,
+        request: Request<pb::CompareAndSwapRequest>,
+    ) -> Result<Response<pb::CompareAndSwapResponse>, Status> {
+        let pb::CompareAndSwapRequest { key, expected, value } = request.into_inner();
+        let reply = self.execute(&["CAS", &key, &expected, &value]).await?;
+        Ok(Response::new(pb::CompareAndSwapResponse { swapped: decode_value(reply)? == 1 }))
+    }
+
+    async fn txn(
+        &self,
+        request: Request<pb::TxnRequest>,
+    ) -> Result<Response<pb::TxnResponse>, Status> {
+        let ops = request.into_inner().ops;
+        if ops.is_empty() {
+            return Err(Status::invalid_argument("a transaction needs at least one operation"));
+        }
+
+        // * Runs as a single `BATCH`, whose operations are separated by a standalone ";"
+        let mut args = vec!["BATCH".to_string()];
+        for (i, op) in ops.into_iter().enumerate() {
+            let op_args = match op.op {
+                | Some(pb::txn_op::Op::Set(set)) => set_args(&set),
+                | Some(pb::txn_op::Op::Delete(delete)) => {
+                    std::iter::once("DEL".to_string()).chain(delete.keys).collect()
+                },
+                | None => return Err(Status::invalid_argument("transaction operation not set")),
+            };
+            if op_args.iter().any(|arg| arg == ";") {
+                return Err(Status::invalid_argument("keys and values cannot be a standalone ';'"));
+            }
+            if i > 0 {
+                args.push(";".to_string());
+            }
+            args.extend(op_args);
+        }
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let applied = decode_value(self.execute(&args).await?)?;
+        Ok(Response::new(pb::TxnResponse { applied: applied as u64 }))
+    }
+}
+
+#[tonic::async_trait]
+impl Watch for Gateway {
+    type WatchTopologyStream = ReceiverStream<Result<pb::TopologyEvent, Status>>;
+
+    async fn watch_topology(
+        &self,
+        _request: Request<pb::WatchTopologyRequest>,
+    ) -> Result<Response<Self::WatchTopologyStream>, Status> {
+        // * Each watch subscribes on a connection of its own, as the subscription takes it over
+        let client = DuvaClient::connect_with_password(&self.address, self.password.clone())
+            .await
+            .map_err(into_status)?;
+        let mut subscription = client.subscribe_shards().await.map_err(into_status)?;
+
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            loop {
+                let event = match subscription.next().await {
+                    | Ok(lines) => lines
+                        .iter()
+                        .map(|line| parse_shard(line))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|shards| pb::TopologyEvent { shards }),
+                    | Err(err) => Err(into_status(err)),
+                };
+                let failed = event.is_err();
+                // * Stops once the caller goes away, or after telling it the subscription broke
+                if tx.send(event).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+fn set_args(set: &pb::SetRequest) -> Vec<String> {
+    let mut args = vec!["SET".to_string(), set.key.clone(), set.value.clone()];
+    if let Some(ttl_ms) = set.ttl_ms {
+        args.extend(["PX".to_string(), ttl_ms.to_string()]);
+    }
+    args
+}
+
+// * Writes are answered with the value they produced alongside the log index they were committed at
+fn decode_value(reply: QueryIO) -> Result<i64, Status> {
+    let QueryIO::SimpleString(value) = reply else {
+        return Err(Status::internal(format!("unexpected reply {reply:?}")));
+    };
+    IndexedValueCodec::decode_value(String::from_utf8_lossy(&value))
+        .ok_or_else(|| Status::internal(format!("unexpected reply {value:?}")))
+}
+
+/// `<replid> <leader> <replica,...|-> <start-end,...>`, as `CLUSTER SHARDS` lists shards.
+fn parse_shard(line: &str) -> Result<pb::Shard, Status> {
+    let invalid = || Status::internal(format!("unexpected shard {line:?}"));
+    let mut fields = line.split(' ');
+    let (Some(replid), Some(leader), Some(replicas), ranges) =
+        (fields.next(), fields.next(), fields.next(), fields.next().unwrap_or_default())
+    else {
+        return Err(invalid());
+    };
+    let replicas = match replicas {
+        | "-" => vec![],
+        | replicas => replicas.split(',').map(String::from).collect(),
+    };
+    let ranges = ranges
+        .split(',')
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+            Ok(pb::TokenRange {
+                start: start.parse().map_err(|_| invalid())?,
+                end: end.parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect::<Result<_, Status>>()?;
+    Ok(pb::Shard { replid: replid.to_string(), leader: leader.to_string(), replicas, ranges })
+}
+
+// * Errors the server replied with are the caller's to fix; the rest mean the cluster is out of reach
+fn into_status(err: anyhow::Error) -> Status {
+    let message = err.to_string();
+    if err.downcast_ref::<std::io::Error>().is_some() || message.starts_with("TRYAGAIN") {
+        return Status::unavailable(message);
+    }
+    Status::invalid_argument(message)
+}
@@ -0,0 +1,30 @@
+use clap::Parser;
+use duva::prelude::{anyhow, tokio};
+use duva_grpc::Gateway;
+use duva_grpc::pb::{kv_server::KvServer, watch_server::WatchServer};
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[command(name = "duva-grpc", about = "gRPC gateway to a duva cluster")]
+struct Args {
+    /// Node of the cluster to connect to
+    #[arg(long, default_value = "127.0.0.1:6379")]
+    address: String,
+    #[arg(short = 'a', long)]
+    password: Option<String>,
+    /// Address to serve gRPC on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: std::net::SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let gateway = Arc::new(Gateway::connect(&args.address, args.password).await?);
+    tonic::transport::Server::builder()
+        .add_service(KvServer::from_arc(gateway.clone()))
+        .add_service(WatchServer::from_arc(gateway))
+        .serve(args.listen)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use duva::prelude::anyhow;
+use duva::prelude::tokio::{self, time::Duration};
+use duva_grpc::Gateway;
+use duva_grpc::pb::kv_client::KvClient;
+use duva_grpc::pb::watch_client::WatchClient;
+use duva_grpc::pb::{self, kv_server::KvServer, watch_server::WatchServer};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use tonic::transport::Channel;
+
+fn available_port() -> u16 {
+    loop {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        // * Peers of a node connect on its port + 10000
+        if port < 55000 {
+            return port;
+        }
+    }
+}
+
+/// A `duva` server process, killed and cleaned up after on drop.
+struct Server {
+    process: Child,
+    port: u16,
+    dir: std::path::PathBuf,
+}
+
+impl Server {
+    fn spawn() -> Self {
+        static ONCE: std::sync::Once = std::sync::Once::new();
+        ONCE.call_once(|| {
+            let mut command = Command::new("cargo");
+            command.args(["build", "-p", "duva"]);
+            command.stdout(Stdio::null()).stderr(Stdio::null()).status().unwrap();
+        });
+
+        let port = available_port();
+        let dir = std::env::temp_dir().join(format!("duva-grpc-test-{port}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = std::env::current_dir().unwrap().parent().unwrap().join("target/debug/duva");
+        let process = Command::new(path)
+            .args(["--port", &port.to_string(), "--dir", dir.to_str().unwrap()])
+            .args(["--tpp", dir.join("duva.tp").to_str().unwrap()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        Self { process, port, dir }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Serves the gateway for `server` in the background, and connects to it.
+async fn serve_gateway(server: &Server) -> anyhow::Result<Channel> {
+    let address = format!("127.0.0.1:{}", server.port);
+    let mut gateway = None;
+    for _ in 0..100 {
+        if let Ok(connected) = Gateway::connect(&address, None).await {
+            gateway = Some(Arc::new(connected));
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    let gateway = gateway.ok_or(anyhow::anyhow!("server on {address} did not start"))?;
+
+    let listen = format!("127.0.0.1:{}", available_port());
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(KvServer::from_arc(gateway.clone()))
+            .add_service(WatchServer::from_arc(gateway))
+            .serve(listen.parse()?),
+    );
+    for _ in 0..50 {
+        if let Ok(channel) = Channel::from_shared(format!("http://{listen}"))?.connect().await {
+            return Ok(channel);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    anyhow::bail!("gateway on {listen} did not start")
+}
+
+fn set(key: &str, value: &str) -> pb::SetRequest {
+    pb::SetRequest { key: key.into(), value: value.into(), ttl_ms: None }
+}
+
+#[tokio::test]
+async fn test_gateway_serves_kv_operations() -> anyhow::Result<()> {
+    // GIVEN
+    let server = Server::spawn();
+    let mut kv = KvClient::new(serve_gateway(&server).await?);
+
+    // WHEN
+    kv.set(set("foo", "bar")).await?;
+    kv.set(pb::SetRequest { ttl_ms: Some(60_000), ..set("expiring", "1") }).await?;
+
+    // THEN
+    let get = |key: &str| pb::GetRequest { key: key.into() };
+    assert_eq!(kv.get(get("foo")).await?.into_inner().value.as_deref(), Some("bar"));
+    assert_eq!(kv.get(get("missing")).await?.into_inner().value, None);
+
+    let incr = pb::IncrByRequest { key: "counter".into(), delta: 5 };
+    assert_eq!(kv.incr_by(incr.clone()).await?.into_inner().value, 5);
+    assert_eq!(kv.incr_by(incr).await?.into_inner().value, 10);
+
+    let cas = |expected: &str| pb::CompareAndSwapRequest {
+        key: "foo".into(),
+        expected: expected.into(),
+        value: "baz".into(),
+    };
+    assert!(!kv.compare_and_swap(cas("nope")).await?.into_inner().swapped);
+    assert!(kv.compare_and_swap(cas("bar")).await?.into_inner().swapped);
+    assert_eq!(kv.get(get("foo")).await?.into_inner().value.as_deref(), Some("baz"));
+
+    let deleted =
+        kv.delete(pb::DeleteRequest { keys: vec!["foo".into(), "missing".into()] }).await?;
+    assert_eq!(deleted.into_inner().deleted, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gateway_applies_transactions_whole() -> anyhow::Result<()> {
+    // GIVEN
+    let server = Server::spawn();
+    let mut kv = KvClient::new(serve_gateway(&server).await?);
+    kv.set(set("c", "3")).await?;
+    let op = |op| pb::TxnOp { op: Some(op) };
+
+    // WHEN
+    let txn = pb::TxnRequest {
+        ops: vec![
+            op(pb::txn_op::Op::Set(set("a", "1"))),
+            op(pb::txn_op::Op::Set(set("b", "2"))),
+            op(pb::txn_op::Op::Delete(pb::DeleteRequest { keys: vec!["c".into()] })),
+        ],
+    };
+    let applied = kv.txn(txn).await?.into_inner().applied;
+
+    // THEN
+    assert_eq!(applied, 3);
+    let get = |key: &str| pb::GetRequest { key: key.into() };
+    assert_eq!(kv.get(get("a")).await?.into_inner().value.as_deref(), Some("1"));
+    assert_eq!(kv.get(get("b")).await?.into_inner().value.as_deref(), Some("2"));
+    assert_eq!(kv.get(get("c")).await?.into_inner().value, None);
+
+    // * A transaction without operations is turned down by the gateway itself
+    let empty = kv.txn(pb::TxnRequest { ops: vec![] }).await.unwrap_err();
+    assert_eq!(empty.code(), tonic::Code::InvalidArgument);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gateway_streams_topology() -> anyhow::Result<()> {
+    // GIVEN
+    let server = Server::spawn();
+    let mut watch = WatchClient::new(serve_gateway(&server).await?);
+
+    // WHEN
+    let mut events = watch.watch_topology(pb::WatchTopologyRequest {}).await?.into_inner();
+
+    // THEN - the current shards come first
+    let event = events.message().await?.expect("stream ended before the first event");
+    assert_eq!(event.shards.len(), 1);
+    let shard = &event.shards[0];
+    assert_eq!(shard.leader, format!("127.0.0.1:{}", server.port));
+    assert!(shard.replicas.is_empty());
+    assert_eq!(shard.ranges, vec![pb::TokenRange { start: 0, end: u64::MAX }]);
+    Ok(())
+}
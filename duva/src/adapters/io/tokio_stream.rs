@@ -1,6 +1,6 @@
 use crate::domains::IoError;
 use crate::domains::interface::{TRead, TSerdeReadWrite, TWrite};
-use crate::domains::query_io::SERDE_CONFIG;
+use crate::domains::query_io::{IncompleteFrame, SERDE_CONFIG};
 use crate::domains::{QueryIO, deserialize};
 use bytes::BytesMut;
 use std::fmt::Debug;
@@ -10,7 +10,11 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 #[async_trait::async_trait]
 impl<T: AsyncWriteExt + std::marker::Unpin + Sync + Send + Debug + 'static> TWrite for T {
     async fn write(&mut self, io: QueryIO) -> Result<(), IoError> {
-        self.write_all(&io.serialize()).await.map_err(|e| Into::<IoError>::into(e.kind()))
+        self.write_bytes(&io.serialize()).await
+    }
+
+    async fn write_bytes(&mut self, buf: &[u8]) -> Result<(), IoError> {
+        self.write_all(buf).await.map_err(|e| Into::<IoError>::into(e.kind()))
     }
 }
 
@@ -66,6 +70,14 @@ impl<T: AsyncReadExt + std::marker::Unpin + Sync + Send + Debug + 'static> TRead
                     // * Remove the parsed portion from the buffer
                     remaining_buffer = remaining_buffer.split_off(consumed);
                 },
+                | Err(e) if e.downcast_ref::<IncompleteFrame>().is_some() => {
+                    // * The rest of the frame has not arrived yet
+                    let received = remaining_buffer.len();
+                    self.read_bytes(&mut remaining_buffer).await?;
+                    if remaining_buffer.len() == received {
+                        return Err(IoError::ConnectionAborted);
+                    }
+                },
                 | Err(e) => {
                     // Handle parsing errors
                     // You might want to log the error or handle it differently based on your use case
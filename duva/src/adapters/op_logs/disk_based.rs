@@ -1,14 +1,19 @@
-use crate::domains::operation_logs::interfaces::TWriteAheadLog;
-use crate::domains::operation_logs::{WriteOperation, WriteRequest};
+use super::frame;
+use crate::domains::compression::Compression;
+use crate::domains::encryption::KeyRing;
+use crate::domains::operation_logs::WriteOperation;
+use crate::domains::operation_logs::interfaces::{FsyncPolicy, TWriteAheadLog, WalVerification};
 use anyhow::{Context, Result};
-use bytes::Bytes;
 use regex::Regex;
+use std::fmt::Display;
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter, Seek, Write};
 use std::io::{ErrorKind, Read};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use tracing::{error, warn};
 
-const SEGMENT_SIZE: usize = 1024 * 1024; // 1MB per segment
+pub const DEFAULT_SEGMENT_SIZE: usize = 1024 * 1024; // 1MB per segment
 
 /// A local write-ahead-log (WAL) file (op_logs) implementation using segmented logs.
 pub struct FileOpLogs {
@@ -16,6 +21,15 @@ pub struct FileOpLogs {
     path: PathBuf,
     active_segment: Segment,
     segments: Vec<Segment>,
+    // * Size in bytes past which the active segment is sealed and a new one is started
+    segment_size: usize,
+    fsync_policy: FsyncPolicy,
+    // * Codec new entries are compressed with. Entries are read back whichever codec they were written with.
+    compression: Compression,
+    // * Keys entries are sealed with. Segments are opened with them, so they can't change afterwards.
+    encryption: KeyRing,
+    // * Whether the active segment holds writes that have not been synced yet
+    unsynced: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -55,7 +69,7 @@ impl Segment {
         Self { path, start_index: 0, end_index: 0, size: 0, lookups: Vec::new() }
     }
 
-    fn read_operations(&self) -> Result<Vec<WriteOperation>> {
+    fn read_operations(&self, keys: &KeyRing) -> Result<Vec<WriteOperation>> {
         let file = OpenOptions::new()
             .read(true)
             .open(&self.path)
@@ -64,15 +78,20 @@ impl Segment {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
 
-        if buf.is_empty() {
-            return Ok(Vec::new()); // Return empty vec for empty files
+        let decoded = frame::decode(&buf, keys)?;
+        if decoded.is_corrupted(buf.len()) {
+            return Err(anyhow::anyhow!(
+                "Corrupted entry at byte {} of segment '{}'",
+                decoded.valid_len,
+                self.path.display()
+            ));
         }
-
-        let bytes = Bytes::copy_from_slice(&buf[..]);
-        WriteRequest::deserialize(bytes) // Assuming this returns Result<Vec<WriteOperation>>
+        Ok(decoded.into_operations())
     }
 
-    fn from_path(path: &PathBuf) -> Result<Self> {
+    /// Loads the segment, cutting it at the last valid entry when it holds a torn write or an entry
+    /// failing its checksum. What was cut is reported back.
+    fn recover(path: &PathBuf, keys: &KeyRing) -> Result<(Self, Option<RecoveryReport>)> {
         let file = OpenOptions::new()
             .read(true)
             .open(path)
@@ -82,28 +101,99 @@ impl Segment {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
 
-        // Parse the segment file to get operations and build index
-        let bytes = Bytes::copy_from_slice(&buf[..]);
-        let operations = WriteRequest::deserialize(bytes)?;
+        let decoded = frame::decode(&buf, keys)
+            .with_context(|| format!("Failed to read segment '{}'", path.display()))?;
+        let report = decoded.is_corrupted(buf.len()).then(|| RecoveryReport {
+            segment: path.clone(),
+            last_valid_index: decoded.entries.last().map(|(_, op)| op.log_index),
+            dropped_bytes: buf.len() - decoded.valid_len,
+            dropped_segments: 0,
+        });
+        if report.is_some() {
+            let file = OpenOptions::new().write(true).open(path)?;
+            file.set_len(decoded.valid_len as u64)?;
+            file.sync_all()?;
+        }
 
-        let mut current_offset = 0;
-        let mut index_data = Vec::new();
+        let lookups: Vec<LookupIndex> = decoded
+            .entries
+            .iter()
+            .map(|(offset, op)| LookupIndex::new(op.log_index, *offset))
+            .collect();
+        let segment = Segment {
+            path: path.clone(),
+            start_index: lookups.first().map(|op| op.log_index).unwrap_or(0),
+            end_index: lookups.last().map(|op| op.log_index).unwrap_or(0),
+            size: decoded.valid_len,
+            lookups,
+        };
+        Ok((segment, report))
+    }
 
-        // Build index_data by calculating offsets for each operation
-        for op in operations.into_iter() {
-            index_data.push(LookupIndex::new(op.log_index, current_offset));
-            // Each operation is prefixed with REPLICATE_PREFIX (1 byte) and followed by serialized data
-            let serialized = op.serialize();
-            current_offset += serialized.len();
+    /// Checks the entries of the segment as stored, along with the in-memory index kept for it.
+    /// Returns false once an inconsistency is found.
+    fn verify(&self, keys: &KeyRing, verification: &mut WalVerification) -> bool {
+        let name = self
+            .path
+            .file_name()
+            .map_or(self.path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        verification.segments += 1;
+
+        let decoded = match std::fs::read(&self.path) {
+            | Ok(buf) => frame::decode(&buf, keys).map(|decoded| (decoded, buf.len())),
+            | Err(err) => Err(err.into()),
+        };
+        let (decoded, len) = match decoded {
+            | Ok(decoded) => decoded,
+            | Err(err) => {
+                verification.fail(format!("{name}: {err}"));
+                return false;
+            },
+        };
+
+        for (offset, op) in &decoded.entries {
+            if !verification.check(format_args!("{name} at byte {offset}"), op) {
+                return false;
+            }
+        }
+        if decoded.is_corrupted(len) {
+            verification.fail(format!(
+                "{name} at byte {}: entry is torn or fails its checksum",
+                decoded.valid_len
+            ));
+            return false;
+        }
+        let indexed =
+            decoded.entries.iter().map(|(offset, op)| LookupIndex::new(op.log_index, *offset));
+        if let Some((position, (stored, expected))) = indexed
+            .zip(&self.lookups)
+            .enumerate()
+            .find(|(_, (stored, expected))| stored != *expected)
+        {
+            verification.fail(format!(
+                "{name}: entry {position} is log index {} at byte {}, the index expects log index {} at byte {}",
+                stored.log_index, stored.byte_offset, expected.log_index, expected.byte_offset
+            ));
+            return false;
         }
+        if decoded.entries.len() != self.lookups.len() {
+            verification.fail(format!(
+                "{name}: holds {} entries, the index expects {}",
+                decoded.entries.len(),
+                self.lookups.len()
+            ));
+            return false;
+        }
+        true
+    }
 
-        Ok(Segment {
-            path: path.clone(),
-            start_index: index_data.first().map(|op| op.log_index).unwrap_or(0),
-            end_index: index_data.last().map(|op| op.log_index).unwrap_or(0),
-            size: buf.len(),
-            lookups: index_data,
-        })
+    fn number(&self) -> u64 {
+        self.path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix("segment_"))
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
     }
 
     fn create_writer(&self) -> Result<BufWriter<File>> {
@@ -120,7 +210,7 @@ impl Segment {
     }
 
     // Add method to read operation at specific offset
-    fn read_at_offset(&self, offset: usize) -> Result<WriteOperation> {
+    fn read_at_offset(&self, offset: usize, keys: &KeyRing) -> Result<WriteOperation> {
         let file = OpenOptions::new().read(true).open(&self.path)?;
 
         let mut reader = BufReader::new(file);
@@ -129,10 +219,34 @@ impl Segment {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
 
-        let bytes = Bytes::copy_from_slice(&buf);
-        let operations = WriteRequest::deserialize(bytes)?;
+        frame::decode(&buf, keys)?
+            .into_operations()
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No valid operation found at offset"))
+    }
+}
+
+/// What startup recovery cut off a segment holding a torn write or a corrupted entry.
+#[derive(Debug)]
+struct RecoveryReport {
+    segment: PathBuf,
+    last_valid_index: Option<u64>,
+    dropped_bytes: usize,
+    // * Segments following the corrupted one, which no longer follow on from the log
+    dropped_segments: usize,
+}
 
-        operations.into_iter().next().ok_or_else(|| anyhow::anyhow!("No operation found at offset"))
+impl Display for RecoveryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Recovered WAL segment '{}': truncated {} corrupted bytes after log index {}, dropped {} later segment(s)",
+            self.segment.display(),
+            self.dropped_bytes,
+            self.last_valid_index.map_or("none".to_string(), |index| index.to_string()),
+            self.dropped_segments
+        )
     }
 }
 
@@ -146,6 +260,12 @@ impl FileOpLogs {
     ///
     /// Returns an error if the file/directory cannot be created or opened.
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path, KeyRing::default())
+    }
+
+    /// Like `new`, for segments whose entries are sealed with `encryption`. Opening fails if an
+    /// entry was sealed with a key that is not in it.
+    pub fn open<P: AsRef<Path>>(path: P, encryption: KeyRing) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
         Self::validate_folder(&path)?;
@@ -153,16 +273,53 @@ impl FileOpLogs {
         // Detect and sort existing segment files
         let segment_paths = Self::detect_and_sort_existing_segments(&path)?;
 
-        let active_segment = Self::take_last_segment_otherwise_init(&path, segment_paths.clone())?;
-
-        // Load all segments except the last one (which is the active segment)
         let mut segments = Vec::new();
-        for segment_path in segment_paths.iter().take(segment_paths.len().saturating_sub(1)) {
-            let segment = Segment::from_path(segment_path)?;
+        for (i, segment_path) in segment_paths.iter().enumerate() {
+            let (segment, report) = Segment::recover(segment_path, &encryption)?;
             segments.push(segment);
+            let Some(mut report) = report else {
+                continue;
+            };
+            // * Entries past a corrupted one can't be trusted to follow on from the log
+            for later in &segment_paths[i + 1..] {
+                std::fs::remove_file(later)?;
+                report.dropped_segments += 1;
+            }
+            warn!("{report}");
+            break;
         }
 
-        Ok(Self { path, active_segment, segments })
+        // The last segment is the active one
+        let active_segment = match segments.pop() {
+            | Some(segment) => segment,
+            | None => Segment::new(path.join("segment_0.oplog")),
+        };
+
+        Ok(Self {
+            path,
+            active_segment,
+            segments,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            fsync_policy: FsyncPolicy::default(),
+            compression: Compression::default(),
+            encryption,
+            unsynced: false,
+        })
+    }
+
+    pub fn with_segment_size(mut self, segment_size: usize) -> Self {
+        self.segment_size = segment_size.max(1);
+        self
+    }
+
+    pub fn with_fsync_policy(mut self, fsync_policy: FsyncPolicy) -> Self {
+        self.fsync_policy = fsync_policy;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
     }
 
     fn validate_folder(path: &PathBuf) -> Result<(), anyhow::Error> {
@@ -219,33 +376,20 @@ impl FileOpLogs {
         Ok(segments.into_iter().map(|(_, path)| path).collect())
     }
 
-    fn take_last_segment_otherwise_init(
-        path: &Path,
-        segment_paths: Vec<PathBuf>,
-    ) -> Result<Segment, anyhow::Error> {
-        let active_segment = if segment_paths.is_empty() {
-            // No segments exist — create initial segment
-            let segment_path = path.join("segment_0.oplog");
-            Segment::new(segment_path)
-        } else {
-            // Segments exist — use the last one as active
-            Segment::from_path(segment_paths.last().unwrap())?
-        };
-        Ok(active_segment)
-    }
-
     fn rotate_segment(&mut self) -> Result<()> {
         // Close current segment
         if let Ok(mut writer) = self.active_segment.create_writer() {
             writer.flush()?;
             writer.get_mut().sync_all()?;
         }
+        self.unsynced = false;
 
         // Add to segments list
         self.segments.push(self.active_segment.clone());
 
         // Create new segment
-        let next_index = self.segments.len();
+        // * Numbers keep increasing even after older segments are compacted away
+        let next_index = self.active_segment.number() + 1;
         let segment_path = self.path.join(format!("segment_{next_index}.oplog"));
         let _ = OpenOptions::new().create(true).append(true).read(true).open(&segment_path)?;
 
@@ -260,6 +404,82 @@ impl FileOpLogs {
         Ok(())
     }
 
+    /// Segments starting after `log_index` are deleted and the one holding it is cut right after it,
+    /// becoming the active segment again.
+    fn truncate_segments_after(&mut self, log_index: u64) -> Result<()> {
+        let mut segments = std::mem::take(&mut self.segments);
+        segments.push(self.active_segment.clone());
+
+        // * The first segment is always kept so that there is one to append to
+        while segments.len() > 1
+            && segments.last().is_some_and(|segment| {
+                segment.lookups.first().map_or(segment.start_index, |index| index.log_index)
+                    > log_index
+            })
+        {
+            let segment = segments.pop().unwrap();
+            if segment.path.exists() {
+                std::fs::remove_file(&segment.path)?;
+            }
+        }
+
+        let mut active_segment = segments.pop().unwrap();
+        let cut = active_segment.lookups.partition_point(|index| index.log_index <= log_index);
+        if let Some(byte_offset) = active_segment.lookups.get(cut).map(|index| index.byte_offset) {
+            let file = OpenOptions::new().write(true).open(&active_segment.path)?;
+            file.set_len(byte_offset as u64)?;
+            file.sync_all()?;
+            active_segment.lookups.truncate(cut);
+            active_segment.size = byte_offset;
+        }
+        match active_segment.lookups.last() {
+            | Some(last) => active_segment.end_index = last.log_index,
+            | None => {
+                active_segment.start_index = log_index + 1;
+                active_segment.end_index = log_index;
+            },
+        }
+
+        self.segments = segments;
+        self.active_segment = active_segment;
+        Ok(())
+    }
+
+    fn write(&mut self, op: WriteOperation) -> Result<()> {
+        // Check if we need to rotate
+        if self.active_segment.size >= self.segment_size {
+            self.rotate_segment()?;
+        }
+
+        let log_index = op.log_index;
+        if self.active_segment.lookups.is_empty() {
+            self.active_segment.start_index = log_index;
+        }
+
+        // Update index before writing
+        self.active_segment.lookups.push(LookupIndex::new(log_index, self.active_segment.size));
+
+        let serialized = frame::encode(op, self.compression, &self.encryption);
+
+        let mut writer = self.active_segment.create_writer()?;
+        writer.write_all(&serialized)?;
+        writer.flush()?;
+        self.unsynced = true;
+
+        self.active_segment.size += serialized.len();
+        self.active_segment.end_index = log_index;
+
+        Ok(())
+    }
+
+    fn sync_per_policy(&mut self) -> Result<()> {
+        match self.fsync_policy {
+            | FsyncPolicy::Always => self.fsync(),
+            // * Left to the background flusher or to the operating system
+            | FsyncPolicy::EverySec | FsyncPolicy::No => Ok(()),
+        }
+    }
+
     fn read_ops_from_reader(
         &self,
         reader: &mut BufReader<File>,
@@ -275,11 +495,7 @@ impl FileOpLogs {
             return Ok(collected_ops);
         }
 
-        let bytes = Bytes::copy_from_slice(&buffer);
-        // Assuming deserialize can handle a buffer with multiple concatenated operations
-        let operations_in_buffer = WriteRequest::deserialize(bytes)?;
-
-        for op in operations_in_buffer {
+        for op in frame::decode(&buffer, &self.encryption)?.into_operations() {
             if op.log_index > end_inclusive {
                 // Reached beyond the end of the desired range
                 break;
@@ -297,34 +513,16 @@ impl FileOpLogs {
 impl TWriteAheadLog for FileOpLogs {
     /// Appends a single `WriteOperation` to the file.
     fn append(&mut self, op: WriteOperation) -> Result<()> {
-        // Check if we need to rotate
-        if self.active_segment.size >= SEGMENT_SIZE {
-            self.rotate_segment()?;
-        }
-
-        let log_index = op.log_index;
-
-        // Update index before writing
-        self.active_segment.lookups.push(LookupIndex::new(log_index, self.active_segment.size));
-
-        let serialized = op.serialize();
-
-        let mut writer = self.active_segment.create_writer()?;
-        writer.write_all(&serialized)?;
-        writer.flush()?;
-        writer.get_mut().sync_all()?;
-
-        self.active_segment.size += serialized.len();
-        self.active_segment.end_index = log_index;
-
-        Ok(())
+        self.write(op)?;
+        self.sync_per_policy()
     }
 
+    /// Appends all operations before syncing, so that they share a single sync under `FsyncPolicy::Always`.
     fn append_many(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
         for op in ops {
-            self.append(op)?;
+            self.write(op)?;
         }
-        Ok(())
+        self.sync_per_policy()
     }
 
     fn range(&self, start_exclusive: u64, end_inclusive: u64) -> Vec<WriteOperation> {
@@ -340,7 +538,7 @@ impl TWriteAheadLog for FileOpLogs {
             }
 
             // Find the index in index_data for the first log entry >= start_exclusive + 1
-            let start_log_index_in_segment = start_exclusive.checked_add(1).unwrap_or(u64::MAX); // Avoid overflow
+            let start_log_index_in_segment = start_exclusive.saturating_add(1); // Avoid overflow
 
             // Find the position in index_data where log_index is >= start_log_index_in_segment
             // Use binary search on the sorted index_data for efficiency
@@ -371,17 +569,16 @@ impl TWriteAheadLog for FileOpLogs {
                 // If the desired start index is before or at the beginning of the segment,
                 // we should start reading from the beginning of the segment file (offset 0)
                 // if the segment also overlaps with the end_inclusive range.
-                if segment.start_index <= end_inclusive {
-                    if let Ok(file) = OpenOptions::new().read(true).open(&segment.path) {
-                        let mut reader = BufReader::new(file); // Starts at offset 0
-                        if let Ok(ops) =
-                            self.read_ops_from_reader(&mut reader, start_exclusive, end_inclusive)
-                        {
-                            result.extend(ops);
-                        }
-                        // else: Handle error during reading ops from reader
+                if segment.start_index <= end_inclusive
+                    && let Ok(file) = OpenOptions::new().read(true).open(&segment.path)
+                {
+                    let mut reader = BufReader::new(file); // Starts at offset 0
+                    if let Ok(ops) =
+                        self.read_ops_from_reader(&mut reader, start_exclusive, end_inclusive)
+                    {
+                        result.extend(ops);
                     }
-                    // else: Handle error opening file
+                    // else: Handle error during reading ops from reader
                 }
             }
         }
@@ -399,14 +596,14 @@ impl TWriteAheadLog for FileOpLogs {
     {
         // Replay all segments in order
         for segment in &self.segments {
-            let operations = segment.read_operations()?;
+            let operations = segment.read_operations(&self.encryption)?;
             for op in operations {
                 f(op);
             }
         }
 
         // Replay active segment
-        let active_operations = self.active_segment.read_operations()?;
+        let active_operations = self.active_segment.read_operations(&self.encryption)?;
         for op in active_operations {
             f(op);
         }
@@ -416,14 +613,30 @@ impl TWriteAheadLog for FileOpLogs {
 
     /// Forces any buffered data to be written to disk.
     fn fsync(&mut self) -> Result<()> {
+        if !self.unsynced {
+            return Ok(());
+        }
         // Open in append mode to get a file handle to the active segment
         let mut file = OpenOptions::new().append(true).open(&self.active_segment.path)?;
         file.flush()?;
         file.sync_all()?;
+        self.unsynced = false;
 
         Ok(())
     }
 
+    fn fsync_policy(&self) -> FsyncPolicy {
+        self.fsync_policy
+    }
+
+    fn set_fsync_policy(&mut self, policy: FsyncPolicy) -> Result<()> {
+        self.fsync_policy = policy;
+        if policy == FsyncPolicy::Always {
+            self.fsync()?;
+        }
+        Ok(())
+    }
+
     fn read_at(&self, log_index: u64) -> Option<WriteOperation> {
         // First check sealed segments
         for segment in &self.segments {
@@ -431,7 +644,7 @@ impl TWriteAheadLog for FileOpLogs {
                 && segment.end_index >= log_index
                 && let Some(offset) = segment.find_offset(log_index)
             {
-                return segment.read_at_offset(offset).ok();
+                return segment.read_at_offset(offset, &self.encryption).ok();
             }
         }
 
@@ -440,7 +653,7 @@ impl TWriteAheadLog for FileOpLogs {
             && self.active_segment.end_index >= log_index
             && let Some(offset) = self.active_segment.find_offset(log_index)
         {
-            return self.active_segment.read_at_offset(offset).ok();
+            return self.active_segment.read_at_offset(offset, &self.encryption).ok();
         }
 
         None
@@ -459,15 +672,39 @@ impl TWriteAheadLog for FileOpLogs {
     }
 
     fn truncate_after(&mut self, log_index: u64) {
-        // Remove segments after the truncation point
-        self.segments.retain(|segment| segment.end_index <= log_index);
+        if let Err(err) = self.truncate_segments_after(log_index) {
+            error!("failed to truncate logs after {log_index}: {err}");
+        }
+    }
 
-        // If active segment needs truncation
+    fn sealed_segments(&self) -> Vec<RangeInclusive<u64>> {
+        self.segments.iter().map(|segment| segment.start_index..=segment.end_index).collect()
+    }
 
-        if self.active_segment.start_index <= log_index && self.active_segment.end_index > log_index
-        {
-            // TODO: Implement truncation of active segment
+    fn compact_until(&mut self, log_index: u64) -> Result<()> {
+        // * Only sealed segments fully covered by the snapshot are removed.
+        // * The active segment is kept as it is still being appended to.
+        let (compacted, retained): (Vec<_>, Vec<_>) = std::mem::take(&mut self.segments)
+            .into_iter()
+            .partition(|segment| segment.end_index <= log_index);
+        self.segments = retained;
+
+        for segment in compacted {
+            if segment.path.exists() {
+                std::fs::remove_file(&segment.path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn verify(&self) -> WalVerification {
+        let mut verification = WalVerification::default();
+        for segment in self.segments.iter().chain(std::iter::once(&self.active_segment)) {
+            if !segment.verify(&self.encryption, &mut verification) {
+                break;
+            }
         }
+        verification
     }
 
     fn follower_full_sync(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
@@ -504,7 +741,7 @@ impl TWriteAheadLog for FileOpLogs {
 
         for op in ops.into_iter() {
             let log_index = op.log_index;
-            let serialized = op.serialize();
+            let serialized = frame::encode(op, self.compression, &self.encryption);
             new_segment.lookups.push(LookupIndex::new(log_index, current_offset));
             current_offset += serialized.len();
             writer.write_all(&serialized)?;
@@ -518,6 +755,7 @@ impl TWriteAheadLog for FileOpLogs {
 
         // Replace existing segments with the new one
         self.active_segment = new_segment;
+        self.unsynced = false;
 
         Ok(())
     }
@@ -526,6 +764,8 @@ impl TWriteAheadLog for FileOpLogs {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domains::operation_logs::WriteRequest;
+    use crate::domains::operation_logs::logger::{LogSnapshot, RecoveryTarget, ReplicatedLogs};
     use anyhow::Result;
     use tempfile::TempDir;
 
@@ -535,6 +775,7 @@ mod tests {
             log_index: index,
             term,
             session_req: None,
+            timestamp: 0,
         }
     }
 
@@ -562,8 +803,13 @@ mod tests {
         let mut op_logs = FileOpLogs::new(path).unwrap();
         let request =
             WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None };
-        let write_op =
-            WriteOperation { request: request.clone(), log_index: 0, term: 0, session_req: None };
+        let write_op = WriteOperation {
+            request: request.clone(),
+            log_index: 0,
+            term: 0,
+            session_req: None,
+            timestamp: 0,
+        };
 
         // WHEN
         op_logs.append(write_op).unwrap();
@@ -574,8 +820,11 @@ mod tests {
         let mut buf = Vec::new();
         file.read_to_end(&mut buf).unwrap();
 
-        let (encoded, _): (WriteOperation, usize) =
-            bincode::decode_from_slice(&buf[1..], bincode::config::standard()).unwrap();
+        let (encoded, _): (WriteOperation, usize) = bincode::decode_from_slice(
+            &buf[frame::ENTRY_HEADER_LEN + 1..],
+            bincode::config::standard(),
+        )
+        .unwrap();
 
         assert_eq!(encoded.request, request);
     }
@@ -609,49 +858,71 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "This is desired behavior. However, currently deserialize fails if any part of the file is corrupted."]
-    fn test_replay_partial_data() -> Result<()> {
+    fn test_replay_encrypted_operations() -> Result<()> {
         // GIVEN
         let dir = TempDir::new()?;
-        let path = dir.path().join("local.oplog");
+        const OLD_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        const NEW_KEY: &str = "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100";
+        let old: KeyRing = OLD_KEY.parse()?;
+        let rotated: KeyRing = format!("{NEW_KEY},{OLD_KEY}").parse()?;
+        FileOpLogs::open(dir.path(), old)?.append(set_helper(0, 0))?;
+        FileOpLogs::open(dir.path(), rotated.clone())?.append(set_helper(1, 0))?;
 
         // WHEN
-        // Append three ops.
+        let mut op_logs = FileOpLogs::open(dir.path(), rotated)?;
+        let mut ops = Vec::new();
+        op_logs.replay(|op| ops.push(op))?;
+
+        // THEN
+        assert_eq!(ops, vec![set_helper(0, 0), set_helper(1, 0)]);
+        let raw = std::fs::read(dir.path().join("segment_0.oplog"))?;
+        assert!(!raw.windows(3).any(|window| window == b"foo"));
+        // * Without the key, the log is refused rather than truncated
+        assert!(FileOpLogs::new(dir.path()).is_err());
+        assert_eq!(std::fs::read(dir.path().join("segment_0.oplog"))?, raw);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_partial_data() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let path = dir.path();
         {
-            let mut op_logs = FileOpLogs::new(&path)?;
+            let mut op_logs = FileOpLogs::new(path)?;
             op_logs.append(set_helper(0, 0))?;
             op_logs.append(set_helper(1, 0))?;
+            op_logs.rotate_segment()?;
             op_logs.append(set_helper(2, 1))?;
         }
 
-        // Corrupt file content by truncating to the first half.
-        // We should only have one complete op.
-        {
-            let mut file = OpenOptions::new().read(true).open(&path)?;
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)?;
-
-            data.truncate(data.len() / 2);
-
-            let mut file = OpenOptions::new().write(true).truncate(true).open(&path)?;
-            file.write_all(&data)?;
-        }
+        // Tear the second entry of the first segment in half
+        let segment_path = path.join("segment_0.oplog");
+        let len = std::fs::metadata(&segment_path)?.len();
+        let torn_len = len
+            - frame::encode(set_helper(1, 0), Compression::None, &KeyRing::default()).len() as u64
+                / 2;
+        OpenOptions::new().write(true).open(&segment_path)?.set_len(torn_len)?;
 
-        let mut op_logs = FileOpLogs::new(&path)?;
+        // WHEN
+        let mut op_logs = FileOpLogs::new(path)?;
         let mut ops = Vec::new();
+        op_logs.replay(|op| ops.push(op))?;
 
-        assert!(
-            op_logs
-                .replay(|op| {
-                    ops.push(op);
-                })
-                .is_err()
+        // THEN - the log ends at the last valid entry and the segment after it is dropped
+        assert_eq!(ops, vec![set_helper(0, 0)]);
+        assert!(!path.join("segment_1.oplog").exists());
+        assert_eq!(
+            std::fs::metadata(&segment_path)?.len(),
+            frame::encode(set_helper(0, 0), Compression::None, &KeyRing::default()).len() as u64
         );
 
-        // THEN
-        assert_eq!(ops.len(), 1);
-        assert_eq!(ops[0], set_helper(0, 0));
+        // WHEN - appending after recovery
+        op_logs.append(set_helper(1, 1))?;
 
+        // THEN
+        assert_eq!(op_logs.range(0, 2), vec![set_helper(1, 1)]);
         Ok(())
     }
 
@@ -706,8 +977,7 @@ mod tests {
         let mut reader = BufReader::new(file);
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let bytes = Bytes::copy_from_slice(&buf[..]);
-        let operations = WriteRequest::deserialize(bytes)?;
+        let operations = frame::decode(&buf, &KeyRing::default()).unwrap().into_operations();
         assert_eq!(operations.len(), 2);
 
         Ok(())
@@ -726,13 +996,14 @@ mod tests {
         for i in 0..100 {
             op_logs.append(WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{i}"),
-                    value: format!("value_{i}"),
+                    key: format!("key_{i}").into(),
+                    value: format!("value_{i}").into(),
                     expires_at: None,
                 },
                 log_index: i as u64,
                 term: 1,
                 session_req: None,
+                timestamp: 0,
             })?;
         }
         // Force rotation
@@ -747,6 +1018,7 @@ mod tests {
             log_index: 100,
             term: 1,
             session_req: None,
+            timestamp: 0,
         })?;
 
         // WHEN
@@ -773,7 +1045,7 @@ mod tests {
     }
 
     #[test]
-    fn test_new_handles_corrupted_segment() -> Result<()> {
+    fn test_new_recovers_corrupted_segment() -> Result<()> {
         // GIVEN
         let dir = TempDir::new()?;
         let path = dir.path();
@@ -787,8 +1059,12 @@ mod tests {
         let mut file = OpenOptions::new().write(true).open(&segment_path)?;
         file.write_all(b"corrupted data")?;
 
-        // WHEN/THEN
-        assert!(FileOpLogs::new(path).is_err());
+        // WHEN
+        let op_logs = FileOpLogs::new(path)?;
+
+        // THEN - nothing valid is left, so the segment is emptied instead of replayed
+        assert!(op_logs.is_empty());
+        assert_eq!(std::fs::metadata(&segment_path)?.len(), 0);
 
         Ok(())
     }
@@ -805,13 +1081,14 @@ mod tests {
             // Append 100 ops to segment_0.oplog
             op_logs.append(WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{i}"),
-                    value: format!("value_{i}"),
+                    key: format!("key_{i}").into(),
+                    value: format!("value_{i}").into(),
                     expires_at: None,
                 },
                 log_index: i as u64,
                 term: 1,
                 session_req: None,
+                timestamp: 0,
             })?;
         }
         // Rotate segment_0.oplog into sealed segments, create segment_1.oplog
@@ -828,6 +1105,7 @@ mod tests {
             // If op 99 was the last in segment_0, this should be 100.
             term: 1,
             session_req: None,
+            timestamp: 0,
         })?;
 
         // Store the paths of existing segments before sync
@@ -919,13 +1197,14 @@ mod tests {
         let new_ops: Vec<_> = (0..1000)
             .map(|i| WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{i}"),
-                    value: format!("value_{i}"),
+                    key: format!("key_{i}").into(),
+                    value: format!("value_{i}").into(),
                     expires_at: None,
                 },
                 log_index: i as u64,
                 term: 2,
                 session_req: None,
+                timestamp: 0,
             })
             .collect();
         op_logs.follower_full_sync(new_ops.clone())?;
@@ -959,13 +1238,14 @@ mod tests {
         (0..count)
             .map(|i| WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{}", start_index + i as u64),
-                    value: format!("value_{}", start_index + i as u64),
+                    key: format!("key_{}", start_index + i as u64).into(),
+                    value: format!("value_{}", start_index + i as u64).into(),
                     expires_at: None,
                 },
                 log_index: start_index + i as u64,
                 term,
                 session_req: None,
+                timestamp: 0,
             })
             .collect()
     }
@@ -1077,6 +1357,305 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn test_compact_until_removes_covered_segments() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        op_logs.rotate_segment()?; // segment_0 (1-10) sealed
+        op_logs.append_many(create_ops(11, 10, 1))?;
+        op_logs.rotate_segment()?; // segment_1 (11-20) sealed
+        op_logs.append_many(create_ops(21, 5, 1))?; // segment_2 (21-25) active
+
+        // WHEN
+        op_logs.compact_until(15)?;
+
+        // THEN - only the fully covered segment is removed
+        assert_eq!(op_logs.segments.len(), 1);
+        assert!(!dir.path().join("segment_0.oplog").exists());
+        assert!(op_logs.read_at(5).is_none());
+        assert_eq!(op_logs.read_at(15).unwrap().log_index, 15);
+        assert_eq!(op_logs.log_start_index(), 11);
+
+        // WHEN - rotating after compaction
+        op_logs.rotate_segment()?;
+        op_logs.append_many(create_ops(26, 1, 1))?;
+
+        // THEN - the new segment does not reuse an existing file
+        assert!(dir.path().join("segment_3.oplog").exists());
+        assert_eq!(op_logs.range(20, 26).len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_size_is_configurable() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let one_entry =
+            frame::encode(set_helper(1, 1), Compression::None, &KeyRing::default()).len();
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_segment_size(one_entry * 2);
+
+        // WHEN
+        op_logs.append_many(create_ops(1, 5, 1))?;
+
+        // THEN - a segment is sealed every two entries
+        assert_eq!(op_logs.sealed_segments(), vec![1..=2, 3..=4]);
+        assert_eq!(op_logs.range(0, 5).len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsync_policy_always_syncs_every_append() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+
+        // WHEN
+        op_logs.append_many(create_ops(1, 3, 1))?;
+        op_logs.append(set_helper(4, 1))?;
+
+        // THEN
+        assert_eq!(op_logs.fsync_policy(), FsyncPolicy::Always);
+        assert!(!op_logs.unsynced);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsync_policy_everysec_leaves_sync_to_flusher() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_fsync_policy(FsyncPolicy::EverySec);
+
+        // WHEN
+        op_logs.append_many(create_ops(1, 3, 1))?;
+
+        // THEN
+        assert!(op_logs.unsynced);
+        op_logs.fsync()?;
+        assert!(!op_logs.unsynced);
+        assert_eq!(op_logs.range(0, 3).len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_switching_to_fsync_policy_always_syncs_pending_writes() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_fsync_policy(FsyncPolicy::No);
+        op_logs.append(set_helper(1, 1))?;
+        assert!(op_logs.unsynced);
+
+        // WHEN
+        op_logs.set_fsync_policy(FsyncPolicy::Always)?;
+
+        // THEN
+        assert!(!op_logs.unsynced);
+        assert_eq!(op_logs.fsync_policy(), FsyncPolicy::Always);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_entries_survive_restart() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let large_set = |index| WriteOperation {
+            request: WriteRequest::Set {
+                key: format!("key{index}").into(),
+                value: "v".repeat(1024).into(),
+                expires_at: None,
+            },
+            log_index: index,
+            term: 1,
+            session_req: None,
+            timestamp: 0,
+        };
+        let ops: Vec<_> = (1..=3).map(large_set).collect();
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_compression(Compression::Zstd);
+
+        // WHEN
+        op_logs.append_many(ops.clone())?;
+        // * Compression can be turned off without rewriting what is already stored
+        let mut reopened = FileOpLogs::new(dir.path())?;
+
+        // THEN
+        assert!(op_logs.active_segment.size < 1024);
+        assert_eq!(reopened.read_at(2), Some(ops[1].clone()));
+        let mut replayed = vec![];
+        reopened.replay(|op| replayed.push(op))?;
+        assert_eq!(replayed, ops);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsync_policy_from_str() {
+        assert_eq!("always".parse::<FsyncPolicy>().unwrap(), FsyncPolicy::Always);
+        assert_eq!("EVERYSEC".parse::<FsyncPolicy>().unwrap(), FsyncPolicy::EverySec);
+        assert_eq!("no".parse::<FsyncPolicy>().unwrap(), FsyncPolicy::No);
+        assert!("sometimes".parse::<FsyncPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_truncate_after_cuts_segments() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        op_logs.rotate_segment()?; // segment_0 (1-10) sealed
+        op_logs.append_many(create_ops(11, 10, 1))?;
+        op_logs.rotate_segment()?; // segment_1 (11-20) sealed
+        op_logs.append_many(create_ops(21, 5, 1))?; // segment_2 (21-25) active
+
+        // WHEN
+        op_logs.truncate_after(15);
+
+        // THEN - later segments are deleted and the one holding the index becomes active
+        assert!(!dir.path().join("segment_2.oplog").exists());
+        assert_eq!(op_logs.sealed_segments(), vec![1..=10]);
+        assert_eq!(op_logs.range(0, 25).last().unwrap().log_index, 15);
+        assert!(op_logs.read_at(16).is_none());
+
+        // WHEN - appending after truncation, also across a restart
+        op_logs.append(set_helper(16, 2))?;
+        let op_logs = FileOpLogs::new(dir.path())?;
+
+        // THEN
+        assert_eq!(op_logs.read_at(16), Some(set_helper(16, 2)));
+        assert_eq!(op_logs.range(0, 25).len(), 16);
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_after_everything() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        op_logs.rotate_segment()?;
+        op_logs.append_many(create_ops(11, 5, 1))?;
+
+        // WHEN
+        op_logs.truncate_after(0);
+
+        // THEN
+        assert!(op_logs.is_empty());
+        op_logs.append(set_helper(1, 2))?;
+        assert_eq!(op_logs.range(0, 10), vec![set_helper(1, 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compaction_retains_covered_segments() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        for start in [1, 11, 21] {
+            op_logs.append_many(create_ops(start, 10, 1))?;
+            op_logs.rotate_segment()?;
+        }
+        op_logs.append_many(create_ops(31, 5, 1))?;
+        let mut logger = ReplicatedLogs::new(op_logs, 35, 1);
+        let snapshot = LogSnapshot { last_included_index: 32, last_included_term: 1, data: vec![] };
+
+        // WHEN
+        logger.compact(snapshot, 1)?;
+
+        // THEN - of the three covered segments, the newest one is kept
+        assert_eq!(logger.segment_boundaries(), vec![21..=30]);
+        assert!(logger.read_at(20).is_none());
+        assert_eq!(logger.read_at(21).unwrap().log_index, 21);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_after_snapshot() -> Result<()> {
+        // GIVEN - a snapshot at 5 and entries up to 20 across two segments
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        op_logs.rotate_segment()?;
+        op_logs.append_many(create_ops(11, 10, 2))?;
+        let mut logger = ReplicatedLogs::new(op_logs, 5, 1);
+
+        // WHEN
+        let replayed = logger.entries_after_snapshot();
+
+        // THEN - new entries follow the replayed ones
+        assert_eq!(replayed, [create_ops(6, 5, 1), create_ops(11, 10, 2)].concat());
+        assert_eq!((logger.last_log_index, logger.last_log_term), (20, 2));
+        logger.write_single_entry(&WriteRequest::NoOp, 2, None)?;
+        assert_eq!(logger.read_at(21).unwrap().request, WriteRequest::NoOp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_to_log_index() -> Result<()> {
+        // GIVEN - a snapshot at 5 and entries up to 20 across two segments
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        op_logs.rotate_segment()?;
+        op_logs.append_many(create_ops(11, 10, 2))?;
+        let mut logger = ReplicatedLogs::new(op_logs, 5, 1);
+
+        // WHEN
+        let replayed = logger.recover_to(RecoveryTarget::LogIndex(12))?;
+
+        // THEN
+        assert_eq!(replayed, [create_ops(6, 5, 1), create_ops(11, 2, 2)].concat());
+        assert_eq!((logger.last_log_index, logger.last_log_term), (12, 2));
+        assert!(logger.read_at(13).is_none());
+        logger.write_single_entry(&WriteRequest::NoOp, 2, None)?;
+        assert_eq!(logger.read_at(13).unwrap().request, WriteRequest::NoOp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_to_time() -> Result<()> {
+        // GIVEN - entry i was appended at i seconds
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        let timed = |ops: Vec<WriteOperation>| -> Vec<WriteOperation> {
+            ops.into_iter()
+                .map(|op| WriteOperation { timestamp: op.log_index * 1000, ..op })
+                .collect()
+        };
+        op_logs.append_many(timed(create_ops(1, 20, 1)))?;
+        let mut logger = ReplicatedLogs::new(op_logs, 5, 1);
+
+        // WHEN
+        let replayed = logger.recover_to(RecoveryTarget::Time(12_500))?;
+
+        // THEN
+        assert_eq!(replayed, timed(create_ops(6, 7, 1)));
+        assert_eq!(logger.last_log_index, 12);
+        assert!(logger.read_at(13).is_none());
+
+        // * Times before the snapshot cannot be recovered to
+        let err = logger.recover_to(RecoveryTarget::Time(4_000)).unwrap_err();
+        assert_eq!(err.to_string(), "the snapshot at log index 12 is newer than 4000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_to_refuses_targets_outside_the_log() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(create_ops(1, 10, 1))?;
+        let mut logger = ReplicatedLogs::new(op_logs, 5, 1);
+
+        // WHEN
+        let before = logger.recover_to(RecoveryTarget::LogIndex(4)).unwrap_err();
+        let after = logger.recover_to(RecoveryTarget::LogIndex(11)).unwrap_err();
+
+        // THEN - the log is left as it is
+        assert_eq!(before.to_string(), "log index 4 is before the snapshot at log index 5");
+        assert_eq!(after.to_string(), "log index 11 is past the end of the log at log index 10");
+        assert_eq!(logger.range(0, 10), create_ops(1, 10, 1));
+        Ok(())
+    }
+
     // --- Tests for read_at ---
 
     #[test]
@@ -1200,13 +1779,14 @@ mod tests {
         for i in 0..100 {
             op_logs.append(WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{i}"),
-                    value: format!("value_{i}"),
+                    key: format!("key_{i}").into(),
+                    value: format!("value_{i}").into(),
                     expires_at: None,
                 },
                 log_index: i as u64,
                 term: 1,
                 session_req: None,
+                timestamp: 0,
             })?;
         }
 
@@ -1229,6 +1809,7 @@ mod tests {
             log_index: 100,
             term: 1,
             session_req: None,
+            timestamp: 0,
         })?;
 
         // Verify index data in active segment
@@ -1249,13 +1830,14 @@ mod tests {
             for i in 0..50 {
                 op_logs.append(WriteOperation {
                     request: WriteRequest::Set {
-                        key: format!("key_{i}"),
-                        value: format!("value_{i}"),
+                        key: format!("key_{i}").into(),
+                        value: format!("value_{i}").into(),
                         expires_at: None,
                     },
                     log_index: i as u64,
                     term: 1,
                     session_req: None,
+                    timestamp: 0,
                 })?;
             }
         }
@@ -1289,13 +1871,14 @@ mod tests {
         for i in 0..10 {
             op_logs.append(WriteOperation {
                 request: WriteRequest::Set {
-                    key: format!("key_{i}"),
-                    value: format!("value_{i}"),
+                    key: format!("key_{i}").into(),
+                    value: format!("value_{i}").into(),
                     expires_at: None,
                 },
                 log_index: i as u64,
                 term: 1,
                 session_req: None,
+                timestamp: 0,
             })?;
         }
 
@@ -1321,4 +1904,79 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_verify_consistent_log() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let one_entry =
+            frame::encode(set_helper(1, 1), Compression::None, &KeyRing::default()).len();
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_segment_size(one_entry * 2);
+        for i in 1..=5 {
+            op_logs.append(set_helper(i, i / 2))?;
+        }
+
+        // WHEN
+        let verification = op_logs.verify();
+
+        // THEN
+        assert!(verification.is_consistent(), "{verification}");
+        assert_eq!(verification.segments, 3);
+        assert_eq!(verification.entries, 5);
+        assert_eq!(verification.last, Some((5, 2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_reports_first_corrupted_entry() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let one_entry =
+            frame::encode(set_helper(1, 1), Compression::None, &KeyRing::default()).len();
+        let mut op_logs = FileOpLogs::new(dir.path())?.with_segment_size(one_entry * 2);
+        for i in 1..=5 {
+            op_logs.append(set_helper(i, 1))?;
+        }
+        let segment = dir.path().join("segment_1.oplog");
+        let mut buf = std::fs::read(&segment)?;
+        *buf.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&segment, buf)?;
+
+        // WHEN
+        let verification = op_logs.verify();
+
+        // THEN
+        assert_eq!(
+            verification.inconsistency.as_deref(),
+            Some(
+                format!("segment_1.oplog at byte {one_entry}: entry is torn or fails its checksum")
+                    .as_str()
+            )
+        );
+        assert_eq!(verification.entries, 3);
+        // * The log is left as it is and keeps serving reads
+        assert_eq!(op_logs.read_at(5).map(|op| op.log_index), Some(5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_reports_non_monotonic_entries() -> Result<()> {
+        // GIVEN
+        let dir = TempDir::new()?;
+        let mut op_logs = FileOpLogs::new(dir.path())?;
+        op_logs.append_many(vec![set_helper(1, 2), set_helper(2, 1), set_helper(2, 2)])?;
+
+        // WHEN
+        let verification = op_logs.verify();
+
+        // THEN
+        let inconsistency = verification.inconsistency.unwrap();
+        assert!(inconsistency.starts_with("segment_0.oplog at byte "), "{inconsistency}");
+        assert!(
+            inconsistency.ends_with("term 1 of log index 2 is lower than term 2 of log index 1"),
+            "{inconsistency}"
+        );
+        assert_eq!(verification.entries, 1);
+        Ok(())
+    }
 }
@@ -0,0 +1,360 @@
+//! Framing of the entries written to WAL segments, so that torn writes and bit rot are detected on read.
+use crate::domains::QueryIO;
+use crate::domains::cluster_actors::SessionRequest;
+use crate::domains::compression::{COMPRESSION_MIN_LEN, Compression};
+use crate::domains::deserialize;
+use crate::domains::encryption::{DecryptError, KeyRing};
+use crate::domains::operation_logs::{WriteOperation, WriteRequest};
+use crate::domains::query_io::SERDE_CONFIG;
+use anyhow::Result;
+use bytes::Bytes;
+
+// * Each entry is framed as the marker, the payload length and the CRC32 of the payload, then the payload
+const ENTRY_MARKER: u8 = b'E';
+pub(super) const ENTRY_HEADER_LEN: usize = 9;
+// * Compressed entries have the codec right after the marker, and are checksummed as stored
+const COMPRESSED_ENTRY_MARKER: u8 = b'C';
+const COMPRESSED_ENTRY_HEADER_LEN: usize = 10;
+// * Entries written before checksums were introduced start right away with the operation
+const LEGACY_ENTRY_PREFIX: u8 = b'#';
+// * Encrypted entries are the marker and the sealed length, then one of the frames above, sealed
+const ENCRYPTED_ENTRY_MARKER: u8 = b'X';
+const ENCRYPTED_ENTRY_HEADER_LEN: usize = 5;
+
+// * Layout of operations appended before they carried a timestamp
+type UntimedOperation = (WriteRequest, u64, u64, Option<SessionRequest>);
+
+pub(super) fn encode(op: WriteOperation, compression: Compression, keys: &KeyRing) -> Vec<u8> {
+    let frame = encode_plain(op, compression);
+    if !keys.is_enabled() {
+        return frame;
+    }
+    let sealed = keys.seal(&frame);
+    let mut encrypted = Vec::with_capacity(ENCRYPTED_ENTRY_HEADER_LEN + sealed.len());
+    encrypted.push(ENCRYPTED_ENTRY_MARKER);
+    encrypted.extend_from_slice(&(sealed.len() as u32).to_le_bytes());
+    encrypted.extend_from_slice(&sealed);
+    encrypted
+}
+
+fn encode_plain(op: WriteOperation, compression: Compression) -> Vec<u8> {
+    let payload = op.serialize();
+    if compression.is_enabled() && payload.len() >= COMPRESSION_MIN_LEN {
+        let compressed = compression.compress(&payload);
+        // * Entries that don't shrink are stored as they are
+        if compressed.len() < payload.len() {
+            let mut frame = Vec::with_capacity(COMPRESSED_ENTRY_HEADER_LEN + compressed.len());
+            frame.push(COMPRESSED_ENTRY_MARKER);
+            frame.push(compression.code());
+            frame.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&crc32fast::hash(&compressed).to_le_bytes());
+            frame.extend_from_slice(&compressed);
+            return frame;
+        }
+    }
+
+    let mut frame = Vec::with_capacity(ENTRY_HEADER_LEN + payload.len());
+    frame.push(ENTRY_MARKER);
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+/// Entries decoded from the start of a buffer, up to the first one that is torn or fails its checksum.
+#[derive(Debug, Default)]
+pub(super) struct DecodedEntries {
+    // * Each entry along with its byte offset in the buffer
+    pub(super) entries: Vec<(usize, WriteOperation)>,
+    // * Bytes taken up by the valid entries. Anything past it is corrupted.
+    pub(super) valid_len: usize,
+}
+
+impl DecodedEntries {
+    pub(super) fn is_corrupted(&self, buf_len: usize) -> bool {
+        self.valid_len < buf_len
+    }
+
+    pub(super) fn into_operations(self) -> Vec<WriteOperation> {
+        self.entries.into_iter().map(|(_, op)| op).collect()
+    }
+}
+
+/// Fails on entries sealed with a key missing from `keys`, as they can't be told apart from
+/// corrupted ones otherwise, and would be cut off by recovery.
+pub(super) fn decode(buf: &[u8], keys: &KeyRing) -> Result<DecodedEntries> {
+    let mut decoded = DecodedEntries::default();
+    while decoded.valid_len < buf.len() {
+        let offset = decoded.valid_len;
+        let entry = match buf[offset] {
+            | ENCRYPTED_ENTRY_MARKER => decode_encrypted(&buf[offset..], keys)?,
+            | _ => decode_one(&buf[offset..]),
+        };
+        let Some((op, len)) = entry else {
+            break;
+        };
+        decoded.entries.push((offset, op));
+        decoded.valid_len += len;
+    }
+    Ok(decoded)
+}
+
+fn decode_encrypted(buf: &[u8], keys: &KeyRing) -> Result<Option<(WriteOperation, usize)>> {
+    let Some(header) = buf.get(..ENCRYPTED_ENTRY_HEADER_LEN) else {
+        return Ok(None);
+    };
+    let sealed_len = u32::from_le_bytes(header[1..5].try_into()?) as usize;
+    let Some(sealed) = buf.get(ENCRYPTED_ENTRY_HEADER_LEN..ENCRYPTED_ENTRY_HEADER_LEN + sealed_len)
+    else {
+        return Ok(None);
+    };
+    let frame = match keys.open(sealed) {
+        | Ok(frame) => frame,
+        | Err(DecryptError::Corrupted) => return Ok(None),
+        | Err(err) => return Err(err.into()),
+    };
+    Ok(decode_one(&frame)
+        .filter(|(_, len)| *len == frame.len())
+        .map(|(op, _)| (op, ENCRYPTED_ENTRY_HEADER_LEN + sealed_len)))
+}
+
+fn decode_one(buf: &[u8]) -> Option<(WriteOperation, usize)> {
+    match buf[0] {
+        | ENTRY_MARKER => {
+            let header = buf.get(..ENTRY_HEADER_LEN)?;
+            let payload = checked_payload(buf, ENTRY_HEADER_LEN, &header[1..9])?;
+            let op = decode_operation(payload)?;
+            Some((op, ENTRY_HEADER_LEN + payload.len()))
+        },
+        | COMPRESSED_ENTRY_MARKER => {
+            let header = buf.get(..COMPRESSED_ENTRY_HEADER_LEN)?;
+            let compression = Compression::from_code(header[1])?;
+            let stored = checked_payload(buf, COMPRESSED_ENTRY_HEADER_LEN, &header[2..10])?;
+            let op = decode_operation(&compression.decompress(stored).ok()?)?;
+            Some((op, COMPRESSED_ENTRY_HEADER_LEN + stored.len()))
+        },
+        // * These predate timestamps as well
+        | LEGACY_ENTRY_PREFIX => decode_untimed_operation(buf),
+        | _ => None,
+    }
+}
+
+// * `len_and_checksum` holds the payload length and its CRC32, both u32 LE
+fn checked_payload<'a>(
+    buf: &'a [u8],
+    header_len: usize,
+    len_and_checksum: &[u8],
+) -> Option<&'a [u8]> {
+    let payload_len = u32::from_le_bytes(len_and_checksum[..4].try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(len_and_checksum[4..8].try_into().ok()?);
+    let payload = buf.get(header_len..header_len + payload_len)?;
+    (crc32fast::hash(payload) == checksum).then_some(payload)
+}
+
+fn decode_operation(payload: &[u8]) -> Option<WriteOperation> {
+    if let Ok((QueryIO::WriteOperation(op), consumed)) =
+        deserialize(Bytes::copy_from_slice(payload))
+        && consumed == payload.len()
+    {
+        return Some(op);
+    }
+    let (op, consumed) = decode_untimed_operation(payload)?;
+    (consumed == payload.len()).then_some(op)
+}
+
+fn decode_untimed_operation(buf: &[u8]) -> Option<(WriteOperation, usize)> {
+    let body = buf.strip_prefix(&[LEGACY_ENTRY_PREFIX])?;
+    let ((request, log_index, term, session_req), consumed): (UntimedOperation, usize) =
+        bincode::decode_from_slice(body, SERDE_CONFIG).ok()?;
+    Some((WriteOperation { request, log_index, term, session_req, timestamp: 0 }, consumed + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::operation_logs::WriteRequest;
+
+    fn op(log_index: u64) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None },
+            log_index,
+            term: 1,
+            session_req: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn decode_stops_at_torn_write() {
+        let mut buf =
+            [encode_plain(op(1), Compression::None), encode_plain(op(2), Compression::None)]
+                .concat();
+        let first_len = encode_plain(op(1), Compression::None).len();
+        buf.truncate(buf.len() - 3);
+
+        let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+        assert_eq!(decoded.valid_len, first_len);
+        assert!(decoded.is_corrupted(buf.len()));
+        assert_eq!(decoded.into_operations(), vec![op(1)]);
+    }
+
+    #[test]
+    fn decode_stops_at_checksum_mismatch() {
+        let mut buf = [
+            encode_plain(op(1), Compression::None),
+            encode_plain(op(2), Compression::None),
+            encode_plain(op(3), Compression::None),
+        ]
+        .concat();
+        let first_len = encode_plain(op(1), Compression::None).len();
+        // * Flips a bit in the payload of the second entry
+        buf[first_len + ENTRY_HEADER_LEN + 4] ^= 1;
+
+        let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+        assert_eq!(decoded.valid_len, first_len);
+        assert_eq!(decoded.into_operations(), vec![op(1)]);
+    }
+
+    fn untimed(op: &WriteOperation) -> Vec<u8> {
+        let fields: UntimedOperation =
+            (op.request.clone(), op.log_index, op.term, op.session_req.clone());
+        [vec![LEGACY_ENTRY_PREFIX], bincode::encode_to_vec(fields, SERDE_CONFIG).unwrap()].concat()
+    }
+
+    #[test]
+    fn decode_reads_entries_written_without_checksums() {
+        let buf =
+            [untimed(&op(1)), untimed(&op(2)), encode_plain(op(3), Compression::None)].concat();
+
+        let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+        assert!(!decoded.is_corrupted(buf.len()));
+        assert_eq!(decoded.into_operations(), vec![op(1), op(2), op(3)]);
+    }
+
+    #[test]
+    fn decode_reads_entries_written_without_timestamps() {
+        let payload = untimed(&op(1));
+        let mut buf = vec![ENTRY_MARKER];
+        buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        buf.extend_from_slice(&payload);
+        buf.extend_from_slice(&encode_plain(
+            WriteOperation { timestamp: 1700000000000, ..op(2) },
+            Compression::None,
+        ));
+
+        let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+        assert!(!decoded.is_corrupted(buf.len()));
+        let ops = decoded.into_operations();
+        assert_eq!(ops, vec![op(1), WriteOperation { timestamp: 1700000000000, ..op(2) }]);
+    }
+
+    #[test]
+    fn values_encode_as_they_did_when_they_were_strings() {
+        // * Entries logged before values were binary-safe must still decode
+        let legacy = bincode::encode_to_vec((0u32, "foo", "bar", None::<u64>), SERDE_CONFIG);
+        let request =
+            WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None };
+
+        assert_eq!(bincode::encode_to_vec(request, SERDE_CONFIG).unwrap(), legacy.unwrap());
+    }
+
+    fn large_op(log_index: u64) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Set {
+                key: "foo".into(),
+                value: "bar".repeat(100).into(),
+                expires_at: None,
+            },
+            log_index,
+            term: 1,
+            session_req: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn decode_reads_compressed_entries() {
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let compressed = encode_plain(large_op(1), compression);
+            assert_eq!(compressed[0], COMPRESSED_ENTRY_MARKER);
+            assert!(compressed.len() < encode_plain(large_op(1), Compression::None).len());
+
+            // * Small entries are not worth compressing
+            let buf = [compressed, encode_plain(op(2), compression)].concat();
+            let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+            assert!(!decoded.is_corrupted(buf.len()));
+            assert_eq!(decoded.into_operations(), vec![large_op(1), op(2)]);
+        }
+    }
+
+    #[test]
+    fn decode_stops_at_corrupted_compressed_entry() {
+        let mut buf =
+            [encode_plain(op(1), Compression::None), encode_plain(large_op(2), Compression::Lz4)]
+                .concat();
+        let first_len = encode_plain(op(1), Compression::None).len();
+        buf[first_len + COMPRESSED_ENTRY_HEADER_LEN + 2] ^= 1;
+
+        let decoded = decode(&buf, &KeyRing::default()).unwrap();
+
+        assert_eq!(decoded.valid_len, first_len);
+        assert_eq!(decoded.into_operations(), vec![op(1)]);
+    }
+
+    fn keys(hex_keys: &str) -> KeyRing {
+        hex_keys.parse().unwrap()
+    }
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn decode_reads_encrypted_entries() {
+        let keys = keys(KEY);
+        let encrypted = encode(large_op(1), Compression::Lz4, &keys);
+        assert_eq!(encrypted[0], ENCRYPTED_ENTRY_MARKER);
+        assert!(!encrypted.windows(3).any(|w| w == b"bar"));
+
+        // * Entries written before encryption was turned on are read along
+        let buf = [
+            encode_plain(op(1), Compression::None),
+            encrypted,
+            encode(op(2), Compression::None, &keys),
+        ]
+        .concat();
+        let decoded = decode(&buf, &keys).unwrap();
+
+        assert!(!decoded.is_corrupted(buf.len()));
+        assert_eq!(decoded.into_operations(), vec![op(1), large_op(1), op(2)]);
+    }
+
+    #[test]
+    fn decode_stops_at_tampered_or_torn_encrypted_entry() {
+        let keys = keys(KEY);
+        let first = encode(op(1), Compression::None, &keys);
+        let mut buf = [first.clone(), encode(op(2), Compression::None, &keys)].concat();
+        buf[first.len() + ENCRYPTED_ENTRY_HEADER_LEN + 20] ^= 1;
+
+        let decoded = decode(&buf, &keys).unwrap();
+        assert_eq!(decoded.valid_len, first.len());
+
+        let decoded = decode(&buf[..first.len() + 10], &keys).unwrap();
+        assert_eq!(decoded.valid_len, first.len());
+    }
+
+    #[test]
+    fn decode_fails_without_the_key() {
+        let buf =
+            [encode(op(1), Compression::None, &keys(KEY)), encode_plain(op(2), Compression::None)]
+                .concat();
+
+        let err = decode(&buf, &KeyRing::default()).unwrap_err();
+
+        assert!(err.to_string().contains("not among the configured encryption keys"), "{err}");
+    }
+}
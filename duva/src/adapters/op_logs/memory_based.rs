@@ -1,11 +1,13 @@
 //! A local write-ahead-lof file (WAL) adapter.
 use crate::domains::operation_logs::WriteOperation;
-use crate::domains::operation_logs::interfaces::TWriteAheadLog;
+use crate::domains::operation_logs::interfaces::{FsyncPolicy, TWriteAheadLog, WalVerification};
 use anyhow::Result;
+use std::ops::RangeInclusive;
 
 #[derive(Default, Clone)]
 pub struct MemoryOpLogs {
     pub writer: Vec<WriteOperation>,
+    fsync_policy: FsyncPolicy,
 }
 
 impl TWriteAheadLog for MemoryOpLogs {
@@ -33,6 +35,15 @@ impl TWriteAheadLog for MemoryOpLogs {
         Ok(())
     }
 
+    fn fsync_policy(&self) -> FsyncPolicy {
+        self.fsync_policy
+    }
+
+    fn set_fsync_policy(&mut self, policy: FsyncPolicy) -> Result<()> {
+        self.fsync_policy = policy;
+        Ok(())
+    }
+
     fn follower_full_sync(&mut self, ops: Vec<WriteOperation>) -> Result<()> {
         self.writer = ops;
         Ok(())
@@ -61,4 +72,23 @@ impl TWriteAheadLog for MemoryOpLogs {
     fn truncate_after(&mut self, log_index: u64) {
         self.writer.retain(|op| op.log_index <= log_index);
     }
+
+    fn sealed_segments(&self) -> Vec<RangeInclusive<u64>> {
+        vec![]
+    }
+
+    fn compact_until(&mut self, log_index: u64) -> Result<()> {
+        self.writer.retain(|op| op.log_index > log_index);
+        Ok(())
+    }
+
+    fn verify(&self) -> WalVerification {
+        let mut verification = WalVerification::default();
+        for (position, op) in self.writer.iter().enumerate() {
+            if !verification.check(format_args!("entry {position}"), op) {
+                break;
+            }
+        }
+        verification
+    }
 }
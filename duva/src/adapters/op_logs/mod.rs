@@ -1,2 +1,3 @@
 pub mod disk_based;
+mod frame;
 pub mod memory_based;
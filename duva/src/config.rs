@@ -1,18 +1,58 @@
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 
 use crate::{
     domains::{
-        cluster_actors::replication::ReplicationRole,
-        peers::{identifier::TPeerAddress, peer::PeerState},
+        caches::eviction::EvictionPolicy,
+        cluster_actors::{
+            actor::heartbeat_scheduler::{
+                LEADER_HEARTBEAT_INTERVAL, LEADER_HEARTBEAT_INTERVAL_RANGE, RaftTimings,
+            },
+            consensus::compaction::SavePolicy,
+            replication::ReplicationRole,
+        },
+        compression::Compression,
+        encryption::KeyRing,
+        operation_logs::{interfaces::FsyncPolicy, logger::RecoveryTarget},
+        peers::{
+            banlist::BanList,
+            identifier::{TPeerAddress, join_host_port},
+            peer::PeerState,
+            seeds::ClusterSeeds,
+        },
     },
     env_var,
     prelude::PeerIdentifier,
+    presentation::clients::output::OutputBufferLimit,
 };
-use std::fs::OpenOptions;
 
 pub struct Environment {
     pub seed_server: Option<PeerIdentifier>,
+    // * Seeds a node with no cluster to go back to meets to join one, resolved anew every
+    // * `cluster_seeds_interval` ms until it has joined
+    pub(crate) cluster_seeds: Option<ClusterSeeds>,
+    pub cluster_seeds_interval: u64,
+    // * Address peers and clients reach this node at when it is not the one it binds, as behind
+    // * NAT or in a container; peers connect to `cluster_announce_port` + 10000
+    pub cluster_announce_ip: Option<String>,
+    pub cluster_announce_port: Option<u16>,
+    // * Replica to take the log from instead of the leader
+    pub replicate_from: Option<PeerIdentifier>,
+    // * Redis dump whose keys are imported once the node is up
+    pub import: Option<String>,
+    // * Local directory or s3[+http]://<host:port>/<bucket>[/<prefix>] that snapshots and WAL segments are shipped to
+    pub backup_target: Option<String>,
+    pub backup_interval: u64,
+    pub backup_s3_access_key: Option<String>,
+    pub backup_s3_secret_key: Option<String>,
+    pub backup_s3_region: String,
+    // * Rebuilds the snapshot and WAL from the backup target before the node starts
+    pub restore_from_backup: bool,
+    // * Log index or time the node is recovered to on startup, from its snapshot and WAL
+    pub recover_to: Option<RecoveryTarget>,
     pub stored_peer_states: Vec<PeerState>,
+    // * Bans saved in the topology file, with the duration of those issued from now on
+    pub(crate) banlist: BanList,
+    pub ban_duration: u64,
     pub(crate) role: ReplicationRole,
     pub dir: String,
     pub dbfilename: String,
@@ -20,14 +60,118 @@ pub struct Environment {
     pub host: String,
     pub hf_mills: u64,
     pub ttl_mills: u128,
+    // * Interval of the leader's AppendEntries heartbeats and range election timeouts are drawn
+    // * from, from `--raft_heartbeat_interval`, `--election_timeout_min` and `--election_timeout_max`
+    pub(crate) raft_timings: RaftTimings,
+    // * Bytes of keys and values a node holds before `maxmemory_policy` kicks in. 0 means no limit.
+    pub maxmemory: u64,
+    pub(crate) maxmemory_policy: EvictionPolicy,
+    // * Cache actors the keyspace is split over, and the seed mixed into the key hash that picks one
+    pub cache_shards: usize,
+    pub cache_hash_seed: u64,
+    // * Codec for string values longer than `value_compression_threshold` bytes while held in memory
+    pub value_compression: Compression,
+    pub value_compression_threshold: usize,
     pub append_only: bool,
+    pub append_fsync: FsyncPolicy,
+    pub snapshot_threshold: u64,
+    pub snapshot_catchup_lag: u64,
+    // * `<changes> <seconds>` after which a background snapshot is taken and the log compacted
+    pub(crate) save: Option<SavePolicy>,
+    pub wal_segment_size: usize,
+    pub wal_retained_segments: usize,
+    pub wal_compression: Compression,
+    // * Keys WAL segments, snapshots and the topology file are encrypted with, the active one first
+    pub encryption_keys: KeyRing,
+    pub replication_compression: Compression,
+    pub replica_max_lag: u64,
+    pub min_replicas_to_write: usize,
+    pub min_replicas_max_lag: u64,
+    pub pending_writes_max: usize,
+    pub pending_writes_timeout: u64,
+    // * Milliseconds an appended write waits on a majority before its client is told to retry (0 for no limit)
+    pub consensus_timeout: u64,
+    // * Milliseconds a client session is remembered after its last write, for deduplicating retries (0 for ever)
+    pub client_session_ttl: u64,
+    // * Client writes and other client requests the cluster actor may have queued; past either,
+    // * requests of that class are answered with BUSY
+    pub cluster_queue_writes_max: usize,
+    pub cluster_queue_reads_max: usize,
+    pub group_commit_window: u64,
+    pub group_commit_max_entries: usize,
+    pub append_entries_max_entries: usize,
+    pub append_entries_max_bytes: usize,
+    pub election_priority: u8,
+    pub vnode_num: u16,
+    pub partition_weight: u8,
+    pub phi_threshold: f64,
+    pub migration_max_batches: usize,
+    pub migration_max_bytes_per_sec: u64,
+    pub migration_batch_size: usize,
+    pub migration_batch_timeout: u64,
+    // * File administrative operations and client handshakes are appended to, rotated past
+    // * `audit_log_max_size` bytes with `audit_log_retained` rotated files kept
+    pub audit_log: Option<String>,
+    pub audit_log_max_size: u64,
+    pub audit_log_retained: usize,
+    // * host:port of an OTLP/gRPC collector that the spans of traced writes are exported to
+    pub otlp_endpoint: Option<String>,
+    // * Password clients have to AUTH with before any other command is served
+    pub requirepass: Option<String>,
+    // * Secret peers prove they share through a challenge-response before joining the cluster
+    pub cluster_secret: Option<String>,
+    // * PEM files of the node's certificate, its private key and the cluster CA peer connections
+    // * are secured with; set together or not at all
+    pub peer_tls_cert: Option<String>,
+    pub peer_tls_key: Option<String>,
+    pub peer_tls_ca: Option<String>,
+    // * Client connections served at a time, and new ones accepted from one address per second
+    // * (0 for no limit); connections past either are rejected
+    pub maxclients: u64,
+    pub connection_rate_limit: u32,
+    // * Commands and reply bytes per second each user, or each client address for the default
+    // * user, may use across its connections (0 for no limit); reads over quota are delayed
+    pub user_command_rate: u64,
+    pub user_output_rate: u64,
+    // * Seconds a client connection may stay idle before it is closed (0 for never), and between
+    // * TCP keepalive probes of client connections (0 to turn keepalive off)
+    pub timeout: u64,
+    pub tcp_keepalive: u64,
+    // * Path of a Unix domain socket clients can connect through, alongside the TCP port
+    pub unixsocket: Option<String>,
+    // * Output a client connection may have pending before it is closed, for MONITOR connections
+    // * and for the others; see `OutputBufferLimit`
+    pub client_output_buffer_limit_normal: OutputBufferLimit,
+    pub client_output_buffer_limit_monitor: OutputBufferLimit,
+    // * Seconds SHUTDOWN waits for in-flight writes to commit before going on without them
+    pub shutdown_timeout: u64,
     pub tpp: String,
     pub log_level: tracing::Level,
 }
 
 impl Environment {
     pub fn init() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Reads the configuration out of `--<name> <value>` pairs, the way the command line is read.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        Self::parse_with(args, |name| std::env::var(name).ok()).load_topology()
+    }
+
+    /// Every setting at its default, whatever the command line and environment variables say.
+    /// Nothing is read from the topology file until `load_topology`.
+    pub fn defaults() -> Self {
+        Self::parse_with(std::iter::empty(), |_| None)
+    }
+
+    fn parse_with(
+        args: impl IntoIterator<Item = String>,
+        vars: impl Fn(&str) -> Option<String>,
+    ) -> Self {
         env_var!(
+            args: args,
+            vars: vars,
             defaults: {
                 port: u16 = 6379,
                 host: String = "127.0.0.1".to_string(),
@@ -35,48 +179,253 @@ impl Environment {
                 dbfilename: String = "dump.rdb".to_string(),
                 hf: u64 = 1000,
                 ttl: u128 = 60000,
+                raft_heartbeat_interval: u64 = LEADER_HEARTBEAT_INTERVAL,
+                election_timeout_min: u64 = LEADER_HEARTBEAT_INTERVAL_RANGE.start,
+                election_timeout_max: u64 = LEADER_HEARTBEAT_INTERVAL_RANGE.end,
+                maxmemory: u64 = 0,
+                maxmemory_policy: EvictionPolicy = EvictionPolicy::NoEviction,
+                cache_shards: usize = 10,
+                cache_hash_seed: u64 = 0,
+                value_compression: Compression = Compression::None,
+                value_compression_threshold: usize = 1024,
                 append_only: bool = false,
+                append_fsync: FsyncPolicy = FsyncPolicy::Always,
+                snapshot_threshold: u64 = 10000,
+                snapshot_catchup_lag: u64 = 0,
+                wal_segment_size: usize = 1024 * 1024,
+                wal_retained_segments: usize = 0,
+                wal_compression: Compression = Compression::None,
+                replication_compression: Compression = Compression::None,
+                replica_max_lag: u64 = 100,
+                min_replicas_to_write: usize = 0,
+                min_replicas_max_lag: u64 = 10000,
+                pending_writes_max: usize = 10000,
+                pending_writes_timeout: u64 = 5000,
+                consensus_timeout: u64 = 5000,
+                client_session_ttl: u64 = 3600000,
+                cluster_queue_writes_max: usize = 1024,
+                cluster_queue_reads_max: usize = 1024,
+                group_commit_window: u64 = 0,
+                group_commit_max_entries: usize = 128,
+                append_entries_max_entries: usize = 512,
+                append_entries_max_bytes: usize = 1024 * 1024,
+                election_priority: u8 = 0,
+                vnode_num: u16 = 256,
+                partition_weight: u8 = 1,
+                phi_threshold: f64 = 8.0,
+                ban_duration: u64 = 60,
+                cluster_seeds_interval: u64 = 5000,
+                migration_max_batches: usize = 4,
+                migration_max_bytes_per_sec: u64 = 0,
+                migration_batch_size: usize = 100,
+                migration_batch_timeout: u64 = 10000,
+                backup_interval: u64 = 60000,
+                backup_s3_region: String = "us-east-1".to_string(),
+                restore_from_backup: bool = false,
+                audit_log_max_size: u64 = 64 * 1024 * 1024,
+                audit_log_retained: usize = 5,
+                maxclients: u64 = 10000,
+                connection_rate_limit: u32 = 0,
+                user_command_rate: u64 = 0,
+                user_output_rate: u64 = 0,
+                timeout: u64 = 0,
+                tcp_keepalive: u64 = 300,
+                client_output_buffer_limit_normal: OutputBufferLimit = OutputBufferLimit::default(),
+                client_output_buffer_limit_monitor: OutputBufferLimit = OutputBufferLimit {
+                    hard: 32 * 1024 * 1024,
+                    soft: 8 * 1024 * 1024,
+                    soft_seconds: 60,
+                },
+                shutdown_timeout: u64 = 10,
                 tpp: String = "duva.tp".to_string(),
                 log_level : tracing::Level = tracing::Level::INFO,
             },
             optional: {
-                replicaof
+                replicaof,
+                cluster_seeds,
+                cluster_announce_ip,
+                cluster_announce_port,
+                replicate_from,
+                import,
+                backup_target,
+                backup_s3_access_key,
+                backup_s3_secret_key,
+                recover_to_index,
+                recover_to_time,
+                save,
+                encryption_keys,
+                audit_log,
+                otlp_endpoint,
+                requirepass,
+                cluster_secret,
+                peer_tls_cert,
+                peer_tls_key,
+                peer_tls_ca,
+                unixsocket
             }
         );
 
         let replicaof = replicaof.map(|s| PeerIdentifier(s.bind_addr().unwrap()));
-        let stored_peer_states = PeerState::from_file(&tpp);
-        let role = Self::determine_role(replicaof.as_ref(), &stored_peer_states);
+        let cluster_seeds =
+            cluster_seeds.map(|seeds| seeds.parse().expect("Failed to parse cluster_seeds"));
+        let cluster_announce_ip = cluster_announce_ip.inspect(|ip| {
+            PeerIdentifier::try_new(ip, 0)
+                .expect("cluster_announce_ip must be an IP address or hostname");
+        });
+        let cluster_announce_port = cluster_announce_port
+            .map(|port| port.parse().expect("cluster_announce_port must be a port"));
+        let replicate_from = replicate_from.map(|s| PeerIdentifier(s.bind_addr().unwrap()));
+        let recover_to = Self::recovery_target(recover_to_index, recover_to_time);
+        let save = save.map(|policy| policy.parse().expect("Failed to parse save"));
+        let encryption_keys: KeyRing = encryption_keys
+            .map(|keys| keys.parse().expect("Failed to parse encryption_keys"))
+            .unwrap_or_default();
+        let raft_timings =
+            RaftTimings::new(raft_heartbeat_interval, election_timeout_min..election_timeout_max)
+                .expect("Invalid raft timings");
 
         Self {
-            role,
+            role: ReplicationRole::Leader,
             seed_server: replicaof,
+            cluster_seeds,
+            cluster_seeds_interval,
+            cluster_announce_ip,
+            cluster_announce_port,
+            replicate_from,
+            import,
+            backup_target,
+            backup_interval,
+            backup_s3_access_key,
+            backup_s3_secret_key,
+            backup_s3_region,
+            restore_from_backup,
+            recover_to,
             dir,
             dbfilename,
             port,
             host,
             hf_mills: hf,
             ttl_mills: ttl,
+            raft_timings,
+            banlist: BanList::default(),
+            ban_duration,
+            maxmemory,
+            maxmemory_policy,
+            cache_shards,
+            cache_hash_seed,
+            value_compression,
+            value_compression_threshold,
             append_only,
+            append_fsync,
+            snapshot_threshold,
+            snapshot_catchup_lag,
+            save,
+            wal_segment_size,
+            wal_retained_segments,
+            wal_compression,
+            encryption_keys,
+            replication_compression,
+            replica_max_lag,
+            min_replicas_to_write,
+            min_replicas_max_lag,
+            pending_writes_max,
+            pending_writes_timeout,
+            consensus_timeout,
+            client_session_ttl,
+            cluster_queue_writes_max,
+            cluster_queue_reads_max,
+            group_commit_window,
+            group_commit_max_entries,
+            append_entries_max_entries,
+            append_entries_max_bytes,
+            election_priority,
+            vnode_num,
+            partition_weight,
+            phi_threshold,
+            migration_max_batches,
+            migration_max_bytes_per_sec,
+            migration_batch_size,
+            migration_batch_timeout,
+            audit_log,
+            audit_log_max_size,
+            audit_log_retained,
+            otlp_endpoint,
+            requirepass,
+            cluster_secret,
+            peer_tls_cert,
+            peer_tls_key,
+            peer_tls_ca,
+            maxclients,
+            connection_rate_limit,
+            user_command_rate,
+            user_output_rate,
+            timeout,
+            tcp_keepalive,
+            unixsocket,
+            client_output_buffer_limit_normal,
+            client_output_buffer_limit_monitor,
+            shutdown_timeout,
             tpp,
-            stored_peer_states,
+            stored_peer_states: Vec::new(),
             log_level,
         }
     }
 
+    /// Takes in the peers and bans saved in the topology file, and the role the node restarts in.
+    pub(crate) fn load_topology(mut self) -> Self {
+        self.stored_peer_states = PeerState::from_file(&self.tpp, &self.encryption_keys);
+        self.banlist =
+            BanList::from_file(&self.tpp, &self.encryption_keys).with_duration(self.ban_duration);
+        self.role = Self::determine_role(
+            self.seed_server.as_ref(),
+            &self.stored_peer_states,
+            &self.announce_addr(),
+        );
+        self
+    }
+
+    // * The time is either RFC 3339 or Unix time in milliseconds
+    fn recovery_target(index: Option<String>, time: Option<String>) -> Option<RecoveryTarget> {
+        match (index, time) {
+            | (Some(_), Some(_)) => panic!("recover_to_index and recover_to_time are exclusive"),
+            | (Some(index), None) => Some(RecoveryTarget::LogIndex(
+                index.parse().expect("recover_to_index must be a log index"),
+            )),
+            | (None, Some(time)) => {
+                let millis = time.parse().ok().or_else(|| {
+                    chrono::DateTime::parse_from_rfc3339(&time)
+                        .ok()
+                        .map(|time| time.timestamp_millis() as u64)
+                });
+                Some(RecoveryTarget::Time(
+                    millis.expect("recover_to_time must be RFC 3339 or Unix time in milliseconds"),
+                ))
+            },
+            | (None, None) => None,
+        }
+    }
+
+    // * A node restarted onto its topology file follows its shard if the file names another node of
+    // * it; otherwise no election could ever be won, so it leads
     fn determine_role(
         replicaof: Option<&PeerIdentifier>,
         pre_connected_peers: &[PeerState],
+        bind_addr: &str,
     ) -> ReplicationRole {
-        if replicaof.is_none() && pre_connected_peers.is_empty() {
-            ReplicationRole::Leader
-        } else {
-            ReplicationRole::Follower
+        if replicaof.is_some() {
+            return ReplicationRole::Follower;
+        }
+        let Some(myself) = pre_connected_peers.iter().find(|p| p.is_self(bind_addr)) else {
+            return match pre_connected_peers.is_empty() {
+                | true => ReplicationRole::Leader,
+                | false => ReplicationRole::Follower,
+            };
+        };
+        match pre_connected_peers.iter().any(|p| !p.is_self(bind_addr) && p.replid == myself.replid)
+        {
+            | true => ReplicationRole::Follower,
+            | false => ReplicationRole::Leader,
         }
-    }
-
-    pub async fn open_topology_file(tpp: String) -> std::fs::File {
-        OpenOptions::new().create(true).write(true).truncate(true).open(tpp).unwrap()
     }
 
     pub(crate) fn get_filepath(&self) -> String {
@@ -84,12 +433,41 @@ impl Environment {
     }
 
     pub(crate) fn bind_addr(&self) -> String {
-        format!("{}:{}", self.host, self.port)
+        join_host_port(&self.host, self.port)
+    }
+
+    pub(crate) fn announce_host(&self) -> &str {
+        self.cluster_announce_ip.as_deref().unwrap_or(&self.host)
+    }
+
+    pub(crate) fn announce_port(&self) -> u16 {
+        self.cluster_announce_port.unwrap_or(self.port)
+    }
+
+    // * What this node is known by in the cluster, which is its bind address unless announced otherwise
+    pub(crate) fn announce_addr(&self) -> String {
+        join_host_port(self.announce_host(), self.announce_port())
     }
 
     pub(crate) fn peer_bind_addr(&self) -> String {
-        format!("{}:{}", self.host, self.port + 10000)
+        join_host_port(&self.host, self.port + 10000)
     }
 }
 
-pub static ENV: LazyLock<Environment> = LazyLock::new(Environment::init);
+// * Configuration an embedding application hands over in place of the command line
+static EMBEDDED: Mutex<Option<Environment>> = Mutex::new(None);
+
+pub static ENV: LazyLock<Environment> =
+    LazyLock::new(|| EMBEDDED.lock().unwrap().take().unwrap_or_else(Environment::init));
+
+impl Environment {
+    /// Makes this the configuration of the process, which can only be done before it is first read.
+    pub(crate) fn install(self) -> anyhow::Result<()> {
+        *EMBEDDED.lock().unwrap() = Some(self);
+        LazyLock::force(&ENV);
+        if EMBEDDED.lock().unwrap().take().is_some() {
+            anyhow::bail!("duva is already configured in this process, which runs a single node");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use super::TBackupSink;
+use anyhow::Context;
+use std::path::PathBuf;
+
+/// Keeps backups as files of a local directory, which may be a mounted network share.
+#[derive(Debug)]
+pub struct FsBackupSink {
+    root: PathBuf,
+}
+
+impl FsBackupSink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl TBackupSink for FsBackupSink {
+    async fn put(&self, name: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.root).await.with_context(|| {
+            format!("Failed to create backup directory {}", self.root.display())
+        })?;
+        // * Written aside first, so that a reader never sees a partial object
+        let tmp_path = self.root.join(format!("{name}.tmp"));
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, self.root.join(name)).await?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.root.join(name)).await {
+            | Ok(data) => Ok(Some(data)),
+            | Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            | Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Ships snapshots and WAL segments to a backup target, and rebuilds a node from them.
+pub mod fs;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod shipper;
+#[cfg(feature = "s3")]
+pub(crate) mod sigv4;
+
+use fs::FsBackupSink;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Object storage that backups are written to and restored from.
+#[async_trait::async_trait]
+pub trait TBackupSink: Send + Sync + Debug + 'static {
+    async fn put(&self, name: &str, data: Vec<u8>) -> anyhow::Result<()>;
+
+    /// Returns `None` when there is no object with that name.
+    async fn get(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct S3Credentials {
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    pub region: String,
+}
+
+/// Opens the sink a backup target names: `s3://<host:port>/<bucket>[/<prefix>]` for an S3
+/// compatible store reached over HTTPS, `s3+http://` for one reached over plain HTTP, and a local
+/// directory, optionally as a `file://` URL, otherwise. S3 targets need the `s3` feature.
+pub fn open_backup_sink(
+    target: &str,
+    credentials: S3Credentials,
+) -> anyhow::Result<Arc<dyn TBackupSink>> {
+    let (scheme, location) = match (target.strip_prefix("s3://"), target.strip_prefix("s3+http://"))
+    {
+        | (Some(location), _) => ("https", location),
+        | (_, Some(location)) => ("http", location),
+        | _ => {
+            return Ok(Arc::new(FsBackupSink::new(
+                target.strip_prefix("file://").unwrap_or(target),
+            )));
+        },
+    };
+    open_s3_sink(target, scheme, location, credentials)
+}
+
+#[cfg(feature = "s3")]
+fn open_s3_sink(
+    target: &str,
+    scheme: &str,
+    location: &str,
+    credentials: S3Credentials,
+) -> anyhow::Result<Arc<dyn TBackupSink>> {
+    use anyhow::Context;
+
+    let mut parts = location.splitn(3, '/');
+    let (endpoint, bucket) = match (parts.next(), parts.next()) {
+        | (Some(endpoint), Some(bucket)) if !endpoint.is_empty() && !bucket.is_empty() => {
+            (endpoint, bucket)
+        },
+        | _ => {
+            anyhow::bail!("invalid backup target '{target}', expected s3://<host:port>/<bucket>")
+        },
+    };
+    let endpoint = reqwest::Url::parse(&format!("{scheme}://{endpoint}"))
+        .with_context(|| format!("invalid backup endpoint '{endpoint}'"))?;
+    let access_key = credentials.access_key.context("backup_s3_access_key is not set")?;
+    let secret_key = credentials.secret_key.context("backup_s3_secret_key is not set")?;
+    Ok(Arc::new(s3::S3BackupSink::new(
+        endpoint,
+        bucket,
+        parts.next().unwrap_or(""),
+        access_key,
+        secret_key,
+        credentials.region,
+    )))
+}
+
+#[cfg(not(feature = "s3"))]
+fn open_s3_sink(
+    target: &str,
+    _scheme: &str,
+    _location: &str,
+    _credentials: S3Credentials,
+) -> anyhow::Result<Arc<dyn TBackupSink>> {
+    anyhow::bail!("backup target '{target}' needs duva built with the s3 feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_backup_sink() {
+        let credentials = S3Credentials {
+            access_key: Some("key".into()),
+            secret_key: Some("secret".into()),
+            region: "us-east-1".into(),
+        };
+
+        let sink = open_backup_sink("file:///var/backups", credentials.clone()).unwrap();
+        assert!(format!("{sink:?}").starts_with("FsBackupSink"));
+        #[cfg(not(feature = "s3"))]
+        assert!(open_backup_sink("s3://127.0.0.1:9000/bucket", credentials).is_err());
+        #[cfg(feature = "s3")]
+        {
+            let sink =
+                open_backup_sink("s3://127.0.0.1:9000/bucket/a/b", credentials.clone()).unwrap();
+            let debug = format!("{sink:?}");
+            assert!(debug.contains("bucket: \"bucket\"") && debug.contains("prefix: \"a/b\""));
+            assert!(debug.contains("scheme: \"https\""));
+            let sink =
+                open_backup_sink("s3+http://127.0.0.1:9000/bucket", credentials.clone()).unwrap();
+            assert!(format!("{sink:?}").contains("scheme: \"http\""));
+
+            assert!(open_backup_sink("s3://127.0.0.1:9000", credentials).is_err());
+            assert!(
+                open_backup_sink("s3://127.0.0.1:9000/bucket", S3Credentials::default()).is_err()
+            );
+        }
+    }
+}
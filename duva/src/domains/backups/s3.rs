@@ -0,0 +1,225 @@
+use super::TBackupSink;
+use super::sigv4::{Credentials, uri_encode};
+use crate::domains::crypto::sha256;
+use anyhow::{Context, bail};
+use chrono::Utc;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Method, Response, StatusCode, Url};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keeps backups in a bucket of an S3 compatible object store, addressed path-style
+/// (`<endpoint>/<bucket>/<prefix>/<name>`). The store is reached over HTTPS unless its endpoint
+/// says `http`, as a MinIO next to the node usually is.
+#[derive(Debug)]
+pub struct S3BackupSink {
+    // * Scheme, host and port of the store
+    endpoint: Url,
+    bucket: String,
+    prefix: String,
+    credentials: Credentials,
+    client: reqwest::Client,
+}
+
+impl S3BackupSink {
+    pub fn new(
+        endpoint: Url,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        access_key: impl Into<String>,
+        secret_key: impl Into<String>,
+        region: impl Into<String>,
+    ) -> Self {
+        Self {
+            endpoint,
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+            credentials: Credentials {
+                access_key: access_key.into(),
+                secret_key: secret_key.into(),
+                region: region.into(),
+            },
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn object_path(&self, name: &str) -> String {
+        std::iter::once(self.bucket.as_str())
+            .chain(self.prefix.split('/').filter(|segment| !segment.is_empty()))
+            .chain(std::iter::once(name))
+            .map(|segment| format!("/{}", uri_encode(segment)))
+            .collect()
+    }
+
+    // * The `Host` header the client sends, which the signature covers
+    fn host(&self) -> String {
+        let host = self.endpoint.host_str().unwrap_or_default();
+        match self.endpoint.port() {
+            | Some(port) => format!("{host}:{port}"),
+            | None => host.to_string(),
+        }
+    }
+
+    async fn request(&self, method: Method, name: &str, body: Vec<u8>) -> anyhow::Result<Response> {
+        let path = self.object_path(name);
+        let payload_hash = hex::encode(sha256(&body));
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.credentials.authorization(
+            method.as_str(),
+            &path,
+            &[
+                ("host", &self.host()),
+                ("x-amz-content-sha256", &payload_hash),
+                ("x-amz-date", &amz_date),
+            ],
+            &payload_hash,
+            &amz_date,
+        );
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        self.client
+            .request(method.clone(), url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(AUTHORIZATION, authorization)
+            .body(body)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .with_context(|| format!("{method} {path} to {} failed", self.endpoint))
+    }
+}
+
+#[async_trait::async_trait]
+impl TBackupSink for S3BackupSink {
+    async fn put(&self, name: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let response = self.request(Method::PUT, name, data).await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("PUT {name} failed with {status}: {}", response.text().await.unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let response = self.request(Method::GET, name, Vec::new()).await?;
+        match response.status() {
+            | StatusCode::NOT_FOUND => Ok(None),
+            | status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+            | status => bail!(
+                "GET {name} failed with {status}: {}",
+                response.text().await.unwrap_or_default()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn header_of(head: &str, name: &str) -> Option<String> {
+        head.lines().skip(1).find_map(|l| {
+            let (key, value) = l.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+        })
+    }
+
+    /// Minimal S3 stand-in that checks the signature of every request before serving it.
+    async fn fake_s3(credentials: Credentials) -> (Url, Arc<Mutex<HashMap<String, Vec<u8>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let objects: Arc<Mutex<HashMap<String, Vec<u8>>>> = Default::default();
+
+        let store = objects.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                let (head, body) = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") else {
+                        continue;
+                    };
+                    let head = String::from_utf8(raw[..end].to_vec()).unwrap();
+                    let len: usize =
+                        header_of(&head, "Content-Length").map_or(0, |len| len.parse().unwrap());
+                    if raw.len() >= end + 4 + len {
+                        break (head, raw[end + 4..end + 4 + len].to_vec());
+                    }
+                };
+
+                let header = |name: &str| header_of(&head, name).unwrap();
+                let mut request_line = head.lines().next().unwrap().split(' ');
+                let (method, path) = (request_line.next().unwrap(), request_line.next().unwrap());
+                let (host, hash, date) =
+                    (header("Host"), header("x-amz-content-sha256"), header("x-amz-date"));
+                let expected = credentials.authorization(
+                    method,
+                    path,
+                    &[("host", &host), ("x-amz-content-sha256", &hash), ("x-amz-date", &date)],
+                    &hash,
+                    &date,
+                );
+
+                let response = if header("Authorization") != expected
+                    || hash != hex::encode(sha256(&body))
+                {
+                    "HTTP/1.1 403 Forbidden\r\nConnection: close\r\nContent-Length: 17\r\n\r\nSignatureMismatch".into()
+                } else if method == "PUT" {
+                    store.lock().unwrap().insert(path.to_string(), body);
+                    "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n".to_string()
+                } else {
+                    match store.lock().unwrap().get(path) {
+                        | Some(data) => format!(
+                            "HTTP/1.1 200 OK\r\nConnection: close\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                            data.len(),
+                            String::from_utf8_lossy(data)
+                        ),
+                        | None => "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 0\r\n\r\n".into(),
+                    }
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (endpoint, objects)
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get_objects() {
+        let credentials = Credentials {
+            access_key: "key".into(),
+            secret_key: "secret".into(),
+            region: "eu".into(),
+        };
+        let (endpoint, objects) = fake_s3(credentials).await;
+        let sink = S3BackupSink::new(endpoint, "backups", "nodes/a", "key", "secret", "eu");
+
+        sink.put("dump.rdb", b"snapshot".to_vec()).await.unwrap();
+
+        assert!(objects.lock().unwrap().contains_key("/backups/nodes/a/dump.rdb"));
+        assert_eq!(sink.get("dump.rdb").await.unwrap(), Some(b"snapshot".to_vec()));
+        assert_eq!(sink.get("missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_signature_is_an_error() {
+        let credentials = Credentials {
+            access_key: "key".into(),
+            secret_key: "secret".into(),
+            region: "eu".into(),
+        };
+        let (endpoint, _) = fake_s3(credentials).await;
+        let sink = S3BackupSink::new(endpoint, "backups", "", "key", "wrong", "eu");
+
+        let err = sink.put("dump.rdb", b"snapshot".to_vec()).await.unwrap_err();
+        assert!(err.to_string().contains("403"), "{err}");
+    }
+}
@@ -0,0 +1,166 @@
+use super::TBackupSink;
+use anyhow::Context;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info};
+
+// * Names of the objects making up the latest backup, written after all of them
+const MANIFEST: &str = "MANIFEST";
+
+/// Uploads the snapshot and the WAL segments of a node to its backup target.
+#[derive(Debug)]
+pub struct BackupShipper {
+    sink: Arc<dyn TBackupSink>,
+    dir: PathBuf,
+    dbfilename: String,
+    // * File name to the size and modification time it had when last uploaded
+    shipped: HashMap<String, (u64, SystemTime)>,
+    manifest: Vec<String>,
+}
+
+impl BackupShipper {
+    pub fn new(sink: Arc<dyn TBackupSink>, dir: impl Into<PathBuf>, dbfilename: String) -> Self {
+        Self { sink, dir: dir.into(), dbfilename, shipped: HashMap::new(), manifest: Vec::new() }
+    }
+
+    /// Uploads the files that changed since the last call, then the manifest if the set of files or
+    /// any of them changed. Returns the number of files uploaded.
+    pub async fn ship(&mut self) -> anyhow::Result<usize> {
+        let files = self.local_files()?;
+        let mut uploaded = 0;
+        for name in &files {
+            let path = self.dir.join(name);
+            let metadata = tokio::fs::metadata(&path).await?;
+            let version = (metadata.len(), metadata.modified()?);
+            if self.shipped.get(name) == Some(&version) {
+                continue;
+            }
+            let data = tokio::fs::read(&path).await?;
+            self.sink.put(name, data).await.with_context(|| format!("Failed to back up {name}"))?;
+            self.shipped.insert(name.clone(), version);
+            uploaded += 1;
+        }
+
+        if uploaded > 0 || files != self.manifest {
+            self.sink.put(MANIFEST, files.join("\n").into_bytes()).await?;
+            self.manifest = files;
+        }
+        Ok(uploaded)
+    }
+
+    pub fn schedule(mut self, interval_millis: u64) {
+        tokio::spawn(async move {
+            let mut itv = tokio::time::interval(Duration::from_millis(interval_millis.max(1)));
+            loop {
+                itv.tick().await;
+                if let Err(err) = self.ship().await {
+                    error!("backup failed: {err:#}");
+                }
+            }
+        });
+    }
+
+    // * The snapshot first, then the segments in log order
+    fn local_files(&self) -> anyhow::Result<Vec<String>> {
+        let mut segments = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if let Some(number) = segment_number(&name) {
+                segments.push((number, name));
+            }
+        }
+        segments.sort();
+
+        let snapshot = self.dir.join(&self.dbfilename).exists().then(|| self.dbfilename.clone());
+        Ok(snapshot.into_iter().chain(segments.into_iter().map(|(_, name)| name)).collect())
+    }
+}
+
+/// Replaces the snapshot and the WAL segments in `dir` with the latest backup. Returns the number of
+/// files restored.
+pub async fn restore_from_backup(
+    sink: &dyn TBackupSink,
+    dir: impl Into<PathBuf>,
+) -> anyhow::Result<usize> {
+    let dir = dir.into();
+    let manifest = sink.get(MANIFEST).await?.context("no backup found at the backup target")?;
+    let manifest = String::from_utf8(manifest)?;
+
+    tokio::fs::create_dir_all(&dir).await?;
+    // * Segments left over from before would otherwise be replayed along with the restored ones
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        if segment_number(&entry.file_name().to_string_lossy()).is_some() {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    let names: Vec<&str> = manifest.lines().filter(|name| !name.is_empty()).collect();
+    for name in &names {
+        let data = sink.get(name).await?.with_context(|| format!("backup is missing {name}"))?;
+        tokio::fs::write(dir.join(name), data).await?;
+        info!("Restored {name} from backup");
+    }
+    Ok(names.len())
+}
+
+fn segment_number(name: &str) -> Option<u64> {
+    name.strip_prefix("segment_")?.strip_suffix(".oplog")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::backups::fs::FsBackupSink;
+
+    #[tokio::test]
+    async fn test_ship_uploads_changed_files_only() -> anyhow::Result<()> {
+        let (node, backup) = (tempfile::tempdir()?, tempfile::tempdir()?);
+        std::fs::write(node.path().join("dump.rdb"), b"snapshot")?;
+        std::fs::write(node.path().join("segment_10.oplog"), b"later")?;
+        std::fs::write(node.path().join("segment_2.oplog"), b"earlier")?;
+        std::fs::write(node.path().join("raft.state"), b"not backed up")?;
+        let sink = Arc::new(FsBackupSink::new(backup.path()));
+        let mut shipper = BackupShipper::new(sink.clone(), node.path(), "dump.rdb".into());
+
+        assert_eq!(shipper.ship().await?, 3);
+        assert_eq!(
+            sink.get(MANIFEST).await?,
+            Some(b"dump.rdb\nsegment_2.oplog\nsegment_10.oplog".to_vec())
+        );
+        assert_eq!(sink.get("raft.state").await?, None);
+        assert_eq!(shipper.ship().await?, 0);
+
+        std::fs::write(node.path().join("segment_10.oplog"), b"later, and more")?;
+        assert_eq!(shipper.ship().await?, 1);
+        assert_eq!(sink.get("segment_10.oplog").await?, Some(b"later, and more".to_vec()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_replaces_local_state() -> anyhow::Result<()> {
+        let (node, backup, restored) =
+            (tempfile::tempdir()?, tempfile::tempdir()?, tempfile::tempdir()?);
+        std::fs::write(node.path().join("dump.rdb"), b"snapshot")?;
+        std::fs::write(node.path().join("segment_0.oplog"), b"entries")?;
+        let sink = Arc::new(FsBackupSink::new(backup.path()));
+        BackupShipper::new(sink.clone(), node.path(), "dump.rdb".into()).ship().await?;
+        std::fs::write(restored.path().join("segment_7.oplog"), b"stale")?;
+
+        assert_eq!(restore_from_backup(sink.as_ref(), restored.path()).await?, 2);
+
+        assert_eq!(std::fs::read(restored.path().join("dump.rdb"))?, b"snapshot");
+        assert_eq!(std::fs::read(restored.path().join("segment_0.oplog"))?, b"entries");
+        assert!(!restored.path().join("segment_7.oplog").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_without_backup_fails() {
+        let (backup, restored) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let sink = FsBackupSink::new(backup.path());
+        assert!(restore_from_backup(&sink, restored.path()).await.is_err());
+    }
+}
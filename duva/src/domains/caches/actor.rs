@@ -1,27 +1,50 @@
+use super::big_keys::ScannedKey;
 use super::cache_objects::{CacheEntry, CacheValue};
-use super::command::CacheCommand;
+use super::command::{BatchOp, CacheCommand};
 use crate::domains::caches::cache_objects::TypedValue;
-use crate::domains::caches::lru_cache::LruCache;
+use crate::domains::caches::eviction::{self, EvictionPolicy, EvictionPoolEntry};
+use crate::domains::caches::lru_cache::{Access, Entry, LruCache};
 use crate::domains::caches::read_queue::ReadQueue;
+use crate::domains::caches::value_compression::{CompressionStats, ValueCompression};
 use crate::make_smart_pointer;
 use anyhow::Context;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use tokio::sync::mpsc::{self};
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, watch};
 
 pub struct CacheActor {
-    pub(crate) cache: LruCache<String, CacheValue>,
+    pub(crate) cache: LruCache<Bytes, CacheValue>,
     pub(crate) self_handler: CacheCommandSender,
+    pub(crate) compression: ValueCompression,
+    // * Fencing tokens of the locks taken on this shard, so that expired ones can be released
+    pub(crate) locks: HashMap<Bytes, u64>,
+    pub(crate) staged_batch: Option<StagedBatch>,
+}
+
+/// The shard's share of a batch, waiting for the other shards to stage theirs.
+pub(crate) struct StagedBatch {
+    ops: Vec<BatchOp>,
+    // * Reads of the batch's keys, held until it is settled so that none of them sees the batch
+    // * applied on one shard and not yet on another
+    reads: Vec<CacheCommand>,
 }
 
 impl CacheActor {
-    pub(crate) fn run(hwm: Arc<AtomicU64>) -> CacheCommandSender {
+    pub(crate) fn run(hwm: Arc<AtomicU64>, compression: ValueCompression) -> CacheCommandSender {
         let (tx, cache_actor_inbox) = mpsc::channel(100);
         tokio::spawn(
-            Self { cache: LruCache::new(1000), self_handler: CacheCommandSender(tx.clone()) }
-                .handle(cache_actor_inbox, ReadQueue::new(hwm)),
+            Self {
+                cache: LruCache::new(1000),
+                self_handler: CacheCommandSender(tx.clone()),
+                compression,
+                locks: HashMap::new(),
+                staged_batch: None,
+            }
+            .handle(cache_actor_inbox, ReadQueue::new(hwm)),
         );
         CacheCommandSender(tx)
     }
@@ -33,71 +56,311 @@ impl CacheActor {
         self.cache.keys_with_expiry
     }
 
-    pub(crate) fn keys(&self, pattern: Option<String>, callback: oneshot::Sender<Vec<String>>) {
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.cache.iter().map(|(key, value)| eviction::memory_usage(key, value)).sum()
+    }
+
+    pub(crate) fn compression_stats(&self) -> CompressionStats {
+        self.compression.stats(self.cache.iter().map(|(_, value)| value))
+    }
+
+    /// The shard's entries as they were stored, for snapshots.
+    pub(crate) fn entries(&self) -> Vec<CacheEntry> {
+        self.cache
+            .iter()
+            .map(|(key, value)| {
+                CacheEntry::new_with_cache_value(key.clone(), ValueCompression::decompress(value))
+            })
+            .collect()
+    }
+
+    pub(crate) fn scan_key_sizes(
+        &self,
+        cursor: usize,
+        count: usize,
+    ) -> (Vec<ScannedKey>, Option<usize>) {
+        let (entries, next) = self.cache.scan(cursor, count);
+        let sizes = entries
+            .into_iter()
+            .filter_map(|(key, value)| ScannedKey::new(key, &ValueCompression::decompress(value)))
+            .collect();
+        (sizes, next)
+    }
+
+    /// Keys to evict for the shard to fit in `budget` bytes. They are only nominated here:
+    /// the leader deletes them through the log so that replicas drop the same keys.
+    pub(crate) fn eviction_candidates(&self, budget: usize, policy: EvictionPolicy) -> Vec<Bytes> {
+        let used = self.memory_usage();
+        if used <= budget {
+            return vec![];
+        }
+        policy.pick(self.cache.iter_from_lru().collect(), used - budget)
+    }
+
+    /// The keys the policy evicts first from this shard, at most `size` of them.
+    pub(crate) fn eviction_pool(
+        &self,
+        policy: EvictionPolicy,
+        size: usize,
+    ) -> Vec<EvictionPoolEntry> {
+        policy
+            .rank(self.cache.iter_from_lru().collect())
+            .into_iter()
+            .take(size)
+            .filter_map(|(key, value, hits)| {
+                let access = self.cache.access(key)?;
+                Some(EvictionPoolEntry {
+                    key: key.clone(),
+                    idle: access.last_access.elapsed(),
+                    freq: hits,
+                    expiry: value.expiry,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn access(&self, key: &[u8]) -> Option<Access> {
+        self.cache.access(key)
+    }
+
+    pub(crate) fn keys(&self, pattern: Option<Bytes>, callback: oneshot::Sender<Vec<Bytes>>) {
         let keys = self
             .cache
             .keys()
             .filter_map(move |k| {
-                if pattern.as_ref().is_none_or(|p| k.contains(p)) { Some(k.clone()) } else { None }
+                if pattern.as_ref().is_none_or(|p| memchr::memmem::find(k, p).is_some()) {
+                    Some(k.clone())
+                } else {
+                    None
+                }
             })
             .collect();
         let _ = callback.send(keys);
     }
-    pub(crate) fn delete(&mut self, key: String, callback: oneshot::Sender<bool>) {
+    pub(crate) fn delete(&mut self, key: Bytes, callback: oneshot::Sender<bool>) {
         if let Some(_value) = self.cache.remove(&key) {
             let _ = callback.send(true);
         } else {
             let _ = callback.send(false);
         }
     }
-    pub(crate) fn exists(&mut self, key: String, callback: oneshot::Sender<bool>) {
+    pub(crate) fn unlink(&mut self, key: Bytes, callback: oneshot::Sender<bool>) {
+        let value = self.cache.remove(&key);
+        let _ = callback.send(value.is_some());
+        if let Some(value) = value {
+            Self::lazy_free(value);
+        }
+    }
+
+    /// Detached values that are costly to drop, such as long lists, are dropped on a blocking
+    /// task so that the other keys of the shard are not held up.
+    fn lazy_free(value: CacheValue) {
+        if value.is_large() {
+            tokio::task::spawn_blocking(move || drop(value));
+        }
+    }
+
+    pub(crate) fn exists(&mut self, key: Bytes, callback: oneshot::Sender<bool>) {
         let _ = callback.send(self.cache.get(&key).is_some());
     }
-    pub(crate) fn get(&mut self, key: &str, callback: oneshot::Sender<CacheValue>) {
-        let _ = callback.send(self.cache.get(key).cloned().unwrap_or(Default::default()));
+    pub(crate) fn get(&mut self, key: &[u8], callback: oneshot::Sender<CacheValue>) {
+        let _ = callback
+            .send(self.cache.get(key).map(ValueCompression::decompress).unwrap_or_default());
     }
 
     pub(crate) fn set(&mut self, cache_entry: CacheEntry) {
         let (key, value) = cache_entry.destructure();
-        self.cache.put(key, value);
+        self.cache.put(key, self.compression.compress(value));
     }
 
     pub(crate) async fn try_send_ttl(&self, cache_entry: &CacheEntry) -> anyhow::Result<()> {
         let Some(expire_in) = cache_entry.expire_in()? else { return Ok(()) };
         let handler = self.self_handler.clone();
         tokio::spawn({
-            let key = cache_entry.key().to_string();
+            let key = cache_entry.key.clone();
             async move {
                 tokio::time::sleep(expire_in).await;
                 let (tx, rx) = oneshot::channel();
-                let _ = handler.send(CacheCommand::Delete { key, callback: tx }).await;
+                let _ = handler.send(CacheCommand::Unlink { key, callback: tx }).await;
                 let _ = rx.await;
             }
         });
         Ok(())
     }
 
-    pub(crate) fn append(&mut self, key: String, value: String) -> anyhow::Result<usize> {
+    /// Serves `Get`, `IndexGet` and `Exists`, or holds them back while they read a key of the
+    /// staged batch.
+    pub(crate) fn read(&mut self, command: CacheCommand, rq: &mut ReadQueue) {
+        if let Some(batch) = self.staged_batch.as_mut() {
+            let key = match &command {
+                | CacheCommand::Get { key, .. }
+                | CacheCommand::IndexGet { key, .. }
+                | CacheCommand::Exists { key, .. } => key,
+                | _ => unreachable!("only reads are held back for a staged batch"),
+            };
+            if batch.ops.iter().any(|op| op.key() == key) {
+                batch.reads.push(command);
+                return;
+            }
+        }
+        match command {
+            | CacheCommand::Get { key, callback } => self.get(&key, callback),
+            | CacheCommand::IndexGet { key, read_idx, callback } => {
+                if let Some(callback) = rq.defer_if_stale(read_idx, &key, callback) {
+                    self.get(&key, callback);
+                }
+            },
+            | CacheCommand::Exists { key, callback } => self.exists(key, callback),
+            | _ => unreachable!("only reads are held back for a staged batch"),
+        }
+    }
+
+    /// Stages the shard's share of a batch without holding up the commands that follow it, and
+    /// settles it once `go` says whether the whole batch goes ahead.
+    pub(crate) fn stage_batch(
+        &mut self,
+        ops: Vec<BatchOp>,
+        mut go: watch::Receiver<bool>,
+        callback: oneshot::Sender<bool>,
+    ) {
+        self.staged_batch = Some(StagedBatch { ops, reads: Vec::new() });
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            // * Dropping `go` without a go-ahead discards the batch
+            let applied = go.wait_for(|go| *go).await.is_ok();
+            let _ = handler.send(CacheCommand::SettleBatch { applied, callback }).await;
+        });
+    }
+
+    pub(crate) async fn settle_batch(&mut self, applied: bool, rq: &mut ReadQueue) -> bool {
+        let Some(batch) = self.staged_batch.take() else { return false };
+        if applied {
+            self.apply_batch(batch.ops).await;
+        }
+        for read in batch.reads {
+            self.read(read, rq);
+        }
+        applied
+    }
+
+    async fn apply_batch(&mut self, ops: Vec<BatchOp>) {
+        for op in ops {
+            match op {
+                | BatchOp::Set(cache_entry) => {
+                    let _ = self.try_send_ttl(&cache_entry).await;
+                    self.set(cache_entry);
+                },
+                | BatchOp::Delete(key) => {
+                    self.cache.remove(&key);
+                },
+            }
+        }
+    }
+
+    pub(crate) fn append(&mut self, key: Bytes, value: Bytes) -> anyhow::Result<usize> {
         let val = self.cache.entry(key.clone()).or_insert(CacheValue::new(""));
 
-        let mut current_str = val.try_to_string()?;
-        current_str.push_str(value.as_str());
-        val.value = TypedValue::String(Bytes::from(current_str));
+        let mut current = ValueCompression::decompress(val);
+        let mut appended = BytesMut::from(current.value.as_str()?.as_ref());
+        appended.extend_from_slice(&value);
+        current.value = TypedValue::String(appended.freeze());
 
-        Ok(val.len())
+        let len = current.len();
+        *val = self.compression.compress(current);
+        Ok(len)
     }
 
-    pub(crate) fn numeric_delta(&mut self, key: String, delta: i64) -> anyhow::Result<i64> {
+    pub(crate) fn numeric_delta(&mut self, key: Bytes, delta: i64) -> anyhow::Result<i64> {
         let val = self.cache.entry(key.clone()).or_insert(CacheValue::new("0"));
 
-        let curr = val
+        let mut current = ValueCompression::decompress(val);
+        let curr = current
             .try_to_string()?
             .parse::<i64>()
             .context("ERR value is not an integer or out of range")?;
 
-        val.value = TypedValue::String(Bytes::from((curr + delta).to_string()));
+        current.value = TypedValue::String(Bytes::from((curr + delta).to_string()));
+        *val = current;
         Ok(curr + delta)
     }
+
+    /// Swaps the value only when the current value equals `expected`.
+    /// Missing keys never match, so followers replaying the same log reach the same outcome.
+    pub(crate) fn cas(
+        &mut self,
+        key: Bytes,
+        expected: Bytes,
+        value: Bytes,
+    ) -> anyhow::Result<bool> {
+        let Entry::Occupied(entry) = self.cache.entry(key) else {
+            return Ok(false);
+        };
+        let val = entry.into_mut();
+        let mut current = ValueCompression::decompress(val);
+        if *current.value.as_str()? != expected {
+            return Ok(false);
+        }
+        current.value = TypedValue::String(value);
+        *val = self.compression.compress(current);
+        Ok(true)
+    }
+
+    /// Whether the key holds a value whose expiry, if any, has not passed yet.
+    pub(crate) fn is_live(&mut self, key: &[u8]) -> bool {
+        self.cache.get(key).is_some_and(|v| v.expiry.is_none_or(|exp| exp > Utc::now()))
+    }
+
+    /// A lock is free when the key is absent or its lease had run out by `now`.
+    pub(crate) fn is_lock_free(&mut self, key: &[u8], now: &DateTime<Utc>) -> bool {
+        self.cache.get(key).is_none_or(|v| v.expiry.is_some_and(|exp| exp <= *now))
+    }
+
+    /// Takes the lock held by `cache_entry` when it is free at `proposed_at`.
+    pub(crate) fn lock(&mut self, cache_entry: CacheEntry, proposed_at: &DateTime<Utc>) -> bool {
+        let acquired = self.is_lock_free(cache_entry.key(), proposed_at);
+        // * A lease that had run out by the time it was asked for is granted and released at once.
+        // * Held locks are not unlinked on this node's clock: the leader releases them through the
+        // * log once expired, and a LOCK applied before that finds them expired anyway
+        if acquired && cache_entry.is_valid(proposed_at) {
+            if let Some(token) = cache_entry.as_str().ok().and_then(|token| token.parse().ok()) {
+                self.locks.insert(cache_entry.key.clone(), token);
+            }
+            self.set(cache_entry);
+        }
+        acquired
+    }
+
+    /// Releases the lock only for the holder of the given fencing token.
+    pub(crate) fn unlock(&mut self, key: Bytes, token: u64) -> bool {
+        if !self
+            .cache
+            .get(&key)
+            .is_some_and(|v| ValueCompression::decompress(v) == token.to_string().as_str())
+        {
+            return false;
+        }
+        self.cache.remove(&key);
+        self.locks.remove(&key);
+        true
+    }
+
+    /// Locks whose lease had run out by `now`, with their fencing token. Locks released or
+    /// overwritten since they were taken are forgotten.
+    pub(crate) fn expired_locks(&mut self, now: &DateTime<Utc>) -> Vec<(Bytes, u64)> {
+        let cache = &self.cache;
+        self.locks.retain(|key, token| {
+            cache
+                .peek(&key[..])
+                .is_some_and(|v| ValueCompression::decompress(v) == token.to_string().as_str())
+        });
+        self.locks
+            .iter()
+            .filter(|(key, _)| {
+                cache.peek(&key[..]).and_then(|v| v.expiry).is_some_and(|exp| exp <= *now)
+            })
+            .map(|(key, token)| (key.clone(), *token))
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
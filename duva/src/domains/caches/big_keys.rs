@@ -0,0 +1,134 @@
+use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
+use crate::domains::query_io::SERDE_CONFIG;
+use bincode::enc::EncoderImpl;
+use bincode::enc::write::SizeWriter;
+use bytes::Bytes;
+use std::fmt::Display;
+
+// * Largest keys reported per type
+const TOP_KEYS: usize = 5;
+
+/// A key seen by `DEBUG BIGKEYS` with the size it takes in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScannedKey {
+    pub(crate) key: Bytes,
+    pub(crate) kind: &'static str,
+    pub(crate) size: usize,
+}
+
+impl ScannedKey {
+    /// `None` for values without a type, which are never stored.
+    pub(crate) fn new(key: &[u8], value: &CacheValue) -> Option<Self> {
+        let kind = match value.value {
+            | TypedValue::Null => return None,
+            | TypedValue::String(_) => "string",
+            | TypedValue::List(_) => "list",
+        };
+        let mut encoder = EncoderImpl::new(SizeWriter::default(), SERDE_CONFIG);
+        bincode::Encode::encode(key, &mut encoder).ok()?;
+        bincode::Encode::encode(value, &mut encoder).ok()?;
+        Some(Self {
+            key: Bytes::copy_from_slice(key),
+            kind,
+            size: encoder.into_writer().bytes_written,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct TypeSummary {
+    keys: usize,
+    bytes: usize,
+    // * Largest first
+    biggest: Vec<(Bytes, usize)>,
+}
+
+impl TypeSummary {
+    fn record(&mut self, key: Bytes, size: usize) {
+        self.keys += 1;
+        self.bytes += size;
+        let position = self.biggest.partition_point(|(_, biggest)| *biggest >= size);
+        if position < TOP_KEYS {
+            self.biggest.insert(position, (key, size));
+            self.biggest.truncate(TOP_KEYS);
+        }
+    }
+}
+
+/// Largest keys per type found by scanning the shards, with the count and total size of each type.
+#[derive(Debug, Default)]
+pub(crate) struct BigKeys {
+    scanned: usize,
+    strings: TypeSummary,
+    lists: TypeSummary,
+}
+
+impl BigKeys {
+    pub(crate) fn record(&mut self, scanned: ScannedKey) {
+        self.scanned += 1;
+        let summary = match scanned.kind {
+            | "list" => &mut self.lists,
+            | _ => &mut self.strings,
+        };
+        summary.record(scanned.key, scanned.size);
+    }
+}
+
+impl Display for BigKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "keys_scanned:{}\r\nstring_keys:{}\r\nstring_bytes:{}\r\nlist_keys:{}\r\nlist_bytes:{}",
+            self.scanned, self.strings.keys, self.strings.bytes, self.lists.keys, self.lists.bytes
+        )?;
+        for (kind, summary) in [("string", &self.strings), ("list", &self.lists)] {
+            for (key, size) in summary.biggest.iter() {
+                write!(f, "\r\nbiggest_{kind}:{} ({size} bytes)", String::from_utf8_lossy(key))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanned_key_size_matches_snapshot_encoding() {
+        let value = CacheValue::new("value");
+        let scanned = ScannedKey::new(b"key", &value).unwrap();
+
+        let mut expected = bincode::encode_to_vec("key", SERDE_CONFIG).unwrap();
+        expected.extend(bincode::encode_to_vec(&value, SERDE_CONFIG).unwrap());
+        assert_eq!(scanned, ScannedKey { key: "key".into(), kind: "string", size: expected.len() });
+        assert_eq!(ScannedKey::new(b"null", &CacheValue::default()), None);
+    }
+
+    #[test]
+    fn test_big_keys_keeps_the_largest_per_type() {
+        let mut big_keys = BigKeys::default();
+        for size in 1..=7 {
+            big_keys.record(ScannedKey { key: format!("s{size}").into(), kind: "string", size });
+        }
+        big_keys.record(ScannedKey { key: "l".into(), kind: "list", size: 3 });
+
+        assert_eq!(
+            big_keys.to_string(),
+            [
+                "keys_scanned:8",
+                "string_keys:7",
+                "string_bytes:28",
+                "list_keys:1",
+                "list_bytes:3",
+                "biggest_string:s7 (7 bytes)",
+                "biggest_string:s6 (6 bytes)",
+                "biggest_string:s5 (5 bytes)",
+                "biggest_string:s4 (4 bytes)",
+                "biggest_string:s3 (3 bytes)",
+                "biggest_list:l (3 bytes)",
+            ]
+            .join("\r\n")
+        );
+    }
+}
@@ -1,15 +1,26 @@
 use super::cache_objects::CacheValue;
 use crate::domains::caches::actor::CacheActor;
 use crate::domains::caches::actor::CacheCommandSender;
+use crate::domains::caches::big_keys::BigKeys;
 use crate::domains::caches::cache_objects::CacheEntry;
-use crate::domains::caches::command::CacheCommand;
-use crate::domains::cluster_actors::replication::ReplicationId;
-use crate::domains::operation_logs::WriteRequest;
+use crate::domains::caches::command::{BatchOp, CacheCommand};
+use crate::domains::caches::eviction::{EVICTION_POOL_SIZE, EvictionPolicy, EvictionPool};
+use crate::domains::caches::lru_cache::Access;
+use crate::domains::caches::value_compression::{CompressionStats, ValueCompression};
+use crate::domains::encryption::KeyRing;
+use crate::domains::leases::actor::{LeaseActor, LeaseCommandSender, LeaseStates};
+use crate::domains::leases::command::LeaseCommand;
+use crate::domains::leases::lease_id;
+use crate::domains::operation_logs::{WriteOperation, WriteRequest};
 use crate::domains::saves::actor::SaveActor;
 use crate::domains::saves::actor::SaveTarget;
+use crate::domains::saves::command::SaveCommand;
 use crate::domains::saves::endec::StoredDuration;
+use crate::domains::saves::snapshot::Metadata;
+use crate::domains::saves::status::SaveStatus;
 use anyhow::Result;
-use chrono::Utc;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use futures::future::join_all;
 use futures::stream::FuturesUnordered;
@@ -17,11 +28,17 @@ use std::fmt::Display;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use std::{hash::Hasher, iter::Zip};
+use tokio::io::AsyncWriteExt;
 use tokio::sync::oneshot::Sender;
 use tokio::sync::oneshot::error::RecvError;
 use tokio::task::JoinHandle;
 use tracing::debug;
 
+// * Slots a shard scans per `DEBUG BIGKEYS` request, and the pause before its next one, so that
+// * other commands to the shard are served in between
+const BIG_KEYS_SCAN_BATCH: usize = 100;
+const BIG_KEYS_SCAN_PAUSE: std::time::Duration = std::time::Duration::from_millis(1);
+
 type OneShotSender<T> = tokio::sync::oneshot::Sender<T>;
 type OneShotReceiverJoinHandle<T> =
     tokio::task::JoinHandle<std::result::Result<T, tokio::sync::oneshot::error::RecvError>>;
@@ -29,23 +46,57 @@ type OneShotReceiverJoinHandle<T> =
 #[derive(Clone, Debug)]
 pub(crate) struct CacheManager {
     pub(crate) inboxes: Vec<CacheCommandSender>,
+    pub(crate) leases: LeaseCommandSender,
+    // * Mixed into every key hash, so that keys spread over the shards differently
+    pub(crate) hash_seed: u64,
+    // * Batches are staged one at a time, so that every shard takes them in the same order
+    pub(crate) batches: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl CacheManager {
+    #[cfg(test)]
     pub(crate) fn run_cache_actors(hwm: Arc<AtomicU64>) -> CacheManager {
-        const NUM_OF_PERSISTENCE: usize = 10;
+        Self::run_sharded(hwm, 10, 0, ValueCompression::default())
+    }
+
+    pub(crate) fn run_sharded(
+        hwm: Arc<AtomicU64>,
+        shards: usize,
+        hash_seed: u64,
+        compression: ValueCompression,
+    ) -> CacheManager {
         CacheManager {
-            inboxes: (0..NUM_OF_PERSISTENCE)
-                .map(|_| CacheActor::run(hwm.clone()))
+            inboxes: (0..shards.max(1))
+                .map(|_| CacheActor::run(hwm.clone(), compression.clone()))
                 .collect::<Vec<_>>(),
+            leases: LeaseActor::run(),
+            hash_seed,
+            batches: Default::default(),
         }
     }
 
-    pub(crate) async fn route_get(&self, key: impl AsRef<str>) -> Result<CacheValue> {
+    /// Commands waiting in each shard's inbox, in shard order.
+    pub(crate) fn queue_depths(&self) -> Vec<usize> {
+        self.inboxes.iter().map(|inbox| inbox.max_capacity() - inbox.capacity()).collect()
+    }
+
+    pub(crate) fn shard_stats(&self) -> Vec<String> {
+        let depths = self.queue_depths();
+        vec![
+            format!("cache_shards:{}", depths.len()),
+            format!("cache_hash_seed:{}", self.hash_seed),
+            format!(
+                "cache_shard_queue_depths:{}",
+                depths.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+            ),
+        ]
+    }
+
+    pub(crate) async fn route_get(&self, key: impl AsRef<[u8]>) -> Result<CacheValue> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         let key_ref = key.as_ref();
         self.select_shard(key_ref)
-            .send(CacheCommand::Get { key: key_ref.into(), callback: tx })
+            .send(CacheCommand::Get { key: Bytes::copy_from_slice(key_ref), callback: tx })
             .await?;
 
         Ok(rx.await?)
@@ -72,12 +123,67 @@ impl CacheManager {
     pub(crate) async fn route_save(
         &self,
         save_target: SaveTarget,
-        repl_id: ReplicationId,
-        current_offset: u64,
+        mut metadata: Metadata,
     ) -> Result<JoinHandle<Result<SaveActor>>> {
-        let (outbox, inbox) = tokio::sync::mpsc::channel(100);
+        metadata.leases = self.route_lease_states().await?;
+        let save_actor = SaveActor::new(save_target, self.inboxes.len(), metadata).await?;
+        Ok(self.run_save(save_actor))
+    }
+
+    /// Writes a snapshot of every shard to `path`. The snapshot goes to a temporary file first and
+    /// replaces `path` only once complete, so a failed save leaves the previous snapshot intact.
+    /// With encryption, the snapshot is taken in memory and sealed as a whole.
+    pub(crate) async fn save_to_file(
+        &self,
+        path: &str,
+        mut metadata: Metadata,
+        status: SaveStatus,
+        encryption: &KeyRing,
+    ) -> Result<()> {
+        metadata.leases = self.route_lease_states().await?;
+        let tmp_path = format!("{path}.tmp");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).truncate(true).create(true);
+        let target = match encryption.is_enabled() {
+            | true => SaveTarget::InMemory(Vec::new()),
+            | false => SaveTarget::File(options.open(&tmp_path).await?),
+        };
         let save_actor =
-            SaveActor::new(save_target, self.inboxes.len(), repl_id, current_offset).await?;
+            SaveActor::new(target, self.inboxes.len(), metadata).await?.with_status(status);
+
+        let mut file = match self.run_save(save_actor).await??.target {
+            | SaveTarget::File(file) => file,
+            | SaveTarget::InMemory(snapshot) => {
+                let mut file = options.open(&tmp_path).await?;
+                file.write_all(&encryption.seal_file(snapshot)).await?;
+                file
+            },
+        };
+        file.flush().await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+
+    /// Collects the entries of every shard, each copied as of the moment its actor handles the request.
+    pub(crate) async fn route_dump_entries(&self) -> Vec<CacheEntry> {
+        let (outbox, mut inbox) = tokio::sync::mpsc::channel(100);
+        for cache_handler in self.inboxes.iter() {
+            let _ = cache_handler.send(CacheCommand::Save { outbox: outbox.clone() }).await;
+        }
+        drop(outbox);
+
+        let mut entries = Vec::new();
+        while let Some(command) = inbox.recv().await {
+            if let SaveCommand::SaveChunk(chunk) = command {
+                entries.extend(chunk);
+            }
+        }
+        entries
+    }
+
+    fn run_save(&self, save_actor: SaveActor) -> JoinHandle<Result<SaveActor>> {
+        let (outbox, inbox) = tokio::sync::mpsc::channel(100);
 
         // get all the handlers to cache actors
         for cache_handler in self.inboxes.iter().map(Clone::clone) {
@@ -88,22 +194,73 @@ impl CacheManager {
         }
 
         //* defaults to BGSAVE but optionally waitable
-        Ok(tokio::spawn(save_actor.run(inbox)))
+        tokio::spawn(save_actor.run(inbox))
     }
 
     pub(crate) async fn apply_log(&self, msg: WriteRequest, log_index: u64) -> Result<()> {
+        self.apply_request(msg, log_index).await?;
+
+        // * This is to wake up the cache actors to process the pending read requests
+        self.pings().await;
+
+        Ok(())
+    }
+
+    /// Applies logs in log order. Logs whose keys all live on one shard are applied alongside the
+    /// logs of other shards, while any other log waits for every log before it, so that each key
+    /// still sees its writes in log order.
+    pub(crate) async fn apply_logs(&self, ops: Vec<WriteOperation>) {
+        let mut lanes = vec![Vec::new(); self.inboxes.len()];
+        for op in ops {
+            match self.single_shard_of(&op.request) {
+                | Some(shard) => lanes[shard].push(op),
+                | None => {
+                    self.apply_lanes(&mut lanes).await;
+                    self.apply_logged(op).await;
+                },
+            }
+        }
+        self.apply_lanes(&mut lanes).await;
+        self.pings().await;
+    }
+
+    fn single_shard_of(&self, request: &WriteRequest) -> Option<usize> {
+        // * Leases are kept apart from the shards
+        if matches!(request, WriteRequest::LeaseAttach { .. }) {
+            return None;
+        }
+        let mut shards = request.all_keys().into_iter().map(|key| self.take_shard_key(key));
+        let first = shards.next()?;
+        shards.all(|shard| shard == first).then_some(first)
+    }
+
+    async fn apply_lanes(&self, lanes: &mut [Vec<WriteOperation>]) {
+        join_all(lanes.iter_mut().map(|lane| async move {
+            for op in std::mem::take(lane) {
+                self.apply_logged(op).await;
+            }
+        }))
+        .await;
+    }
+
+    async fn apply_logged(&self, op: WriteOperation) {
+        // * Writes rejected when they were first applied, such as INCR on a string, fail again
+        if let Err(err) = self.apply_request(op.request, op.log_index).await {
+            debug!("log {} was not applied: {err}", op.log_index);
+        }
+    }
+
+    async fn apply_request(&self, msg: WriteRequest, log_index: u64) -> Result<()> {
         match msg {
             | WriteRequest::Set { key, value, expires_at } => {
-                let mut cache_entry = CacheEntry::new(key, value.as_str());
-                if let Some(expires_at) = expires_at {
-                    cache_entry = cache_entry
-                        .with_expiry(StoredDuration::Milliseconds(expires_at).to_datetime());
-                }
-                self.route_set(cache_entry, log_index).await?;
+                self.route_set(Self::entry_to_set(key, value, expires_at), log_index).await?;
             },
             | WriteRequest::Delete { keys } => {
                 self.route_delete(keys).await?;
             },
+            | WriteRequest::Unlink { keys } => {
+                self.route_unlink(keys).await?;
+            },
             | WriteRequest::Append { key, value } => {
                 self.route_append(key, value).await?;
             },
@@ -116,18 +273,126 @@ impl CacheManager {
             | WriteRequest::MSet { entries } => {
                 self.route_mset(entries).await;
             },
+            | WriteRequest::Cas { key, expected, value } => {
+                self.route_cas(key, expected, value, log_index).await?;
+            },
+            | WriteRequest::Lock { key, expires_at, proposed_at } => {
+                let expiry = StoredDuration::Milliseconds(expires_at).to_datetime();
+                let proposed_at = StoredDuration::Milliseconds(proposed_at).to_datetime();
+                self.route_lock(key, expiry, proposed_at, log_index).await?;
+            },
+            | WriteRequest::Unlock { key, token } => {
+                self.route_unlock(key, token, log_index).await?;
+            },
+            | WriteRequest::Restore { entry, replace } => {
+                self.route_restore(entry, replace).await?;
+            },
+            | WriteRequest::LeaseGrant { ttl_millis, partition } => {
+                self.route_lease_grant(ttl_millis, partition, log_index).await?;
+            },
+            | WriteRequest::LeaseKeepAlive { id } => {
+                self.route_lease_keep_alive(id, log_index).await?;
+            },
+            | WriteRequest::LeaseAttach { id, keys } => {
+                self.route_lease_attach(id, keys, log_index).await?;
+            },
+            | WriteRequest::LeaseRevoke { id } => {
+                self.route_lease_revoke(id, log_index).await?;
+            },
+            | WriteRequest::Batch { requests } | WriteRequest::TxnCommit { requests, .. } => {
+                self.route_batch(requests, log_index).await?;
+            },
+            | WriteRequest::NoOp
+            | WriteRequest::MigrationStart { .. }
+            | WriteRequest::MigrationEnd
+            | WriteRequest::TxnPrepare { .. }
+            | WriteRequest::TxnAbort { .. }
+            | WriteRequest::TxnDecision { .. }
+            | WriteRequest::TxnEnd { .. } => {},
         };
+        Ok(())
+    }
+    fn entry_to_set(key: Bytes, value: Bytes, expires_at: Option<u64>) -> CacheEntry {
+        let cache_entry = CacheEntry::new(key, value);
+        match expires_at {
+            | Some(expires_at) => {
+                cache_entry.with_expiry(StoredDuration::Milliseconds(expires_at).to_datetime())
+            },
+            | None => cache_entry,
+        }
+    }
 
-        // * This is to wake up the cache actors to process the pending read requests
-        self.pings().await;
+    /// Applies every operation of a batch under the same log index.
+    /// Each shard is handed its share of the batch first and applies it only once every share is
+    /// staged, so that a committed batch is never left half-applied. Shards keep serving other
+    /// commands meanwhile, holding back only reads of the batch's keys until it is applied.
+    pub(crate) async fn route_batch(
+        &self,
+        requests: Vec<WriteRequest>,
+        current_idx: u64,
+    ) -> Result<String> {
+        let applied = requests.len();
+        let mut shares: Vec<Vec<BatchOp>> = (0..self.inboxes.len()).map(|_| Vec::new()).collect();
+        for request in requests {
+            match request {
+                | WriteRequest::Set { key, value, expires_at } => {
+                    let entry = Self::entry_to_set(key, value, expires_at);
+                    shares[self.take_shard_key(entry.key())].push(BatchOp::Set(entry));
+                },
+                | WriteRequest::Delete { keys } => {
+                    for key in keys {
+                        shares[self.take_shard_key(&key)].push(BatchOp::Delete(key));
+                    }
+                },
+                | invalid => {
+                    return Err(anyhow::anyhow!("ERR {invalid:?} is not allowed in a batch"));
+                },
+            }
+        }
 
-        Ok(())
+        let _staging = self.batches.lock().await;
+        let (go, go_rx) = tokio::sync::watch::channel(false);
+        let (mut staged, mut settled) = (Vec::new(), Vec::new());
+        let mut staged_everywhere = true;
+        for (shard, ops) in self.inboxes.iter().zip(shares).filter(|(_, ops)| !ops.is_empty()) {
+            let (staged_tx, staged_rx) = tokio::sync::oneshot::channel();
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let share = CacheCommand::StageBatch {
+                ops,
+                go: go_rx.clone(),
+                staged: staged_tx,
+                callback: tx,
+            };
+            if shard.send(share).await.is_err() {
+                staged_everywhere = false;
+                break;
+            }
+            staged.push(staged_rx);
+            settled.push(rx);
+        }
+        // * Every shard holds back reads of the batch's keys before any of them applies it
+        staged_everywhere &= join_all(staged).await.iter().all(Result::is_ok);
+        if staged_everywhere {
+            go.send_replace(true);
+        } else {
+            // * Dropping `go` has the shards staged so far discard their share
+            drop(go);
+        }
+        // * Shards are waited on even for a discarded batch, so that the next one isn't staged
+        // * before they are done with this one
+        let applied_everywhere =
+            join_all(settled).await.into_iter().all(|applied| applied == Ok(true));
+        if !(staged_everywhere && applied_everywhere) {
+            return Err(anyhow::anyhow!("batch was discarded by a shard"));
+        }
+        Ok(IndexedValueCodec::encode(applied, current_idx))
     }
+
     async fn pings(&self) {
         join_all(self.inboxes.iter().map(|shard| shard.send(CacheCommand::Ping))).await;
     }
 
-    pub(crate) async fn route_keys(&self, pattern: Option<String>) -> Vec<String> {
+    pub(crate) async fn route_keys(&self, pattern: Option<Bytes>) -> Vec<Bytes> {
         let (senders, receivers) = self.oneshot_channels();
 
         // send keys to shards
@@ -181,13 +446,13 @@ impl CacheManager {
     // stateless function to send keys
     async fn send_keys_to_shard(
         shard: CacheCommandSender,
-        pattern: Option<String>,
-        tx: OneShotSender<Vec<String>>,
+        pattern: Option<Bytes>,
+        tx: OneShotSender<Vec<Bytes>>,
     ) -> Result<()> {
-        Ok(shard.send(CacheCommand::Keys { pattern: pattern.clone(), callback: tx }).await?)
+        Ok(shard.send(CacheCommand::Keys { pattern, callback: tx }).await?)
     }
 
-    pub(crate) async fn route_delete(&self, keys: Vec<String>) -> Result<u64> {
+    pub(crate) async fn route_delete(&self, keys: Vec<Bytes>) -> Result<u64> {
         let closure = |key, callback| -> CacheCommand { CacheCommand::Delete { key, callback } };
         // Create futures for all delete operations at once
         let results = self.send_selectively(keys, closure).await;
@@ -195,7 +460,15 @@ impl CacheManager {
         let deleted = results.into_iter().filter_map(|r| r.ok().filter(|&success| success)).count();
         Ok(deleted as u64)
     }
-    pub(crate) async fn route_exists(&self, keys: Vec<String>) -> Result<u64> {
+    pub(crate) async fn route_unlink(&self, keys: Vec<Bytes>) -> Result<u64> {
+        let closure = |key, callback| -> CacheCommand { CacheCommand::Unlink { key, callback } };
+        let results = self.send_selectively(keys, closure).await;
+
+        let unlinked =
+            results.into_iter().filter_map(|r| r.ok().filter(|&success| success)).count();
+        Ok(unlinked as u64)
+    }
+    pub(crate) async fn route_exists(&self, keys: Vec<Bytes>) -> Result<u64> {
         let closure = |key, callback| -> CacheCommand { CacheCommand::Exists { key, callback } };
         // Create futures for all delete operations at once
         let results = self.send_selectively(keys, closure).await;
@@ -204,15 +477,15 @@ impl CacheManager {
         Ok(found as u64)
     }
 
-    pub(crate) fn select_shard(&self, key: &str) -> &CacheCommandSender {
-        let shard_key = self.take_shard_key_from_str(key);
+    pub(crate) fn select_shard(&self, key: &[u8]) -> &CacheCommandSender {
+        let shard_key = self.take_shard_key(key);
         &self.inboxes[shard_key]
     }
 
     async fn send_selectively<T>(
         &self,
-        keys: Vec<String>,
-        func: fn(String, Sender<T>) -> CacheCommand,
+        keys: Vec<Bytes>,
+        func: fn(Bytes, Sender<T>) -> CacheCommand,
     ) -> Vec<Result<T, RecvError>> {
         FuturesUnordered::from_iter(keys.into_iter().map(|key| {
             let (tx, rx) = tokio::sync::oneshot::channel();
@@ -225,13 +498,19 @@ impl CacheManager {
         .await
     }
 
-    fn take_shard_key_from_str(&self, s: &str) -> usize {
+    fn take_shard_key(&self, key: &[u8]) -> usize {
         let mut hasher = std::hash::DefaultHasher::new();
-        std::hash::Hash::hash(&s, &mut hasher);
+        // * Left out when unset, so that keys stay on the shards they were always hashed to
+        if self.hash_seed != 0 {
+            hasher.write_u64(self.hash_seed);
+        }
+        // * The bytes `str` feeds the hasher, so that keys keep their shards
+        hasher.write(key);
+        hasher.write_u8(0xff);
         hasher.finish() as usize % self.inboxes.len()
     }
 
-    pub(crate) async fn route_index_get(&self, key: String, index: u64) -> Result<CacheValue> {
+    pub(crate) async fn route_index_get(&self, key: Bytes, index: u64) -> Result<CacheValue> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.select_shard(&key)
             .send(CacheCommand::IndexGet { key, read_idx: index, callback: tx })
@@ -239,7 +518,7 @@ impl CacheManager {
 
         Ok(rx.await?)
     }
-    pub(crate) async fn route_mget(&self, keys: Vec<String>) -> Vec<Option<CacheEntry>> {
+    pub(crate) async fn route_mget(&self, keys: Vec<Bytes>) -> Vec<Option<CacheEntry>> {
         let futures = keys.into_iter().map(|key| {
             let shard = self.select_shard(&key).clone();
             tokio::spawn(async move {
@@ -253,6 +532,111 @@ impl CacheManager {
         join_all(futures).await.into_iter().filter_map(Result::ok).collect()
     }
 
+    /// Bytes of keys and values held across every shard.
+    pub(crate) async fn route_memory_usage(&self) -> usize {
+        join_all(self.inboxes.iter().map(|shard| async move {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            shard.send(CacheCommand::MemoryUsage { callback: tx }).await.ok()?;
+            rx.await.ok()
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .sum()
+    }
+
+    pub(crate) async fn route_compression_stats(&self) -> CompressionStats {
+        join_all(self.inboxes.iter().map(|shard| async move {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            shard.send(CacheCommand::CompressionStats { callback: tx }).await.ok()?;
+            rx.await.ok()
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .reduce(CompressionStats::merge)
+        .unwrap_or_default()
+    }
+
+    /// Scans the shards one after another, a batch of keys at a time.
+    pub(crate) async fn route_big_keys(&self) -> Result<BigKeys> {
+        let mut big_keys = BigKeys::default();
+        for shard in self.inboxes.iter() {
+            let mut cursor = Some(0);
+            while let Some(from) = cursor {
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                shard
+                    .send(CacheCommand::ScanKeySizes {
+                        cursor: from,
+                        count: BIG_KEYS_SCAN_BATCH,
+                        callback: tx,
+                    })
+                    .await?;
+                let (scanned, next) = rx.await?;
+                scanned.into_iter().for_each(|key| big_keys.record(key));
+                cursor = next;
+                tokio::time::sleep(BIG_KEYS_SCAN_PAUSE).await;
+            }
+        }
+        Ok(big_keys)
+    }
+
+    /// Keys to evict for the keyspace to fit in `limit` bytes, which shards share evenly as keys
+    /// are spread evenly over them.
+    pub(crate) async fn route_eviction_candidates(
+        &self,
+        limit: u64,
+        policy: EvictionPolicy,
+    ) -> Vec<Bytes> {
+        let budget = limit as usize / self.inboxes.len();
+        join_all(self.inboxes.iter().map(|shard| async move {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            shard
+                .send(CacheCommand::EvictionCandidates { budget, policy, callback: tx })
+                .await
+                .ok()?;
+            rx.await.ok()
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+    }
+
+    /// Locks of every shard whose lease had run out by `now`, with their fencing token.
+    pub(crate) async fn route_expired_locks(&self, now: DateTime<Utc>) -> Vec<(Bytes, u64)> {
+        join_all(self.inboxes.iter().map(|shard| async move {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            shard.send(CacheCommand::ExpiredLocks { now, callback: tx }).await.ok()?;
+            rx.await.ok()
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+    }
+
+    pub(crate) async fn route_access(&self, key: Bytes) -> Result<Option<Access>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(&key).send(CacheCommand::Access { key, callback: tx }).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_eviction_pool(&self, policy: EvictionPolicy) -> EvictionPool {
+        let shards = join_all(self.inboxes.iter().map(|shard| async move {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            shard
+                .send(CacheCommand::EvictionPool { policy, size: EVICTION_POOL_SIZE, callback: tx })
+                .await
+                .ok()?;
+            rx.await.ok()
+        }))
+        .await;
+        EvictionPool::new(policy, shards.into_iter().flatten().collect())
+    }
+
     pub(crate) async fn drop_cache(&self) {
         let (txs, rxs) = self.oneshot_channels();
         join_all(
@@ -261,10 +645,15 @@ impl CacheManager {
         )
         .await;
 
-        join_all(rxs.into_iter()).await;
+        join_all(rxs).await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if self.leases.send(LeaseCommand::Drop { callback: tx }).await.is_ok() {
+            let _ = rx.await;
+        }
     }
 
-    pub(crate) async fn route_ttl(&self, key: String) -> Result<String> {
+    pub(crate) async fn route_ttl(&self, key: Bytes) -> Result<String> {
         let Ok(CacheValue { expiry: Some(exp), .. }) = self.route_get(key).await else {
             return Ok("-1".to_string());
         };
@@ -275,27 +664,142 @@ impl CacheManager {
         Ok(ttl)
     }
 
-    pub(crate) async fn route_append(&self, key: String, value: String) -> Result<usize> {
+    pub(crate) async fn route_append(&self, key: Bytes, value: Bytes) -> Result<usize> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.select_shard(key.as_str())
-            .send(CacheCommand::Append { key, value, callback: tx })
-            .await?;
+        self.select_shard(&key).send(CacheCommand::Append { key, value, callback: tx }).await?;
         rx.await?
     }
 
     pub(crate) async fn route_numeric_delta(
         &self,
-        key: String,
+        key: Bytes,
         arg: i64,
         current_idx: u64,
     ) -> Result<String> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.select_shard(key.as_str())
+        self.select_shard(&key)
             .send(CacheCommand::NumericDetla { key, delta: arg, callback: tx })
             .await?;
         let current = rx.await?;
         Ok(IndexedValueCodec::encode(current?, current_idx))
     }
+
+    pub(crate) async fn route_cas(
+        &self,
+        key: Bytes,
+        expected: Bytes,
+        value: Bytes,
+        current_idx: u64,
+    ) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(&key)
+            .send(CacheCommand::Cas { key, expected, value, callback: tx })
+            .await?;
+        let swapped = rx.await??;
+        Ok(IndexedValueCodec::encode(swapped as i64, current_idx))
+    }
+
+    /// Acquires the lock with the log index as its fencing token. Returns 0 when the lock is held.
+    pub(crate) async fn route_lock(
+        &self,
+        key: Bytes,
+        expiry: DateTime<Utc>,
+        proposed_at: DateTime<Utc>,
+        current_idx: u64,
+    ) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let cache_entry =
+            CacheEntry::new(key, current_idx.to_string().as_str()).with_expiry(expiry);
+        self.select_shard(cache_entry.key())
+            .send(CacheCommand::Lock { cache_entry, proposed_at, callback: tx })
+            .await?;
+        let token = if rx.await? { current_idx } else { 0 };
+        Ok(IndexedValueCodec::encode(token, current_idx))
+    }
+
+    pub(crate) async fn route_unlock(
+        &self,
+        key: Bytes,
+        token: u64,
+        current_idx: u64,
+    ) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(&key).send(CacheCommand::Unlock { key, token, callback: tx }).await?;
+        let released = rx.await?;
+        Ok(IndexedValueCodec::encode(released as i64, current_idx))
+    }
+
+    /// Stores the entry unless its key already exists and `replace` is not set. Returns whether it was stored.
+    pub(crate) async fn route_restore(
+        &self,
+        cache_entry: CacheEntry,
+        replace: bool,
+    ) -> Result<bool> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.select_shard(cache_entry.key())
+            .send(CacheCommand::Restore { cache_entry, replace, callback: tx })
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Grants a lease whose id is made of the granting partition and the log index it was granted at.
+    pub(crate) async fn route_lease_grant(
+        &self,
+        ttl_millis: u64,
+        partition: u16,
+        current_idx: u64,
+    ) -> Result<String> {
+        let id = lease_id(partition, current_idx);
+        self.leases.send(LeaseCommand::Grant { id, ttl_millis }).await?;
+        Ok(IndexedValueCodec::encode(id, current_idx))
+    }
+
+    pub(crate) async fn route_lease_keep_alive(&self, id: u64, current_idx: u64) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::KeepAlive { id, callback: tx }).await?;
+        Ok(IndexedValueCodec::encode(rx.await? as i64, current_idx))
+    }
+
+    pub(crate) async fn route_lease_attach(
+        &self,
+        id: u64,
+        keys: Vec<Bytes>,
+        current_idx: u64,
+    ) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::Attach { id, keys, callback: tx }).await?;
+        Ok(IndexedValueCodec::encode(rx.await? as i64, current_idx))
+    }
+
+    /// Revokes the lease and deletes every key attached to it. Returns the number of deleted keys.
+    pub(crate) async fn route_lease_revoke(&self, id: u64, current_idx: u64) -> Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::Revoke { id, callback: tx }).await?;
+        let deleted = match rx.await? {
+            | Some(keys) => self.route_delete(keys).await?,
+            | None => 0,
+        };
+        Ok(IndexedValueCodec::encode(deleted, current_idx))
+    }
+
+    pub(crate) async fn route_expired_leases(&self) -> Result<Vec<u64>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::Expired { callback: tx }).await?;
+        Ok(rx.await?)
+    }
+
+    async fn route_lease_states(&self) -> Result<LeaseStates> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::Snapshot { callback: tx }).await?;
+        Ok(rx.await?)
+    }
+
+    /// Brings back the leases of a loaded snapshot, replacing the current ones.
+    pub(crate) async fn restore_leases(&self, leases: LeaseStates) -> Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.leases.send(LeaseCommand::Restore { leases, callback: tx }).await?;
+        Ok(rx.await?)
+    }
 }
 
 pub struct IndexedValueCodec;
@@ -378,4 +882,255 @@ mod tests {
         assert_eq!(value2.value, "expire_value2");
         assert!(value2.expiry.is_some());
     }
+
+    #[tokio::test]
+    async fn test_append_and_cas_keep_values_that_are_not_utf8() {
+        // GIVEN
+        let cache_manager = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+        let key = "binary".to_string();
+        cache_manager
+            .route_append(key.clone().into(), Bytes::from_static(b"\xff\x00"))
+            .await
+            .unwrap();
+
+        // WHEN
+        let len = cache_manager.route_append(key.clone().into(), Bytes::from_static(b"\xfe")).await;
+        let swapped = cache_manager
+            .route_cas(
+                key.clone().into(),
+                Bytes::from_static(b"\xff\x00\xfe"),
+                Bytes::from_static(b"\x80"),
+                1,
+            )
+            .await
+            .unwrap();
+
+        // THEN
+        assert_eq!(len.unwrap(), 3);
+        assert_eq!(swapped, IndexedValueCodec::encode(1, 1));
+        assert_eq!(cache_manager.route_get(&key).await.unwrap(), &b"\x80"[..]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_logs_keeps_log_order_per_key() {
+        // GIVEN
+        let cache_manager = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+        let keys = (0..20).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+        let mut requests = vec![];
+        for key in &keys {
+            requests.push(WriteRequest::Set {
+                key: key.clone().into(),
+                value: "1".into(),
+                expires_at: None,
+            });
+        }
+        for _ in 0..3 {
+            for key in &keys {
+                requests.push(WriteRequest::Incr { key: key.clone().into(), delta: 1 });
+            }
+        }
+        // * Spans several shards, so it waits for the increments before it
+        requests.push(WriteRequest::Delete {
+            keys: keys[..10].iter().map(|key| key.clone().into()).collect(),
+        });
+        for key in &keys[..10] {
+            requests.push(WriteRequest::Append { key: key.clone().into(), value: "x".into() });
+        }
+        requests.push(WriteRequest::Set { key: "s".into(), value: "abc".into(), expires_at: None });
+        requests.push(WriteRequest::Incr { key: "s".into(), delta: 1 });
+        requests.push(WriteRequest::Append { key: "s".into(), value: "d".into() });
+        let ops = requests
+            .into_iter()
+            .zip(1..)
+            .map(|(request, log_index)| WriteOperation {
+                request,
+                log_index,
+                term: 1,
+                session_req: None,
+                timestamp: 0,
+            })
+            .collect();
+
+        // WHEN
+        cache_manager.apply_logs(ops).await;
+
+        // THEN
+        for key in &keys[..10] {
+            assert_eq!(cache_manager.route_get(key).await.unwrap(), CacheValue::new("x"));
+        }
+        for key in &keys[10..] {
+            assert_eq!(cache_manager.route_get(key).await.unwrap(), CacheValue::new("4"));
+        }
+        // * A log that fails to apply does not stop the ones after it
+        assert_eq!(cache_manager.route_get("s").await.unwrap(), CacheValue::new("abcd"));
+    }
+
+    #[tokio::test]
+    async fn test_run_sharded_routes_by_seeded_hash() {
+        // GIVEN
+        let hwm = Arc::new(AtomicU64::new(0));
+        let unseeded = CacheManager::run_sharded(hwm.clone(), 32, 0, ValueCompression::default());
+        let seeded = CacheManager::run_sharded(hwm.clone(), 32, 42, ValueCompression::default());
+        let keys = (0..100).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+
+        // WHEN
+        seeded.route_mset(keys.iter().map(|key| CacheEntry::new(key.clone(), "v")).collect()).await;
+
+        // THEN
+        assert_eq!(seeded.inboxes.len(), 32);
+        assert!(keys.iter().any(|key| {
+            seeded.take_shard_key(key.as_bytes()) != unseeded.take_shard_key(key.as_bytes())
+        }));
+        for key in &keys {
+            assert_eq!(seeded.route_get(key).await.unwrap(), CacheValue::new("v"));
+        }
+        // * At least one shard even when configured with none
+        assert_eq!(
+            CacheManager::run_sharded(hwm, 0, 0, ValueCompression::default()).inboxes.len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shard_stats_report_queue_depths() {
+        // GIVEN
+        let (tx1, _rx1) = tokio::sync::mpsc::channel(10);
+        let (tx2, _rx2) = tokio::sync::mpsc::channel(10);
+        let cache_manager = CacheManager {
+            inboxes: vec![CacheCommandSender(tx1), CacheCommandSender(tx2)],
+            leases: LeaseActor::run(),
+            hash_seed: 7,
+            batches: Default::default(),
+        };
+
+        // WHEN
+        cache_manager.inboxes[0].send(CacheCommand::Ping).await.unwrap();
+
+        // THEN
+        assert_eq!(
+            cache_manager.shard_stats(),
+            vec!["cache_shards:2", "cache_hash_seed:7", "cache_shard_queue_depths:1,0"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_batch_applies_shares_of_every_shard() {
+        // GIVEN
+        let cache_manager = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+        cache_manager.route_mset(vec![CacheEntry::new("stale", "1")]).await;
+        let mut requests: Vec<WriteRequest> = (0..20)
+            .map(|i| WriteRequest::Set {
+                key: format!("key_{i}").into(),
+                value: i.to_string().into(),
+                expires_at: None,
+            })
+            .collect();
+        requests.push(WriteRequest::Delete { keys: vec!["stale".into(), "key_0".into()] });
+
+        // WHEN
+        let res = cache_manager.route_batch(requests, 3).await.unwrap();
+
+        // THEN
+        assert_eq!(res, IndexedValueCodec::encode(21, 3));
+        assert_eq!(cache_manager.route_get("key_0").await.unwrap(), CacheValue::default());
+        assert_eq!(cache_manager.route_get("stale").await.unwrap(), CacheValue::default());
+        for i in 1..20 {
+            let value = cache_manager.route_get(format!("key_{i}")).await.unwrap();
+            assert_eq!(value, CacheValue::new(i.to_string().as_str()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_batch_rejects_whole_batch_with_invalid_request() {
+        // GIVEN
+        let cache_manager = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+        let requests = vec![
+            WriteRequest::Set { key: "a".into(), value: "1".into(), expires_at: None },
+            WriteRequest::Incr { key: "b".into(), delta: 1 },
+        ];
+
+        // WHEN
+        let res = cache_manager.route_batch(requests, 1).await;
+
+        // THEN
+        assert!(res.is_err());
+        assert_eq!(cache_manager.route_get("a").await.unwrap(), CacheValue::default());
+    }
+
+    #[tokio::test]
+    async fn test_staged_batch_holds_back_only_reads_of_its_keys() {
+        // GIVEN - a share of a batch staged on a shard, waiting for the go-ahead
+        let cache_manager = CacheManager::run_sharded(
+            Arc::new(AtomicU64::new(0)),
+            1,
+            0,
+            ValueCompression::default(),
+        );
+        cache_manager
+            .route_mset(vec![CacheEntry::new("a", "old"), CacheEntry::new("b", "b")])
+            .await;
+        let (go, go_rx) = tokio::sync::watch::channel(false);
+        let (staged_tx, staged_rx) = tokio::sync::oneshot::channel();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cache_manager.inboxes[0]
+            .send(CacheCommand::StageBatch {
+                ops: vec![BatchOp::Set(CacheEntry::new("a", "new"))],
+                go: go_rx,
+                staged: staged_tx,
+                callback: tx,
+            })
+            .await
+            .unwrap();
+        staged_rx.await.unwrap();
+
+        // WHEN
+        let held = tokio::spawn({
+            let cache_manager = cache_manager.clone();
+            async move { cache_manager.route_get("a").await.unwrap() }
+        });
+
+        // THEN - other commands are served meanwhile, and the read of the batch's key waits for it
+        assert_eq!(cache_manager.route_get("b").await.unwrap(), CacheValue::new("b"));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!held.is_finished());
+
+        go.send_replace(true);
+        assert!(rx.await.unwrap());
+        assert_eq!(held.await.unwrap(), CacheValue::new("new"));
+    }
+
+    #[tokio::test]
+    async fn test_discarded_batch_releases_reads_of_its_keys() {
+        // GIVEN
+        let cache_manager = CacheManager::run_sharded(
+            Arc::new(AtomicU64::new(0)),
+            1,
+            0,
+            ValueCompression::default(),
+        );
+        cache_manager.route_mset(vec![CacheEntry::new("a", "old")]).await;
+        let (go, go_rx) = tokio::sync::watch::channel(false);
+        let (staged_tx, _staged_rx) = tokio::sync::oneshot::channel();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        cache_manager.inboxes[0]
+            .send(CacheCommand::StageBatch {
+                ops: vec![BatchOp::Delete("a".into())],
+                go: go_rx,
+                staged: staged_tx,
+                callback: tx,
+            })
+            .await
+            .unwrap();
+        let held = tokio::spawn({
+            let cache_manager = cache_manager.clone();
+            async move { cache_manager.route_get("a").await.unwrap() }
+        });
+
+        // WHEN - the batch is called off
+        drop(go);
+
+        // THEN
+        assert!(!rx.await.unwrap());
+        assert_eq!(held.await.unwrap(), CacheValue::new("old"));
+    }
 }
@@ -3,6 +3,7 @@ use bincode::{
     BorrowDecode,
     error::{DecodeError, EncodeError},
 };
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use std::time::Duration;
 
@@ -10,16 +11,16 @@ use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CacheEntry {
-    pub(crate) key: String,
+    pub(crate) key: Bytes,
     pub(crate) value: CacheValue,
 }
 
 impl CacheEntry {
-    pub(crate) fn new(key: impl Into<String>, value: impl Into<TypedValue>) -> Self {
+    pub(crate) fn new(key: impl Into<Bytes>, value: impl Into<TypedValue>) -> Self {
         Self { key: key.into(), value: CacheValue::new(value) }
     }
 
-    pub(crate) fn new_with_cache_value(key: impl Into<String>, value: CacheValue) -> Self {
+    pub(crate) fn new_with_cache_value(key: impl Into<Bytes>, value: CacheValue) -> Self {
         Self { key: key.into(), value }
     }
 
@@ -38,17 +39,10 @@ impl CacheEntry {
         self.value.expiry
     }
 
-    pub(crate) fn key(&self) -> &str {
+    pub(crate) fn key(&self) -> &[u8] {
         &self.key
     }
 
-    pub(crate) fn from_slice(chunk: &[(&String, &CacheValue)]) -> Vec<Self> {
-        chunk
-            .iter()
-            .map(|(k, v)| CacheEntry::new_with_cache_value(k.to_string(), (*v).clone()))
-            .collect::<Vec<CacheEntry>>()
-    }
-
     pub(crate) fn expire_in(&self) -> anyhow::Result<Option<Duration>> {
         if let Some(expiry) = self.value.expiry {
             let dr = expiry
@@ -60,7 +54,7 @@ impl CacheEntry {
         Ok(None)
     }
 
-    pub(crate) fn destructure(self) -> (String, CacheValue) {
+    pub(crate) fn destructure(self) -> (Bytes, CacheValue) {
         (self.key, self.value)
     }
 
@@ -71,7 +65,8 @@ impl CacheEntry {
 
 impl bincode::Encode for CacheEntry {
     fn encode<E: bincode::enc::Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
-        bincode::Encode::encode(&self.key, encoder)?;
+        // * Same length-prefixed bytes as when keys were strings
+        bincode::Encode::encode(&self.key.to_vec(), encoder)?;
         bincode::Encode::encode(&self.value, encoder)?;
         Ok(())
     }
@@ -79,9 +74,9 @@ impl bincode::Encode for CacheEntry {
 
 impl<Ctx> bincode::Decode<Ctx> for CacheEntry {
     fn decode<D: bincode::de::Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
-        let key: String = bincode::Decode::decode(decoder)?;
+        let key: Vec<u8> = bincode::Decode::decode(decoder)?;
         let value: CacheValue = bincode::Decode::decode(decoder)?;
-        Ok(CacheEntry { key, value })
+        Ok(CacheEntry { key: key.into(), value })
     }
 }
 
@@ -89,9 +84,9 @@ impl<'de, Ctx> BorrowDecode<'de, Ctx> for CacheEntry {
     fn borrow_decode<D: bincode::de::BorrowDecoder<'de>>(
         decoder: &mut D,
     ) -> Result<Self, DecodeError> {
-        let key: String = BorrowDecode::borrow_decode(decoder)?;
+        let key: Vec<u8> = BorrowDecode::borrow_decode(decoder)?;
         let value: CacheValue = BorrowDecode::borrow_decode(decoder)?;
-        Ok(CacheEntry { key, value })
+        Ok(CacheEntry { key: key.into(), value })
     }
 }
 
@@ -6,16 +6,24 @@ use bytes::Bytes;
 use chrono::{DateTime, Utc};
 
 use crate::domains::caches::cache_objects::THasExpiry;
+use crate::domains::compression::Compression;
+
+// * Values past these sizes are dropped on a background task when unlinked, expired or evicted
+const LAZYFREE_THRESHOLD_ELEMENTS: usize = 64;
+const LAZYFREE_THRESHOLD_BYTES: usize = 64 * 1024;
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct CacheValue {
     pub(crate) value: TypedValue,
     pub(crate) expiry: Option<DateTime<Utc>>,
+    // * Codec and length before compression while a shard keeps the string compressed.
+    // * Values are always decompressed before they leave the shard.
+    pub(crate) compressed: Option<(Compression, usize)>,
 }
 
 impl CacheValue {
     pub(crate) fn new(value: impl Into<TypedValue>) -> Self {
-        Self { value: value.into(), expiry: None }
+        Self { value: value.into(), expiry: None, compressed: None }
     }
     pub(crate) fn with_expiry(self, expiry: DateTime<Utc>) -> Self {
         Self { expiry: Some(expiry), ..self }
@@ -35,6 +43,23 @@ impl CacheValue {
             | TypedValue::List(list) => list.len(),
         }
     }
+
+    /// Bytes held by the value, the unit `maxmemory` is accounted in.
+    pub(crate) fn memory_usage(&self) -> usize {
+        match &self.value {
+            | TypedValue::Null => 0,
+            | TypedValue::String(b) => b.len(),
+            | TypedValue::List(list) => list.iter().map(Bytes::len).sum(),
+        }
+    }
+
+    /// Whether dropping the value takes long enough to be done off the cache actor.
+    pub(crate) fn is_large(&self) -> bool {
+        match &self.value {
+            | TypedValue::List(list) => list.len() > LAZYFREE_THRESHOLD_ELEMENTS,
+            | _ => self.memory_usage() > LAZYFREE_THRESHOLD_BYTES,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -51,6 +76,12 @@ impl From<&str> for TypedValue {
     }
 }
 
+impl From<Bytes> for TypedValue {
+    fn from(b: Bytes) -> Self {
+        TypedValue::String(b)
+    }
+}
+
 impl From<Vec<&str>> for TypedValue {
     fn from(v: Vec<&str>) -> Self {
         TypedValue::List(v.into_iter().map(|s| Bytes::copy_from_slice(s.as_bytes())).collect())
@@ -196,7 +227,7 @@ mod tests {
     #[test]
     fn test_list_variant_encode_decode() {
         let list = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")];
-        let original = CacheValue { value: TypedValue::List(list.clone()), expiry: None };
+        let original = CacheValue::new(TypedValue::List(list.clone()));
         let encoded = encode_to_vec(&original, bincode::config::standard()).unwrap();
         let (decoded, _): (CacheValue, usize) =
             decode_from_slice(&encoded, bincode::config::standard()).unwrap();
@@ -211,6 +242,14 @@ mod tests {
         assert_eq!(tv, TypedValue::List(vec![Bytes::from("foo"), Bytes::from("bar")]));
     }
 
+    #[test]
+    fn test_is_large() {
+        assert!(!CacheValue::new("small").is_large());
+        assert!(CacheValue::new("v".repeat(64 * 1024 + 1).as_str()).is_large());
+        assert!(!CacheValue::new(vec!["a"; 64]).is_large());
+        assert!(CacheValue::new(vec!["a"; 65]).is_large());
+    }
+
     #[test]
     fn test_as_bytes_returns_err_on_list() {
         let tv = TypedValue::List(vec![Bytes::from("x")]);
@@ -1,17 +1,135 @@
+use super::big_keys::ScannedKey;
 use super::cache_objects::{CacheEntry, CacheValue};
+use super::eviction::{EvictionPolicy, EvictionPoolEntry};
+use super::lru_cache::Access;
+use super::value_compression::CompressionStats;
 use crate::domains::saves::command::SaveCommand;
-use tokio::sync::{mpsc, oneshot};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use tokio::sync::{mpsc, oneshot, watch};
 
 pub(crate) enum CacheCommand {
-    Set { cache_entry: CacheEntry },
-    Save { outbox: mpsc::Sender<SaveCommand> },
-    Get { key: String, callback: oneshot::Sender<CacheValue> },
-    Keys { pattern: Option<String>, callback: oneshot::Sender<Vec<String>> },
-    Delete { key: String, callback: oneshot::Sender<bool> },
-    IndexGet { key: String, read_idx: u64, callback: oneshot::Sender<CacheValue> },
+    Set {
+        cache_entry: CacheEntry,
+    },
+    Save {
+        outbox: mpsc::Sender<SaveCommand>,
+    },
+    Get {
+        key: Bytes,
+        callback: oneshot::Sender<CacheValue>,
+    },
+    Keys {
+        pattern: Option<Bytes>,
+        callback: oneshot::Sender<Vec<Bytes>>,
+    },
+    Delete {
+        key: Bytes,
+        callback: oneshot::Sender<bool>,
+    },
+    // * Removes the key at once and leaves large values to be dropped on a background task
+    Unlink {
+        key: Bytes,
+        callback: oneshot::Sender<bool>,
+    },
+    IndexGet {
+        key: Bytes,
+        read_idx: u64,
+        callback: oneshot::Sender<CacheValue>,
+    },
     Ping,
-    Drop { callback: oneshot::Sender<()> },
-    Exists { key: String, callback: oneshot::Sender<bool> },
-    Append { key: String, value: String, callback: oneshot::Sender<anyhow::Result<usize>> },
-    NumericDetla { key: String, delta: i64, callback: oneshot::Sender<anyhow::Result<i64>> },
+    Drop {
+        callback: oneshot::Sender<()>,
+    },
+    Exists {
+        key: Bytes,
+        callback: oneshot::Sender<bool>,
+    },
+    Append {
+        key: Bytes,
+        value: Bytes,
+        callback: oneshot::Sender<anyhow::Result<usize>>,
+    },
+    NumericDetla {
+        key: Bytes,
+        delta: i64,
+        callback: oneshot::Sender<anyhow::Result<i64>>,
+    },
+    Cas {
+        key: Bytes,
+        expected: Bytes,
+        value: Bytes,
+        callback: oneshot::Sender<anyhow::Result<bool>>,
+    },
+    Lock {
+        cache_entry: CacheEntry,
+        proposed_at: DateTime<Utc>,
+        callback: oneshot::Sender<bool>,
+    },
+    Unlock {
+        key: Bytes,
+        token: u64,
+        callback: oneshot::Sender<bool>,
+    },
+    ExpiredLocks {
+        now: DateTime<Utc>,
+        callback: oneshot::Sender<Vec<(Bytes, u64)>>,
+    },
+    Restore {
+        cache_entry: CacheEntry,
+        replace: bool,
+        callback: oneshot::Sender<bool>,
+    },
+    // * The shard's share of a batch, staged until `go` says whether the whole batch goes ahead.
+    // * `staged` is answered right away, and `callback` once the share is applied or discarded
+    StageBatch {
+        ops: Vec<BatchOp>,
+        go: watch::Receiver<bool>,
+        staged: oneshot::Sender<()>,
+        callback: oneshot::Sender<bool>,
+    },
+    SettleBatch {
+        applied: bool,
+        callback: oneshot::Sender<bool>,
+    },
+    MemoryUsage {
+        callback: oneshot::Sender<usize>,
+    },
+    CompressionStats {
+        callback: oneshot::Sender<CompressionStats>,
+    },
+    ScanKeySizes {
+        cursor: usize,
+        count: usize,
+        callback: oneshot::Sender<(Vec<ScannedKey>, Option<usize>)>,
+    },
+    // * Read without counting as an access
+    Access {
+        key: Bytes,
+        callback: oneshot::Sender<Option<Access>>,
+    },
+    EvictionPool {
+        policy: EvictionPolicy,
+        size: usize,
+        callback: oneshot::Sender<Vec<EvictionPoolEntry>>,
+    },
+    EvictionCandidates {
+        budget: usize,
+        policy: EvictionPolicy,
+        callback: oneshot::Sender<Vec<Bytes>>,
+    },
+}
+
+pub(crate) enum BatchOp {
+    Set(CacheEntry),
+    Delete(Bytes),
+}
+
+impl BatchOp {
+    pub(crate) fn key(&self) -> &[u8] {
+        match self {
+            | BatchOp::Set(cache_entry) => cache_entry.key(),
+            | BatchOp::Delete(key) => key,
+        }
+    }
 }
@@ -0,0 +1,338 @@
+use crate::domains::caches::cache_objects::CacheValue;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
+use std::cmp::Reverse;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// * Keys `MEMORY EVICTIONPOOL` lists, as many as the eviction pool of Redis holds
+pub(crate) const EVICTION_POOL_SIZE: usize = 16;
+
+/// Which keys make room once the keyspace outgrows `maxmemory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum EvictionPolicy {
+    // * Writes are refused instead
+    #[default]
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    // * Only keys with an expiry, the ones expiring soonest first
+    VolatileTtl,
+    AllKeysRandom,
+}
+
+impl EvictionPolicy {
+    /// Puts `candidates`, ordered from the least recently used one and paired with their access
+    /// counts, in the order they are evicted in. Keys the policy never evicts are left out.
+    pub(crate) fn rank<'a>(
+        &self,
+        mut candidates: Vec<(&'a Bytes, &'a CacheValue, u32)>,
+    ) -> Vec<(&'a Bytes, &'a CacheValue, u32)> {
+        match self {
+            | Self::NoEviction => candidates.clear(),
+            | Self::AllKeysLru => {},
+            // * Stable, so that keys accessed as often are evicted least recently used first
+            | Self::AllKeysLfu => candidates.sort_by_key(|(_, _, hits)| *hits),
+            | Self::VolatileTtl => {
+                candidates.retain(|(_, value, _)| value.expiry.is_some());
+                candidates.sort_by_key(|(_, value, _)| value.expiry);
+            },
+            | Self::AllKeysRandom => candidates.shuffle(&mut rand::rng()),
+        }
+        candidates
+    }
+
+    /// Picks keys until `overflow` bytes are freed, in the order `rank` puts them in.
+    pub(crate) fn pick(
+        &self,
+        candidates: Vec<(&Bytes, &CacheValue, u32)>,
+        overflow: usize,
+    ) -> Vec<Bytes> {
+        let mut freed = 0;
+        self.rank(candidates)
+            .into_iter()
+            .take_while(|(key, value, _)| {
+                let more = freed < overflow;
+                freed += memory_usage(key, value);
+                more
+            })
+            .map(|(key, _, _)| key.clone())
+            .collect()
+    }
+
+    /// Orders pool entries gathered from several shards the way `rank` orders a shard's keys.
+    fn sort_pool(&self, entries: &mut [EvictionPoolEntry]) {
+        match self {
+            | Self::NoEviction | Self::AllKeysRandom => {},
+            | Self::AllKeysLru => entries.sort_by_key(|entry| Reverse(entry.idle)),
+            | Self::AllKeysLfu => entries.sort_by_key(|entry| (entry.freq, Reverse(entry.idle))),
+            | Self::VolatileTtl => entries.sort_by_key(|entry| entry.expiry),
+        }
+    }
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            | "noeviction" => Ok(Self::NoEviction),
+            | "allkeys-lru" => Ok(Self::AllKeysLru),
+            | "allkeys-lfu" => Ok(Self::AllKeysLfu),
+            | "volatile-ttl" => Ok(Self::VolatileTtl),
+            | "allkeys-random" => Ok(Self::AllKeysRandom),
+            | _ => Err(anyhow::anyhow!(
+                "invalid eviction policy '{s}', expected noeviction, allkeys-lru, allkeys-lfu, volatile-ttl or allkeys-random"
+            )),
+        }
+    }
+}
+
+impl Display for EvictionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Self::NoEviction => write!(f, "noeviction"),
+            | Self::AllKeysLru => write!(f, "allkeys-lru"),
+            | Self::AllKeysLfu => write!(f, "allkeys-lfu"),
+            | Self::VolatileTtl => write!(f, "volatile-ttl"),
+            | Self::AllKeysRandom => write!(f, "allkeys-random"),
+        }
+    }
+}
+
+/// Bytes a key accounts for against `maxmemory`.
+pub(crate) fn memory_usage(key: &[u8], value: &CacheValue) -> usize {
+    key.len() + value.memory_usage()
+}
+
+/// Memory ceiling of the keyspace, shared by every client connection of a node.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MaxMemory {
+    // * Bytes of keys and values the node may hold. 0 means no limit.
+    pub(crate) limit: u64,
+    pub(crate) policy: EvictionPolicy,
+    evicted_keys: Arc<AtomicU64>,
+}
+
+impl MaxMemory {
+    pub(crate) fn new(limit: u64, policy: EvictionPolicy) -> Self {
+        Self { limit, policy, evicted_keys: Arc::default() }
+    }
+
+    /// Whether keys are evicted to stay under the limit.
+    pub(crate) fn evicts(&self) -> bool {
+        self.limit > 0 && self.policy != EvictionPolicy::NoEviction
+    }
+
+    /// Whether writes are refused, rather than keys evicted, once the limit is exceeded.
+    pub(crate) fn refuses_writes(&self) -> bool {
+        self.limit > 0 && self.policy == EvictionPolicy::NoEviction
+    }
+
+    pub(crate) fn is_exceeded(&self, used_memory: usize) -> bool {
+        self.limit > 0 && used_memory as u64 > self.limit
+    }
+
+    pub(crate) fn record_evictions(&self, count: usize) {
+        self.evicted_keys.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn vectorize(&self, used_memory: usize) -> Vec<String> {
+        vec![
+            format!("used_memory:{used_memory}"),
+            format!("used_memory_human:{}", bytes_to_human(used_memory as u64)),
+            format!("maxmemory:{}", self.limit),
+            format!("maxmemory_human:{}", bytes_to_human(self.limit)),
+            format!("maxmemory_policy:{}", self.policy),
+            format!("evicted_keys:{}", self.evicted_keys.load(Ordering::Relaxed)),
+        ]
+    }
+}
+
+/// Byte count the way Redis renders its `*_human` fields, e.g. `1.50M`.
+fn bytes_to_human(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.2}{}", UNITS[unit])
+}
+
+/// A key next in line for eviction, with what the policy ranked it by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EvictionPoolEntry {
+    pub(crate) key: Bytes,
+    pub(crate) idle: Duration,
+    pub(crate) freq: u32,
+    pub(crate) expiry: Option<DateTime<Utc>>,
+}
+
+/// The keys the policy evicts first across the shards, reported by `MEMORY EVICTIONPOOL`.
+#[derive(Debug)]
+pub(crate) struct EvictionPool {
+    policy: EvictionPolicy,
+    entries: Vec<EvictionPoolEntry>,
+}
+
+impl EvictionPool {
+    pub(crate) fn new(policy: EvictionPolicy, shards: Vec<Vec<EvictionPoolEntry>>) -> Self {
+        let mut entries = shards.into_iter().flatten().collect::<Vec<_>>();
+        if policy == EvictionPolicy::AllKeysRandom {
+            entries.shuffle(&mut rand::rng());
+        }
+        policy.sort_pool(&mut entries);
+        entries.truncate(EVICTION_POOL_SIZE);
+        Self { policy, entries }
+    }
+}
+
+impl Display for EvictionPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "maxmemory_policy:{}\r\npool_size:{}", self.policy, self.entries.len())?;
+        let now = Utc::now();
+        for entry in self.entries.iter() {
+            let ttl = entry
+                .expiry
+                .map(|expiry| expiry.signed_duration_since(now).num_seconds().max(0))
+                .unwrap_or(-1);
+            write!(
+                f,
+                "\r\ncandidate:{} idle={} freq={} ttl={}",
+                String::from_utf8_lossy(&entry.key),
+                entry.idle.as_secs(),
+                entry.freq,
+                ttl
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn candidates(entries: &[(Bytes, CacheValue, u32)]) -> Vec<(&Bytes, &CacheValue, u32)> {
+        entries.iter().map(|(key, value, hits)| (key, value, *hits)).collect()
+    }
+
+    fn entries() -> Vec<(Bytes, CacheValue, u32)> {
+        let now = Utc::now();
+        vec![
+            ("k1".into(), CacheValue::new("v1"), 5),
+            ("k2".into(), CacheValue::new("v2").with_expiry(now + Duration::minutes(10)), 1),
+            ("k3".into(), CacheValue::new("v3").with_expiry(now + Duration::minutes(1)), 3),
+        ]
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used_until_overflow_is_freed() {
+        let entries = entries();
+        assert_eq!(EvictionPolicy::AllKeysLru.pick(candidates(&entries), 1), vec!["k1"]);
+        assert_eq!(EvictionPolicy::AllKeysLru.pick(candidates(&entries), 5), vec!["k1", "k2"]);
+        assert!(EvictionPolicy::AllKeysLru.pick(candidates(&entries), 0).is_empty());
+    }
+
+    #[test]
+    fn test_bytes_to_human() {
+        assert_eq!(bytes_to_human(0), "0B");
+        assert_eq!(bytes_to_human(1536), "1.50K");
+        assert_eq!(bytes_to_human(3 * 1024 * 1024), "3.00M");
+    }
+
+    #[test]
+    fn test_lfu_evicts_least_frequently_used() {
+        let entries = entries();
+        assert_eq!(EvictionPolicy::AllKeysLfu.pick(candidates(&entries), 5), vec!["k2", "k3"]);
+    }
+
+    #[test]
+    fn test_volatile_ttl_only_evicts_keys_with_expiry() {
+        let entries = entries();
+        assert_eq!(EvictionPolicy::VolatileTtl.pick(candidates(&entries), 100), vec!["k3", "k2"]);
+    }
+
+    #[test]
+    fn test_random_and_noeviction() {
+        let entries = entries();
+        assert_eq!(EvictionPolicy::AllKeysRandom.pick(candidates(&entries), 100).len(), 3);
+        assert!(EvictionPolicy::NoEviction.pick(candidates(&entries), 100).is_empty());
+    }
+
+    #[test]
+    fn test_rank_orders_keys_by_policy() {
+        let entries = entries();
+        let ranked = |policy: EvictionPolicy| -> Vec<&Bytes> {
+            policy.rank(candidates(&entries)).into_iter().map(|(key, _, _)| key).collect()
+        };
+        assert_eq!(ranked(EvictionPolicy::AllKeysLru), vec!["k1", "k2", "k3"]);
+        assert_eq!(ranked(EvictionPolicy::AllKeysLfu), vec!["k2", "k3", "k1"]);
+        assert_eq!(ranked(EvictionPolicy::VolatileTtl), vec!["k3", "k2"]);
+        assert!(ranked(EvictionPolicy::NoEviction).is_empty());
+    }
+
+    #[test]
+    fn test_eviction_pool_merges_shards_in_policy_order() {
+        let entry = |key: &str, idle: u64, freq: u32| EvictionPoolEntry {
+            key: key.to_string().into(),
+            idle: std::time::Duration::from_secs(idle),
+            freq,
+            expiry: None,
+        };
+        let shards = || vec![vec![entry("a", 30, 1), entry("b", 5, 1)], vec![entry("c", 10, 0)]];
+
+        let lru = EvictionPool::new(EvictionPolicy::AllKeysLru, shards());
+        assert_eq!(
+            lru.to_string(),
+            [
+                "maxmemory_policy:allkeys-lru",
+                "pool_size:3",
+                "candidate:a idle=30 freq=1 ttl=-1",
+                "candidate:c idle=10 freq=0 ttl=-1",
+                "candidate:b idle=5 freq=1 ttl=-1",
+            ]
+            .join("\r\n")
+        );
+
+        let lfu = EvictionPool::new(EvictionPolicy::AllKeysLfu, shards());
+        let keys: Vec<_> = lfu.entries.iter().map(|entry| &entry.key).collect();
+        assert_eq!(keys, vec!["c", "a", "b"]);
+
+        let many = vec![(0..20).map(|i| entry(&i.to_string(), i, 0)).collect()];
+        assert_eq!(EvictionPool::new(EvictionPolicy::AllKeysLru, many).entries.len(), 16);
+    }
+
+    #[test]
+    fn test_parse_eviction_policy() {
+        for policy in ["noeviction", "allkeys-lru", "allkeys-lfu", "volatile-ttl", "allkeys-random"]
+        {
+            assert_eq!(policy.parse::<EvictionPolicy>().unwrap().to_string(), policy);
+        }
+        assert!("volatile-lru".parse::<EvictionPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_maxmemory_enforcement() {
+        let noeviction = MaxMemory::new(10, EvictionPolicy::NoEviction);
+        assert!(noeviction.refuses_writes() && !noeviction.evicts());
+        assert!(noeviction.is_exceeded(11));
+        assert!(!noeviction.is_exceeded(10));
+
+        let lru = MaxMemory::new(10, EvictionPolicy::AllKeysLru);
+        assert!(!lru.refuses_writes() && lru.evicts());
+
+        let unlimited = MaxMemory::new(0, EvictionPolicy::AllKeysLru);
+        assert!(!unlimited.evicts() && !unlimited.is_exceeded(usize::MAX));
+    }
+}
@@ -24,6 +24,7 @@ use std::borrow::Borrow;
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::time::Instant;
 use std::vec;
 
 use crate::domains::caches::cache_objects::THasExpiry;
@@ -70,8 +71,17 @@ impl<T: Clone> Slab<T> {
 struct Node<K: Debug + Clone, V: Debug + Clone> {
     key: K,
     value: V,
-    prev: Option<usize>, // pointer
-    next: Option<usize>, // pointer
+    hits: u32,            // accesses, for least frequently used eviction
+    last_access: Instant, // for idle time
+    prev: Option<usize>,  // pointer
+    next: Option<usize>,  // pointer
+}
+
+/// How often and how recently a key was read or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Access {
+    pub(crate) hits: u32,
+    pub(crate) last_access: Instant,
 }
 
 pub struct LruCache<K: Eq + std::hash::Hash + Debug + Clone, V: Debug + Clone> {
@@ -104,11 +114,50 @@ impl<K: Eq + Hash + Clone + Debug, V: Debug + Clone + THasExpiry> LruCache<K, V>
         self.map.keys()
     }
 
-    pub fn iter(&self) -> LruIter<K, V> {
+    pub fn iter(&self) -> LruIter<'_, K, V> {
         LruIter { cache: self, current: self.head }
     }
 
-    pub fn entry(&mut self, key: K) -> Entry<K, V> {
+    /// Up to `count` entries from slab slot `cursor` on, and the cursor to resume from.
+    /// A key keeps its slot while it lives, so keys present for the whole scan are all visited.
+    pub(crate) fn scan(&self, cursor: usize, count: usize) -> (Vec<(&K, &V)>, Option<usize>) {
+        // * Slot 0 is never used
+        let start = cursor.max(1);
+        let end = (start + count).min(self.slab.data.len());
+        let entries = (start..end)
+            .filter_map(|index| self.slab.get(index))
+            .map(|node| (&node.key, &node.value))
+            .collect();
+        (entries, (end < self.slab.data.len()).then_some(end))
+    }
+
+    /// Entries with their access counts, from the least recently used one.
+    pub(crate) fn iter_from_lru(&self) -> impl Iterator<Item = (&K, &V, u32)> {
+        std::iter::successors(self.tail, |&index| self.slab.get(index)?.prev)
+            .filter_map(|index| self.slab.get(index))
+            .map(|node| (&node.key, &node.value, node.hits))
+    }
+
+    /// Access stats of a key, without counting as an access.
+    pub(crate) fn access<Q>(&self, key: &Q) -> Option<Access>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.slab.get(*self.map.get(key)?)?;
+        Some(Access { hits: node.hits, last_access: node.last_access })
+    }
+
+    /// Value of a key, without counting as an access.
+    pub(crate) fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Some(&self.slab.get(*self.map.get(key)?)?.value)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
         if let Some(&index) = self.map.get(&key) {
             Entry::Occupied(OccupiedEntry { cache: self, index })
         } else {
@@ -117,6 +166,9 @@ impl<K: Eq + Hash + Clone + Debug, V: Debug + Clone + THasExpiry> LruCache<K, V>
     }
 
     fn move_to_head(&mut self, index: usize) {
+        let node = self.slab.get_mut(index).expect("Node not found");
+        node.hits = node.hits.saturating_add(1);
+        node.last_access = Instant::now();
         if self.head == Some(index) {
             return; // Already at head
         }
@@ -257,7 +309,14 @@ impl<K: Eq + Hash + Clone + Debug, V: Debug + Clone + THasExpiry> LruCache<K, V>
             if value.has_expiry() {
                 self.keys_with_expiry += 1;
             }
-            let new_node = Node { key: key.clone(), value, prev: None, next: None };
+            let new_node = Node {
+                key: key.clone(),
+                value,
+                hits: 0,
+                last_access: Instant::now(),
+                prev: None,
+                next: None,
+            };
             let new_idx = self.slab.insert(new_node).expect("Slab should have space");
             self.map.insert(key, new_idx);
             self.current_size += 1;
@@ -586,6 +645,52 @@ mod tests {
         assert_eq!(cache.get(&3), Some(&CacheValue::new("three")));
     }
 
+    #[test]
+    fn test_scan_visits_every_slot_once() {
+        let mut cache = LruCache::new(5);
+        for i in 0..4 {
+            cache.put(i, "value");
+        }
+        cache.remove(&1);
+
+        let (first, cursor) = cache.scan(0, 2);
+        let (second, cursor) = cache.scan(cursor.unwrap(), 2);
+        let (third, cursor) = cache.scan(cursor.unwrap(), 2);
+        assert_eq!(cursor, None);
+
+        let mut keys: Vec<_> =
+            first.into_iter().chain(second).chain(third).map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_iter_from_lru_counts_accesses() {
+        let mut cache = LruCache::new(3);
+        cache.put(1, "one");
+        cache.put(2, "two");
+        cache.put(3, "three");
+        let _ = cache.get(&1);
+        let _ = cache.get(&1);
+
+        let items: Vec<_> = cache.iter_from_lru().collect();
+        assert_eq!(items, vec![(&2, &"two", 1), (&3, &"three", 1), (&1, &"one", 3)]);
+    }
+
+    #[test]
+    fn test_access_is_read_without_counting_as_one() {
+        let mut cache = LruCache::new(3);
+        cache.put(1, "one");
+        let written = cache.access(&1).unwrap();
+        let _ = cache.get(&1);
+
+        let read = cache.access(&1).unwrap();
+        assert_eq!(read.hits, 2);
+        assert!(read.last_access >= written.last_access);
+        assert_eq!(cache.access(&1), Some(read));
+        assert_eq!(cache.access(&2), None);
+    }
+
     #[test]
     fn test_lru_with_expiry() {
         let mut cache = LruCache::new(3);
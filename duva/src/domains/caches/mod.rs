@@ -1,8 +1,11 @@
 pub mod actor;
+pub(crate) mod big_keys;
 
 pub mod cache_manager;
 pub mod cache_objects;
 pub mod command;
+pub(crate) mod eviction;
 mod lru_cache;
 pub mod read_queue;
 mod service;
+pub(crate) mod value_compression;
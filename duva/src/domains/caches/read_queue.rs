@@ -1,3 +1,4 @@
+use bytes::Bytes;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -11,7 +12,7 @@ pub struct ReadQueue {
 }
 
 pub(crate) struct DeferredRead {
-    pub(crate) key: String,
+    pub(crate) key: Bytes,
     pub(crate) callback: Sender<CacheValue>,
 }
 
@@ -26,12 +27,12 @@ impl ReadQueue {
     pub(crate) fn defer_if_stale(
         &mut self,
         read_idx: u64,
-        key: &str,
+        key: &Bytes,
         callback: Sender<CacheValue>,
     ) -> Option<Sender<CacheValue>> {
         let current_hwm = self.hwm.load(Ordering::Relaxed);
         if current_hwm < read_idx {
-            self.push(read_idx, DeferredRead { key: key.into(), callback });
+            self.push(read_idx, DeferredRead { key: key.clone(), callback });
             None
         } else {
             Some(callback)
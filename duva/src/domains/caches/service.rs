@@ -1,5 +1,4 @@
 use crate::domains::caches::actor::CacheActor;
-use crate::domains::caches::cache_objects::CacheEntry;
 use crate::domains::caches::command::CacheCommand;
 use crate::domains::caches::read_queue::{DeferredRead, ReadQueue};
 
@@ -19,13 +18,17 @@ impl CacheActor {
                     let _ = self.try_send_ttl(&cache_entry).await;
                     self.set(cache_entry);
                 },
-                | CacheCommand::Get { key, callback } => {
-                    self.get(&key, callback);
+                | read @ (CacheCommand::Get { .. }
+                | CacheCommand::IndexGet { .. }
+                | CacheCommand::Exists { .. }) => {
+                    self.read(read, &mut rq);
                 },
-                | CacheCommand::IndexGet { key, read_idx, callback } => {
-                    if let Some(callback) = rq.defer_if_stale(read_idx, &key, callback) {
-                        self.get(&key, callback);
-                    }
+                | CacheCommand::StageBatch { ops, go, staged, callback } => {
+                    self.stage_batch(ops, go, callback);
+                    let _ = staged.send(());
+                },
+                | CacheCommand::SettleBatch { applied, callback } => {
+                    let _ = callback.send(self.settle_batch(applied, &mut rq).await);
                 },
                 | CacheCommand::Keys { pattern, callback } => {
                     self.keys(pattern, callback);
@@ -33,22 +36,26 @@ impl CacheActor {
                 | CacheCommand::Delete { key, callback } => {
                     self.delete(key, callback);
                 },
-                | CacheCommand::Exists { key, callback } => {
-                    self.exists(key, callback);
+                | CacheCommand::Unlink { key, callback } => {
+                    self.unlink(key, callback);
                 },
                 | CacheCommand::Save { outbox } => {
-                    outbox
-                        .send(SaveCommand::LocalShardSize {
-                            table_size: self.len(),
-                            expiry_size: self.keys_with_expiry(),
-                        })
-                        .await?;
-
-                    for chunk in self.cache.iter().collect::<Vec<_>>().chunks(10) {
-                        outbox.send(SaveCommand::SaveChunk(CacheEntry::from_slice(chunk))).await?;
-                    }
-                    // finalize the save operation
-                    outbox.send(SaveCommand::StopSentinel).await?;
+                    // * Copy the shard as of now and stream it from a separate task, so that
+                    // * the actor keeps serving commands while the snapshot is being written.
+                    let table_size = self.len();
+                    let expiry_size = self.keys_with_expiry();
+                    let chunks = self.entries().chunks(10).map(<[_]>::to_vec).collect::<Vec<_>>();
+
+                    tokio::spawn(async move {
+                        outbox
+                            .send(SaveCommand::LocalShardSize { table_size, expiry_size })
+                            .await?;
+                        for chunk in chunks {
+                            outbox.send(SaveCommand::SaveChunk(chunk)).await?;
+                        }
+                        // finalize the save operation
+                        outbox.send(SaveCommand::StopSentinel).await
+                    });
                 },
                 | CacheCommand::Ping => {
                     if let Some(pending_rqs) = rq.take_pending_requests() {
@@ -67,6 +74,44 @@ impl CacheActor {
                 | CacheCommand::NumericDetla { key, delta, callback } => {
                     let _ = callback.send(self.numeric_delta(key, delta));
                 },
+                | CacheCommand::Cas { key, expected, value, callback } => {
+                    let _ = callback.send(self.cas(key, expected, value));
+                },
+                | CacheCommand::Lock { cache_entry, proposed_at, callback } => {
+                    let _ = callback.send(self.lock(cache_entry, &proposed_at));
+                },
+                | CacheCommand::Unlock { key, token, callback } => {
+                    let _ = callback.send(self.unlock(key, token));
+                },
+                | CacheCommand::ExpiredLocks { now, callback } => {
+                    let _ = callback.send(self.expired_locks(&now));
+                },
+                | CacheCommand::Restore { cache_entry, replace, callback } => {
+                    let restored = replace || !self.is_live(cache_entry.key());
+                    if restored {
+                        let _ = self.try_send_ttl(&cache_entry).await;
+                        self.set(cache_entry);
+                    }
+                    let _ = callback.send(restored);
+                },
+                | CacheCommand::MemoryUsage { callback } => {
+                    let _ = callback.send(self.memory_usage());
+                },
+                | CacheCommand::CompressionStats { callback } => {
+                    let _ = callback.send(self.compression_stats());
+                },
+                | CacheCommand::ScanKeySizes { cursor, count, callback } => {
+                    let _ = callback.send(self.scan_key_sizes(cursor, count));
+                },
+                | CacheCommand::Access { key, callback } => {
+                    let _ = callback.send(self.access(&key));
+                },
+                | CacheCommand::EvictionPool { policy, size, callback } => {
+                    let _ = callback.send(self.eviction_pool(policy, size));
+                },
+                | CacheCommand::EvictionCandidates { budget, policy, callback } => {
+                    let _ = callback.send(self.eviction_candidates(budget, policy));
+                },
             }
         }
         Ok(self)
@@ -84,6 +129,10 @@ mod test {
     use crate::domains::caches::command::CacheCommand;
     use crate::domains::caches::lru_cache::LruCache;
     use crate::domains::caches::read_queue::ReadQueue;
+    use crate::domains::caches::value_compression::ValueCompression;
+    use crate::domains::compression::Compression;
+    use crate::domains::saves::command::SaveCommand;
+    use bytes::Bytes;
     use std::sync::Arc;
     use std::sync::atomic::AtomicU64;
     use std::time::Duration;
@@ -100,7 +149,7 @@ mod test {
                 .unwrap();
         }
         async fn get(&self, key: String, callback: oneshot::Sender<CacheValue>) {
-            self.0.send(CacheCommand::Get { key, callback }).await.unwrap();
+            self.0.send(CacheCommand::Get { key: key.into(), callback }).await.unwrap();
         }
         async fn index_get(
             &self,
@@ -108,7 +157,10 @@ mod test {
             read_idx: u64,
             callback: oneshot::Sender<CacheValue>,
         ) {
-            self.0.send(CacheCommand::IndexGet { key, read_idx, callback }).await.unwrap();
+            self.0
+                .send(CacheCommand::IndexGet { key: key.into(), read_idx, callback })
+                .await
+                .unwrap();
         }
         async fn ping(&self) {
             self.0.send(CacheCommand::Ping).await.unwrap();
@@ -128,7 +180,10 @@ mod test {
         tokio::spawn(
             CacheActor {
                 cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
                 self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
             }
             .handle(rx, ReadQueue::new(hwm.clone())),
         );
@@ -162,7 +217,10 @@ mod test {
         tokio::spawn(
             CacheActor {
                 cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
                 self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
             }
             .handle(rx, ReadQueue::new(hwm.clone())),
         );
@@ -198,7 +256,10 @@ mod test {
         tokio::spawn(
             CacheActor {
                 cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
                 self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
             }
             .handle(rx, ReadQueue::new(hwm.clone())),
         );
@@ -220,4 +281,220 @@ mod test {
 
         assert!(matches!(rx.await, Ok(CacheValue { value: TypedValue::Null, .. })));
     }
+
+    #[tokio::test]
+    async fn test_unlink_detaches_large_values() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor {
+                cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
+                self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
+            }
+            .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let items = vec!["item"; 10_000];
+        cache
+            .send(CacheCommand::Set {
+                cache_entry: CacheEntry::new_with_cache_value("list", CacheValue::new(items)),
+            })
+            .await
+            .unwrap();
+        let cache = S(cache);
+        cache.set("small".to_string(), "value").await;
+
+        // WHEN
+        for (key, expected) in [("list", true), ("small", true), ("missing", false)] {
+            let (tx, rx) = oneshot::channel();
+            cache
+                .0
+                .send(CacheCommand::Unlink { key: key.to_string().into(), callback: tx })
+                .await
+                .unwrap();
+            assert_eq!(rx.await.unwrap(), expected);
+        }
+
+        // THEN
+        for key in ["list", "small"] {
+            let (tx, rx) = oneshot::channel();
+            cache.get(key.to_string(), tx).await;
+            assert!(rx.await.unwrap().null());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_does_not_block_cache_while_snapshot_is_drained() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor {
+                cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
+                self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
+            }
+            .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+        for i in 0..50 {
+            cache.set(format!("key{i}"), "value").await;
+        }
+
+        // WHEN - nobody drains the snapshot yet
+        let (outbox, mut inbox) = tokio::sync::mpsc::channel(1);
+        cache.0.send(CacheCommand::Save { outbox }).await.unwrap();
+        cache.set("key0".to_string(), "changed").await;
+
+        // THEN
+        let (tx, rx) = oneshot::channel();
+        cache.get("key0".to_string(), tx).await;
+        let res = timeout(Duration::from_millis(1000), rx).await.unwrap().unwrap();
+        assert_eq!(res, CacheValue::new("changed"));
+
+        // * the snapshot still holds the shard as it was when the save started
+        let mut saved = vec![];
+        while let Some(cmd) = inbox.recv().await {
+            match cmd {
+                | SaveCommand::SaveChunk(chunk) => saved.extend(chunk),
+                | SaveCommand::StopSentinel => break,
+                | SaveCommand::LocalShardSize { table_size, .. } => assert_eq!(table_size, 50),
+            }
+        }
+        assert_eq!(saved.len(), 50);
+        let key0 = saved.iter().find(|entry| entry.key() == b"key0").unwrap();
+        assert_eq!(key0.as_str().unwrap(), "value");
+    }
+
+    #[tokio::test]
+    async fn test_compressed_values_are_served_and_saved_as_they_were_set() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor {
+                cache: LruCache::new(1000),
+                compression: ValueCompression::new(Compression::Lz4, 100),
+                self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
+            }
+            .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let cache = S(cache);
+        let value = "duva".repeat(100);
+        cache.set("key".to_string(), &value).await;
+
+        // WHEN
+        let (tx, rx) = oneshot::channel();
+        cache
+            .0
+            .send(CacheCommand::Append { key: "key".into(), value: "!".into(), callback: tx })
+            .await
+            .unwrap();
+        assert_eq!(rx.await.unwrap().unwrap(), value.len() + 1);
+
+        // THEN
+        let expected = format!("{value}!");
+        let (tx, rx) = oneshot::channel();
+        cache.get("key".to_string(), tx).await;
+        assert_eq!(rx.await.unwrap(), CacheValue::new(expected.as_str()));
+
+        let (tx, rx) = oneshot::channel();
+        cache.0.send(CacheCommand::MemoryUsage { callback: tx }).await.unwrap();
+        assert!(rx.await.unwrap() < expected.len());
+
+        let (outbox, mut inbox) = tokio::sync::mpsc::channel(10);
+        cache.0.send(CacheCommand::Save { outbox }).await.unwrap();
+        let mut saved = vec![];
+        while let Some(cmd) = inbox.recv().await {
+            match cmd {
+                | SaveCommand::SaveChunk(chunk) => saved.extend(chunk),
+                | SaveCommand::StopSentinel => break,
+                | SaveCommand::LocalShardSize { .. } => {},
+            }
+        }
+        assert_eq!(saved[0].as_str().unwrap(), expected.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_lock_grants_are_judged_at_proposal_time() {
+        // GIVEN - a lease that ran out long ago by the wall clock
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor {
+                cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
+                self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
+            }
+            .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let proposed_at = chrono::Utc::now() - chrono::Duration::seconds(10);
+        let lock = |token: &str, after_ms: i64| {
+            let proposed_at = proposed_at + chrono::Duration::milliseconds(after_ms);
+            let cache_entry = CacheEntry::new("lock", token)
+                .with_expiry(proposed_at + chrono::Duration::seconds(1));
+            let (tx, rx) = oneshot::channel();
+            (CacheCommand::Lock { cache_entry, proposed_at, callback: tx }, rx)
+        };
+
+        // WHEN
+        let mut granted = vec![];
+        for (token, after_ms) in [("1", 0), ("2", 500), ("3", 1500)] {
+            let (cmd, rx) = lock(token, after_ms);
+            cache.send(cmd).await.unwrap();
+            granted.push(rx.await.unwrap());
+        }
+
+        // THEN - the second one came in while the first lease was held, however late it is applied
+        assert_eq!(granted, vec![true, false, true]);
+    }
+
+    #[tokio::test]
+    async fn test_expired_locks_are_reported_with_their_token() {
+        // GIVEN
+        let (cache, rx) = tokio::sync::mpsc::channel(100);
+        let hwm: Arc<AtomicU64> = Arc::new(0.into());
+        tokio::spawn(
+            CacheActor {
+                cache: LruCache::new(1000),
+                compression: ValueCompression::default(),
+                self_handler: CacheCommandSender(cache.clone()),
+                locks: Default::default(),
+                staged_batch: None,
+            }
+            .handle(rx, ReadQueue::new(hwm.clone())),
+        );
+        let now = chrono::Utc::now();
+        for (key, token, lease) in [("expired", "1", 1), ("held", "2", 60), ("overwritten", "3", 1)]
+        {
+            let cache_entry =
+                CacheEntry::new(key, token).with_expiry(now + chrono::Duration::seconds(lease));
+            let (tx, rx) = oneshot::channel();
+            cache
+                .send(CacheCommand::Lock { cache_entry, proposed_at: now, callback: tx })
+                .await
+                .unwrap();
+            assert!(rx.await.unwrap());
+        }
+        let cache_entry =
+            CacheEntry::new("overwritten", "value").with_expiry(now + chrono::Duration::seconds(1));
+        cache.send(CacheCommand::Set { cache_entry }).await.unwrap();
+
+        // WHEN
+        let (tx, rx) = oneshot::channel();
+        let later = now + chrono::Duration::seconds(2);
+        cache.send(CacheCommand::ExpiredLocks { now: later, callback: tx }).await.unwrap();
+
+        // THEN - only the lock still holding its token is released
+        assert_eq!(rx.await.unwrap(), vec![(Bytes::from("expired"), 1)]);
+    }
 }
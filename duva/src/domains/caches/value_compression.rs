@@ -0,0 +1,179 @@
+use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
+use crate::domains::compression::Compression;
+use bytes::Bytes;
+use std::fmt::Display;
+
+/// Keeps string values past `threshold` bytes compressed while they sit in a shard, trading CPU
+/// for memory.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ValueCompression {
+    codec: Compression,
+    threshold: usize,
+    // * Values past the threshold kept as they are because they did not shrink
+    skipped: u64,
+}
+
+impl ValueCompression {
+    pub(crate) fn new(codec: Compression, threshold: usize) -> Self {
+        Self { codec, threshold, skipped: 0 }
+    }
+
+    /// Compresses a string value past the threshold. Values that would not shrink, such as
+    /// already compressed data, are stored as they are.
+    pub(crate) fn compress(&mut self, value: CacheValue) -> CacheValue {
+        let TypedValue::String(raw) = &value.value else { return value };
+        if !self.codec.is_enabled() || raw.len() <= self.threshold {
+            return value;
+        }
+        let compressed = self.codec.compress(raw);
+        if compressed.len() >= raw.len() {
+            self.skipped += 1;
+            return value;
+        }
+        CacheValue {
+            compressed: Some((self.codec, raw.len())),
+            value: TypedValue::String(Bytes::from(compressed)),
+            ..value
+        }
+    }
+
+    /// The value as it was stored, whichever codec compressed it.
+    pub(crate) fn decompress(value: &CacheValue) -> CacheValue {
+        let (Some((codec, _)), TypedValue::String(compressed)) = (value.compressed, &value.value)
+        else {
+            return value.clone();
+        };
+        // * Blocks compressed in memory by the shard itself are never corrupt
+        let raw = codec.decompress(compressed).unwrap();
+        CacheValue {
+            value: TypedValue::String(Bytes::from(raw)),
+            compressed: None,
+            ..value.clone()
+        }
+    }
+
+    pub(crate) fn stats<'a>(
+        &self,
+        values: impl Iterator<Item = &'a CacheValue>,
+    ) -> CompressionStats {
+        let mut stats = CompressionStats {
+            codec: self.codec,
+            threshold: self.threshold,
+            skipped: self.skipped,
+            ..Default::default()
+        };
+        for value in values {
+            if let Some((_, raw_len)) = value.compressed {
+                stats.keys += 1;
+                stats.raw_bytes += raw_len;
+                stats.stored_bytes += value.memory_usage();
+            }
+        }
+        stats
+    }
+}
+
+/// How much compressing values saves across the shards, reported by `MEMORY STATS`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CompressionStats {
+    codec: Compression,
+    threshold: usize,
+    keys: usize,
+    raw_bytes: usize,
+    stored_bytes: usize,
+    skipped: u64,
+}
+
+impl CompressionStats {
+    /// Adds up the stats of another shard. Every shard compresses with the same settings.
+    pub(crate) fn merge(mut self, other: Self) -> Self {
+        self.codec = other.codec;
+        self.threshold = other.threshold;
+        self.keys += other.keys;
+        self.raw_bytes += other.raw_bytes;
+        self.stored_bytes += other.stored_bytes;
+        self.skipped += other.skipped;
+        self
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.stored_bytes == 0 { 1.0 } else { self.raw_bytes as f64 / self.stored_bytes as f64 }
+    }
+}
+
+impl Display for CompressionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "value_compression:{}\r\nvalue_compression_threshold:{}\r\ncompressed_keys:{}\r\ncompressed_raw_bytes:{}\r\ncompressed_stored_bytes:{}\r\ncompression_ratio:{:.2}\r\nincompressible_values:{}",
+            self.codec,
+            self.threshold,
+            self.keys,
+            self.raw_bytes,
+            self.stored_bytes,
+            self.ratio(),
+            self.skipped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn test_compresses_strings_past_threshold() {
+        let raw = "duva".repeat(100);
+        for codec in [Compression::Lz4, Compression::Zstd] {
+            let mut compression = ValueCompression::new(codec, 100);
+
+            let stored = compression.compress(CacheValue::new(raw.as_str()));
+
+            assert_eq!(stored.compressed, Some((codec, raw.len())));
+            assert!(stored.memory_usage() < raw.len());
+            assert_eq!(ValueCompression::decompress(&stored), CacheValue::new(raw.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_leaves_small_lists_and_disabled_values_as_they_are() {
+        let mut compression = ValueCompression::new(Compression::Lz4, 100);
+        let small = CacheValue::new("duva".repeat(25).as_str());
+        assert_eq!(compression.compress(small.clone()), small);
+        let list = CacheValue::new(vec!["duva"; 100]);
+        assert_eq!(compression.compress(list.clone()), list);
+
+        let large = CacheValue::new("duva".repeat(100).as_str());
+        assert_eq!(ValueCompression::default().compress(large.clone()), large);
+    }
+
+    #[test]
+    fn test_skips_incompressible_values() {
+        let mut raw = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut raw);
+        let value = CacheValue::new(TypedValue::String(Bytes::from(raw)));
+        let mut compression = ValueCompression::new(Compression::Zstd, 100);
+
+        assert_eq!(compression.compress(value.clone()), value);
+        assert_eq!(compression.stats([&value].into_iter()).skipped, 1);
+    }
+
+    #[test]
+    fn test_stats_report_compression_ratio() {
+        let mut compression = ValueCompression::new(Compression::Lz4, 100);
+        let values = [
+            compression.compress(CacheValue::new("a".repeat(1000).as_str())),
+            compression.compress(CacheValue::new("small")),
+        ];
+
+        let stats = compression.stats(values.iter());
+
+        assert_eq!(stats.clone().merge(stats.clone()).keys, 2);
+        assert_eq!(stats.keys, 1);
+        assert_eq!(stats.raw_bytes, 1000);
+        assert_eq!(stats.stored_bytes, values[0].memory_usage());
+        assert!(stats.to_string().contains("value_compression:lz4\r\n"));
+        assert!(stats.ratio() > 10.0);
+    }
+}
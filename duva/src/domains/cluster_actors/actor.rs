@@ -2,109 +2,288 @@ use super::ClusterCommand;
 use super::ConsensusClientResponse;
 use super::ConsensusRequest;
 use super::LazyOption;
+use super::consensus::append_budget::AppendEntriesBudget;
+use super::consensus::applied::AppliedIndex;
+use super::consensus::compaction::LogCompaction;
 use super::consensus::election::ElectionState;
-use super::hash_ring::HashRing;
+use super::consensus::election::{HandOffHold, LeadershipTransfer};
+use super::consensus::group_commit::GroupCommit;
+use super::consensus::group_commit::GroupCommitAction;
+use super::consensus::hard_state::HardState;
+use super::consensus::hard_state::HardStateStore;
+use super::consensus::min_replicas::MinReplicas;
+use super::consensus::pending_writes::PendingWriteLimit;
+use super::consensus::pending_writes::PendingWriteStats;
+use super::consensus::read_index::ReadIndexQueue;
+use super::consensus::replica_wait::ReplicaWaitQueue;
+use super::consensus::report::{ConsensusReport, InFlightEntry, PeerProgress};
+use super::consensus::snapshot_stream::{SNAPSHOT_CHUNK_SIZE, SnapshotAssembler, split_snapshot};
+use super::hash_ring::{DEFAULT_WEIGHT, HashRing};
 pub mod client_sessions;
 pub(crate) mod heartbeat_scheduler;
+pub(crate) mod topology_writer;
+use super::replication::KnownLeader;
 use super::replication::ReplicationId;
+use super::replication::ReplicationLinks;
 use super::replication::ReplicationRole;
 use super::replication::ReplicationState;
 use super::replication::time_in_secs;
 use super::*;
+use crate::domains::IoError;
 use crate::domains::QueryIO;
 use crate::domains::caches::cache_manager::CacheManager;
 use crate::domains::cluster_actors::consensus::election::ElectionVoting;
+use crate::domains::cluster_actors::forwarding::ForwardId;
+use crate::domains::cluster_actors::forwarding::ForwardedOp;
+use crate::domains::cluster_actors::forwarding::ForwardedReply;
 use crate::domains::cluster_actors::hash_ring::BatchId;
+use crate::domains::cluster_actors::hash_ring::MigratingKeys;
 use crate::domains::cluster_actors::hash_ring::MigrationBatch;
+use crate::domains::cluster_actors::hash_ring::MigrationProgress;
+use crate::domains::cluster_actors::hash_ring::MigrationTask;
+use crate::domains::cluster_actors::hash_ring::MigrationThrottle;
 use crate::domains::cluster_actors::hash_ring::PendingMigrationBatch;
+use crate::domains::cluster_actors::hash_ring::encoded_len;
+use crate::domains::cluster_actors::history::ObservedTopology;
+use crate::domains::cluster_actors::history::TopologyHistory;
+use crate::domains::cluster_actors::mailbox::{Mailbox, QueueClass, QueueLimits};
+use crate::domains::cluster_actors::topology::RoutingTable;
+use crate::domains::cluster_actors::topology::Shard;
 use crate::domains::cluster_actors::topology::Topology;
+use crate::domains::cluster_actors::transactions::TXN_RESOLVE_INTERVAL;
+use crate::domains::cluster_actors::transactions::Transactions;
+use crate::domains::cluster_actors::transactions::TxnId;
+use crate::domains::cluster_actors::transactions::TxnOutcome;
+use crate::domains::encryption::KeyRing;
+use crate::domains::operation_logs::WriteOperation;
 use crate::domains::operation_logs::WriteRequest;
+use crate::domains::operation_logs::interfaces::FsyncPolicy;
 use crate::domains::operation_logs::interfaces::TWriteAheadLog;
+use crate::domains::operation_logs::logger::LogSnapshot;
 use crate::domains::operation_logs::logger::ReplicatedLogs;
 use crate::domains::peers::command::BannedPeer;
 use crate::domains::peers::command::ElectionVote;
+use crate::domains::peers::command::ForwardRequest;
+use crate::domains::peers::command::ForwardResponse;
 use crate::domains::peers::command::HeartBeat;
+use crate::domains::peers::command::InstallSnapshot;
 use crate::domains::peers::command::MigrateBatch;
 use crate::domains::peers::command::MigrationBatchAck;
 use crate::domains::peers::command::RejectionReason;
 use crate::domains::peers::command::ReplicationAck;
 use crate::domains::peers::command::RequestVote;
+use crate::domains::peers::command::TimeoutNow;
 use crate::domains::peers::connections::inbound::stream::InboundStream;
 use crate::domains::peers::connections::outbound::stream::OutboundStream;
+use crate::domains::peers::failure_detector::DEFAULT_PHI_THRESHOLD;
+use crate::domains::peers::gossip::{GossipViews, nodes_digest};
+use crate::domains::peers::peer::MAX_INFLIGHT_ENTRIES;
 use crate::domains::peers::peer::PeerState;
+use crate::domains::peers::peer::{SharedMessage, broadcast, frame_each, send_frames};
+use crate::domains::peers::reconnect::{LinkState, RECONNECT_BACKOFF_BASE, Reconnects};
+use crate::domains::saves::actor::SaveTarget;
+use crate::domains::saves::snapshot::Metadata;
+use crate::domains::saves::snapshot::snapshot_loader::SnapshotLoader;
+use crate::domains::telemetry::{TraceContext, joined_span};
 use crate::err;
 use crate::res_err;
 use crate::types::Callback;
 use crate::types::ConnectionStream;
-use client_sessions::ClientSessions;
+use client_sessions::{ClientSessionStats, ClientSessions};
 
-use heartbeat_scheduler::HeartBeatScheduler;
+use heartbeat_scheduler::{HeartBeatScheduler, RaftTimings};
+use topology_writer::TopologyWriter;
 
+use bytes::Bytes;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::fmt::Debug;
-use std::fs::File;
-use std::io::Seek;
-use std::io::Write;
 use std::iter;
+use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
+use tracing::Instrument;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::instrument;
 use tracing::warn;
+use uuid::Uuid;
 #[cfg(test)]
 mod tests;
 
+// * How often the background flusher syncs the log under `FsyncPolicy::EverySec`
+const LOG_SYNC_INTERVAL: u64 = 1000;
+// * How often idle client sessions are looked for
+const CLIENT_SESSION_EXPIRY_INTERVAL: u64 = 1000;
+// * How long compaction waits for committed entries handed out to be applied
+const APPLY_SETTLE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct ClusterActor<T> {
     pub(crate) members: BTreeMap<PeerIdentifier, Peer>,
+    // * Members lost to failure detection or bans, see `Reconnects`
+    pub(crate) reconnects: Reconnects,
+    // * Node lists peers last gossiped in full, which their digest-only heartbeats stand for
+    pub(crate) gossip_views: GossipViews,
     pub(crate) replication: ReplicationState,
     pub(crate) node_timeout: u128,
+    // * Suspicion level above which an idle peer is considered failed
+    pub(crate) phi_threshold: f64,
     pub(crate) consensus_tracker: LogConsensusTracker,
     pub(crate) receiver: tokio::sync::mpsc::Receiver<ClusterCommand>,
     pub(crate) self_handler: ClusterCommandHandler,
     pub(crate) heartbeat_scheduler: HeartBeatScheduler,
-    pub(crate) topology_writer: std::fs::File,
+    pub(crate) topology_writer: TopologyWriter,
+    // * Keys the topology file is sealed with
+    pub(crate) topology_encryption: KeyRing,
     pub(crate) node_change_broadcast: tokio::sync::broadcast::Sender<Topology>,
+    // * Key ownership clients route reads with, kept in step with the hash ring
+    pub(crate) routing: tokio::sync::watch::Sender<RoutingTable>,
+    // * Flipped once this node has left the cluster, so that it stops serving clients
+    pub(crate) shutdown: tokio::sync::watch::Sender<bool>,
 
     // * Pending requests are used to store requests that are received while the actor is in the process of election/cluster rebalancing.
     // * These requests will be processed once the actor is back to a stable state.
     pub(crate) client_sessions: ClientSessions,
+    // * How long a session is remembered after its last write, None for as long as the node runs
+    pub(crate) client_session_ttl: Option<std::time::Duration>,
+    pub(crate) client_session_stats: ClientSessionStats,
     pub(crate) logger: ReplicatedLogs<T>,
+    pub(crate) log_compaction: LogCompaction,
+    // * Committed entries handed out to be applied outside of the actor, as a leader does
+    pub(crate) applied: AppliedIndex,
+    // * Snapshot being streamed from the leader, reassembled chunk by chunk
+    pub(crate) snapshot_assembler: SnapshotAssembler,
+    pub(crate) min_replicas: MinReplicas,
+    pub(crate) append_entries_budget: AppendEntriesBudget,
+    pub(crate) hard_state: HardStateStore,
     pub(crate) hash_ring: HashRing,
     pub(crate) pending_requests: Option<VecDeque<ConsensusRequest>>,
+    pub(crate) pending_write_limit: PendingWriteLimit,
+    pub(crate) group_commit: GroupCommit,
+    pub(crate) pending_write_stats: PendingWriteStats,
     pub(crate) pending_migrations: Option<HashMap<BatchId, PendingMigrationBatch>>,
+    pub(crate) migrating_keys: MigratingKeys,
+    // * Ring to adopt once the migrations it calls for are done
+    pub(crate) pending_ring: Option<HashRing>,
+    pub(crate) migration_throttle: MigrationThrottle,
+    pub(crate) migration_progress: MigrationProgress,
+    pub(crate) leadership_transfer: Option<LeadershipTransfer>,
+    pub(crate) hand_off_hold: Option<HandOffHold>,
+    // * Set by `CLUSTER LEAVE` while the keys of this node are drained to the other partitions
+    pub(crate) departure: Option<Callback<anyhow::Result<()>>>,
+    pub(crate) pending_reads: ReadIndexQueue,
+    pub(crate) known_leader: Option<KnownLeader>,
+    // * Election timeouts skipped so far in favour of higher priority replicas
+    pub(crate) deferred_elections: usize,
+    pub(crate) replica_waits: ReplicaWaitQueue,
+    // * Parts of multi-key commands sent to other shard leaders, waiting for their replies
+    pub(crate) pending_forwards: HashMap<ForwardId, Callback<anyhow::Result<ForwardedReply>>>,
+    pub(crate) transactions: Transactions,
+    pub(crate) topology_history: TopologyHistory,
 }
 
 #[derive(Debug, Clone)]
-pub struct ClusterCommandHandler(pub(super) tokio::sync::mpsc::Sender<ClusterCommand>);
+pub struct ClusterCommandHandler {
+    pub(super) tx: tokio::sync::mpsc::Sender<ClusterCommand>,
+    pub(super) mailbox: Arc<Mailbox>,
+}
 impl ClusterCommandHandler {
+    pub(crate) fn new(tx: tokio::sync::mpsc::Sender<ClusterCommand>, mailbox: Mailbox) -> Self {
+        Self { tx, mailbox: Arc::new(mailbox) }
+    }
+
     pub(crate) async fn send(
         &self,
         cmd: impl Into<ClusterCommand>,
     ) -> Result<(), tokio::sync::mpsc::error::SendError<ClusterCommand>> {
-        self.0.send(cmd.into()).await
+        let cmd = cmd.into();
+        let class = QueueClass::of_command(&cmd);
+        if let Some(class) = class {
+            self.mailbox.enqueue(class);
+        }
+        self.tx.send(cmd).await.inspect_err(|_| {
+            if let Some(class) = class {
+                self.mailbox.release(class);
+            }
+        })
+    }
+
+    /// Queues a request from a client, turning it away with `BUSY` when its class is at its limit.
+    pub(crate) async fn send_client(&self, msg: ClientMessage) -> anyhow::Result<()> {
+        let class = QueueClass::of(&msg);
+        self.mailbox.admit(class)?;
+        if let Err(err) = self.tx.send(msg.into()).await {
+            self.mailbox.release(class);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn queue_stats(&self) -> Vec<String> {
+        self.mailbox.vectorize(self.tx.max_capacity() - self.tx.capacity())
     }
 }
 
 impl<T: TWriteAheadLog> ClusterActor<T> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn run(
         node_timeout: u128,
-        topology_writer: std::fs::File,
+        topology_writer: TopologyWriter,
         heartbeat_interval: u64,
+        raft_timings: RaftTimings,
         init_replication: ReplicationState,
         cache_manager: CacheManager,
-        wal: T,
+        logs: ReplicatedLogs<T>,
+        log_compaction: LogCompaction,
+        hard_state: HardStateStore,
+        min_replicas: MinReplicas,
+        append_entries_budget: AppendEntriesBudget,
+        phi_threshold: f64,
+        vnode_num: u16,
+        migration_throttle: MigrationThrottle,
+        pending_write_limit: PendingWriteLimit,
+        group_commit: GroupCommit,
+        topology_encryption: KeyRing,
+        queue_limits: QueueLimits,
+        client_sessions: ClientSessions,
+        client_session_ttl: u64,
+        transactions: Transactions,
+        stored_peers: Vec<PeerState>,
     ) -> ClusterCommandHandler {
-        let cluster_actor = ClusterActor::new(
+        let mut cluster_actor = ClusterActor::new(
             node_timeout,
             init_replication,
             heartbeat_interval,
             topology_writer,
-            wal,
+            logs.target,
+            hard_state,
+            queue_limits,
         );
+        cluster_actor.heartbeat_scheduler.retime(raft_timings);
+        log_compaction.schedule(cluster_actor.self_handler.tx.clone());
+        cluster_actor.schedule_log_sync();
+        cluster_actor.schedule_reconnects();
+        cluster_actor.schedule_client_session_expiry();
+        cluster_actor.rejoin(stored_peers);
+        cluster_actor.log_compaction = log_compaction;
+        cluster_actor.logger.snapshot = logs.snapshot;
+        cluster_actor.min_replicas = min_replicas;
+        cluster_actor.append_entries_budget = append_entries_budget;
+        cluster_actor.phi_threshold = phi_threshold;
+        cluster_actor.hash_ring = cluster_actor.hash_ring.with_vnode_num(vnode_num);
+        cluster_actor.migration_throttle = migration_throttle;
+        cluster_actor.pending_write_limit = pending_write_limit;
+        cluster_actor.group_commit = group_commit;
+        cluster_actor.topology_encryption = topology_encryption;
+        cluster_actor.client_sessions = client_sessions;
+        cluster_actor.transactions = transactions;
+        cluster_actor.set_client_session_ttl(client_session_ttl);
+        cluster_actor.publish_routing();
+        // * Peers stored by an earlier run stay in the file until they are back or given up on
+        cluster_actor.snapshot_topology();
         let actor_handler = cluster_actor.self_handler.clone();
         tokio::spawn(cluster_actor.handle(cache_manager));
         actor_handler
@@ -114,10 +293,22 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         node_timeout: u128,
         init_repl_state: ReplicationState,
         heartbeat_interval_in_mills: u64,
-        topology_writer: File,
+        topology_writer: TopologyWriter,
         log_writer: T,
+        mut hard_state: HardStateStore,
+        queue_limits: QueueLimits,
     ) -> Self {
-        let (self_handler, receiver) = tokio::sync::mpsc::channel(100);
+        // * Restore term and vote before taking part in any election, so a restarted node never votes twice in a term
+        let mut init_repl_state = init_repl_state;
+        let saved = hard_state.load();
+        init_repl_state.term = init_repl_state.term.max(saved.term);
+        if let ElectionState::Follower { voted_for } = &mut init_repl_state.election_state
+            && saved.term == init_repl_state.term
+        {
+            *voted_for = saved.voted_for;
+        }
+
+        let (self_handler, receiver) = tokio::sync::mpsc::channel(queue_limits.capacity());
         let heartbeat_scheduler = HeartBeatScheduler::run(
             self_handler.clone(),
             init_repl_state.is_leader(),
@@ -125,9 +316,10 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         );
 
         let (tx, _) = tokio::sync::broadcast::channel::<Topology>(100);
-        let hash_ring = HashRing::default().add_partitions(vec![(
+        let hash_ring = HashRing::default().add_weighted_partitions(vec![(
             init_repl_state.replid.clone(),
             init_repl_state.self_identifier(),
+            init_repl_state.weight,
         )]);
 
         Self {
@@ -136,20 +328,51 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                 init_repl_state.hwm.load(Ordering::Acquire),
                 init_repl_state.term,
             ),
+            log_compaction: LogCompaction::default(),
+            applied: AppliedIndex::default(),
+            reconnects: Reconnects::default(),
+            gossip_views: GossipViews::default(),
+            snapshot_assembler: SnapshotAssembler::default(),
+            min_replicas: MinReplicas::default(),
+            append_entries_budget: AppendEntriesBudget::default(),
+            hard_state,
             heartbeat_scheduler,
             replication: init_repl_state,
             node_timeout,
+            phi_threshold: DEFAULT_PHI_THRESHOLD,
             receiver,
-            self_handler: ClusterCommandHandler(self_handler),
+            self_handler: ClusterCommandHandler::new(self_handler, Mailbox::new(queue_limits)),
             topology_writer,
+            topology_encryption: KeyRing::default(),
             node_change_broadcast: tx,
+            routing: tokio::sync::watch::channel(RoutingTable::default()).0,
+            shutdown: tokio::sync::watch::channel(false).0,
             hash_ring,
             members: BTreeMap::new(),
             consensus_tracker: LogConsensusTracker::default(),
             client_sessions: ClientSessions::default(),
+            client_session_ttl: None,
+            client_session_stats: ClientSessionStats::default(),
 
             pending_requests: None,
+            pending_write_limit: PendingWriteLimit::default(),
+            group_commit: GroupCommit::default(),
+            pending_write_stats: PendingWriteStats::default(),
             pending_migrations: None,
+            migrating_keys: MigratingKeys::default(),
+            pending_ring: None,
+            migration_throttle: MigrationThrottle::default(),
+            migration_progress: MigrationProgress::default(),
+            leadership_transfer: None,
+            hand_off_hold: None,
+            departure: None,
+            pending_reads: ReadIndexQueue::default(),
+            known_leader: None,
+            deferred_elections: 0,
+            replica_waits: ReplicaWaitQueue::default(),
+            pending_forwards: HashMap::new(),
+            transactions: Transactions::default(),
+            topology_history: TopologyHistory::default(),
         }
     }
 
@@ -160,7 +383,8 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         optional_callback: Option<Callback<anyhow::Result<()>>>,
     ) {
         let peer_id = peer.id().clone();
-        self.replication.banlist.remove(&peer_id);
+        self.replication.banlist.forget(&peer_id);
+        self.reconnects.forget(&peer_id);
 
         // If the map did have this key present, the value is updated, and the old
         // value is returned. The key is not updated,
@@ -169,7 +393,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         }
 
         self.broadcast_topology_change();
-        let _ = self.snapshot_topology().await;
+        self.snapshot_topology();
 
         let peer = self.members.get_mut(&peer_id).unwrap();
         if peer.is_follower(&self.replication.replid) && self.replication.is_leader() {
@@ -186,6 +410,33 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         }
     }
 
+    /// Takes up the topology an earlier run left behind: its peers are retried with backoff until
+    /// they are back, and the ring is laid out over the shard leaders it names until the cluster
+    /// hands over the current one.
+    pub(crate) fn rejoin(&mut self, stored_peers: Vec<PeerState>) {
+        let self_id = self.replication.self_identifier();
+        let leaders = stored_peers
+            .iter()
+            .filter(|peer| peer.role == ReplicationRole::Leader)
+            .map(|peer| {
+                let weight =
+                    if *peer.id() == self_id { self.replication.weight } else { DEFAULT_WEIGHT };
+                (peer.replid.clone(), peer.id().clone(), weight)
+            })
+            .collect::<Vec<_>>();
+        // * Left unversioned, so that any ring the cluster gossips replaces it
+        if !leaders.is_empty() {
+            self.hash_ring = HashRing::default().add_weighted_partitions(leaders);
+        }
+
+        let now = Instant::now();
+        for peer in stored_peers {
+            if *peer.id() != self_id && !self.replication.in_ban_list(peer.id()) {
+                self.reconnects.track(peer, now);
+            }
+        }
+    }
+
     #[instrument(skip(self, optional_callback))]
     pub(crate) async fn connect_to_server(
         &mut self,
@@ -216,8 +467,10 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
     }
 
     pub(crate) fn accept_inbound_stream(&mut self, peer_stream: ConnectionStream) {
-        let inbound_stream = InboundStream::new(peer_stream.0, self.replication.clone());
-        tokio::spawn(inbound_stream.add_peer(self.self_handler.clone()));
+        let (replication, handler) = (self.replication.clone(), self.self_handler.clone());
+        tokio::spawn(async move {
+            InboundStream::accept(peer_stream.0, replication).await?.add_peer(handler).await
+        });
     }
 
     #[instrument(level = tracing::Level::INFO, skip(self,replid))]
@@ -227,6 +480,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
 
     pub(crate) fn set_repl_id(&mut self, replid: ReplicationId) {
         self.replication.replid = replid;
+        self.publish_routing();
         // Update hash ring with leader's replication ID and identifier
     }
 
@@ -242,16 +496,50 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         self.send_heartbeat(hb).await;
     }
 
+    /// Removes a peer and bans it for the configured duration, or for good when `permanent` is set.
     pub(crate) async fn forget_peer(
         &mut self,
         peer_addr: PeerIdentifier,
+        permanent: bool,
     ) -> anyhow::Result<Option<()>> {
-        let res = self.remove_peer(&peer_addr).await;
-        self.replication.banlist.insert(BannedPeer { p_id: peer_addr, ban_time: time_in_secs()? });
+        let res = self.remove_peer(&peer_addr).await.map(|state| {
+            self.reconnects.track(state, Instant::now());
+        });
+        self.reconnects.ban(&peer_addr);
+        match permanent {
+            | true => self.replication.banlist.ban_permanently(peer_addr, time_in_secs()?),
+            | false => self.replication.banlist.ban(peer_addr, time_in_secs()?),
+        }
+        self.snapshot_topology();
 
         Ok(res)
     }
 
+    /// Bans in force, each with the seconds left of it or None when permanent.
+    pub(crate) fn banned_peers(&self) -> Vec<(PeerIdentifier, Option<u64>)> {
+        let now = time_in_secs().unwrap_or_default();
+        self.replication
+            .banlist
+            .active(now)
+            .map(|banned| {
+                let left = banned.duration.map(|d| (banned.ban_time + d).saturating_sub(now));
+                (banned.p_id.clone(), left)
+            })
+            .collect()
+    }
+
+    /// Lifts the ban of `peer`, or of every banned peer, returning how many were lifted. Peers that
+    /// were lifted are let back in and, like any other, join again through gossip or `CLUSTER MEET`.
+    pub(crate) fn clear_bans(&mut self, peer: Option<PeerIdentifier>) -> usize {
+        let lifted =
+            self.replication.banlist.lift(peer.as_ref(), time_in_secs().unwrap_or_default());
+        if lifted > 0 {
+            self.reconnects.lift_bans(|peer_id| self.replication.in_ban_list(peer_id));
+            self.snapshot_topology();
+        }
+        lifted
+    }
+
     #[instrument(level = tracing::Level::DEBUG, skip(self, heartbeat,cache_manager), fields(peer_id = %heartbeat.from))]
     pub(crate) async fn receive_cluster_heartbeat(
         &mut self,
@@ -263,28 +551,80 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         }
         self.apply_banlist(std::mem::take(&mut heartbeat.ban_list)).await;
-        self.update_cluster_members(&heartbeat.from, heartbeat.hwm, &heartbeat.cluster_nodes).await;
-        self.join_peer_network_if_absent(heartbeat.cluster_nodes).await;
+        self.record_peer_settings(&heartbeat);
+        let cluster_nodes = self.gossip_views.resolve(
+            &heartbeat.from,
+            heartbeat.nodes_digest,
+            std::mem::take(&mut heartbeat.cluster_nodes),
+        );
+        self.update_cluster_members(&heartbeat.from, heartbeat.hwm, &cluster_nodes).await;
+        self.join_peer_network_if_absent(cluster_nodes).await;
         self.gossip(heartbeat.hop_count).await;
         self.maybe_update_hashring(heartbeat.hashring, cache_manager).await;
     }
 
-    pub(crate) async fn leader_req_consensus(&mut self, req: ConsensusRequest) {
+    pub(crate) async fn leader_req_consensus(&mut self, mut req: ConsensusRequest) {
+        if let Some(hold) = self.hand_off_hold.as_mut() {
+            hold.reqs.push_back(req);
+            return;
+        }
         if let Some(pending_requests) = self.pending_requests.as_mut() {
+            // * Keys already committed on the target are not held back behind the rest of the migration
+            if let Some(target) = self.migrating_keys.ask_target(&req.request.all_keys()) {
+                let _ = req.callback.send(format!("ASK {target}").into());
+                return;
+            }
+            if self.pending_write_limit.is_full(pending_requests.len()) {
+                self.pending_write_stats.rejected += 1;
+                let _ = req.callback.send("TRYAGAIN too many writes held back".to_string().into());
+                return;
+            }
+            // * Requeued writes keep the deadline they were first given
+            if req.deadline.is_none() {
+                let timeout = self.pending_write_limit.timeout();
+                req.deadline = Some(Instant::now() + timeout);
+                let handler = self.self_handler.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(timeout).await;
+                    let _ = handler.send(SchedulerMessage::ExpirePendingRequests).await;
+                });
+            }
             pending_requests.push_back(req);
             return;
         }
         if self.client_sessions.is_processed(&req.session_req) {
+            self.client_session_stats.duplicates_suppressed += 1;
             // mapping between early returned values to client result
-            let key = req.request.all_keys().into_iter().map(String::from).collect();
+            let key = req.request.all_keys().into_iter().map(Bytes::copy_from_slice).collect();
             let _ = req.callback.send(ConsensusClientResponse::AlreadyProcessed {
                 key,
                 index: self.logger.last_log_index,
             });
             return;
         };
+        // * A retry of a write that timed out is answered once the entry it left behind is settled
+        if self.consensus_tracker.is_pending(&req.session_req) {
+            let _ = req.callback.send("TRYAGAIN write is still waiting for a majority".into());
+            return;
+        }
+
+        if let Some(holder) = self.transactions.conflict(&req.request) {
+            let _ = req
+                .callback
+                .send(format!("TRYAGAIN keys locked by transaction {}", holder.id).into());
+            return;
+        }
+        if let Some(response) = self.settled_transaction(&req.request) {
+            let _ = req.callback.send(response);
+            return;
+        }
+
+        // * Keyless requests (e.g. lease grants) are owned by the shard that receives them
+        if req.request.all_keys().is_empty() {
+            self.req_consensus(req).await;
+            return;
+        }
 
-        // TODO get_node_for_keys need to be revisited as currently it takes only the first key
         match self.hash_ring.get_node_for_keys(&req.request.all_keys()) {
             | Ok(replid) if replid == self.replication.replid => {
                 self.req_consensus(req).await;
@@ -300,30 +640,147 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         }
     }
 
+    /// Answers transaction records that have nothing left to do: the outcome of a transaction that
+    /// is no longer prepared here, a decision contradicting the one already recorded, or the end of
+    /// a transaction with none.
+    fn settled_transaction(&self, request: &WriteRequest) -> Option<ConsensusClientResponse> {
+        let already_processed = ConsensusClientResponse::AlreadyProcessed {
+            key: vec![],
+            index: self.logger.last_log_index,
+        };
+        match request {
+            | WriteRequest::TxnCommit { txn_id, .. } | WriteRequest::TxnAbort { txn_id }
+                if self.transactions.prepared(txn_id).is_none() =>
+            {
+                Some(already_processed)
+            },
+            | WriteRequest::TxnDecision { txn_id, outcome } => {
+                match self.transactions.proposed_decision(txn_id)? {
+                    | decided if decided == *outcome => Some(already_processed),
+                    | _ => Some(format!("transaction {} was already aborted", txn_id.id).into()),
+                }
+            },
+            | WriteRequest::TxnEnd { txn_id }
+                if self.transactions.proposed_decision(txn_id).is_none() =>
+            {
+                Some(already_processed)
+            },
+            | _ => None,
+        }
+    }
+
     async fn req_consensus(&mut self, req: ConsensusRequest) {
         if !self.replication.is_leader() {
-            let _ = req.callback.send("Write given to follower".into());
+            self.reject_write_on_follower(req);
             return;
         }
 
-        // * Check if the request has already been processed
-        if let Err(err) = self.logger.write_single_entry(
-            &req.request,
-            self.replication.term,
-            req.session_req.clone(),
-        ) {
+        // * The no-op appended on election must go through, as it is what lets the new leader commit
+        if req.request != WriteRequest::NoOp
+            && let Err(err) = self.check_min_replicas()
+        {
             let _ = req.callback.send(ConsensusClientResponse::Err(err.to_string()));
             return;
+        }
+
+        match self.group_commit.push(req) {
+            | GroupCommitAction::Commit => self.commit_group().await,
+            | GroupCommitAction::ScheduleCommit => {
+                let handler = self.self_handler.clone();
+                let window = self.group_commit.window();
+                tokio::spawn(async move {
+                    tokio::time::sleep(window).await;
+                    let _ = handler.send(SchedulerMessage::CommitGroup).await;
+                });
+            },
+            | GroupCommitAction::Wait => {},
+        }
+    }
+
+    fn reject_write_on_follower(&self, req: ConsensusRequest) {
+        // * Only writes are redirected; replicas keep serving reads themselves
+        let response = match self.known_leader.as_ref() {
+            | Some(leader) => format!("MOVED {}", leader.id),
+            | None => "Write given to follower".to_string(),
+        };
+        let _ = req.callback.send(response.into());
+    }
+
+    /// Appends the grouped writes with a single write to the log and replicates them in one round,
+    /// so that their callbacks are resolved together.
+    pub(crate) async fn commit_group(&mut self) {
+        let reqs = self.group_commit.take();
+        if reqs.is_empty() {
+            return;
+        }
+        // * The group is replicated in one round, traced under the first write that was traced
+        let span = joined_span!("commit_group", reqs.iter().find_map(|req| req.trace));
+        self.append_group(reqs).instrument(span).await;
+    }
+
+    async fn append_group(&mut self, reqs: Vec<ConsensusRequest>) {
+        // * Leadership may have been lost while the group was waiting
+        if !self.replication.is_leader() {
+            reqs.into_iter().for_each(|req| self.reject_write_on_follower(req));
+            return;
+        }
+
+        // * Transaction records are checked in log order, so that a decision queued along with a
+        // * contradicting one is rejected rather than appended after it
+        let last_index = self.logger.last_log_index;
+        let mut appended = Vec::with_capacity(reqs.len());
+        for req in reqs {
+            match self.settled_transaction(&req.request) {
+                | Some(response) => {
+                    let _ = req.callback.send(response);
+                },
+                | None => {
+                    self.track_transaction(&req.request, last_index + 1 + appended.len() as u64);
+                    appended.push(req);
+                },
+            }
+        }
+        let reqs = appended;
+        if reqs.is_empty() {
+            return;
+        }
+
+        let first_index = match self.logger.write_entries(
+            reqs.iter().map(|req| (req.request.clone(), req.session_req.clone())),
+            self.replication.term,
+        ) {
+            | Ok(first_index) => first_index,
+            | Err(err) => {
+                self.transactions.truncate_after(last_index);
+                for req in reqs {
+                    let _ = req.callback.send(ConsensusClientResponse::Err(err.to_string()));
+                }
+                return;
+            },
         };
 
         let repl_cnt = self.replicas().count();
         if repl_cnt == 0 {
-            // * If there are no replicas, we can send the response immediately
-            self.replication.hwm.fetch_add(1, Ordering::Relaxed);
-            req.callback.send(ConsensusClientResponse::LogIndex(self.logger.last_log_index)).ok();
+            // * If there are no replicas, we can send the responses immediately
+            self.replication.hwm.fetch_max(self.logger.last_log_index, Ordering::Relaxed);
+            for (req, log_index) in reqs.into_iter().zip(first_index..) {
+                let applying = self.applied.track(log_index);
+                req.callback.send(ConsensusClientResponse::LogIndex(log_index, applying)).ok();
+            }
             return;
         }
-        self.consensus_tracker.add(self.logger.last_log_index, req, repl_cnt);
+        let timeout = self.pending_write_limit.consensus_timeout();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        for (req, log_index) in reqs.into_iter().zip(first_index..) {
+            self.consensus_tracker.add(log_index, req, repl_cnt, deadline);
+        }
+        if let Some(timeout) = timeout {
+            let handler = self.self_handler.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                let _ = handler.send(SchedulerMessage::ExpireConsensusRequests).await;
+            });
+        }
         self.send_rpc_to_replicas().await;
     }
 
@@ -332,9 +789,349 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         if self.replicas().count() == 0 {
             return;
         }
+        if self.step_down_if_quorum_lost().await {
+            return;
+        }
         self.send_rpc_to_replicas().await;
     }
 
+    /// ReadIndex - answers with the commit index once this node has confirmed it is still the leader,
+    /// so that reads served up to that index are linearizable.
+    pub(crate) async fn read_index(&mut self, callback: Callback<anyhow::Result<u64>>) {
+        if !self.replication.is_leader() {
+            let _ = callback.send(res_err!("Read given to follower"));
+            return;
+        }
+        self.pending_reads.push(callback);
+        self.confirm_pending_reads();
+        if !self.pending_reads.is_empty() {
+            // * Starts a heartbeat round right away instead of waiting for the next tick
+            self.send_rpc().await;
+        }
+    }
+
+    pub(crate) fn confirm_pending_reads(&mut self) {
+        if self.pending_reads.is_empty() {
+            return;
+        }
+        if !self.replication.is_leader() {
+            self.pending_reads
+                .fail_all("TRYAGAIN leadership changed before the read was confirmed");
+            return;
+        }
+        // * The commit index is only known to be up to date once an entry of the current term is committed
+        let hwm = self.replication.hwm.load(Ordering::Acquire);
+        if hwm != self.logger.last_log_index
+            && self.logger.read_at(hwm).is_none_or(|op| op.term != self.replication.term)
+        {
+            return;
+        }
+
+        let replicas = self.replicas().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let heartbeat_scheduler = &self.heartbeat_scheduler;
+        self.pending_reads.confirm(hwm, |requested_at| {
+            heartbeat_scheduler.confirms_leadership_since(requested_at, &replicas)
+        });
+    }
+
+    /// WAIT - answers with the number of replicas whose match index reached `index`, once there are
+    /// `numreplicas` of them or `timeout` milliseconds have passed. A timeout of 0 blocks until then.
+    pub(crate) fn wait_for_replicas(
+        &mut self,
+        index: u64,
+        numreplicas: usize,
+        timeout: u64,
+        callback: Callback<anyhow::Result<usize>>,
+    ) {
+        if !self.replication.is_leader() {
+            let _ = callback.send(res_err!("ERR WAIT cannot be used with replica instances"));
+            return;
+        }
+        let deadline = (timeout > 0).then(|| {
+            let handler = self.self_handler.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(timeout)).await;
+                let _ = handler.send(SchedulerMessage::ExpireReplicaWaits).await;
+            });
+            Instant::now() + std::time::Duration::from_millis(timeout)
+        });
+        self.replica_waits.push(index, numreplicas, deadline, callback);
+        self.resolve_replica_waits();
+    }
+
+    pub(crate) fn expire_pending_requests(&mut self) {
+        let Some(pending_requests) = self.pending_requests.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let (expired, held): (VecDeque<_>, VecDeque<_>) = std::mem::take(pending_requests)
+            .into_iter()
+            .partition(|req| req.deadline.is_some_and(|deadline| deadline <= now));
+        *pending_requests = held;
+
+        for req in expired {
+            self.pending_write_stats.timed_out += 1;
+            let _ = req.callback.send("TRYAGAIN write held back for too long".to_string().into());
+        }
+    }
+
+    fn schedule_log_sync(&self) {
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            let mut itv =
+                tokio::time::interval(std::time::Duration::from_millis(LOG_SYNC_INTERVAL));
+            loop {
+                itv.tick().await;
+                if handler.send(SchedulerMessage::SyncLogs).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn schedule_client_session_expiry(&self) {
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            let mut itv = tokio::time::interval(std::time::Duration::from_millis(
+                CLIENT_SESSION_EXPIRY_INTERVAL,
+            ));
+            loop {
+                itv.tick().await;
+                if handler.send(SchedulerMessage::ExpireClientSessions).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Forgets sessions idle for longer than `client_session_ttl`. Each node expires its own copy,
+    /// as sessions are rebuilt from the entries it applies.
+    pub(crate) fn expire_client_sessions(&mut self) {
+        let Some(ttl) = self.client_session_ttl else { return };
+        let expired = self.client_sessions.expire(chrono::Utc::now(), ttl);
+        if expired > 0 {
+            self.client_session_stats.expired += expired as u64;
+            debug!("Expired {expired} idle client sessions");
+        }
+    }
+
+    /// Sets how many milliseconds a session is remembered after its last write, 0 for as long as the
+    /// node runs. Sessions idle for longer than a shortened window go on the next expiry tick.
+    pub(crate) fn set_client_session_ttl(&mut self, ttl: u64) {
+        self.client_session_ttl = (ttl > 0).then(|| std::time::Duration::from_millis(ttl));
+    }
+
+    pub(crate) fn client_session_stats(&self) -> ClientSessionStats {
+        ClientSessionStats {
+            sessions: self.client_sessions.len(),
+            ttl: self.client_session_ttl.map_or(0, |ttl| ttl.as_millis() as u64),
+            ..self.client_session_stats
+        }
+    }
+
+    /// Metadata a snapshot of the applied state is saved with.
+    pub(crate) fn snapshot_metadata(&self) -> Metadata {
+        let log_idx = self.applied.get(self.replication.hwm.load(Ordering::Acquire));
+        Metadata {
+            repl_id: self.replication.replid.clone(),
+            log_idx,
+            log_term: self.term_at(log_idx),
+            sessions: self.client_sessions.clone(),
+            leases: Default::default(),
+            transactions: self.transactions.snapshot(log_idx),
+        }
+    }
+
+    /// Background flush of appends that were left unsynced under `FsyncPolicy::EverySec`.
+    pub(crate) fn sync_logs(&mut self) {
+        if self.logger.target.fsync_policy() != FsyncPolicy::EverySec {
+            return;
+        }
+        if let Err(err) = self.logger.target.fsync() {
+            error!("failed to sync logs: {err}");
+        }
+    }
+
+    /// Syncs appends whatever the fsync policy and writes the topology out, so that a restart
+    /// picks up from where the node stopped.
+    pub(crate) async fn flush(&mut self) -> anyhow::Result<()> {
+        self.logger.target.fsync()?;
+        self.topology_writer.flush(self.sealed_topology()).await
+    }
+
+    pub(crate) fn set_fsync_policy(
+        &mut self,
+        policy: FsyncPolicy,
+        callback: Callback<anyhow::Result<()>>,
+    ) {
+        let _ = callback.send(self.logger.target.set_fsync_policy(policy));
+    }
+
+    pub(crate) fn set_raft_timing(
+        &mut self,
+        parameter: &str,
+        value: u64,
+        callback: Callback<anyhow::Result<()>>,
+    ) {
+        let res = self.heartbeat_scheduler.timings().with(parameter, value).map(|timings| {
+            info!("Raft timings changed to {timings:?}");
+            self.heartbeat_scheduler.retime(timings)
+        });
+        let _ = callback.send(res);
+    }
+
+    pub(crate) fn pending_write_stats(&self) -> PendingWriteStats {
+        PendingWriteStats {
+            held: self.pending_requests.as_ref().map_or(0, |reqs| reqs.len()),
+            ..self.pending_write_stats
+        }
+    }
+
+    pub(crate) fn resolve_replica_waits(&mut self) {
+        if self.replica_waits.is_empty() {
+            return;
+        }
+        if !self.replication.is_leader() {
+            self.replica_waits.fail_all("ERR leadership changed while waiting for replicas");
+            return;
+        }
+        let match_indexes = self.replicas().map(|(_, match_index)| match_index).collect::<Vec<_>>();
+        self.replica_waits.resolve(|index| {
+            match_indexes.iter().filter(|&&match_index| match_index >= index).count()
+        });
+    }
+
+    fn check_min_replicas(&self) -> anyhow::Result<()> {
+        if !self.min_replicas.is_enabled() {
+            return Ok(());
+        }
+        let replicas = self.replicas().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let good_replicas = self
+            .heartbeat_scheduler
+            .replicas_answered_within(&replicas, self.min_replicas.max_lag());
+        self.min_replicas.check(good_replicas)
+    }
+
+    /// Bounded staleness - a replica serves reads only while it hears from its leader and trails
+    /// the leader's commit index by at most `max_lag` entries.
+    pub(crate) fn check_replica_staleness(&self, max_lag: u64) -> anyhow::Result<()> {
+        if self.replication.is_leader() {
+            return Ok(());
+        }
+        let Some(leader) = self.known_leader.as_ref() else {
+            return Err(anyhow::anyhow!("TRYAGAIN replica has not heard from a leader yet"));
+        };
+        if !self.heartbeat_scheduler.heard_from_leader_recently() {
+            return Err(anyhow::anyhow!(
+                "TRYAGAIN replica lost contact with the leader {}",
+                leader.id
+            ));
+        }
+        let lag = leader.hwm.saturating_sub(self.replication.hwm.load(Ordering::Acquire));
+        if lag > max_lag {
+            return Err(anyhow::anyhow!(
+                "TRYAGAIN replica is {lag} entries behind the leader {}",
+                leader.id
+            ));
+        }
+        Ok(())
+    }
+
+    /// CheckQuorum - a leader cut off from the majority can never commit, so rather than piling up
+    /// writes it steps down and fails the ones in flight.
+    async fn step_down_if_quorum_lost(&mut self) -> bool {
+        if !self.replication.is_leader() {
+            return false;
+        }
+        let replicas = self.replicas().map(|(id, _)| id.clone()).collect();
+        if self.heartbeat_scheduler.has_quorum(replicas) {
+            return false;
+        }
+        warn!("Lost contact with the majority of replicas, stepping down");
+
+        // * The vote for itself is kept so that no other node can be elected in the same term with its help
+        self.replication.vote_for(Some(self.replication.self_identifier()));
+        self.persist_hard_state();
+        self.heartbeat_scheduler.turn_follower_mode().await;
+
+        for (_, voting) in self.consensus_tracker.drain() {
+            let _ = voting.callback.send(ConsensusClientResponse::Err(
+                "TRYAGAIN leader lost contact with the majority".into(),
+            ));
+        }
+        self.abort_leadership_transfer();
+        true
+    }
+
+    pub(crate) fn cluster_shards(&self) -> Vec<Shard> {
+        let nodes = self.cluster_nodes();
+        self.hash_ring
+            .token_ranges()
+            .into_iter()
+            .filter_map(|(replid, ranges)| {
+                let leader = self.hash_ring.get_node_id(&replid)?.clone();
+                let mut replicas = nodes
+                    .iter()
+                    .filter(|node| node.replid == replid && *node.id() != leader)
+                    .map(|node| node.id().clone())
+                    .collect::<Vec<_>>();
+                replicas.sort();
+                Some(Shard { replid, leader, replicas, ranges })
+            })
+            .collect()
+    }
+
+    pub(crate) fn reshard_status(&self) -> String {
+        let in_flight = self.pending_migrations.as_ref().map_or(0, |p| p.len());
+        self.migration_progress.report(in_flight)
+    }
+
+    pub(crate) fn consensus_report(&self) -> ConsensusReport {
+        let now = Instant::now();
+        let mut in_flight: Vec<_> = self
+            .consensus_tracker
+            .iter()
+            .map(|(index, voting)| InFlightEntry {
+                index: *index,
+                votes: voting.cnt,
+                required_votes: voting.get_required_votes(),
+                voters: voting.voters.clone(),
+            })
+            .collect();
+        in_flight.sort_by_key(|entry| entry.index);
+
+        ConsensusReport {
+            term: self.replication.term,
+            role: self.replication.role.clone(),
+            election_state: self.replication.election_state.name(),
+            voted_for: self
+                .replication
+                .election_state
+                .voted_for(&self.replication.self_identifier()),
+            commit_index: self.replication.hwm.load(Ordering::Relaxed),
+            last_log_index: self.logger.last_log_index,
+            last_log_term: self.logger.last_log_term,
+            peers: self
+                .members
+                .iter()
+                .map(|(id, peer)| PeerProgress {
+                    id: id.clone(),
+                    role: peer.role(),
+                    same_shard: peer.is_replica(&self.replication.replid),
+                    match_index: peer.match_index(),
+                    last_seen_ms: now.saturating_duration_since(peer.last_seen).as_millis(),
+                })
+                .collect(),
+            in_flight,
+            pending_requests: self.pending_requests.as_ref().map(|reqs| reqs.len()),
+            pending_reads: self.pending_reads.len(),
+            replica_waits: self.replica_waits.len(),
+            pending_forwards: self.pending_forwards.len(),
+            pending_migrations: self.pending_migrations.as_ref().map_or(0, |p| p.len()),
+            migrating_keys: self.migrating_keys.len(),
+        }
+    }
+
     pub(crate) fn cluster_nodes(&self) -> Vec<PeerState> {
         self.members
             .values()
@@ -342,6 +1139,15 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             .chain(std::iter::once(self.replication.self_info()))
             .collect()
     }
+
+    /// Nodes as `CLUSTER NODES` lists them: the connected ones, then those lost and not given up on.
+    pub(crate) fn cluster_node_links(&self) -> Vec<(PeerState, LinkState)> {
+        self.cluster_nodes()
+            .into_iter()
+            .map(|state| (state, LinkState::Connected))
+            .chain(self.reconnects.links().map(|(state, link)| (state.clone(), link)))
+            .collect()
+    }
     #[instrument(level = tracing::Level::INFO, skip(self, request_vote))]
     pub(crate) async fn vote_election(&mut self, request_vote: RequestVote) {
         if self.find_replica_mut(&request_vote.candidate_id).is_none() {
@@ -359,6 +1165,8 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             request_vote.candidate_id, request_vote.term
         );
 
+        // ! the vote must hit the disk before the candidate can count it
+        self.persist_hard_state();
         let term = self.replication.term;
 
         let Some(peer) = self.find_replica_mut(&request_vote.candidate_id) else {
@@ -368,15 +1176,52 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         let _ = peer.send(ElectionVote { term, vote_granted: grant_vote }).await;
     }
 
+    // * Pre-vote is granted without touching the term or the vote, only telling the candidate whether it could win
+    #[instrument(level = tracing::Level::INFO, skip(self, request_vote))]
+    pub(crate) async fn vote_pre_election(&mut self, request_vote: RequestVote) {
+        let grant_vote = request_vote.term > self.replication.term
+            && self.logger.last_log_index <= request_vote.last_log_index
+            && !self.has_live_leader();
+
+        info!(
+            "Pre-voting for {} with term {} and granted: {grant_vote}",
+            request_vote.candidate_id, request_vote.term
+        );
+
+        let term = self.replication.term;
+        let Some(peer) = self.find_replica_mut(&request_vote.candidate_id) else {
+            return;
+        };
+        let _ =
+            peer.send(QueryIO::PreVoteReply(ElectionVote { term, vote_granted: grant_vote })).await;
+    }
+
+    fn has_live_leader(&self) -> bool {
+        self.replication.is_leader() || self.heartbeat_scheduler.heard_from_leader_recently()
+    }
+
     #[instrument(level = tracing::Level::DEBUG, skip(self, repl_res), fields(peer_id = %repl_res.from))]
     pub(crate) async fn ack_replication(&mut self, repl_res: ReplicationAck) {
+        if !self.replication.is_leader() && self.downstream_replicas().contains(&repl_res.from) {
+            self.relay_replication_ack(repl_res).await;
+            return;
+        }
+        self.heartbeat_scheduler.record_replica_contact(&repl_res.from);
+        if repl_res.is_heartbeat() {
+            self.yield_to_preferred_replica().await;
+            return;
+        }
         if !repl_res.is_granted() {
             info!("vote cannot be granted {:?}", repl_res.rej_reason);
             self.handle_repl_rejection(repl_res).await;
             return;
         }
         self.update_peer_index(&repl_res.from, repl_res.log_idx);
+        let from = repl_res.from.clone();
         self.track_replication_progress(repl_res);
+        self.send_next_batch(&from).await;
+        self.try_complete_leadership_transfer().await;
+        self.yield_to_preferred_replica().await;
     }
 
     #[instrument(level = tracing::Level::DEBUG, skip(self, cache_manager,heartbeat), fields(peer_id = %heartbeat.from))]
@@ -390,12 +1235,29 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         };
         self.reset_election_timeout(&heartbeat.from);
+        self.record_peer_settings(&heartbeat);
         self.maybe_update_term(heartbeat.term);
+        self.known_leader = Some(KnownLeader { id: heartbeat.from.clone(), hwm: heartbeat.hwm });
         self.replicate(heartbeat, cache_manager).await;
     }
 
+    #[instrument(level = tracing::Level::DEBUG, skip(self, pre_vote))]
+    pub(crate) async fn receive_pre_vote(&mut self, pre_vote: ElectionVote) {
+        if !pre_vote.vote_granted {
+            return;
+        }
+        if !self.replication.election_state.can_start_election() {
+            return;
+        }
+        self.run_for_election().await;
+    }
+
     #[instrument(level = tracing::Level::DEBUG, skip(self, election_vote))]
-    pub(crate) async fn receive_election_vote(&mut self, election_vote: ElectionVote) {
+    pub(crate) async fn receive_election_vote(
+        &mut self,
+        election_vote: ElectionVote,
+        cache_manager: &CacheManager,
+    ) {
         if !election_vote.vote_granted {
             return;
         }
@@ -403,28 +1265,182 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         }
 
-        self.become_leader().await;
+        self.become_leader(cache_manager).await;
         let msg = self.replication.default_heartbeat(
             0,
             self.logger.last_log_index,
             self.logger.last_log_term,
         );
 
-        self.replicas_mut()
-            .map(|(peer, _)| peer.send(QueryIO::AppendEntriesRPC(msg.clone())))
-            .collect::<FuturesUnordered<_>>()
-            .for_each(|_| async {})
-            .await;
+        let append_entries = SharedMessage::new(QueryIO::AppendEntriesRPC(msg.clone()));
+        broadcast(self.replicas_mut().map(|(peer, _)| peer), append_entries).await;
 
         // * update hash ring with the new leader
         self.hash_ring.update_repl_leader(
             self.replication.replid.clone(),
             self.replication.self_identifier(),
         );
+        self.broadcast_topology_change();
         let msg = msg.set_hashring(self.hash_ring.clone());
         self.send_heartbeat(msg).await;
     }
 
+    // * Hands leadership off to the given replica, or to the most up-to-date one when none is given.
+    // * Writes are held back until the target has caught up and been told to start an election.
+    pub(crate) async fn cluster_failover(
+        &mut self,
+        target: Option<PeerIdentifier>,
+        callback: Callback<anyhow::Result<()>>,
+    ) {
+        if !self.replication.is_leader() {
+            let _ = callback
+                .send(res_err!("invalid operation: only the leader can hand off leadership"));
+            return;
+        }
+        if self.pending_requests.is_some() || self.hand_off_hold.is_some() {
+            let _ = callback.send(res_err!("invalid state: writes are already blocked"));
+            return;
+        }
+        let target = target.or_else(|| {
+            self.replicas().max_by_key(|(_, match_index)| *match_index).map(|(id, _)| id.clone())
+        });
+        let Some(target) = target.filter(|id| self.replicas().any(|(peer, _)| peer == id)) else {
+            let _ = callback.send(res_err!("no such replica to hand off leadership to"));
+            return;
+        };
+
+        info!("Handing off leadership to {target}");
+        // ! BLOCK subsequent requests until the transfer is done
+        let id = Uuid::now_v7();
+        self.hand_off_hold = Some(HandOffHold { transfer_id: id, reqs: VecDeque::new() });
+        self.leadership_transfer = Some(LeadershipTransfer { id, target, callback });
+
+        let handler = self.self_handler.clone();
+        let abort_after = self.heartbeat_scheduler.timings().election_timeout.end;
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(abort_after)).await;
+            let _ = handler.send(SchedulerMessage::AbortLeadershipTransfer(id)).await;
+        });
+
+        self.send_rpc_to_replicas().await;
+        self.try_complete_leadership_transfer().await;
+    }
+
+    fn record_peer_settings(&mut self, heartbeat: &HeartBeat) {
+        if let Some(peer) = self.members.get_mut(&heartbeat.from) {
+            peer.set_priority(heartbeat.priority);
+            peer.set_weight(heartbeat.weight);
+            peer.set_upstream(heartbeat.upstream.clone());
+        }
+    }
+
+    /// Hands leadership off to the highest priority replica once it has caught up with the log.
+    async fn yield_to_preferred_replica(&mut self) {
+        if !self.replication.is_leader()
+            || self.pending_requests.is_some()
+            || self.hand_off_hold.is_some()
+        {
+            return;
+        }
+        let Some(target) = self
+            .members
+            .values()
+            .filter(|peer| {
+                peer.is_replica(&self.replication.replid)
+                    && peer.priority() > self.replication.priority
+                    && peer.match_index() >= self.logger.last_log_index
+            })
+            .max_by_key(|peer| peer.priority())
+            .map(|peer| peer.id().clone())
+        else {
+            return;
+        };
+
+        info!("Replica {target} has a higher election priority");
+        let (tx, _) = tokio::sync::oneshot::channel();
+        self.cluster_failover(Some(target), tx.into()).await;
+    }
+
+    async fn try_complete_leadership_transfer(&mut self) {
+        let Some(transfer) = self.leadership_transfer.as_ref() else {
+            return;
+        };
+        // * In-flight writes must be committed before leadership moves
+        if !self.consensus_tracker.is_empty() {
+            return;
+        }
+        let last_log_index = self.logger.last_log_index;
+        let msg =
+            TimeoutNow { from: self.replication.self_identifier(), term: self.replication.term };
+        let Some(peer) = self.members.get_mut(&transfer.target) else {
+            return;
+        };
+        if peer.match_index() < last_log_index {
+            return;
+        }
+        let _ = peer.send(msg).await;
+
+        let LeadershipTransfer { callback, .. } = self.leadership_transfer.take().unwrap();
+        self.step_down().await;
+        let _ = callback.send(Ok(()));
+    }
+
+    pub(crate) fn abort_leadership_transfer(&mut self) {
+        let Some(transfer) = self.leadership_transfer.take() else {
+            return;
+        };
+        warn!("Leadership transfer to {} timed out", transfer.target);
+        let _ = transfer.callback.send(res_err!("leadership transfer timed out"));
+        self.release_handed_off_writes();
+    }
+
+    /// Called an election timeout after the hand-off `id` started. A transfer still in flight is
+    /// aborted, and writes still waiting for the new leader to be known are told to retry.
+    pub(crate) fn expire_leadership_transfer(&mut self, id: Uuid) {
+        if self.leadership_transfer.as_ref().is_some_and(|transfer| transfer.id == id) {
+            self.abort_leadership_transfer();
+            return;
+        }
+        if self.hand_off_hold.as_ref().is_some_and(|hold| hold.transfer_id == id) {
+            for req in self.hand_off_hold.take().unwrap().reqs {
+                let _ = req.callback.send("TRYAGAIN leadership changed".to_string().into());
+            }
+        }
+    }
+
+    /// Lets go of the writes held during a hand-off that is over. They are taken back when this node
+    /// still or again leads, and redirected to the new leader once it is known.
+    pub(crate) fn release_handed_off_writes(&mut self) {
+        if self.leadership_transfer.is_some() {
+            return;
+        }
+        let Some(hold) = self.hand_off_hold.take() else {
+            return;
+        };
+        if self.replication.is_leader() {
+            self.requeue_pending_requests(hold.reqs);
+            return;
+        }
+        let Some(leader) = self.known_leader.as_ref() else {
+            self.hand_off_hold = Some(hold);
+            return;
+        };
+        let reply = format!("MOVED {}", leader.id);
+        for req in hold.reqs {
+            let _ = req.callback.send(reply.clone().into());
+        }
+    }
+
+    // FOLLOWER side operation
+    pub(crate) async fn receive_timeout_now(&mut self, timeout_now: TimeoutNow) {
+        if timeout_now.term < self.replication.term || self.replication.is_leader() {
+            return;
+        }
+        info!("Leadership handed off by {}", timeout_now.from);
+        // * Skips pre-vote as the current leader has stepped down on purpose
+        self.run_for_election().await;
+    }
+
     // * Forces the current node to become a replica of the given peer.
     pub(crate) async fn replicaof(
         &mut self,
@@ -491,7 +1507,15 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         }
 
-        let Some(new_hashring) = self.hash_ring.set_partitions(self.shard_leaders()) else {
+        let Some(new_hashring) = self.hash_ring.set_weighted_partitions(self.shard_leaders())
+        else {
+            // * Keys left behind by abandoned batches are sent again
+            let keys = cache_manager.route_keys(None).await;
+            let stranded = self.hash_ring.misplaced_keys(&self.replication.replid, keys);
+            if !stranded.is_empty() {
+                self.start_migration(self.hash_ring.clone(), stranded).await;
+                return;
+            }
             warn!("No need for update on hashring");
             return;
         };
@@ -512,6 +1536,155 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         self.maybe_update_hashring(Some(Box::new(new_hashring)), cache_manager).await;
     }
 
+    // * Moves the keys matched by the selector to the target partition, wherever they currently live
+    pub(crate) async fn cluster_migrate(
+        &mut self,
+        selector: KeySelector,
+        target: ReplicationId,
+        cache_manager: &CacheManager,
+        callback: Callback<anyhow::Result<()>>,
+    ) {
+        if !self.replication.is_leader() {
+            let _ = callback.send(res_err!("invalid operation: only a leader can migrate keys"));
+            return;
+        }
+        if self.pending_requests.is_some() {
+            let _ = callback.send(res_err!("invalid state: writes are already blocked"));
+            return;
+        }
+        if self.hash_ring.get_node_id(&target).is_none() {
+            let _ = callback.send(res_err!("no such partition: {target}"));
+            return;
+        }
+
+        let new_hashring = self.hash_ring.pin(selector, target);
+        let hb = self
+            .replication
+            .default_heartbeat(
+                Self::hop_count(FANOUT, self.members.len()),
+                self.logger.last_log_index,
+                self.logger.last_log_term,
+            )
+            .set_hashring(new_hashring.clone());
+
+        self.send_heartbeat(hb).await;
+        self.maybe_update_hashring(Some(Box::new(new_hashring)), cache_manager).await;
+        let _ = callback.send(Ok(()));
+    }
+
+    // * Leaves the cluster without stranding data:
+    // * - a leader with replicas hands leadership to one of them, which keeps the data
+    // * - the only node of a partition first drains its keys to the remaining partitions
+    // * - a follower leaves right away
+    pub(crate) async fn cluster_leave(
+        &mut self,
+        cache_manager: &CacheManager,
+        callback: Callback<anyhow::Result<()>>,
+    ) {
+        if self.departure.is_some() || self.pending_requests.is_some() {
+            let _ = callback.send(res_err!("invalid state: writes are already blocked"));
+            return;
+        }
+        if !self.replication.is_leader() {
+            self.depart(callback).await;
+            return;
+        }
+
+        if self.replicas().next().is_some() {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            self.cluster_failover(None, tx.into()).await;
+            let handler = self.self_handler.clone();
+            tokio::spawn(async move {
+                match rx.await {
+                    | Ok(Ok(())) => {
+                        let _ = handler.send(SchedulerMessage::Depart(callback)).await;
+                    },
+                    | Ok(Err(err)) => {
+                        let _ = callback.send(Err(err));
+                    },
+                    | Err(_) => {
+                        let _ = callback.send(res_err!("Channel closed"));
+                    },
+                }
+            });
+            return;
+        }
+
+        let remaining = self
+            .shard_leaders()
+            .into_iter()
+            .filter(|(replid, _, _)| *replid != self.replication.replid)
+            .collect::<Vec<_>>();
+        if remaining.is_empty() {
+            let _ = callback.send(res_err!("invalid state: no other partition to take over keys"));
+            return;
+        }
+
+        warn!("Leaving the cluster! keys are being drained to the other partitions");
+        self.departure = Some(callback);
+        let Some(new_hashring) = self.hash_ring.set_weighted_partitions(remaining) else {
+            // * The ring already excludes this node: keys left by an earlier attempt are sent again
+            let keys = cache_manager.route_keys(None).await;
+            let stranded = self.hash_ring.misplaced_keys(&self.replication.replid, keys);
+            if stranded.is_empty() {
+                self.finish_departure().await;
+            } else {
+                self.start_migration(self.hash_ring.clone(), stranded).await;
+            }
+            return;
+        };
+
+        let hb = self
+            .replication
+            .default_heartbeat(
+                Self::hop_count(FANOUT, self.members.len()),
+                self.logger.last_log_index,
+                self.logger.last_log_term,
+            )
+            .set_hashring(new_hashring.clone());
+        self.send_heartbeat(hb).await;
+        self.maybe_update_hashring(Some(Box::new(new_hashring)), cache_manager).await;
+
+        // * Nothing to migrate
+        if self.pending_requests.is_none() {
+            self.finish_departure().await;
+        }
+    }
+
+    async fn finish_departure(&mut self) {
+        let Some(callback) = self.departure.take() else {
+            return;
+        };
+        if self.migration_progress.has_failures() {
+            let _ =
+                callback.send(res_err!("some keys could not be migrated, run CLUSTER LEAVE again"));
+            return;
+        }
+        self.depart(callback).await;
+    }
+
+    // * Peers are told through the ban list of the heartbeat, so they forget this node instead of suspecting it
+    pub(crate) async fn depart(&mut self, callback: Callback<anyhow::Result<()>>) {
+        info!("Leaving the cluster");
+        let Ok(ban_time) = time_in_secs() else {
+            let _ = callback.send(res_err!("failed to read the system clock"));
+            return;
+        };
+        self.replication.banlist.ban(self.replication.self_identifier(), ban_time);
+        let hb = self.replication.default_heartbeat(
+            Self::hop_count(FANOUT, self.members.len()),
+            self.logger.last_log_index,
+            self.logger.last_log_term,
+        );
+        self.send_heartbeat(hb).await;
+
+        for peer_id in self.members.keys().cloned().collect::<Vec<_>>() {
+            self.remove_peer(&peer_id).await;
+        }
+        let _ = callback.send(Ok(()));
+        let _ = self.shutdown.send(true);
+    }
+
     fn hop_count(fanout: usize, node_count: usize) -> u8 {
         if node_count <= fanout {
             return 0;
@@ -525,17 +1698,18 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         })
     }
 
-    fn shard_leaders(&self) -> Vec<(ReplicationId, PeerIdentifier)> {
+    fn shard_leaders(&self) -> Vec<(ReplicationId, PeerIdentifier, u8)> {
         let iter = self
             .members
             .iter()
             .filter(|(_, peer)| peer.role() == ReplicationRole::Leader)
-            .map(|(id, peer)| (peer.replid().clone(), id.clone()));
+            .map(|(id, peer)| (peer.replid().clone(), id.clone(), peer.weight()));
 
         if self.replication.is_leader() {
             iter.chain(iter::once((
                 self.replication.replid.clone(),
                 self.replication.self_identifier(),
+                self.replication.weight,
             )))
             .collect()
         } else {
@@ -554,30 +1728,61 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         self.members.get_mut(peer_id).filter(|peer| peer.is_replica(&self.replication.replid))
     }
 
+    // * Prefers the live leader of the partition, so that a batch retried after a failover reaches the new one
     fn peerid_by_replid(&self, target_repl_id: &ReplicationId) -> Option<&PeerIdentifier> {
+        let now = Instant::now();
         self.members
             .iter()
-            .find(|(_, peer)| peer.replid() == target_repl_id)
+            .filter(|(_, peer)| peer.replid() == target_repl_id)
+            .max_by_key(|(_, peer)| {
+                (!self.is_suspected(peer, now), peer.role() == ReplicationRole::Leader)
+            })
             .map(|(peer_id, _)| peer_id)
     }
 
-    async fn send_heartbeat(&mut self, heartbeat: HeartBeat) {
+    // * Carries the node list and possibly the hash ring, so it is serialized once and shared by all
+    // * peers. Peers that already hold the node list get it as its digest alone, shared as well
+    async fn send_heartbeat(&mut self, mut heartbeat: HeartBeat) {
+        if heartbeat.cluster_nodes.is_empty() {
+            let mut heartbeat = SharedMessage::new(heartbeat);
+            for peer in self.members.values_mut() {
+                let _ = peer.send_shared(&mut heartbeat).await;
+            }
+            return;
+        }
+
+        heartbeat.nodes_digest = nodes_digest(&heartbeat.cluster_nodes);
+        let digest = heartbeat.nodes_digest;
+        let mut digest_only =
+            SharedMessage::new(HeartBeat { cluster_nodes: vec![], ..heartbeat.clone() });
+        let mut full = SharedMessage::new(heartbeat);
         for peer in self.members.values_mut() {
-            let _ = peer.send(heartbeat.clone()).await;
+            let message = if peer.needs_full_nodes(digest) { &mut full } else { &mut digest_only };
+            let _ = peer.send_shared(message).await;
         }
     }
 
-    async fn snapshot_topology(&mut self) -> anyhow::Result<()> {
+    // * Debounced and written off the actor, see `TopologyWriter`
+    fn snapshot_topology(&self) {
+        self.topology_writer.save(self.sealed_topology());
+    }
+
+    // * Peers being retried are kept, so that a node restarted before they are back still finds them.
+    // * Bans in force are saved after the nodes, so that they still hold once the node restarts
+    fn sealed_topology(&self) -> Vec<u8> {
+        let retried = self
+            .reconnects
+            .links()
+            .filter(|(_, link)| *link == LinkState::Disconnected)
+            .map(|(state, _)| state.clone());
         let topology = self
             .cluster_nodes()
             .into_iter()
+            .chain(retried)
             .map(|cn| cn.format(&self.replication.self_identifier()))
-            .collect::<Vec<_>>()
-            .join("\r\n");
-        self.topology_writer.seek(std::io::SeekFrom::Start(0))?;
-        self.topology_writer.set_len(topology.len() as u64)?;
-        self.topology_writer.write_all(topology.as_bytes())?;
-        Ok(())
+            .chain(self.replication.banlist.lines(time_in_secs().unwrap_or_default()))
+            .collect::<Vec<_>>();
+        self.topology_encryption.seal_file(topology.join("\r\n").into_bytes())
     }
 
     // ! BLOCK subsequent requests until rebalance is done
@@ -588,12 +1793,32 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         }
     }
 
-    // * Broadcasts the current topology to all connected clients
-    // TODO hashring information should be included in the broadcast so clients can update their routing tables
+    // * Broadcasts the current topology, hash ring included, to all connected clients
     fn broadcast_topology_change(&self) {
+        self.publish_routing();
         self.node_change_broadcast.send(self.get_topology()).ok();
     }
 
+    pub(crate) fn record_topology_changes(&mut self) {
+        let leader = if self.replication.is_leader() {
+            Some(self.replication.self_identifier())
+        } else {
+            self.known_leader.as_ref().map(|leader| leader.id.clone())
+        };
+        self.topology_history.observe(ObservedTopology {
+            members: self.members.keys().collect(),
+            banned: self
+                .replication
+                .banlist
+                .active(time_in_secs().unwrap_or_default())
+                .map(|banned| &banned.p_id)
+                .collect(),
+            leader,
+            term: self.replication.term,
+            ring_version: self.hash_ring.last_modified,
+        });
+    }
+
     pub(crate) fn get_topology(&self) -> Topology {
         Topology::new(
             self.members
@@ -605,34 +1830,112 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         )
     }
 
-    async fn remove_peer(&mut self, peer_addr: &PeerIdentifier) -> Option<()> {
+    async fn remove_peer(&mut self, peer_addr: &PeerIdentifier) -> Option<PeerState> {
+        self.gossip_views.forget(peer_addr);
         if let Some(peer) = self.members.remove(peer_addr) {
             warn!("{} is being removed!", peer_addr);
+            let state = peer.state().clone();
             // stop the runnin process and take the connection in case topology changes are made
             let _read_connected = peer.kill().await;
             self.broadcast_topology_change();
-            return Some(());
+            return Some(state);
         }
         None
     }
 
     //  remove idle peers based on ttl.
     async fn remove_idle_peers(&mut self) {
-        // loop over members, if ttl is expired, remove the member
+        // loop over members, if the peer is suspected to have failed, remove the member
         let now = Instant::now();
 
         for peer_id in self
             .members
             .iter()
-            .filter(|&(_, peer)| now.duration_since(peer.last_seen).as_millis() > self.node_timeout)
+            .filter(|&(_, peer)| self.is_suspected(peer, now))
             .map(|(id, _)| id)
             .cloned()
             .collect::<Vec<_>>()
         {
-            self.remove_peer(&peer_id).await;
+            if let Some(state) = self.remove_peer(&peer_id).await {
+                self.reconnects.track(state, now);
+            }
+        }
+    }
+
+    fn schedule_reconnects(&self) {
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            let mut itv = tokio::time::interval(RECONNECT_BACKOFF_BASE / 2);
+            loop {
+                itv.tick().await;
+                if handler.send(SchedulerMessage::ReconnectPeers).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Attempts lost peers whose backoff ran out. Connecting is left to tasks, as an unreachable
+    /// host may take long to time out, and a peer that answers is added like any other.
+    pub(crate) fn reconnect_peers(&mut self) {
+        self.reconnects.lift_bans(|peer_id| self.replication.in_ban_list(peer_id));
+        for peer_id in self.reconnects.due(Instant::now()) {
+            let (replication, self_port) = (self.replication.clone(), self.replication.self_port);
+            let handler = self.self_handler.clone();
+            tokio::spawn(async move {
+                let stream = match OutboundStream::new(peer_id.clone(), replication).await {
+                    | Ok(stream) => stream,
+                    | Err(err) => return debug!("reconnecting to {peer_id} failed: {err}"),
+                };
+                if let Err(err) = stream.add_peer(self_port, handler, None).await {
+                    debug!("reconnecting to {peer_id} failed: {err}");
+                }
+            });
         }
     }
 
+    /// Half of `node_timeout` is tolerated on top of the usual heartbeat interval, so a peer with
+    /// a steady link is given up on after roughly `node_timeout`.
+    fn acceptable_pause(&self) -> std::time::Duration {
+        std::time::Duration::from_millis((self.node_timeout / 2) as u64)
+    }
+
+    fn is_suspected(&self, peer: &Peer, now: Instant) -> bool {
+        peer.phi(now, self.acceptable_pause()) > self.phi_threshold
+    }
+
+    pub(crate) fn peer_suspicion(&self) -> Vec<(PeerIdentifier, f64)> {
+        let now = Instant::now();
+        self.members
+            .iter()
+            .map(|(id, peer)| (id.clone(), peer.phi(now, self.acceptable_pause())))
+            .collect()
+    }
+
+    pub(crate) fn replication_links(&self) -> ReplicationLinks {
+        let now = Instant::now();
+        let idle = |peer: &Peer| now.saturating_duration_since(peer.last_seen).as_secs();
+        let replid = &self.replication.replid;
+
+        if !self.replication.is_leader() {
+            let leader = self
+                .members
+                .iter()
+                .find(|(_, peer)| peer.is_replica(replid) && peer.role() == ReplicationRole::Leader)
+                .map(|(id, peer)| (id.clone(), idle(peer)));
+            return ReplicationLinks { leader, replicas: vec![] };
+        }
+
+        let mut replicas: Vec<_> = self
+            .members
+            .iter()
+            .filter(|(_, peer)| peer.is_follower(replid))
+            .map(|(id, peer)| (id.clone(), peer.match_index(), idle(peer)))
+            .collect();
+        replicas.sort();
+        ReplicationLinks { leader: None, replicas }
+    }
+
     async fn gossip(&mut self, mut hop_count: u8) {
         // If hop_count is 0, don't send the message to other peers
         if hop_count == 0 {
@@ -648,21 +1951,20 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
     }
 
     async fn apply_banlist(&mut self, ban_list: Vec<BannedPeer>) {
-        for banned_peer in ban_list {
-            let ban_list = &mut self.replication.banlist;
-            if let Some(existing) = ban_list.take(&banned_peer) {
-                let newer =
-                    if banned_peer.ban_time > existing.ban_time { banned_peer } else { existing };
-                ban_list.insert(newer);
-            } else {
-                ban_list.insert(banned_peer);
-            }
-        }
+        let changed = self.replication.banlist.merge(ban_list);
 
         let current_time_in_sec = time_in_secs().unwrap();
-        self.replication.banlist.retain(|node| current_time_in_sec - node.ban_time < 60);
-        for banned_peer in self.replication.banlist.iter().cloned().collect::<Vec<_>>() {
-            self.remove_peer(&banned_peer.p_id).await;
+        self.replication.banlist.expire(current_time_in_sec);
+        let banned: Vec<_> =
+            self.replication.banlist.active(current_time_in_sec).map(|b| b.p_id.clone()).collect();
+        for peer_id in banned {
+            if let Some(state) = self.remove_peer(&peer_id).await {
+                self.reconnects.track(state, Instant::now());
+            }
+            self.reconnects.ban(&peer_id);
+        }
+        if changed {
+            self.snapshot_topology();
         }
     }
 
@@ -673,24 +1975,163 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
     }
 
     async fn send_rpc_to_replicas(&mut self) {
-        self.iter_follower_append_entries()
-            .await
-            .map(|(peer, hb)| peer.send(QueryIO::AppendEntriesRPC(hb)))
+        let targets = self.direct_replicas();
+        let (from, term) = (self.replication.self_identifier(), self.replication.term);
+        self.send_snapshot_to_lagging_replicas(from, term, &targets).await;
+        let framed = frame_each(
+            self.iter_follower_append_entries()
+                .await
+                .map(|(peer, hb)| (peer, QueryIO::AppendEntriesRPC(hb))),
+        );
+        send_frames(framed).await;
+    }
+
+    /// Replicas the leader sends the log to itself. Those chained behind another replica take it
+    /// from their upstream instead.
+    fn direct_replicas(&self) -> HashSet<PeerIdentifier> {
+        self.replicas().map(|(id, _)| id).filter(|id| !self.is_chained(id)).cloned().collect()
+    }
+
+    /// Whether following the replica's upstream leads to a connected replica that the leader
+    /// sends the log to. Chains that loop or break off are served by the leader directly.
+    fn is_chained(&self, peer_id: &PeerIdentifier) -> bool {
+        let self_id = self.replication.self_identifier();
+        let mut visited = HashSet::from([peer_id]);
+        let mut current = peer_id;
+        loop {
+            let Some(upstream) = self.members.get(current).and_then(|peer| peer.upstream()) else {
+                return current != peer_id;
+            };
+            if *upstream == self_id
+                || !visited.insert(upstream)
+                || !self
+                    .members
+                    .get(upstream)
+                    .is_some_and(|p| p.is_follower(&self.replication.replid))
+            {
+                return false;
+            }
+            current = upstream;
+        }
+    }
+
+    /// Replicas chained behind this one.
+    fn downstream_replicas(&self) -> HashSet<PeerIdentifier> {
+        let self_id = self.replication.self_identifier();
+        self.replicas()
+            .map(|(id, _)| id)
+            .filter(|id| self.members[*id].upstream() == Some(&self_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Passes entries appended from the leader on to the replicas chained behind this one. What each
+    /// of them is sent comes from this node's own log, tailored to what it has acknowledged.
+    async fn relay_append_entries(&mut self, mut heartbeat: HeartBeat) {
+        let downstream = self.downstream_replicas();
+        if downstream.is_empty() {
+            return;
+        }
+        heartbeat.trace = TraceContext::current().or(heartbeat.trace);
+        self.send_snapshot_to_lagging_replicas(heartbeat.from.clone(), heartbeat.term, &downstream)
+            .await;
+        let framed = frame_each(
+            self.tailored_append_entries(heartbeat, downstream)
+                .map(|(peer, hb)| (peer, QueryIO::AppendEntriesRPC(hb))),
+        );
+        send_frames(framed).await;
+    }
+
+    /// Acks of replicas chained behind this one are passed on towards the leader, which counts them
+    /// towards consensus. Their progress is kept here as well to tailor what they are relayed next.
+    async fn relay_replication_ack(&mut self, ack: ReplicationAck) {
+        match ack.rej_reason {
+            | None if !ack.is_heartbeat() => self.update_peer_index(&ack.from, ack.log_idx),
+            | Some(RejectionReason::LogInconsistency) => {
+                self.decrease_match_index(&ack.from, ack.log_idx)
+            },
+            | Some(RejectionReason::FailToWrite) => {
+                if let Some(peer) = self.members.get_mut(&ack.from) {
+                    peer.rewind_window();
+                }
+            },
+            | _ => {},
+        }
+        let Some(leader) = self.known_leader.as_ref().map(|leader| leader.id.clone()) else {
+            return;
+        };
+        self.send_replication_ack(&leader, ack).await;
+    }
+
+    /// Replicas whose match index falls below the snapshot can no longer be caught up from the log,
+    /// so the snapshot is streamed to them instead, chunk by chunk over the peer connection. The match index
+    /// is advanced optimistically so that the following append entries carry only the tail of the log;
+    /// a rejection from the replica reverts it.
+    async fn send_snapshot_to_lagging_replicas(
+        &mut self,
+        from: PeerIdentifier,
+        term: u64,
+        targets: &HashSet<PeerIdentifier>,
+    ) {
+        let Some(snapshot) = self.logger.snapshot.as_ref() else {
+            return;
+        };
+        let last_included_index = snapshot.last_included_index;
+        if !self
+            .replicas()
+            .any(|(id, match_index)| targets.contains(id) && match_index < last_included_index)
+        {
+            return;
+        }
+        let chunks = split_snapshot(
+            InstallSnapshot {
+                from,
+                term,
+                last_included_index,
+                last_included_term: snapshot.last_included_term,
+                offset: 0,
+                data: snapshot.data.clone(),
+                done: true,
+            },
+            SNAPSHOT_CHUNK_SIZE,
+        );
+
+        self.replicas_mut()
+            .filter(|(peer, match_index)| {
+                targets.contains(peer.id()) && *match_index < last_included_index
+            })
+            .map(|(peer, _)| {
+                info!(
+                    "Streaming snapshot up to {last_included_index} to {} in {} chunks",
+                    peer.id(),
+                    chunks.len()
+                );
+                peer.set_match_index(last_included_index);
+                let chunks = chunks.clone();
+                async move {
+                    for chunk in chunks {
+                        peer.send(chunk).await?;
+                    }
+                    Ok::<_, IoError>(())
+                }
+            })
             .collect::<FuturesUnordered<_>>()
             .for_each(|_| async {})
             .await;
     }
 
-    /// Creates individualized append entries messages for each follower.
+    /// Creates individualized append entries messages for each follower the leader sends the log
+    /// to directly.
     ///
     /// This function generates customized heartbeat messages containing only the log entries
     /// that each specific follower needs based on their current high watermark.
     ///
     /// For each follower:
-    /// - Filters entries to include only those the follower doesn't have
+    /// - Filters entries to include only those the follower neither has nor has in flight,
+    ///   up to `MAX_INFLIGHT_ENTRIES` past its match index and within the append entries budget
     /// - Sets correct previous log information based on follower's replication state:
     ///   - If follower needs all entries: Uses backup entry or defaults to (0,0)
-    ///   - Otherwise: Uses the last entry the follower already has
+    ///   - Otherwise: Uses the last entry the follower already has or was already sent
     /// - Creates a tailored heartbeat message with exactly the entries needed
     ///
     /// Returns an iterator yielding tuples of mutable peer references and their
@@ -698,60 +2139,164 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
     async fn iter_follower_append_entries(
         &mut self,
     ) -> Box<dyn Iterator<Item = (&mut Peer, HeartBeat)> + '_> {
-        let lowest_watermark = self.take_low_watermark();
-
-        let append_entries = self.logger.list_append_log_entries(lowest_watermark);
-
         let default_heartbeat: HeartBeat = self.replication.default_heartbeat(
             0,
             self.logger.last_log_index,
             self.logger.last_log_term,
         );
+        let targets = self.direct_replicas();
+        self.tailored_append_entries(default_heartbeat, targets)
+    }
+
+    fn tailored_append_entries(
+        &mut self,
+        default_heartbeat: HeartBeat,
+        targets: HashSet<PeerIdentifier>,
+    ) -> Box<dyn Iterator<Item = (&mut Peer, HeartBeat)> + '_> {
+        let lowest_watermark = self.take_low_watermark(&targets);
+
+        let append_entries = self.logger.list_append_log_entries(lowest_watermark);
 
         // Handle empty entries case
         if append_entries.is_empty() {
             return Box::new(
-                self.replicas_mut().map(move |(peer, _)| (peer, default_heartbeat.clone())),
+                self.replicas_mut()
+                    .filter(move |(peer, _)| targets.contains(peer.id()))
+                    .map(move |(peer, _)| (peer, default_heartbeat.clone())),
             );
         }
 
         // If we have entries, find the entry before the first one to use as backup
-        let backup_entry = self.logger.read_at(append_entries[0].log_index - 1);
-
-        let iterator = self.replicas_mut().map(move |(peer, hwm)| {
-            let logs =
-                append_entries.iter().filter(|op| op.log_index > hwm).cloned().collect::<Vec<_>>();
-
-            // Create base heartbeat
-            let mut heart_beat = default_heartbeat.clone();
-
-            if logs.len() == append_entries.len() {
-                // Follower needs all entries, use backup entry
-                if let Some(backup_entry) = backup_entry.as_ref() {
-                    heart_beat.prev_log_index = backup_entry.log_index;
-                    heart_beat.prev_log_term = backup_entry.term;
-                } else {
-                    heart_beat.prev_log_index = 0;
-                    heart_beat.prev_log_term = 0;
-                }
-            } else {
-                // Follower has some entries already, use the last one it has
-                let last_log = &append_entries[append_entries.len() - logs.len() - 1];
-                heart_beat.prev_log_index = last_log.log_index;
-                heart_beat.prev_log_term = last_log.term;
-            }
-            let heart_beat = heart_beat.set_append_entries(logs);
+        let backup_entry = self.entry_before(&append_entries);
+        let budget = self.append_entries_budget;
+
+        let replicas = self.replicas_mut().filter(move |(peer, _)| targets.contains(peer.id()));
+        let iterator = replicas.map(move |(peer, match_index)| {
+            let heart_beat = Self::next_append_entries(
+                peer,
+                match_index,
+                &append_entries,
+                backup_entry,
+                default_heartbeat.clone(),
+                budget,
+            );
             (peer, heart_beat)
         });
 
         Box::new(iterator)
     }
 
-    fn take_low_watermark(&self) -> Option<u64> {
+    /// Sends the next batch to a replica that acknowledged entries but is still behind, so that
+    /// catching up does not wait for the next heartbeat.
+    async fn send_next_batch(&mut self, peer_id: &PeerIdentifier) {
+        if self.is_chained(peer_id) {
+            return;
+        }
+        let Some(match_index) = self.find_replica_mut(peer_id).map(|peer| peer.match_index())
+        else {
+            return;
+        };
+        // * Replicas behind the snapshot are caught up by installing it
+        let snapshot_index = self.logger.snapshot_index();
+        if match_index >= self.logger.last_log_index || match_index < snapshot_index {
+            return;
+        }
+
+        let append_entries = self.logger.list_append_log_entries(Some(match_index));
+        if append_entries.is_empty() {
+            return;
+        }
+        let backup_entry = self.entry_before(&append_entries);
+        let default_heartbeat = self.replication.default_heartbeat(
+            0,
+            self.logger.last_log_index,
+            self.logger.last_log_term,
+        );
+        let budget = self.append_entries_budget;
+
+        let Some(peer) = self.find_replica_mut(peer_id) else {
+            return;
+        };
+        let heart_beat = Self::next_append_entries(
+            peer,
+            match_index,
+            &append_entries,
+            backup_entry,
+            default_heartbeat,
+            budget,
+        );
+        if !heart_beat.append_entries.is_empty() {
+            let _ = peer.send(QueryIO::AppendEntriesRPC(heart_beat)).await;
+        }
+    }
+
+    // * Term of the entry at `index`, 0 when neither the log nor its snapshot covers it
+    fn term_at(&self, index: u64) -> u64 {
+        self.logger.read_at(index).map(|op| op.term).unwrap_or_else(|| {
+            self.logger
+                .snapshot
+                .as_ref()
+                .filter(|s| s.last_included_index == index)
+                .map_or(0, |s| s.last_included_term)
+        })
+    }
+
+    // * When the entry has been compacted away, the snapshot stands in for it
+    fn entry_before(&self, append_entries: &[WriteOperation]) -> Option<(u64, u64)> {
+        let backup_index = append_entries[0].log_index - 1;
+        self.logger.read_at(backup_index).map(|op| (op.log_index, op.term)).or_else(|| {
+            self.logger
+                .snapshot
+                .as_ref()
+                .filter(|s| s.last_included_index == backup_index)
+                .map(|s| (s.last_included_index, s.last_included_term))
+        })
+    }
+
+    fn next_append_entries(
+        peer: &mut Peer,
+        match_index: u64,
+        append_entries: &[WriteOperation],
+        backup_entry: Option<(u64, u64)>,
+        mut heart_beat: HeartBeat,
+        budget: AppendEntriesBudget,
+    ) -> HeartBeat {
+        // * Entries already in flight are not sent again; the window caps how far ahead of the
+        // * replica's acknowledgements the leader gets
+        let sent_up_to = peer.sent_up_to();
+        let logs = budget.take(
+            append_entries
+                .iter()
+                .filter(|op| {
+                    op.log_index > sent_up_to && op.log_index <= match_index + MAX_INFLIGHT_ENTRIES
+                })
+                .cloned()
+                .collect(),
+        );
+        let Some(last_sent) = logs.last().map(|op| op.log_index) else {
+            return heart_beat;
+        };
+
+        // * Previous log is the last entry the replica has or was already sent
+        let prev_log = if sent_up_to == append_entries[0].log_index - 1 {
+            backup_entry
+        } else {
+            append_entries
+                .iter()
+                .find(|op| op.log_index == sent_up_to)
+                .map(|op| (op.log_index, op.term))
+        };
+        (heart_beat.prev_log_index, heart_beat.prev_log_term) = prev_log.unwrap_or((0, 0));
+
+        peer.mark_sent(last_sent);
+        heart_beat.set_append_entries(logs)
+    }
+
+    fn take_low_watermark(&self, targets: &HashSet<PeerIdentifier>) -> Option<u64> {
         self.members
             .values()
             .filter_map(|peer| {
-                if peer.is_replica(&self.replication.replid) {
+                if peer.is_replica(&self.replication.replid) && targets.contains(peer.id()) {
                     Some(peer.match_index())
                 } else {
                     None
@@ -769,7 +2314,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             info!("Received acks for log index num: {}", res.log_idx);
             if let Some(peer) = self.members.get_mut(&res.from) {
                 peer.set_match_index(res.log_idx);
-                peer.last_seen = Instant::now();
+                peer.seen(Instant::now());
             }
             consensus.increase_vote(res.from);
         }
@@ -778,16 +2323,100 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         }
 
-        // * Increase the high water mark
-        self.replication.hwm.fetch_add(1, Ordering::Relaxed);
+        // * Committing an entry commits every entry before it, including those replicated in the same
+        // * round, which replicas acknowledge only through the last index of the round
+        self.replication.hwm.fetch_max(res.log_idx, Ordering::Relaxed);
 
-        self.client_sessions.set_response(consensus.session_req.take());
-        let _ = consensus.callback.send(ConsensusClientResponse::LogIndex(res.log_idx));
+        let committed = self.consensus_tracker.take_up_to(res.log_idx);
+        for (log_idx, mut voting) in committed.into_iter().chain([(res.log_idx, consensus)]) {
+            self.client_sessions.set_response(voting.session_req.take());
+            let applying = self.applied.track(log_idx);
+            let _ = voting.callback.send(ConsensusClientResponse::LogIndex(log_idx, applying));
+        }
+    }
+
+    /// Answers the writes this node took as leader once it no longer is one, so that none is left
+    /// hanging: with `MOVED` to the new leader when it is known, and `TRYAGAIN` until then. Entries
+    /// already appended may still be committed by the new leader; retrying them is safe, as writes
+    /// are deduplicated by session. Appended writes without a session would be applied twice if
+    /// retried, so they are answered with an error saying their outcome is unknown instead.
+    pub(crate) fn redirect_in_flight_writes(&mut self) {
+        if self.replication.is_leader() {
+            return;
+        }
+        // * Writes stay blocked, if they were, until whatever blocked them is settled
+        let held: Vec<_> =
+            self.pending_requests.as_mut().map(|reqs| reqs.drain(..).collect()).unwrap_or_default();
+        if self.consensus_tracker.is_empty() && held.is_empty() {
+            return;
+        }
+        let reply = match self.known_leader.as_ref() {
+            | Some(leader) => format!("MOVED {}", leader.id),
+            | None => "TRYAGAIN leadership changed".to_string(),
+        };
+        warn!(
+            "No longer the leader, answering {} in-flight writes with {reply}",
+            self.consensus_tracker.len() + held.len()
+        );
+        for (_, voting) in self.consensus_tracker.drain() {
+            let reply = match voting.session_req {
+                | Some(_) => reply.clone(),
+                | None => "ERR write outcome unknown, leadership changed before it was committed"
+                    .to_string(),
+            };
+            let _ = voting.callback.send(ConsensusClientResponse::Err(reply));
+        }
+        for req in held {
+            let _ = req.callback.send(reply.clone().into());
+        }
+    }
+
+    /// Answers the writes that no majority acknowledged before their deadline. Their entries stay in
+    /// the log and tracked, so their clients are told to retry: once an entry is committed, the
+    /// leader applies it itself and records its session, which answers the retry.
+    pub(crate) fn expire_consensus_requests(&mut self, cache_manager: &CacheManager) {
+        for (log_idx, voting) in self.consensus_tracker.expired(Instant::now()) {
+            warn!("log {log_idx} was not acknowledged by a majority in time");
+            self.pending_write_stats.consensus_timed_out += 1;
+
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let callback = std::mem::replace(&mut voting.callback, tx.into());
+            let _ = callback.send(ConsensusClientResponse::Err(
+                "TRYAGAIN write was not acknowledged by a majority in time".into(),
+            ));
+
+            let request = self.logger.read_at(log_idx).map(|op| op.request);
+            let cache_manager = cache_manager.clone();
+            tokio::spawn(async move {
+                let (Some(request), Ok(ConsensusClientResponse::LogIndex(idx, applying))) =
+                    (request, rx.await)
+                else {
+                    return;
+                };
+                if let Err(e) = cache_manager.apply_log(request, idx).await {
+                    error!("failed to apply log: {e}")
+                }
+                drop(applying);
+            });
+        }
     }
 
     // Follower notified the leader of its acknowledgment, then leader store match index for the given follower
     async fn send_replication_ack(&mut self, send_to: &PeerIdentifier, ack: ReplicationAck) {
-        let Some(leader) = self.members.get_mut(send_to) else {
+        // * Chained replicas answer through the replica they take the log from, while it is connected
+        let self_id = self.replication.self_identifier();
+        let send_to = match self.replication.upstream.as_ref() {
+            | Some(upstream)
+                if self
+                    .members
+                    .get(upstream)
+                    .is_some_and(|peer| peer.upstream() != Some(&self_id)) =>
+            {
+                upstream.clone()
+            },
+            | _ => send_to.clone(),
+        };
+        let Some(leader) = self.members.get_mut(&send_to) else {
             return;
         };
         let _ = leader.send(ack).await;
@@ -808,11 +2437,16 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         };
 
+        let relayed = heartbeat.clone();
         self.replicate_state(heartbeat, cache_manager).await;
+        self.relay_append_entries(relayed).await;
     }
 
     async fn replicate_log_entries(&mut self, rpc: &mut HeartBeat) -> Result<(), RejectionReason> {
         if rpc.append_entries.is_empty() {
+            // * Lets the leader know it still has this replica's support
+            self.send_replication_ack(&rpc.from, ReplicationAck::heartbeat(&self.replication))
+                .await;
             return Ok(());
         }
         let mut entries = Vec::with_capacity(rpc.append_entries.len());
@@ -851,8 +2485,8 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
     ) -> Result<(), RejectionReason> {
         // Case: Empty log
         if self.logger.is_empty() {
-            if prev_log_index == 0 {
-                return Ok(()); // First entry, no previous log to check
+            if prev_log_index <= self.logger.snapshot_index() {
+                return Ok(()); // First entry or covered by the snapshot, no previous log to check
             }
             error!("Log is empty but leader expects an entry");
             return Err(RejectionReason::LogInconsistency); // Log empty but leader expects an entry
@@ -866,6 +2500,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                 // ! Term mismatch -> triggers log truncation
                 error!("Term mismatch: {} != {}", prev_entry.term, prev_log_term);
                 self.logger.truncate_after(prev_log_index);
+                self.transactions.truncate_after(prev_log_index);
 
                 return Err(RejectionReason::LogInconsistency);
             }
@@ -874,29 +2509,82 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         Ok(())
     }
 
+    #[instrument(level = tracing::Level::INFO, skip(self))]
+    /// Election timeout fired. A replica holds back one timeout per live replica of the shard
+    /// with a higher priority, giving the preferred ones the first chance to take over.
+    pub(crate) async fn on_election_timeout(&mut self) {
+        let outranked_by = self
+            .members
+            .values()
+            .filter(|peer| {
+                peer.is_replica(&self.replication.replid)
+                    && peer.priority() > self.replication.priority
+                    && !self.is_suspected(peer, Instant::now())
+            })
+            .count();
+        if self.deferred_elections < outranked_by {
+            self.deferred_elections += 1;
+            debug!("Deferring election ({}/{outranked_by})", self.deferred_elections);
+            return;
+        }
+        self.start_pre_vote().await;
+    }
+
+    pub(crate) async fn start_pre_vote(&mut self) {
+        warn!("Starting pre-vote for term {}", self.replication.term + 1);
+
+        let replica_count = self.replicas().count() as u8;
+        let self_id = self.replication.self_identifier();
+        self.replication.election_state.become_pre_candidate(&self_id, replica_count);
+
+        let request_vote = RequestVote {
+            term: self.replication.term + 1,
+            ..RequestVote::new(
+                &self.replication,
+                self.logger.last_log_index,
+                self.logger.last_log_term,
+            )
+        };
+
+        let pre_vote = SharedMessage::new(QueryIO::PreVote(request_vote));
+        broadcast(self.replicas_mut().map(|(peer, _)| peer), pre_vote).await;
+    }
+
     #[instrument(level = tracing::Level::INFO, skip(self))]
     pub(crate) async fn run_for_election(&mut self) {
         warn!("Running for election term {}", self.replication.term);
 
         self.become_candidate();
+        self.persist_hard_state();
         let request_vote = RequestVote::new(
             &self.replication,
             self.logger.last_log_index,
             self.logger.last_log_index,
         );
 
-        self.replicas_mut()
-            .map(|(peer, _)| peer.send(request_vote.clone()))
-            .collect::<FuturesUnordered<_>>()
-            .for_each(|_| async {})
+        broadcast(self.replicas_mut().map(|(peer, _)| peer), SharedMessage::new(request_vote))
             .await;
     }
 
+    pub(crate) fn persist_hard_state(&mut self) {
+        let state = HardState {
+            term: self.replication.term,
+            voted_for: self
+                .replication
+                .election_state
+                .voted_for(&self.replication.self_identifier()),
+        };
+        if let Err(err) = self.hard_state.save(state) {
+            error!("Failed to persist term and vote: {err}");
+        }
+    }
+
     fn reset_election_timeout(&mut self, leader_id: &PeerIdentifier) {
         if let Some(peer) = self.members.get_mut(leader_id) {
-            peer.last_seen = Instant::now();
+            peer.seen(Instant::now());
         }
         self.heartbeat_scheduler.reset_election_timeout();
+        self.deferred_elections = 0;
         self.replication.election_state = ElectionState::Follower { voted_for: None };
     }
 
@@ -905,6 +2593,8 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         if leader_hwm.hwm > old_hwm {
             debug!("Received commit offset {}", leader_hwm.hwm);
 
+            // * Read up front, so that a missing entry leaves the batch unapplied rather than half applied
+            let mut committed = Vec::with_capacity((leader_hwm.hwm - old_hwm) as usize);
             for log_index in (old_hwm + 1)..=leader_hwm.hwm {
                 let Some(log) = self.logger.read_at(log_index) else {
                     warn!("log has never been replicated!");
@@ -919,14 +2609,169 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                     .await;
                     return;
                 };
+                committed.push(log);
+            }
+
+            for log in &committed {
+                self.track_migration(&log.request);
+                self.transactions.track(&log.request, log.log_index);
+            }
+            // * Entries on different cache shards are applied side by side, those of a key in log order
+            cache_manager.apply_logs(committed).await;
+            self.replication.hwm.store(leader_hwm.hwm, Ordering::Release);
+        }
+    }
+
+    /// Snapshots are taken at the applied index, and only once the committed entries handed out are
+    /// applied: an entry applied while the shards are saved would otherwise be in the snapshot and
+    /// replayed on top of it again. Entries still waiting for consensus are not committed until the
+    /// snapshot is taken, so they never hold it back.
+    pub(crate) async fn maybe_compact_logs(&mut self, cache_manager: &CacheManager) {
+        let hwm = self.replication.hwm.load(Ordering::Acquire);
+        if !self.log_compaction.should_compact(self.applied.get(hwm), self.logger.snapshot_index())
+        {
+            return;
+        }
+        // * Nothing is committed while the actor waits, so the applied index can only catch up
+        let applied = self.applied.settle(hwm, APPLY_SETTLE_TIMEOUT).await;
+        if applied < hwm {
+            debug!("Compaction put off: entries up to {hwm} are still being applied");
+            return;
+        }
+        let Some(last_included_term) = self.logger.read_at(applied).map(|op| op.term) else {
+            return;
+        };
+
+        if let Err(err) = self.compact_logs(applied, last_included_term, cache_manager).await {
+            error!("failed to compact logs: {err}");
+        }
+    }
+
+    async fn compact_logs(
+        &mut self,
+        last_included_index: u64,
+        last_included_term: u64,
+        cache_manager: &CacheManager,
+    ) -> anyhow::Result<()> {
+        let data = cache_manager
+            .route_save(
+                SaveTarget::InMemory(vec![]),
+                Metadata {
+                    repl_id: self.replication.replid.clone(),
+                    log_idx: last_included_index,
+                    log_term: last_included_term,
+                    sessions: self.client_sessions.clone(),
+                    leases: Default::default(),
+                    transactions: self.transactions.snapshot(last_included_index),
+                },
+            )
+            .await?
+            .await??
+            .into_inner();
+
+        self.log_compaction.persist(&data).await?;
+        let snapshot = LogSnapshot { last_included_index, last_included_term, data };
+        self.logger.compact(snapshot, self.log_compaction.retained_segments)?;
+        info!("Compacted logs up to {last_included_index}");
+        Ok(())
+    }
+
+    // FOLLOWER side operation
+    #[instrument(level = tracing::Level::INFO, skip(self, cache_manager, snapshot), fields(peer_id = %snapshot.from))]
+    pub(crate) async fn install_snapshot(
+        &mut self,
+        snapshot: InstallSnapshot,
+        cache_manager: &CacheManager,
+    ) {
+        if snapshot.term < self.replication.term {
+            self.send_replication_ack(
+                &snapshot.from,
+                ReplicationAck::reject(
+                    self.logger.last_log_index,
+                    RejectionReason::ReceiverHasHigherTerm,
+                    &self.replication,
+                ),
+            )
+            .await;
+            return;
+        }
+        self.reset_election_timeout(&snapshot.from);
+        self.maybe_update_term(snapshot.term);
 
-                if let Err(e) = cache_manager.apply_log(log.request, log_index).await {
-                    // ! DON'T PANIC - post validation is where we just don't update state
-                    error!("failed to apply log: {e}")
-                }
-            }
-            self.replication.hwm.store(leader_hwm.hwm, Ordering::Release);
+        let from = snapshot.from.clone();
+        let snapshot = match self.snapshot_assembler.receive(snapshot) {
+            | Ok(Some(snapshot)) => snapshot,
+            | Ok(None) => return,
+            | Err(err) => {
+                // * The leader reverts the match index and streams the snapshot again from the start
+                err!("{}", err);
+                self.send_replication_ack(
+                    &from,
+                    ReplicationAck::reject(
+                        self.logger.last_log_index,
+                        RejectionReason::LogInconsistency,
+                        &self.replication,
+                    ),
+                )
+                .await;
+                return;
+            },
+        };
+
+        let last_included_index = snapshot.last_included_index;
+        if last_included_index > self.replication.hwm.load(Ordering::Acquire)
+            && let Err(err) = self.apply_install_snapshot(&snapshot, cache_manager).await
+        {
+            err!("{}", err);
+            self.send_replication_ack(
+                &snapshot.from,
+                ReplicationAck::reject(
+                    self.logger.last_log_index,
+                    RejectionReason::FailToWrite,
+                    &self.replication,
+                ),
+            )
+            .await;
+            return;
         }
+
+        self.send_replication_ack(
+            &snapshot.from,
+            ReplicationAck::ack(last_included_index, &self.replication),
+        )
+        .await;
+    }
+
+    async fn apply_install_snapshot(
+        &mut self,
+        snapshot: &InstallSnapshot,
+        cache_manager: &CacheManager,
+    ) -> anyhow::Result<()> {
+        let loaded = SnapshotLoader::load_from_bytes(&snapshot.data)?;
+        self.client_sessions = loaded.metadata.sessions.clone();
+        self.transactions = Transactions::restore(
+            loaded.metadata.transactions.clone(),
+            snapshot.last_included_index,
+        );
+        let leases = loaded.metadata.leases.clone();
+        let key_values = loaded.key_values();
+
+        cache_manager.drop_cache().await;
+        cache_manager.clone().apply_snapshot(key_values).await?;
+        cache_manager.restore_leases(leases).await?;
+
+        self.logger.install_snapshot(
+            LogSnapshot {
+                last_included_index: snapshot.last_included_index,
+                last_included_term: snapshot.last_included_term,
+                data: snapshot.data.clone(),
+            },
+            self.log_compaction.retained_segments,
+        )?;
+        self.replication.hwm.store(snapshot.last_included_index, Ordering::Release);
+        self.log_compaction.persist(&snapshot.data).await?;
+        info!("Installed snapshot up to {}", snapshot.last_included_index);
+        Ok(())
     }
 
     fn maybe_update_term(&mut self, new_term: u64) {
@@ -961,12 +2806,58 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         self.heartbeat_scheduler.turn_follower_mode().await;
     }
 
-    async fn become_leader(&mut self) {
+    async fn become_leader(&mut self, cache_manager: &CacheManager) {
         info!("\x1b[32mElection succeeded\x1b[0m");
 
         self.replication.role = ReplicationRole::Leader;
         self.replication.election_state = ElectionState::Leader;
+        // * Otherwise the previous leader would be taken for the current one once this node steps down
+        self.known_leader = None;
         self.heartbeat_scheduler.turn_leader_mode().await;
+        // * Checkpoints not yet committed are committed along with the no-op below
+        let hwm = self.replication.hwm.load(Ordering::Acquire);
+        for idx in (hwm + 1)..=self.logger.last_log_index {
+            if let Some(op) = self.logger.read_at(idx) {
+                self.track_migration(&op.request);
+                self.transactions.track(&op.request, op.log_index);
+            }
+        }
+        // * Transactions prepared under the previous leader still wait for their outcome
+        for txn_id in self.transactions.prepared_ids() {
+            self.schedule_transaction_resolution(txn_id);
+        }
+        self.append_no_op(cache_manager).await;
+        self.resume_migration(cache_manager).await;
+    }
+
+    /// A leader may only commit entries of its own term; entries left over from previous terms are committed
+    /// along with them. Appending a no-op right away advances the high water mark without waiting for a client write.
+    async fn append_no_op(&mut self, cache_manager: &CacheManager) {
+        let hwm = self.replication.hwm.load(Ordering::Acquire);
+        let uncommitted: Vec<(u64, WriteRequest)> = ((hwm + 1)..=self.logger.last_log_index)
+            .filter_map(|idx| self.logger.read_at(idx).map(|op| (idx, op.request)))
+            .collect();
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.req_consensus(ConsensusRequest::new(WriteRequest::NoOp, tx, None)).await;
+
+        if uncommitted.is_empty() {
+            return;
+        }
+        // * Followers apply these on the next heartbeat; the leader applies them once the no-op is committed
+        let applying = self.applied.track(hwm + 1);
+        let cache_manager = cache_manager.clone();
+        tokio::spawn(async move {
+            let Ok(ConsensusClientResponse::LogIndex(..)) = rx.await else {
+                return;
+            };
+            for (idx, request) in uncommitted {
+                if let Err(e) = cache_manager.apply_log(request, idx).await {
+                    error!("failed to apply log: {e}")
+                }
+            }
+            drop(applying);
+        });
     }
     fn become_candidate(&mut self) {
         let replica_count = self.replicas().count() as u8;
@@ -986,16 +2877,41 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                 info!("Log inconsistency, reverting match index");
                 //TODO we can refactor this to set match index to given log index from the follower
                 self.decrease_match_index(&repl_res.from, repl_res.log_idx);
+                self.catch_up_with_snapshot(&repl_res.from).await;
             },
             | RejectionReason::FailToWrite => {
                 info!("Follower failed to write log for technical reason, resend..");
+                if let Some(peer) = self.members.get_mut(&repl_res.from) {
+                    peer.rewind_window();
+                }
             },
         }
     }
 
+    /// A replica that fell behind the compacted log, or too far behind the log to be worth sending
+    /// entry by entry, is streamed a snapshot followed by the log tail. Without a recent enough snapshot,
+    /// one is taken on the next compaction tick and streamed from there.
+    async fn catch_up_with_snapshot(&mut self, peer_id: &PeerIdentifier) {
+        let Some(match_index) = self.members.get(peer_id).map(Peer::match_index) else {
+            return;
+        };
+        let snapshot_index = self.logger.snapshot_index();
+        if match_index < snapshot_index {
+            let (from, term) = (self.replication.self_identifier(), self.replication.term);
+            self.send_snapshot_to_lagging_replicas(from, term, &HashSet::from([peer_id.clone()]))
+                .await;
+            return;
+        }
+        if self.log_compaction.is_far_behind(match_index, self.logger.last_log_index) {
+            info!("{peer_id} is far behind at {match_index}, taking a snapshot to catch it up");
+            self.log_compaction.request_catchup();
+        }
+    }
+
     fn decrease_match_index(&mut self, from: &PeerIdentifier, current_log_idx: u64) {
         if let Some(peer) = self.members.get_mut(from) {
             peer.set_match_index(current_log_idx);
+            peer.rewind_window();
         }
     }
 
@@ -1061,6 +2977,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         if !self.replication.is_leader() {
             self.hash_ring = *new_ring;
             info!("Replica updated hash ring");
+            self.broadcast_topology_change();
             return;
         }
 
@@ -1072,54 +2989,121 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         if migration_plans.is_empty() {
             info!("No migration tasks to schedule");
             self.hash_ring = *new_ring;
-            let _ = self.node_change_broadcast.send(self.get_topology());
+            self.broadcast_topology_change();
             return;
         }
 
+        self.start_migration(*new_ring, migration_plans).await;
+    }
+
+    // * The migration is checkpointed in the log before any key moves, so that the next leader can resume it
+    async fn start_migration(
+        &mut self,
+        new_ring: HashRing,
+        migration_plans: BTreeMap<ReplicationId, Vec<MigrationTask>>,
+    ) {
         info!("Leader scheduling {} migration plan(s)", migration_plans.len());
         self.block_write_reqs();
+        let (tx, _) = tokio::sync::oneshot::channel();
+        self.req_consensus(ConsensusRequest::new(
+            WriteRequest::MigrationStart { ring: Box::new(new_ring.clone()) },
+            tx,
+            None,
+        ))
+        .await;
+        self.pending_ring = Some(new_ring);
 
-        let batch_handles = FuturesUnordered::new();
-        for (target_replid, mut migration_tasks) in migration_plans {
-            while !migration_tasks.is_empty() {
-                let mut num = 0;
-                let mut batch_to_migrate = Vec::new();
-
-                // Create a batch of tasks up to a certain size.
-                while let Some(task) = migration_tasks.pop() {
-                    num += task.key_len();
-                    batch_to_migrate.push(task);
-                    if num > 100 {
-                        break;
-                    }
-                }
+        let batches = migration_plans
+            .into_iter()
+            .flat_map(|(target_replid, migration_tasks)| {
+                self.migration_throttle
+                    .batches(migration_tasks)
+                    .into_iter()
+                    .map(move |tasks| MigrationBatch::new(target_replid.clone(), tasks))
+            })
+            .collect::<Vec<_>>();
+        self.migration_progress.plan(&batches);
+
+        tokio::spawn(Self::drive_migration(
+            batches,
+            self.self_handler.clone(),
+            self.migration_throttle,
+        ));
+    }
+
+    // * Sends the batches with at most `max_concurrent_batches` in flight, paced to the byte rate limit
+    async fn drive_migration(
+        batches: Vec<MigrationBatch>,
+        handler: ClusterCommandHandler,
+        throttle: MigrationThrottle,
+    ) {
+        let started = Instant::now();
+        let mut bytes_sent = 0;
+        let mut in_flight = FuturesUnordered::new();
+
+        let sent = |result: Result<anyhow::Result<usize>, tokio::task::JoinError>| match result {
+            | Ok(Ok(bytes)) => bytes as u64,
+            | Ok(Err(e)) => {
+                error!("Migration batch failed: {}", e);
+                0
+            },
+            | Err(e) => {
+                error!("Migration batch panicked: {}", e);
+                0
+            },
+        };
 
-                // Spawn each batch as a separate task for parallel execution
-                batch_handles.push(tokio::spawn(Self::schedule_migration_in_batch(
-                    MigrationBatch::new(target_replid.clone(), batch_to_migrate),
-                    self.self_handler.clone(),
-                )));
+        for batch in batches {
+            // * Account for the batches done so far, waiting only when the concurrency limit is reached
+            while let Some(Some(result)) = in_flight.next().now_or_never() {
+                bytes_sent += sent(result);
             }
+            if in_flight.len() >= throttle.max_concurrent_batches
+                && let Some(result) = in_flight.next().await
+            {
+                bytes_sent += sent(result);
+            }
+            if let Some(wait) = throttle.pace(bytes_sent, started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+            in_flight.push(tokio::spawn(Self::migrate_with_retry(
+                batch,
+                handler.clone(),
+                throttle,
+            )));
         }
+        while let Some(result) = in_flight.next().await {
+            sent(result);
+        }
+    }
 
-        // Process all batches
-        tokio::spawn(async move {
-            batch_handles
-                .for_each(
-                    |result: Result<Result<(), anyhow::Error>, tokio::task::JoinError>| async {
-                        if let Err(e) = result {
-                            error!("Migration batch panicked: {}", e);
-                        }
-                    },
-                )
-                .await;
-        });
+    async fn migrate_with_retry(
+        batch: MigrationBatch,
+        handler: ClusterCommandHandler,
+        throttle: MigrationThrottle,
+    ) -> anyhow::Result<usize> {
+        let mut attempt = 0;
+        loop {
+            match Self::schedule_migration_in_batch(batch.clone(), handler.clone()).await {
+                | Err(e) if attempt < throttle.max_retries => {
+                    let backoff = throttle.backoff(attempt);
+                    warn!("Migration batch {} rejected ({e}), retrying in {backoff:?}", batch.id.0);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                },
+                | Err(e) => {
+                    let _ = handler.send(SchedulerMessage::AbandonMigrationBatch(batch.id)).await;
+                    return Err(e);
+                },
+                | result => return result,
+            }
+        }
     }
 
     async fn schedule_migration_in_batch(
         batch: MigrationBatch,
         handler: ClusterCommandHandler,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<usize> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         handler.send(SchedulerMessage::ScheduleMigrationBatch(batch, tx.into())).await?;
         rx.await?
@@ -1129,7 +3113,7 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         &mut self,
         target: MigrationBatch,
         cache_manager: &CacheManager,
-        callback: impl Into<Callback<anyhow::Result<()>>>,
+        callback: impl Into<Callback<anyhow::Result<usize>>>,
     ) {
         let callback = callback.into();
         //  Find target peer based on replication ID
@@ -1158,11 +3142,44 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             return;
         };
 
-        self.pending_migrations
-            .as_mut()
-            .map(|p| p.insert(target.id.clone(), PendingMigrationBatch::new(callback, keys)));
+        self.migrating_keys.start(&keys, &peer_id);
+        let bytes = encoded_len(&cache_entries);
+        let timeout = self.migration_throttle.batch_timeout;
+        self.pending_migrations.as_mut().map(|p| {
+            let pending = PendingMigrationBatch::new(callback, keys)
+                .with_bytes(bytes)
+                .with_deadline(Instant::now() + timeout);
+            p.insert(target.id.clone(), pending)
+        });
+
+        // * A retry reuses the batch id, and applying the same entries again on the target is harmless
+        let batch = MigrateBatch {
+            batch_id: target.id.clone(),
+            cache_entries,
+            trace: TraceContext::current(),
+        };
+        let _ = target_peer.send(batch).await;
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let _ = handler.send(SchedulerMessage::ExpireMigrationBatch(target.id)).await;
+        });
+    }
 
-        let _ = target_peer.send(MigrateBatch { batch_id: target.id, cache_entries }).await;
+    // * Fails a batch whose acknowledgement never came, handing it back to the driver for a retry
+    pub(crate) fn expire_migration_batch(&mut self, batch_id: BatchId) {
+        let Some(pending) = self.pending_migrations.as_mut() else {
+            return;
+        };
+        if !pending.get(&batch_id).is_some_and(|batch| batch.is_expired(Instant::now())) {
+            return;
+        }
+        let Some(expired) = pending.remove(&batch_id) else {
+            return;
+        };
+        warn!("Migration batch {} timed out", batch_id.0);
+        self.migrating_keys.abort(&expired.keys);
+        let _ = expired.callback.send(res_err!("migration batch {} timed out", batch_id.0));
     }
 
     pub(crate) async fn receive_batch(
@@ -1195,20 +3212,28 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             let handler = self.self_handler.clone();
             let cache_manager = cache_manager.clone();
             async move {
-                if rx.await.is_ok() {
+                // * A rejected batch is retried by the source after a backoff
+                let response = rx.await;
+                let success = matches!(
+                    response,
+                    Ok(ConsensusClientResponse::LogIndex(..)
+                        | ConsensusClientResponse::AlreadyProcessed { .. })
+                );
+                if success {
                     let _ = cache_manager.route_mset(migrate_batch.cache_entries.clone()).await; // reflect state change
-                    let _ = handler
-                        .send(SchedulerMessage::SendBatchAck {
-                            batch_id: migrate_batch.batch_id,
-                            to: from,
-                        })
-                        .await;
-                    return;
+                } else {
+                    error!(
+                        "Failed to write some keys during migration for batch {}",
+                        migrate_batch.batch_id.0
+                    );
                 }
-                error!(
-                    "Failed to write some keys during migration for batch {}",
-                    migrate_batch.batch_id.0
-                );
+                let _ = handler
+                    .send(SchedulerMessage::SendBatchAck {
+                        batch_id: migrate_batch.batch_id,
+                        to: from,
+                        success,
+                    })
+                    .await;
             }
         });
     }
@@ -1229,12 +3254,20 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         };
 
         if !ack.success {
+            self.migrating_keys.abort(&pending_migration_batch.keys);
             let _ = pending_migration_batch
                 .callback
                 .send(res_err!("Failed to send migration completion signal for batch"));
             return;
         }
 
+        self.migrating_keys.complete(&pending_migration_batch.keys);
+        self.migration_progress.complete(
+            &ack.batch_id,
+            pending_migration_batch.keys.len(),
+            pending_migration_batch.bytes,
+        );
+
         // make consensus request for delete
         let (tx, rx) = tokio::sync::oneshot::channel();
         let w_req = ConsensusRequest::new(
@@ -1249,52 +3282,343 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             let cache_manager = cache_manager.clone();
 
             async move {
-                if rx.await.is_ok() {
+                // * Held until the delete is applied
+                if let Ok(_response) = rx.await {
                     let _ = cache_manager.route_delete(pending_migration_batch.keys).await; // reflect state change
                     let _ = handler.send(SchedulerMessage::TryUnblockWriteReqs).await;
-                    let _ = pending_migration_batch.callback.send(Ok(()));
+                    let _ =
+                        pending_migration_batch.callback.send(Ok(pending_migration_batch.bytes));
                 }
             }
         });
     }
 
+    // * Picks up a migration the previous leader checkpointed but never closed.
+    // * Keys already moved were deleted through the log, so only the remaining ones are sent again.
+    async fn resume_migration(&mut self, cache_manager: &CacheManager) {
+        let Some(mut ring) = self.pending_ring.take() else {
+            return;
+        };
+        // * Roll back a checkpoint the cluster has moved on from; the newer ring drives its own migration
+        if ring.last_modified < self.hash_ring.last_modified {
+            warn!("Dropping outdated migration checkpoint");
+            self.close_migration().await;
+            return;
+        }
+        ring.update_repl_leader(
+            self.replication.replid.clone(),
+            self.replication.self_identifier(),
+        );
+
+        let keys = cache_manager.route_keys(None).await;
+        let migration_plans = ring.misplaced_keys(&self.replication.replid, keys);
+        if migration_plans.is_empty() {
+            self.hash_ring = ring;
+            self.broadcast_topology_change();
+            self.close_migration().await;
+            return;
+        }
+        warn!("Resuming the migration left over by the previous leader");
+        self.start_migration(ring, migration_plans).await;
+    }
+
+    async fn close_migration(&mut self) {
+        let (tx, _) = tokio::sync::oneshot::channel();
+        self.req_consensus(ConsensusRequest::new(WriteRequest::MigrationEnd, tx, None)).await;
+    }
+
+    // * Followers keep the open migration checkpoint around in case they become leader
+    fn track_migration(&mut self, request: &WriteRequest) {
+        match request {
+            | WriteRequest::MigrationStart { ring } => self.pending_ring = Some(*ring.clone()),
+            | WriteRequest::MigrationEnd => self.pending_ring = None,
+            | _ => {},
+        }
+    }
+
+    // * Rolls back a batch given up on: its keys stay on this node, and `CLUSTER RESHARD` sends them again later.
+    // * Writes resume once the rest of the migration is settled.
+    pub(crate) async fn abandon_migration_batch(&mut self, batch_id: BatchId) {
+        error!("Giving up on migration batch {}", batch_id.0);
+        self.migration_progress.fail(&batch_id);
+        if let Some(abandoned) = self.pending_migrations.as_mut().and_then(|p| p.remove(&batch_id))
+        {
+            self.migrating_keys.abort(&abandoned.keys);
+        }
+        self.unblock_write_reqs_if_done().await;
+    }
+
     // New hash ring stored at this point with the current shard leaders
-    pub(crate) fn unblock_write_reqs_if_done(&mut self) {
-        let migrations_done = self.pending_migrations.as_ref().is_none_or(|p| p.is_empty());
+    pub(crate) async fn unblock_write_reqs_if_done(&mut self) {
+        // * Batches the driver has yet to send are not in `pending_migrations`, but still count
+        let migrations_done = self.pending_migrations.as_ref().is_none_or(|p| p.is_empty())
+            && self.migration_progress.is_done();
 
         if migrations_done {
-            if let Some(new_ring) = self.hash_ring.set_partitions(self.shard_leaders()) {
+            if let Some(new_ring) = self.pending_ring.take() {
+                self.hash_ring = new_ring;
+            }
+            // * A leaving node must not put itself back on the ring
+            if self.departure.is_none()
+                && let Some(new_ring) = self.hash_ring.set_weighted_partitions(self.shard_leaders())
+            {
                 self.hash_ring = new_ring;
             }
-            let _ = self.node_change_broadcast.send(self.get_topology());
-            if let Some(mut pending_reqs) = self.pending_requests.take() {
+            self.broadcast_topology_change();
+            if let Some(pending_reqs) = self.pending_requests.take() {
                 info!("All migrations complete, processing pending requests.");
                 self.pending_migrations = None;
+                self.migrating_keys.clear();
+                self.close_migration().await;
+                self.requeue_pending_requests(pending_reqs);
+                self.finish_departure().await;
+            }
+        }
+    }
 
-                if pending_reqs.is_empty() {
-                    return;
-                }
+    fn requeue_pending_requests(&self, mut pending_reqs: VecDeque<ConsensusRequest>) {
+        if pending_reqs.is_empty() {
+            return;
+        }
 
-                let handler = self.self_handler.clone();
-                tokio::spawn(async move {
-                    while let Some(req) = pending_reqs.pop_front() {
-                        if let Err(err) = handler
-                            .send(ClusterCommand::Client(ClientMessage::LeaderReqConsensus(req)))
-                            .await
-                        {
-                            error!("{}", err)
-                        }
-                    }
-                });
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            while let Some(req) = pending_reqs.pop_front() {
+                if let Err(err) = handler
+                    .send(ClusterCommand::Client(ClientMessage::LeaderReqConsensus(req)))
+                    .await
+                {
+                    error!("{}", err)
+                }
             }
-        }
+        });
     }
 
-    pub(crate) async fn send_batch_ack(&mut self, batch_id: BatchId, to: PeerIdentifier) {
+    pub(crate) async fn send_batch_ack(
+        &mut self,
+        batch_id: BatchId,
+        to: PeerIdentifier,
+        success: bool,
+    ) {
         let Some(peer) = self.members.get_mut(&to) else {
             return;
         };
-        let _ = peer.send(MigrationBatchAck::with_success(batch_id)).await;
+        let ack = if success {
+            MigrationBatchAck::with_success(batch_id)
+        } else {
+            MigrationBatchAck::with_reject(batch_id)
+        };
+        let _ = peer.send(ack).await;
+    }
+
+    /// Hands the current hash ring to the client handlers that route keys without the actor.
+    fn publish_routing(&self) {
+        self.routing.send_replace(RoutingTable {
+            replid: self.replication.replid.clone(),
+            hash_ring: Arc::new(self.hash_ring.clone()),
+        });
+    }
+
+    pub(crate) async fn forward_to_shard(
+        &mut self,
+        to: ReplicationId,
+        op: ForwardedOp,
+        callback: Callback<anyhow::Result<ForwardedReply>>,
+    ) {
+        let Some(peer_id) = self.peerid_by_replid(&to).cloned() else {
+            let _ = callback.send(res_err!("CLUSTERDOWN no node serves partition {to}"));
+            return;
+        };
+        let Some(peer) = self.members.get_mut(&peer_id) else {
+            let _ = callback.send(res_err!("CLUSTERDOWN no node serves partition {to}"));
+            return;
+        };
+
+        let id = ForwardId::generate();
+        if let Err(err) = peer.send(ForwardRequest { id: id.clone(), op }).await {
+            let _ = callback.send(res_err!("TRYAGAIN failed to reach {peer_id}: {err}"));
+            return;
+        }
+        self.pending_forwards.insert(id.clone(), callback);
+
+        // * A shard leader that never answers must not hold the client forever
+        let handler = self.self_handler.clone();
+        let timeout = std::time::Duration::from_millis(self.node_timeout as u64);
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let _ = handler.send(SchedulerMessage::ExpireForward(id)).await;
+        });
+    }
+
+    pub(crate) fn expire_forward(&mut self, id: ForwardId) {
+        if let Some(callback) = self.pending_forwards.remove(&id) {
+            let _ = callback.send(res_err!("TRYAGAIN forwarded request timed out"));
+        }
+    }
+
+    pub(crate) fn receive_forward_response(&mut self, response: ForwardResponse) {
+        let Some(callback) = self.pending_forwards.remove(&response.id) else {
+            warn!("Forward response {} came after it timed out", response.id.0);
+            return;
+        };
+        let _ = callback.send(Ok(response.reply));
+    }
+
+    /// Runs the part of a multi-key command another node forwarded here, provided this partition
+    /// still owns all of its keys.
+    pub(crate) async fn serve_forward(
+        &mut self,
+        request: ForwardRequest,
+        cache_manager: &CacheManager,
+        from: PeerIdentifier,
+    ) {
+        if let ForwardedOp::TxnStatus(txn_id) = &request.op {
+            let status = self.transaction_status(txn_id).await;
+            let handler = self.self_handler.clone();
+            tokio::spawn(async move {
+                let reply = ForwardedReply::Outcome(status.await.unwrap_or_default());
+                let response = ForwardResponse { id: request.id, reply };
+                let _ = handler
+                    .send(SchedulerMessage::SendForwardResponse { to: from, response })
+                    .await;
+            });
+            return;
+        }
+
+        let keys = request.op.keys();
+        let reply = match self.hash_ring.get_node_for_keys(&keys) {
+            | _ if keys.is_empty() => None,
+            | Ok(replid) if replid == self.replication.replid => None,
+            | Ok(replid) => Some(ForwardedReply::Err(format!("MOVED {replid}"))),
+            | Err(err) => Some(ForwardedReply::Err(err.to_string())),
+        };
+
+        let handler = self.self_handler.clone();
+        let cache_manager = cache_manager.clone();
+        tokio::spawn(async move {
+            let reply = match reply {
+                | Some(reply) => reply,
+                | None => request.op.execute(&handler, &cache_manager).await,
+            };
+            let response = ForwardResponse { id: request.id, reply };
+            let _ =
+                handler.send(SchedulerMessage::SendForwardResponse { to: from, response }).await;
+        });
+    }
+
+    pub(crate) async fn send_forward_response(
+        &mut self,
+        to: PeerIdentifier,
+        response: ForwardResponse,
+    ) {
+        if let Some(peer) = self.members.get_mut(&to) {
+            let _ = peer.send(response).await;
+        }
+    }
+
+    /// Outcome of a transaction this partition coordinates, answered once the entry recording it is
+    /// committed. A transaction still undecided when a participant asks is aborted, so that a
+    /// coordinator that failed midway cannot leave keys locked for good; its commit is rejected
+    /// should it come back. `None` leaves the participant to ask again later.
+    pub(crate) async fn transaction_status(
+        &mut self,
+        txn_id: &TxnId,
+    ) -> tokio::sync::oneshot::Receiver<Option<TxnOutcome>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let hwm = self.replication.hwm.load(Ordering::Acquire);
+        if let Some(outcome) = self.transactions.decision(txn_id, hwm) {
+            let _ = tx.send(Some(outcome));
+            return rx;
+        }
+        // * A decision not committed yet may still be lost along with this node's leadership
+        if self.transactions.proposed_decision(txn_id).is_some() || !self.replication.is_leader() {
+            let _ = tx.send(None);
+            return rx;
+        }
+
+        warn!("Aborting undecided transaction {}", txn_id.id);
+        let (consensus_tx, consensus_rx) = tokio::sync::oneshot::channel();
+        let request =
+            WriteRequest::TxnDecision { txn_id: txn_id.clone(), outcome: TxnOutcome::Abort };
+        self.req_consensus(ConsensusRequest::new(request, consensus_tx, None)).await;
+        // * Under group commit the abort is only queued here, and answered once committed
+        tokio::spawn(async move {
+            let outcome = match consensus_rx.await {
+                | Ok(ConsensusClientResponse::LogIndex(..)) => Some(TxnOutcome::Abort),
+                | _ => None,
+            };
+            let _ = tx.send(outcome);
+        });
+        rx
+    }
+
+    // * Leaders track transaction records as they append them, followers once they are committed
+    fn track_transaction(&mut self, request: &WriteRequest, log_index: u64) {
+        self.transactions.track(request, log_index);
+        if let WriteRequest::TxnPrepare { txn_id, .. } = request
+            && self.replication.is_leader()
+        {
+            self.schedule_transaction_resolution(txn_id.clone());
+        }
+    }
+
+    fn schedule_transaction_resolution(&self, txn_id: TxnId) {
+        let handler = self.self_handler.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(TXN_RESOLVE_INTERVAL).await;
+            let _ = handler.send(SchedulerMessage::ResolveTransaction(txn_id)).await;
+        });
+    }
+
+    /// Settles a transaction prepared here whose outcome never arrived, e.g. because its
+    /// coordinator failed between the two phases, by asking the coordinating partition for it.
+    pub(crate) async fn resolve_transaction(
+        &mut self,
+        txn_id: TxnId,
+        cache_manager: &CacheManager,
+    ) {
+        if !self.replication.is_leader() {
+            return;
+        }
+        let Some(requests) = self.transactions.prepared(&txn_id).cloned() else {
+            return;
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if txn_id.coordinator == self.replication.replid {
+            let status = self.transaction_status(&txn_id).await;
+            tokio::spawn(async move {
+                let _ = tx.send(Ok(ForwardedReply::Outcome(status.await.unwrap_or_default())));
+            });
+        } else {
+            let status = ForwardedOp::TxnStatus(txn_id.clone());
+            self.forward_to_shard(txn_id.coordinator.clone(), status, tx.into()).await;
+        }
+
+        let handler = self.self_handler.clone();
+        let cache_manager = cache_manager.clone();
+        tokio::spawn(async move {
+            let op = match rx.await {
+                | Ok(Ok(ForwardedReply::Outcome(Some(TxnOutcome::Commit)))) => {
+                    Some(ForwardedOp::Commit(txn_id.clone(), requests))
+                },
+                | Ok(Ok(ForwardedReply::Outcome(Some(TxnOutcome::Abort)))) => {
+                    Some(ForwardedOp::Abort(txn_id.clone()))
+                },
+                | _ => None,
+            };
+            if let Some(op) = op {
+                match op.execute(&handler, &cache_manager).await {
+                    | ForwardedReply::Err(err) => {
+                        error!("Failed to resolve transaction {}: {err}", txn_id.id)
+                    },
+                    | _ => return,
+                }
+            }
+            // * Asked again until the outcome is known and applied
+            tokio::time::sleep(TXN_RESOLVE_INTERVAL).await;
+            let _ = handler.send(SchedulerMessage::ResolveTransaction(txn_id)).await;
+        });
     }
 
     async fn update_cluster_members(
@@ -1303,11 +3627,19 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         hwm: u64,
         cluster_nodes: &[PeerState],
     ) {
-        self.update_peer_index(from, hwm);
+        // * The commit index a replica gossips trails the match index its acks report to the leader,
+        // * so it must not pull that match index back
+        let keeps_match_index = self.replication.is_leader()
+            && self.members.get(from).is_some_and(|peer| {
+                peer.is_replica(&self.replication.replid) && peer.match_index() >= hwm
+            });
+        if !keeps_match_index {
+            self.update_peer_index(from, hwm);
+        }
         let now = Instant::now();
         for node in cluster_nodes.iter() {
             if let Some(peer) = self.members.get_mut(node.id()) {
-                peer.last_seen = now;
+                peer.seen(now);
                 peer.set_role(node.role.clone())
             }
         }
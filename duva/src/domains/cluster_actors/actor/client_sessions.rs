@@ -1,13 +1,16 @@
 use crate::{domains::cluster_actors::SessionRequest, make_smart_pointer};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
 use uuid::Uuid;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ClientSessions(HashMap<Uuid, Session>);
 make_smart_pointer!(ClientSessions,HashMap<Uuid, Session>);
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Session {
     last_accessed: DateTime<Utc>,
     processed_req_id: Option<u64>,
@@ -37,4 +40,124 @@ impl ClientSessions {
         entry.last_accessed = Utc::now();
         entry.processed_req_id = Some(session_req.request_id);
     }
+
+    /// Forgets sessions with no request processed within `window`, returning how many were dropped.
+    /// A retry arriving after that is taken for a new request.
+    pub(crate) fn expire(&mut self, now: DateTime<Utc>, window: Duration) -> usize {
+        let Ok(window) = chrono::Duration::from_std(window) else { return 0 };
+        let before = self.len();
+        self.retain(|_, session| now - session.last_accessed < window);
+        before - self.len()
+    }
+}
+
+/// Session table counters reported in `INFO stats`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClientSessionStats {
+    pub(crate) sessions: usize,
+    // * Milliseconds a session is remembered after its last write, 0 for as long as the node runs
+    pub(crate) ttl: u64,
+    // * Retried writes answered from the session table instead of being applied again
+    pub(crate) duplicates_suppressed: u64,
+    pub(crate) expired: u64,
+}
+
+impl ClientSessionStats {
+    pub(crate) fn vectorize(self) -> Vec<String> {
+        vec![
+            format!("client_sessions:{}", self.sessions),
+            format!("client_session_ttl:{}", self.ttl),
+            format!("client_sessions_expired:{}", self.expired),
+            format!("duplicate_writes_suppressed:{}", self.duplicates_suppressed),
+        ]
+    }
+}
+
+/// Snapshot form: `client_id:request_id:last_accessed_ms` for each session, comma separated.
+impl Display for ClientSessions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sessions = self
+            .iter()
+            .filter_map(|(client_id, session)| {
+                let request_id = session.processed_req_id?;
+                Some(format!(
+                    "{client_id}:{request_id}:{}",
+                    session.last_accessed.timestamp_millis()
+                ))
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", sessions.join(","))
+    }
+}
+
+impl FromStr for ClientSessions {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut sessions = HashMap::new();
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            let mut parts = entry.split(':');
+            let (Some(client_id), Some(request_id), Some(last_accessed), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(anyhow::anyhow!("invalid client session '{entry}'"));
+            };
+            let last_accessed = DateTime::from_timestamp_millis(last_accessed.parse()?)
+                .ok_or_else(|| anyhow::anyhow!("invalid client session '{entry}'"))?;
+            sessions.insert(
+                client_id.parse()?,
+                Session { last_accessed, processed_req_id: Some(request_id.parse()?) },
+            );
+        }
+        Ok(Self(sessions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expire_forgets_idle_sessions() {
+        // GIVEN
+        let mut sessions = ClientSessions::default();
+        let idle = SessionRequest::new(1, Uuid::now_v7());
+        let active = SessionRequest::new(2, Uuid::now_v7());
+        sessions.set_response(Some(idle.clone()));
+        sessions.set_response(Some(active.clone()));
+        sessions.get_mut(&idle.client_id).unwrap().last_accessed =
+            Utc::now() - chrono::Duration::seconds(120);
+
+        // WHEN
+        let expired = sessions.expire(Utc::now(), Duration::from_secs(60));
+
+        // THEN
+        assert_eq!(expired, 1);
+        assert!(!sessions.is_processed(&Some(idle)));
+        assert!(sessions.is_processed(&Some(active)));
+    }
+
+    #[test]
+    fn test_snapshot_form_round_trips() {
+        // GIVEN
+        let mut sessions = ClientSessions::default();
+        sessions.set_response(Some(SessionRequest::new(7, Uuid::now_v7())));
+        sessions.set_response(Some(SessionRequest::new(9, Uuid::now_v7())));
+
+        // WHEN
+        let restored: ClientSessions = sessions.to_string().parse().unwrap();
+
+        // THEN - timestamps are kept to the millisecond
+        assert_eq!(restored.len(), 2);
+        for (client_id, session) in restored.iter() {
+            let original = &sessions[client_id];
+            assert_eq!(session.processed_req_id, original.processed_req_id);
+            assert_eq!(
+                session.last_accessed.timestamp_millis(),
+                original.last_accessed.timestamp_millis()
+            );
+        }
+        assert_eq!("".parse::<ClientSessions>().unwrap(), ClientSessions::default());
+        assert!("not-a-session".parse::<ClientSessions>().is_err());
+    }
 }
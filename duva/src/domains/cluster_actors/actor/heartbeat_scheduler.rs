@@ -1,18 +1,106 @@
 use crate::domains::cluster_actors::ClusterCommand;
-use std::{ops::Range, time::Duration};
-use tokio::{select, sync::mpsc::Sender, time::interval};
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::{collections::HashMap, ops::Range, time::Duration};
+use tokio::{
+    select,
+    sync::mpsc::Sender,
+    time::{Instant, interval},
+};
 use tracing::warn;
 
 use super::SchedulerMessage;
-const LEADER_HEARTBEAT_INTERVAL: u64 = 300;
+pub(crate) const LEADER_HEARTBEAT_INTERVAL: u64 = 300;
 pub const LEADER_HEARTBEAT_INTERVAL_MAX: u64 = LEADER_HEARTBEAT_INTERVAL * 5;
-const LEADER_HEARTBEAT_INTERVAL_RANGE: Range<u64> =
+pub(crate) const LEADER_HEARTBEAT_INTERVAL_RANGE: Range<u64> =
     LEADER_HEARTBEAT_INTERVAL * 3..LEADER_HEARTBEAT_INTERVAL_MAX;
 
+/// Interval of leader heartbeats and range election timeouts are drawn from, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RaftTimings {
+    pub(crate) heartbeat_interval: u64,
+    pub(crate) election_timeout: Range<u64>,
+}
+
+impl Default for RaftTimings {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: LEADER_HEARTBEAT_INTERVAL,
+            election_timeout: LEADER_HEARTBEAT_INTERVAL_RANGE,
+        }
+    }
+}
+
+impl RaftTimings {
+    pub(crate) fn new(
+        heartbeat_interval: u64,
+        election_timeout: Range<u64>,
+    ) -> anyhow::Result<Self> {
+        if heartbeat_interval == 0 {
+            anyhow::bail!("heartbeat interval must be greater than 0");
+        }
+        // * A follower has to miss more than one heartbeat before it stands for election
+        if election_timeout.start < heartbeat_interval.saturating_mul(2) {
+            anyhow::bail!(
+                "election timeout min ({}) must be at least twice the heartbeat interval ({heartbeat_interval})",
+                election_timeout.start
+            );
+        }
+        // * With a narrower spread, followers that lost the leader at once time out at once and split the vote
+        if election_timeout.end < election_timeout.start.saturating_add(heartbeat_interval) {
+            anyhow::bail!(
+                "election timeout max ({}) must exceed min ({}) by at least the heartbeat interval ({heartbeat_interval})",
+                election_timeout.end,
+                election_timeout.start
+            );
+        }
+        Ok(Self { heartbeat_interval, election_timeout })
+    }
+
+    /// Value of the `CONFIG` parameter named `parameter`.
+    pub(crate) fn get(&self, parameter: &str) -> Option<u64> {
+        match parameter {
+            | "raft-heartbeat-interval" => Some(self.heartbeat_interval),
+            | "election-timeout-min" => Some(self.election_timeout.start),
+            | "election-timeout-max" => Some(self.election_timeout.end),
+            | _ => None,
+        }
+    }
+
+    /// These timings with the `CONFIG` parameter named `parameter` set to `value`, if they still hold together.
+    pub(crate) fn with(&self, parameter: &str, value: u64) -> anyhow::Result<Self> {
+        let (mut heartbeat_interval, mut min, mut max) =
+            (self.heartbeat_interval, self.election_timeout.start, self.election_timeout.end);
+        match parameter {
+            | "raft-heartbeat-interval" => heartbeat_interval = value,
+            | "election-timeout-min" => min = value,
+            | "election-timeout-max" => max = value,
+            | _ => anyhow::bail!("Unsupported CONFIG parameter: {parameter}"),
+        }
+        Self::new(heartbeat_interval, min..max)
+    }
+
+    // * Drawn anew for every wait, so that followers and failed candidates do not time out in lockstep
+    fn election_timeout(&self) -> Duration {
+        Duration::from_millis(rand::random_range(self.election_timeout.clone()))
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct HeartBeatScheduler {
     cluster_handler: Sender<ClusterCommand>,
     controller: Option<SchedulerMode>,
+    timings: RaftTimings,
+    // * Last time the leader reached this node directly; gossip does not count
+    last_leader_contact: Option<Instant>,
+    // * Last time each replica answered this leader
+    replica_contacts: HashMap<PeerIdentifier, ReplicaContact>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ReplicaContact {
+    at: Instant,
+    // * false while the replica is within the grace period given when it is first seen
+    answered: bool,
 }
 
 impl HeartBeatScheduler {
@@ -24,16 +112,27 @@ impl HeartBeatScheduler {
         let jitter = rand::random::<u64>() % 100; // Add up to 100ms jitter
         let interval = cluster_heartbeat_interval + jitter;
 
+        let timings = RaftTimings::default();
         let controller = if is_leader_mode {
             SchedulerMode::Leader(Self::send_append_entries_rpc(
-                LEADER_HEARTBEAT_INTERVAL,
+                timings.heartbeat_interval,
                 cluster_handler.clone(),
             ))
         } else {
-            SchedulerMode::Follower(Self::start_election_timer(cluster_handler.clone()))
+            SchedulerMode::Follower(Self::start_election_timer(
+                cluster_handler.clone(),
+                timings.clone(),
+            ))
         };
 
-        Self { cluster_handler, controller: Some(controller) }.send_cluster_heartbeat(interval)
+        Self {
+            cluster_handler,
+            controller: Some(controller),
+            timings,
+            last_leader_contact: None,
+            replica_contacts: HashMap::new(),
+        }
+        .send_cluster_heartbeat(interval)
     }
 
     pub(crate) fn send_cluster_heartbeat(self, cluster_heartbeat_interval: u64) -> Self {
@@ -76,19 +175,21 @@ impl HeartBeatScheduler {
 
     pub(crate) fn start_election_timer(
         cluster_handler: Sender<ClusterCommand>,
+        timings: RaftTimings,
     ) -> tokio::sync::mpsc::Sender<ElectionTimeOutCommand> {
         let (tx, mut rx) = tokio::sync::mpsc::channel::<ElectionTimeOutCommand>(5);
 
         tokio::spawn(async move {
             loop {
                 select! {
-                    Some(msg) = rx.recv() => {
+                    // * The timer also stops once its sender is dropped
+                    msg = rx.recv() => {
                         match msg {
-                            ElectionTimeOutCommand::Stop => return,
-                            ElectionTimeOutCommand::Ping => {},
+                            Some(ElectionTimeOutCommand::Ping) => {},
+                            Some(ElectionTimeOutCommand::Stop) | None => return,
                         }
                     },
-                    _ =  tokio::time::sleep(Duration::from_millis(rand::random_range(LEADER_HEARTBEAT_INTERVAL_RANGE)))=>{
+                    _ =  tokio::time::sleep(timings.election_timeout())=>{
                         warn!("\x1b[33mElection timeout\x1b[0m");
                         let _ = cluster_handler.send(SchedulerMessage::StartLeaderElection.into()).await;
 
@@ -100,18 +201,124 @@ impl HeartBeatScheduler {
         tx
     }
 
-    pub(crate) fn reset_election_timeout(&self) {
+    pub(crate) fn timings(&self) -> &RaftTimings {
+        &self.timings
+    }
+
+    /// Takes on `timings`, restarting the leader heartbeats or the election timer with them.
+    pub(crate) fn retime(&mut self, timings: RaftTimings) {
+        self.timings = timings;
+        self.controller = match self.controller.take() {
+            | Some(SchedulerMode::Leader(sender)) => {
+                let _ = sender.send(());
+                Some(SchedulerMode::Leader(Self::send_append_entries_rpc(
+                    self.timings.heartbeat_interval,
+                    self.cluster_handler.clone(),
+                )))
+            },
+            | Some(SchedulerMode::Follower(_)) => Some(SchedulerMode::Follower(
+                Self::start_election_timer(self.cluster_handler.clone(), self.timings.clone()),
+            )),
+            | None => None,
+        };
+    }
+
+    pub(crate) fn reset_election_timeout(&mut self) {
+        self.last_leader_contact = Some(Instant::now());
         if let Some(SchedulerMode::Follower(tx)) = &self.controller {
             let _ = tx.try_send(ElectionTimeOutCommand::Ping);
         }
     }
 
+    pub(crate) fn heard_from_leader_recently(&self) -> bool {
+        self.last_leader_contact.is_some_and(|contact| {
+            contact.elapsed() < Duration::from_millis(self.timings.election_timeout.start)
+        })
+    }
+
+    pub(crate) fn record_replica_contact(&mut self, replica_id: &PeerIdentifier) {
+        self.replica_contacts
+            .insert(replica_id.clone(), ReplicaContact { at: Instant::now(), answered: true });
+    }
+
+    /// CheckQuorum - whether this leader, together with the replicas that answered it within an election timeout,
+    /// still forms a majority. A replica seen for the first time is given a full timeout to answer.
+    pub(crate) fn has_quorum(&mut self, replicas: Vec<PeerIdentifier>) -> bool {
+        let now = Instant::now();
+        self.replica_contacts.retain(|id, _| replicas.contains(id));
+
+        let reachable = replicas
+            .iter()
+            .filter(|id| {
+                let contact = self
+                    .replica_contacts
+                    .entry((*id).clone())
+                    .or_insert(ReplicaContact { at: now, answered: false });
+                now.duration_since(contact.at)
+                    < Duration::from_millis(self.timings.election_timeout.end)
+            })
+            .count();
+        Self::is_majority(reachable, replicas.len())
+    }
+
+    /// ReadIndex - whether the majority answered this leader after `requested_at`, or recently enough for the lease to hold.
+    pub(crate) fn confirms_leadership_since(
+        &self,
+        requested_at: Instant,
+        replicas: &[PeerIdentifier],
+    ) -> bool {
+        // * Followers refuse pre-votes for the minimum election timeout after hearing from the leader,
+        // * so a leader answered by the majority within a heartbeat interval cannot have been replaced
+        let since = Instant::now()
+            .checked_sub(Duration::from_millis(self.timings.heartbeat_interval))
+            .map_or(requested_at, |lease_start| lease_start.min(requested_at));
+
+        let answered = replicas
+            .iter()
+            .filter(|id| {
+                self.replica_contacts
+                    .get(*id)
+                    .is_some_and(|contact| contact.answered && contact.at >= since)
+            })
+            .count();
+        Self::is_majority(answered, replicas.len())
+    }
+
+    /// Replicas that answered this leader within `window`.
+    pub(crate) fn replicas_answered_within(
+        &self,
+        replicas: &[PeerIdentifier],
+        window: Duration,
+    ) -> usize {
+        replicas
+            .iter()
+            .filter(|id| {
+                self.replica_contacts
+                    .get(*id)
+                    .is_some_and(|contact| contact.answered && contact.at.elapsed() <= window)
+            })
+            .count()
+    }
+
+    // * The leader always counts itself
+    fn is_majority(reachable_replicas: usize, replica_count: usize) -> bool {
+        let cluster_size = replica_count + 1;
+        reachable_replicas + 1 > cluster_size / 2
+    }
+
+    #[cfg(test)]
+    pub(crate) fn expire_replica_contacts(&mut self) {
+        let expired = Instant::now() - Duration::from_millis(self.timings.election_timeout.end);
+        self.replica_contacts.values_mut().for_each(|contact| contact.at = expired);
+    }
+
     pub(crate) async fn turn_leader_mode(&mut self) {
+        self.replica_contacts.clear();
         let controller = match self.controller.take() {
             | Some(SchedulerMode::Follower(sender)) => {
                 let _ = sender.send(ElectionTimeOutCommand::Stop).await;
                 Some(SchedulerMode::Leader(Self::send_append_entries_rpc(
-                    LEADER_HEARTBEAT_INTERVAL,
+                    self.timings.heartbeat_interval,
                     self.cluster_handler.clone(),
                 )))
             },
@@ -127,6 +334,7 @@ impl HeartBeatScheduler {
                 let _ = sender.send(());
                 Some(SchedulerMode::Follower(Self::start_election_timer(
                     self.cluster_handler.clone(),
+                    self.timings.clone(),
                 )))
             },
             | Some(SchedulerMode::Follower(sender)) => Some(SchedulerMode::Follower(sender)),
@@ -165,22 +373,20 @@ mod tests {
     async fn test_leader_mode_initialization() {
         let (scheduler, _) = setup_scheduler(true).await;
 
-        match scheduler.controller {
-            | Some(SchedulerMode::Leader(_)) => assert!(true),
-            | Some(SchedulerMode::Follower(_)) => assert!(false, "Expected Leader mode"),
-            | None => assert!(false, "Expected Leader mode"),
-        }
+        assert!(
+            matches!(scheduler.controller, Some(SchedulerMode::Leader(_))),
+            "Expected Leader mode"
+        );
     }
 
     #[tokio::test]
     async fn test_follower_mode_initialization() {
         let (scheduler, _) = setup_scheduler(false).await;
 
-        match scheduler.controller {
-            | Some(SchedulerMode::Follower(_)) => assert!(true),
-            | Some(SchedulerMode::Leader(_)) => assert!(false, "Expected Follower mode"),
-            | None => todo!(),
-        }
+        assert!(
+            matches!(scheduler.controller, Some(SchedulerMode::Follower(_))),
+            "Expected Follower mode"
+        );
     }
 
     #[tokio::test]
@@ -242,7 +448,7 @@ mod tests {
     #[tokio::test]
     async fn test_election_timeout() {
         let (tx, mut _rx) = channel(10);
-        let controller = HeartBeatScheduler::start_election_timer(tx);
+        let controller = HeartBeatScheduler::start_election_timer(tx, RaftTimings::default());
 
         // Test stopping the election timeout
         let stop_result = timeout(Duration::from_millis(100), async {
@@ -255,7 +461,7 @@ mod tests {
 
         // Test election trigger after timeout
         let (tx2, mut rx2) = channel(10);
-        let _ = HeartBeatScheduler::start_election_timer(tx2);
+        let _timer = HeartBeatScheduler::start_election_timer(tx2, RaftTimings::default());
 
         let election_triggered =
             timeout(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL * 6), async {
@@ -273,10 +479,88 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_has_quorum_requires_recent_contact_with_majority() {
+        let (mut scheduler, _) = setup_scheduler(true).await;
+        let replicas =
+            vec![PeerIdentifier("127.0.0.1:6380".into()), PeerIdentifier("127.0.0.1:6381".into())];
+
+        // replicas seen for the first time are given a full timeout
+        assert!(scheduler.has_quorum(replicas.clone()));
+
+        // one replica answered recently - together with the leader it is still a majority
+        scheduler.expire_replica_contacts();
+        scheduler.record_replica_contact(&replicas[0]);
+        assert!(scheduler.has_quorum(replicas.clone()));
+
+        // no replica answered
+        scheduler.expire_replica_contacts();
+        assert!(!scheduler.has_quorum(replicas));
+    }
+
+    #[tokio::test]
+    async fn test_confirms_leadership_only_with_answers_from_majority() {
+        let (mut scheduler, _) = setup_scheduler(true).await;
+        let replicas =
+            vec![PeerIdentifier("127.0.0.1:6380".into()), PeerIdentifier("127.0.0.1:6381".into())];
+
+        // the grace period given to new replicas does not confirm anything
+        assert!(scheduler.has_quorum(replicas.clone()));
+        let requested_at = Instant::now();
+        assert!(!scheduler.confirms_leadership_since(requested_at, &replicas));
+
+        scheduler.record_replica_contact(&replicas[1]);
+        assert!(scheduler.confirms_leadership_since(requested_at, &replicas));
+
+        // answers older than the lease do not count
+        scheduler.expire_replica_contacts();
+        assert!(!scheduler.confirms_leadership_since(Instant::now(), &replicas));
+    }
+
+    #[test]
+    fn test_raft_timings_must_hold_together() {
+        assert!(RaftTimings::new(100, 200..300).is_ok());
+        assert!(RaftTimings::new(0, 200..300).is_err());
+        // a follower would stand for election after missing a single heartbeat
+        assert!(RaftTimings::new(100, 150..300).is_err());
+        // timeouts drawn from too narrow a spread
+        assert!(RaftTimings::new(100, 200..250).is_err());
+
+        let timings = RaftTimings::default();
+        let retimed = timings.with("election-timeout-max", 2000).unwrap();
+        assert_eq!(retimed.get("election-timeout-max"), Some(2000));
+        assert_eq!(retimed.get("election-timeout-min"), timings.get("election-timeout-min"));
+        assert!(timings.with("raft-heartbeat-interval", 500).is_err());
+        assert!(timings.with("hz", 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retime_restarts_leader_heartbeats_at_the_new_interval() {
+        // GIVEN
+        let (tx, mut rx) = channel(100);
+        let mut scheduler = HeartBeatScheduler::run(tx, true, 60_000);
+        // * drains the ticks that come right away
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        while rx.try_recv().is_ok() {}
+
+        // WHEN
+        scheduler.retime(RaftTimings::new(20, 100..200).unwrap());
+        tokio::time::sleep(Duration::from_millis(110)).await;
+
+        // THEN
+        let mut append_entries = 0;
+        while let Ok(cmd) = rx.try_recv() {
+            if matches!(cmd, ClusterCommand::Scheduler(SchedulerMessage::SendAppendEntriesRPC)) {
+                append_entries += 1;
+            }
+        }
+        assert!(append_entries >= 3, "got {append_entries} heartbeats");
+    }
+
     #[tokio::test]
     async fn test_update_leader_heartbeat() {
         let (tx, _rx) = channel(10);
-        let controller = HeartBeatScheduler::start_election_timer(tx);
+        let controller = HeartBeatScheduler::start_election_timer(tx, RaftTimings::default());
 
         // Test sending UpdateLeaderHeartBeat command
         let ping_result = timeout(Duration::from_millis(100), async {
@@ -1,5 +1,10 @@
 use tokio::sync::RwLock;
 
+use crate::domains::peers::banlist::BanList;
+use crate::domains::peers::connections::cluster_secret::ClusterSecret;
+#[cfg(feature = "tls")]
+use crate::domains::peers::connections::tls::tests::TestCa;
+
 use super::*;
 
 #[tokio::test]
@@ -47,26 +52,52 @@ async fn test_cluster_nodes() {
 
     let mut temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
     write!(temp_file, "{file_content}").expect("Failed to write to temp file");
-    let nodes = PeerState::from_file(temp_file.path().to_str().unwrap());
+    let nodes = PeerState::from_file(temp_file.path().to_str().unwrap(), &KeyRing::default());
 
     for value in nodes {
         assert!(res.contains(&value));
     }
 }
 
+#[tokio::test]
+async fn test_unchanged_node_list_is_gossiped_as_its_digest() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (buf, _) = cluster_actor.test_add_peer(6379, None, false);
+
+    // WHEN
+    cluster_actor.send_cluster_heartbeat().await;
+    cluster_actor.send_cluster_heartbeat().await;
+
+    // THEN - the node list goes out once, then only its digest
+    let mut sent = buf.lock().await;
+    let Some(QueryIO::ClusterHeartBeat(full)) = sent.pop_front() else { panic!() };
+    let Some(QueryIO::ClusterHeartBeat(digest_only)) = sent.pop_front() else { panic!() };
+    assert_eq!(full.cluster_nodes.len(), 2);
+    assert_ne!(full.nodes_digest, 0);
+    assert!(digest_only.cluster_nodes.is_empty());
+    assert_eq!(digest_only.nodes_digest, full.nodes_digest);
+
+    // * The receiver reads the digest-only heartbeat as the list it got before
+    let from = full.from.clone();
+    let mut views = GossipViews::default();
+    views.resolve(&from, full.nodes_digest, full.cluster_nodes.clone());
+    assert_eq!(views.resolve(&from, digest_only.nodes_digest, vec![]), full.cluster_nodes);
+}
+
 #[tokio::test]
 async fn test_store_current_topology() {
     // GIVEN
     let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
     let path = "test_store_current_topology.tp";
-    cluster_actor.topology_writer = std::fs::File::create(path).unwrap();
+    cluster_actor.topology_writer = TopologyWriter::spawn(path);
 
     let repl_id = cluster_actor.replication.replid.clone();
     let self_id = cluster_actor.replication.self_identifier();
     let hwm = cluster_actor.replication.hwm.load(Ordering::Relaxed);
 
     // WHEN
-    cluster_actor.snapshot_topology().await.unwrap();
+    cluster_actor.flush().await.unwrap();
 
     // THEN
     let topology = tokio::fs::read_to_string(path).await.unwrap();
@@ -77,6 +108,30 @@ async fn test_store_current_topology() {
     tokio::fs::remove_file(path).await.unwrap();
 }
 
+#[tokio::test]
+async fn test_store_encrypted_topology() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_str().unwrap();
+    let keys: KeyRing =
+        "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".parse().unwrap();
+    cluster_actor.topology_writer = TopologyWriter::spawn(path);
+    cluster_actor.topology_encryption = keys.clone();
+
+    // WHEN
+    cluster_actor.flush().await.unwrap();
+
+    // THEN
+    let self_id = cluster_actor.replication.self_identifier();
+    let stored = std::fs::read(path).unwrap();
+    assert!(!stored.windows(self_id.len()).any(|window| window == self_id.as_bytes()));
+    let nodes = PeerState::from_file(path, &keys);
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].id(), &self_id);
+    assert!(PeerState::from_file(path, &KeyRing::default()).is_empty());
+}
+
 #[tokio::test]
 async fn test_reconnection_on_gossip() {
     // GIVEN
@@ -94,7 +149,8 @@ async fn test_reconnection_on_gossip() {
     // Spawn the listener task
     let handle = tokio::spawn(async move {
         let (stream, _) = listener.accept().await.unwrap();
-        let mut inbound_stream = InboundStream::new(stream, replication_state.clone());
+        let mut inbound_stream =
+            InboundStream::accept(stream, replication_state.clone()).await.unwrap();
         if inbound_stream.recv_handshake().await.is_ok() {
             let _ = tx.send(());
         };
@@ -114,6 +170,78 @@ async fn test_reconnection_on_gossip() {
     assert!(rx.await.is_ok());
 }
 
+/// Whether a node requiring `acceptor_secret` completes the handshake of a peer connecting with
+/// `connector_secret`.
+async fn peer_handshake_succeeds(
+    port: u16,
+    connector_secret: Option<&str>,
+    acceptor_secret: Option<&str>,
+) -> bool {
+    handshake_succeeds(
+        port,
+        |connector| connector.cluster_secret = connector_secret.map(ClusterSecret::new),
+        |acceptor| acceptor.cluster_secret = acceptor_secret.map(ClusterSecret::new),
+    )
+    .await
+}
+
+/// Whether a node set up by `acceptor` completes the handshake of a peer set up by `connector`.
+async fn handshake_succeeds(
+    port: u16,
+    connector: impl FnOnce(&mut ReplicationState),
+    acceptor: impl FnOnce(&mut ReplicationState),
+) -> bool {
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let mut replication_state = cluster_actor.replication.clone();
+    replication_state.role = ReplicationRole::Follower;
+    acceptor(&mut replication_state);
+    connector(&mut cluster_actor.replication);
+
+    let listener = TcpListener::bind(format!("127.0.0.1:{port}")).await.unwrap();
+    let accepted = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let Ok(mut inbound_stream) = InboundStream::accept(stream, replication_state).await else {
+            return false;
+        };
+        inbound_stream.recv_handshake().await.is_ok()
+    });
+
+    cluster_actor
+        .join_peer_network_if_absent(vec![PeerState::new(
+            &format!("127.0.0.1:{}", port - 10000),
+            0,
+            cluster_actor.replication.replid.clone(),
+            ReplicationRole::Follower,
+        )])
+        .await;
+    accepted.await.unwrap()
+}
+
+#[tokio::test]
+async fn test_peer_handshake_requires_cluster_secret() {
+    assert!(peer_handshake_succeeds(44456, Some("s3cret"), Some("s3cret")).await);
+    assert!(!peer_handshake_succeeds(44457, Some("other"), Some("s3cret")).await);
+    assert!(!peer_handshake_succeeds(44458, None, Some("s3cret")).await);
+    // * Nor does a node with the secret join one that doesn't ask for it
+    assert!(!peer_handshake_succeeds(44459, Some("s3cret"), None).await);
+}
+
+#[tokio::test]
+#[cfg(feature = "tls")]
+async fn test_peer_handshake_over_tls_requires_certificate_of_cluster_ca() {
+    let (ca, other_ca) = (TestCa::new(), TestCa::new());
+    let tls = |ca: &TestCa| {
+        let tls = ca.peer_tls();
+        move |replication: &mut ReplicationState| replication.tls = Some(tls)
+    };
+
+    assert!(handshake_succeeds(44460, tls(&ca), tls(&ca)).await);
+    assert!(!handshake_succeeds(44461, tls(&other_ca), tls(&ca)).await);
+    // * Nor does a peer get in without TLS, or a node with TLS join one without it
+    assert!(!handshake_succeeds(44462, |_| {}, tls(&ca)).await);
+    assert!(!handshake_succeeds(44463, tls(&ca), |_| {}).await);
+}
+
 #[tokio::test]
 async fn test_topology_broadcast_on_hash_ring_change() {
     // GIVEN
@@ -133,10 +261,9 @@ async fn test_topology_broadcast_on_hash_ring_change() {
     let task = tokio::spawn({
         let topology = topology.clone();
         async move {
-            while let Ok(tp) = subscriber.recv().await {
+            if let Ok(tp) = subscriber.recv().await {
                 let mut guard = topology.write().await;
                 *guard = tp;
-                break;
             }
         }
     });
@@ -144,7 +271,7 @@ async fn test_topology_broadcast_on_hash_ring_change() {
     cluster_actor.block_write_reqs();
 
     // WHEN
-    cluster_actor.unblock_write_reqs_if_done();
+    cluster_actor.unblock_write_reqs_if_done().await;
     let _ = task.await;
 
     // THEN
@@ -205,9 +332,9 @@ async fn test_shard_leaders() {
 
     // Verify all expected leaders are present
     let expected_leaders = vec![
-        (shard1_replid.clone(), leader1_id.clone()),
-        (shard2_replid.clone(), leader2_id.clone()),
-        (shard3_replid.clone(), leader3_id.clone()),
+        (shard1_replid.clone(), leader1_id.clone(), 1),
+        (shard2_replid.clone(), leader2_id.clone(), 1),
+        (shard3_replid.clone(), leader3_id.clone(), 1),
     ];
 
     for expected_leader in expected_leaders {
@@ -255,3 +382,235 @@ async fn test_add_peer_for_follower_send_heartbeat() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn test_remove_idle_peers_drops_suspected_peers_only() {
+    // GIVEN: node_timeout of 100ms, so a steady peer is tolerated for about that long
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, silent_id) = cluster_actor.test_add_peer(6379, None, false);
+    let (_, alive_id) = cluster_actor.test_add_peer(6380, None, false);
+
+    // WHEN
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    cluster_actor.members.get_mut(&alive_id).unwrap().seen(Instant::now());
+    let suspicion = cluster_actor.peer_suspicion();
+    cluster_actor.remove_idle_peers().await;
+
+    // THEN
+    let phi_of = |id: &PeerIdentifier| suspicion.iter().find(|(p, _)| p == id).unwrap().1;
+    assert!(phi_of(&silent_id) > cluster_actor.phi_threshold);
+    assert!(phi_of(&alive_id) < 1.0);
+    assert!(!cluster_actor.members.contains_key(&silent_id));
+    assert!(cluster_actor.members.contains_key(&alive_id));
+}
+
+#[tokio::test]
+async fn test_lost_peers_are_retried_unless_banned() {
+    fn links_of(cluster_actor: &ClusterActor<MemoryOpLogs>, id: &PeerIdentifier) -> Vec<LinkState> {
+        cluster_actor
+            .cluster_node_links()
+            .into_iter()
+            .filter(|(state, _)| state.id() == id)
+            .map(|(_, link)| link)
+            .collect()
+    }
+
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, lost_id) = cluster_actor.test_add_peer(6379, None, false);
+    let (_, forgotten_id) = cluster_actor.test_add_peer(6380, None, false);
+
+    // WHEN
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    cluster_actor.remove_idle_peers().await;
+    cluster_actor.forget_peer(forgotten_id.clone(), false).await.unwrap();
+
+    // THEN - only the peer that went silent is retried
+    assert_eq!(links_of(&cluster_actor, &lost_id), vec![LinkState::Disconnected]);
+    assert_eq!(links_of(&cluster_actor, &forgotten_id), vec![LinkState::Banned]);
+    let self_id = cluster_actor.replication.self_identifier();
+    assert_eq!(links_of(&cluster_actor, &self_id), vec![LinkState::Connected]);
+    let retried = cluster_actor.reconnects.due(Instant::now() + Duration::from_secs(60));
+    assert_eq!(retried, vec![lost_id.clone()]);
+
+    // WHEN the lost peer connects again
+    let (_, peer) = Helper::create_peer(
+        cluster_actor.self_handler.clone(),
+        0,
+        &cluster_actor.replication.replid.clone(),
+        6379,
+        ReplicationRole::Follower,
+        FakeReadWrite::new(),
+    );
+    cluster_actor.add_peer(peer, None).await;
+
+    // THEN
+    assert_eq!(links_of(&cluster_actor, &lost_id), vec![LinkState::Connected]);
+}
+
+#[tokio::test]
+async fn test_rejoin_retries_stored_peers_and_lays_out_their_ring() {
+    // GIVEN - the topology a previous run of this node left behind
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_str().unwrap();
+    let stored = [
+        "127.0.0.1:6379 master 0 10 leader",
+        "127.0.0.1:8080 myself,master 0 10 follower",
+        "127.0.0.1:6390 other 0 4 leader",
+        "127.0.0.1:6391 other 0 4 follower",
+        "banned 127.0.0.1:6391 1 permanent",
+    ];
+    std::fs::write(path, stored.join("\r\n")).unwrap();
+    cluster_actor.replication.banlist = BanList::from_file(path, &KeyRing::default());
+
+    // WHEN
+    cluster_actor.rejoin(PeerState::from_file(path, &KeyRing::default()));
+
+    // THEN - every peer but the banned one is retried, and stays in the topology file meanwhile
+    let retried = cluster_actor.reconnects.due(Instant::now() + Duration::from_secs(60));
+    assert_eq!(
+        retried,
+        vec![PeerIdentifier("127.0.0.1:6379".into()), PeerIdentifier("127.0.0.1:6390".into())]
+    );
+    cluster_actor.topology_writer = TopologyWriter::spawn(path);
+    cluster_actor.flush().await.unwrap();
+    let nodes = PeerState::from_file(path, &KeyRing::default());
+    assert_eq!(nodes.len(), 3);
+
+    // THEN - keys are routed to the stored shard leaders until the cluster sends its ring
+    let shards: Vec<_> = cluster_actor.hash_ring.token_ranges().into_keys().collect();
+    assert_eq!(
+        shards,
+        vec![ReplicationId::Key("master".into()), ReplicationId::Key("other".into())]
+    );
+    assert_eq!(cluster_actor.hash_ring.last_modified, 0);
+}
+
+#[tokio::test]
+async fn test_cluster_shards() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, replica_id) = cluster_actor.test_add_peer(6379, None, false);
+    let other_replid = ReplicationId::Key("other".into());
+    let (_, other_leader) = cluster_actor.test_add_peer(7001, Some(other_replid.clone()), true);
+    let (_, other_replica) = cluster_actor.test_add_peer(7002, Some(other_replid.clone()), false);
+    let self_id = cluster_actor.replication.self_identifier();
+    let replid = cluster_actor.replication.replid.clone();
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (replid.clone(), self_id.clone()),
+        (other_replid.clone(), other_leader.clone()),
+    ]);
+
+    // WHEN
+    let shards = cluster_actor.cluster_shards();
+
+    // THEN
+    assert_eq!(shards.len(), 2);
+    let mine = shards.iter().find(|s| s.replid == replid).unwrap();
+    assert_eq!(mine.leader, self_id);
+    assert_eq!(mine.replicas, vec![replica_id]);
+    let other = shards.iter().find(|s| s.replid == other_replid).unwrap();
+    assert_eq!(other.leader, other_leader);
+    assert_eq!(other.replicas, vec![other_replica]);
+    assert_eq!(mine.ranges, cluster_actor.hash_ring.token_ranges()[&replid]);
+}
+
+#[tokio::test]
+async fn test_cluster_leave_follower_announces_departure() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (leader_buf, _) = cluster_actor.test_add_peer(7101, None, true);
+    let mut shutdown = cluster_actor.shutdown.subscribe();
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (callback, callback_rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor.cluster_leave(&cache_manager, callback.into()).await;
+
+    // THEN
+    callback_rx.await.unwrap().unwrap();
+    let QueryIO::ClusterHeartBeat(HeartBeat { ban_list, .. }) =
+        leader_buf.lock().await.pop_front().unwrap()
+    else {
+        panic!("departure must be announced")
+    };
+    assert!(
+        ban_list.iter().any(|banned| banned.p_id == cluster_actor.replication.self_identifier())
+    );
+    assert!(cluster_actor.members.is_empty());
+    assert!(*shutdown.borrow_and_update());
+}
+
+#[tokio::test]
+async fn test_cluster_leave_hands_off_leadership_to_replica() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (replica_buf, _) = cluster_actor.test_add_peer(7111, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (callback, _callback_rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor.cluster_leave(&cache_manager, callback.into()).await;
+
+    // THEN - the replica keeps the data, so nothing is migrated
+    assert!(!cluster_actor.replication.is_leader());
+    assert!(cluster_actor.pending_ring.is_none());
+    let sent = replica_buf.lock().await.drain(..).collect::<Vec<_>>();
+    assert!(sent.iter().any(|msg| matches!(msg, QueryIO::TimeoutNow(_))));
+}
+
+#[tokio::test]
+async fn test_cluster_leave_drains_keys_before_departing() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let other_replid = ReplicationId::Key("other_shard".into());
+    let (_, other_id) = cluster_actor.test_add_peer(7121, Some(other_replid.clone()), true);
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (other_replid.clone(), other_id),
+    ]);
+    let local_keys = (0..50)
+        .map(|i| format!("key:{i}"))
+        .filter(|key| cluster_actor.hash_ring.get_node_for_key(key) != Some(&other_replid))
+        .collect::<Vec<_>>();
+    let (_hwm, cache_manager) = Helper::cache_manager_with_keys(local_keys).await;
+    let mut shutdown = cluster_actor.shutdown.subscribe();
+    let (callback, mut callback_rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor.cluster_leave(&cache_manager, callback.into()).await;
+
+    // THEN - the node stays until its keys are moved
+    assert!(cluster_actor.pending_requests.is_some());
+    assert!(callback_rx.try_recv().is_err());
+    let ring = cluster_actor.pending_ring.clone().unwrap();
+    assert_eq!(ring.get_pnode_count(), 1);
+    assert!(ring.get_node_id(&cluster_actor.replication.replid).is_none());
+
+    // WHEN - every batch is acknowledged
+    cluster_actor.pending_migrations = Some(HashMap::new());
+    cluster_actor.migration_progress = MigrationProgress::default();
+    cluster_actor.unblock_write_reqs_if_done().await;
+
+    // THEN
+    callback_rx.await.unwrap().unwrap();
+    assert_eq!(cluster_actor.hash_ring, ring);
+    assert!(*shutdown.borrow_and_update());
+}
+
+#[tokio::test]
+async fn test_cluster_leave_rejects_last_partition() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (callback, callback_rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor.cluster_leave(&cache_manager, callback.into()).await;
+
+    // THEN
+    assert!(callback_rx.await.unwrap().is_err());
+    assert!(cluster_actor.replication.is_leader());
+    assert!(!*cluster_actor.shutdown.borrow());
+}
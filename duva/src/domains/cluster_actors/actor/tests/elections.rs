@@ -1,9 +1,12 @@
 use super::*;
 
+use crate::domains::caches::cache_objects::CacheValue;
 use crate::domains::cluster_actors::consensus::election::ElectionState;
 use crate::domains::cluster_actors::consensus::election::ElectionVoting;
+use crate::domains::cluster_actors::consensus::hard_state::HardStateStore;
 use crate::domains::peers::command::ElectionVote;
 use crate::domains::peers::command::RequestVote;
+use crate::domains::peers::command::TimeoutNow;
 
 #[tokio::test]
 async fn test_run_for_election_transitions_to_candidate_and_sends_request_votes() {
@@ -93,6 +96,51 @@ async fn test_vote_election_grant_vote() {
     .await;
 }
 
+#[tokio::test]
+async fn test_vote_election_persists_vote_across_restart() {
+    // GIVEN: A follower actor that persists its hard state
+    let dir = TempDir::new().unwrap();
+    let state_path = dir.path().join("raft.state");
+    let mut follower_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    follower_actor.hard_state = HardStateStore::new(Some(state_path.clone()));
+    let initial_term = follower_actor.replication.term;
+    let (_, candidate_id) = follower_actor.test_add_peer(8012, None, false);
+
+    // WHEN: It grants a vote and restarts
+    follower_actor
+        .vote_election(RequestVote {
+            term: initial_term + 1,
+            candidate_id: candidate_id.clone(),
+            last_log_index: 1,
+            last_log_term: 1,
+        })
+        .await;
+
+    let topology_writer = TopologyWriter::spawn(dir.path().join("duva.tp"));
+    let restarted = ClusterActor::new(
+        100,
+        ReplicationState::new(
+            ReplicationId::Key("master".into()),
+            ReplicationRole::Follower,
+            "127.0.0.1",
+            8080,
+            0,
+        ),
+        100,
+        topology_writer,
+        MemoryOpLogs::default(),
+        HardStateStore::new(Some(state_path)),
+        QueueLimits::default(),
+    );
+
+    // THEN: The term and the vote are restored, so another candidate cannot get a vote in the same term
+    assert_eq!(restarted.replication.term, initial_term + 1);
+    assert!(
+        !restarted.replication.election_state.is_votable(&PeerIdentifier::new("127.0.0.1", 8013))
+    );
+    assert!(restarted.replication.election_state.is_votable(&candidate_id));
+}
+
 #[tokio::test]
 async fn test_vote_election_deny_vote_older_log() {
     // GIVEN: A follower actor
@@ -106,6 +154,7 @@ async fn test_vote_election_deny_vote_older_log() {
             term: initial_term,
             request: WriteRequest::Set { key: "k".into(), value: "v".into(), expires_at: None },
             session_req: None,
+            timestamp: 0,
         }])
         .unwrap(); // Follower log: idx 2, term 2
 
@@ -180,21 +229,38 @@ async fn test_receive_election_vote_candidate_wins_election() {
     let election_vote = ElectionVote { term: candidate_term, vote_granted: true };
 
     // WHEN: Candidate receives the winning vote
-    candidate_actor.receive_election_vote(election_vote).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    candidate_actor.receive_election_vote(election_vote, &cache_manager).await;
 
     // THEN: Candidate should become Leader
     assert!(candidate_actor.replication.is_leader());
     assert_eq!(candidate_actor.replication.role, ReplicationRole::Leader);
     assert_eq!(candidate_actor.replication.term, candidate_term); // Term remains the same as election term
 
-    // THEN: Initial heartbeat should be sent to the replica
-    // The receive_election_vote calls become_leader, which sends an AppendEntriesRPC
+    // THEN: A no-op entry of the new term is replicated first
     let hb = HeartBeat {
         term: candidate_term,
         from: candidate_actor.replication.self_identifier(),
         replid: candidate_actor.replication.replid.clone(),
+        weight: 1,
         ..Default::default()
     };
+    let no_op = WriteOperation {
+        request: WriteRequest::NoOp,
+        log_index: 1,
+        term: candidate_term,
+        session_req: None,
+        timestamp: 0,
+    };
+    assert_expected_queryio(
+        &replica1_fake_buf,
+        QueryIO::AppendEntriesRPC(hb.clone().set_append_entries(vec![no_op])),
+    )
+    .await;
+
+    // THEN: Initial heartbeat should be sent to the replica
+    // The receive_election_vote calls become_leader, which sends an AppendEntriesRPC
+    let hb = HeartBeat { prev_log_index: 1, prev_log_term: candidate_term, ..hb };
     assert_expected_queryio(&replica1_fake_buf, QueryIO::AppendEntriesRPC(hb.clone())).await;
 
     assert_expected_queryio(
@@ -204,6 +270,38 @@ async fn test_receive_election_vote_candidate_wins_election() {
     .await;
 }
 
+#[tokio::test]
+async fn test_no_op_commits_entries_from_previous_terms() {
+    // GIVEN: A candidate holding an entry from a previous term that was never committed
+    let candidate_term = 3;
+    let mut candidate_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    candidate_actor.replication.term = candidate_term;
+    candidate_actor.replication.election_state =
+        ElectionState::Candidate { voting: Some(ElectionVoting::new(2)) };
+    candidate_actor.logger.follower_write_entries(vec![Helper::write(1, 2, "foo", "bar")]).unwrap();
+    let (_, replica_id) = candidate_actor.test_add_peer(8052, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+
+    // WHEN: It wins the election and the replica acknowledges the no-op
+    candidate_actor
+        .receive_election_vote(
+            ElectionVote { term: candidate_term, vote_granted: true },
+            &cache_manager,
+        )
+        .await;
+    assert_eq!(candidate_actor.logger.last_log_index, 2);
+    assert_eq!(candidate_actor.replication.hwm.load(Ordering::Acquire), 0);
+
+    candidate_actor
+        .ack_replication(ReplicationAck::ack(2, &candidate_actor.replication).set_from(&replica_id))
+        .await;
+
+    // THEN: The high water mark covers the previous term's entry, which is applied on the leader
+    assert_eq!(candidate_actor.replication.hwm.load(Ordering::Acquire), 2);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache_manager.route_get("foo").await.unwrap(), CacheValue::new("bar"));
+}
+
 #[tokio::test]
 async fn test_receive_election_vote_candidate_gets_vote_not_enough_to_win() {
     let candidate_term = 3;
@@ -215,7 +313,8 @@ async fn test_receive_election_vote_candidate_gets_vote_not_enough_to_win() {
 
     let election_vote = ElectionVote { term: candidate_term, vote_granted: true };
 
-    candidate_actor.receive_election_vote(election_vote).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    candidate_actor.receive_election_vote(election_vote, &cache_manager).await;
 
     assert_eq!(candidate_actor.replication.role, ReplicationRole::Follower); // Stays follower
 
@@ -225,3 +324,453 @@ async fn test_receive_election_vote_candidate_gets_vote_not_enough_to_win() {
         panic!("Expected candidate state");
     }
 }
+
+#[tokio::test]
+async fn test_start_pre_vote_keeps_term_and_sends_pre_votes() {
+    // GIVEN
+    let mut actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let initial_term = actor.replication.term;
+    let (fakebuf, _) = actor.test_add_peer(8061, None, false);
+
+    // WHEN
+    actor.start_pre_vote().await;
+
+    // THEN: term is not bumped until the pre-vote succeeds
+    assert_eq!(actor.replication.term, initial_term);
+    assert!(matches!(
+        actor.replication.election_state,
+        ElectionState::PreCandidate {
+            voting: Some(ElectionVoting { cnt: 1, replica_count: 1 }),
+            ..
+        }
+    ));
+    assert_expected_queryio(
+        &fakebuf,
+        QueryIO::PreVote(RequestVote {
+            term: initial_term + 1,
+            candidate_id: actor.replication.self_identifier(),
+            last_log_index: actor.logger.last_log_index,
+            last_log_term: actor.logger.last_log_term,
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_vote_pre_election_denied_while_leader_is_alive() {
+    // GIVEN: a follower that has recently heard from its leader
+    let mut follower_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let initial_term = follower_actor.replication.term;
+    let (_, leader_id) = follower_actor.test_add_peer(8071, None, true);
+    follower_actor.reset_election_timeout(&leader_id);
+    let (candidate_fake_buf, candidate_id) = follower_actor.test_add_peer(8072, None, false);
+
+    // WHEN: a rejoining node asks for a pre-vote with an inflated term
+    follower_actor
+        .vote_pre_election(RequestVote {
+            term: initial_term + 10,
+            candidate_id,
+            last_log_index: 0,
+            last_log_term: 0,
+        })
+        .await;
+
+    // THEN: the pre-vote is denied and the term stays put
+    assert_eq!(follower_actor.replication.term, initial_term);
+    assert_expected_queryio(
+        &candidate_fake_buf,
+        QueryIO::PreVoteReply(ElectionVote { term: initial_term, vote_granted: false }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_vote_pre_election_granted_without_changing_state() {
+    // GIVEN: a follower that has lost its leader
+    let mut follower_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let initial_term = follower_actor.replication.term;
+    let (candidate_fake_buf, candidate_id) = follower_actor.test_add_peer(8081, None, false);
+
+    // WHEN
+    follower_actor
+        .vote_pre_election(RequestVote {
+            term: initial_term + 1,
+            candidate_id,
+            last_log_index: 0,
+            last_log_term: 0,
+        })
+        .await;
+
+    // THEN: granted, but neither the term nor the vote is recorded
+    assert_eq!(follower_actor.replication.term, initial_term);
+    assert!(matches!(
+        follower_actor.replication.election_state,
+        ElectionState::Follower { voted_for: None }
+    ));
+    assert_expected_queryio(
+        &candidate_fake_buf,
+        QueryIO::PreVoteReply(ElectionVote { term: initial_term, vote_granted: true }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_receive_pre_vote_starts_election_on_majority() {
+    // GIVEN: a pre-candidate with two replicas
+    let mut actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let initial_term = actor.replication.term;
+    let (fakebuf1, _) = actor.test_add_peer(8091, None, false);
+    let _ = actor.test_add_peer(8092, None, false);
+    actor.start_pre_vote().await;
+    fakebuf1.lock().await.clear();
+
+    // WHEN
+    actor.receive_pre_vote(ElectionVote { term: initial_term, vote_granted: true }).await;
+
+    // THEN: the real election begins
+    assert_eq!(actor.replication.term, initial_term + 1);
+    assert!(matches!(actor.replication.election_state, ElectionState::Candidate { .. }));
+    assert!(matches!(fakebuf1.lock().await.pop_front(), Some(QueryIO::RequestVote(_))));
+}
+
+#[tokio::test]
+async fn test_send_rpc_steps_down_when_quorum_is_lost() {
+    // GIVEN: a leader with a write in flight and two replicas
+    let dir = TempDir::new().unwrap();
+    let state_path = dir.path().join("raft.state");
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.hard_state = HardStateStore::new(Some(state_path.clone()));
+    let (replica_buf, _) = leader.test_add_peer(8095, None, false);
+    let _ = leader.test_add_peer(8096, None, false);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.req_consensus(Helper::consensus_request(tx, None)).await;
+    leader.send_rpc().await;
+    replica_buf.lock().await.clear();
+
+    // WHEN: neither replica answered within an election timeout
+    leader.heartbeat_scheduler.expire_replica_contacts();
+    leader.send_rpc().await;
+
+    // THEN: the leader steps down, keeps its vote and fails the pending write
+    assert_eq!(leader.replication.role, ReplicationRole::Follower);
+    assert!(!leader.replication.election_state.is_votable(&PeerIdentifier::new("127.0.0.1", 8096)));
+    assert!(leader.consensus_tracker.is_empty());
+    assert!(matches!(rx.await, Ok(ConsensusClientResponse::Err(_))));
+    assert!(replica_buf.lock().await.is_empty());
+    let persisted = HardStateStore::new(Some(state_path)).load();
+    assert_eq!(persisted.term, leader.replication.term);
+    assert_eq!(persisted.voted_for, Some(leader.replication.self_identifier()));
+}
+
+#[tokio::test]
+async fn test_in_flight_writes_are_redirected_to_the_new_leader() {
+    // GIVEN: a leader with a write waiting on its replica
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, new_leader_id) = leader.test_add_peer(8098, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let session_req = SessionRequest::new(1, Uuid::now_v7());
+    leader.req_consensus(Helper::consensus_request(tx, Some(session_req))).await;
+
+    // WHEN: the replica was elected in a later term
+    let heartbeat = HeartBeat {
+        from: new_leader_id.clone(),
+        term: leader.replication.term + 1,
+        replid: leader.replication.replid.clone(),
+        ..Default::default()
+    };
+    leader.append_entries_rpc(&cache_manager, heartbeat).await;
+    leader.redirect_in_flight_writes();
+
+    // THEN
+    assert_eq!(leader.replication.role, ReplicationRole::Follower);
+    assert!(leader.consensus_tracker.is_empty());
+    assert_eq!(rx.await.unwrap(), ConsensusClientResponse::Err(format!("MOVED {new_leader_id}")));
+}
+
+#[tokio::test]
+async fn test_in_flight_writes_are_retried_until_a_new_leader_is_known() {
+    // GIVEN: a leader holding writes back, with one more waiting on its replica
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, candidate_id) = leader.test_add_peer(8099, None, false);
+    let (tracked_tx, tracked_rx) = tokio::sync::oneshot::channel();
+    let session_req = SessionRequest::new(1, Uuid::now_v7());
+    leader.req_consensus(Helper::consensus_request(tracked_tx, Some(session_req))).await;
+    leader.block_write_reqs();
+    let (held_tx, held_rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(held_tx, None)).await;
+
+    // WHEN: it votes for a candidate of a later term
+    let request_vote = RequestVote {
+        term: leader.replication.term + 1,
+        candidate_id,
+        last_log_index: leader.logger.last_log_index,
+        last_log_term: leader.replication.term,
+    };
+    leader.vote_election(request_vote).await;
+    leader.redirect_in_flight_writes();
+
+    // THEN: both are answered, and writes stay blocked until the migration is settled
+    for rx in [tracked_rx, held_rx] {
+        assert!(
+            matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+        );
+    }
+    assert!(leader.pending_requests.as_ref().is_some_and(|reqs| reqs.is_empty()));
+}
+
+#[tokio::test]
+async fn test_in_flight_writes_without_a_session_are_not_retried() {
+    // GIVEN: a leader with a write waiting on its replica, sent outside of any session
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, new_leader_id) = leader.test_add_peer(8097, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.req_consensus(Helper::consensus_request(tx, None)).await;
+
+    // WHEN: the replica was elected in a later term
+    let heartbeat = HeartBeat {
+        from: new_leader_id,
+        term: leader.replication.term + 1,
+        replid: leader.replication.replid.clone(),
+        ..Default::default()
+    };
+    leader.append_entries_rpc(&cache_manager, heartbeat).await;
+    leader.redirect_in_flight_writes();
+
+    // THEN: it may have been committed by the new leader, so the client is not told to retry it
+    assert!(
+        matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.contains("outcome unknown"))
+    );
+}
+
+#[tokio::test]
+async fn test_follower_answers_empty_append_entries() {
+    // GIVEN
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (leader_buf, leader_id) = follower.test_add_peer(8097, None, true);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+
+    // WHEN
+    let heartbeat = HeartBeat {
+        from: leader_id,
+        term: follower.replication.term,
+        replid: follower.replication.replid.clone(),
+        ..Default::default()
+    };
+    follower.append_entries_rpc(&cache_manager, heartbeat).await;
+
+    // THEN: the leader learns the replica is reachable without any replication progress
+    assert_expected_queryio(&leader_buf, ReplicationAck::heartbeat(&follower.replication)).await;
+}
+
+#[tokio::test]
+async fn test_cluster_failover_hands_off_to_caught_up_replica() {
+    // GIVEN: a leader with an up-to-date replica
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let term = leader.replication.term;
+    let (target_buf, target_id) = leader.test_add_peer(8101, None, false);
+    let (callback, callback_rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    leader.cluster_failover(Some(target_id.clone()), callback.into()).await;
+
+    // THEN: the target is told to start an election and the leader steps down
+    assert!(matches!(target_buf.lock().await.pop_front(), Some(QueryIO::AppendEntriesRPC(_))));
+    assert_expected_queryio(
+        &target_buf,
+        TimeoutNow { from: leader.replication.self_identifier(), term },
+    )
+    .await;
+    assert!(!leader.replication.is_leader());
+    assert!(leader.leadership_transfer.is_none());
+    assert!(leader.pending_requests.is_none());
+    assert!(callback_rx.await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn test_abort_timer_of_an_earlier_transfer_leaves_a_later_one_alone() {
+    // GIVEN: a transfer that was aborted, followed by another one
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, target_id) = leader.test_add_peer(8103, None, false);
+    leader.logger.last_log_index = 1;
+    let (callback, _callback_rx) = tokio::sync::oneshot::channel();
+    leader.cluster_failover(Some(target_id.clone()), callback.into()).await;
+    let earlier = leader.leadership_transfer.as_ref().unwrap().id;
+    leader.abort_leadership_transfer();
+    let (callback, _callback_rx) = tokio::sync::oneshot::channel();
+    leader.cluster_failover(Some(target_id), callback.into()).await;
+
+    // WHEN: the timer of the first one goes off
+    leader.expire_leadership_transfer(earlier);
+
+    // THEN
+    assert!(leader.leadership_transfer.is_some());
+    assert!(leader.hand_off_hold.is_some());
+}
+
+#[tokio::test]
+async fn test_cluster_failover_waits_for_target_to_catch_up() {
+    // GIVEN: a leader whose replica is behind
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (target_buf, target_id) = leader.test_add_peer(8111, None, false);
+    leader
+        .logger
+        .write_single_entry(
+            &WriteRequest::Set { key: "k".into(), value: "v".into(), expires_at: None },
+            leader.replication.term,
+            None,
+        )
+        .unwrap();
+    let (callback, _callback_rx) = tokio::sync::oneshot::channel();
+    leader.cluster_failover(Some(target_id.clone()), callback.into()).await;
+
+    // WHEN: a write arrives during the transfer
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+
+    // THEN: the write is held back and the replica is brought up to date first
+    assert!(leader.replication.is_leader());
+    assert!(leader.pending_requests.is_none());
+    assert_eq!(leader.hand_off_hold.as_ref().unwrap().reqs.len(), 1);
+    assert!(matches!(target_buf.lock().await.pop_front(), Some(QueryIO::AppendEntriesRPC(_))));
+
+    // WHEN: the replica acknowledges the last entry
+    target_buf.lock().await.clear();
+    leader.ack_replication(ReplicationAck::ack(1, &leader.replication).set_from(&target_id)).await;
+
+    // THEN: leadership is handed off, and the write waits for the new leader to be known
+    assert!(matches!(target_buf.lock().await.pop_front(), Some(QueryIO::TimeoutNow(_))));
+    assert!(!leader.replication.is_leader());
+    leader.release_handed_off_writes();
+    assert_eq!(leader.hand_off_hold.as_ref().unwrap().reqs.len(), 1);
+
+    // WHEN: the target won the election
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let heartbeat = HeartBeat {
+        from: target_id.clone(),
+        term: leader.replication.term + 1,
+        replid: leader.replication.replid.clone(),
+        ..Default::default()
+    };
+    leader.append_entries_rpc(&cache_manager, heartbeat).await;
+    leader.release_handed_off_writes();
+
+    // THEN: the held-back write is sent to it
+    assert!(leader.hand_off_hold.is_none());
+    assert_eq!(rx.await.unwrap(), ConsensusClientResponse::Err(format!("MOVED {target_id}")));
+}
+
+#[tokio::test]
+async fn test_writes_held_after_hand_off_are_retried_when_no_leader_shows_up() {
+    // GIVEN: a leader that handed off leadership while a write was held back
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, target_id) = leader.test_add_peer(8113, None, false);
+    leader.logger.last_log_index = 1;
+    let (callback, _callback_rx) = tokio::sync::oneshot::channel();
+    leader.cluster_failover(Some(target_id.clone()), callback.into()).await;
+    let id = leader.leadership_transfer.as_ref().unwrap().id;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+    leader.ack_replication(ReplicationAck::ack(1, &leader.replication).set_from(&target_id)).await;
+    assert!(!leader.replication.is_leader());
+
+    // WHEN: the transfer's timer goes off before any leader was heard from
+    leader.expire_leadership_transfer(id);
+
+    // THEN
+    assert!(leader.hand_off_hold.is_none());
+    assert!(
+        matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+}
+
+#[tokio::test]
+async fn test_abort_leadership_transfer_releases_writes() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, target_id) = leader.test_add_peer(8121, None, false);
+    leader.logger.last_log_index = 1;
+    let (callback, callback_rx) = tokio::sync::oneshot::channel();
+    leader.cluster_failover(Some(target_id), callback.into()).await;
+
+    // WHEN
+    leader.abort_leadership_transfer();
+
+    // THEN
+    assert!(leader.replication.is_leader());
+    assert!(leader.hand_off_hold.is_none());
+    assert!(callback_rx.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_receive_timeout_now_starts_election_without_pre_vote() {
+    // GIVEN
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let term = follower.replication.term;
+    let (leader_buf, leader_id) = follower.test_add_peer(8131, None, true);
+
+    // WHEN
+    follower.receive_timeout_now(TimeoutNow { from: leader_id, term }).await;
+
+    // THEN
+    assert_eq!(follower.replication.term, term + 1);
+    assert!(matches!(follower.replication.election_state, ElectionState::Candidate { .. }));
+    assert!(matches!(leader_buf.lock().await.pop_front(), Some(QueryIO::RequestVote(_))));
+}
+
+#[tokio::test]
+async fn test_election_timeout_deferred_for_higher_priority_replica() {
+    // GIVEN: a follower that sees a live replica with a higher election priority
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (peer_buf, peer_id) = follower.test_add_peer(8141, None, false);
+    follower.members.get_mut(&peer_id).unwrap().set_priority(1);
+
+    // WHEN
+    follower.on_election_timeout().await;
+
+    // THEN: the first timeout is left to the preferred replica
+    assert!(peer_buf.lock().await.is_empty());
+    assert!(!matches!(follower.replication.election_state, ElectionState::PreCandidate { .. }));
+
+    // WHEN: nobody took over in the meantime
+    follower.on_election_timeout().await;
+
+    // THEN
+    assert!(matches!(follower.replication.election_state, ElectionState::PreCandidate { .. }));
+    assert!(matches!(peer_buf.lock().await.pop_front(), Some(QueryIO::PreVote(_))));
+}
+
+#[tokio::test]
+async fn test_leader_yields_to_caught_up_higher_priority_replica() {
+    // GIVEN: a replica with a higher election priority that is behind the log
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (preferred_buf, preferred_id) = leader.test_add_peer(8151, None, false);
+    leader.members.get_mut(&preferred_id).unwrap().set_priority(2);
+    leader
+        .logger
+        .write_single_entry(
+            &WriteRequest::Set { key: "k".into(), value: "v".into(), expires_at: None },
+            leader.replication.term,
+            None,
+        )
+        .unwrap();
+
+    // WHEN
+    leader
+        .ack_replication(ReplicationAck::heartbeat(&leader.replication).set_from(&preferred_id))
+        .await;
+
+    // THEN: leadership stays until the replica catches up
+    assert!(leader.replication.is_leader());
+    assert!(leader.leadership_transfer.is_none());
+
+    // WHEN: the replica acknowledges the last entry
+    leader
+        .ack_replication(ReplicationAck::ack(1, &leader.replication).set_from(&preferred_id))
+        .await;
+
+    // THEN: leadership is handed off to it
+    assert!(matches!(preferred_buf.lock().await.pop_back(), Some(QueryIO::TimeoutNow(_))));
+    assert!(!leader.replication.is_leader());
+}
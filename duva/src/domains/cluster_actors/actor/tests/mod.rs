@@ -2,8 +2,8 @@ mod cluster_managements;
 mod elections;
 mod partitionings;
 mod replications;
-#[allow(unused_variables)]
 use super::actor::ClusterCommandHandler;
+use crate::domains::cluster_actors::mailbox::{Mailbox, QueueLimits};
 
 use super::*;
 use crate::CacheManager;
@@ -15,6 +15,8 @@ use crate::domains::caches::actor::CacheCommandSender;
 use crate::domains::caches::cache_objects::CacheEntry;
 use crate::domains::caches::command::CacheCommand;
 use crate::domains::cluster_actors::replication::ReplicationRole;
+use crate::domains::compression::Compression;
+use crate::domains::leases::actor::LeaseActor;
 use crate::domains::operation_logs::WriteOperation;
 use crate::domains::operation_logs::WriteRequest;
 use crate::domains::operation_logs::logger::ReplicatedLogs;
@@ -25,7 +27,7 @@ use crate::domains::peers::connections::inbound::stream::InboundStream;
 use crate::domains::peers::peer::PeerState;
 use crate::domains::peers::service::PeerListener;
 use crate::types::Callback;
-use std::fs::OpenOptions;
+use bytes::Bytes;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tempfile::TempDir;
@@ -58,6 +60,13 @@ impl TWrite for FakeReadWrite {
         guard.push_back(io);
         Ok(())
     }
+
+    async fn write_bytes(&mut self, buf: &[u8]) -> Result<(), IoError> {
+        // * Frames are recorded the way they went out, compressed entries with their codec
+        let (io, _) = crate::domains::query_io::deserialize_as_sent(buf.to_vec())
+            .map_err(|err| IoError::Custom(err.to_string()))?;
+        self.write(io).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -115,9 +124,14 @@ impl Helper {
     pub(crate) fn write(index_num: u64, term: u64, key: &str, value: &str) -> WriteOperation {
         WriteOperation {
             log_index: index_num,
-            request: WriteRequest::Set { key: key.into(), value: value.into(), expires_at: None },
+            request: WriteRequest::Set {
+                key: Bytes::copy_from_slice(key.as_bytes()),
+                value: Bytes::copy_from_slice(value.as_bytes()),
+                expires_at: None,
+            },
             term,
             session_req: None,
+            timestamp: 0,
         }
     }
     pub(crate) fn session_write(
@@ -129,9 +143,14 @@ impl Helper {
     ) -> WriteOperation {
         WriteOperation {
             log_index: index_num,
-            request: WriteRequest::Set { key: key.into(), value: value.into(), expires_at: None },
+            request: WriteRequest::Set {
+                key: Bytes::copy_from_slice(key.as_bytes()),
+                value: Bytes::copy_from_slice(value.as_bytes()),
+                expires_at: None,
+            },
             term,
             session_req: Some(session_req),
+            timestamp: 0,
         }
     }
 
@@ -147,7 +166,12 @@ impl Helper {
             replid: ReplicationId::Key("localhost".to_string()),
             hop_count: 0,
             cluster_nodes: vec![],
+            nodes_digest: 0,
             hashring: None,
+            priority: 0,
+            weight: 1,
+            upstream: None,
+            trace: None,
         }
     }
 
@@ -157,10 +181,17 @@ impl Helper {
         let dir = TempDir::new().unwrap();
         let path = dir.path().join("duva.tp");
 
-        let topology_writer =
-            OpenOptions::new().create(true).write(true).truncate(true).open(path).unwrap();
+        let topology_writer = TopologyWriter::spawn(path);
 
-        ClusterActor::new(100, replication, 100, topology_writer, MemoryOpLogs::default())
+        ClusterActor::new(
+            100,
+            replication,
+            100,
+            topology_writer,
+            MemoryOpLogs::default(),
+            HardStateStore::default(),
+            QueueLimits::default(),
+        )
     }
 
     async fn cluster_actor_with_receiver(
@@ -168,7 +199,7 @@ impl Helper {
     ) -> (ClusterActor<MemoryOpLogs>, InterceptedReceiver) {
         let mut actor = Self::cluster_actor(role).await;
         let (tx, rx) = channel(100);
-        let cluster_sender = ClusterCommandHandler(tx);
+        let cluster_sender = ClusterCommandHandler::new(tx, Mailbox::default());
         actor.self_handler = cluster_sender.clone();
         (actor, InterceptedReceiver(rx))
     }
@@ -257,7 +288,11 @@ pub(crate) async fn assert_expected_queryio(
 ) {
     let mut sent_messages = message_buf.lock().await;
 
-    let message = sent_messages.pop_front().unwrap();
+    let mut message = sent_messages.pop_front().unwrap();
+    // * Entries are stamped with the time they were appended at, which expectations leave at 0
+    if let QueryIO::AppendEntriesRPC(heartbeat) = &mut message {
+        heartbeat.append_entries.iter_mut().for_each(|op| op.timestamp = 0);
+    }
 
     assert_eq!(message, expected_query_io.into());
 }
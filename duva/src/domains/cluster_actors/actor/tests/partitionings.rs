@@ -1,6 +1,8 @@
 use crate::domains::QueryIO;
 use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
 use crate::domains::cluster_actors::hash_ring::BatchId;
+use crate::domains::cluster_actors::hash_ring::KeyMigration;
+use crate::domains::cluster_actors::hash_ring::MigrationThrottle;
 use crate::domains::cluster_actors::hash_ring::{HashRing, tests::migration_task_create_helper};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -137,6 +139,7 @@ async fn test_start_rebalance_happy_path() {
             from: cluster_actor.replication.self_identifier(),
             hashring: Some(Box::new(cluster_actor.hash_ring.clone())),
             replid: cluster_actor.replication.replid.clone(),
+            weight: 1,
             ..Default::default()
         }),
     )
@@ -158,7 +161,7 @@ async fn test_start_rebalance_schedules_migration_batches() {
         cluster_actor.test_add_peer(6570, Some(ReplicationId::Key("testnode_a".into())), true);
 
     let (tx, mut rx) = tokio::sync::mpsc::channel(2);
-    let cluster_handler = ClusterCommandHandler(tx);
+    let cluster_handler = ClusterCommandHandler::new(tx, Mailbox::default());
 
     // WHEN
     cluster_actor.self_handler = cluster_handler.clone();
@@ -310,7 +313,7 @@ async fn test_send_migrate_and_wait_channel_error() {
 async fn test_send_migrate_and_wait_callback_error() {
     // GIVEN
     let (tx, mut rx) = tokio::sync::mpsc::channel(10);
-    let fake_handler = ClusterCommandHandler(tx);
+    let fake_handler = ClusterCommandHandler::new(tx, Mailbox::default());
 
     let target_replid = ReplicationId::Key("error_response_test".to_string());
     let batch_to_migrate = vec![migration_task_create_helper(0, 10)];
@@ -376,7 +379,11 @@ async fn test_migrate_batch_send_migrate_batch_peer_message() {
     // THEN
     assert_expected_queryio(
         &buf,
-        QueryIO::MigrateBatch(MigrateBatch { batch_id: batch.id, cache_entries: vec![] }),
+        QueryIO::MigrateBatch(MigrateBatch {
+            batch_id: batch.id,
+            cache_entries: vec![],
+            trace: None,
+        }),
     )
     .await;
 }
@@ -390,7 +397,8 @@ async fn test_receive_batch_when_empty_cache_entries() {
     let (buf, _id) = cluster_actor.test_add_peer(6909, Some(replid.clone()), true);
 
     // WHEN
-    let batch = MigrateBatch { batch_id: BatchId("empty_test".into()), cache_entries: vec![] };
+    let batch =
+        MigrateBatch { batch_id: BatchId("empty_test".into()), cache_entries: vec![], trace: None };
     cluster_actor.receive_batch(batch.clone(), &cache_manager, _id).await;
 
     // THEN - verify that no log index is incremented
@@ -419,6 +427,7 @@ async fn test_receive_batch_when_consensus_is_required() {
     let batch = MigrateBatch {
         batch_id: BatchId("success_test".into()),
         cache_entries: cache_entries.clone(),
+        trace: None,
     };
 
     // WHEN
@@ -436,7 +445,9 @@ async fn test_receive_batch_when_consensus_is_required() {
                 log_index: 1,
                 term: 0,
                 session_req: None,
+                timestamp: 0,
             }],
+            weight: 1,
             ..Default::default()
         }),
     )
@@ -459,12 +470,14 @@ async fn test_receive_batch_when_noreplica_found() {
     let batch = MigrateBatch {
         batch_id: BatchId("success_test".into()),
         cache_entries: cache_entries.clone(),
+        trace: None,
     };
 
     // WHEN
     let task = tokio::spawn(recv.wait_message(SchedulerMessage::SendBatchAck {
         batch_id: batch.batch_id.clone(),
         to: ack_to.clone(),
+        success: true,
     }));
     cluster_actor.receive_batch(batch, &cache_manager, ack_to).await;
 
@@ -482,7 +495,7 @@ async fn test_unblock_write_reqs_if_done_when_no_pending_migrations() {
     cluster_actor.pending_migrations = Some(HashMap::new());
 
     // WHEN
-    cluster_actor.unblock_write_reqs_if_done();
+    cluster_actor.unblock_write_reqs_if_done().await;
 
     // THEN
     assert!(cluster_actor.pending_requests.is_none());
@@ -504,7 +517,7 @@ async fn test_unblock_write_reqs_if_done_when_migrations_still_pending() {
         .insert(batch_id, PendingMigrationBatch::new(callback, vec![]));
 
     // WHEN
-    cluster_actor.unblock_write_reqs_if_done();
+    cluster_actor.unblock_write_reqs_if_done().await;
 
     // THEN - Nothing should change - requests should remain blocked
     assert!(cluster_actor.pending_requests.is_some());
@@ -520,7 +533,7 @@ async fn test_unblock_write_reqs_if_done_when_not_blocked() {
     cluster_actor.pending_migrations = Some(HashMap::new());
 
     // WHEN
-    cluster_actor.unblock_write_reqs_if_done();
+    cluster_actor.unblock_write_reqs_if_done().await;
 
     // THEN - Should not crash and pending_migrations should remain as empty
     assert!(cluster_actor.pending_requests.is_none());
@@ -535,9 +548,9 @@ async fn test_unblock_write_reqs_if_done_multiple_times() {
     cluster_actor.pending_migrations = Some(HashMap::new());
 
     // WHEN - call unblock multiple times
-    cluster_actor.unblock_write_reqs_if_done();
-    cluster_actor.unblock_write_reqs_if_done();
-    cluster_actor.unblock_write_reqs_if_done();
+    cluster_actor.unblock_write_reqs_if_done().await;
+    cluster_actor.unblock_write_reqs_if_done().await;
+    cluster_actor.unblock_write_reqs_if_done().await;
 
     // THEN - Should be idempotent
     assert!(cluster_actor.pending_requests.is_none());
@@ -634,7 +647,7 @@ async fn test_handle_migration_ack_success_case_with_pending_reqs_and_migration(
     let (_hwm, cache_manager) = Helper::cache_manager();
 
     // Set up test keys in cache that will be part of the migration
-    let test_keys = vec!["migrate_key_1".to_string(), "migrate_key_2".to_string()];
+    let test_keys = vec![Bytes::from("migrate_key_1"), Bytes::from("migrate_key_2")];
     cache_manager.route_set(CacheEntry::new("migrate_key_1", "value_1"), 1).await.unwrap();
     cache_manager.route_set(CacheEntry::new("migrate_key_2", "value_2"), 2).await.unwrap();
 
@@ -720,3 +733,764 @@ async fn test_maybe_update_hashring_replica_only_updates_ring() {
     assert!(cluster_actor.pending_requests.is_none());
     assert!(cluster_actor.pending_migrations.is_none());
 }
+
+#[tokio::test]
+async fn test_leader_req_consensus_asks_for_keys_moved_mid_migration() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(0).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let replid = ReplicationId::Key("target".to_string());
+    let (_buf, target_id) = cluster_actor.test_add_peer(6910, Some(replid.clone()), true);
+
+    let moved = MigrationBatch::new(replid.clone(), vec![migration_task_create_helper(0, 2)]);
+    let in_flight = MigrationBatch::new(replid, vec![migration_task_create_helper(2, 4)]);
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor.migrate_batch(moved.clone(), &cache_manager, tx).await;
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor.migrate_batch(in_flight, &cache_manager, tx).await;
+
+    // WHEN - only the first batch is acknowledged by the target
+    cluster_actor
+        .handle_migration_ack(MigrationBatchAck::with_success(moved.id), &cache_manager)
+        .await;
+
+    let set = |key: &str| WriteRequest::Set {
+        key: key.to_string().into(),
+        value: "v".into(),
+        expires_at: None,
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor.leader_req_consensus(ConsensusRequest::new(set("key_0"), tx, None)).await;
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor.leader_req_consensus(ConsensusRequest::new(set("key_2"), tx, None)).await;
+
+    // THEN - moved key is redirected, in-flight key waits for the migration to finish
+    assert_eq!(rx.await.unwrap(), ConsensusClientResponse::Err(format!("ASK {target_id}")));
+    assert_eq!(cluster_actor.pending_requests.as_ref().unwrap().len(), 1);
+    assert_eq!(cluster_actor.migrating_keys.get(b"key_0"), Some(&KeyMigration::Moved(target_id)));
+}
+
+#[tokio::test]
+async fn test_failed_migration_ack_keeps_keys_blocked() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(0).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let replid = ReplicationId::Key("target".to_string());
+    cluster_actor.test_add_peer(6911, Some(replid.clone()), true);
+
+    let batch = MigrationBatch::new(replid, vec![migration_task_create_helper(0, 2)]);
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor.migrate_batch(batch.clone(), &cache_manager, tx).await;
+
+    // WHEN
+    cluster_actor
+        .handle_migration_ack(MigrationBatchAck::with_reject(batch.id), &cache_manager)
+        .await;
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::Set { key: "key_0".into(), value: "v".into(), expires_at: None },
+            tx,
+            None,
+        ))
+        .await;
+
+    // THEN
+    assert_eq!(cluster_actor.migrating_keys.get(b"key_0"), None);
+    assert_eq!(cluster_actor.pending_requests.as_ref().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_leader_req_consensus_rejects_keys_across_partitions() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let other_replid = ReplicationId::Key("other".to_string());
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (other_replid.clone(), PeerIdentifier::new("127.0.0.1", 6920)),
+    ]);
+    let keys = (0..100).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+    let local = keys
+        .iter()
+        .find(|key| cluster_actor.hash_ring.get_node_for_key(key) != Some(&other_replid))
+        .unwrap();
+    let remote = keys
+        .iter()
+        .find(|key| cluster_actor.hash_ring.get_node_for_key(key) == Some(&other_replid))
+        .unwrap();
+
+    // WHEN
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::Delete { keys: vec![local.clone().into(), remote.clone().into()] },
+            tx,
+            None,
+        ))
+        .await;
+
+    // THEN
+    let ConsensusClientResponse::Err(err) = rx.await.unwrap() else {
+        panic!("cross-partition request must be rejected");
+    };
+    assert!(err.starts_with("CROSSSLOT"));
+    assert_eq!(cluster_actor.logger.last_log_index, 0);
+}
+
+#[tokio::test]
+async fn test_start_rebalance_uses_weights_advertised_in_heartbeats() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let other_replid = ReplicationId::Key(uuid::Uuid::now_v7().to_string());
+    let (_buf, other_leader) = cluster_actor.test_add_peer(6930, Some(other_replid.clone()), true);
+    let heartbeat = HeartBeat { from: other_leader, weight: 3, ..Helper::heartbeat(0, 0, vec![]) };
+
+    // WHEN
+    cluster_actor.receive_cluster_heartbeat(heartbeat, &cache_manager).await;
+    cluster_actor.start_rebalance(&cache_manager).await;
+
+    // THEN
+    assert_eq!(cluster_actor.hash_ring.weight(&other_replid), 3);
+    assert_eq!(cluster_actor.hash_ring.weight(&cluster_actor.replication.replid), 1);
+    assert_eq!(cluster_actor.hash_ring.get_vnode_count(), 256 * 4);
+}
+
+#[tokio::test]
+async fn test_cluster_migrate_moves_selected_keys_to_target() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let target_replid = ReplicationId::Key("testnode_a".into());
+    let (buf, target_id) = cluster_actor.test_add_peer(6940, Some(target_replid.clone()), true);
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (target_replid.clone(), target_id),
+    ]);
+    let local_keys = (0..100)
+        .flat_map(|i| [format!("user:{i}"), format!("order:{i}")])
+        .filter(|key| cluster_actor.hash_ring.get_node_for_key(key) != Some(&target_replid))
+        .collect::<Vec<_>>();
+    let (_hwm, cache_manager) = Helper::cache_manager_with_keys(local_keys.clone()).await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    cluster_actor.self_handler = ClusterCommandHandler::new(tx, Mailbox::default());
+
+    // WHEN
+    let (callback, res) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .cluster_migrate(
+            KeySelector::Prefix("user:".into()),
+            target_replid.clone(),
+            &cache_manager,
+            callback.into(),
+        )
+        .await;
+
+    // THEN
+    res.await.unwrap().unwrap();
+    assert!(cluster_actor.pending_requests.is_some());
+    let QueryIO::ClusterHeartBeat(HeartBeat { hashring: Some(ring), .. }) =
+        buf.lock().await.pop_front().unwrap()
+    else {
+        panic!("new ring must be gossiped")
+    };
+    assert_eq!(ring.get_node_for_key("user:1"), Some(&target_replid));
+
+    let batch = tokio::time::timeout(Duration::from_millis(1000), async {
+        loop {
+            if let Some(ClusterCommand::Scheduler(SchedulerMessage::ScheduleMigrationBatch(b, _))) =
+                rx.recv().await
+            {
+                return b;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(batch.target_repl, target_replid);
+    let mut moved = batch.tasks.iter().flat_map(|t| t.keys_to_migrate.clone()).collect::<Vec<_>>();
+    moved.sort();
+    let mut expected =
+        local_keys.into_iter().filter(|key| key.starts_with("user:")).collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(moved, expected);
+
+    // * once the batches are acknowledged, the pinned ring is adopted
+    cluster_actor.pending_migrations = Some(HashMap::new());
+    cluster_actor.migration_progress.complete(&batch.id, moved.len(), 0);
+    cluster_actor.unblock_write_reqs_if_done().await;
+    assert!(cluster_actor.pending_requests.is_none());
+    assert_eq!(cluster_actor.hash_ring.get_node_for_key("user:1"), Some(&target_replid));
+}
+
+#[tokio::test]
+async fn test_cluster_migrate_rejects_unknown_partition() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+
+    // WHEN
+    let (callback, res) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .cluster_migrate(
+            KeySelector::Range(0, 100),
+            ReplicationId::Key("unknown".into()),
+            &cache_manager,
+            callback.into(),
+        )
+        .await;
+
+    // THEN
+    assert!(res.await.unwrap().is_err());
+    assert!(cluster_actor.pending_requests.is_none());
+}
+
+#[tokio::test]
+async fn test_rejected_migration_batch_is_retried_after_backoff() {
+    // GIVEN
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let handler = ClusterCommandHandler::new(tx, Mailbox::default());
+    let batch = MigrationBatch::new(
+        ReplicationId::Key("retry_test".to_string()),
+        vec![migration_task_create_helper(0, 10)],
+    );
+    let throttle = MigrationThrottle {
+        retry_backoff: Duration::from_millis(1),
+        ..MigrationThrottle::default()
+    };
+
+    // * the target rejects the first attempt and accepts the second
+    tokio::spawn(async move {
+        let mut attempts = 0;
+        while let Some(ClusterCommand::Scheduler(SchedulerMessage::ScheduleMigrationBatch(
+            _,
+            callback,
+        ))) = rx.recv().await
+        {
+            attempts += 1;
+            if attempts == 1 {
+                let _ = callback.send(Err(anyhow::anyhow!("rejected")));
+            } else {
+                let _ = callback.send(Ok(42));
+            }
+        }
+    });
+
+    // WHEN
+    let result = ClusterActor::<MemoryOpLogs>::migrate_with_retry(batch, handler, throttle).await;
+
+    // THEN
+    assert_eq!(result.unwrap(), 42);
+}
+
+#[tokio::test]
+async fn test_follower_tracks_migration_checkpoints_once_committed() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let ring = HashRing::default().add_partitions(vec![(
+        cluster_actor.replication.replid.clone(),
+        cluster_actor.replication.self_identifier(),
+    )]);
+    let checkpoint = |log_index, request| WriteOperation {
+        log_index,
+        request,
+        term: 0,
+        session_req: None,
+        timestamp: 0,
+    };
+    let start = checkpoint(1, WriteRequest::MigrationStart { ring: Box::new(ring.clone()) });
+
+    // WHEN
+    cluster_actor.replicate(Helper::heartbeat(0, 1, vec![start]), &cache_manager).await;
+
+    // THEN
+    assert_eq!(cluster_actor.pending_ring, Some(ring));
+
+    // WHEN
+    let end = checkpoint(2, WriteRequest::MigrationEnd);
+    cluster_actor.replicate(Helper::heartbeat(0, 2, vec![end]), &cache_manager).await;
+
+    // THEN
+    assert_eq!(cluster_actor.pending_ring, None);
+}
+
+#[tokio::test]
+async fn test_new_leader_resumes_checkpointed_migration() {
+    // GIVEN - a replica that saw the checkpoint of its former leader
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let target_replid = ReplicationId::Key("testnode_a".into());
+    let (_buf, target_id) = cluster_actor.test_add_peer(6950, Some(target_replid.clone()), true);
+    let ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (target_replid.clone(), target_id),
+    ]);
+    cluster_actor.hash_ring = ring.clone();
+    cluster_actor.pending_ring = Some(ring.clone());
+
+    // * keys that still belong to the target were not migrated before the failover
+    let remaining = (0..50)
+        .map(|i| format!("key_{i}"))
+        .filter(|key| ring.get_node_for_key(key) == Some(&target_replid))
+        .collect::<Vec<_>>();
+    let (_hwm, cache_manager) = Helper::cache_manager_with_keys(remaining.clone()).await;
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    cluster_actor.self_handler = ClusterCommandHandler::new(tx, Mailbox::default());
+
+    // WHEN
+    cluster_actor.become_leader(&cache_manager).await;
+
+    // THEN
+    assert!(cluster_actor.pending_requests.is_some());
+    assert!(matches!(
+        cluster_actor.logger.read_at(cluster_actor.logger.last_log_index).unwrap().request,
+        WriteRequest::MigrationStart { .. }
+    ));
+    let batch = tokio::time::timeout(Duration::from_millis(1000), async {
+        loop {
+            if let Some(ClusterCommand::Scheduler(SchedulerMessage::ScheduleMigrationBatch(b, _))) =
+                rx.recv().await
+            {
+                return b;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    let mut moved = batch.tasks.iter().flat_map(|t| t.keys_to_migrate.clone()).collect::<Vec<_>>();
+    moved.sort();
+    let mut remaining = remaining;
+    remaining.sort();
+    assert_eq!(batch.target_repl, target_replid);
+    assert_eq!(moved, remaining);
+}
+
+#[tokio::test]
+async fn test_new_leader_drops_outdated_migration_checkpoint() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let mut outdated = cluster_actor.hash_ring.clone();
+    outdated.last_modified = 0;
+    cluster_actor.hash_ring.last_modified = 1;
+    cluster_actor.pending_ring = Some(outdated);
+
+    // WHEN
+    cluster_actor.become_leader(&cache_manager).await;
+
+    // THEN
+    assert_eq!(cluster_actor.pending_ring, None);
+    assert!(cluster_actor.pending_requests.is_none());
+    assert_eq!(
+        cluster_actor.logger.read_at(cluster_actor.logger.last_log_index).unwrap().request,
+        WriteRequest::MigrationEnd
+    );
+}
+
+#[tokio::test]
+async fn test_reshard_status_reports_batch_progress() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(0).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let target_replid = ReplicationId::Key("testnode_a".into());
+    let batches = vec![
+        MigrationBatch::new(target_replid.clone(), vec![migration_task_create_helper(0, 2)]),
+        MigrationBatch::new(target_replid.clone(), vec![migration_task_create_helper(2, 5)]),
+    ];
+    cluster_actor.migration_progress.plan(&batches);
+    for batch in &batches {
+        let (callback, _rx) = tokio::sync::oneshot::channel();
+        let keys = batch.tasks[0].keys_to_migrate.clone();
+        cluster_actor
+            .pending_migrations
+            .as_mut()
+            .unwrap()
+            .insert(batch.id.clone(), PendingMigrationBatch::new(callback, keys).with_bytes(10));
+    }
+
+    // WHEN
+    let ack = MigrationBatchAck::with_success(batches[1].id.clone());
+    cluster_actor.handle_migration_ack(ack, &cache_manager).await;
+
+    // THEN
+    let status = cluster_actor.reshard_status();
+    assert_eq!(
+        status.split("\r\n").collect::<Vec<_>>(),
+        vec![
+            "reshard_state:migrating",
+            "reshard_batches_planned:2",
+            "reshard_batches_completed:1",
+            "reshard_batches_failed:0",
+            "reshard_batches_in_flight:1",
+            "reshard_keys_moved:3",
+            "reshard_bytes_moved:10",
+            "reshard_pending_partitions:testnode_a",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_expired_migration_batch_fails_its_callback() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(1).await;
+    let (callback, callback_rx) = tokio::sync::oneshot::channel();
+    let batch_id = BatchId("stuck_batch".into());
+    cluster_actor.pending_migrations.as_mut().unwrap().insert(
+        batch_id.clone(),
+        PendingMigrationBatch::new(callback, vec!["stuck_key".into()])
+            .with_deadline(tokio::time::Instant::now()),
+    );
+
+    // WHEN
+    cluster_actor.expire_migration_batch(batch_id);
+
+    // THEN
+    let err = callback_rx.await.unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "migration batch stuck_batch timed out");
+    assert!(cluster_actor.pending_migrations.as_ref().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_migration_batch_within_deadline_is_kept() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(1).await;
+    let (callback, mut callback_rx) = tokio::sync::oneshot::channel();
+    let batch_id = BatchId("slow_batch".into());
+    cluster_actor.pending_migrations.as_mut().unwrap().insert(
+        batch_id.clone(),
+        PendingMigrationBatch::new(callback, vec![])
+            .with_deadline(tokio::time::Instant::now() + Duration::from_secs(60)),
+    );
+
+    // WHEN
+    cluster_actor.expire_migration_batch(batch_id.clone());
+
+    // THEN
+    assert!(callback_rx.try_recv().is_err());
+    assert!(cluster_actor.pending_migrations.as_ref().unwrap().contains_key(&batch_id));
+}
+
+#[tokio::test]
+async fn test_abandoned_migration_batch_unblocks_writes() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(1).await;
+    let target_replid = ReplicationId::Key("testnode_a".into());
+    let batch = MigrationBatch::new(target_replid, vec![migration_task_create_helper(0, 2)]);
+    cluster_actor.migration_progress.plan(std::slice::from_ref(&batch));
+
+    // WHEN
+    cluster_actor.abandon_migration_batch(batch.id).await;
+
+    // THEN
+    assert!(cluster_actor.pending_requests.is_none());
+    assert!(cluster_actor.reshard_status().contains("reshard_batches_failed:1"));
+}
+
+#[tokio::test]
+async fn test_migration_target_prefers_partition_leader() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let repl_id = ReplicationId::Key("repl_1".to_string());
+    cluster_actor.test_add_peer(6563, Some(repl_id.clone()), false);
+    let (_, leader_id) = cluster_actor.test_add_peer(6564, Some(repl_id.clone()), true);
+    cluster_actor.test_add_peer(6565, Some(repl_id.clone()), false);
+
+    // WHEN & THEN
+    assert_eq!(cluster_actor.peerid_by_replid(&repl_id), Some(&leader_id));
+}
+
+fn two_partition_keys(
+    cluster_actor: &ClusterActor<MemoryOpLogs>,
+    other_replid: &ReplicationId,
+) -> (Bytes, Bytes) {
+    let keys = (0..100).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+    let local = keys
+        .iter()
+        .find(|key| cluster_actor.hash_ring.get_node_for_key(key) != Some(other_replid))
+        .unwrap();
+    let remote = keys
+        .iter()
+        .find(|key| cluster_actor.hash_ring.get_node_for_key(key) == Some(other_replid))
+        .unwrap();
+    (local.clone().into(), remote.clone().into())
+}
+
+#[tokio::test]
+async fn test_routing_groups_keys_by_owning_partition() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let routing = cluster_actor.routing.subscribe();
+    let other_replid = ReplicationId::Key("other".to_string());
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (other_replid.clone(), PeerIdentifier::new("127.0.0.1", 6940)),
+    ]);
+    let (local, remote) = two_partition_keys(&cluster_actor, &other_replid);
+
+    // WHEN - the new ring is published along with the topology change
+    cluster_actor.broadcast_topology_change();
+    let groups = routing.borrow().group_keys(&[remote.clone(), local.clone(), remote]);
+
+    // THEN
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[&None], vec![1]);
+    assert_eq!(groups[&Some(other_replid)], vec![0, 2]);
+}
+
+#[tokio::test]
+async fn test_forward_to_shard_resolves_on_response() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let other_replid = ReplicationId::Key("other".to_string());
+    let (buf, _) = cluster_actor.test_add_peer(6941, Some(other_replid.clone()), true);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor
+        .forward_to_shard(other_replid, ForwardedOp::Exists(vec!["a".into()]), tx.into())
+        .await;
+    let Some(QueryIO::ForwardRequest(request)) = buf.lock().await.pop_back() else {
+        panic!("request must be sent to the shard leader");
+    };
+    cluster_actor.receive_forward_response(ForwardResponse {
+        id: request.id,
+        reply: ForwardedReply::Count(1),
+    });
+
+    // THEN
+    assert_eq!(rx.await.unwrap().unwrap(), ForwardedReply::Count(1));
+    assert!(cluster_actor.pending_forwards.is_empty());
+}
+
+#[tokio::test]
+async fn test_forward_to_unknown_partition_fails() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    // WHEN
+    cluster_actor
+        .forward_to_shard(
+            ReplicationId::Key("nowhere".into()),
+            ForwardedOp::MGet(vec!["a".into()]),
+            tx.into(),
+        )
+        .await;
+
+    // THEN
+    let err = rx.await.unwrap().unwrap_err();
+    assert!(err.to_string().starts_with("CLUSTERDOWN"));
+}
+
+#[tokio::test]
+async fn test_expired_forward_fails_its_callback() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let other_replid = ReplicationId::Key("other".to_string());
+    let (buf, _) = cluster_actor.test_add_peer(6942, Some(other_replid.clone()), true);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .forward_to_shard(other_replid, ForwardedOp::Exists(vec!["a".into()]), tx.into())
+        .await;
+    let Some(QueryIO::ForwardRequest(request)) = buf.lock().await.pop_back() else {
+        panic!("request must be sent to the shard leader");
+    };
+
+    // WHEN
+    cluster_actor.expire_forward(request.id);
+
+    // THEN
+    let err = rx.await.unwrap().unwrap_err();
+    assert!(err.to_string().starts_with("TRYAGAIN"));
+}
+
+#[tokio::test]
+async fn test_serve_forward_rejects_keys_owned_elsewhere() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let other_replid = ReplicationId::Key("other".to_string());
+    let (buf, from) = cluster_actor.test_add_peer(6943, Some(other_replid.clone()), true);
+    cluster_actor.hash_ring = HashRing::default().add_partitions(vec![
+        (cluster_actor.replication.replid.clone(), cluster_actor.replication.self_identifier()),
+        (other_replid.clone(), from.clone()),
+    ]);
+    let (_, remote) = two_partition_keys(&cluster_actor, &other_replid);
+    let id = ForwardId("forward".into());
+
+    // WHEN
+    cluster_actor
+        .serve_forward(
+            ForwardRequest { id: id.clone(), op: ForwardedOp::MGet(vec![remote]) },
+            &cache_manager,
+            from.clone(),
+        )
+        .await;
+    let Some(ClusterCommand::Scheduler(SchedulerMessage::SendForwardResponse { to, response })) =
+        cluster_actor.receiver.recv().await
+    else {
+        panic!("response must be scheduled");
+    };
+    cluster_actor.send_forward_response(to, response).await;
+
+    // THEN
+    let Some(QueryIO::ForwardResponse(response)) = buf.lock().await.pop_back() else {
+        panic!("response must be sent back");
+    };
+    assert_eq!(response.id, id);
+    assert_eq!(response.reply, ForwardedReply::Err(format!("MOVED {other_replid}")));
+}
+
+fn prepare_request(txn_id: &TxnId, key: &str) -> WriteRequest {
+    WriteRequest::TxnPrepare {
+        txn_id: txn_id.clone(),
+        requests: vec![WriteRequest::Set {
+            key: key.to_string().into(),
+            value: "v".into(),
+            expires_at: None,
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_writes_to_keys_of_prepared_transaction_are_rejected() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let txn_id = TxnId::new(ReplicationId::Key("coordinator".into()));
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(prepare_request(&txn_id, "a"), tx, None))
+        .await;
+
+    // WHEN
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::Delete { keys: vec!["a".into()] },
+            tx,
+            None,
+        ))
+        .await;
+
+    // THEN
+    let ConsensusClientResponse::Err(err) = rx.await.unwrap() else {
+        panic!("write to a locked key must be rejected");
+    };
+    assert_eq!(err, format!("TRYAGAIN keys locked by transaction {}", txn_id.id));
+}
+
+#[tokio::test]
+async fn test_undecided_transaction_is_aborted_when_asked_for() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let txn_id = TxnId::new(cluster_actor.replication.replid.clone());
+
+    // WHEN
+    let status = cluster_actor.transaction_status(&txn_id).await;
+
+    // THEN
+    assert_eq!(status.await.unwrap(), Some(TxnOutcome::Abort));
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::TxnDecision { txn_id: txn_id.clone(), outcome: TxnOutcome::Commit },
+            tx,
+            None,
+        ))
+        .await;
+    let ConsensusClientResponse::Err(err) = rx.await.unwrap() else {
+        panic!("commit of an aborted transaction must be rejected");
+    };
+    assert_eq!(err, format!("transaction {} was already aborted", txn_id.id));
+}
+
+#[tokio::test]
+async fn test_transaction_status_is_answered_once_the_abort_is_committed() {
+    // GIVEN - writes wait in the group until it is committed
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    cluster_actor.group_commit = GroupCommit::new(1_000_000, 10);
+    let txn_id = TxnId::new(cluster_actor.replication.replid.clone());
+
+    // WHEN
+    let mut status = cluster_actor.transaction_status(&txn_id).await;
+
+    // THEN - the abort is only queued
+    assert!(status.try_recv().is_err());
+
+    cluster_actor.commit_group().await;
+    assert_eq!(status.await.unwrap(), Some(TxnOutcome::Abort));
+    assert_eq!(cluster_actor.logger.last_log_index, 1);
+}
+
+#[tokio::test]
+async fn test_decision_queued_after_a_contradicting_one_is_rejected() {
+    // GIVEN - the coordinator's commit and a participant's question share a group
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    cluster_actor.group_commit = GroupCommit::new(1_000_000, 10);
+    let txn_id = TxnId::new(cluster_actor.replication.replid.clone());
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::TxnDecision { txn_id: txn_id.clone(), outcome: TxnOutcome::Commit },
+            tx,
+            None,
+        ))
+        .await;
+    let status = cluster_actor.transaction_status(&txn_id).await;
+
+    // WHEN
+    cluster_actor.commit_group().await;
+
+    // THEN - only the commit is appended, and the participant learns it once it asks again
+    assert!(matches!(rx.await.unwrap(), ConsensusClientResponse::LogIndex(1, _)));
+    assert_eq!(status.await.unwrap(), None);
+    assert_eq!(cluster_actor.logger.last_log_index, 1);
+    let status = cluster_actor.transaction_status(&txn_id).await;
+    assert_eq!(status.await.unwrap(), Some(TxnOutcome::Commit));
+}
+
+#[tokio::test]
+async fn test_commit_of_unprepared_transaction_is_already_processed() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let txn_id = TxnId::new(ReplicationId::Key("coordinator".into()));
+
+    // WHEN
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(
+            WriteRequest::TxnCommit { txn_id, requests: vec![] },
+            tx,
+            None,
+        ))
+        .await;
+
+    // THEN
+    assert!(matches!(rx.await.unwrap(), ConsensusClientResponse::AlreadyProcessed { .. }));
+    assert_eq!(cluster_actor.logger.last_log_index, 0);
+}
+
+#[tokio::test]
+async fn test_prepared_transaction_without_outcome_is_resolved_by_aborting() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let txn_id = TxnId::new(cluster_actor.replication.replid.clone());
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    cluster_actor
+        .leader_req_consensus(ConsensusRequest::new(prepare_request(&txn_id, "a"), tx, None))
+        .await;
+
+    // WHEN
+    cluster_actor.resolve_transaction(txn_id.clone(), &cache_manager).await;
+
+    // THEN
+    let Some(ClusterCommand::Client(ClientMessage::LeaderReqConsensus(req))) =
+        cluster_actor.receiver.recv().await
+    else {
+        panic!("abort must be proposed");
+    };
+    assert_eq!(req.request, WriteRequest::TxnAbort { txn_id: txn_id.clone() });
+    cluster_actor.leader_req_consensus(req).await;
+    assert!(cluster_actor.transactions.prepared(&txn_id).is_none());
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
+use crate::domains::cluster_actors::consensus::compaction::SavePolicy;
 
 #[test]
 fn logger_create_entries_from_lowest() {
@@ -46,7 +48,7 @@ async fn test_generate_follower_entries() {
     Helper::cluster_member(
         &mut cluster_actor,
         follower_buffs.clone(),
-        ClusterCommandHandler(cluster_sender.clone()),
+        ClusterCommandHandler::new(cluster_sender.clone(), Mailbox::default()),
         3,
         Some(replid.clone()),
     );
@@ -67,7 +69,7 @@ async fn test_generate_follower_entries() {
     Helper::cluster_member(
         &mut cluster_actor,
         follower_buffs,
-        ClusterCommandHandler(cluster_sender),
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
         1,
         Some(replid),
     );
@@ -103,6 +105,9 @@ async fn follower_cluster_actor_replicate_log() {
     );
     let cache_manager = CacheManager {
         inboxes: (0..10).map(|_| CacheCommandSender(channel(10).0)).collect::<Vec<_>>(),
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
     };
     cluster_actor.replicate(heartbeat, &cache_manager).await;
 
@@ -135,7 +140,12 @@ async fn follower_cluster_actor_sessionless_replicate_state() {
         vec![Helper::write(1, 0, "foo", "bar"), Helper::write(2, 0, "foo2", "bar")],
     );
 
-    let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(cache_handler)] };
+    let cache_manager = CacheManager {
+        inboxes: vec![CacheCommandSender(cache_handler)],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
     cluster_actor.replicate(heartbeat, &cache_manager).await;
 
     // WHEN - commit until 2
@@ -184,7 +194,12 @@ async fn replicate_stores_only_latest_session_per_client() {
         ],
     );
 
-    let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(cache_handler)] };
+    let cache_manager = CacheManager {
+        inboxes: vec![CacheCommandSender(cache_handler)],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
     // WHEN
     cluster_actor.replicate(heartbeat, &cache_manager).await;
 
@@ -206,7 +221,12 @@ async fn follower_cluster_actor_replicate_state_only_upto_hwm() {
     );
 
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-    let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+    let cache_manager = CacheManager {
+        inboxes: vec![CacheCommandSender(tx)],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
 
     // This just appends the entries to the log but doesn't commit them
     cluster_actor.replicate(heartbeat, &cache_manager).await;
@@ -266,7 +286,12 @@ async fn test_apply_multiple_committed_entries() {
     let heartbeat = Helper::heartbeat(1, 0, entries);
 
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-    let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+    let cache_manager = CacheManager {
+        inboxes: vec![CacheCommandSender(tx)],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
 
     // First append entries but don't commit
     cluster_actor.replicate(heartbeat, &cache_manager).await;
@@ -277,7 +302,7 @@ async fn test_apply_multiple_committed_entries() {
 
         while let Some(message) = rx.recv().await {
             if let CacheCommand::Set { cache_entry } = message {
-                applied_keys.push(cache_entry.key().to_string());
+                applied_keys.push(cache_entry.key.clone());
                 if applied_keys.len() == 3 {
                     break;
                 }
@@ -303,6 +328,67 @@ async fn test_apply_multiple_committed_entries() {
     assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), 3);
 }
 
+#[tokio::test]
+async fn follower_applies_committed_entries_across_cache_shards_in_log_order() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let cache_manager = CacheManager::run_cache_actors(Arc::new(AtomicU64::new(0)));
+    let keys = (0..20).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+
+    let mut requests = vec![];
+    for key in &keys {
+        requests.push(WriteRequest::Set {
+            key: key.clone().into(),
+            value: "1".into(),
+            expires_at: None,
+        });
+    }
+    for key in &keys {
+        requests.push(WriteRequest::Incr { key: key.clone().into(), delta: 1 });
+    }
+    // * Spans several shards, so it waits for the increments before it
+    requests.push(WriteRequest::Delete {
+        keys: keys[..10].iter().map(|key| key.clone().into()).collect(),
+    });
+    for key in &keys[..10] {
+        requests.push(WriteRequest::Append { key: key.clone().into(), value: "x".into() });
+    }
+    let entries: Vec<_> = requests
+        .into_iter()
+        .zip(1..)
+        .map(|(request, log_index)| WriteOperation {
+            request,
+            log_index,
+            term: 0,
+            session_req: None,
+            timestamp: 0,
+        })
+        .collect();
+    let last_index = entries.len() as u64;
+    cluster_actor.replicate(Helper::heartbeat(0, 0, entries), &cache_manager).await;
+
+    // WHEN
+    cluster_actor.replicate(Helper::heartbeat(0, last_index, vec![]), &cache_manager).await;
+
+    // THEN
+    assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), last_index);
+    for key in &keys[..10] {
+        assert_eq!(cache_manager.route_get(key).await.unwrap().value, "x");
+    }
+    for key in &keys[10..] {
+        assert_eq!(cache_manager.route_get(key).await.unwrap().value, "2");
+    }
+
+    // WHEN - the commit index runs past the log
+    let late = Helper::write(last_index + 1, 0, "late", "value");
+    cluster_actor.replicate(Helper::heartbeat(0, last_index, vec![late]), &cache_manager).await;
+    cluster_actor.replicate(Helper::heartbeat(0, last_index + 2, vec![]), &cache_manager).await;
+
+    // THEN - nothing of the batch is applied
+    assert_eq!(cluster_actor.replication.hwm.load(Ordering::Relaxed), last_index);
+    assert_eq!(cache_manager.route_get("late").await.unwrap().value, TypedValue::Null);
+}
+
 #[tokio::test]
 async fn test_partial_commit_with_new_entries() {
     // GIVEN
@@ -315,7 +401,12 @@ async fn test_partial_commit_with_new_entries() {
     let first_heartbeat = Helper::heartbeat(1, 0, first_entries);
 
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-    let cache_manager = CacheManager { inboxes: vec![CacheCommandSender(tx)] };
+    let cache_manager = CacheManager {
+        inboxes: vec![CacheCommandSender(tx)],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
 
     cluster_actor.replicate(first_heartbeat, &cache_manager).await;
 
@@ -325,7 +416,7 @@ async fn test_partial_commit_with_new_entries() {
 
         while let Some(message) = rx.recv().await {
             if let CacheCommand::Set { cache_entry } = message {
-                applied_keys.push(cache_entry.key().to_string());
+                applied_keys.push(cache_entry.key.clone());
                 if applied_keys.len() == 1 {
                     break;
                 }
@@ -416,6 +507,35 @@ async fn follower_rejects_entries_with_empty_log_and_prev_log_index_nonzero() {
     assert_eq!(cluster_actor.logger.last_log_index, 0); // Log should remain unchanged
 }
 
+#[tokio::test]
+async fn test_consensus_report_shows_in_flight_entries_and_peer_progress() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, follower_id) = cluster_actor.test_add_peer(6380, None, false);
+    let (_, other_shard_id) =
+        cluster_actor.test_add_peer(6381, Some(ReplicationId::Key("other".into())), true);
+    let (tx, _) = tokio::sync::oneshot::channel();
+    let w_req = WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None };
+
+    // WHEN
+    cluster_actor.req_consensus(ConsensusRequest::new(w_req, Callback(tx), None)).await;
+    let report = cluster_actor.consensus_report();
+
+    // THEN
+    assert_eq!(report.election_state, "leader");
+    assert_eq!(report.last_log_index, 1);
+    assert_eq!(report.commit_index, 0);
+    assert_eq!(
+        report.in_flight,
+        vec![InFlightEntry { index: 1, votes: 1, required_votes: 2, voters: vec![] }]
+    );
+    let same_shard =
+        |id: &PeerIdentifier| report.peers.iter().find(|peer| &peer.id == id).unwrap().same_shard;
+    assert!(same_shard(&follower_id));
+    assert!(!same_shard(&other_shard_id));
+    assert_eq!(report.pending_requests, None);
+}
+
 #[tokio::test]
 async fn req_consensus_inserts_consensus_voting() {
     // GIVEN
@@ -429,7 +549,7 @@ async fn req_consensus_inserts_consensus_voting() {
     Helper::cluster_member(
         &mut cluster_actor,
         follower_buffs.clone(),
-        ClusterCommandHandler(cluster_sender),
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
         0,
         Some(replid),
     );
@@ -465,7 +585,9 @@ async fn req_consensus_inserts_consensus_voting() {
                     log_index: 1,
                     term: 0,
                     session_req: Some(session_request.clone()),
+                    timestamp: 0,
                 }],
+                weight: 1,
                 ..Default::default()
             }),
         )
@@ -473,12 +595,160 @@ async fn req_consensus_inserts_consensus_voting() {
     }
 }
 
+#[tokio::test]
+async fn req_consensus_groups_writes_into_a_single_append_entries() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    cluster_actor.group_commit = GroupCommit::new(1_000_000, 3);
+    let replid = cluster_actor.replication.replid.clone();
+    let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+    let follower_buffs = (0..2).map(|_| FakeReadWrite::new()).collect::<Vec<_>>();
+    Helper::cluster_member(
+        &mut cluster_actor,
+        follower_buffs.clone(),
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
+        0,
+        Some(replid),
+    );
+    let writes = ["a", "b", "c"].map(|key| Helper::write(0, 0, key, "v").request);
+
+    // WHEN
+    for w_req in writes[..2].iter() {
+        let (tx, _) = tokio::sync::oneshot::channel();
+        cluster_actor.req_consensus(ConsensusRequest::new(w_req.clone(), Callback(tx), None)).await;
+    }
+
+    // THEN - the group waits for its window to close
+    assert_eq!(cluster_actor.logger.last_log_index, 0);
+    assert_eq!(cluster_actor.consensus_tracker.len(), 0);
+
+    // WHEN - the group is full
+    let (tx, _) = tokio::sync::oneshot::channel();
+    cluster_actor.req_consensus(ConsensusRequest::new(writes[2].clone(), Callback(tx), None)).await;
+
+    // THEN
+    assert_eq!(cluster_actor.logger.last_log_index, 3);
+    assert_eq!(cluster_actor.consensus_tracker.len(), 3);
+    for follower in follower_buffs {
+        assert_expected_queryio(
+            &follower,
+            QueryIO::AppendEntriesRPC(HeartBeat {
+                from: cluster_actor.replication.self_identifier(),
+                replid: cluster_actor.replication.replid.clone(),
+                append_entries: writes
+                    .iter()
+                    .zip(1..)
+                    .map(|(w_req, log_index)| WriteOperation {
+                        request: w_req.clone(),
+                        log_index,
+                        term: 0,
+                        session_req: None,
+                        timestamp: 0,
+                    })
+                    .collect(),
+                weight: 1,
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert!(follower.lock().await.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn commit_group_resolves_writes_together_without_replicas() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    cluster_actor.group_commit = GroupCommit::new(1_000_000, 10);
+    let mut receivers = vec![];
+    for key in ["a", "b"] {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let w_req = Helper::write(0, 0, key, "v").request;
+        cluster_actor.req_consensus(ConsensusRequest::new(w_req, Callback(tx), None)).await;
+        receivers.push(rx);
+    }
+
+    // WHEN - the window closes
+    cluster_actor.commit_group().await;
+
+    // THEN
+    assert_eq!(cluster_actor.logger.last_log_index, 2);
+    assert_eq!(cluster_actor.replication.hwm.load(Ordering::Acquire), 2);
+    for (rx, log_index) in receivers.into_iter().zip(1..) {
+        assert!(matches!(
+            rx.await.unwrap(),
+            ConsensusClientResponse::LogIndex(idx, _) if idx == log_index
+        ));
+    }
+}
+
+#[tokio::test]
+async fn append_entries_are_compressed_for_peers_that_agreed_to_it() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (buf, id) = cluster_actor.test_add_peer(6559, None, false);
+    let peer = cluster_actor.members.remove(&id).unwrap();
+    cluster_actor.members.insert(id, peer.with_compression(Compression::Lz4));
+
+    // WHEN
+    let (tx, _) = tokio::sync::oneshot::channel();
+    let w_req = Helper::write(0, 0, "foo", "bar").request;
+    cluster_actor.req_consensus(ConsensusRequest::new(w_req, Callback(tx), None)).await;
+    cluster_actor.send_rpc().await;
+
+    // THEN
+    let mut sent = buf.lock().await;
+    let Some(QueryIO::CompressedAppendEntriesRPC(heartbeat, Compression::Lz4)) = sent.pop_front()
+    else {
+        panic!("entries should be sent compressed");
+    };
+    assert_eq!(heartbeat.append_entries.len(), 1);
+    // * Heartbeats without entries have nothing to compress
+    assert!(
+        matches!(sent.pop_front(), Some(QueryIO::AppendEntriesRPC(heartbeat)) if heartbeat.append_entries.is_empty())
+    );
+}
+
+#[tokio::test]
+async fn replicas_equally_caught_up_are_sent_the_same_entries_in_their_own_codec() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (compressed_buf, id) = cluster_actor.test_add_peer(6560, None, false);
+    let peer = cluster_actor.members.remove(&id).unwrap();
+    cluster_actor.members.insert(id, peer.with_compression(Compression::Lz4));
+    let (plain_buf, _) = cluster_actor.test_add_peer(6561, None, false);
+    let (other_plain_buf, _) = cluster_actor.test_add_peer(6562, None, false);
+
+    // WHEN
+    let (tx, _) = tokio::sync::oneshot::channel();
+    let w_req = Helper::write(0, 0, "foo", "bar").request;
+    cluster_actor.req_consensus(ConsensusRequest::new(w_req, Callback(tx), None)).await;
+    cluster_actor.send_rpc().await;
+
+    // THEN
+    let compressed = compressed_buf.lock().await.pop_front();
+    let Some(QueryIO::CompressedAppendEntriesRPC(compressed, Compression::Lz4)) = compressed else {
+        panic!("entries should be sent compressed");
+    };
+    let Some(QueryIO::AppendEntriesRPC(plain)) = plain_buf.lock().await.pop_front() else {
+        panic!("entries should be sent as they are");
+    };
+    assert_eq!(plain.append_entries.len(), 1);
+    assert_eq!(compressed, plain);
+    assert_eq!(other_plain_buf.lock().await.pop_front(), Some(QueryIO::AppendEntriesRPC(plain)));
+}
+
 #[tokio::test]
 async fn test_leader_req_consensus_early_return_when_already_processed_session_req_given() {
     // GIVEN
     let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
 
-    let cache_manager = CacheManager { inboxes: vec![] };
+    let cache_manager = CacheManager {
+        inboxes: vec![],
+        leases: LeaseActor::run(),
+        hash_seed: 0,
+        batches: Default::default(),
+    };
 
     let client_id = Uuid::now_v7();
     let client_req = SessionRequest::new(1, client_id);
@@ -513,7 +783,7 @@ async fn test_consensus_voting_deleted_when_consensus_reached() {
     Helper::cluster_member(
         &mut cluster_actor,
         follower_buffs.clone(),
-        ClusterCommandHandler(cluster_sender),
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
         0,
         Some(replid),
     );
@@ -560,7 +830,7 @@ async fn test_same_voter_can_vote_only_once() {
     Helper::cluster_member(
         &mut cluster_actor,
         follower_buffs.clone(),
-        ClusterCommandHandler(cluster_sender),
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
         0,
         Some(replid),
     );
@@ -625,6 +895,135 @@ async fn test_leader_req_consensus_with_pending_requests() {
     assert_eq!(cluster_actor.logger.last_log_index, 0);
 }
 
+#[tokio::test]
+async fn test_leader_req_consensus_rejects_writes_beyond_pending_cap() {
+    // GIVEN
+    let mut cluster_actor = setup_blocked_cluster_actor_with_requests(2).await;
+    cluster_actor.pending_write_limit = PendingWriteLimit::new(2, 5000);
+
+    // WHEN
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cluster_actor.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+
+    // THEN
+    assert!(
+        matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+    assert_eq!(
+        cluster_actor.pending_write_stats(),
+        PendingWriteStats { held: 2, rejected: 1, timed_out: 0, consensus_timed_out: 0 }
+    );
+}
+
+#[tokio::test]
+async fn test_expire_pending_requests_answers_writes_past_deadline() {
+    // GIVEN
+    let mut cluster_actor = Helper::cluster_actor(ReplicationRole::Leader).await;
+    cluster_actor.block_write_reqs();
+    let (expired_tx, expired_rx) = tokio::sync::oneshot::channel();
+    cluster_actor.leader_req_consensus(Helper::consensus_request(expired_tx, None)).await;
+    cluster_actor.pending_requests.as_mut().unwrap()[0].deadline = Some(Instant::now());
+    let (held_tx, mut held_rx) = tokio::sync::oneshot::channel();
+    cluster_actor.leader_req_consensus(Helper::consensus_request(held_tx, None)).await;
+
+    // WHEN
+    cluster_actor.expire_pending_requests();
+
+    // THEN
+    assert!(
+        matches!(expired_rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+    assert!(held_rx.try_recv().is_err());
+    assert!(cluster_actor.pending_requests.as_ref().unwrap()[0].deadline.is_some());
+    assert_eq!(
+        cluster_actor.pending_write_stats(),
+        PendingWriteStats { held: 1, rejected: 0, timed_out: 1, consensus_timed_out: 0 }
+    );
+}
+
+#[tokio::test]
+async fn test_expire_consensus_requests_answers_writes_no_majority_acked() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.pending_write_limit = PendingWriteLimit::new(2, 5000).with_consensus_timeout(1);
+    leader.test_add_peer(6597, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.req_consensus(Helper::consensus_request(tx, None)).await;
+    assert_eq!(leader.consensus_tracker.len(), 1);
+
+    // WHEN
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    leader.expire_consensus_requests(&cache_manager);
+
+    // THEN
+    assert!(
+        matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+    assert_eq!(leader.pending_write_stats().consensus_timed_out, 1);
+    // * The entry stays in the log and tracked, and may still be committed
+    assert_eq!(leader.consensus_tracker.len(), 1);
+    assert_eq!(leader.logger.last_log_index, 1);
+}
+
+#[tokio::test]
+async fn test_write_that_timed_out_is_applied_and_recorded_once_committed() {
+    // GIVEN - a write that no majority acknowledged in time
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.pending_write_limit = PendingWriteLimit::new(2, 5000).with_consensus_timeout(1);
+    let (_, replica) = leader.test_add_peer(6596, None, false);
+    let (_hwm, cache_manager) = Helper::cache_manager();
+    let session_req = SessionRequest::new(1, Uuid::now_v7());
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(tx, Some(session_req.clone()))).await;
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    leader.expire_consensus_requests(&cache_manager);
+    assert!(
+        matches!(rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+
+    // WHEN - its client retries before and after the replica acknowledges it after all
+    let (early_tx, early_rx) = tokio::sync::oneshot::channel();
+    leader
+        .leader_req_consensus(Helper::consensus_request(early_tx, Some(session_req.clone())))
+        .await;
+    let ack = ReplicationAck::ack(1, &leader.replication).set_from(&replica);
+    leader.ack_replication(ack).await;
+    let (late_tx, late_rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(late_tx, Some(session_req))).await;
+
+    // THEN - the leader applies the entry itself, and neither retry appends it again
+    assert!(
+        matches!(early_rx.await.unwrap(), ConsensusClientResponse::Err(e) if e.starts_with("TRYAGAIN"))
+    );
+    assert!(matches!(late_rx.await.unwrap(), ConsensusClientResponse::AlreadyProcessed { .. }));
+    assert!(leader.consensus_tracker.is_empty());
+    assert_eq!(leader.logger.last_log_index, 1);
+    assert_eq!(leader.applied.settle(1, Duration::from_secs(1)).await, 1);
+    assert_eq!(cache_manager.route_get("foo").await.unwrap(), CacheValue::new("bar"));
+}
+
+#[tokio::test]
+async fn test_commit_answers_every_write_up_to_the_acked_index() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, replica) = leader.test_add_peer(6598, None, false);
+    let (first_tx, first_rx) = tokio::sync::oneshot::channel();
+    leader.req_consensus(Helper::consensus_request(first_tx, None)).await;
+    let (second_tx, second_rx) = tokio::sync::oneshot::channel();
+    leader.req_consensus(Helper::consensus_request(second_tx, None)).await;
+
+    // WHEN - the replica acknowledges both entries at once
+    let ack = ReplicationAck::ack(2, &leader.replication).set_from(&replica);
+    leader.ack_replication(ack).await;
+
+    // THEN
+    assert!(matches!(first_rx.await.unwrap(), ConsensusClientResponse::LogIndex(1, _)));
+    assert!(matches!(second_rx.await.unwrap(), ConsensusClientResponse::LogIndex(2, _)));
+    assert!(leader.consensus_tracker.is_empty());
+    assert_eq!(leader.replication.hwm.load(Ordering::Relaxed), 2);
+}
+
 #[tokio::test]
 async fn test_leader_req_consensus_with_processed_session() {
     // GIVEN
@@ -657,3 +1056,529 @@ async fn test_leader_req_consensus_with_processed_session() {
     assert_eq!(key, vec!["test_key".to_string()]);
     assert_eq!(index, 0);
 }
+
+#[tokio::test]
+async fn test_read_index_waits_until_majority_answers() {
+    // GIVEN: a leader with two replicas that have not answered yet
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (replica_buf, replica_id) = leader.test_add_peer(6591, None, false);
+    let _ = leader.test_add_peer(6592, None, false);
+
+    // WHEN
+    let (tx, mut rx) = tokio::sync::oneshot::channel();
+    leader.read_index(tx.into()).await;
+
+    // THEN: the read waits for a heartbeat round, which is sent right away
+    assert!(rx.try_recv().is_err());
+    assert!(matches!(replica_buf.lock().await.pop_front(), Some(QueryIO::AppendEntriesRPC(_))));
+
+    // WHEN: one replica answers - together with the leader that is a majority
+    leader
+        .ack_replication(ReplicationAck::heartbeat(&leader.replication).set_from(&replica_id))
+        .await;
+    leader.confirm_pending_reads();
+
+    // THEN
+    assert_eq!(rx.await.unwrap().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_read_index_fails_once_leadership_is_lost() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let _ = leader.test_add_peer(6593, None, false);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.read_index(tx.into()).await;
+
+    // WHEN
+    leader.replication.vote_for(None);
+    leader.confirm_pending_reads();
+
+    // THEN
+    assert!(rx.await.unwrap().is_err());
+
+    // AND: followers do not serve linearizable reads
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.read_index(tx.into()).await;
+    assert!(rx.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_replica_staleness_is_bounded_by_leader_hwm() {
+    // GIVEN
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_, cache_manager) = Helper::cache_manager();
+    assert!(follower.check_replica_staleness(100).is_err());
+
+    // WHEN - the leader reports a commit index 5 entries ahead
+    follower.append_entries_rpc(&cache_manager, Helper::heartbeat(0, 5, vec![])).await;
+
+    // THEN
+    assert!(follower.check_replica_staleness(5).is_ok());
+    assert!(follower.check_replica_staleness(4).is_err());
+
+    // AND: writes are redirected to the leader
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    follower.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+    assert_eq!(
+        rx.await.unwrap(),
+        ConsensusClientResponse::Err(format!("MOVED {}", PeerIdentifier::new("localhost", 8080)))
+    );
+}
+
+#[tokio::test]
+async fn test_wait_answers_once_enough_replicas_ack_the_index() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, replica_a) = leader.test_add_peer(6594, None, false);
+    let (_, replica_b) = leader.test_add_peer(6595, None, false);
+    let (tx, mut rx) = tokio::sync::oneshot::channel();
+    leader.wait_for_replicas(2, 2, 0, tx.into());
+
+    // WHEN - only one replica acked the index
+    let ack = ReplicationAck::ack(2, &leader.replication).set_from(&replica_a);
+    leader.ack_replication(ack).await;
+    leader.resolve_replica_waits();
+
+    // THEN
+    assert!(rx.try_recv().is_err());
+
+    // WHEN - the second replica catches up
+    let ack = ReplicationAck::ack(2, &leader.replication).set_from(&replica_b);
+    leader.ack_replication(ack).await;
+    leader.resolve_replica_waits();
+
+    // THEN
+    assert_eq!(rx.await.unwrap().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_wait_returns_acked_replicas_on_timeout() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, replica) = leader.test_add_peer(6596, None, false);
+    let ack = ReplicationAck::ack(1, &leader.replication).set_from(&replica);
+    leader.ack_replication(ack).await;
+
+    // WHEN
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.wait_for_replicas(1, 3, 10, tx.into());
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    leader.resolve_replica_waits();
+
+    // THEN
+    assert_eq!(rx.await.unwrap().unwrap(), 1);
+
+    // AND: replicas do not accept WAIT
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    follower.wait_for_replicas(0, 0, 0, tx.into());
+    assert!(rx.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_writes_rejected_without_enough_good_replicas() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.min_replicas = MinReplicas::new(1, 1000);
+    let (_, replica) = leader.test_add_peer(6597, None, false);
+
+    // WHEN - the replica has not answered yet
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+
+    // THEN
+    let ConsensusClientResponse::Err(err) = rx.await.unwrap() else { panic!() };
+    assert!(err.starts_with("NOREPLICAS"));
+    assert_eq!(leader.logger.last_log_index, 0);
+
+    // WHEN - the replica answers a heartbeat
+    leader.ack_replication(ReplicationAck::heartbeat(&leader.replication).set_from(&replica)).await;
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    leader.leader_req_consensus(Helper::consensus_request(tx, None)).await;
+
+    // THEN
+    assert_eq!(leader.logger.last_log_index, 1);
+}
+
+#[tokio::test]
+async fn test_append_entries_pipeline_in_flight_entries() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, replica) = leader.test_add_peer(6598, None, false);
+    let write = |key: &str| WriteRequest::Set {
+        key: key.to_string().into(),
+        value: "v".into(),
+        expires_at: None,
+    };
+    leader.logger.write_single_entry(&write("a"), 0, None).unwrap();
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+    assert_eq!(sent[0].append_entries.len(), 1);
+
+    // WHEN - another write goes out before the replica acknowledges the first one
+    leader.logger.write_single_entry(&write("b"), 0, None).unwrap();
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+
+    // THEN - only the new entry is sent, chained after the one in flight
+    assert_eq!(sent[0].append_entries.iter().map(|op| op.log_index).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(sent[0].prev_log_index, 1);
+
+    // WHEN - the replica rejects the first entry
+    let rejection =
+        ReplicationAck::reject(0, RejectionReason::LogInconsistency, &leader.replication)
+            .set_from(&replica);
+    leader.ack_replication(rejection).await;
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+
+    // THEN - the whole window is sent again
+    assert_eq!(
+        sent[0].append_entries.iter().map(|op| op.log_index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(sent[0].prev_log_index, 0);
+}
+
+#[tokio::test]
+async fn test_append_entries_capped_by_inflight_window() {
+    // GIVEN - a replica far behind the leader
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.append_entries_budget = AppendEntriesBudget::new(usize::MAX, usize::MAX);
+    let _ = leader.test_add_peer(6599, None, false);
+    let entries = (1..=MAX_INFLIGHT_ENTRIES + 10)
+        .map(|idx| Helper::write(idx, 0, &format!("key{idx}"), "v"))
+        .collect::<Vec<_>>();
+    leader.logger.follower_write_entries(entries).unwrap();
+
+    // WHEN
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+
+    // THEN
+    assert_eq!(sent[0].append_entries.len() as u64, MAX_INFLIGHT_ENTRIES);
+
+    // WHEN - nothing was acknowledged yet
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+
+    // THEN - the window is full
+    assert!(sent[0].append_entries.is_empty());
+}
+
+#[tokio::test]
+async fn test_append_entries_batches_stream_as_replica_acks() {
+    // GIVEN - a replica 5 entries behind and a budget of 2 entries per message
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.append_entries_budget = AppendEntriesBudget::new(2, usize::MAX);
+    let (buf, replica) = leader.test_add_peer(6600, None, false);
+    let entries = (1..=5).map(|idx| Helper::write(idx, 0, &format!("key{idx}"), "v")).collect();
+    leader.logger.follower_write_entries(entries).unwrap();
+
+    // WHEN
+    let sent = leader.iter_follower_append_entries().await.map(|(_, hb)| hb).collect::<Vec<_>>();
+
+    // THEN
+    assert_eq!(
+        sent[0].append_entries.iter().map(|op| op.log_index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+
+    // WHEN - the replica acknowledges the first batch
+    let ack = ReplicationAck::ack(2, &leader.replication).set_from(&replica);
+    leader.ack_replication(ack).await;
+
+    // THEN - the next batch goes out right away
+    let QueryIO::AppendEntriesRPC(next) = buf.lock().await.pop_back().unwrap() else { panic!() };
+    assert_eq!(next.append_entries.iter().map(|op| op.log_index).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(next.prev_log_index, 2);
+}
+
+#[tokio::test]
+async fn test_leader_leaves_chained_replicas_to_their_upstream() {
+    // GIVEN - a replica chained behind another one
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (_, relay) = leader.test_add_peer(6601, None, false);
+    let (_, chained) = leader.test_add_peer(6602, None, false);
+    leader.members.get_mut(&chained).unwrap().set_upstream(Some(relay.clone()));
+    leader.logger.write_single_entry(&Helper::write(1, 0, "a", "v").request, 0, None).unwrap();
+
+    // WHEN
+    let sent = leader.iter_follower_append_entries().await.map(|(peer, _)| peer.id().clone());
+
+    // THEN - only the relay is sent the log
+    assert_eq!(sent.collect::<Vec<_>>(), vec![relay.clone()]);
+
+    // WHEN - the relay goes away
+    leader.members.remove(&relay);
+    let sent = leader.iter_follower_append_entries().await.map(|(peer, _)| peer.id().clone());
+
+    // THEN - the chained replica is served directly
+    assert_eq!(sent.collect::<Vec<_>>(), vec![chained]);
+}
+
+#[tokio::test]
+async fn test_relay_passes_appended_entries_downstream() {
+    // GIVEN
+    let mut relay = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_, cache_manager) = Helper::cache_manager();
+    let (_, leader) = relay.test_add_peer(6603, None, true);
+    let (downstream_buf, downstream) = relay.test_add_peer(6604, None, false);
+    let self_id = relay.replication.self_identifier();
+    relay.members.get_mut(&downstream).unwrap().set_upstream(Some(self_id));
+
+    // WHEN
+    let mut heartbeat = Helper::heartbeat(0, 0, vec![Helper::write(1, 0, "a", "v")]);
+    heartbeat.from = leader.clone();
+    relay.append_entries_rpc(&cache_manager, heartbeat).await;
+
+    // THEN - the downstream replica gets the entry as if it came from the leader
+    let QueryIO::AppendEntriesRPC(relayed) = downstream_buf.lock().await.pop_back().unwrap() else {
+        panic!()
+    };
+    assert_eq!(relayed.from, leader);
+    assert_eq!(relayed.append_entries.iter().map(|op| op.log_index).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(relayed.prev_log_index, 0);
+}
+
+#[tokio::test]
+async fn test_relay_passes_downstream_acks_to_leader() {
+    // GIVEN
+    let mut relay = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_, cache_manager) = Helper::cache_manager();
+    let (leader_buf, leader) = relay.test_add_peer(6605, None, true);
+    let (_, downstream) = relay.test_add_peer(6606, None, false);
+    let self_id = relay.replication.self_identifier();
+    relay.members.get_mut(&downstream).unwrap().set_upstream(Some(self_id));
+    let mut heartbeat = Helper::heartbeat(0, 0, vec![]);
+    heartbeat.from = leader.clone();
+    relay.append_entries_rpc(&cache_manager, heartbeat).await;
+
+    // WHEN
+    let ack = ReplicationAck::ack(3, &relay.replication).set_from(&downstream);
+    relay.ack_replication(ack.clone()).await;
+
+    // THEN
+    let QueryIO::Ack(relayed) = leader_buf.lock().await.pop_back().unwrap() else { panic!() };
+    assert_eq!(relayed, ack);
+    assert_eq!(relay.members[&downstream].match_index(), 3);
+}
+
+#[tokio::test]
+async fn test_chained_replica_acks_through_its_upstream() {
+    // GIVEN
+    let mut replica = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let (_, cache_manager) = Helper::cache_manager();
+    let (leader_buf, leader) = replica.test_add_peer(6607, None, true);
+    let (upstream_buf, upstream) = replica.test_add_peer(6608, None, false);
+    replica.replication.upstream = Some(upstream);
+
+    // WHEN
+    let mut heartbeat = Helper::heartbeat(0, 0, vec![Helper::write(1, 0, "a", "v")]);
+    heartbeat.from = leader;
+    replica.append_entries_rpc(&cache_manager, heartbeat).await;
+
+    // THEN
+    let QueryIO::Ack(ack) = upstream_buf.lock().await.pop_back().unwrap() else { panic!() };
+    assert_eq!(ack.log_idx, 1);
+    assert!(leader_buf.lock().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_rejection_behind_compacted_log_streams_snapshot_in_chunks() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let (buf, replica) = leader.test_add_peer(6599, None, false);
+    leader.logger.snapshot = Some(LogSnapshot {
+        last_included_index: 5,
+        last_included_term: 0,
+        data: vec![7; SNAPSHOT_CHUNK_SIZE * 2 + 1],
+    });
+
+    // WHEN - the replica rejects entries it is missing from the start of the log
+    let rejection =
+        ReplicationAck::reject(0, RejectionReason::LogInconsistency, &leader.replication)
+            .set_from(&replica);
+    leader.ack_replication(rejection).await;
+
+    // THEN - the snapshot is streamed in order, and the log tail follows from its last index
+    let chunks: Vec<InstallSnapshot> = buf
+        .lock()
+        .await
+        .drain(..)
+        .filter_map(|io| match io {
+            | QueryIO::InstallSnapshot(chunk) => Some(chunk),
+            | _ => None,
+        })
+        .collect();
+    assert_eq!(
+        chunks.iter().map(|c| c.offset as usize).collect::<Vec<_>>(),
+        vec![0, SNAPSHOT_CHUNK_SIZE, SNAPSHOT_CHUNK_SIZE * 2]
+    );
+    assert_eq!(chunks.iter().map(|c| c.done).collect::<Vec<_>>(), vec![false, false, true]);
+    assert_eq!(leader.members[&replica].match_index(), 5);
+}
+
+#[tokio::test]
+async fn test_rejection_far_behind_log_requests_catchup_snapshot() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.log_compaction = LogCompaction::new(0, 0, None).with_catchup_lag(3);
+    let (_, replica) = leader.test_add_peer(6600, None, false);
+    for key in ["a", "b", "c"] {
+        let write = WriteRequest::Set { key: key.into(), value: "v".into(), expires_at: None };
+        leader.logger.write_single_entry(&write, 0, None).unwrap();
+    }
+    assert!(!leader.log_compaction.should_compact(3, 0));
+
+    // WHEN
+    let rejection =
+        ReplicationAck::reject(0, RejectionReason::LogInconsistency, &leader.replication)
+            .set_from(&replica);
+    leader.ack_replication(rejection).await;
+
+    // THEN - a snapshot is taken on the next tick rather than replaying the log
+    assert!(leader.log_compaction.should_compact(3, 0));
+}
+
+#[tokio::test]
+async fn test_compaction_waits_for_committed_entries_being_applied() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.log_compaction = LogCompaction::new(1, 0, None);
+    let cache_manager = CacheManager::run_cache_actors(leader.replication.hwm.clone());
+    for key in ["a", "b"] {
+        let write = WriteRequest::Set { key: key.into(), value: "v".into(), expires_at: None };
+        leader.logger.write_single_entry(&write, 0, None).unwrap();
+    }
+    leader.replication.hwm.store(2, Ordering::Release);
+    let applying = leader.applied.track(2);
+
+    // WHEN - the second entry is still being applied
+    leader.maybe_compact_logs(&cache_manager).await;
+    leader.maybe_compact_logs(&cache_manager).await;
+
+    // THEN
+    assert_eq!(leader.logger.snapshot_index(), 0);
+    assert_eq!(leader.snapshot_metadata().log_idx, 1);
+
+    // WHEN - it is applied
+    drop(applying);
+    leader.maybe_compact_logs(&cache_manager).await;
+    leader.maybe_compact_logs(&cache_manager).await;
+
+    // THEN
+    assert_eq!(leader.logger.snapshot_index(), 2);
+    assert_eq!(leader.snapshot_metadata().log_idx, 2);
+}
+
+#[tokio::test]
+async fn test_compaction_does_not_wait_for_writes_awaiting_consensus() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.log_compaction = LogCompaction::new(2, 0, None);
+    let cache_manager = CacheManager::run_cache_actors(leader.replication.hwm.clone());
+    let replid = leader.replication.replid.clone();
+    let (cluster_sender, _) = tokio::sync::mpsc::channel(100);
+    Helper::cluster_member(
+        &mut leader,
+        vec![FakeReadWrite::new()],
+        ClusterCommandHandler::new(cluster_sender, Mailbox::default()),
+        0,
+        Some(replid),
+    );
+    for key in ["a", "b"] {
+        let write = WriteRequest::Set { key: key.into(), value: "v".into(), expires_at: None };
+        leader.logger.write_single_entry(&write, 0, None).unwrap();
+    }
+    leader.replication.hwm.store(2, Ordering::Release);
+
+    // WHEN - the next write is still waiting for the replica
+    let (tx, _rx) = tokio::sync::oneshot::channel();
+    let write = WriteRequest::Set { key: "c".into(), value: "v".into(), expires_at: None };
+    leader.req_consensus(ConsensusRequest::new(write, Callback(tx), None)).await;
+    leader.maybe_compact_logs(&cache_manager).await;
+
+    // THEN - the snapshot stops at the applied index and the pending write is left to commit
+    assert_eq!(leader.consensus_tracker.len(), 1);
+    assert_eq!(leader.logger.snapshot_index(), 2);
+    assert_eq!(leader.logger.last_log_index, 3);
+}
+
+#[tokio::test]
+async fn test_save_policy_snapshots_a_node_that_keeps_writing() {
+    // GIVEN - a window of a second, with far fewer writes than the change count
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    leader.log_compaction = LogCompaction::new(0, 0, None)
+        .with_save_policy(Some(SavePolicy { changes: 1000, seconds: 1 }));
+    let cache_manager = CacheManager::run_cache_actors(leader.replication.hwm.clone());
+    leader.maybe_compact_logs(&cache_manager).await;
+
+    // WHEN - writes keep coming on every tick while the window expires
+    for i in 0..15 {
+        let (tx, _) = tokio::sync::oneshot::channel();
+        let write =
+            WriteRequest::Set { key: format!("{i}").into(), value: "v".into(), expires_at: None };
+        leader.req_consensus(ConsensusRequest::new(write, Callback(tx), None)).await;
+        leader.maybe_compact_logs(&cache_manager).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // THEN
+    assert_ne!(leader.logger.snapshot_index(), 0);
+}
+
+#[tokio::test]
+async fn test_compacted_snapshot_carries_client_sessions_to_followers() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let cache_manager = CacheManager::run_cache_actors(leader.replication.hwm.clone());
+    let session_req = SessionRequest::new(4, Uuid::now_v7());
+    let write = WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None };
+    leader.logger.write_single_entry(&write, 0, Some(session_req.clone())).unwrap();
+    leader.client_sessions.set_response(Some(session_req.clone()));
+    leader.replication.hwm.store(1, Ordering::Release);
+
+    // WHEN
+    leader.compact_logs(1, 0, &cache_manager).await.unwrap();
+    let mut follower = Helper::cluster_actor(ReplicationRole::Follower).await;
+    let follower_cache = CacheManager::run_cache_actors(follower.replication.hwm.clone());
+    let snapshot = InstallSnapshot {
+        from: PeerIdentifier("leader".into()),
+        term: 0,
+        last_included_index: 1,
+        last_included_term: 0,
+        offset: 0,
+        data: leader.logger.snapshot.clone().unwrap().data,
+        done: true,
+    };
+    follower.apply_install_snapshot(&snapshot, &follower_cache).await.unwrap();
+
+    // THEN - a retry of the write is still recognised on the follower
+    assert!(follower.client_sessions.is_processed(&Some(session_req)));
+    assert_eq!(follower.client_sessions, leader.client_sessions.to_string().parse().unwrap());
+}
+
+#[tokio::test]
+async fn test_client_session_stats_count_suppressed_retries_and_expired_sessions() {
+    // GIVEN
+    let mut leader = Helper::cluster_actor(ReplicationRole::Leader).await;
+    let session_req = SessionRequest::new(1, Uuid::now_v7());
+    leader.client_sessions.set_response(Some(session_req.clone()));
+
+    // WHEN - the client retries a write that was already applied
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let write = WriteRequest::Set { key: "foo".into(), value: "bar".into(), expires_at: None };
+    leader.leader_req_consensus(ConsensusRequest::new(write, tx, Some(session_req))).await;
+
+    // THEN
+    assert!(matches!(rx.await.unwrap(), ConsensusClientResponse::AlreadyProcessed { .. }));
+    assert_eq!(leader.logger.last_log_index, 0);
+    let stats = leader.client_session_stats();
+    assert_eq!((stats.sessions, stats.duplicates_suppressed, stats.expired), (1, 1, 0));
+
+    // WHEN - the window is shortened below the idle time of the session
+    leader.set_client_session_ttl(1);
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    leader.expire_client_sessions();
+
+    // THEN
+    let stats = leader.client_session_stats();
+    assert_eq!((stats.sessions, stats.ttl, stats.expired), (0, 1, 1));
+}
@@ -0,0 +1,125 @@
+use crate::types::Callback;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tracing::error;
+
+// * Membership changes come in bursts, e.g. while a cluster forms, so writes are coalesced over this window
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Writes the topology file on a blocking thread, away from the cluster actor. A write is replaced by
+/// any that follows it within [`DEBOUNCE`], and lands as a whole through write-then-rename.
+#[derive(Debug, Clone)]
+pub(crate) struct TopologyWriter(UnboundedSender<TopologyWrite>);
+
+#[derive(Debug)]
+struct TopologyWrite {
+    contents: Vec<u8>,
+    // * Set by flushes, which skip the debounce and wait for the file to be on disk
+    done: Option<Callback<anyhow::Result<()>>>,
+}
+
+impl TopologyWriter {
+    pub(crate) fn spawn(path: impl Into<PathBuf>) -> Self {
+        let (tx, rx) = unbounded_channel();
+        tokio::spawn(Self::run(path.into(), rx));
+        Self(tx)
+    }
+
+    pub(crate) fn save(&self, contents: Vec<u8>) {
+        let _ = self.0.send(TopologyWrite { contents, done: None });
+    }
+
+    pub(crate) async fn flush(&self, contents: Vec<u8>) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.0
+            .send(TopologyWrite { contents, done: Some(tx.into()) })
+            .map_err(|_| anyhow::anyhow!("topology writer is gone"))?;
+        rx.await?
+    }
+
+    async fn run(path: PathBuf, mut rx: UnboundedReceiver<TopologyWrite>) {
+        while let Some(write) = rx.recv().await {
+            let mut contents = write.contents;
+            let mut waiters: Vec<_> = write.done.into_iter().collect();
+
+            if waiters.is_empty() {
+                let debounce = tokio::time::sleep(DEBOUNCE);
+                tokio::pin!(debounce);
+                loop {
+                    tokio::select! {
+                        _ = &mut debounce => break,
+                        next = rx.recv() => {
+                            let Some(next) = next else { break };
+                            contents = next.contents;
+                            if let Some(done) = next.done {
+                                waiters.push(done);
+                                break;
+                            }
+                        },
+                    }
+                }
+            }
+
+            let target = path.clone();
+            let result = tokio::task::spawn_blocking(move || write_atomically(&target, &contents))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            if let Err(err) = &result {
+                error!("failed to write topology to {}: {err}", path.display());
+            }
+            for done in waiters {
+                let _ =
+                    done.send(result.as_ref().map(|_| ()).map_err(|err| anyhow::anyhow!("{err}")));
+            }
+        }
+    }
+}
+
+// * Write-then-rename so that a crash never leaves a torn file behind
+fn write_atomically(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_save_keeps_the_last_write_of_a_burst() {
+        // GIVEN
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("duva.tp");
+        let writer = TopologyWriter::spawn(&path);
+
+        // WHEN
+        writer.save(b"first".to_vec());
+        writer.save(b"second".to_vec());
+
+        // THEN - nothing lands before the burst settles, then only the last write does
+        assert!(!path.exists());
+        tokio::time::sleep(DEBOUNCE * 4).await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_right_away_over_pending_saves() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("duva.tp");
+        let writer = TopologyWriter::spawn(&path);
+
+        writer.save(b"pending".to_vec());
+        writer.flush(b"flushed".to_vec()).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"flushed");
+    }
+}
@@ -1,14 +1,30 @@
 use crate::ReplicationState;
-use crate::domains::cluster_actors::hash_ring::{BatchId, MigrationBatch};
-use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
-use crate::domains::cluster_actors::topology::Topology;
+use crate::domains::cluster_actors::actor::client_sessions::ClientSessionStats;
+use crate::domains::cluster_actors::actor::heartbeat_scheduler::RaftTimings;
+use crate::domains::cluster_actors::consensus::applied::Applying;
+use crate::domains::cluster_actors::consensus::pending_writes::PendingWriteStats;
+use crate::domains::cluster_actors::consensus::report::ConsensusReport;
+use crate::domains::cluster_actors::forwarding::{ForwardId, ForwardedOp, ForwardedReply};
+use crate::domains::cluster_actors::hash_ring::{BatchId, KeySelector, MigrationBatch};
+use crate::domains::cluster_actors::history::TopologyEvent;
+use crate::domains::cluster_actors::replication::{
+    ReplicationId, ReplicationLinks, ReplicationRole,
+};
+use crate::domains::cluster_actors::topology::{RoutingTable, Shard, Topology};
+use crate::domains::cluster_actors::transactions::TxnId;
 use crate::domains::operation_logs::WriteRequest;
-use crate::domains::peers::command::PeerCommand;
+use crate::domains::operation_logs::interfaces::{FsyncPolicy, WalVerification};
+use crate::domains::peers::command::{ForwardResponse, PeerCommand};
 use crate::domains::peers::peer::{Peer, PeerState};
+use crate::domains::peers::reconnect::LinkState;
+use crate::domains::saves::snapshot::Metadata;
+use crate::domains::telemetry::TraceContext;
 use crate::prelude::PeerIdentifier;
 use crate::types::{Callback, ConnectionStream};
+use bytes::Bytes;
 
 use std::str::FromStr;
+use tokio::time::Instant;
 
 use uuid::Uuid;
 
@@ -26,9 +42,24 @@ pub enum SchedulerMessage {
     SendAppendEntriesRPC,
     StartLeaderElection,
     RebalanceRequest { request_to: PeerIdentifier, lazy_option: LazyOption },
-    ScheduleMigrationBatch(MigrationBatch, Callback<anyhow::Result<()>>),
+    ScheduleMigrationBatch(MigrationBatch, Callback<anyhow::Result<usize>>),
     TryUnblockWriteReqs,
-    SendBatchAck { batch_id: BatchId, to: PeerIdentifier },
+    ExpireMigrationBatch(BatchId),
+    AbandonMigrationBatch(BatchId),
+    SendBatchAck { batch_id: BatchId, to: PeerIdentifier, success: bool },
+    CompactLogs,
+    SyncLogs,
+    ReconnectPeers,
+    CommitGroup,
+    AbortLeadershipTransfer(Uuid),
+    Depart(Callback<anyhow::Result<()>>),
+    ExpireReplicaWaits,
+    ExpirePendingRequests,
+    ExpireConsensusRequests,
+    ExpireClientSessions,
+    ExpireForward(ForwardId),
+    ResolveTransaction(TxnId),
+    SendForwardResponse { to: PeerIdentifier, response: ForwardResponse },
 }
 impl From<SchedulerMessage> for ClusterCommand {
     fn from(msg: SchedulerMessage) -> Self {
@@ -52,17 +83,53 @@ impl From<ConnectionMessage> for ClusterCommand {
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ClientMessage {
-    GetPeers(Callback<Vec<PeerIdentifier>>),
+    PeerSuspicion(Callback<Vec<(PeerIdentifier, f64)>>),
     ReplicationInfo(Callback<ReplicationState>),
-    ForgetPeer(PeerIdentifier, Callback<Option<()>>),
+    ReplicationLinks(Callback<ReplicationLinks>),
+    SnapshotMetadata(Callback<Metadata>),
+    ForgetPeer(PeerIdentifier, bool, Callback<Option<()>>),
+    BannedPeers(Callback<Vec<(PeerIdentifier, Option<u64>)>>),
+    ClearBans(Option<PeerIdentifier>, Callback<usize>),
     ReplicaOf(PeerIdentifier, Callback<anyhow::Result<()>>),
     LeaderReqConsensus(ConsensusRequest),
-    ClusterNodes(Callback<Vec<PeerState>>),
+    ClusterNodes(Callback<Vec<(PeerState, LinkState)>>),
+    ClusterShards(Callback<Vec<Shard>>),
     GetRole(Callback<ReplicationRole>),
     SubscribeToTopologyChange(Callback<tokio::sync::broadcast::Receiver<Topology>>),
+    SubscribeToShutdown(Callback<tokio::sync::watch::Receiver<bool>>),
+    SubscribeToRouting(Callback<tokio::sync::watch::Receiver<RoutingTable>>),
     ClusterMeet(PeerIdentifier, LazyOption, Callback<anyhow::Result<()>>),
     GetTopology(Callback<Topology>),
     ClusterReshard(Callback<Result<(), anyhow::Error>>),
+    ClusterReshardStatus(Callback<String>),
+    ClusterConsensus(Callback<ConsensusReport>),
+    ClusterHistory(Option<usize>, Callback<Vec<TopologyEvent>>),
+    ClusterMigrate(KeySelector, ReplicationId, Callback<anyhow::Result<()>>),
+    ClusterFailover(Option<PeerIdentifier>, Callback<anyhow::Result<()>>),
+    ClusterLeave(Callback<anyhow::Result<()>>),
+    PendingWriteStats(Callback<PendingWriteStats>),
+    ClientSessionStats(Callback<ClientSessionStats>),
+    SetClientSessionTtl(u64, Callback<()>),
+    GetFsyncPolicy(Callback<FsyncPolicy>),
+    SetFsyncPolicy(FsyncPolicy, Callback<anyhow::Result<()>>),
+    GetRaftTimings(Callback<RaftTimings>),
+    SetRaftTiming(String, u64, Callback<anyhow::Result<()>>),
+    VerifyWal(Callback<WalVerification>),
+    // * Syncs the WAL and writes the topology file out, ahead of a shutdown
+    Flush(Callback<anyhow::Result<()>>),
+    ReadIndex(Callback<anyhow::Result<u64>>),
+    CheckReplicaStaleness(u64, Callback<anyhow::Result<()>>),
+    Wait {
+        index: u64,
+        numreplicas: usize,
+        timeout: u64,
+        callback: Callback<anyhow::Result<usize>>,
+    },
+    ForwardToShard {
+        to: ReplicationId,
+        op: ForwardedOp,
+        callback: Callback<anyhow::Result<ForwardedReply>>,
+    },
 }
 
 impl From<ClientMessage> for ClusterCommand {
@@ -76,6 +143,10 @@ pub(crate) struct ConsensusRequest {
     pub(crate) request: WriteRequest,
     pub(crate) callback: Callback<ConsensusClientResponse>,
     pub(crate) session_req: Option<SessionRequest>,
+    // * Set once the write is held back, so that it is answered even if the leader stays blocked
+    pub(crate) deadline: Option<Instant>,
+    // * Span the write was proposed from, which the entries replicating it are traced under
+    pub(crate) trace: Option<TraceContext>,
 }
 impl ConsensusRequest {
     pub(crate) fn new(
@@ -83,14 +154,21 @@ impl ConsensusRequest {
         callback: impl Into<Callback<ConsensusClientResponse>>,
         session_req: Option<SessionRequest>,
     ) -> Self {
-        Self { request, callback: callback.into(), session_req }
+        Self {
+            request,
+            callback: callback.into(),
+            session_req,
+            deadline: None,
+            trace: TraceContext::current(),
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum ConsensusClientResponse {
-    AlreadyProcessed { key: Vec<String>, index: u64 },
-    LogIndex(u64),
+    AlreadyProcessed { key: Vec<Bytes>, index: u64 },
+    // * Committed at the index, and applied once the guard is dropped
+    LogIndex(u64, Applying),
     Err(String),
 }
 
@@ -0,0 +1,83 @@
+use crate::domains::{operation_logs::WriteOperation, query_io::SERDE_CONFIG};
+use bincode::{
+    Encode,
+    enc::{EncoderImpl, write::SizeWriter},
+};
+
+/// Caps how much of the log goes into a single AppendEntries message, so that a replica far behind
+/// is caught up over several messages instead of one that overflows the buffers on either side.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AppendEntriesBudget {
+    pub(crate) max_entries: usize,
+    pub(crate) max_bytes: usize,
+}
+
+impl Default for AppendEntriesBudget {
+    fn default() -> Self {
+        Self { max_entries: 512, max_bytes: 1024 * 1024 }
+    }
+}
+
+impl AppendEntriesBudget {
+    pub(crate) fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self { max_entries, max_bytes }
+    }
+
+    /// Longest prefix of `entries` that fits the budget. The first entry is always taken so that
+    /// an entry larger than the byte budget still makes progress.
+    pub(crate) fn take(&self, mut entries: Vec<WriteOperation>) -> Vec<WriteOperation> {
+        let mut bytes = 0;
+        let mut count = 0;
+        for entry in entries.iter() {
+            bytes += encoded_size(entry);
+            if count > 0 && (count >= self.max_entries || bytes > self.max_bytes) {
+                break;
+            }
+            count += 1;
+        }
+        entries.truncate(count);
+        entries
+    }
+}
+
+fn encoded_size(entry: &WriteOperation) -> usize {
+    let mut encoder = EncoderImpl::new(SizeWriter::default(), SERDE_CONFIG);
+    let _ = entry.encode(&mut encoder);
+    encoder.into_writer().bytes_written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::operation_logs::WriteRequest;
+    use bytes::Bytes;
+
+    fn entry(log_index: u64, value: &str) -> WriteOperation {
+        WriteOperation {
+            request: WriteRequest::Set {
+                key: "key".into(),
+                value: Bytes::copy_from_slice(value.as_bytes()),
+                expires_at: None,
+            },
+            log_index,
+            term: 0,
+            session_req: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_take_caps_entries_and_bytes() {
+        let entries = (1..=10).map(|idx| entry(idx, "v")).collect::<Vec<_>>();
+        assert_eq!(AppendEntriesBudget::new(3, usize::MAX).take(entries.clone()).len(), 3);
+
+        let one_entry = encoded_size(&entries[0]);
+        assert_eq!(AppendEntriesBudget::new(100, one_entry * 4).take(entries).len(), 4);
+    }
+
+    #[test]
+    fn test_take_always_sends_the_first_entry() {
+        let entries = vec![entry(1, &"v".repeat(100)), entry(2, "v")];
+        assert_eq!(AppendEntriesBudget::new(100, 10).take(entries).len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Committed entries the leader handed out to be applied outside of the cluster actor, so that a
+/// snapshot only covers entries the state machine already reflects.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AppliedIndex {
+    // * Number of guards held per log index
+    in_flight: Arc<Mutex<BTreeMap<u64, usize>>>,
+    drained: Arc<Notify>,
+}
+
+impl AppliedIndex {
+    /// Marks entries from `index` on as being applied until the returned guard is dropped.
+    pub(crate) fn track(&self, index: u64) -> Applying {
+        *self.in_flight.lock().unwrap().entry(index).or_default() += 1;
+        Applying(Some((index, self.clone())))
+    }
+
+    /// Index up to which every committed entry is applied, out of the `hwm` committed ones.
+    pub(crate) fn get(&self, hwm: u64) -> u64 {
+        match self.in_flight.lock().unwrap().first_key_value() {
+            | Some((index, _)) => hwm.min(index - 1),
+            | None => hwm,
+        }
+    }
+
+    /// Waits up to `timeout` for the entries handed out to be applied, then returns the applied index.
+    pub(crate) async fn settle(&self, hwm: u64, timeout: Duration) -> u64 {
+        let _ = tokio::time::timeout(timeout, async {
+            loop {
+                // * Created before the check, so that a release in between is not missed
+                let drained = self.drained.notified();
+                if self.in_flight.lock().unwrap().is_empty() {
+                    return;
+                }
+                drained.await;
+            }
+        })
+        .await;
+        self.get(hwm)
+    }
+
+    fn release(&self, index: u64) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Entry::Occupied(mut guards) = in_flight.entry(index) {
+            *guards.get_mut() -= 1;
+            if *guards.get() == 0 {
+                guards.remove();
+            }
+        }
+        if in_flight.is_empty() {
+            self.drained.notify_waiters();
+        }
+    }
+}
+
+/// Held by whoever applies a committed entry. Dropping it marks the entry applied.
+#[derive(Debug, Default)]
+pub(crate) struct Applying(Option<(u64, AppliedIndex)>);
+
+impl Applying {
+    pub(crate) fn index(&self) -> Option<u64> {
+        self.0.as_ref().map(|(index, _)| *index)
+    }
+}
+
+impl PartialEq for Applying {
+    fn eq(&self, other: &Self) -> bool {
+        self.index() == other.index()
+    }
+}
+
+impl Drop for Applying {
+    fn drop(&mut self) {
+        if let Some((index, applied)) = self.0.take() {
+            applied.release(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_applied_index_stops_before_entries_being_applied() {
+        // GIVEN
+        let applied = AppliedIndex::default();
+        let third = applied.track(3);
+        let fifth = applied.track(5);
+
+        // WHEN & THEN
+        assert_eq!(applied.get(6), 2);
+        drop(third);
+        assert_eq!(applied.get(6), 4);
+        assert_eq!(applied.settle(6, Duration::from_millis(10)).await, 4);
+
+        let settled = tokio::spawn({
+            let applied = applied.clone();
+            async move { applied.settle(6, Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(fifth);
+        assert_eq!(settled.await.unwrap(), 6);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::domains::cluster_actors::{ClusterCommand, SchedulerMessage};
+use crate::domains::encryption::KeyRing;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::{sync::mpsc::Sender, time::interval};
+
+const LOG_COMPACTION_INTERVAL: u64 = 1000;
+
+/// `save <changes> <seconds>` policy: a snapshot is taken once `changes` entries were committed since
+/// the last one, or once `seconds` passed with at least one entry to fold in. 0 disables either trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SavePolicy {
+    pub(crate) changes: u64,
+    pub(crate) seconds: u64,
+}
+
+impl SavePolicy {
+    fn is_due(&self, changes: u64, elapsed: Duration) -> bool {
+        (self.changes > 0 && changes >= self.changes)
+            || (self.seconds > 0 && elapsed >= Duration::from_secs(self.seconds))
+    }
+}
+
+impl FromStr for SavePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.split_whitespace().map(str::parse::<u64>);
+        match (parts.next(), parts.next(), parts.next()) {
+            | (Some(Ok(changes)), Some(Ok(seconds)), None) => Ok(Self { changes, seconds }),
+            | _ => {
+                Err(anyhow::anyhow!("invalid save policy '{s}', expected '<changes> <seconds>'"))
+            },
+        }
+    }
+}
+
+/// Decides when the replicated log is folded into a state machine snapshot.
+#[derive(Debug, Default)]
+pub(crate) struct LogCompaction {
+    // * Number of committed entries kept in the log before a snapshot is taken. 0 disables compaction.
+    pub(crate) threshold: u64,
+    // * Sealed log segments covered by the snapshot that are kept anyway, for archival and recovery
+    pub(crate) retained_segments: usize,
+    // * Where snapshots are persisted so that a restarted node can recover the compacted state
+    pub(crate) filepath: Option<String>,
+    // * How many entries a replica may fall behind the log before it is caught up with a snapshot
+    // * rather than entry by entry. 0 only sends snapshots to replicas behind the compacted log.
+    pub(crate) catchup_lag: u64,
+    // * Snapshots taken on log growth or elapsed time, on top of the threshold
+    save: Option<SavePolicy>,
+    // * Keys persisted snapshots are sealed with
+    encryption: KeyRing,
+    // * Set when a replica fell too far behind, so that a snapshot is taken on the next tick
+    catchup_requested: bool,
+    // * Snapshot index observed on the previous tick and when it last changed
+    last_snapshot: Option<(u64, Instant)>,
+}
+
+impl LogCompaction {
+    pub(crate) fn new(threshold: u64, retained_segments: usize, filepath: Option<String>) -> Self {
+        Self {
+            threshold,
+            retained_segments,
+            filepath,
+            catchup_lag: 0,
+            save: None,
+            encryption: KeyRing::default(),
+            catchup_requested: false,
+            last_snapshot: None,
+        }
+    }
+
+    pub(crate) fn with_catchup_lag(mut self, catchup_lag: u64) -> Self {
+        self.catchup_lag = catchup_lag;
+        self
+    }
+
+    pub(crate) fn with_save_policy(mut self, save: Option<SavePolicy>) -> Self {
+        self.save = save;
+        self
+    }
+
+    pub(crate) fn with_encryption(mut self, encryption: KeyRing) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Whether a replica that has `match_index` out of `last_log_index` entries is better caught up
+    /// with a snapshot than entry by entry.
+    pub(crate) fn is_far_behind(&self, match_index: u64, last_log_index: u64) -> bool {
+        self.catchup_lag > 0 && last_log_index.saturating_sub(match_index) >= self.catchup_lag
+    }
+
+    pub(crate) fn request_catchup(&mut self) {
+        self.catchup_requested = true;
+    }
+
+    pub(crate) fn schedule(&self, cluster_handler: Sender<ClusterCommand>) {
+        if self.threshold == 0 && self.catchup_lag == 0 && self.save.is_none() {
+            return;
+        }
+        let mut itv = interval(Duration::from_millis(LOG_COMPACTION_INTERVAL));
+        tokio::spawn(async move {
+            loop {
+                itv.tick().await;
+                if cluster_handler.send(SchedulerMessage::CompactLogs.into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Whether a snapshot is due at the `applied` index. Writes may keep coming in meanwhile: the
+    /// triggers count entries since the last snapshot, so a busy node is not snapshotted on every tick.
+    pub(crate) fn should_compact(&mut self, applied: u64, snapshot_index: u64) -> bool {
+        let snapshot_taken_at = match self.last_snapshot {
+            | Some((index, at)) if index == snapshot_index => at,
+            | _ => self.last_snapshot.insert((snapshot_index, Instant::now())).1,
+        };
+        if applied <= snapshot_index {
+            return false;
+        }
+        if self.catchup_requested {
+            self.catchup_requested = false;
+            return true;
+        }
+        let changes = applied - snapshot_index;
+        (self.threshold > 0 && changes >= self.threshold)
+            || self.save.is_some_and(|save| save.is_due(changes, snapshot_taken_at.elapsed()))
+    }
+
+    pub(crate) async fn persist(&self, data: &[u8]) -> anyhow::Result<()> {
+        if let Some(filepath) = self.filepath.as_ref() {
+            tokio::fs::write(filepath, self.encryption.seal_file(data.to_vec())).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_compact_while_writes_keep_coming() {
+        let mut compaction = LogCompaction::new(10, 0, None);
+
+        assert!(!compaction.should_compact(9, 0));
+        // * The applied index moves on every tick, the threshold alone decides
+        assert!(compaction.should_compact(10, 0));
+        assert!(compaction.should_compact(12, 0));
+        // not enough entries since the last snapshot
+        assert!(!compaction.should_compact(15, 10));
+        assert!(compaction.should_compact(20, 10));
+    }
+
+    #[test]
+    fn test_catchup_request_compacts_below_threshold() {
+        let mut compaction = LogCompaction::new(0, 0, None).with_catchup_lag(100);
+        assert!(compaction.is_far_behind(10, 110));
+        assert!(!compaction.is_far_behind(11, 110));
+
+        assert!(!compaction.should_compact(20, 0));
+
+        compaction.request_catchup();
+        assert!(compaction.should_compact(20, 0));
+        // * The request is served by a single snapshot
+        assert!(!compaction.should_compact(20, 0));
+    }
+
+    #[test]
+    fn test_save_policy_compacts_on_log_growth() {
+        let mut compaction = LogCompaction::new(0, 0, None)
+            .with_save_policy(Some(SavePolicy { changes: 5, seconds: 0 }));
+
+        assert!(!compaction.should_compact(4, 0));
+        assert!(compaction.should_compact(5, 0));
+        // * Counted from the new snapshot
+        assert!(!compaction.should_compact(9, 5));
+    }
+
+    #[test]
+    fn test_save_policy_compacts_after_elapsed_time() {
+        let mut compaction = LogCompaction::new(0, 0, None)
+            .with_save_policy(Some(SavePolicy { changes: 0, seconds: 60 }));
+
+        assert!(!compaction.should_compact(1, 0));
+        assert!(!compaction.should_compact(1, 0));
+
+        let (index, _) = compaction.last_snapshot.unwrap();
+        compaction.last_snapshot =
+            Some((index, Instant::now().checked_sub(Duration::from_secs(60)).unwrap()));
+        assert!(compaction.should_compact(1, 0));
+
+        // * Nothing to fold in since the last snapshot
+        compaction.last_snapshot =
+            Some((1, Instant::now().checked_sub(Duration::from_secs(60)).unwrap()));
+        assert!(!compaction.should_compact(1, 1));
+    }
+
+    #[test]
+    fn test_save_policy_window_expires_while_writes_keep_coming() {
+        let mut compaction = LogCompaction::new(0, 0, None)
+            .with_save_policy(Some(SavePolicy { changes: 1000, seconds: 60 }));
+
+        // * The applied index moves on every tick, well below the change count
+        assert!(!compaction.should_compact(1, 0));
+        assert!(!compaction.should_compact(2, 0));
+
+        let (index, _) = compaction.last_snapshot.unwrap();
+        compaction.last_snapshot =
+            Some((index, Instant::now().checked_sub(Duration::from_secs(60)).unwrap()));
+        assert!(compaction.should_compact(3, 0));
+
+        // * The window starts over from the new snapshot
+        assert!(!compaction.should_compact(4, 3));
+        assert!(!compaction.should_compact(5, 3));
+    }
+
+    #[test]
+    fn test_parse_save_policy() {
+        assert_eq!(
+            "100 60".parse::<SavePolicy>().unwrap(),
+            SavePolicy { changes: 100, seconds: 60 }
+        );
+        assert!("100".parse::<SavePolicy>().is_err());
+        assert!("100 60 1".parse::<SavePolicy>().is_err());
+        assert!("a 60".parse::<SavePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_should_compact_disabled() {
+        let mut compaction = LogCompaction::new(0, 0, None);
+        assert!(!compaction.should_compact(100, 0));
+    }
+}
@@ -1,11 +1,20 @@
+use std::collections::VecDeque;
 use tracing::warn;
+use uuid::Uuid;
 
-use crate::domains::{
-    cluster_actors::replication::ReplicationRole, peers::identifier::PeerIdentifier,
+use crate::{
+    domains::{
+        cluster_actors::{ConsensusRequest, replication::ReplicationRole},
+        peers::identifier::PeerIdentifier,
+    },
+    types::Callback,
 };
 
 #[derive(Debug, Clone)]
 pub(crate) enum ElectionState {
+    // * Asks peers whether it could win before bumping its term, so a node that cannot reach
+    // * the majority never disrupts the cluster with an inflated term
+    PreCandidate { voting: Option<ElectionVoting>, voted_for: Option<PeerIdentifier> },
     Candidate { voting: Option<ElectionVoting> },
     Follower { voted_for: Option<PeerIdentifier> },
     Leader,
@@ -21,7 +30,8 @@ impl ElectionState {
 
     pub(crate) fn is_votable(&self, candidate_id: &PeerIdentifier) -> bool {
         match self {
-            | ElectionState::Follower { voted_for } => match voted_for {
+            | ElectionState::Follower { voted_for }
+            | ElectionState::PreCandidate { voted_for, .. } => match voted_for {
                 | None => true,
                 | Some(id) => id == candidate_id,
             },
@@ -29,8 +39,49 @@ impl ElectionState {
         }
     }
 
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            | ElectionState::PreCandidate { .. } => "pre_candidate",
+            | ElectionState::Candidate { .. } => "candidate",
+            | ElectionState::Follower { .. } => "follower",
+            | ElectionState::Leader => "leader",
+        }
+    }
+
+    /// Vote cast in the current term. Candidates and leaders have voted for themselves.
+    pub(crate) fn voted_for(&self, self_id: &PeerIdentifier) -> Option<PeerIdentifier> {
+        match self {
+            | ElectionState::Follower { voted_for }
+            | ElectionState::PreCandidate { voted_for, .. } => voted_for.clone(),
+            | ElectionState::Candidate { .. } | ElectionState::Leader => Some(self_id.clone()),
+        }
+    }
+
+    /// Enters the pre-vote round. The vote cast in the current term is kept, as the term does not change.
+    pub(crate) fn become_pre_candidate(&mut self, self_id: &PeerIdentifier, replica_count: u8) {
+        let voted_for = match self {
+            | ElectionState::Follower { voted_for }
+            | ElectionState::PreCandidate { voted_for, .. } => voted_for.take(),
+            | ElectionState::Candidate { .. } => Some(self_id.clone()),
+            | ElectionState::Leader => return,
+        };
+        *self = ElectionState::PreCandidate {
+            voting: Some(ElectionVoting::new(replica_count)),
+            voted_for,
+        };
+    }
+
+    pub(crate) fn can_start_election(&mut self) -> bool {
+        let ElectionState::PreCandidate { voting, .. } = self else { return false };
+        Self::tally(voting)
+    }
+
     pub(crate) fn can_transition_to_leader(&mut self) -> bool {
         let ElectionState::Candidate { voting } = self else { return false };
+        Self::tally(voting)
+    }
+
+    fn tally(voting: &mut Option<ElectionVoting>) -> bool {
         // Try to take ownership of the current voting state
         let Some(current_voting) = voting.take() else {
             return false;
@@ -41,6 +92,23 @@ impl ElectionState {
     }
 }
 
+/// Leadership hand-off requested through `CLUSTER FAILOVER`, in flight until `target` has caught up.
+#[derive(Debug)]
+pub(crate) struct LeadershipTransfer {
+    // * Lets the abort timer of an earlier transfer tell it is not meant for this one
+    pub(crate) id: Uuid,
+    pub(crate) target: PeerIdentifier,
+    pub(crate) callback: Callback<anyhow::Result<()>>,
+}
+
+/// Writes that arrived during a leadership hand-off, held until the node they belong to is known:
+/// the new leader once the hand-off is done, or this node again when it was aborted.
+#[derive(Debug)]
+pub(crate) struct HandOffHold {
+    pub(crate) transfer_id: Uuid,
+    pub(crate) reqs: VecDeque<ConsensusRequest>,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct ElectionVoting {
     pub(crate) cnt: u8,
@@ -68,6 +136,39 @@ impl ElectionVoting {
     }
 }
 
+#[test]
+fn test_become_pre_candidate_keeps_vote() {
+    let self_id = PeerIdentifier("127.0.0.1:6379".into());
+    let other = PeerIdentifier("127.0.0.1:6380".into());
+
+    let mut state = ElectionState::Follower { voted_for: Some(other.clone()) };
+    state.become_pre_candidate(&self_id, 2);
+    assert!(state.is_votable(&other));
+    assert!(!state.is_votable(&self_id));
+
+    let mut state = ElectionState::Candidate { voting: None };
+    state.become_pre_candidate(&self_id, 2);
+    assert!(!state.is_votable(&other));
+
+    let mut state = ElectionState::Leader;
+    state.become_pre_candidate(&self_id, 2);
+    assert!(matches!(state, ElectionState::Leader));
+}
+
+#[test]
+fn test_can_start_election_once_majority_granted() {
+    let self_id = PeerIdentifier("127.0.0.1:6379".into());
+    let mut state = ElectionState::Follower { voted_for: None };
+    state.become_pre_candidate(&self_id, 4);
+
+    // self + 1 grant is not a majority of 5
+    assert!(!state.can_start_election());
+    assert!(state.can_start_election());
+    // further grants do not trigger another election
+    assert!(!state.can_start_election());
+    assert!(!state.can_transition_to_leader());
+}
+
 #[test]
 fn test_get_required_votes() {
     let ev = ElectionVoting { cnt: 0, replica_count: 0 };
@@ -0,0 +1,80 @@
+use crate::domains::cluster_actors::ConsensusRequest;
+use crate::domains::operation_logs::WriteRequest;
+use std::time::Duration;
+
+/// Gathers the writes a leader receives within a short window, so that they are appended to the
+/// log with a single write and fsync and replicated in a single round.
+#[derive(Debug, Default)]
+pub(crate) struct GroupCommit {
+    // * How long, in microseconds, the first write of a group waits for others. 0 commits every write on its own.
+    pub(crate) window: u64,
+    // * Writes in a group at most; reaching it commits the group right away
+    pub(crate) max_entries: usize,
+    queued: Vec<ConsensusRequest>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum GroupCommitAction {
+    // * The group is ready to be committed
+    Commit,
+    // * The write opened a new group, which is committed once the window closes
+    ScheduleCommit,
+    // * The write joined a group that is already waiting
+    Wait,
+}
+
+impl GroupCommit {
+    pub(crate) fn new(window: u64, max_entries: usize) -> Self {
+        Self { window, max_entries: max_entries.max(1), queued: Vec::new() }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        Duration::from_micros(self.window)
+    }
+
+    pub(crate) fn push(&mut self, req: ConsensusRequest) -> GroupCommitAction {
+        // * Keys are locked once a prepare is logged, so writes arriving after it must not join its group
+        let locks_keys = matches!(req.request, WriteRequest::TxnPrepare { .. });
+        self.queued.push(req);
+        if self.window == 0 || locks_keys || self.queued.len() >= self.max_entries {
+            GroupCommitAction::Commit
+        } else if self.queued.len() == 1 {
+            GroupCommitAction::ScheduleCommit
+        } else {
+            GroupCommitAction::Wait
+        }
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<ConsensusRequest> {
+        std::mem::take(&mut self.queued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req() -> ConsensusRequest {
+        let (tx, _rx) = tokio::sync::oneshot::channel();
+        ConsensusRequest::new(WriteRequest::NoOp, tx, None)
+    }
+
+    #[test]
+    fn test_push_without_window_commits_right_away() {
+        let mut group = GroupCommit::new(0, 10);
+        assert_eq!(group.push(req()), GroupCommitAction::Commit);
+        assert_eq!(group.take().len(), 1);
+    }
+
+    #[test]
+    fn test_push_groups_writes_until_max_entries() {
+        let mut group = GroupCommit::new(500, 3);
+        assert_eq!(group.push(req()), GroupCommitAction::ScheduleCommit);
+        assert_eq!(group.push(req()), GroupCommitAction::Wait);
+        assert_eq!(group.push(req()), GroupCommitAction::Commit);
+        assert_eq!(group.take().len(), 3);
+
+        // * The next write opens a new group
+        assert_eq!(group.push(req()), GroupCommitAction::ScheduleCommit);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::domains::{peers::identifier::PeerIdentifier, query_io::SERDE_CONFIG};
+use std::{io::Write, path::PathBuf};
+
+/// Raft state that must survive a restart: a node that forgets its vote could vote twice in the same term.
+#[derive(Debug, Clone, Default, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) struct HardState {
+    pub(crate) term: u64,
+    pub(crate) voted_for: Option<PeerIdentifier>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct HardStateStore {
+    // * None keeps the state in memory only
+    path: Option<PathBuf>,
+    persisted: HardState,
+}
+
+impl HardStateStore {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        Self { path, persisted: HardState::default() }
+    }
+
+    pub(crate) fn load(&mut self) -> HardState {
+        let Some(path) = self.path.as_ref() else {
+            return HardState::default();
+        };
+        if let Ok(bytes) = std::fs::read(path)
+            && let Ok((state, _)) = bincode::decode_from_slice::<HardState, _>(&bytes, SERDE_CONFIG)
+        {
+            self.persisted = state;
+        }
+        self.persisted.clone()
+    }
+
+    /// Writes the state to disk if it changed. Must be called before the new term or vote is made visible to peers.
+    pub(crate) fn save(&mut self, state: HardState) -> anyhow::Result<()> {
+        if state == self.persisted {
+            return Ok(());
+        }
+        if let Some(path) = self.path.as_ref() {
+            // * Write-then-rename so that a crash never leaves a torn file behind
+            let tmp_path = path.with_extension("tmp");
+            let mut file = std::fs::File::create(&tmp_path)?;
+            file.write_all(&bincode::encode_to_vec(&state, SERDE_CONFIG)?)?;
+            file.sync_all()?;
+            std::fs::rename(&tmp_path, path)?;
+        }
+        self.persisted = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_hard_state() {
+        // GIVEN
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("raft.state");
+        let state = HardState { term: 3, voted_for: Some(PeerIdentifier::new("127.0.0.1", 6379)) };
+
+        // WHEN
+        HardStateStore::new(Some(path.clone())).save(state.clone()).unwrap();
+
+        // THEN
+        assert_eq!(HardStateStore::new(Some(path)).load(), state);
+    }
+
+    #[test]
+    fn test_load_without_file_defaults() {
+        let dir = TempDir::new().unwrap();
+        let mut store = HardStateStore::new(Some(dir.path().join("raft.state")));
+        assert_eq!(store.load(), HardState::default());
+        assert_eq!(HardStateStore::default().load(), HardState::default());
+    }
+}
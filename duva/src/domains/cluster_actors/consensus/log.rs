@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use tokio::time::Instant;
 
 use crate::{
     domains::{
@@ -13,8 +14,50 @@ pub(crate) type ReplicationVote = Callback<ConsensusClientResponse>;
 #[derive(Default, Debug)]
 pub struct LogConsensusTracker(pub(crate) HashMap<u64, LogConsensusVoting>);
 impl LogConsensusTracker {
-    pub(crate) fn add(&mut self, key: u64, req: ConsensusRequest, replica_count: usize) {
-        self.insert(key, LogConsensusVoting::new(req.callback, replica_count, req.session_req));
+    pub(crate) fn add(
+        &mut self,
+        key: u64,
+        req: ConsensusRequest,
+        replica_count: usize,
+        deadline: Option<Instant>,
+    ) {
+        let mut voting = LogConsensusVoting::new(req.callback, replica_count, req.session_req);
+        voting.deadline = deadline;
+        self.insert(key, voting);
+    }
+
+    /// Takes the entries up to `index`, which are committed along with it.
+    pub(crate) fn take_up_to(&mut self, index: u64) -> Vec<(u64, LogConsensusVoting)> {
+        self.take_where(|key, _| *key <= index)
+    }
+
+    /// The entries whose deadline passed as of `now`. They stay tracked, as they may still be
+    /// committed, and each is reported only once.
+    pub(crate) fn expired(
+        &mut self,
+        now: Instant,
+    ) -> impl Iterator<Item = (u64, &mut LogConsensusVoting)> {
+        self.iter_mut().filter_map(move |(key, voting)| {
+            voting.deadline.take_if(|deadline| *deadline <= now)?;
+            Some((*key, voting))
+        })
+    }
+
+    /// Whether a write of the session is still waiting for a majority.
+    pub(crate) fn is_pending(&self, session_req: &Option<SessionRequest>) -> bool {
+        session_req.is_some() && self.values().any(|voting| voting.session_req == *session_req)
+    }
+
+    fn take_where(
+        &mut self,
+        pred: impl Fn(&u64, &LogConsensusVoting) -> bool,
+    ) -> Vec<(u64, LogConsensusVoting)> {
+        let keys: Vec<u64> =
+            self.iter().filter(|(key, voting)| pred(key, voting)).map(|(key, _)| *key).collect();
+        let mut taken: Vec<_> =
+            keys.into_iter().filter_map(|key| self.remove(&key).map(|v| (key, v))).collect();
+        taken.sort_by_key(|(key, _)| *key);
+        taken
     }
 }
 make_smart_pointer!(LogConsensusTracker, HashMap<u64, LogConsensusVoting>);
@@ -25,6 +68,9 @@ pub struct LogConsensusVoting {
     pub(crate) callback: ReplicationVote,
     pub(crate) cnt: u8,
     pub(crate) session_req: Option<SessionRequest>,
+    // * Past it, the client is told to retry; the entry may still be committed later, and is then
+    // * applied by the leader itself
+    pub(crate) deadline: Option<Instant>,
 }
 impl LogConsensusVoting {
     fn new(
@@ -32,7 +78,13 @@ impl LogConsensusVoting {
         replica_count: usize,
         session_req: Option<SessionRequest>,
     ) -> Self {
-        Self { callback, cnt: 1, voters: Vec::with_capacity(replica_count), session_req }
+        Self {
+            callback,
+            cnt: 1,
+            voters: Vec::with_capacity(replica_count),
+            session_req,
+            deadline: None,
+        }
     }
 
     pub(crate) fn increase_vote(&mut self, voter: PeerIdentifier) {
@@ -75,6 +127,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_take_up_to_and_expired() {
+        let now = Instant::now();
+        let mut tracker = LogConsensusTracker::default();
+        for (index, deadline) in [(1, None), (2, Some(now)), (3, Some(now)), (4, None)] {
+            let req = ConsensusRequest::new(
+                crate::domains::operation_logs::WriteRequest::NoOp,
+                tokio::sync::oneshot::channel().0,
+                None,
+            );
+            tracker.add(index, req, 1, deadline);
+        }
+
+        let mut expired: Vec<_> = tracker.expired(now).map(|(i, _)| i).collect();
+        expired.sort();
+        assert_eq!(expired, vec![2, 3]);
+        assert_eq!(tracker.expired(now).count(), 0);
+        let committed: Vec<_> = tracker.take_up_to(3).into_iter().map(|(i, _)| i).collect();
+        assert_eq!(committed, vec![1, 2, 3]);
+        assert_eq!(tracker.keys().copied().collect::<Vec<_>>(), vec![4]);
+    }
+
     #[test]
     fn test_get_required_votes_edge_cases() {
         // Test with a large but safe number of followers
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// min-replicas-to-write - the leader refuses writes unless enough replicas are keeping up with it.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct MinReplicas {
+    // * Replicas that must have answered recently for a write to be accepted. 0 disables the check.
+    pub(crate) count: usize,
+    // * How long ago, in milliseconds, a replica may have last answered and still count
+    pub(crate) max_lag: u64,
+}
+
+impl MinReplicas {
+    pub(crate) fn new(count: usize, max_lag: u64) -> Self {
+        Self { count, max_lag }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.count > 0
+    }
+
+    pub(crate) fn max_lag(&self) -> Duration {
+        Duration::from_millis(self.max_lag)
+    }
+
+    pub(crate) fn check(&self, good_replicas: usize) -> anyhow::Result<()> {
+        if good_replicas < self.count {
+            return Err(anyhow::anyhow!(
+                "NOREPLICAS Not enough good replicas to write ({good_replicas} of {} within {}ms)",
+                self.count,
+                self.max_lag
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,14 @@
 mod log;
 pub(crate) use log::LogConsensusTracker;
+pub(crate) mod append_budget;
+pub(crate) mod applied;
+pub(crate) mod compaction;
 pub(crate) mod election;
+pub(crate) mod group_commit;
+pub(crate) mod hard_state;
+pub(crate) mod min_replicas;
+pub(crate) mod pending_writes;
+pub(crate) mod read_index;
+pub(crate) mod replica_wait;
+pub(crate) mod report;
+pub(crate) mod snapshot_stream;
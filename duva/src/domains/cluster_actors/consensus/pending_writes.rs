@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+/// Bounds the writes a leader holds back while it is rebalancing or handing off leadership, and how
+/// long it waits on replicas to acknowledge a write.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PendingWriteLimit {
+    // * Writes held back at most; any more are turned away. 0 means unlimited.
+    pub(crate) max_writes: usize,
+    // * How long, in milliseconds, a write may be held back before its client is told to retry
+    pub(crate) timeout: u64,
+    // * How long, in milliseconds, an appended write may wait on a majority before its client is told
+    // * to retry. 0 means no deadline.
+    pub(crate) consensus_timeout: u64,
+}
+
+impl Default for PendingWriteLimit {
+    fn default() -> Self {
+        Self { max_writes: 10000, timeout: 5000, consensus_timeout: 5000 }
+    }
+}
+
+impl PendingWriteLimit {
+    pub(crate) fn new(max_writes: usize, timeout: u64) -> Self {
+        Self { max_writes, timeout: timeout.max(1), ..Default::default() }
+    }
+
+    pub(crate) fn with_consensus_timeout(self, consensus_timeout: u64) -> Self {
+        Self { consensus_timeout, ..self }
+    }
+
+    pub(crate) fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout)
+    }
+
+    pub(crate) fn consensus_timeout(&self) -> Option<Duration> {
+        (self.consensus_timeout > 0).then(|| Duration::from_millis(self.consensus_timeout))
+    }
+
+    pub(crate) fn is_full(&self, held: usize) -> bool {
+        self.max_writes > 0 && held >= self.max_writes
+    }
+}
+
+/// Writes held back by the leader, and those it gave up on, reported by `INFO`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PendingWriteStats {
+    pub(crate) held: usize,
+    pub(crate) rejected: u64,
+    pub(crate) timed_out: u64,
+    // * Appended writes that no majority acknowledged in time
+    pub(crate) consensus_timed_out: u64,
+}
+
+impl PendingWriteStats {
+    pub(crate) fn vectorize(self) -> Vec<String> {
+        vec![
+            format!("pending_writes:{}", self.held),
+            format!("pending_writes_rejected:{}", self.rejected),
+            format!("pending_writes_timed_out:{}", self.timed_out),
+            format!("consensus_timed_out:{}", self.consensus_timed_out),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_full_respects_unlimited() {
+        assert!(PendingWriteLimit::new(2, 100).is_full(2));
+        assert!(!PendingWriteLimit::new(2, 100).is_full(1));
+        assert!(!PendingWriteLimit::new(0, 100).is_full(usize::MAX));
+    }
+}
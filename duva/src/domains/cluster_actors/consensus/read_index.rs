@@ -0,0 +1,71 @@
+use crate::types::Callback;
+use std::collections::VecDeque;
+use tokio::time::Instant;
+
+/// Linearizable reads (ReadIndex) waiting for the leader to confirm that it was still the leader when they arrived.
+#[derive(Debug, Default)]
+pub(crate) struct ReadIndexQueue(VecDeque<PendingRead>);
+
+#[derive(Debug)]
+struct PendingRead {
+    requested_at: Instant,
+    callback: Callback<anyhow::Result<u64>>,
+}
+
+impl ReadIndexQueue {
+    pub(crate) fn push(&mut self, callback: Callback<anyhow::Result<u64>>) {
+        self.0.push_back(PendingRead { requested_at: Instant::now(), callback });
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Answers with `read_index` every read, oldest first, for which leadership was confirmed.
+    pub(crate) fn confirm(&mut self, read_index: u64, is_confirmed: impl Fn(Instant) -> bool) {
+        while let Some(read) = self.0.front()
+            && is_confirmed(read.requested_at)
+        {
+            let read = self.0.pop_front().unwrap();
+            let _ = read.callback.send(Ok(read_index));
+        }
+    }
+
+    pub(crate) fn fail_all(&mut self, reason: &str) {
+        for read in self.0.drain(..) {
+            let _ = read.callback.send(Err(anyhow::anyhow!(reason.to_string())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_confirm_answers_oldest_reads_first() {
+        // GIVEN
+        let mut queue = ReadIndexQueue::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        queue.push(tx1.into());
+        let confirmed_until = Instant::now();
+        queue.push(tx2.into());
+
+        // WHEN - only the first read arrived before leadership was confirmed
+        queue.confirm(7, |requested_at| requested_at <= confirmed_until);
+
+        // THEN
+        assert_eq!(rx1.await.unwrap().unwrap(), 7);
+        assert!(rx2.try_recv().is_err());
+        assert!(!queue.is_empty());
+
+        queue.fail_all("TRYAGAIN");
+        assert!(rx2.await.unwrap().is_err());
+        assert!(queue.is_empty());
+    }
+}
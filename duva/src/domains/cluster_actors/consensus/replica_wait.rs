@@ -0,0 +1,85 @@
+use crate::types::Callback;
+use tokio::time::Instant;
+
+/// WAIT requests blocked until enough replicas have acknowledged a log index.
+#[derive(Debug, Default)]
+pub(crate) struct ReplicaWaitQueue(Vec<PendingWait>);
+
+#[derive(Debug)]
+struct PendingWait {
+    index: u64,
+    numreplicas: usize,
+    // * None blocks until enough replicas acknowledge the index
+    deadline: Option<Instant>,
+    callback: Callback<anyhow::Result<usize>>,
+}
+
+impl ReplicaWaitQueue {
+    pub(crate) fn push(
+        &mut self,
+        index: u64,
+        numreplicas: usize,
+        deadline: Option<Instant>,
+        callback: Callback<anyhow::Result<usize>>,
+    ) {
+        self.0.push(PendingWait { index, numreplicas, deadline, callback });
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Answers every wait that has enough acknowledgements or has run out of time with the number
+    /// of replicas that acknowledged its index.
+    pub(crate) fn resolve(&mut self, acked: impl Fn(u64) -> usize) {
+        let now = Instant::now();
+        let (done, pending): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.0).into_iter().partition(|wait| {
+                acked(wait.index) >= wait.numreplicas
+                    || wait.deadline.is_some_and(|deadline| deadline <= now)
+            });
+        self.0 = pending;
+        for wait in done {
+            let _ = wait.callback.send(Ok(acked(wait.index)));
+        }
+    }
+
+    pub(crate) fn fail_all(&mut self, reason: &str) {
+        for wait in self.0.drain(..) {
+            let _ = wait.callback.send(Err(anyhow::anyhow!(reason.to_string())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_answers_waits_with_enough_acks_or_past_deadline() {
+        // GIVEN
+        let mut queue = ReplicaWaitQueue::default();
+        let (tx1, rx1) = tokio::sync::oneshot::channel();
+        let (tx2, mut rx2) = tokio::sync::oneshot::channel();
+        let (tx3, rx3) = tokio::sync::oneshot::channel();
+        queue.push(3, 1, None, tx1.into());
+        queue.push(5, 2, None, tx2.into());
+        queue.push(5, 2, Some(Instant::now()), tx3.into());
+
+        // WHEN - one replica acknowledged index 5
+        queue.resolve(|index| if index <= 5 { 1 } else { 0 });
+
+        // THEN
+        assert_eq!(rx1.await.unwrap().unwrap(), 1);
+        assert_eq!(rx3.await.unwrap().unwrap(), 1);
+        assert!(rx2.try_recv().is_err());
+
+        queue.fail_all("ERR");
+        assert!(rx2.await.unwrap().is_err());
+        assert!(queue.is_empty());
+    }
+}
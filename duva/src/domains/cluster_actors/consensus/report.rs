@@ -0,0 +1,140 @@
+use crate::domains::cluster_actors::replication::ReplicationRole;
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::fmt::Display;
+
+/// Snapshot of the consensus internals of a node, reported by `CLUSTER CONSENSUS` for debugging.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConsensusReport {
+    pub(crate) term: u64,
+    pub(crate) role: ReplicationRole,
+    pub(crate) election_state: &'static str,
+    pub(crate) voted_for: Option<PeerIdentifier>,
+    pub(crate) commit_index: u64,
+    pub(crate) last_log_index: u64,
+    pub(crate) last_log_term: u64,
+    pub(crate) peers: Vec<PeerProgress>,
+    pub(crate) in_flight: Vec<InFlightEntry>,
+    // * None unless writes are held back, e.g. during an election or a rebalance
+    pub(crate) pending_requests: Option<usize>,
+    pub(crate) pending_reads: usize,
+    pub(crate) replica_waits: usize,
+    pub(crate) pending_forwards: usize,
+    pub(crate) pending_migrations: usize,
+    pub(crate) migrating_keys: usize,
+}
+
+/// What this node knows of a peer's log.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PeerProgress {
+    pub(crate) id: PeerIdentifier,
+    pub(crate) role: ReplicationRole,
+    // * Whether the peer replicates the same partition as this node
+    pub(crate) same_shard: bool,
+    pub(crate) match_index: u64,
+    pub(crate) last_seen_ms: u128,
+}
+
+/// Log entry the leader is still collecting acknowledgements for.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct InFlightEntry {
+    pub(crate) index: u64,
+    pub(crate) votes: u8,
+    pub(crate) required_votes: u8,
+    pub(crate) voters: Vec<PeerIdentifier>,
+}
+
+impl Display for ConsensusReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![
+            format!("term:{}", self.term),
+            format!("role:{}", self.role),
+            format!("election_state:{}", self.election_state),
+            format!("voted_for:{}", self.voted_for.as_deref().map_or("-", |id| id.as_str())),
+            format!("commit_index:{}", self.commit_index),
+            format!("last_log_index:{}", self.last_log_index),
+            format!("last_log_term:{}", self.last_log_term),
+            format!("peers:{}", self.peers.len()),
+        ];
+        for peer in &self.peers {
+            lines.push(format!(
+                "peer:{} role={} shard={} match_index={} last_seen_ms={}",
+                peer.id,
+                peer.role,
+                if peer.same_shard { "same" } else { "other" },
+                peer.match_index,
+                peer.last_seen_ms
+            ));
+        }
+        lines.push(format!("in_flight_entries:{}", self.in_flight.len()));
+        for entry in &self.in_flight {
+            let voters = entry.voters.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(",");
+            lines.push(format!(
+                "in_flight:{} votes={}/{} voters={}",
+                entry.index,
+                entry.votes,
+                entry.required_votes,
+                if voters.is_empty() { "-" } else { &voters }
+            ));
+        }
+        lines.extend([
+            format!("writes_blocked:{}", self.pending_requests.is_some() as u8),
+            format!("pending_requests:{}", self.pending_requests.unwrap_or(0)),
+            format!("pending_reads:{}", self.pending_reads),
+            format!("replica_waits:{}", self.replica_waits),
+            format!("pending_forwards:{}", self.pending_forwards),
+            format!("pending_migrations:{}", self.pending_migrations),
+            format!("migrating_keys:{}", self.migrating_keys),
+        ]);
+        write!(f, "{}", lines.join("\r\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_peers_and_in_flight_entries() {
+        let follower = PeerIdentifier::new("127.0.0.1", 6380);
+        let report = ConsensusReport {
+            term: 3,
+            role: ReplicationRole::Leader,
+            election_state: "leader",
+            voted_for: None,
+            commit_index: 7,
+            last_log_index: 8,
+            last_log_term: 3,
+            peers: vec![PeerProgress {
+                id: follower.clone(),
+                role: ReplicationRole::Follower,
+                same_shard: true,
+                match_index: 7,
+                last_seen_ms: 40,
+            }],
+            in_flight: vec![InFlightEntry {
+                index: 8,
+                votes: 1,
+                required_votes: 2,
+                voters: vec![],
+            }],
+            pending_requests: Some(2),
+            pending_reads: 0,
+            replica_waits: 1,
+            pending_forwards: 0,
+            pending_migrations: 0,
+            migrating_keys: 0,
+        };
+
+        let report = report.to_string();
+        let lines = report.split("\r\n").collect::<Vec<_>>();
+
+        assert_eq!(lines[3], "voted_for:-");
+        assert_eq!(
+            lines[8],
+            "peer:127.0.0.1:6380 role=follower shard=same match_index=7 last_seen_ms=40"
+        );
+        assert_eq!(lines[10], "in_flight:8 votes=1/2 voters=-");
+        assert!(lines.contains(&"writes_blocked:1"));
+        assert!(lines.contains(&"pending_requests:2"));
+    }
+}
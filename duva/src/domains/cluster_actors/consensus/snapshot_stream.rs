@@ -0,0 +1,121 @@
+use crate::domains::peers::command::InstallSnapshot;
+
+// * Snapshots are streamed in pieces of this size so that a large state never has to go out as a single frame
+pub(crate) const SNAPSHOT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Splits a snapshot into the chunks streamed to a replica, in order. The last chunk is marked `done`.
+pub(crate) fn split_snapshot(snapshot: InstallSnapshot, chunk_size: usize) -> Vec<InstallSnapshot> {
+    let InstallSnapshot { from, term, last_included_index, last_included_term, data, .. } =
+        snapshot;
+    let chunk = |offset: usize, end: usize| InstallSnapshot {
+        from: from.clone(),
+        term,
+        last_included_index,
+        last_included_term,
+        offset: offset as u64,
+        data: data[offset..end].to_vec(),
+        done: end == data.len(),
+    };
+    if data.is_empty() {
+        return vec![chunk(0, 0)];
+    }
+    (0..data.len())
+        .step_by(chunk_size.max(1))
+        .map(|offset| chunk(offset, (offset + chunk_size).min(data.len())))
+        .collect()
+}
+
+/// Reassembles a snapshot streamed by the leader on the replica side.
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotAssembler {
+    pending: Option<InstallSnapshot>,
+}
+
+impl SnapshotAssembler {
+    /// Returns the whole snapshot once its last chunk arrives. A chunk that does not continue the
+    /// snapshot being assembled discards it, and the leader has to stream it again from the start.
+    pub(crate) fn receive(
+        &mut self,
+        chunk: InstallSnapshot,
+    ) -> anyhow::Result<Option<InstallSnapshot>> {
+        let mut snapshot = match self.pending.take() {
+            | Some(pending)
+                if pending.last_included_index == chunk.last_included_index
+                    && pending.data.len() as u64 == chunk.offset =>
+            {
+                pending
+            },
+            | _ if chunk.offset == 0 => InstallSnapshot { data: Vec::new(), ..chunk.clone() },
+            | _ => {
+                return Err(anyhow::anyhow!(
+                    "snapshot chunk at offset {} up to {} arrived out of order",
+                    chunk.offset,
+                    chunk.last_included_index
+                ));
+            },
+        };
+
+        snapshot.data.extend_from_slice(&chunk.data);
+        if !chunk.done {
+            self.pending = Some(snapshot);
+            return Ok(None);
+        }
+        snapshot.done = true;
+        Ok(Some(snapshot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::peers::identifier::PeerIdentifier;
+
+    fn snapshot(data: Vec<u8>) -> InstallSnapshot {
+        InstallSnapshot {
+            from: PeerIdentifier::new("127.0.0.1", 6379),
+            term: 1,
+            last_included_index: 10,
+            last_included_term: 1,
+            offset: 0,
+            data,
+            done: true,
+        }
+    }
+
+    #[test]
+    fn test_split_and_reassemble_snapshot() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let chunks = split_snapshot(snapshot(data.clone()), 300);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.iter().map(|c| c.offset).collect::<Vec<_>>(), vec![0, 300, 600, 900]);
+        assert_eq!(chunks.iter().filter(|c| c.done).count(), 1);
+
+        let mut assembler = SnapshotAssembler::default();
+        let mut assembled = None;
+        for chunk in chunks {
+            assembled = assembler.receive(chunk).unwrap();
+        }
+        assert_eq!(assembled, Some(snapshot(data)));
+    }
+
+    #[test]
+    fn test_out_of_order_chunk_discards_pending_snapshot() {
+        let chunks = split_snapshot(snapshot(vec![1; 100]), 40);
+        let mut assembler = SnapshotAssembler::default();
+        assert_eq!(assembler.receive(chunks[0].clone()).unwrap(), None);
+        assert!(assembler.receive(chunks[2].clone()).is_err());
+        assert!(assembler.receive(chunks[1].clone()).is_err());
+
+        // * The stream starts over from the first chunk
+        for chunk in &chunks[..2] {
+            assert_eq!(assembler.receive(chunk.clone()).unwrap(), None);
+        }
+        assert_eq!(assembler.receive(chunks[2].clone()).unwrap(), Some(snapshot(vec![1; 100])));
+    }
+
+    #[test]
+    fn test_split_empty_snapshot() {
+        let chunks = split_snapshot(snapshot(vec![]), 40);
+        assert_eq!(chunks, vec![snapshot(vec![])]);
+    }
+}
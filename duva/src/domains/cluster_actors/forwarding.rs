@@ -0,0 +1,234 @@
+use super::actor::ClusterCommandHandler;
+use super::consensus::applied::Applying;
+use super::transactions::{TxnId, TxnOutcome};
+use super::{ClientMessage, ConsensusClientResponse, ConsensusRequest};
+use crate::domains::caches::cache_manager::CacheManager;
+use crate::domains::caches::cache_objects::{CacheEntry, CacheValue, TypedValue};
+use crate::domains::operation_logs::WriteRequest;
+use bytes::Bytes;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
+pub(crate) struct ForwardId(pub(crate) String);
+
+impl ForwardId {
+    pub(crate) fn generate() -> Self {
+        Self(uuid::Uuid::now_v7().to_string())
+    }
+}
+
+/// Part of a multi-key command that the node the client talks to hands to the shard leader
+/// owning its keys.
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) enum ForwardedOp {
+    MGet(#[bincode(with_serde)] Vec<Bytes>),
+    Exists(#[bincode(with_serde)] Vec<Bytes>),
+    Delete(#[bincode(with_serde)] Vec<Bytes>),
+    Unlink(#[bincode(with_serde)] Vec<Bytes>),
+    // * Two-phase commit of a transaction spanning partitions
+    Prepare(TxnId, Vec<WriteRequest>),
+    Commit(TxnId, Vec<WriteRequest>),
+    Abort(TxnId),
+    // * Asked of the coordinating partition by a participant left without an outcome
+    TxnStatus(TxnId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) enum ForwardedReply {
+    // * Positional, `None` for missing or non-string values
+    Values(Vec<Option<Vec<u8>>>),
+    Count(u64),
+    // * `None` while the coordinator has yet to decide
+    Outcome(Option<TxnOutcome>),
+    Err(String),
+}
+
+impl ForwardedOp {
+    pub(crate) fn keys(&self) -> Vec<&[u8]> {
+        match self {
+            | ForwardedOp::MGet(keys)
+            | ForwardedOp::Exists(keys)
+            | ForwardedOp::Delete(keys)
+            | ForwardedOp::Unlink(keys) => keys.iter().map(|k| &k[..]).collect(),
+            | ForwardedOp::Prepare(_, requests) | ForwardedOp::Commit(_, requests) => {
+                requests.iter().flat_map(WriteRequest::all_keys).collect()
+            },
+            | ForwardedOp::Abort(_) | ForwardedOp::TxnStatus(_) => vec![],
+        }
+    }
+
+    /// The same operation restricted to the keys at the given positions.
+    pub(crate) fn select(&self, positions: &[usize]) -> Self {
+        let keys = self.keys();
+        let keys = positions.iter().map(|&i| Bytes::copy_from_slice(keys[i])).collect();
+        match self {
+            | ForwardedOp::MGet(_) => ForwardedOp::MGet(keys),
+            | ForwardedOp::Exists(_) => ForwardedOp::Exists(keys),
+            | ForwardedOp::Delete(_) => ForwardedOp::Delete(keys),
+            | ForwardedOp::Unlink(_) => ForwardedOp::Unlink(keys),
+            | op => op.clone(),
+        }
+    }
+
+    /// Runs the operation against this node. Deletes are committed through the local shard's log
+    /// first.
+    pub(crate) async fn execute(
+        self,
+        handler: &ClusterCommandHandler,
+        cache_manager: &CacheManager,
+    ) -> ForwardedReply {
+        match self {
+            | ForwardedOp::MGet(keys) => ForwardedReply::Values(
+                cache_manager
+                    .route_mget(keys)
+                    .await
+                    .into_iter()
+                    .map(|entry| match entry {
+                        | Some(CacheEntry {
+                            value: CacheValue { value: TypedValue::String(s), .. },
+                            ..
+                        }) => Some(s.to_vec()),
+                        | _ => None,
+                    })
+                    .collect(),
+            ),
+            | ForwardedOp::Exists(keys) => match cache_manager.route_exists(keys).await {
+                | Ok(count) => ForwardedReply::Count(count),
+                | Err(err) => ForwardedReply::Err(err.to_string()),
+            },
+            | ForwardedOp::Delete(keys) => {
+                match propose(handler, WriteRequest::Delete { keys: keys.clone() }).await {
+                    | Ok(_applying) => match cache_manager.route_delete(keys).await {
+                        | Ok(count) => ForwardedReply::Count(count),
+                        | Err(err) => ForwardedReply::Err(err.to_string()),
+                    },
+                    | Err(err) => ForwardedReply::Err(err),
+                }
+            },
+            | ForwardedOp::Unlink(keys) => {
+                match propose(handler, WriteRequest::Unlink { keys: keys.clone() }).await {
+                    | Ok(_applying) => match cache_manager.route_unlink(keys).await {
+                        | Ok(count) => ForwardedReply::Count(count),
+                        | Err(err) => ForwardedReply::Err(err.to_string()),
+                    },
+                    | Err(err) => ForwardedReply::Err(err),
+                }
+            },
+            | ForwardedOp::Prepare(txn_id, requests) => {
+                let count = requests.len() as u64;
+                match propose(handler, WriteRequest::TxnPrepare { txn_id, requests }).await {
+                    | Ok(_) => ForwardedReply::Count(count),
+                    | Err(err) => ForwardedReply::Err(err),
+                }
+            },
+            | ForwardedOp::Commit(txn_id, requests) => {
+                let count = requests.len() as u64;
+                let request = WriteRequest::TxnCommit { txn_id, requests };
+                match propose(handler, request.clone()).await {
+                    | Ok(Some((index, _applying))) => {
+                        match cache_manager.apply_log(request, index).await {
+                            | Ok(()) => ForwardedReply::Count(count),
+                            | Err(err) => ForwardedReply::Err(err.to_string()),
+                        }
+                    },
+                    // * Committed earlier, when the participant resolved it on its own
+                    | Ok(None) => ForwardedReply::Count(count),
+                    | Err(err) => ForwardedReply::Err(err),
+                }
+            },
+            | ForwardedOp::Abort(txn_id) => {
+                match propose(handler, WriteRequest::TxnAbort { txn_id }).await {
+                    | Ok(_) => ForwardedReply::Count(0),
+                    | Err(err) => ForwardedReply::Err(err),
+                }
+            },
+            | ForwardedOp::TxnStatus(_) => {
+                ForwardedReply::Err("transaction status is answered by the cluster actor".into())
+            },
+        }
+    }
+}
+
+/// Commits the request through the local shard's log. `None` means it had already been processed.
+/// The entry counts as applied once the returned guard is dropped.
+async fn propose(
+    handler: &ClusterCommandHandler,
+    request: WriteRequest,
+) -> Result<Option<(u64, Applying)>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    handler
+        .send(ClientMessage::LeaderReqConsensus(ConsensusRequest::new(request, tx, None)))
+        .await
+        .map_err(|_| "cluster actor is gone".to_string())?;
+    match rx.await {
+        | Ok(ConsensusClientResponse::LogIndex(index, applying)) => Ok(Some((index, applying))),
+        | Ok(ConsensusClientResponse::AlreadyProcessed { .. }) => Ok(None),
+        | Ok(ConsensusClientResponse::Err(err)) => Err(err),
+        | Err(err) => Err(err.to_string()),
+    }
+}
+
+impl ForwardedReply {
+    /// Puts the replies of the partitions a command was split over back together.
+    /// `parts` pairs each reply with the positions of the keys it covered.
+    pub(crate) fn merge(key_count: usize, parts: Vec<(Vec<usize>, ForwardedReply)>) -> Self {
+        let mut values: Option<Vec<Option<Vec<u8>>>> = None;
+        let mut count = 0;
+        for (positions, reply) in parts {
+            match reply {
+                | ForwardedReply::Err(err) => return ForwardedReply::Err(err),
+                | ForwardedReply::Count(c) => count += c,
+                // * Only answers status queries, which are never scattered
+                | ForwardedReply::Outcome(_) => {},
+                | ForwardedReply::Values(part) => {
+                    let merged = values.get_or_insert_with(|| vec![None; key_count]);
+                    for (i, value) in positions.into_iter().zip(part) {
+                        merged[i] = value;
+                    }
+                },
+            }
+        }
+        match values {
+            | Some(values) => ForwardedReply::Values(values),
+            | None => ForwardedReply::Count(count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_keeps_the_operation() {
+        let op = ForwardedOp::Delete(vec!["a".into(), "b".into(), "c".into()]);
+        assert_eq!(op.select(&[2, 0]), ForwardedOp::Delete(vec!["c".into(), "a".into()]));
+    }
+
+    #[test]
+    fn merge_puts_values_back_in_key_order() {
+        let merged = ForwardedReply::merge(
+            3,
+            vec![
+                (vec![1], ForwardedReply::Values(vec![Some(b"b".to_vec())])),
+                (vec![0, 2], ForwardedReply::Values(vec![Some(b"a".to_vec()), None])),
+            ],
+        );
+        assert_eq!(
+            merged,
+            ForwardedReply::Values(vec![Some(b"a".to_vec()), Some(b"b".to_vec()), None])
+        );
+    }
+
+    #[test]
+    fn merge_sums_counts_and_fails_on_any_error() {
+        let counts =
+            vec![(vec![0], ForwardedReply::Count(1)), (vec![1, 2], ForwardedReply::Count(2))];
+        assert_eq!(ForwardedReply::merge(3, counts), ForwardedReply::Count(3));
+
+        let failed = vec![
+            (vec![0], ForwardedReply::Count(1)),
+            (vec![1], ForwardedReply::Err("MOVED x".into())),
+        ];
+        assert_eq!(ForwardedReply::merge(2, failed), ForwardedReply::Err("MOVED x".into()));
+    }
+}
@@ -2,30 +2,73 @@
 ///
 /// The `HashRing` maps keys to physical nodes using virtual nodes to ensure
 /// even distribution. Each physical node is represented by multiple virtual
-/// nodes on the ring, determined by `vnode_num`.
+/// nodes on the ring, determined by `vnode_num` times the weight of its partition.
 use crate::ReplicationId;
 use crate::prelude::PeerIdentifier;
+use bytes::Bytes;
 use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 mod hash_func;
+mod key_selector;
 mod migration_task;
-pub(crate) use hash_func::fnv_1a_hash;
+mod migration_throttle;
+pub(crate) use hash_func::{fnv_1a_hash, key_hash};
+pub use key_selector::KeySelector;
 pub(crate) use migration_task::*;
+pub(crate) use migration_throttle::{MigrationThrottle, encoded_len};
 
 #[cfg(test)]
 pub(crate) mod tests;
 
 // Number of virtual nodes to create for each physical node.
-const V_NODE_NUM: u16 = 256;
+pub(crate) const V_NODE_NUM: u16 = 256;
+pub(crate) const DEFAULT_WEIGHT: u8 = 1;
 
-#[derive(Debug, Default, bincode::Decode, bincode::Encode, Clone, Eq)]
+#[derive(Debug, bincode::Decode, bincode::Encode, Clone, Eq)]
 pub struct HashRing {
     vnodes: BTreeMap<u64, Rc<ReplicationId>>,
     pnodes: HashMap<ReplicationId, PeerIdentifier>,
+    // * A partition with weight N owns N times as many virtual nodes as one with weight 1
+    weights: HashMap<ReplicationId, u8>,
+    vnode_num: u16,
+    // * Keys moved by CLUSTER MIGRATE stay with their partition regardless of the vnodes.
+    // * Later pins take precedence over earlier ones.
+    pins: Vec<(KeySelector, ReplicationId)>,
     pub(crate) last_modified: u128,
 }
 
+impl Default for HashRing {
+    fn default() -> Self {
+        Self::new(V_NODE_NUM)
+    }
+}
+
 impl HashRing {
+    pub(crate) fn new(vnode_num: u16) -> Self {
+        Self {
+            vnodes: BTreeMap::new(),
+            pnodes: HashMap::new(),
+            weights: HashMap::new(),
+            vnode_num: vnode_num.max(1),
+            pins: Vec::new(),
+            last_modified: 0,
+        }
+    }
+
+    /// Rebuilds the ring with the given number of virtual nodes per unit of weight.
+    pub(crate) fn with_vnode_num(self, vnode_num: u16) -> HashRing {
+        let partitions = self
+            .pnodes
+            .into_iter()
+            .map(|(repl_id, leader_id)| {
+                let weight = self.weights.get(&repl_id).copied().unwrap_or(DEFAULT_WEIGHT);
+                (repl_id, leader_id, weight)
+            })
+            .collect();
+        HashRing { last_modified: self.last_modified, pins: self.pins, ..HashRing::new(vnode_num) }
+            .add_weighted_partitions(partitions)
+    }
+
     fn update_last_modified(&mut self) {
         self.last_modified = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -37,43 +80,81 @@ impl HashRing {
     /// This method will not respect any existing partitions that are not in the given partitions
     ///
     /// Returns None if the new partitions are identical to the current ones.
+    #[cfg(test)]
     pub(crate) fn set_partitions(
         &self,
         partitions: Vec<(ReplicationId, PeerIdentifier)>,
+    ) -> Option<HashRing> {
+        self.set_weighted_partitions(
+            partitions
+                .into_iter()
+                .map(|(repl_id, leader_id)| (repl_id, leader_id, DEFAULT_WEIGHT))
+                .collect(),
+        )
+    }
+
+    /// Same as [`HashRing::set_partitions`], with the weight of each partition.
+    /// A change of weight alone is enough to make a new ring.
+    pub(crate) fn set_weighted_partitions(
+        &self,
+        partitions: Vec<(ReplicationId, PeerIdentifier, u8)>,
     ) -> Option<HashRing> {
         // Create a set of new replication IDs for easy comparison
         let new_repl_ids: std::collections::HashSet<_> =
-            partitions.iter().map(|(repl_id, _)| repl_id).collect();
+            partitions.iter().map(|(repl_id, _, _)| repl_id).collect();
 
         // Check if any changes are needed
         let current_repl_ids: std::collections::HashSet<_> = self.pnodes.keys().collect();
 
         // If the sets are identical and all peer identifiers match, no changes needed
         if new_repl_ids == current_repl_ids {
-            let all_unchanged = partitions
-                .iter()
-                .all(|(repl_id, peer_id)| self.pnodes.get(repl_id) == Some(peer_id));
+            let all_unchanged = partitions.iter().all(|(repl_id, peer_id, weight)| {
+                self.pnodes.get(repl_id) == Some(peer_id)
+                    && self.weight(repl_id) == (*weight).max(1)
+            });
             if all_unchanged {
                 return None;
             }
         }
         // Create a new hash ring with only the specified partitions
-        let mut ring = HashRing::default().add_partitions(partitions);
+        let mut ring = HashRing::new(self.vnode_num).add_weighted_partitions(partitions);
+        // Pins to partitions that are still around survive the rebuild
+        ring.pins = self
+            .pins
+            .iter()
+            .filter(|(_, replid)| ring.pnodes.contains_key(replid))
+            .cloned()
+            .collect();
         ring.update_last_modified();
         Some(ring)
     }
 
+    #[cfg(test)]
     pub(crate) fn add_partitions(
-        mut self,
+        self,
         partitions: Vec<(ReplicationId, PeerIdentifier)>,
+    ) -> HashRing {
+        self.add_weighted_partitions(
+            partitions
+                .into_iter()
+                .map(|(repl_id, leader_id)| (repl_id, leader_id, DEFAULT_WEIGHT))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn add_weighted_partitions(
+        mut self,
+        partitions: Vec<(ReplicationId, PeerIdentifier, u8)>,
     ) -> HashRing {
         // Add all specified partitions
-        for (repl_id, leader_id) in partitions {
+        for (repl_id, leader_id, weight) in partitions {
+            let weight = weight.max(1);
             self.pnodes.insert(repl_id.clone(), leader_id);
+            self.weights.insert(repl_id.clone(), weight);
 
             let repl_id = Rc::new(repl_id);
             // Create virtual nodes for better distribution
-            for i in 0..V_NODE_NUM {
+            for i in 0..self.vnode_num as u32 * weight as u32 {
                 let virtual_node_id = format!("{repl_id}-{i}");
                 let hash = fnv_1a_hash(&virtual_node_id);
                 self.vnodes.insert(hash, repl_id.clone());
@@ -82,6 +163,27 @@ impl HashRing {
         self
     }
 
+    /// New ring in which the keys matched by `selector` belong to `target`.
+    pub(crate) fn pin(&self, selector: KeySelector, target: ReplicationId) -> HashRing {
+        let mut ring = self.clone();
+        ring.pins.retain(|(pinned, _)| *pinned != selector);
+        ring.pins.push((selector, target));
+        ring.update_last_modified();
+        ring
+    }
+
+    fn pinned_owner(&self, key: &[u8]) -> Option<&ReplicationId> {
+        self.pins
+            .iter()
+            .rev()
+            .find(|(selector, replid)| selector.matches(key) && self.pnodes.contains_key(replid))
+            .map(|(_, replid)| replid)
+    }
+
+    pub(crate) fn owner_of(&self, key: &[u8]) -> Option<&ReplicationId> {
+        self.pinned_owner(key).or_else(|| self.find_replid(key_hash(key)))
+    }
+
     fn find_replid(&self, hash: u64) -> Option<&ReplicationId> {
         // Find the first vnode with hash >= target hash
         self.vnodes
@@ -91,27 +193,40 @@ impl HashRing {
             .map(|(_, node_id)| node_id.as_ref())
     }
 
-    fn find_node(&self, hash: u64) -> Option<&PeerIdentifier> {
-        // Find the first vnode with hash >= target hash
-        self.pnodes.get(self.find_replid(hash)?)
-    }
-
     /// Verifies that all given keys belong to the specified node according to the hash ring
+    #[allow(dead_code)]
     pub(crate) fn verify_key_belongs_to_node(
         &self,
-        keys: &[&str],
+        keys: &[&[u8]],
         expected_node: &PeerIdentifier,
     ) -> bool {
-        keys.iter().all(|key| self.find_node(fnv_1a_hash(key)) == Some(expected_node))
+        keys.iter().all(|key| {
+            self.owner_of(key).and_then(|replid| self.pnodes.get(replid)) == Some(expected_node)
+        })
     }
 
     pub(crate) fn create_migration_tasks(
         &self,
         new_ring: &HashRing,
-        keys: Vec<String>,
+        keys: Vec<Bytes>,
     ) -> BTreeMap<ReplicationId, Vec<MigrationTask>> {
         let mut migration_tasks: BTreeMap<ReplicationId, Vec<MigrationTask>> = BTreeMap::new();
 
+        // Pinned keys don't follow the token ranges and are moved one by one
+        let (pinned, keys): (Vec<Bytes>, Vec<Bytes>) = keys.into_iter().partition(|key| {
+            self.pinned_owner(key).is_some() || new_ring.pinned_owner(key).is_some()
+        });
+        let mut pinned_moves: BTreeMap<ReplicationId, Vec<Bytes>> = BTreeMap::new();
+        for key in pinned {
+            if let (Some(old_owner), Some(new_owner)) =
+                (self.owner_of(&key), new_ring.owner_of(&key))
+                && old_owner != new_owner
+            {
+                pinned_moves.entry(new_owner.clone()).or_default().push(key);
+            }
+        }
+        migration_tasks.extend(tasks_by_owner(pinned_moves));
+
         // Get all token positions from both rings as partition boundaries
         let mut tokens: Vec<u64> =
             self.vnodes.keys().chain(new_ring.vnodes.keys()).cloned().collect();
@@ -143,6 +258,50 @@ impl HashRing {
         migration_tasks
     }
 
+    /// Tasks moving the keys held by `holder` that belong to other partitions on this ring.
+    /// Used to resume a migration whose previous ring is no longer known.
+    pub(crate) fn misplaced_keys(
+        &self,
+        holder: &ReplicationId,
+        keys: Vec<Bytes>,
+    ) -> BTreeMap<ReplicationId, Vec<MigrationTask>> {
+        let mut moves: BTreeMap<ReplicationId, Vec<Bytes>> = BTreeMap::new();
+        for key in keys {
+            if let Some(owner) = self.owner_of(&key)
+                && owner != holder
+            {
+                moves.entry(owner.clone()).or_default().push(key);
+            }
+        }
+        tasks_by_owner(moves)
+    }
+
+    /// Inclusive ranges of key hashes owned by each partition, in ascending order.
+    /// A key hash belongs to the first vnode at or after it, wrapping around past the last token.
+    pub(crate) fn token_ranges(&self) -> BTreeMap<ReplicationId, Vec<(u64, u64)>> {
+        let mut ranges: BTreeMap<ReplicationId, Vec<(u64, u64)>> = BTreeMap::new();
+        let mut push = |owner: &ReplicationId, start: u64, end: u64| {
+            let owned = ranges.entry(owner.clone()).or_default();
+            match owned.last_mut() {
+                | Some((_, last_end)) if last_end.wrapping_add(1) == start => *last_end = end,
+                | _ => owned.push((start, end)),
+            }
+        };
+
+        let mut start = 0;
+        for (&token, owner) in self.vnodes.iter() {
+            push(owner, start, token);
+            start = token.wrapping_add(1);
+        }
+        if let (Some((&last, _)), Some((_, first_owner))) =
+            (self.vnodes.last_key_value(), self.vnodes.first_key_value())
+            && last != u64::MAX
+        {
+            push(first_owner, last + 1, u64::MAX);
+        }
+        ranges
+    }
+
     #[cfg(test)]
     pub(crate) fn get_virtual_nodes(&self) -> Vec<(&u64, &std::rc::Rc<ReplicationId>)> {
         self.vnodes.iter().collect()
@@ -158,24 +317,38 @@ impl HashRing {
         self.vnodes.len()
     }
 
-    pub(crate) fn get_node_for_keys(&self, keys: &[&str]) -> anyhow::Result<ReplicationId> {
-        // Use the first key to determine the node
-        let hash = fnv_1a_hash(keys[0]);
-        self.find_replid(hash)
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("No node found for keys: {:?}", keys))
+    /// Partition owning all of the given keys. Keys spread over several partitions are rejected,
+    /// as a single request can only be committed by one of them.
+    pub(crate) fn get_node_for_keys(&self, keys: &[&[u8]]) -> anyhow::Result<ReplicationId> {
+        let mut owners = keys.iter().map(|key| self.owner_of(key));
+        let Some(Some(owner)) = owners.next() else {
+            return Err(anyhow::anyhow!("No node found for keys: {:?}", keys));
+        };
+        if !owners.all(|other| other == Some(owner)) {
+            return Err(anyhow::anyhow!(
+                "CROSSSLOT Keys in request don't hash to the same partition"
+            ));
+        }
+        Ok(owner.clone())
     }
 
     #[cfg(test)]
-    pub(crate) fn get_node_for_key(&self, key: &str) -> Option<&ReplicationId> {
-        let hash = fnv_1a_hash(key);
-        self.find_replid(hash)
+    pub(crate) fn get_node_for_key(&self, key: impl AsRef<[u8]>) -> Option<&ReplicationId> {
+        self.owner_of(key.as_ref())
+    }
+
+    pub(crate) fn partitions(&self) -> impl Iterator<Item = &ReplicationId> {
+        self.pnodes.keys()
     }
 
     pub fn get_node_id(&self, replid: &ReplicationId) -> Option<&PeerIdentifier> {
         self.pnodes.get(replid)
     }
 
+    pub(crate) fn weight(&self, replid: &ReplicationId) -> u8 {
+        self.weights.get(replid).copied().unwrap_or(DEFAULT_WEIGHT)
+    }
+
     pub(crate) fn update_repl_leader(&mut self, replid: ReplicationId, new_pnode: PeerIdentifier) {
         if let Some(existing_pnode) = self.pnodes.get_mut(&replid)
             && existing_pnode != &new_pnode
@@ -186,21 +359,35 @@ impl HashRing {
     }
 }
 
+// * One task per partition, spanning the hashes of the keys it receives
+fn tasks_by_owner(
+    moves: BTreeMap<ReplicationId, Vec<Bytes>>,
+) -> BTreeMap<ReplicationId, Vec<MigrationTask>> {
+    moves
+        .into_iter()
+        .map(|(owner, keys)| {
+            let hashes = keys.iter().map(|key| key_hash(key));
+            let task_id = (hashes.clone().min().unwrap_or(0), hashes.max().unwrap_or(0));
+            (owner, vec![MigrationTask { task_id, keys_to_migrate: keys }])
+        })
+        .collect()
+}
+
 fn filter_keys_in_partition(
-    keys: &[String],
+    keys: &[Bytes],
     partition_start: u64,
     partition_end: u64,
-) -> Vec<String> {
+) -> Vec<Bytes> {
     keys.iter()
         .filter(|key| {
-            let key_hash = fnv_1a_hash(key);
+            let hash = key_hash(key);
             // Check if key hash falls in range (partition_start, partition_end]
             // Handle wrap-around case where start > end
             if partition_start < partition_end {
-                key_hash > partition_start && key_hash <= partition_end
+                hash > partition_start && hash <= partition_end
             } else {
                 // Wrap-around: key is either > start OR <= end
-                key_hash > partition_start || key_hash <= partition_end
+                hash > partition_start || hash <= partition_end
             }
         })
         .cloned()
@@ -214,6 +401,6 @@ unsafe impl Sync for HashRing {}
 
 impl PartialEq for HashRing {
     fn eq(&self, other: &Self) -> bool {
-        self.vnodes == other.vnodes && self.pnodes == other.pnodes
+        self.vnodes == other.vnodes && self.pnodes == other.pnodes && self.pins == other.pins
     }
 }
@@ -1,7 +1,7 @@
 use std::num::Wrapping;
 
 #[inline]
-pub(crate) fn fnv_1a_hash(value: &str) -> u64 {
+pub(crate) fn fnv_1a_hash(value: &(impl AsRef<[u8]> + ?Sized)) -> u64 {
     // Using FNV-1a hash algorithm which is:
     // - Fast
     // - Good distribution
@@ -11,7 +11,7 @@ pub(crate) fn fnv_1a_hash(value: &str) -> u64 {
 
     let mut hash = Wrapping(FNV_OFFSET_BASIS);
 
-    for byte in value.bytes() {
+    for &byte in value.as_ref() {
         hash ^= Wrapping(byte as u64);
         hash *= Wrapping(FNV_PRIME);
     }
@@ -27,6 +27,23 @@ pub(crate) fn fnv_1a_hash(value: &str) -> u64 {
     h
 }
 
+/// Hashes the part of the key that decides its partition. When the key has a non-empty hash tag
+/// such as `{user:1}:name`, only the tag is hashed so that related keys land on the same partition.
+#[inline]
+pub(crate) fn key_hash(key: &[u8]) -> u64 {
+    fnv_1a_hash(hash_tag(key))
+}
+
+fn hash_tag(key: &[u8]) -> &[u8] {
+    if let Some(start) = memchr::memchr(b'{', key)
+        && let Some(len) = memchr::memchr(b'}', &key[start + 1..])
+        && len > 0
+    {
+        return &key[start + 1..start + 1 + len];
+    }
+    key
+}
+
 #[test]
 fn test_hash_deterministic() {
     // Same input should always produce same output
@@ -137,3 +154,16 @@ fn test_hash_avalanche_effect() {
         "Small changes should cause significant hash changes"
     );
 }
+
+#[test]
+fn test_key_hash_uses_hash_tag() {
+    assert_eq!(key_hash(b"{user:1}:name"), key_hash(b"{user:1}:email"));
+    assert_eq!(key_hash(b"{user:1}:name"), fnv_1a_hash("user:1"));
+    assert_eq!(key_hash(b"a{b}c{d}"), fnv_1a_hash("b"));
+    // * empty or unterminated tags hash the whole key
+    assert_eq!(key_hash(b"{}:name"), fnv_1a_hash("{}:name"));
+    assert_eq!(key_hash(b"{user:1:name"), fnv_1a_hash("{user:1:name"));
+    assert_eq!(key_hash(b"plain"), fnv_1a_hash("plain"));
+    // * keys need not be UTF-8
+    assert_eq!(key_hash(b"{\xff\x00}:name"), fnv_1a_hash(b"\xff\x00"));
+}
@@ -0,0 +1,48 @@
+use super::key_hash;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Subset of keys an operator moves with `CLUSTER MIGRATE`.
+/// `start-end` selects an inclusive range of key hashes, anything else is a key prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
+pub enum KeySelector {
+    Prefix(String),
+    Range(u64, u64),
+}
+
+impl KeySelector {
+    pub(crate) fn matches(&self, key: &[u8]) -> bool {
+        match self {
+            | KeySelector::Prefix(prefix) => key.starts_with(prefix.as_bytes()),
+            | KeySelector::Range(start, end) => (*start..=*end).contains(&key_hash(key)),
+        }
+    }
+}
+
+impl FromStr for KeySelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(anyhow::anyhow!("empty key selector"));
+        }
+        if let Some((start, end)) = s.split_once('-')
+            && let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>())
+        {
+            if start > end {
+                return Err(anyhow::anyhow!("invalid hash range {s}"));
+            }
+            return Ok(KeySelector::Range(start, end));
+        }
+        Ok(KeySelector::Prefix(s.to_string()))
+    }
+}
+
+impl Display for KeySelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | KeySelector::Prefix(prefix) => write!(f, "{prefix}"),
+            | KeySelector::Range(start, end) => write!(f, "{start}-{end}"),
+        }
+    }
+}
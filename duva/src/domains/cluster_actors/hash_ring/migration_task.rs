@@ -1,9 +1,12 @@
-use crate::{ReplicationId, types::Callback};
+use crate::{ReplicationId, prelude::PeerIdentifier, types::Callback};
+use bytes::Bytes;
+use std::collections::HashMap;
+use tokio::time::Instant;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct MigrationTask {
-    pub(crate) task_id: (u64, u64),          // (start_hash, end_hash)
-    pub(crate) keys_to_migrate: Vec<String>, // actual keys in this range
+    pub(crate) task_id: (u64, u64),         // (start_hash, end_hash)
+    pub(crate) keys_to_migrate: Vec<Bytes>, // actual keys in this range
 }
 
 impl MigrationTask {
@@ -30,15 +33,154 @@ impl MigrationBatch {
 
 #[derive(Debug)]
 pub(crate) struct PendingMigrationBatch {
-    pub(crate) callback: Callback<anyhow::Result<()>>,
-    pub(crate) keys: Vec<String>,
+    // * Resolved with the number of bytes migrated
+    pub(crate) callback: Callback<anyhow::Result<usize>>,
+    pub(crate) keys: Vec<Bytes>,
+    pub(crate) bytes: usize,
+    // * Past this point the target is presumed dead and the batch is failed
+    pub(crate) deadline: Option<Instant>,
 }
 
 impl PendingMigrationBatch {
     pub(crate) fn new(
-        callback: impl Into<Callback<anyhow::Result<()>>>,
-        keys: Vec<String>,
+        callback: impl Into<Callback<anyhow::Result<usize>>>,
+        keys: Vec<Bytes>,
     ) -> Self {
-        Self { callback: callback.into(), keys }
+        Self { callback: callback.into(), keys, bytes: 0, deadline: None }
+    }
+
+    pub(crate) fn with_bytes(self, bytes: usize) -> Self {
+        Self { bytes, ..self }
+    }
+
+    pub(crate) fn with_deadline(self, deadline: Instant) -> Self {
+        Self { deadline: Some(deadline), ..self }
+    }
+
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum KeyMigration {
+    // * Sent to the target but not yet acknowledged; the source still owns the key
+    InFlight(PeerIdentifier),
+    // * Committed on the target; requests for the key are answered with ASK
+    Moved(PeerIdentifier),
+}
+
+/// Per-key state of the migration in progress, so that routing stays correct mid-rebalance.
+#[derive(Debug, Default)]
+pub(crate) struct MigratingKeys(HashMap<Bytes, KeyMigration>);
+
+impl MigratingKeys {
+    pub(crate) fn start(&mut self, keys: &[Bytes], target: &PeerIdentifier) {
+        for key in keys {
+            self.0.insert(key.clone(), KeyMigration::InFlight(target.clone()));
+        }
+    }
+
+    pub(crate) fn complete(&mut self, keys: &[Bytes]) {
+        for key in keys {
+            if let Some(state) = self.0.get_mut(key)
+                && let KeyMigration::InFlight(target) = state
+            {
+                *state = KeyMigration::Moved(target.clone());
+            }
+        }
+    }
+
+    pub(crate) fn abort(&mut self, keys: &[Bytes]) {
+        for key in keys {
+            self.0.remove(key);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Node to send the request to when every given key has already moved to the same target.
+    pub(crate) fn ask_target(&self, keys: &[&[u8]]) -> Option<&PeerIdentifier> {
+        let mut targets = keys.iter().map(|key| match self.0.get(*key) {
+            | Some(KeyMigration::Moved(target)) => Some(target),
+            | _ => None,
+        });
+        let first = targets.next()??;
+        targets.all(|target| target == Some(first)).then_some(first)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get(&self, key: &[u8]) -> Option<&KeyMigration> {
+        self.0.get(key)
+    }
+}
+
+/// Counters of the last migration led by this node, reported by `CLUSTER RESHARD STATUS`.
+#[derive(Debug, Default)]
+pub(crate) struct MigrationProgress {
+    planned_batches: usize,
+    failed_batches: usize,
+    keys_moved: usize,
+    bytes_moved: usize,
+    // * Batches not acknowledged yet, with the partition they go to
+    pending: HashMap<BatchId, ReplicationId>,
+}
+
+impl MigrationProgress {
+    pub(crate) fn plan(&mut self, batches: &[MigrationBatch]) {
+        *self = Self {
+            planned_batches: batches.len(),
+            pending: batches.iter().map(|b| (b.id.clone(), b.target_repl.clone())).collect(),
+            ..Default::default()
+        };
+    }
+
+    pub(crate) fn complete(&mut self, batch_id: &BatchId, keys: usize, bytes: usize) {
+        if self.pending.remove(batch_id).is_some() {
+            self.keys_moved += keys;
+            self.bytes_moved += bytes;
+        }
+    }
+
+    pub(crate) fn has_failures(&self) -> bool {
+        self.failed_batches > 0
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    // * Batches given up on stay out of the pending set; their keys remain on this node
+    pub(crate) fn fail(&mut self, batch_id: &BatchId) {
+        if self.pending.remove(batch_id).is_some() {
+            self.failed_batches += 1;
+        }
+    }
+
+    pub(crate) fn report(&self, in_flight: usize) -> String {
+        let state = if self.pending.is_empty() { "idle" } else { "migrating" };
+        let mut partitions = self.pending.values().map(|r| r.to_string()).collect::<Vec<_>>();
+        partitions.sort();
+        partitions.dedup();
+        [
+            format!("reshard_state:{state}"),
+            format!("reshard_batches_planned:{}", self.planned_batches),
+            format!(
+                "reshard_batches_completed:{}",
+                self.planned_batches - self.pending.len() - self.failed_batches
+            ),
+            format!("reshard_batches_failed:{}", self.failed_batches),
+            format!("reshard_batches_in_flight:{in_flight}"),
+            format!("reshard_keys_moved:{}", self.keys_moved),
+            format!("reshard_bytes_moved:{}", self.bytes_moved),
+            format!("reshard_pending_partitions:{}", partitions.join(",")),
+        ]
+        .join("\r\n")
     }
 }
@@ -0,0 +1,105 @@
+use super::MigrationTask;
+use crate::domains::{caches::cache_objects::CacheEntry, query_io::SERDE_CONFIG};
+use bincode::{
+    Encode,
+    enc::{EncoderImpl, write::SizeWriter},
+};
+use std::time::Duration;
+
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Limits how hard a rebalance pushes on the network and on the consensus of the target partitions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MigrationThrottle {
+    pub(crate) max_concurrent_batches: usize,
+    // * 0 means unlimited
+    pub(crate) max_bytes_per_sec: u64,
+    pub(crate) batch_size: usize,
+    // * Attempts made for a batch after the target rejected it
+    pub(crate) max_retries: u32,
+    pub(crate) retry_backoff: Duration,
+    // * How long a batch may wait for its acknowledgement before it is retried
+    pub(crate) batch_timeout: Duration,
+}
+
+impl Default for MigrationThrottle {
+    fn default() -> Self {
+        Self {
+            max_concurrent_batches: 4,
+            max_bytes_per_sec: 0,
+            batch_size: 100,
+            max_retries: 5,
+            retry_backoff: Duration::from_millis(100),
+            batch_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl MigrationThrottle {
+    pub(crate) fn new(
+        max_concurrent_batches: usize,
+        max_bytes_per_sec: u64,
+        batch_size: usize,
+        batch_timeout_millis: u64,
+    ) -> Self {
+        Self {
+            max_concurrent_batches: max_concurrent_batches.max(1),
+            max_bytes_per_sec,
+            batch_size: batch_size.max(1),
+            batch_timeout: Duration::from_millis(batch_timeout_millis.max(1)),
+            ..Default::default()
+        }
+    }
+
+    /// Groups the tasks into batches of at most `batch_size` keys, splitting tasks that don't fit.
+    pub(crate) fn batches(&self, tasks: Vec<MigrationTask>) -> Vec<Vec<MigrationTask>> {
+        let mut batches = Vec::new();
+        let mut batch = Vec::new();
+        let mut room = self.batch_size;
+
+        for mut task in tasks {
+            while task.key_len() > room {
+                let rest = MigrationTask {
+                    task_id: task.task_id,
+                    keys_to_migrate: task.keys_to_migrate.split_off(room),
+                };
+                batch.push(task);
+                batches.push(std::mem::take(&mut batch));
+                task = rest;
+                room = self.batch_size;
+            }
+            room -= task.key_len();
+            if task.key_len() > 0 {
+                batch.push(task);
+            }
+            if room == 0 {
+                batches.push(std::mem::take(&mut batch));
+                room = self.batch_size;
+            }
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+
+    /// How long to hold off the next batch so that the bytes sent so far stay within the rate limit.
+    pub(crate) fn pace(&self, bytes_sent: u64, elapsed: Duration) -> Option<Duration> {
+        if self.max_bytes_per_sec == 0 {
+            return None;
+        }
+        let due = Duration::from_secs_f64(bytes_sent as f64 / self.max_bytes_per_sec as f64);
+        due.checked_sub(elapsed).filter(|wait| !wait.is_zero())
+    }
+
+    /// Exponential backoff before the given retry of a rejected batch.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_backoff.saturating_mul(1 << attempt.min(16)).min(MAX_RETRY_BACKOFF)
+    }
+}
+
+pub(crate) fn encoded_len(entries: &[CacheEntry]) -> usize {
+    let mut encoder = EncoderImpl::new(SizeWriter::default(), SERDE_CONFIG);
+    let _ = entries.encode(&mut encoder);
+    encoder.into_writer().bytes_written
+}
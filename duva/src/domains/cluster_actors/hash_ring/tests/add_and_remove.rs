@@ -34,6 +34,36 @@ fn test_get_node_for_key() {
     assert_eq!(*node.unwrap(), repl_id);
 }
 
+#[test]
+fn test_get_node_for_keys_rejects_keys_across_partitions() {
+    let ring = HashRing::default()
+        .set_partitions(vec![replid_and_nodeid(6379), replid_and_nodeid(6380)])
+        .unwrap();
+
+    // * find two keys owned by different partitions
+    let keys = (0..100).map(|i| format!("key_{i}")).collect::<Vec<_>>();
+    let first = keys[0].as_str();
+    let other = keys
+        .iter()
+        .find(|key| ring.get_node_for_key(key) != ring.get_node_for_key(first))
+        .unwrap()
+        .as_str();
+
+    assert_eq!(
+        ring.get_node_for_keys(&[first.as_bytes()]).unwrap(),
+        *ring.get_node_for_key(first).unwrap()
+    );
+    let err = ring.get_node_for_keys(&[first.as_bytes(), other.as_bytes()]).unwrap_err();
+    assert!(err.to_string().starts_with("CROSSSLOT"));
+
+    // * hash tags pin related keys to the same partition
+    let tagged = [format!("{{{first}}}:a"), format!("{{{first}}}:{other}")];
+    assert_eq!(
+        ring.get_node_for_keys(&[tagged[0].as_bytes(), tagged[1].as_bytes()]).unwrap(),
+        *ring.get_node_for_key(first).unwrap()
+    );
+}
+
 #[test]
 fn test_set_partitions_multiple_partitions() {
     let ring = HashRing::default();
@@ -164,3 +194,120 @@ fn test_node_removal_redistribution() {
     assert!(redistributed > 0); // Some keys must be redistributed
     assert!(redistributed < 100); // But not all keys should be redistributed
 }
+
+#[test]
+fn test_token_ranges_cover_hash_space_once() {
+    let ring = HashRing::default()
+        .set_partitions(vec![replid_and_nodeid(6379), replid_and_nodeid(6380)])
+        .unwrap();
+
+    let ranges = ring.token_ranges();
+    assert_eq!(ranges.len(), 2);
+
+    let mut all: Vec<_> = ranges.values().flatten().cloned().collect();
+    all.sort();
+    assert_eq!(all.first().unwrap().0, 0);
+    assert_eq!(all.last().unwrap().1, u64::MAX);
+    assert!(all.windows(2).all(|w| w[0].1 + 1 == w[1].0));
+
+    // * every key falls into a range of the partition it is routed to
+    for key in ["a", "foo", "bar:1", "user:42"] {
+        let hash = crate::domains::cluster_actors::hash_ring::key_hash(key.as_bytes());
+        let owner = ring.get_node_for_key(key).unwrap();
+        assert!(ranges[owner].iter().any(|&(start, end)| start <= hash && hash <= end));
+    }
+}
+
+#[test]
+fn test_weighted_partitions_own_proportionally_more_vnodes() {
+    let (light, light_node) = replid_and_nodeid(6379);
+    let (heavy, heavy_node) = replid_and_nodeid(6380);
+    let ring = HashRing::new(16)
+        .set_weighted_partitions(vec![
+            (light.clone(), light_node, 1),
+            (heavy.clone(), heavy_node, 3),
+        ])
+        .unwrap();
+
+    let owned_by = |replid: &ReplicationId| {
+        ring.get_virtual_nodes().iter().filter(|(_, owner)| owner.as_ref() == replid).count()
+    };
+    assert_eq!(owned_by(&light), 16);
+    assert_eq!(owned_by(&heavy), 48);
+    assert_eq!(ring.weight(&heavy), 3);
+}
+
+#[test]
+fn test_set_weighted_partitions_detects_weight_change() {
+    let (replid, node) = replid_and_nodeid(6379);
+    let ring = HashRing::default()
+        .set_weighted_partitions(vec![(replid.clone(), node.clone(), 1)])
+        .unwrap();
+
+    assert!(ring.set_weighted_partitions(vec![(replid.clone(), node.clone(), 1)]).is_none());
+    let reweighted = ring.set_weighted_partitions(vec![(replid, node, 2)]).unwrap();
+    assert_eq!(reweighted.get_vnode_count(), 512);
+}
+
+#[test]
+fn test_with_vnode_num_keeps_partitions_and_weights() {
+    let (replid, node) = replid_and_nodeid(6379);
+    let ring = HashRing::default().add_weighted_partitions(vec![(replid.clone(), node.clone(), 2)]);
+
+    let ring = ring.with_vnode_num(8);
+
+    assert_eq!(ring.get_vnode_count(), 16);
+    assert_eq!(ring.get_node_id(&replid), Some(&node));
+    // * the new vnode count is carried over to rings derived from it
+    let (other, other_node) = replid_and_nodeid(6380);
+    let ring =
+        ring.set_weighted_partitions(vec![(replid, node, 2), (other, other_node, 1)]).unwrap();
+    assert_eq!(ring.get_vnode_count(), 24);
+}
+
+#[test]
+fn test_pinned_keys_belong_to_target_partition() {
+    let (replid1, node1) = replid_and_nodeid(6379);
+    let (replid2, node2) = replid_and_nodeid(6380);
+    let ring = HashRing::default()
+        .add_partitions(vec![(replid1.clone(), node1), (replid2.clone(), node2)]);
+    let keys = (0..100).map(|i| format!("user:{i}")).collect::<Vec<_>>();
+    assert!(keys.iter().any(|key| ring.get_node_for_key(key) == Some(&replid1)));
+
+    let ring = ring.pin("user:".parse().unwrap(), replid2.clone());
+
+    assert!(keys.iter().all(|key| ring.get_node_for_key(key) == Some(&replid2)));
+    let refs = keys.iter().map(String::as_bytes).collect::<Vec<_>>();
+    assert_eq!(ring.get_node_for_keys(&refs).unwrap(), replid2);
+}
+
+#[test]
+fn test_pins_survive_rebuild_only_while_target_exists() {
+    let (replid1, node1) = replid_and_nodeid(6379);
+    let (replid2, node2) = replid_and_nodeid(6380);
+    let (replid3, node3) = replid_and_nodeid(6381);
+    let ring = HashRing::default()
+        .add_partitions(vec![(replid1.clone(), node1.clone()), (replid2.clone(), node2.clone())])
+        .pin(KeySelector::Range(0, u64::MAX), replid2.clone());
+
+    let grown = ring
+        .set_partitions(vec![
+            (replid1.clone(), node1.clone()),
+            (replid2.clone(), node2),
+            (replid3, node3),
+        ])
+        .unwrap();
+    assert_eq!(grown.get_node_for_key(b"any"), Some(&replid2));
+
+    let shrunk = ring.set_partitions(vec![(replid1.clone(), node1)]).unwrap();
+    assert_eq!(shrunk.get_node_for_key(b"any"), Some(&replid1));
+}
+
+#[test]
+fn test_parse_key_selector() {
+    assert_eq!("10-20".parse::<KeySelector>().unwrap(), KeySelector::Range(10, 20));
+    assert_eq!("user:".parse::<KeySelector>().unwrap(), KeySelector::Prefix("user:".into()));
+    assert_eq!("user-1".parse::<KeySelector>().unwrap(), KeySelector::Prefix("user-1".into()));
+    assert!("20-10".parse::<KeySelector>().is_err());
+    assert!("".parse::<KeySelector>().is_err());
+}
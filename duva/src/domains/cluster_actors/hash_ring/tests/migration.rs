@@ -1,4 +1,5 @@
 use super::*;
+use bytes::Bytes;
 use std::collections::HashMap;
 
 use crate::ReplicationId;
@@ -14,7 +15,7 @@ async fn test_no_migration_when_rings_identical() {
     let ring = HashRing::default();
     let ring = ring.set_partitions(vec![(replid_and_nodeid(6379))]).unwrap();
 
-    let keys = vec!["key1".to_string(), "key2".to_string()];
+    let keys = vec![Bytes::from("key1"), Bytes::from("key2")];
     let tasks = ring.create_migration_tasks(&ring, keys);
 
     assert!(tasks.is_empty(), "Identical rings should require no migration");
@@ -29,7 +30,7 @@ async fn test_empty_keys_migration_plan() {
     let old_ring = old_ring.set_partitions(vec![replid_and_nodeid(6379)]).unwrap();
     let new_ring = new_ring.set_partitions(vec![replid_and_nodeid(6380)]).unwrap();
 
-    let empty_keys: Vec<String> = Vec::new();
+    let empty_keys: Vec<Bytes> = Vec::new();
     let tasks = old_ring.create_migration_tasks(&new_ring, empty_keys);
 
     // Should return empty migration tasks since no keys to migrate
@@ -66,10 +67,10 @@ async fn test_single_node_ownership_change() {
         ])
         .unwrap();
 
-    let test_keys: Vec<String> = (0..10000).map(|i| format!("test_key_{i}")).collect();
+    let test_keys: Vec<Bytes> = (0..10000).map(|i| format!("test_key_{i}").into()).collect();
 
     // Identify expected migrations
-    let mut expected_migrations = HashMap::<ReplicationId, Vec<String>>::new();
+    let mut expected_migrations = HashMap::<ReplicationId, Vec<Bytes>>::new();
     for key in &test_keys {
         let old_owner = old_ring.get_node_for_key(key).unwrap();
         let new_owner = new_ring.get_node_for_key(key).unwrap();
@@ -81,7 +82,7 @@ async fn test_single_node_ownership_change() {
 
     // Create and collect actual migration tasks
     let migration_plans = old_ring.create_migration_tasks(&new_ring, test_keys.clone());
-    let mut actual_migrations = HashMap::<ReplicationId, Vec<String>>::new();
+    let mut actual_migrations = HashMap::<ReplicationId, Vec<Bytes>>::new();
     for (replid, tasks) in &migration_plans {
         actual_migrations
             .entry(replid.clone())
@@ -160,10 +161,10 @@ async fn test_multiple_ownership_changes() {
             (replid4.clone(), PeerIdentifier("peer4".into())),
         ])
         .unwrap();
-    let test_keys: Vec<String> = (0..5000).map(|i| format!("test_key_{i}")).collect();
+    let test_keys: Vec<Bytes> = (0..5000).map(|i| format!("test_key_{i}").into()).collect();
 
     // Identify expected migrations
-    let mut expected_migrations = HashMap::<ReplicationId, Vec<String>>::new();
+    let mut expected_migrations = HashMap::<ReplicationId, Vec<Bytes>>::new();
     for key in &test_keys {
         let old_owner = old_ring.get_node_for_key(key).unwrap();
         let new_owner = new_ring.get_node_for_key(key).unwrap();
@@ -175,7 +176,7 @@ async fn test_multiple_ownership_changes() {
 
     // Create and collect actual migration tasks
     let migration_plans = old_ring.create_migration_tasks(&new_ring, test_keys.clone());
-    let mut actual_migrations = HashMap::<ReplicationId, Vec<String>>::new();
+    let mut actual_migrations = HashMap::<ReplicationId, Vec<Bytes>>::new();
     for (replid, tasks) in &migration_plans {
         actual_migrations
             .entry(replid.clone())
@@ -244,3 +245,88 @@ async fn test_multiple_ownership_changes() {
     println!("  Node4 keys in new ring: {}", node_key_counts.get(&replid4).unwrap_or(&0));
     println!("  Total test keys: {}", test_keys.len());
 }
+
+#[tokio::test]
+async fn test_pinning_moves_only_selected_keys() {
+    let (replid1, nodeid1) = replid_and_nodeid(6379);
+    let (replid2, nodeid2) = replid_and_nodeid(6380);
+    let old_ring = HashRing::default()
+        .add_partitions(vec![(replid1.clone(), nodeid1), (replid2.clone(), nodeid2)]);
+    let new_ring = old_ring.pin("user:".parse().unwrap(), replid2.clone());
+
+    let keys: Vec<Bytes> = (0..200)
+        .flat_map(|i| [format!("user:{i}"), format!("order:{i}")])
+        .map(Bytes::from)
+        .collect();
+    let mut expected: Vec<Bytes> = keys
+        .iter()
+        .filter(|key| key.starts_with(b"user:") && old_ring.get_node_for_key(key) == Some(&replid1))
+        .cloned()
+        .collect();
+
+    let tasks = old_ring.create_migration_tasks(&new_ring, keys);
+
+    assert_eq!(tasks.len(), 1);
+    let mut moved: Vec<Bytes> =
+        tasks[&replid2].iter().flat_map(|t| t.keys_to_migrate.clone()).collect();
+    moved.sort();
+    expected.sort();
+    assert!(!expected.is_empty());
+    assert_eq!(moved, expected);
+}
+
+#[test]
+fn test_throttle_batches_respect_batch_size() {
+    let throttle = MigrationThrottle::new(4, 0, 3, 10000);
+    let tasks = vec![migration_task_create_helper(0, 5), migration_task_create_helper(10, 11)];
+
+    let batches = throttle.batches(tasks);
+
+    let sizes = batches
+        .iter()
+        .map(|batch| batch.iter().map(MigrationTask::key_len).sum::<usize>())
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, vec![3, 3]);
+    // * the task split across batches keeps its id
+    assert_eq!(batches[0][0].task_id, (0, 5));
+    assert_eq!(batches[1][0].task_id, (0, 5));
+    assert_eq!(batches[1][1].keys_to_migrate, vec!["key_10"]);
+}
+
+#[test]
+fn test_throttle_paces_to_byte_rate() {
+    let unlimited = MigrationThrottle::new(4, 0, 100, 10000);
+    assert_eq!(unlimited.pace(1_000_000, Duration::ZERO), None);
+
+    let throttle = MigrationThrottle::new(4, 1000, 100, 10000);
+    assert_eq!(throttle.pace(500, Duration::from_millis(100)), Some(Duration::from_millis(400)));
+    assert_eq!(throttle.pace(500, Duration::from_secs(1)), None);
+}
+
+#[test]
+fn test_throttle_backoff_grows_exponentially_up_to_cap() {
+    let throttle = MigrationThrottle::default();
+    assert_eq!(throttle.backoff(0), Duration::from_millis(100));
+    assert_eq!(throttle.backoff(2), Duration::from_millis(400));
+    assert_eq!(throttle.backoff(20), Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_misplaced_keys_only_lists_keys_owned_elsewhere() {
+    let (replid1, nodeid1) = replid_and_nodeid(6379);
+    let (replid2, nodeid2) = replid_and_nodeid(6380);
+    let ring = HashRing::default()
+        .add_partitions(vec![(replid1.clone(), nodeid1), (replid2.clone(), nodeid2)]);
+    let keys: Vec<Bytes> = (0..100).map(|i| format!("key_{i}").into()).collect();
+
+    let tasks = ring.misplaced_keys(&replid1, keys.clone());
+
+    let mut moved: Vec<Bytes> =
+        tasks[&replid2].iter().flat_map(|t| t.keys_to_migrate.clone()).collect();
+    let mut expected: Vec<Bytes> =
+        keys.into_iter().filter(|key| ring.get_node_for_key(key) == Some(&replid2)).collect();
+    moved.sort();
+    expected.sort();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(moved, expected);
+}
@@ -1,6 +1,6 @@
 use crate::{
     ReplicationId,
-    domains::cluster_actors::hash_ring::{HashRing, MigrationTask},
+    domains::cluster_actors::hash_ring::{HashRing, KeySelector, MigrationTask, MigrationThrottle},
     prelude::PeerIdentifier,
 };
 use std::{collections::HashSet, thread::sleep, time::Duration};
@@ -10,7 +10,7 @@ mod migration;
 pub(crate) fn migration_task_create_helper(start_hash: u64, end_hash: u64) -> MigrationTask {
     MigrationTask {
         task_id: (start_hash, end_hash),
-        keys_to_migrate: (start_hash..end_hash).map(|i| format!("key_{i}")).collect(),
+        keys_to_migrate: (start_hash..end_hash).map(|i| format!("key_{i}").into()).collect(),
     }
 }
 
@@ -0,0 +1,208 @@
+use crate::domains::peers::identifier::PeerIdentifier;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// * Oldest events are dropped past it
+const MAX_EVENTS: usize = 512;
+
+/// Topology transition seen by this node, reported by `CLUSTER HISTORY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TopologyEvent {
+    pub(crate) unix_millis: u128,
+    pub(crate) kind: TopologyEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TopologyEventKind {
+    NodeAdded(PeerIdentifier),
+    NodeRemoved(PeerIdentifier),
+    NodeBanned(PeerIdentifier),
+    LeaderChanged { leader: PeerIdentifier, term: u64 },
+    // * Identified by the time the ring was last modified, as set by the node that changed it
+    RingChanged { version: u128 },
+}
+
+/// The parts of the topology whose changes are recorded.
+#[derive(Debug, Default)]
+pub(crate) struct ObservedTopology<'a> {
+    pub(crate) members: BTreeSet<&'a PeerIdentifier>,
+    pub(crate) banned: BTreeSet<&'a PeerIdentifier>,
+    // * Leader of this node's partition, when known
+    pub(crate) leader: Option<PeerIdentifier>,
+    pub(crate) term: u64,
+    pub(crate) ring_version: u128,
+}
+
+/// Bounded log of topology transitions, found by comparing the topology after every command
+/// with the one seen after the previous command.
+#[derive(Debug, Default)]
+pub(crate) struct TopologyHistory {
+    events: VecDeque<TopologyEvent>,
+    members: BTreeSet<PeerIdentifier>,
+    banned: BTreeSet<PeerIdentifier>,
+    leader: Option<PeerIdentifier>,
+    ring_version: u128,
+}
+
+impl TopologyHistory {
+    pub(crate) fn observe(&mut self, topology: ObservedTopology) {
+        let mut kinds = Vec::new();
+        if !topology.members.iter().copied().eq(self.members.iter()) {
+            kinds.extend(
+                topology
+                    .members
+                    .iter()
+                    .filter(|peer| !self.members.contains(**peer))
+                    .map(|peer| TopologyEventKind::NodeAdded((*peer).clone())),
+            );
+            kinds.extend(
+                self.members
+                    .iter()
+                    .filter(|peer| !topology.members.contains(peer))
+                    .map(|peer| TopologyEventKind::NodeRemoved(peer.clone())),
+            );
+            self.members = topology.members.into_iter().cloned().collect();
+        }
+        if !topology.banned.iter().copied().eq(self.banned.iter()) {
+            kinds.extend(
+                topology
+                    .banned
+                    .iter()
+                    .filter(|peer| !self.banned.contains(**peer))
+                    .map(|peer| TopologyEventKind::NodeBanned((*peer).clone())),
+            );
+            self.banned = topology.banned.into_iter().cloned().collect();
+        }
+        // * No leader is recorded while an election is under way, only the one that comes out of it
+        if let Some(leader) = topology.leader
+            && self.leader.as_ref() != Some(&leader)
+        {
+            self.leader = Some(leader.clone());
+            kinds.push(TopologyEventKind::LeaderChanged { leader, term: topology.term });
+        }
+        if topology.ring_version != self.ring_version {
+            self.ring_version = topology.ring_version;
+            kinds.push(TopologyEventKind::RingChanged { version: topology.ring_version });
+        }
+
+        if kinds.is_empty() {
+            return;
+        }
+        let unix_millis =
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        for kind in kinds {
+            if self.events.len() >= MAX_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(TopologyEvent { unix_millis, kind });
+        }
+    }
+
+    /// The `count` most recent events, oldest first.
+    pub(crate) fn recent(&self, count: Option<usize>) -> Vec<TopologyEvent> {
+        let count = count.unwrap_or(self.events.len());
+        self.events.iter().skip(self.events.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+impl Display for TopologyEvent {
+    /// `<unix millis> <event> <details>`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.unix_millis)?;
+        match &self.kind {
+            | TopologyEventKind::NodeAdded(peer) => write!(f, "node_added {peer}"),
+            | TopologyEventKind::NodeRemoved(peer) => write!(f, "node_removed {peer}"),
+            | TopologyEventKind::NodeBanned(peer) => write!(f, "node_banned {peer}"),
+            | TopologyEventKind::LeaderChanged { leader, term } => {
+                write!(f, "leader_changed {leader} term={term}")
+            },
+            | TopologyEventKind::RingChanged { version } => {
+                write!(f, "ring_changed version={version}")
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(history: &TopologyHistory) -> Vec<TopologyEventKind> {
+        history.recent(None).into_iter().map(|event| event.kind).collect()
+    }
+
+    #[test]
+    fn test_observe_records_only_transitions() {
+        let (a, b) =
+            (PeerIdentifier("127.0.0.1:6380".into()), PeerIdentifier("127.0.0.1:6381".into()));
+        let mut history = TopologyHistory::default();
+
+        history.observe(ObservedTopology {
+            members: BTreeSet::from([&a, &b]),
+            leader: Some(a.clone()),
+            term: 1,
+            ..Default::default()
+        });
+        history.observe(ObservedTopology {
+            members: BTreeSet::from([&a, &b]),
+            leader: Some(a.clone()),
+            term: 1,
+            ..Default::default()
+        });
+        // * Election under way
+        history.observe(ObservedTopology {
+            members: BTreeSet::from([&b]),
+            banned: BTreeSet::from([&a]),
+            term: 2,
+            ..Default::default()
+        });
+        history.observe(ObservedTopology {
+            members: BTreeSet::from([&b]),
+            banned: BTreeSet::from([&a]),
+            leader: Some(b.clone()),
+            term: 2,
+            ring_version: 10,
+        });
+
+        assert_eq!(
+            kinds(&history),
+            [
+                TopologyEventKind::NodeAdded(a.clone()),
+                TopologyEventKind::NodeAdded(b.clone()),
+                TopologyEventKind::LeaderChanged { leader: a.clone(), term: 1 },
+                TopologyEventKind::NodeRemoved(a.clone()),
+                TopologyEventKind::NodeBanned(a.clone()),
+                TopologyEventKind::LeaderChanged { leader: b.clone(), term: 2 },
+                TopologyEventKind::RingChanged { version: 10 },
+            ]
+        );
+        assert_eq!(history.recent(Some(2)).len(), 2);
+        assert_eq!(history.recent(Some(2))[1].kind, TopologyEventKind::RingChanged { version: 10 });
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = TopologyHistory::default();
+        for version in 1..=(MAX_EVENTS as u128 + 10) {
+            history.observe(ObservedTopology { ring_version: version, ..Default::default() });
+        }
+
+        let events = history.recent(None);
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(events[0].kind, TopologyEventKind::RingChanged { version: 11 });
+    }
+
+    #[test]
+    fn test_display() {
+        let event = TopologyEvent {
+            unix_millis: 1700000000000,
+            kind: TopologyEventKind::LeaderChanged {
+                leader: PeerIdentifier("127.0.0.1:6380".into()),
+                term: 3,
+            },
+        };
+
+        assert_eq!(event.to_string(), "1700000000000 leader_changed 127.0.0.1:6380 term=3");
+    }
+}
@@ -0,0 +1,267 @@
+use super::{ClientMessage, ClusterCommand, SchedulerMessage};
+use crate::domains::peers::command::PeerMessage;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+// * Slots kept for peers and timers on top of the client limits, so that client load never crowds them out
+pub(crate) const INTERNAL_QUEUE_CAPACITY: usize = 100;
+
+/// Client requests the cluster actor may have queued per class. Past the limit, requests are turned
+/// away with `BUSY` right away instead of waiting behind a backlog the actor may never catch up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct QueueLimits {
+    pub(crate) writes: usize,
+    pub(crate) reads: usize,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self { writes: 1024, reads: 1024 }
+    }
+}
+
+impl QueueLimits {
+    pub(crate) fn new(writes: usize, reads: usize) -> Self {
+        Self { writes, reads }
+    }
+
+    /// Size of the actor's queue, which fits every class at its limit.
+    pub(crate) fn capacity(&self) -> usize {
+        self.writes + self.reads + INTERNAL_QUEUE_CAPACITY
+    }
+
+    fn of(&self, class: QueueClass) -> usize {
+        match class {
+            | QueueClass::Writes => self.writes,
+            | QueueClass::Reads => self.reads,
+        }
+    }
+}
+
+/// Class a client request is queued under: writes to be committed, or anything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QueueClass {
+    Writes,
+    Reads,
+}
+
+impl QueueClass {
+    pub(crate) fn of(msg: &ClientMessage) -> Self {
+        match msg {
+            | ClientMessage::LeaderReqConsensus(_) => Self::Writes,
+            | _ => Self::Reads,
+        }
+    }
+
+    /// Class of a queued command, `None` for the actor's own traffic, which has no limit.
+    pub(crate) fn of_command(cmd: &ClusterCommand) -> Option<Self> {
+        match cmd {
+            | ClusterCommand::Client(msg) => Some(Self::of(msg)),
+            | _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            | Self::Writes => "writes",
+            | Self::Reads => "reads",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClassGauge {
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+/// Client requests waiting in the cluster actor's queue, by class, and how many were turned away.
+/// Shared by every handle to the actor, so that it can be read without going through the queue.
+#[derive(Debug, Default)]
+pub(crate) struct Mailbox {
+    limits: QueueLimits,
+    writes: ClassGauge,
+    reads: ClassGauge,
+}
+
+impl Mailbox {
+    pub(crate) fn new(limits: QueueLimits) -> Self {
+        Self { limits, ..Default::default() }
+    }
+
+    fn gauge(&self, class: QueueClass) -> &ClassGauge {
+        match class {
+            | QueueClass::Writes => &self.writes,
+            | QueueClass::Reads => &self.reads,
+        }
+    }
+
+    /// Takes a slot for a client request, failing with `BUSY` when its class is at its limit.
+    pub(crate) fn admit(&self, class: QueueClass) -> anyhow::Result<()> {
+        let (gauge, limit) = (self.gauge(class), self.limits.of(class));
+        match gauge.queued.fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+            (queued < limit).then_some(queued + 1)
+        }) {
+            | Ok(_) => Ok(()),
+            | Err(_) => {
+                gauge.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(anyhow::anyhow!(
+                    "BUSY the cluster has {limit} client {} queued already, try again later",
+                    class.name()
+                ))
+            },
+        }
+    }
+
+    /// Takes a slot regardless of the limit, for requests that were admitted once already.
+    pub(crate) fn enqueue(&self, class: QueueClass) {
+        self.gauge(class).queued.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Gives the slot back once the actor takes the request off its queue.
+    pub(crate) fn release(&self, class: QueueClass) {
+        let _ =
+            self.gauge(class)
+                .queued
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| queued.checked_sub(1));
+    }
+
+    /// `depth` is what is left in the actor's channel, its own traffic included. Client requests the
+    /// actor already sorted into [`Lanes`] no longer count there, but still do in their class.
+    pub(crate) fn vectorize(&self, depth: usize) -> Vec<String> {
+        vec![
+            format!("cluster_queue_depth:{depth}"),
+            format!("cluster_queue_capacity:{}", self.limits.capacity()),
+            format!("cluster_queue_writes:{}", self.writes.queued.load(Ordering::Relaxed)),
+            format!("cluster_queue_reads:{}", self.reads.queued.load(Ordering::Relaxed)),
+            format!(
+                "cluster_queue_rejected_writes:{}",
+                self.writes.rejected.load(Ordering::Relaxed)
+            ),
+            format!("cluster_queue_rejected_reads:{}", self.reads.rejected.load(Ordering::Relaxed)),
+        ]
+    }
+}
+
+/// Order in which the actor takes commands off its queue. Votes and heartbeats keep leadership and
+/// liveness settled, so they never wait behind log traffic, which in turn never waits behind clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lane {
+    Control,
+    Replication,
+    Client,
+}
+
+impl Lane {
+    pub(crate) fn of(cmd: &ClusterCommand) -> Self {
+        match cmd {
+            | ClusterCommand::Client(_) => Self::Client,
+            | ClusterCommand::Scheduler(
+                SchedulerMessage::SendPeriodicHeatBeat
+                | SchedulerMessage::StartLeaderElection
+                | SchedulerMessage::AbortLeadershipTransfer(_),
+            ) => Self::Control,
+            | ClusterCommand::Peer(peer) => match peer.msg {
+                | PeerMessage::ClusterHeartBeat(_)
+                | PeerMessage::RequestVote(_)
+                | PeerMessage::ElectionVoteReply(_)
+                | PeerMessage::PreVote(_)
+                | PeerMessage::PreVoteReply(_)
+                | PeerMessage::TimeoutNow(_) => Self::Control,
+                | _ => Self::Replication,
+            },
+            // * Log replication, migrations, forwarding and connection changes alike
+            | ClusterCommand::Scheduler(_) | ClusterCommand::ConnectionReq(_) => Self::Replication,
+        }
+    }
+}
+
+/// Commands taken off the actor's queue and sorted by [`Lane`], first in first out within a lane.
+#[derive(Debug, Default)]
+pub(crate) struct Lanes {
+    control: VecDeque<ClusterCommand>,
+    replication: VecDeque<ClusterCommand>,
+    client: VecDeque<ClusterCommand>,
+}
+
+impl Lanes {
+    pub(crate) fn push(&mut self, cmd: ClusterCommand) {
+        match Lane::of(&cmd) {
+            | Lane::Control => self.control.push_back(cmd),
+            | Lane::Replication => self.replication.push_back(cmd),
+            | Lane::Client => self.client.push_back(cmd),
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<ClusterCommand> {
+        self.control
+            .pop_front()
+            .or_else(|| self.replication.pop_front())
+            .or_else(|| self.client.pop_front())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.replication.is_empty() && self.client.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit_turns_requests_away_past_their_class_limit() {
+        // GIVEN
+        let mailbox = Mailbox::new(QueueLimits::new(2, 1));
+        mailbox.admit(QueueClass::Writes).unwrap();
+        mailbox.admit(QueueClass::Writes).unwrap();
+
+        // WHEN
+        let rejected = mailbox.admit(QueueClass::Writes).unwrap_err();
+
+        // THEN - the other class keeps its own share
+        assert!(rejected.to_string().starts_with("BUSY"));
+        mailbox.admit(QueueClass::Reads).unwrap();
+        assert!(mailbox.vectorize(3).contains(&"cluster_queue_rejected_writes:1".to_string()));
+
+        // WHEN the actor takes one off the queue
+        mailbox.release(QueueClass::Writes);
+
+        // THEN
+        mailbox.admit(QueueClass::Writes).unwrap();
+    }
+
+    #[test]
+    fn test_release_never_goes_below_zero() {
+        let mailbox = Mailbox::default();
+        mailbox.release(QueueClass::Reads);
+        mailbox.enqueue(QueueClass::Reads);
+        assert!(mailbox.vectorize(1).contains(&"cluster_queue_reads:1".to_string()));
+    }
+
+    #[test]
+    fn test_lanes_take_control_before_replication_before_clients() {
+        // GIVEN - queued in the reverse order of their urgency
+        let client = |n| {
+            let (tx, _) = tokio::sync::oneshot::channel();
+            ClusterCommand::Client(ClientMessage::ClusterHistory(Some(n), tx.into()))
+        };
+        let mut lanes = Lanes::default();
+        lanes.push(client(1));
+        lanes.push(ClusterCommand::Scheduler(SchedulerMessage::SendAppendEntriesRPC));
+        lanes.push(client(2));
+        lanes.push(ClusterCommand::Scheduler(SchedulerMessage::StartLeaderElection));
+        lanes.push(ClusterCommand::Scheduler(SchedulerMessage::SyncLogs));
+
+        // WHEN
+        let order: Vec<Lane> =
+            std::iter::from_fn(|| lanes.pop()).map(|cmd| Lane::of(&cmd)).collect();
+
+        // THEN
+        assert_eq!(
+            order,
+            vec![Lane::Control, Lane::Replication, Lane::Replication, Lane::Client, Lane::Client]
+        );
+        assert!(lanes.is_empty());
+    }
+}
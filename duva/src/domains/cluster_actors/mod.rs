@@ -1,19 +1,26 @@
 use crate::domains::peers::identifier::PeerIdentifier;
 use crate::domains::peers::peer::Peer;
 use consensus::LogConsensusTracker;
+use futures::FutureExt;
 use futures::StreamExt;
 use futures::stream::FuturesUnordered;
 use std::collections::BTreeMap;
 use tokio::time::Instant;
 pub mod actor;
 mod command;
+pub use command::LazyOption;
 pub(crate) use command::*;
 pub mod consensus;
+pub(crate) mod forwarding;
 pub(crate) mod hash_ring;
+pub(crate) mod history;
+pub(crate) mod mailbox;
+pub use hash_ring::KeySelector;
 
 pub mod replication;
 mod service;
 pub(crate) mod topology;
+pub(crate) mod transactions;
 
 pub const FANOUT: usize = 2;
 pub use actor::ClusterActor;
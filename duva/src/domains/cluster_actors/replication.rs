@@ -1,10 +1,14 @@
 use super::consensus::election::ElectionState;
-use crate::domains::peers::command::BannedPeer;
+use super::hash_ring::DEFAULT_WEIGHT;
+use crate::domains::compression::Compression;
+use crate::domains::peers::banlist::BanList;
 use crate::domains::peers::command::HeartBeat;
+use crate::domains::peers::connections::cluster_secret::ClusterSecret;
+use crate::domains::peers::connections::tls::PeerTls;
 use crate::domains::peers::identifier::PeerIdentifier;
 
 use crate::domains::peers::peer::PeerState;
-use std::collections::HashSet;
+use crate::domains::telemetry::TraceContext;
 use std::fmt::Display;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
@@ -19,8 +23,23 @@ pub(crate) struct ReplicationState {
     pub(crate) self_port: u16,
     // * state is shared among peers
     pub(crate) term: u64,
-    pub(crate) banlist: HashSet<BannedPeer>,
+    pub(crate) banlist: BanList,
     pub(crate) election_state: ElectionState,
+    // * Higher priority nodes are preferred as leaders of the shard
+    pub(crate) priority: u8,
+    // * Share of the hash ring the node's partition asks for when it leads it
+    pub(crate) weight: u8,
+    // * Replica this node takes the log from when it is chained behind another replica
+    pub(crate) upstream: Option<PeerIdentifier>,
+    // * Codec offered to peers for the entries carried by AppendEntries
+    pub(crate) compression: Compression,
+    // * Peers have to prove they know it during the handshake, both ways
+    pub(crate) cluster_secret: Option<ClusterSecret>,
+    // * Certificate and cluster CA peer connections are secured with, both ways
+    pub(crate) tls: Option<PeerTls>,
+    // * Handed to the peers this node connects to, so they know it by it instead of the address
+    // * the connection comes from
+    pub(crate) announce_ip: Option<String>,
 }
 
 impl ReplicationState {
@@ -40,6 +59,13 @@ impl ReplicationState {
             self_host: self_host.to_string(),
             self_port,
             banlist: Default::default(),
+            priority: 0,
+            weight: DEFAULT_WEIGHT,
+            upstream: None,
+            compression: Compression::None,
+            cluster_secret: None,
+            tls: None,
+            announce_ip: None,
         }
     }
 
@@ -59,17 +85,21 @@ impl ReplicationState {
     }
 
     pub(crate) fn vectorize(self) -> Vec<String> {
+        let hwm = self.hwm.load(Ordering::Relaxed);
         vec![
             format!("role:{}", self.role),
             format!("leader_repl_id:{}", self.replid),
-            format!("high_watermark:{}", self.hwm.load(Ordering::Relaxed)),
+            format!("high_watermark:{hwm}"),
             format!("self_identifier:{}", self.self_identifier()),
+            // * Under the names Redis clients look for
+            format!("master_replid:{}", self.replid),
+            format!("master_repl_offset:{hwm}"),
         ]
     }
 
-    pub(super) fn in_ban_list(&self, peer_identifier: &PeerIdentifier) -> bool {
+    pub(crate) fn in_ban_list(&self, peer_identifier: &PeerIdentifier) -> bool {
         let Ok(current_time) = time_in_secs() else { return false };
-        self.banlist.get(peer_identifier).is_some_and(|node| current_time - node.ban_time < 60)
+        self.banlist.is_banned(peer_identifier, current_time)
     }
 
     pub(super) fn default_heartbeat(
@@ -84,12 +114,17 @@ impl ReplicationState {
             hwm: self.hwm.load(Ordering::Relaxed),
             replid: self.replid.clone(),
             hop_count,
-            ban_list: self.banlist.iter().cloned().collect(),
+            ban_list: self.banlist.to_vec(),
             append_entries: vec![],
             cluster_nodes: vec![],
+            nodes_digest: 0,
             prev_log_index,
             prev_log_term,
             hashring: None,
+            priority: self.priority,
+            weight: self.weight,
+            upstream: self.upstream.clone(),
+            trace: TraceContext::current(),
         }
     }
 
@@ -132,6 +167,55 @@ impl ReplicationState {
     }
 }
 
+/// The shard's replication links as seen from this node, reported by `INFO replication`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ReplicationLinks {
+    // * Leader of the shard and seconds since it was last heard from, when this node follows it
+    pub(crate) leader: Option<(PeerIdentifier, u64)>,
+    // * Replicas of the shard, when this node leads it, with their match index and seconds since
+    // * they were last heard from
+    pub(crate) replicas: Vec<(PeerIdentifier, u64, u64)>,
+}
+
+impl ReplicationLinks {
+    /// Lines named after the ones Redis reports for its master link and replicas.
+    pub(crate) fn vectorize(&self, role: &ReplicationRole, hwm: u64) -> Vec<String> {
+        let mut info = vec![];
+        if *role == ReplicationRole::Follower {
+            let (host, port) = match &self.leader {
+                | Some((id, _)) => id.host_and_port(),
+                | None => ("", ""),
+            };
+            info.push(format!("master_host:{host}"));
+            info.push(format!("master_port:{port}"));
+            info.push(format!(
+                "master_link_status:{}",
+                if self.leader.is_some() { "up" } else { "down" }
+            ));
+            info.push(format!(
+                "master_last_io_seconds_ago:{}",
+                self.leader.as_ref().map_or(-1, |(_, secs)| *secs as i64)
+            ));
+            info.push(format!("slave_repl_offset:{hwm}"));
+        }
+        info.push(format!("connected_slaves:{}", self.replicas.len()));
+        for (i, (id, offset, lag)) in self.replicas.iter().enumerate() {
+            let (host, port) = id.host_and_port();
+            info.push(format!(
+                "slave{i}:ip={host},port={port},state=online,offset={offset},lag={lag}"
+            ));
+        }
+        info
+    }
+}
+
+/// What a follower last heard from its leader; bounds the staleness of the reads it serves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KnownLeader {
+    pub(crate) id: PeerIdentifier,
+    pub(crate) hwm: u64,
+}
+
 pub(crate) fn time_in_secs() -> anyhow::Result<u64> {
     Ok(std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -175,7 +259,7 @@ impl From<String> for ReplicationId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode, Default, Hash)]
 pub enum ReplicationRole {
     #[default]
     Follower,
@@ -207,6 +291,34 @@ impl From<ReplicationRole> for String {
     }
 }
 
+#[test]
+fn test_replication_links_vectorize() {
+    let replica = PeerIdentifier::new("127.0.0.1", 6380);
+    let leader_links = ReplicationLinks { leader: None, replicas: vec![(replica.clone(), 7, 1)] };
+    assert_eq!(
+        leader_links.vectorize(&ReplicationRole::Leader, 9),
+        vec!["connected_slaves:1", "slave0:ip=127.0.0.1,port=6380,state=online,offset=7,lag=1",]
+    );
+
+    let follower_links = ReplicationLinks { leader: Some((replica, 2)), replicas: vec![] };
+    assert_eq!(
+        follower_links.vectorize(&ReplicationRole::Follower, 9),
+        vec![
+            "master_host:127.0.0.1",
+            "master_port:6380",
+            "master_link_status:up",
+            "master_last_io_seconds_ago:2",
+            "slave_repl_offset:9",
+            "connected_slaves:0",
+        ]
+    );
+    assert!(
+        ReplicationLinks::default()
+            .vectorize(&ReplicationRole::Follower, 0)
+            .contains(&"master_link_status:down".to_string())
+    );
+}
+
 #[test]
 fn test_cloning_replication_state() {
     //GIVEN
@@ -4,17 +4,35 @@ use crate::domains::cluster_actors::ClusterActor;
 use crate::domains::cluster_actors::ClusterCommand;
 use crate::domains::cluster_actors::ConnectionMessage;
 use crate::domains::cluster_actors::SchedulerMessage;
+use crate::domains::cluster_actors::mailbox::{Lanes, QueueClass};
 use crate::domains::operation_logs::interfaces::TWriteAheadLog;
 use crate::domains::peers::PeerMessage;
+use crate::domains::telemetry::joined_span;
 
 use crate::prelude::PeerIdentifier;
 use crate::res_err;
-use tracing::{instrument, trace};
+use tracing::{Instrument, instrument, trace};
 
 impl<T: TWriteAheadLog> ClusterActor<T> {
     pub(super) async fn handle(mut self, cache_manager: CacheManager) -> anyhow::Result<Self> {
-        while let Some(command) = self.receiver.recv().await {
+        let mut lanes = Lanes::default();
+        loop {
+            // * Everything queued so far is sorted into lanes first, so that a vote stuck behind a burst of
+            // * client writes is still answered before the election times out
+            if lanes.is_empty() {
+                let Some(command) = self.receiver.recv().await else { break };
+                lanes.push(command);
+            }
+            while let Ok(command) = self.receiver.try_recv() {
+                lanes.push(command);
+            }
+            let Some(command) = lanes.pop() else { continue };
+
             trace!(?command, "Cluster command received");
+            // * Client slots are held until here, so requests waiting in a lane still count towards the limits
+            if let Some(class) = QueueClass::of_command(&command) {
+                self.self_handler.mailbox.release(class);
+            }
             match command {
                 | ClusterCommand::Scheduler(msg) => {
                     self.process_scheduler_message(msg, &cache_manager).await;
@@ -30,6 +48,16 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                     self.process_connection_message(conn_msg).await;
                 },
             }
+            // * Catches term changes learned from heartbeats and replies; votes are persisted before they are sent
+            self.persist_hard_state();
+            // * Replica answers, commits and role changes all may settle reads waiting on ReadIndex
+            self.confirm_pending_reads();
+            // * Replica answers move match indexes that WAIT may be blocked on
+            self.resolve_replica_waits();
+            // * Leadership may be lost to a heartbeat, a vote or a rejection from a newer term
+            self.redirect_in_flight_writes();
+            self.release_handed_off_writes();
+            self.record_topology_changes();
             trace!("Cluster command processed");
         }
         Ok(self)
@@ -49,16 +77,41 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
                 self.send_rpc().await;
             },
             | StartLeaderElection => {
-                self.run_for_election().await;
+                self.on_election_timeout().await;
             },
             | RebalanceRequest { request_to, lazy_option } => {
                 self.rebalance_request(request_to, lazy_option).await;
             },
             | ScheduleMigrationBatch(tasks, callback) => {
-                self.migrate_batch(tasks, cache_manager, callback).await;
+                // * Each batch starts a trace, which the target joins while committing it
+                let span = tracing::debug_span!(
+                    "migrate_batch",
+                    batch_id = %tasks.id.0,
+                    traceparent = tracing::field::Empty
+                );
+                self.migrate_batch(tasks, cache_manager, callback).instrument(span).await;
+            },
+            | TryUnblockWriteReqs => self.unblock_write_reqs_if_done().await,
+            | ExpireMigrationBatch(batch_id) => self.expire_migration_batch(batch_id),
+            | AbandonMigrationBatch(batch_id) => self.abandon_migration_batch(batch_id).await,
+            | SendBatchAck { batch_id, to, success } => {
+                self.send_batch_ack(batch_id, to, success).await
+            },
+            | CompactLogs => self.maybe_compact_logs(cache_manager).await,
+            | SyncLogs => self.sync_logs(),
+            | ReconnectPeers => self.reconnect_peers(),
+            | CommitGroup => self.commit_group().await,
+            | AbortLeadershipTransfer(id) => self.expire_leadership_transfer(id),
+            | Depart(callback) => self.depart(callback).await,
+            | ExpireReplicaWaits => self.resolve_replica_waits(),
+            | ExpirePendingRequests => self.expire_pending_requests(),
+            | ExpireConsensusRequests => self.expire_consensus_requests(cache_manager),
+            | ExpireClientSessions => self.expire_client_sessions(),
+            | ExpireForward(id) => self.expire_forward(id),
+            | ResolveTransaction(txn_id) => self.resolve_transaction(txn_id, cache_manager).await,
+            | SendForwardResponse { to, response } => {
+                self.send_forward_response(to, response).await
             },
-            | TryUnblockWriteReqs => self.unblock_write_reqs_if_done(),
-            | SendBatchAck { batch_id, to } => self.send_batch_ack(batch_id, to).await,
         }
     }
 
@@ -71,17 +124,35 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
         use ClientMessage::*;
 
         match client_message {
-            | GetPeers(callback) => {
-                let _ = callback.send(self.members.keys().cloned().collect::<Vec<_>>());
+            | PeerSuspicion(callback) => {
+                let _ = callback.send(self.peer_suspicion());
+            },
+            | ClusterReshardStatus(callback) => {
+                let _ = callback.send(self.reshard_status());
+            },
+            | ClusterConsensus(callback) => {
+                let _ = callback.send(self.consensus_report());
+            },
+            | ClusterHistory(count, callback) => {
+                let _ = callback.send(self.topology_history.recent(count));
+            },
+            | ClusterShards(callback) => {
+                let _ = callback.send(self.cluster_shards());
             },
             | ClusterNodes(callback) => {
-                let _ = callback.send(self.cluster_nodes());
+                let _ = callback.send(self.cluster_node_links());
             },
             | ReplicationInfo(callback) => {
                 let _ = callback.send(self.replication.clone());
             },
-            | ForgetPeer(peer_addr, callback) => {
-                if let Ok(Some(())) = self.forget_peer(peer_addr).await {
+            | ReplicationLinks(callback) => {
+                let _ = callback.send(self.replication_links());
+            },
+            | SnapshotMetadata(callback) => {
+                let _ = callback.send(self.snapshot_metadata());
+            },
+            | ForgetPeer(peer_addr, permanent, callback) => {
+                if let Ok(Some(())) = self.forget_peer(peer_addr, permanent).await {
                     let _ = callback.send(Some(()));
                 } else {
                     let _ = callback.send(None);
@@ -90,6 +161,12 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             | LeaderReqConsensus(req) => {
                 self.leader_req_consensus(req).await;
             },
+            | BannedPeers(callback) => {
+                let _ = callback.send(self.banned_peers());
+            },
+            | ClearBans(peer, callback) => {
+                let _ = callback.send(self.clear_bans(peer));
+            },
             | ReplicaOf(peer_addr, callback) => {
                 if self.replication.self_identifier() == peer_addr {
                     let _ = callback.send(res_err!("invalid operation: cannot replicate to self"));
@@ -101,6 +178,47 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             | ClusterMeet(peer_addr, lazy_option, callback) => {
                 self.cluster_meet(peer_addr, lazy_option, callback).await;
             },
+            | ClusterFailover(target, callback) => {
+                self.cluster_failover(target, callback).await;
+            },
+            | ClusterLeave(callback) => self.cluster_leave(cache_manager, callback).await,
+            | PendingWriteStats(callback) => {
+                let _ = callback.send(self.pending_write_stats());
+            },
+            | ClientSessionStats(callback) => {
+                let _ = callback.send(self.client_session_stats());
+            },
+            | SetClientSessionTtl(ttl, callback) => {
+                self.set_client_session_ttl(ttl);
+                let _ = callback.send(());
+            },
+            | GetFsyncPolicy(callback) => {
+                let _ = callback.send(self.logger.target.fsync_policy());
+            },
+            | SetFsyncPolicy(policy, callback) => self.set_fsync_policy(policy, callback),
+            | GetRaftTimings(callback) => {
+                let _ = callback.send(self.heartbeat_scheduler.timings().clone());
+            },
+            | SetRaftTiming(parameter, value, callback) => {
+                self.set_raft_timing(&parameter, value, callback)
+            },
+            | VerifyWal(callback) => {
+                let _ = callback.send(self.logger.target.verify());
+            },
+            | Flush(callback) => {
+                let _ = callback.send(self.flush().await);
+            },
+            | ReadIndex(callback) => self.read_index(callback).await,
+            | Wait { index, numreplicas, timeout, callback } => {
+                self.wait_for_replicas(index, numreplicas, timeout, callback);
+            },
+            | ForwardToShard { to, op, callback } => self.forward_to_shard(to, op, callback).await,
+            | CheckReplicaStaleness(max_lag, callback) => {
+                let _ = callback.send(self.check_replica_staleness(max_lag));
+            },
+            | ClusterMigrate(selector, target, callback) => {
+                self.cluster_migrate(selector, target, cache_manager, callback).await;
+            },
             | ClusterReshard(sender) => {
                 let _ = self.start_rebalance(cache_manager).await;
                 let _ = sender.send(Ok(()));
@@ -111,6 +229,12 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
             | SubscribeToTopologyChange(callback) => {
                 let _ = callback.send(self.node_change_broadcast.subscribe());
             },
+            | SubscribeToShutdown(callback) => {
+                let _ = callback.send(self.shutdown.subscribe());
+            },
+            | SubscribeToRouting(callback) => {
+                let _ = callback.send(self.routing.subscribe());
+            },
             | GetTopology(callback) => {
                 let _ = callback.send(self.get_topology());
             },
@@ -127,23 +251,32 @@ impl<T: TWriteAheadLog> ClusterActor<T> {
 
         match peer_message {
             | ClusterHeartBeat(heartbeat) => {
-                self.receive_cluster_heartbeat(heartbeat, cache_manager).await
+                let span = joined_span!("cluster_heartbeat", heartbeat.trace);
+                self.receive_cluster_heartbeat(heartbeat, cache_manager).instrument(span).await
             },
             | RequestVote(request_vote) => self.vote_election(request_vote).await,
             | AckReplication(repl_res) => self.ack_replication(repl_res).await,
+            | TimeoutNow(timeout_now) => self.receive_timeout_now(timeout_now).await,
             | AppendEntriesRPC(heartbeat) => {
-                self.append_entries_rpc(cache_manager, heartbeat).await
+                let span = joined_span!("append_entries", heartbeat.trace);
+                self.append_entries_rpc(cache_manager, heartbeat).instrument(span).await
             },
+            | InstallSnapshot(snapshot) => self.install_snapshot(snapshot, cache_manager).await,
             | ElectionVoteReply(request_vote_reply) => {
-                self.receive_election_vote(request_vote_reply).await
+                self.receive_election_vote(request_vote_reply, cache_manager).await
             },
+            | PreVote(request_vote) => self.vote_pre_election(request_vote).await,
+            | PreVoteReply(pre_vote_reply) => self.receive_pre_vote(pre_vote_reply).await,
             | StartRebalance => self.start_rebalance(cache_manager).await,
             | ReceiveBatch(migrate_batch) => {
-                self.receive_batch(migrate_batch, cache_manager, from).await
+                let span = joined_span!("receive_batch", migrate_batch.trace);
+                self.receive_batch(migrate_batch, cache_manager, from).instrument(span).await
             },
             | MigrationBatchAck(migration_batch_ack) => {
                 self.handle_migration_ack(migration_batch_ack, cache_manager).await
             },
+            | ForwardRequest(request) => self.serve_forward(request, cache_manager, from).await,
+            | ForwardResponse(response) => self.receive_forward_response(response),
         };
     }
 
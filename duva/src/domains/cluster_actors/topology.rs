@@ -1,5 +1,10 @@
 use crate::domains::cluster_actors::hash_ring::HashRing;
+use crate::domains::cluster_actors::replication::ReplicationId;
+use crate::domains::leases::{lease_partition, partition_of};
 use crate::prelude::PeerIdentifier;
+use bytes::Bytes;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 #[derive(bincode::Encode, bincode::Decode, Debug, PartialEq, Clone, Default)]
 pub struct Topology {
@@ -7,8 +12,78 @@ pub struct Topology {
     pub hash_ring: HashRing,
 }
 
+/// Key ownership as this node last saw it. The cluster actor publishes it whenever the hash ring or
+/// the node's own partition changes, so that clients group keys without a round trip to the actor.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RoutingTable {
+    pub(crate) replid: ReplicationId,
+    pub(crate) hash_ring: Arc<HashRing>,
+}
+
+impl RoutingTable {
+    /// Positions of `keys` grouped by the partition that owns them, `None` being this node's own.
+    pub(crate) fn group_keys(&self, keys: &[Bytes]) -> BTreeMap<Option<ReplicationId>, Vec<usize>> {
+        let mut groups: BTreeMap<Option<ReplicationId>, Vec<usize>> = BTreeMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            groups.entry(self.moved_to(key)).or_default().push(i);
+        }
+        groups
+    }
+
+    /// The partition owning `key`, when it is not this node's own.
+    pub(crate) fn moved_to(&self, key: &[u8]) -> Option<ReplicationId> {
+        self.hash_ring.get_node_for_keys(&[key]).ok().filter(|replid| *replid != self.replid)
+    }
+
+    /// The partition holding the lease `id`, when it is not this node's own.
+    pub(crate) fn lease_owner(&self, id: u64) -> Option<ReplicationId> {
+        let partition = partition_of(id);
+        if partition == lease_partition(&self.replid) {
+            return None;
+        }
+        self.hash_ring.partitions().find(|replid| lease_partition(replid) == partition).cloned()
+    }
+}
+
+/// A partition of the key space with the nodes serving it, as reported by `CLUSTER SHARDS`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Shard {
+    pub replid: ReplicationId,
+    pub leader: PeerIdentifier,
+    pub replicas: Vec<PeerIdentifier>,
+    pub ranges: Vec<(u64, u64)>,
+}
+
+impl Shard {
+    /// `<replid> <leader> <replica,...|-> <start-end,...>`
+    pub fn format(&self) -> String {
+        let replicas = if self.replicas.is_empty() {
+            "-".to_string()
+        } else {
+            self.replicas.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(",")
+        };
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|(start, end)| format!("{start}-{end}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("{} {} {replicas} {ranges}", self.replid, self.leader)
+    }
+}
+
 impl Topology {
     pub fn new(connected_peers: Vec<PeerIdentifier>, hash_ring: HashRing) -> Self {
         Self { connected_peers, hash_ring }
     }
+
+    /// Leader of the partition `key` belongs to, which serves its writes.
+    pub fn leader_for_key(&self, key: &[u8]) -> Option<&PeerIdentifier> {
+        self.hash_ring.owner_of(key).and_then(|replid| self.hash_ring.get_node_id(replid))
+    }
+
+    /// Leader of the partition with the given replication id.
+    pub fn leader_of(&self, replid: &ReplicationId) -> Option<&PeerIdentifier> {
+        self.hash_ring.get_node_id(replid)
+    }
 }
@@ -0,0 +1,292 @@
+use super::replication::ReplicationId;
+use crate::domains::operation_logs::WriteRequest;
+use crate::domains::query_io::SERDE_CONFIG;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+// * How long a participant holds a prepared transaction before asking its coordinator for the outcome
+pub(crate) const TXN_RESOLVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Identifies a cross-partition transaction along with the partition coordinating it, which
+/// participants ask for the outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
+pub struct TxnId {
+    pub(crate) coordinator: ReplicationId,
+    pub(crate) id: String,
+}
+
+impl TxnId {
+    pub(crate) fn new(coordinator: ReplicationId) -> Self {
+        Self { coordinator, id: uuid::Uuid::now_v7().to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum TxnOutcome {
+    Commit,
+    Abort,
+}
+
+/// Transactions this partition takes part in or coordinates, rebuilt from the log so that a new
+/// leader picks them up where the old one left off.
+#[derive(Debug, Default)]
+pub(crate) struct Transactions {
+    prepared: HashMap<TxnId, Vec<WriteRequest>>,
+    // * Keys of prepared transactions, which other writes may not touch until the outcome is known
+    locked: HashMap<Bytes, TxnId>,
+    // * Outcomes along with the index of the entry recording them, which counts once committed
+    decisions: HashMap<TxnId, (u64, TxnOutcome)>,
+}
+
+impl Transactions {
+    /// Tracks the record appended at `log_index`.
+    pub(crate) fn track(&mut self, request: &WriteRequest, log_index: u64) {
+        match request {
+            | WriteRequest::TxnPrepare { txn_id, requests } => {
+                let keys = requests.iter().flat_map(WriteRequest::all_keys);
+                self.locked.extend(keys.map(|key| (Bytes::copy_from_slice(key), txn_id.clone())));
+                self.prepared.insert(txn_id.clone(), requests.clone());
+            },
+            | WriteRequest::TxnCommit { txn_id, .. } | WriteRequest::TxnAbort { txn_id } => {
+                self.prepared.remove(txn_id);
+                self.locked.retain(|_, holder| holder != txn_id);
+            },
+            | WriteRequest::TxnDecision { txn_id, outcome } => {
+                self.decisions.insert(txn_id.clone(), (log_index, *outcome));
+            },
+            | WriteRequest::TxnEnd { txn_id } => {
+                self.decisions.remove(txn_id);
+            },
+            | _ => {},
+        }
+    }
+
+    /// Forgets the decisions of entries past `log_index`, which were dropped from the log.
+    pub(crate) fn truncate_after(&mut self, log_index: u64) {
+        self.decisions.retain(|_, (index, _)| *index <= log_index);
+    }
+
+    /// Transaction holding a lock on one of the keys the request writes, other than its own.
+    pub(crate) fn conflict(&self, request: &WriteRequest) -> Option<&TxnId> {
+        let own = match request {
+            | WriteRequest::TxnCommit { txn_id, .. } => Some(txn_id),
+            | _ => None,
+        };
+        request
+            .all_keys()
+            .into_iter()
+            .filter_map(|key| self.locked.get(key))
+            .find(|holder| Some(*holder) != own)
+    }
+
+    pub(crate) fn prepared(&self, txn_id: &TxnId) -> Option<&Vec<WriteRequest>> {
+        self.prepared.get(txn_id)
+    }
+
+    pub(crate) fn prepared_ids(&self) -> Vec<TxnId> {
+        self.prepared.keys().cloned().collect()
+    }
+
+    /// Outcome recorded for the transaction, committed or not.
+    pub(crate) fn proposed_decision(&self, txn_id: &TxnId) -> Option<TxnOutcome> {
+        self.decisions.get(txn_id).map(|(_, outcome)| *outcome)
+    }
+
+    /// Outcome recorded for the transaction, provided the entry recording it is within the `hwm`
+    /// committed ones.
+    pub(crate) fn decision(&self, txn_id: &TxnId, hwm: u64) -> Option<TxnOutcome> {
+        match self.decisions.get(txn_id) {
+            | Some((index, outcome)) if *index <= hwm => Some(*outcome),
+            | _ => None,
+        }
+    }
+
+    /// State as of the entry at `log_index`, for a snapshot taken there.
+    pub(crate) fn snapshot(&self, log_index: u64) -> TxnStates {
+        TxnStates {
+            prepared: self.prepared.iter().map(|(id, reqs)| (id.clone(), reqs.clone())).collect(),
+            decisions: self
+                .decisions
+                .iter()
+                .filter(|(_, (index, _))| *index <= log_index)
+                .map(|(id, (_, outcome))| (id.clone(), *outcome))
+                .collect(),
+        }
+    }
+
+    /// Starts from the state of the snapshot taken at `log_index`.
+    pub(crate) fn restore(states: TxnStates, log_index: u64) -> Self {
+        let mut transactions = Self::default();
+        for (txn_id, requests) in states.prepared {
+            transactions.track(&WriteRequest::TxnPrepare { txn_id, requests }, log_index);
+        }
+        for (txn_id, outcome) in states.decisions {
+            transactions.decisions.insert(txn_id, (log_index, outcome));
+        }
+        transactions
+    }
+}
+
+/// Transactions a snapshot carries, as the entries that tracked them are compacted into it:
+/// those still prepared, and the outcomes participants may still ask for.
+#[derive(Debug, Clone, Default, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub(crate) struct TxnStates {
+    prepared: Vec<(TxnId, Vec<WriteRequest>)>,
+    decisions: Vec<(TxnId, TxnOutcome)>,
+}
+
+impl TxnStates {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.prepared.is_empty() && self.decisions.is_empty()
+    }
+}
+
+/// Snapshot form: the hex encoded states, as metadata values are text.
+impl Display for TxnStates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let encoded = bincode::encode_to_vec(self, SERDE_CONFIG).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", hex::encode(encoded))
+    }
+}
+
+impl FromStr for TxnStates {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (states, _) = bincode::decode_from_slice(&hex::decode(s)?, SERDE_CONFIG)?;
+        Ok(states)
+    }
+}
+
+/// Splits the writes of a batch by the partition owning their keys. `groups` holds the positions of
+/// the batch's keys, in `WriteRequest::all_keys` order, grouped by owner.
+pub(crate) fn partition_requests(
+    requests: Vec<WriteRequest>,
+    groups: BTreeMap<Option<ReplicationId>, Vec<usize>>,
+) -> BTreeMap<Option<ReplicationId>, Vec<WriteRequest>> {
+    let mut owners = HashMap::new();
+    for (owner, positions) in groups {
+        owners.extend(positions.into_iter().map(|i| (i, owner.clone())));
+    }
+
+    let mut partitioned: BTreeMap<Option<ReplicationId>, Vec<WriteRequest>> = BTreeMap::new();
+    let mut position = 0;
+    for request in requests {
+        match request {
+            | WriteRequest::Delete { keys } => {
+                let mut deletes: BTreeMap<Option<ReplicationId>, Vec<Bytes>> = BTreeMap::new();
+                for key in keys {
+                    deletes.entry(owners[&position].clone()).or_default().push(key);
+                    position += 1;
+                }
+                for (owner, keys) in deletes {
+                    partitioned.entry(owner).or_default().push(WriteRequest::Delete { keys });
+                }
+            },
+            | request => {
+                let owner = owners[&position].clone();
+                position += request.all_keys().len();
+                partitioned.entry(owner).or_default().push(request);
+            },
+        }
+    }
+    partitioned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(key: &str) -> WriteRequest {
+        WriteRequest::Set { key: key.to_string().into(), value: "v".into(), expires_at: None }
+    }
+
+    #[test]
+    fn prepared_keys_are_locked_until_resolved() {
+        let mut transactions = Transactions::default();
+        let txn_id = TxnId::new(ReplicationId::Key("coordinator".into()));
+        transactions.track(
+            &WriteRequest::TxnPrepare { txn_id: txn_id.clone(), requests: vec![set("a")] },
+            1,
+        );
+
+        assert_eq!(transactions.conflict(&set("a")), Some(&txn_id));
+        assert_eq!(transactions.conflict(&set("b")), None);
+        let commit = WriteRequest::TxnCommit { txn_id: txn_id.clone(), requests: vec![set("a")] };
+        assert_eq!(transactions.conflict(&commit), None);
+
+        transactions.track(&commit, 2);
+        assert_eq!(transactions.conflict(&set("a")), None);
+        assert!(transactions.prepared(&txn_id).is_none());
+    }
+
+    #[test]
+    fn decisions_count_once_committed_until_the_transaction_ends() {
+        let mut transactions = Transactions::default();
+        let txn_id = TxnId::new(ReplicationId::Key("coordinator".into()));
+        let decision =
+            WriteRequest::TxnDecision { txn_id: txn_id.clone(), outcome: TxnOutcome::Commit };
+
+        transactions.track(&decision, 3);
+        assert_eq!(transactions.decision(&txn_id, 2), None);
+        assert_eq!(transactions.proposed_decision(&txn_id), Some(TxnOutcome::Commit));
+        assert_eq!(transactions.decision(&txn_id, 3), Some(TxnOutcome::Commit));
+
+        transactions.truncate_after(2);
+        assert_eq!(transactions.proposed_decision(&txn_id), None);
+
+        transactions.track(&decision, 3);
+        transactions.track(&WriteRequest::TxnEnd { txn_id: txn_id.clone() }, 4);
+        assert_eq!(transactions.proposed_decision(&txn_id), None);
+    }
+
+    #[test]
+    fn snapshot_carries_prepared_transactions_and_committed_decisions() {
+        let mut transactions = Transactions::default();
+        let prepared = TxnId::new(ReplicationId::Key("coordinator".into()));
+        let decided = TxnId::new(ReplicationId::Key("coordinator".into()));
+        let undecided = TxnId::new(ReplicationId::Key("coordinator".into()));
+        transactions.track(
+            &WriteRequest::TxnPrepare { txn_id: prepared.clone(), requests: vec![set("a")] },
+            1,
+        );
+        transactions.track(
+            &WriteRequest::TxnDecision { txn_id: decided.clone(), outcome: TxnOutcome::Abort },
+            2,
+        );
+        transactions.track(
+            &WriteRequest::TxnDecision { txn_id: undecided.clone(), outcome: TxnOutcome::Commit },
+            3,
+        );
+
+        let states: TxnStates = transactions.snapshot(2).to_string().parse().unwrap();
+        let restored = Transactions::restore(states, 2);
+
+        assert_eq!(restored.prepared(&prepared), Some(&vec![set("a")]));
+        assert_eq!(restored.conflict(&set("a")), Some(&prepared));
+        assert_eq!(restored.decision(&decided, 2), Some(TxnOutcome::Abort));
+        assert_eq!(restored.proposed_decision(&undecided), None);
+    }
+
+    #[test]
+    fn partition_requests_splits_deletes_by_owner() {
+        let other = ReplicationId::Key("other".into());
+        let requests =
+            vec![set("a"), WriteRequest::Delete { keys: vec!["b".into(), "c".into()] }, set("d")];
+        let groups = BTreeMap::from([(None, vec![0, 2]), (Some(other.clone()), vec![1, 3])]);
+
+        let partitioned = partition_requests(requests, groups);
+
+        assert_eq!(
+            partitioned[&None],
+            vec![set("a"), WriteRequest::Delete { keys: vec!["c".into()] }]
+        );
+        assert_eq!(
+            partitioned[&Some(other)],
+            vec![WriteRequest::Delete { keys: vec!["b".into()] }, set("d")]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Block compression shared by WAL entries and the entries carried by AppendEntries.
+use anyhow::Context;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// * Payloads smaller than this are left as they are, as they barely shrink
+pub(crate) const COMPRESSION_MIN_LEN: usize = 64;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, bincode::Encode, bincode::Decode)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn is_enabled(&self) -> bool {
+        *self != Self::None
+    }
+
+    pub(crate) fn compress(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            | Self::None => data.to_vec(),
+            | Self::Lz4 => lz4_flex::block::compress_prepend_size(data),
+            // * Compressing an in-memory buffer can't fail
+            | Self::Zstd => zstd::bulk::compress(data, ZSTD_LEVEL).unwrap(),
+        }
+    }
+
+    pub(crate) fn decompress(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            | Self::None => Ok(data.to_vec()),
+            | Self::Lz4 => lz4_flex::block::decompress_size_prepended(data)
+                .context("Failed to decompress lz4 block"),
+            | Self::Zstd => zstd::decode_all(data).context("Failed to decompress zstd block"),
+        }
+    }
+
+    pub(crate) fn code(&self) -> u8 {
+        match self {
+            | Self::None => 0,
+            | Self::Lz4 => 1,
+            | Self::Zstd => 2,
+        }
+    }
+
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        match code {
+            | 0 => Some(Self::None),
+            | 1 => Some(Self::Lz4),
+            | 2 => Some(Self::Zstd),
+            | _ => None,
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            | "none" => Ok(Self::None),
+            | "lz4" => Ok(Self::Lz4),
+            | "zstd" => Ok(Self::Zstd),
+            | _ => Err(anyhow::anyhow!("invalid compression '{s}', expected none, lz4 or zstd")),
+        }
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Self::None => write!(f, "none"),
+            | Self::Lz4 => write!(f, "lz4"),
+            | Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let data = "duva".repeat(100).into_bytes();
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            let compressed = compression.compress(&data);
+            if compression.is_enabled() {
+                assert!(compressed.len() < data.len());
+            }
+            assert_eq!(compression.decompress(&compressed).unwrap(), data);
+            assert_eq!(Compression::from_code(compression.code()), Some(compression));
+        }
+    }
+
+    #[test]
+    fn test_decompress_rejects_truncated_block() {
+        let data = "duva".repeat(100).into_bytes();
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let compressed = compression.compress(&data);
+            assert!(compression.decompress(&compressed[..compressed.len() / 2]).is_err());
+        }
+    }
+}
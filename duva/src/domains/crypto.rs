@@ -0,0 +1,74 @@
+//! Hashing and message authentication shared by request signing, encryption key ids and the
+//! cluster secret handshake.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Whether `tag` is the HMAC-SHA-256 of `data` under `key`, compared in constant time so that a
+/// tag can't be guessed byte by byte.
+pub(crate) fn verify_hmac_sha256(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.verify_slice(tag).is_ok()
+}
+
+/// Whether two secrets are equal, without the time taken telling how much of them matched.
+///
+/// Both sides are MACed under the same key and the tags compared in constant time, so secrets of
+/// different lengths are compared the same way as ones of equal length.
+pub(crate) fn secrets_match(a: &[u8], b: &[u8]) -> bool {
+    verify_hmac_sha256(a, b"secret comparison", &hmac_sha256(b, b"secret comparison"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            hex::encode(sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex::encode(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(sha256(&[b'a'; 1000])),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // * RFC 4231, test case 2
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(tag),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        assert!(verify_hmac_sha256(b"Jefe", b"what do ya want for nothing?", &tag));
+        assert!(!verify_hmac_sha256(b"Jefe", b"what do ya want for nothing!", &tag));
+        assert!(!verify_hmac_sha256(b"Jefe", b"what do ya want for nothing?", &tag[1..]));
+    }
+
+    #[test]
+    fn test_secrets_match() {
+        assert!(secrets_match(b"hunter2", b"hunter2"));
+        assert!(!secrets_match(b"hunter2", b"hunter3"));
+        assert!(!secrets_match(b"hunter2", b"hunter22"));
+        assert!(!secrets_match(b"", b"hunter2"));
+    }
+}
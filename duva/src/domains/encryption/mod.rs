@@ -0,0 +1,202 @@
+//! Encryption at rest of WAL entries, snapshots and the topology file.
+//!
+//! Data is sealed with ChaCha20-Poly1305 under the first key of a `KeyRing` and records the id of
+//! that key, so that keys can be rotated: a new key goes first and the previous ones stay listed
+//! for as long as data sealed with them is around.
+use crate::domains::crypto::sha256;
+use anyhow::Context;
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const KEY_ID_LEN: usize = 4;
+// * Key id, nonce and tag added to every sealed payload
+pub(crate) const SEALED_OVERHEAD: usize = KEY_ID_LEN + NONCE_LEN + TAG_LEN;
+// * Files sealed as a whole start with it. Files without it are read as plaintext.
+const FILE_MAGIC: &[u8] = b"DUVAENC1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DecryptError {
+    #[error("data is encrypted with key {0}, which is not among the configured encryption keys")]
+    UnknownKey(String),
+    #[error("encrypted data failed authentication")]
+    Corrupted,
+}
+
+#[derive(Clone)]
+struct EncryptionKey {
+    // * First bytes of the SHA-256 of the key, which identify it without revealing it
+    id: [u8; KEY_ID_LEN],
+    cipher: ChaCha20Poly1305,
+}
+
+impl EncryptionKey {
+    fn new(key: [u8; KEY_LEN]) -> Self {
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&sha256(&key)[..KEY_ID_LEN]);
+        Self { id, cipher: ChaCha20Poly1305::new(&key.into()) }
+    }
+}
+
+/// Keys data is encrypted with. The first one seals new data, every one of them opens it.
+/// An empty ring leaves data as plaintext.
+#[derive(Clone, Default)]
+pub struct KeyRing {
+    keys: Vec<EncryptionKey>,
+}
+
+impl KeyRing {
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Id of the key new data is sealed with.
+    pub fn active_key_id(&self) -> Option<String> {
+        self.keys.first().map(|key| hex::encode(key.id))
+    }
+
+    /// Seals `plaintext` under the active key, laid out as the key id, a random nonce, the
+    /// ciphertext and its tag. The ring must be enabled.
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let active = self.keys.first().expect("sealing requires an encryption key");
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let mut sealed = Vec::with_capacity(SEALED_OVERHEAD + plaintext.len());
+        sealed.extend_from_slice(&active.id);
+        sealed.extend_from_slice(&nonce);
+        let ciphertext = active
+            .cipher
+            .encrypt(&nonce.into(), Payload { msg: plaintext, aad: &active.id })
+            .expect("sealing does not fail for payloads that fit in memory");
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    pub(crate) fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, DecryptError> {
+        if sealed.len() < SEALED_OVERHEAD {
+            return Err(DecryptError::Corrupted);
+        }
+        let (id, rest) = sealed.split_at(KEY_ID_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or_else(|| DecryptError::UnknownKey(hex::encode(id)))?;
+        key.cipher
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad: id })
+            .map_err(|_| DecryptError::Corrupted)
+    }
+
+    /// Seals the contents of a file, or leaves them as they are when the ring is empty.
+    pub(crate) fn seal_file(&self, data: Vec<u8>) -> Vec<u8> {
+        if !self.is_enabled() {
+            return data;
+        }
+        [FILE_MAGIC, &self.seal(&data)].concat()
+    }
+
+    /// Opens the contents of a file written by `seal_file`. Files written without encryption are
+    /// returned as they are, so that they stay readable once encryption is turned on.
+    pub(crate) fn open_file<'a>(&self, data: &'a [u8]) -> anyhow::Result<Cow<'a, [u8]>> {
+        let Some(sealed) = data.strip_prefix(FILE_MAGIC) else {
+            return Ok(Cow::Borrowed(data));
+        };
+        let plaintext = self.open(sealed).context("failed to decrypt file")?;
+        Ok(Cow::Owned(plaintext))
+    }
+}
+
+impl Debug for KeyRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.keys.iter().map(|key| hex::encode(key.id))).finish()
+    }
+}
+
+/// Parses comma separated keys of 32 bytes each, hex encoded, the active one first.
+impl FromStr for KeyRing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                hex::decode(key)
+                    .ok()
+                    .and_then(|key| <[u8; KEY_LEN]>::try_from(key).ok())
+                    .map(EncryptionKey::new)
+                    .context("encryption keys must be 32 bytes, hex encoded")
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const NEW_KEY: &str = "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100";
+
+    #[test]
+    fn test_rotated_ring_opens_data_of_previous_keys() {
+        let old: KeyRing = OLD_KEY.parse().unwrap();
+        let rotated: KeyRing = format!("{NEW_KEY},{OLD_KEY}").parse().unwrap();
+
+        let sealed_before = old.seal(b"before");
+        let sealed_after = rotated.seal(b"after");
+
+        assert_eq!(rotated.open(&sealed_before).unwrap(), b"before");
+        assert_eq!(rotated.open(&sealed_after).unwrap(), b"after");
+        assert_eq!(
+            old.open(&sealed_after),
+            Err(DecryptError::UnknownKey(rotated.active_key_id().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_seal_uses_fresh_nonces() {
+        let ring: KeyRing = OLD_KEY.parse().unwrap();
+        assert_ne!(ring.seal(b"same"), ring.seal(b"same"));
+    }
+
+    #[test]
+    fn test_open_rejects_tampered_data() {
+        let ring: KeyRing = OLD_KEY.parse().unwrap();
+        let mut sealed = ring.seal(b"data");
+        *sealed.last_mut().unwrap() ^= 1;
+
+        assert_eq!(ring.open(&sealed), Err(DecryptError::Corrupted));
+        assert_eq!(ring.open(&sealed[..SEALED_OVERHEAD - 1]), Err(DecryptError::Corrupted));
+    }
+
+    #[test]
+    fn test_files() {
+        let ring: KeyRing = OLD_KEY.parse().unwrap();
+
+        let sealed = ring.seal_file(b"REDIS0011".to_vec());
+        assert!(sealed.starts_with(FILE_MAGIC));
+        assert_eq!(ring.open_file(&sealed).unwrap().as_ref(), b"REDIS0011");
+        // * Plaintext files are read as they are, with or without keys
+        assert_eq!(ring.open_file(b"REDIS0011").unwrap().as_ref(), b"REDIS0011");
+        assert_eq!(KeyRing::default().seal_file(b"plain".to_vec()), b"plain");
+        assert!(KeyRing::default().open_file(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(!"".parse::<KeyRing>().unwrap().is_enabled());
+        assert!("abcd".parse::<KeyRing>().is_err());
+        assert!(format!("{OLD_KEY}zz").parse::<KeyRing>().is_err());
+        let ring: KeyRing = format!("{NEW_KEY}, {OLD_KEY}").parse().unwrap();
+        assert_eq!(format!("{ring:?}").matches(',').count(), 1);
+        assert!(!format!("{ring:?}").contains(OLD_KEY));
+    }
+}
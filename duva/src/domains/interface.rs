@@ -13,6 +13,9 @@ pub trait TRead: Send + Sync + Debug + 'static {
 #[async_trait::async_trait]
 pub(crate) trait TWrite: Send + Sync + Debug + 'static {
     async fn write(&mut self, io: QueryIO) -> Result<(), IoError>;
+
+    // * For frames serialized ahead of time
+    async fn write_bytes(&mut self, buf: &[u8]) -> Result<(), IoError>;
 }
 
 #[async_trait::async_trait]
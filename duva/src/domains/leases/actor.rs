@@ -0,0 +1,264 @@
+//! Lease manager keeping track of granted leases and the keys attached to them.
+//!
+//! Every replica applies lease operations from the replicated log, but only the leader
+//! decides that a lease has expired. The revocation is then replicated like any other write
+//! so attached keys are deleted on every node at the same log index.
+use super::command::LeaseCommand;
+use crate::make_smart_pointer;
+use anyhow::Context;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+#[derive(Debug)]
+struct Lease {
+    ttl: Duration,
+    expires_at: Instant,
+    keys: HashSet<Bytes>,
+}
+
+impl Lease {
+    fn new(ttl_millis: u64) -> Self {
+        let ttl = Duration::from_millis(ttl_millis);
+        Self { ttl, expires_at: Instant::now() + ttl, keys: HashSet::new() }
+    }
+
+    fn refresh(&mut self) {
+        self.expires_at = Instant::now() + self.ttl;
+    }
+
+    // * Instants do not outlive the process, so the deadline is stored as wall-clock time
+    fn to_state(&self, id: u64) -> LeaseState {
+        let remaining = self.expires_at.saturating_duration_since(Instant::now());
+        let mut keys: Vec<Bytes> = self.keys.iter().cloned().collect();
+        keys.sort();
+        LeaseState {
+            id,
+            ttl_millis: self.ttl.as_millis() as u64,
+            expires_at: Utc::now() + chrono::Duration::from_std(remaining).unwrap_or_default(),
+            keys,
+        }
+    }
+
+    fn from_state(state: LeaseState) -> Self {
+        let remaining = (state.expires_at - Utc::now()).to_std().unwrap_or_default();
+        Self {
+            ttl: Duration::from_millis(state.ttl_millis),
+            expires_at: Instant::now() + remaining,
+            keys: state.keys.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LeaseActor {
+    leases: HashMap<u64, Lease>,
+}
+
+impl LeaseActor {
+    pub(crate) fn run() -> LeaseCommandSender {
+        let (tx, inbox) = mpsc::channel(100);
+        tokio::spawn(Self::default().handle(inbox));
+        LeaseCommandSender(tx)
+    }
+
+    async fn handle(mut self, mut inbox: mpsc::Receiver<LeaseCommand>) {
+        while let Some(command) = inbox.recv().await {
+            match command {
+                | LeaseCommand::Grant { id, ttl_millis } => {
+                    self.leases.insert(id, Lease::new(ttl_millis));
+                },
+                | LeaseCommand::KeepAlive { id, callback } => {
+                    let _ = callback.send(self.keep_alive(id));
+                },
+                | LeaseCommand::Attach { id, keys, callback } => {
+                    let _ = callback.send(self.attach(id, keys));
+                },
+                | LeaseCommand::Revoke { id, callback } => {
+                    let _ = callback
+                        .send(self.leases.remove(&id).map(|l| l.keys.into_iter().collect()));
+                },
+                | LeaseCommand::Expired { callback } => {
+                    let _ = callback.send(self.expired());
+                },
+                | LeaseCommand::Drop { callback } => {
+                    self.leases.clear();
+                    let _ = callback.send(());
+                },
+                | LeaseCommand::Snapshot { callback } => {
+                    let _ = callback.send(self.snapshot());
+                },
+                | LeaseCommand::Restore { leases, callback } => {
+                    self.restore(leases);
+                    let _ = callback.send(());
+                },
+            }
+        }
+    }
+
+    fn keep_alive(&mut self, id: u64) -> bool {
+        let Some(lease) = self.leases.get_mut(&id) else {
+            return false;
+        };
+        lease.refresh();
+        true
+    }
+
+    fn attach(&mut self, id: u64, keys: Vec<Bytes>) -> bool {
+        let Some(lease) = self.leases.get_mut(&id) else {
+            return false;
+        };
+        lease.keys.extend(keys);
+        true
+    }
+
+    fn expired(&self) -> Vec<u64> {
+        let now = Instant::now();
+        self.leases.iter().filter(|(_, lease)| lease.expires_at <= now).map(|(id, _)| *id).collect()
+    }
+
+    fn snapshot(&self) -> LeaseStates {
+        let mut states: Vec<LeaseState> =
+            self.leases.iter().map(|(id, lease)| lease.to_state(*id)).collect();
+        states.sort_by_key(|state| state.id);
+        LeaseStates(states)
+    }
+
+    /// Replaces the leases with those of a snapshot.
+    fn restore(&mut self, leases: LeaseStates) {
+        self.leases =
+            leases.0.into_iter().map(|state| (state.id, Lease::from_state(state))).collect();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LeaseState {
+    id: u64,
+    ttl_millis: u64,
+    expires_at: DateTime<Utc>,
+    keys: Vec<Bytes>,
+}
+
+/// Leases carried in snapshots, so that attached keys are still revoked after a restart.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LeaseStates(Vec<LeaseState>);
+make_smart_pointer!(LeaseStates, Vec<LeaseState>);
+
+impl LeaseStates {
+    /// Snapshot form: `id:ttl_ms:expires_at_ms:key_count:` for each lease, followed by its keys as
+    /// `len:key`. Keys are length-prefixed as they may hold any byte.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for lease in self.iter() {
+            let header = format!(
+                "{}:{}:{}:{}:",
+                lease.id,
+                lease.ttl_millis,
+                lease.expires_at.timestamp_millis(),
+                lease.keys.len()
+            );
+            encoded.extend_from_slice(header.as_bytes());
+            for key in &lease.keys {
+                encoded.extend_from_slice(format!("{}:", key.len()).as_bytes());
+                encoded.extend_from_slice(key);
+            }
+        }
+        encoded
+    }
+
+    pub(crate) fn decode(mut data: &[u8]) -> anyhow::Result<Self> {
+        fn field<T: FromStr>(data: &mut &[u8]) -> anyhow::Result<T>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            let end = memchr::memchr(b':', data).context("truncated lease")?;
+            let value = std::str::from_utf8(&data[..end])?.parse()?;
+            *data = &data[end + 1..];
+            Ok(value)
+        }
+
+        let mut leases = Vec::new();
+        while !data.is_empty() {
+            let id = field(&mut data)?;
+            let ttl_millis = field(&mut data)?;
+            let expires_at = DateTime::from_timestamp_millis(field(&mut data)?)
+                .context("invalid lease expiry")?;
+            let key_count: usize = field(&mut data)?;
+            let mut keys = Vec::with_capacity(key_count);
+            for _ in 0..key_count {
+                let len: usize = field(&mut data)?;
+                let key = data.get(..len).context("truncated lease key")?;
+                keys.push(Bytes::copy_from_slice(key));
+                data = &data[len..];
+            }
+            leases.push(LeaseState { id, ttl_millis, expires_at, keys });
+        }
+        Ok(Self(leases))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LeaseCommandSender(pub(crate) mpsc::Sender<LeaseCommand>);
+
+make_smart_pointer!(LeaseCommandSender, mpsc::Sender<LeaseCommand>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_returns_only_leases_past_their_ttl() {
+        // GIVEN
+        let mut actor = LeaseActor::default();
+        actor.leases.insert(1, Lease::new(0));
+        actor.leases.insert(2, Lease::new(60_000));
+
+        // WHEN
+        let expired = actor.expired();
+
+        // THEN
+        assert_eq!(expired, vec![1]);
+    }
+
+    #[test]
+    fn test_keep_alive_and_attach_require_existing_lease() {
+        // GIVEN
+        let mut actor = LeaseActor::default();
+        actor.leases.insert(1, Lease::new(60_000));
+
+        // WHEN & THEN
+        assert!(actor.attach(1, vec!["a".into(), "b".into()]));
+        assert!(!actor.attach(2, vec!["c".into()]));
+        assert!(actor.keep_alive(1));
+        assert!(!actor.keep_alive(2));
+        assert!(actor.expired().is_empty());
+        assert_eq!(actor.leases[&1].keys.len(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_restore() {
+        // GIVEN
+        let mut actor = LeaseActor::default();
+        actor.leases.insert(1, Lease::new(0));
+        actor.leases.insert(2, Lease::new(60_000));
+        actor.attach(
+            2,
+            vec!["a:b".into(), "1:x,y".into(), "".into(), Bytes::from_static(b"\xff:\x00")],
+        );
+
+        // WHEN
+        let encoded = actor.snapshot().encode();
+        let mut restored = LeaseActor::default();
+        restored.restore(LeaseStates::decode(&encoded).unwrap());
+
+        // THEN
+        assert_eq!(restored.expired(), vec![1]);
+        assert_eq!(restored.leases[&2].ttl, Duration::from_millis(60_000));
+        assert_eq!(restored.leases[&2].keys, actor.leases[&2].keys);
+        assert!(restored.leases[&2].expires_at > Instant::now() + Duration::from_secs(50));
+    }
+}
@@ -0,0 +1,14 @@
+use super::actor::LeaseStates;
+use bytes::Bytes;
+use tokio::sync::oneshot;
+
+pub(crate) enum LeaseCommand {
+    Grant { id: u64, ttl_millis: u64 },
+    KeepAlive { id: u64, callback: oneshot::Sender<bool> },
+    Attach { id: u64, keys: Vec<Bytes>, callback: oneshot::Sender<bool> },
+    Revoke { id: u64, callback: oneshot::Sender<Option<Vec<Bytes>>> },
+    Expired { callback: oneshot::Sender<Vec<u64>> },
+    Drop { callback: oneshot::Sender<()> },
+    Snapshot { callback: oneshot::Sender<LeaseStates> },
+    Restore { leases: LeaseStates, callback: oneshot::Sender<()> },
+}
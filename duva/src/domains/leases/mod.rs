@@ -0,0 +1,42 @@
+pub mod actor;
+pub mod command;
+
+use crate::domains::cluster_actors::hash_ring::fnv_1a_hash;
+use crate::domains::cluster_actors::replication::ReplicationId;
+
+// * Log indexes of different partitions overlap, so a lease id keeps the granting partition's tag
+// * in its high bits and the log index it was granted at in the rest
+const INDEX_BITS: u32 = 48;
+
+/// Tag standing for the partition `replid` in the ids of the leases it grants.
+pub(crate) fn lease_partition(replid: &ReplicationId) -> u16 {
+    // * The sign bit is left clear so that ids are still replied as integers
+    (fnv_1a_hash(&replid.to_string()) >> (INDEX_BITS + 1)) as u16
+}
+
+/// Id of the lease granted by `partition` at log index `index`.
+pub(crate) fn lease_id(partition: u16, index: u64) -> u64 {
+    ((partition as u64) << INDEX_BITS) | (index & ((1 << INDEX_BITS) - 1))
+}
+
+/// Tag of the partition that granted the lease `id`, which is the only one holding it.
+pub(crate) fn partition_of(id: u64) -> u16 {
+    (id >> INDEX_BITS) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease_ids_of_partitions_do_not_collide() {
+        let a = lease_partition(&ReplicationId::Key("partition-a".into()));
+        let b = lease_partition(&ReplicationId::Key("partition-b".into()));
+        assert_ne!(a, b);
+        assert!(lease_id(a, u64::MAX) <= i64::MAX as u64);
+
+        assert_ne!(lease_id(a, 7), lease_id(b, 7));
+        assert_eq!(partition_of(lease_id(a, 7)), a);
+        assert_eq!(partition_of(lease_id(b, 7)), b);
+    }
+}
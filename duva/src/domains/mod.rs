@@ -1,11 +1,17 @@
+pub mod backups;
 pub mod caches;
 pub mod cluster_actors;
+pub mod compression;
+pub(crate) mod crypto;
+pub mod encryption;
 pub mod operation_logs;
 
 pub mod error;
+pub mod leases;
 pub mod peers;
 
 pub mod saves;
+pub mod telemetry;
 pub use error::IoError;
 pub mod interface;
 pub use interface::*;
@@ -1,5 +1,103 @@
 use super::WriteOperation;
 use anyhow::Result;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// When appended logs are forced to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// Every append is synced before it returns. Logs appended together share a single sync.
+    #[default]
+    Always,
+    /// Appends are synced by a background flusher once per second.
+    EverySec,
+    /// Syncing is left to the operating system.
+    No,
+}
+
+impl FromStr for FsyncPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            | "always" => Ok(Self::Always),
+            | "everysec" => Ok(Self::EverySec),
+            | "no" => Ok(Self::No),
+            | _ => {
+                Err(anyhow::anyhow!("invalid fsync policy '{s}', expected always, everysec or no"))
+            },
+        }
+    }
+}
+
+impl Display for FsyncPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Self::Always => write!(f, "always"),
+            | Self::EverySec => write!(f, "everysec"),
+            | Self::No => write!(f, "no"),
+        }
+    }
+}
+
+/// What checking the log as it is stored found, up to its first inconsistency.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalVerification {
+    pub segments: usize,
+    pub entries: u64,
+    // * Index and term of the last entry checked
+    pub last: Option<(u64, u64)>,
+    pub inconsistency: Option<String>,
+}
+
+impl WalVerification {
+    /// Checks that `op`, found at `location`, follows the entries checked so far: its log index
+    /// has to be higher and its term no lower. Returns false once an inconsistency is found.
+    pub fn check(&mut self, location: impl Display, op: &WriteOperation) -> bool {
+        if let Some((index, term)) = self.last {
+            if op.log_index <= index {
+                self.fail(format!(
+                    "{location}: log index {} does not follow log index {index}",
+                    op.log_index
+                ));
+                return false;
+            }
+            if op.term < term {
+                self.fail(format!(
+                    "{location}: term {} of log index {} is lower than term {term} of log index {index}",
+                    op.term, op.log_index
+                ));
+                return false;
+            }
+        }
+        self.entries += 1;
+        self.last = Some((op.log_index, op.term));
+        true
+    }
+
+    pub fn fail(&mut self, inconsistency: String) {
+        self.inconsistency.get_or_insert(inconsistency);
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistency.is_none()
+    }
+}
+
+impl Display for WalVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.is_consistent() { "ok" } else { "inconsistent" };
+        let (last_index, last_term) = self.last.unwrap_or_default();
+        write!(
+            f,
+            "wal_status:{status}\r\nwal_segments:{}\r\nwal_entries:{}\r\nwal_last_index:{last_index}\r\nwal_last_term:{last_term}\r\nwal_inconsistency:{}",
+            self.segments,
+            self.entries,
+            self.inconsistency.as_deref().unwrap_or("")
+        )
+    }
+}
 
 /// Trait for a write-ahead log (WAL) abstraction.
 pub trait TWriteAheadLog: Send + Sync + 'static {
@@ -22,6 +120,12 @@ pub trait TWriteAheadLog: Send + Sync + 'static {
     /// Forces pending writes to be physically recorded on disk.
     fn fsync(&mut self) -> Result<()>;
 
+    /// Policy deciding when appends are forced to disk.
+    fn fsync_policy(&self) -> FsyncPolicy;
+
+    /// Switches the fsync policy. Pending writes are synced when switching to `FsyncPolicy::Always`.
+    fn set_fsync_policy(&mut self, policy: FsyncPolicy) -> Result<()>;
+
     /// Replicate all logs from the leader. This is intended to only be called from a follower.
     fn follower_full_sync(&mut self, ops: Vec<WriteOperation>) -> Result<()>;
 
@@ -36,4 +140,15 @@ pub trait TWriteAheadLog: Send + Sync + 'static {
 
     /// Truncate logs that are positioned after `log_index`.
     fn truncate_after(&mut self, log_index: u64);
+
+    /// Log index ranges of the sealed segments, oldest first. Logs that are not split into segments have none.
+    fn sealed_segments(&self) -> Vec<RangeInclusive<u64>>;
+
+    /// Discard logs that are positioned at or before `log_index` as they are covered by a snapshot.
+    /// Implementations may keep some of those logs when they cannot be dropped individually.
+    fn compact_until(&mut self, log_index: u64) -> Result<()>;
+
+    /// Reads the log back as it is stored, checking every entry in turn, and reports the first
+    /// inconsistency found. The log is left as it is.
+    fn verify(&self) -> WalVerification;
 }
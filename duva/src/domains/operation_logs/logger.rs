@@ -1,6 +1,9 @@
 use crate::domains::cluster_actors::SessionRequest;
 
 use super::{WriteOperation, WriteRequest, interfaces::TWriteAheadLog};
+use anyhow::bail;
+use chrono::Utc;
+use std::ops::RangeInclusive;
 use tracing::debug;
 
 #[derive(Debug)]
@@ -8,13 +11,30 @@ pub(crate) struct ReplicatedLogs<T> {
     pub(crate) target: T,
     pub(crate) last_log_index: u64,
     pub(crate) last_log_term: u64,
+    // * State machine snapshot that replaces every entry up to `last_included_index`
+    pub(crate) snapshot: Option<LogSnapshot>,
 }
 impl<T> ReplicatedLogs<T> {
     pub fn new(target: T, last_log_index: u64, last_log_term: u64) -> Self {
-        Self { target, last_log_index, last_log_term }
+        Self { target, last_log_index, last_log_term, snapshot: None }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogSnapshot {
+    pub(crate) last_included_index: u64,
+    pub(crate) last_included_term: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Point in the log a node is recovered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryTarget {
+    LogIndex(u64),
+    /// Unix time in milliseconds. The log is cut after the last entry appended at or before it.
+    Time(u64),
+}
+
 impl<T: TWriteAheadLog> ReplicatedLogs<T> {
     pub(crate) fn list_append_log_entries(
         &self,
@@ -31,26 +51,44 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
         logs
     }
 
+    #[cfg(test)]
     pub(crate) fn write_single_entry(
         &mut self,
         req: &WriteRequest,
         current_term: u64,
         session_req: Option<SessionRequest>,
     ) -> anyhow::Result<()> {
-        let op = WriteOperation {
-            request: req.clone(),
-            log_index: (self.last_log_index + 1),
-            term: current_term,
-            session_req,
-        };
+        self.write_entries([(req.clone(), session_req)], current_term)?;
+        Ok(())
+    }
 
-        self.target.append(op)?;
-        self.last_log_index += 1;
+    /// Appends the requests as consecutive entries with a single write to the log, returning the index of the first.
+    pub(crate) fn write_entries(
+        &mut self,
+        reqs: impl IntoIterator<Item = (WriteRequest, Option<SessionRequest>)>,
+        current_term: u64,
+    ) -> anyhow::Result<u64> {
+        let first_index = self.last_log_index + 1;
+        let timestamp = Utc::now().timestamp_millis() as u64;
+        let ops: Vec<WriteOperation> = reqs
+            .into_iter()
+            .zip(first_index..)
+            .map(|((request, session_req), log_index)| WriteOperation {
+                request,
+                log_index,
+                term: current_term,
+                timestamp,
+                session_req,
+            })
+            .collect();
+        let Some(last_index) = ops.last().map(|op| op.log_index) else {
+            return Ok(first_index);
+        };
 
-        // ! Last log term must be updated because
-        // ! log consistency check is based on previous log term and index
+        self.target.append_many(ops)?;
+        self.last_log_index = last_index;
         self.last_log_term = current_term;
-        Ok(())
+        Ok(first_index)
     }
 
     // FOLLOWER side operation
@@ -78,7 +116,7 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
     }
 
     fn from(&self, start_exclusive: u64) -> Vec<WriteOperation> {
-        self.target.range(start_exclusive, self.last_log_index)
+        self.range(start_exclusive, self.last_log_index)
     }
 
     pub(crate) fn read_at(&self, at: u64) -> Option<WriteOperation> {
@@ -93,6 +131,116 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
         self.target.truncate_after(log_index);
     }
 
+    /// Drops every entry covered by the given snapshot, except for the last `retained_segments`
+    /// sealed segments it covers. Entries after the snapshot are kept.
+    pub(crate) fn compact(
+        &mut self,
+        snapshot: LogSnapshot,
+        retained_segments: usize,
+    ) -> anyhow::Result<()> {
+        self.target.compact_until(self.retention_point(&snapshot, retained_segments))?;
+        self.snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    // FOLLOWER side operation
+    /// Replaces the log with a snapshot received from the leader.
+    /// If the log already holds the snapshot's last entry, the entries following it are retained.
+    pub(crate) fn install_snapshot(
+        &mut self,
+        snapshot: LogSnapshot,
+        retained_segments: usize,
+    ) -> anyhow::Result<()> {
+        let matches_tail = self
+            .read_at(snapshot.last_included_index)
+            .is_some_and(|op| op.term == snapshot.last_included_term);
+
+        if matches_tail {
+            self.target.compact_until(self.retention_point(&snapshot, retained_segments))?;
+        } else {
+            self.follower_full_sync(vec![])?;
+            self.last_log_index = snapshot.last_included_index;
+            self.last_log_term = snapshot.last_included_term;
+        }
+        self.snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Log index ranges of the sealed segments the log is split into, oldest first.
+    pub(crate) fn segment_boundaries(&self) -> Vec<RangeInclusive<u64>> {
+        self.target.sealed_segments()
+    }
+
+    // * Entries up to the returned index may go. Segments are dropped whole, so the boundary of the
+    // * newest segment that is covered by the snapshot and not retained is used when the log has any.
+    fn retention_point(&self, snapshot: &LogSnapshot, retained_segments: usize) -> u64 {
+        let boundaries = self.segment_boundaries();
+        if boundaries.is_empty() {
+            return snapshot.last_included_index;
+        }
+        let covered: Vec<u64> = boundaries
+            .iter()
+            .map(|segment| *segment.end())
+            .filter(|end| *end <= snapshot.last_included_index)
+            .collect();
+        covered
+            .len()
+            .checked_sub(retained_segments + 1)
+            .map(|newest_dropped| covered[newest_dropped])
+            .unwrap_or(0)
+    }
+
+    /// Cuts the log back to `target`, dropping every entry past it. Returns the entries between the
+    /// snapshot the log starts from and `target`, which are to be replayed on top of the snapshot.
+    pub(crate) fn recover_to(
+        &mut self,
+        target: RecoveryTarget,
+    ) -> anyhow::Result<Vec<WriteOperation>> {
+        let snapshot_index = self.last_log_index;
+        let mut entries = self.target.range(snapshot_index, u64::MAX);
+        if entries.first().is_some_and(|op| op.log_index != snapshot_index + 1) {
+            bail!("the log has no entry following the snapshot at log index {snapshot_index}");
+        }
+        let last_index = entries.last().map(|op| op.log_index).unwrap_or(snapshot_index);
+
+        let cut_off = match target {
+            | RecoveryTarget::LogIndex(index) if index < snapshot_index => {
+                bail!("log index {index} is before the snapshot at log index {snapshot_index}")
+            },
+            | RecoveryTarget::LogIndex(index) if index > last_index => {
+                bail!("log index {index} is past the end of the log at log index {last_index}")
+            },
+            | RecoveryTarget::LogIndex(index) => index,
+            | RecoveryTarget::Time(time) => {
+                if self.read_at(snapshot_index).is_some_and(|op| op.timestamp > time) {
+                    bail!("the snapshot at log index {snapshot_index} is newer than {time}")
+                }
+                entries
+                    .iter()
+                    .take_while(|op| op.timestamp <= time)
+                    .last()
+                    .map_or(snapshot_index, |op| op.log_index)
+            },
+        };
+
+        entries.retain(|op| op.log_index <= cut_off);
+        self.truncate_after(cut_off);
+        self.update_metadata(&entries);
+        Ok(entries)
+    }
+
+    pub(crate) fn snapshot_index(&self) -> u64 {
+        self.snapshot.as_ref().map(|s| s.last_included_index).unwrap_or(0)
+    }
+
+    /// Entries past `last_log_index`, where the snapshot the node starts from ends. The end of the
+    /// log is moved past them, as they are replayed on top of the snapshot.
+    pub(crate) fn entries_after_snapshot(&mut self) -> Vec<WriteOperation> {
+        let entries = self.target.range(self.last_log_index, u64::MAX);
+        self.update_metadata(&entries);
+        entries
+    }
+
     fn update_metadata(&mut self, new_entries: &[WriteOperation]) {
         if new_entries.is_empty() {
             return;
@@ -105,6 +253,7 @@ impl<T: TWriteAheadLog> ReplicatedLogs<T> {
     pub(crate) fn reset(&mut self) {
         self.last_log_index = 0;
         self.last_log_term = 0;
+        self.snapshot = None;
         self.truncate_after(0);
     }
 }
@@ -1,6 +1,7 @@
 pub mod interfaces;
 pub mod logger;
 pub mod operation;
+pub mod replay;
 
 pub(crate) use operation::WriteOperation;
 pub(crate) use operation::WriteRequest;
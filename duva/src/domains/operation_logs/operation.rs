@@ -1,5 +1,11 @@
 use crate::domains::{
-    QueryIO, caches::cache_objects::CacheEntry, cluster_actors::SessionRequest, deserialize,
+    QueryIO,
+    caches::cache_objects::CacheEntry,
+    cluster_actors::{
+        SessionRequest,
+        hash_ring::HashRing,
+        transactions::{TxnId, TxnOutcome},
+    },
 };
 use bytes::Bytes;
 
@@ -9,18 +15,126 @@ pub struct WriteOperation {
     pub(crate) log_index: u64,
     pub(crate) term: u64,
     pub(crate) session_req: Option<SessionRequest>,
+    // * Unix time in milliseconds at which the leader appended the entry, 0 for entries older than it
+    pub(crate) timestamp: u64,
 }
 
 /// Operations that appear in the Append-Only File (WAL).
 /// Client request is converted to WriteOperation and then it turns into WriteOp when it gets offset
 #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
 pub enum WriteRequest {
-    Set { key: String, value: String, expires_at: Option<u64> },
-    MSet { entries: Vec<CacheEntry> },
-    Delete { keys: Vec<String> },
-    Append { key: String, value: String },
-    Decr { key: String, delta: i64 },
-    Incr { key: String, delta: i64 },
+    Set {
+        // * Keys and values are binary-safe and encode as length-prefixed byte strings, as before
+        #[bincode(with_serde)]
+        key: Bytes,
+        #[bincode(with_serde)]
+        value: Bytes,
+        expires_at: Option<u64>,
+    },
+    MSet {
+        entries: Vec<CacheEntry>,
+    },
+    Delete {
+        #[bincode(with_serde)]
+        keys: Vec<Bytes>,
+    },
+    Append {
+        #[bincode(with_serde)]
+        key: Bytes,
+        #[bincode(with_serde)]
+        value: Bytes,
+    },
+    Decr {
+        #[bincode(with_serde)]
+        key: Bytes,
+        delta: i64,
+    },
+    Incr {
+        #[bincode(with_serde)]
+        key: Bytes,
+        delta: i64,
+    },
+    Cas {
+        #[bincode(with_serde)]
+        key: Bytes,
+        #[bincode(with_serde)]
+        expected: Bytes,
+        #[bincode(with_serde)]
+        value: Bytes,
+    },
+    Lock {
+        #[bincode(with_serde)]
+        key: Bytes,
+        expires_at: u64,
+        // * Unix time in milliseconds the lock was asked for at, which grants are judged against
+        proposed_at: u64,
+    },
+    Unlock {
+        #[bincode(with_serde)]
+        key: Bytes,
+        token: u64,
+    },
+    /// Recreates a key from a `DUMP` payload. Unless `replace` is set, an existing key is left as is.
+    Restore {
+        entry: CacheEntry,
+        replace: bool,
+    },
+    /// Grants a lease with an id made of `partition` and the entry's log index.
+    LeaseGrant {
+        ttl_millis: u64,
+        partition: u16,
+    },
+    LeaseKeepAlive {
+        id: u64,
+    },
+    LeaseAttach {
+        id: u64,
+        #[bincode(with_serde)]
+        keys: Vec<Bytes>,
+    },
+    LeaseRevoke {
+        id: u64,
+    },
+    /// Several writes committed as a single log entry. Only `Set` and `Delete` may be batched.
+    Batch {
+        requests: Vec<WriteRequest>,
+    },
+    /// Appended by a newly elected leader so that entries from previous terms get committed.
+    NoOp,
+    /// Checkpoint of a migration towards `ring`, letting the next leader resume it after a failover.
+    MigrationStart {
+        ring: Box<HashRing>,
+    },
+    /// Closes the migration opened by the last `MigrationStart`.
+    MigrationEnd,
+    /// Writes staged by a cross-partition transaction. Their keys stay locked until the outcome is known.
+    TxnPrepare {
+        txn_id: TxnId,
+        requests: Vec<WriteRequest>,
+    },
+    /// Applies the writes the transaction prepared on this partition.
+    TxnCommit {
+        txn_id: TxnId,
+        requests: Vec<WriteRequest>,
+    },
+    TxnAbort {
+        txn_id: TxnId,
+    },
+    /// Outcome recorded by the coordinating partition before any participant is told to commit.
+    TxnDecision {
+        txn_id: TxnId,
+        outcome: TxnOutcome,
+    },
+    /// Appended by the coordinating partition once every participant applied the outcome, which
+    /// no one is left to ask for.
+    TxnEnd {
+        txn_id: TxnId,
+    },
+    /// Deletes the keys like `Delete`, leaving large values to be freed in the background.
+    Unlink {
+        #[bincode(with_serde)]
+        keys: Vec<Bytes>,
+    },
 }
 
 impl WriteOperation {
@@ -30,32 +144,36 @@ impl WriteOperation {
 }
 
 impl WriteRequest {
-    /// Deserialize `WriteOperation`s from the given bytes.
-    pub(crate) fn deserialize(bytes: impl Into<Bytes>) -> anyhow::Result<Vec<WriteOperation>> {
-        let mut ops: Vec<WriteOperation> = Vec::new();
-        let mut bytes = bytes.into();
-
-        while !bytes.is_empty() {
-            let (query, consumed) = deserialize(bytes.clone())?;
-            bytes = bytes.split_off(consumed);
-
-            let QueryIO::WriteOperation(write_operation) = query else {
-                return Err(anyhow::anyhow!("expected replicate"));
-            };
-            ops.push(write_operation);
-        }
-        Ok(ops)
-    }
-
     /// Returns all keys involved in the operation.
-    pub(crate) fn all_keys(&self) -> Vec<&str> {
+    pub(crate) fn all_keys(&self) -> Vec<&[u8]> {
         match self {
             | WriteRequest::Set { key, .. } => vec![key],
             | WriteRequest::Append { key, .. } => vec![key],
             | WriteRequest::Incr { key, .. } => vec![key],
             | WriteRequest::Decr { key, .. } => vec![key],
-            | WriteRequest::Delete { keys, .. } => keys.iter().map(|k| k.as_str()).collect(),
+            | WriteRequest::Cas { key, .. } => vec![key],
+            | WriteRequest::Lock { key, .. } => vec![key],
+            | WriteRequest::Unlock { key, .. } => vec![key],
+            | WriteRequest::Restore { entry, .. } => vec![entry.key()],
+            | WriteRequest::LeaseAttach { keys, .. } => keys.iter().map(|k| &k[..]).collect(),
+            | WriteRequest::LeaseGrant { .. }
+            | WriteRequest::LeaseKeepAlive { .. }
+            | WriteRequest::LeaseRevoke { .. }
+            | WriteRequest::NoOp
+            | WriteRequest::MigrationStart { .. }
+            | WriteRequest::MigrationEnd
+            | WriteRequest::TxnAbort { .. }
+            | WriteRequest::TxnDecision { .. }
+            | WriteRequest::TxnEnd { .. } => vec![],
+            | WriteRequest::Delete { keys, .. } | WriteRequest::Unlink { keys } => {
+                keys.iter().map(|k| &k[..]).collect()
+            },
             | WriteRequest::MSet { entries } => entries.iter().map(|e| e.key()).collect(),
+            | WriteRequest::Batch { requests }
+            | WriteRequest::TxnPrepare { requests, .. }
+            | WriteRequest::TxnCommit { requests, .. } => {
+                requests.iter().flat_map(WriteRequest::all_keys).collect()
+            },
         }
     }
 }
@@ -0,0 +1,45 @@
+//! Replay of the logs that follow the snapshot, when a node starts.
+use super::WriteOperation;
+use crate::domains::caches::cache_manager::CacheManager;
+use std::time::Instant;
+use tracing::info;
+
+// * Logs applied between two progress reports
+const REPLAY_BATCH_SIZE: usize = 10_000;
+
+/// Outcome of the replay, reported through `INFO persistence`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalReplayStats {
+    pub entries: usize,
+    pub millis: u64,
+}
+
+impl WalReplayStats {
+    pub(crate) fn vectorize(&self) -> Vec<String> {
+        vec![
+            format!("wal_replayed_entries:{}", self.entries),
+            format!("wal_replay_time_ms:{}", self.millis),
+        ]
+    }
+}
+
+/// Applies `logs` to the cache batch by batch, logging the progress made after each batch.
+pub(crate) async fn replay(
+    cache_manager: &CacheManager,
+    logs: Vec<WriteOperation>,
+) -> WalReplayStats {
+    let started_at = Instant::now();
+    let total = logs.len();
+    let mut replayed = 0;
+    let mut logs = logs.into_iter();
+    loop {
+        let batch = logs.by_ref().take(REPLAY_BATCH_SIZE).collect::<Vec<_>>();
+        if batch.is_empty() {
+            break;
+        }
+        replayed += batch.len();
+        cache_manager.apply_logs(batch).await;
+        info!("Replayed {replayed}/{total} WAL entries in {}ms", started_at.elapsed().as_millis());
+    }
+    WalReplayStats { entries: total, millis: started_at.elapsed().as_millis() as u64 }
+}
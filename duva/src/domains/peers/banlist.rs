@@ -0,0 +1,210 @@
+use super::command::BannedPeer;
+use crate::domains::encryption::KeyRing;
+use crate::prelude::PeerIdentifier;
+use std::collections::HashSet;
+
+// * Seconds a ban lasts unless configured otherwise
+pub(crate) const DEFAULT_BAN_DURATION: u64 = 60;
+// * Seconds a ban is still gossiped once it ran out or was lifted, so that it replaces the older
+// * ban on every node rather than being brought back by the nodes that still hold it
+const BAN_RETENTION: u64 = 60;
+
+/// Peers this node refuses to talk to. The list travels with heartbeats, so a ban issued on one node
+/// spreads over the cluster, where the most recently issued ban of a peer wins, and is saved with the
+/// topology so that it outlasts restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BanList {
+    bans: HashSet<BannedPeer>,
+    // * Seconds the bans issued by this node last, None for permanent ones
+    duration: Option<u64>,
+}
+
+impl Default for BanList {
+    fn default() -> Self {
+        Self { bans: HashSet::new(), duration: Some(DEFAULT_BAN_DURATION) }
+    }
+}
+
+impl BanList {
+    /// Bans issued by this node last `duration` seconds; 0 makes them permanent.
+    pub(crate) fn with_duration(mut self, duration: u64) -> Self {
+        self.duration = (duration > 0).then_some(duration);
+        self
+    }
+
+    pub(crate) fn ban(&mut self, p_id: PeerIdentifier, now: u64) {
+        self.bans.replace(BannedPeer { p_id, ban_time: now, duration: self.duration });
+    }
+
+    pub(crate) fn ban_permanently(&mut self, p_id: PeerIdentifier, now: u64) {
+        self.bans.replace(BannedPeer { p_id, ban_time: now, duration: None });
+    }
+
+    /// Lifts the ban of `p_id`, or of every peer, returning how many were lifted. The lifted bans
+    /// are kept as bans that already ran out, so that gossip lifts them on the other nodes too.
+    pub(crate) fn lift(&mut self, p_id: Option<&PeerIdentifier>, now: u64) -> usize {
+        let lifted: Vec<_> = self
+            .active(now)
+            .filter(|banned| p_id.is_none_or(|p_id| banned.p_id == *p_id))
+            .map(|banned| BannedPeer {
+                p_id: banned.p_id.clone(),
+                ban_time: now,
+                duration: Some(0),
+            })
+            .collect();
+        let count = lifted.len();
+        lifted.into_iter().for_each(|banned| {
+            self.bans.replace(banned);
+        });
+        count
+    }
+
+    /// Takes in bans gossiped by a peer, returning whether any was new to this node.
+    pub(crate) fn merge(&mut self, gossiped: Vec<BannedPeer>) -> bool {
+        let mut changed = false;
+        for banned in gossiped {
+            if self.bans.get(&banned).is_none_or(|existing| banned.ban_time > existing.ban_time) {
+                self.bans.replace(banned);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Drops bans that ran out longer than the retention ago.
+    pub(crate) fn expire(&mut self, now: u64) {
+        self.bans.retain(|banned| {
+            banned.is_active(now) || now.saturating_sub(banned.ban_time) < BAN_RETENTION
+        });
+    }
+
+    pub(crate) fn forget(&mut self, p_id: &PeerIdentifier) {
+        self.bans.remove(p_id);
+    }
+
+    pub(crate) fn is_banned(&self, p_id: &PeerIdentifier, now: u64) -> bool {
+        self.bans.get(p_id).is_some_and(|banned| banned.is_active(now))
+    }
+
+    pub(crate) fn active(&self, now: u64) -> impl Iterator<Item = &BannedPeer> {
+        self.bans.iter().filter(move |banned| banned.is_active(now))
+    }
+
+    /// Every ban still gossiped, lifted ones included.
+    pub(crate) fn to_vec(&self) -> Vec<BannedPeer> {
+        self.bans.iter().cloned().collect()
+    }
+
+    /// Lines the active bans take in the topology file: `banned <host:port> <ban_time> <seconds | permanent>`.
+    pub(crate) fn lines(&self, now: u64) -> Vec<String> {
+        self.active(now)
+            .map(|banned| {
+                let duration = banned.duration.map_or("permanent".to_string(), |d| d.to_string());
+                format!("banned {} {} {duration}", banned.p_id, banned.ban_time)
+            })
+            .collect()
+    }
+
+    /// Bans saved in the topology file by an earlier run, taken in as long as they have not run out.
+    pub(crate) fn from_file(path: &str, keys: &KeyRing) -> Self {
+        let Some(contents) = std::fs::read(path)
+            .ok()
+            .and_then(|contents| Some(keys.open_file(&contents).ok()?.into_owned()))
+            .and_then(|contents| String::from_utf8(contents).ok())
+        else {
+            return Self::default();
+        };
+        let bans = contents.lines().filter_map(Self::parse_line).collect();
+        Self { bans, ..Self::default() }
+    }
+
+    fn parse_line(line: &str) -> Option<BannedPeer> {
+        let ["banned", p_id, ban_time, duration] = line.split_whitespace().collect::<Vec<_>>()[..]
+        else {
+            return None;
+        };
+        let duration = match duration {
+            | "permanent" => None,
+            | seconds => Some(seconds.parse().ok()?),
+        };
+        Some(BannedPeer {
+            p_id: PeerIdentifier(p_id.to_string()),
+            ban_time: ban_time.parse().ok()?,
+            duration,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> PeerIdentifier {
+        PeerIdentifier(format!("127.0.0.1:{port}"))
+    }
+
+    #[test]
+    fn test_bans_run_out_unless_permanent() {
+        // GIVEN
+        let mut banlist = BanList::default().with_duration(30);
+
+        // WHEN
+        banlist.ban(peer(6379), 1000);
+        banlist.ban_permanently(peer(6380), 1000);
+
+        // THEN
+        assert!(banlist.is_banned(&peer(6379), 1029));
+        assert!(!banlist.is_banned(&peer(6379), 1030));
+        assert!(banlist.is_banned(&peer(6380), 1_000_000));
+    }
+
+    #[test]
+    fn test_lifted_ban_replaces_older_gossiped_ban() {
+        // GIVEN
+        let mut banlist = BanList::default();
+        banlist.ban_permanently(peer(6379), 1000);
+        let gossiped_earlier = banlist.to_vec();
+
+        // WHEN
+        assert_eq!(banlist.lift(None, 1010), 1);
+        let changed = banlist.merge(gossiped_earlier);
+
+        // THEN - the older ban does not come back, and the lift outlives it in gossip
+        assert!(!changed);
+        assert!(!banlist.is_banned(&peer(6379), 1010));
+        let mut other_node = BanList::default();
+        other_node.ban_permanently(peer(6379), 1000);
+        assert!(other_node.merge(banlist.to_vec()));
+        assert!(!other_node.is_banned(&peer(6379), 1010));
+
+        // WHEN the retention passes
+        banlist.expire(1010 + BAN_RETENTION);
+
+        // THEN
+        assert!(banlist.to_vec().is_empty());
+    }
+
+    #[test]
+    fn test_topology_file_lines_round_trip() {
+        // GIVEN
+        let mut banlist = BanList::default().with_duration(120);
+        banlist.ban(peer(6379), 1000);
+        banlist.ban_permanently(peer(6380), 1000);
+        let path = std::env::temp_dir().join(format!("banlist-{}.tp", uuid::Uuid::now_v7()));
+        let contents = ["127.0.0.1:6381 myself,replid 0 0 leader".to_string()]
+            .into_iter()
+            .chain(banlist.lines(1000))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        std::fs::write(&path, contents).unwrap();
+
+        // WHEN
+        let restored = BanList::from_file(path.to_str().unwrap(), &KeyRing::default());
+
+        // THEN
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.bans, banlist.bans);
+        assert!(restored.is_banned(&peer(6379), 1100));
+        assert!(restored.is_banned(&peer(6380), 1_000_000));
+    }
+}
@@ -19,9 +19,15 @@ pub(crate) enum PeerMessage {
     AckReplication(ReplicationAck),
     RequestVote(RequestVote),
     ElectionVoteReply(ElectionVote),
+    PreVote(RequestVote),
+    PreVoteReply(ElectionVote),
     StartRebalance,
     ReceiveBatch(MigrateBatch),
     MigrationBatchAck(MigrationBatchAck),
+    InstallSnapshot(InstallSnapshot),
+    TimeoutNow(TimeoutNow),
+    ForwardRequest(ForwardRequest),
+    ForwardResponse(ForwardResponse),
 }
 
 impl TryFrom<QueryIO> for PeerMessage {
@@ -33,9 +39,15 @@ impl TryFrom<QueryIO> for PeerMessage {
             | QueryIO::Ack(acks) => Ok(PeerMessage::AckReplication(acks)),
             | QueryIO::RequestVote(vote) => Ok(PeerMessage::RequestVote(vote)),
             | QueryIO::RequestVoteReply(reply) => Ok(PeerMessage::ElectionVoteReply(reply)),
+            | QueryIO::PreVote(vote) => Ok(PeerMessage::PreVote(vote)),
+            | QueryIO::PreVoteReply(reply) => Ok(PeerMessage::PreVoteReply(reply)),
             | QueryIO::StartRebalance => Ok(PeerMessage::StartRebalance),
             | QueryIO::MigrateBatch(batch) => Ok(PeerMessage::ReceiveBatch(batch)),
             | QueryIO::MigrationBatchAck(ack) => Ok(PeerMessage::MigrationBatchAck(ack)),
+            | QueryIO::InstallSnapshot(snapshot) => Ok(PeerMessage::InstallSnapshot(snapshot)),
+            | QueryIO::TimeoutNow(timeout_now) => Ok(PeerMessage::TimeoutNow(timeout_now)),
+            | QueryIO::ForwardRequest(request) => Ok(PeerMessage::ForwardRequest(request)),
+            | QueryIO::ForwardResponse(response) => Ok(PeerMessage::ForwardResponse(response)),
             | _ => Err(anyhow::anyhow!("Invalid data")),
         }
     }
@@ -54,11 +66,13 @@ mod peer_messages {
     use crate::domains::{
         caches::cache_objects::CacheEntry,
         cluster_actors::{
+            forwarding::{ForwardId, ForwardedOp, ForwardedReply},
             hash_ring::{BatchId, HashRing},
             replication::ReplicationId,
         },
         operation_logs::WriteOperation,
         peers::peer::PeerState,
+        telemetry::TraceContext,
     };
 
     #[derive(Clone, Debug, PartialEq, Eq, bincode::Encode, bincode::Decode)]
@@ -127,10 +141,20 @@ mod peer_messages {
             }
         }
 
+        /// Answers an append entries rpc without entries. It only tells the leader this replica is reachable,
+        /// so it carries no log index.
+        pub(crate) fn heartbeat(repl_state: &ReplicationState) -> Self {
+            Self::ack(0, repl_state)
+        }
+
         pub(crate) fn is_granted(&self) -> bool {
             self.rej_reason.is_none()
         }
 
+        pub(crate) fn is_heartbeat(&self) -> bool {
+            self.is_granted() && self.log_idx == 0
+        }
+
         #[cfg(test)]
         pub(crate) fn set_from(self, from: &str) -> Self {
             Self { from: PeerIdentifier(from.to_string()), ..self }
@@ -147,9 +171,17 @@ mod peer_messages {
         pub(crate) ban_list: Vec<BannedPeer>,
         pub(crate) append_entries: Vec<WriteOperation>,
         pub(crate) cluster_nodes: Vec<PeerState>,
+        // * Digest of the sender's node list, sent without the list to peers that already hold it
+        pub(crate) nodes_digest: u64,
         pub(crate) prev_log_index: u64, //index of log entry immediately preceding new ones
         pub(crate) prev_log_term: u64,  //term of prev_log_index entry
         pub(crate) hashring: Option<Box<HashRing>>,
+        pub(crate) priority: u8,
+        pub(crate) weight: u8,
+        // * Replica the sender takes the log from instead of the leader
+        pub(crate) upstream: Option<PeerIdentifier>,
+        // * Span the message was sent from, when it was sent as part of a trace
+        pub(crate) trace: Option<TraceContext>,
     }
     impl HeartBeat {
         pub(crate) fn set_append_entries(mut self, entries: Vec<WriteOperation>) -> Self {
@@ -171,6 +203,13 @@ mod peer_messages {
     pub struct BannedPeer {
         pub(crate) p_id: PeerIdentifier,
         pub(crate) ban_time: u64,
+        // * Seconds the ban lasts from `ban_time`, None for a permanent ban
+        pub(crate) duration: Option<u64>,
+    }
+    impl BannedPeer {
+        pub(crate) fn is_active(&self, now: u64) -> bool {
+            self.duration.is_none_or(|duration| now.saturating_sub(self.ban_time) < duration)
+        }
     }
     impl PartialEq for BannedPeer {
         fn eq(&self, other: &Self) -> bool {
@@ -193,6 +232,7 @@ mod peer_messages {
     pub struct MigrateBatch {
         pub(crate) batch_id: BatchId,
         pub(crate) cache_entries: Vec<CacheEntry>,
+        pub(crate) trace: Option<TraceContext>,
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
@@ -216,4 +256,63 @@ mod peer_messages {
             QueryIO::MigrationBatchAck(value)
         }
     }
+
+    /// Asks the leader of the shard owning the keys of `op` to run it, as part of a command
+    /// whose keys span several partitions.
+    #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+    pub struct ForwardRequest {
+        pub(crate) id: ForwardId,
+        pub(crate) op: ForwardedOp,
+    }
+
+    impl From<ForwardRequest> for QueryIO {
+        fn from(value: ForwardRequest) -> Self {
+            QueryIO::ForwardRequest(value)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+    pub struct ForwardResponse {
+        pub(crate) id: ForwardId,
+        pub(crate) reply: ForwardedReply,
+    }
+
+    impl From<ForwardResponse> for QueryIO {
+        fn from(value: ForwardResponse) -> Self {
+            QueryIO::ForwardResponse(value)
+        }
+    }
+
+    /// Sent by the leader to a follower whose next entry has already been compacted away.
+    /// The encoded snapshot of the state at `last_included_index` is streamed in chunks; `data` holds
+    /// the bytes starting at `offset`, and `done` marks the last chunk.
+    #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+    pub struct InstallSnapshot {
+        pub(crate) from: PeerIdentifier,
+        pub(crate) term: u64,
+        pub(crate) last_included_index: u64,
+        pub(crate) last_included_term: u64,
+        pub(crate) offset: u64,
+        pub(crate) data: Vec<u8>,
+        pub(crate) done: bool,
+    }
+
+    impl From<InstallSnapshot> for QueryIO {
+        fn from(value: InstallSnapshot) -> Self {
+            QueryIO::InstallSnapshot(value)
+        }
+    }
+
+    /// Sent by the leader handing off leadership so that the receiver starts an election right away.
+    #[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+    pub struct TimeoutNow {
+        pub(crate) from: PeerIdentifier,
+        pub(crate) term: u64,
+    }
+
+    impl From<TimeoutNow> for QueryIO {
+        fn from(value: TimeoutNow) -> Self {
+            QueryIO::TimeoutNow(value)
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
+use crate::domains::compression::Compression;
 use crate::domains::peers::peer::PeerState;
 use crate::domains::{TRead, TWrite};
 use crate::prelude::PeerIdentifier;
@@ -11,6 +12,8 @@ pub(crate) struct ConnectedPeerInfo {
     pub(crate) replid: ReplicationId,
     pub(crate) hwm: u64,
     pub(crate) role: ReplicationRole,
+    // * Codec agreed on during the handshake for the entries carried by AppendEntries
+    pub(crate) compression: Compression,
 }
 
 impl ConnectedPeerInfo {
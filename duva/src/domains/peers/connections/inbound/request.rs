@@ -46,7 +46,9 @@ impl HandShakeRequest {
         }
     }
 
-    pub(crate) fn extract_listening_port(&mut self) -> anyhow::Result<u16> {
+    /// Port the connecting peer listens on, and the IP or hostname it announces from
+    /// `REPLCONF listening-port <port> [ip-address <ip>]`.
+    pub(crate) fn extract_listening_port(&mut self) -> anyhow::Result<(u16, Option<String>)> {
         self.match_query(HandShakeRequestEnum::ReplConf)?;
 
         if self.args.len() < 2 {
@@ -54,19 +56,45 @@ impl HandShakeRequest {
         }
 
         match self.args.as_mut_slice() {
-            | [QueryIO::BulkString(key), QueryIO::BulkString(port), ..]
+            | [QueryIO::BulkString(key), QueryIO::BulkString(port)]
                 if key.as_ref() == b"listening-port" =>
             {
-                let value = std::str::from_utf8(port)?.parse()?;
-                Ok(value)
+                Ok((std::str::from_utf8(port)?.parse()?, None))
+            },
+            | [
+                QueryIO::BulkString(key),
+                QueryIO::BulkString(port),
+                QueryIO::BulkString(ip_key),
+                QueryIO::BulkString(ip),
+            ] if key.as_ref() == b"listening-port" && ip_key.as_ref() == b"ip-address" => {
+                let ip = std::str::from_utf8(ip)?.to_string();
+                Ok((std::str::from_utf8(port)?.parse()?, Some(ip)))
             },
             | _ => Err(anyhow::anyhow!("Invalid listening-port arguments")),
         }
     }
 
+    /// Proof of the cluster secret and the nonce the connecting peer challenges back with, from
+    /// `REPLCONF auth <proof> <nonce>`.
+    pub(crate) fn extract_auth(&mut self) -> anyhow::Result<(String, String)> {
+        self.match_query(HandShakeRequestEnum::ReplConf)?;
+
+        let Some([key, proof, nonce]) = self.args.get_mut(..3) else {
+            return Err(anyhow::anyhow!("peer did not prove the cluster secret"));
+        };
+        let key: String = std::mem::take(key).unpack_single_entry()?;
+        if key != "auth" {
+            return Err(anyhow::anyhow!("peer did not prove the cluster secret"));
+        }
+        Ok((
+            std::mem::take(proof).unpack_single_entry()?,
+            std::mem::take(nonce).unpack_single_entry()?,
+        ))
+    }
+
     pub(crate) fn extract_capa(&self) -> anyhow::Result<Vec<(Bytes, Bytes)>> {
         self.match_query(HandShakeRequestEnum::ReplConf)?;
-        if self.args.is_empty() || self.args.len() % 2 != 0 {
+        if self.args.is_empty() || !self.args.len().is_multiple_of(2) {
             return Err(anyhow::anyhow!("Invalid number of arguments"));
         }
 
@@ -1,64 +1,90 @@
 use super::request::HandShakeRequest;
 use super::request::HandShakeRequestEnum;
-use crate::domains::IoError;
 use crate::domains::QueryIO;
 use crate::domains::cluster_actors::ConnectionMessage;
 use crate::domains::cluster_actors::actor::ClusterCommandHandler;
 use crate::domains::cluster_actors::replication::ReplicationId;
 use crate::domains::cluster_actors::replication::ReplicationRole;
 use crate::domains::cluster_actors::replication::ReplicationState;
+use crate::domains::compression::Compression;
 use crate::domains::interface::TRead;
 use crate::domains::interface::TWrite;
+use crate::domains::peers::connections::cluster_secret::{ClusterSecret, Prover};
 use crate::domains::peers::connections::connection_types::ConnectedPeerInfo;
-use crate::domains::peers::connections::connection_types::WriteConnected;
+use crate::domains::peers::connections::connection_types::{ReadConnected, WriteConnected};
 use crate::domains::peers::identifier::PeerIdentifier;
 use crate::domains::peers::peer::Peer;
 use crate::domains::peers::peer::PeerState;
 use crate::domains::peers::service::PeerListener;
 
 use bytes::Bytes;
+use std::net::SocketAddr;
 use std::sync::atomic::Ordering;
 use tokio::net::TcpStream;
-
-use tokio::net::tcp::OwnedReadHalf;
-use tokio::net::tcp::OwnedWriteHalf;
+use tracing::warn;
 
 // The following is used only when the node is in leader mode
 #[derive(Debug)]
 pub(crate) struct InboundStream {
-    r: OwnedReadHalf,
-    w: OwnedWriteHalf,
+    r: Box<dyn TRead>,
+    w: Box<dyn TWrite>,
+    peer_addr: SocketAddr,
     self_repl_info: ReplicationState,
     connected_peer_info: ConnectedPeerInfo,
 }
 
 impl InboundStream {
-    pub(crate) fn new(stream: TcpStream, self_repl_info: ReplicationState) -> Self {
-        let (read, write) = stream.into_split();
-        Self { r: read, w: write, self_repl_info, connected_peer_info: Default::default() }
+    /// Takes a connection a peer opened, over TLS when the cluster has a CA configured.
+    pub(crate) async fn accept(
+        stream: TcpStream,
+        self_repl_info: ReplicationState,
+    ) -> anyhow::Result<Self> {
+        let peer_addr = stream.peer_addr()?;
+        let (r, w): (Box<dyn TRead>, Box<dyn TWrite>) = match self_repl_info.tls.as_ref() {
+            | Some(tls) => tls.accept(stream).await.inspect_err(|err| {
+                warn!("Rejected peer {peer_addr}: {err:#}");
+            })?,
+            | None => {
+                let (r, w) = stream.into_split();
+                (Box::new(r), Box::new(w))
+            },
+        };
+        Ok(Self { r, w, peer_addr, self_repl_info, connected_peer_info: Default::default() })
     }
     pub(crate) async fn recv_handshake(&mut self) -> anyhow::Result<()> {
         self.recv_ping().await?;
 
-        let port = self.recv_replconf_listening_port().await?;
-
-        // TODO find use of capa?
-        let _capa_val_vec = self.recv_replconf_capa().await?;
+        let peer_id = self.recv_replconf_listening_port().await?;
+        self.refuse_if_banned(&peer_id)?;
 
-        let (peer_leader_repl_id, peer_hwm, role) = self.recv_psync().await?;
+        let capa_val_vec = self.recv_replconf_capa().await?;
+        let compression = self.agree_on_compression(&capa_val_vec);
 
-        let addr = self.r.peer_addr().map_err(|error| Into::<IoError>::into(error.kind()))?;
+        let (peer_leader_repl_id, peer_hwm, role) = self.recv_psync(compression).await?;
 
         self.connected_peer_info = ConnectedPeerInfo {
-            id: PeerIdentifier::new(&addr.ip().to_string(), port),
+            id: peer_id,
             replid: peer_leader_repl_id,
             hwm: peer_hwm,
             role,
+            compression,
         };
 
         Ok(())
     }
 
+    /// Entries are compressed with the codec the connecting peer offers, if this node has compression enabled too.
+    fn agree_on_compression(&self, capa_val_vec: &[(Bytes, Bytes)]) -> Compression {
+        if !self.self_repl_info.compression.is_enabled() {
+            return Compression::None;
+        }
+        capa_val_vec
+            .iter()
+            .filter_map(|(_, value)| std::str::from_utf8(value).ok()?.parse().ok())
+            .find(Compression::is_enabled)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn connected_peer_state(&self) -> PeerState {
         self.connected_peer_info.decide_peer_state(&self.self_repl_info.replid)
     }
@@ -67,18 +93,52 @@ impl InboundStream {
         let cmd = self.extract_cmd().await?;
         cmd.match_query(HandShakeRequestEnum::Ping)?;
 
-        self.w.write(QueryIO::SimpleString("PONG".into())).await?;
+        match self.self_repl_info.cluster_secret.clone() {
+            | Some(secret) => self.authenticate(secret).await,
+            | None => Ok(self.w.write(QueryIO::SimpleString("PONG".into())).await?),
+        }
+    }
+
+    /// Challenges the connecting peer to prove it knows the cluster secret, then proves it in turn.
+    async fn authenticate(&mut self, secret: ClusterSecret) -> anyhow::Result<()> {
+        let nonce = ClusterSecret::nonce();
+        self.w.write(QueryIO::SimpleString(format!("CHALLENGE {nonce}").into())).await?;
+
+        let mut cmd = self.extract_cmd().await?;
+        let (proof, peer_nonce) = cmd.extract_auth()?;
+        if !secret.verify(Prover::Connector, &nonce, &proof) {
+            warn!("Rejected peer {}: cluster secret mismatch", self.peer_addr);
+            return Err(anyhow::anyhow!("cluster secret mismatch"));
+        }
+
+        let proof = secret.prove(Prover::Acceptor, &peer_nonce);
+        self.w.write(QueryIO::SimpleString(format!("AUTHENTICATED {proof}").into())).await?;
         Ok(())
     }
 
-    async fn recv_replconf_listening_port(&mut self) -> anyhow::Result<u16> {
+    // * A forgotten peer that does not know it was forgotten keeps reconnecting; it stays out while the ban lasts
+    fn refuse_if_banned(&self, peer_id: &PeerIdentifier) -> anyhow::Result<()> {
+        if self.self_repl_info.in_ban_list(peer_id) {
+            warn!("Rejected peer {peer_id}: banned");
+            return Err(anyhow::anyhow!("peer {peer_id} is banned"));
+        }
+        Ok(())
+    }
+
+    // * The peer is known by the address it announces, or else by the IP its connection comes from
+    async fn recv_replconf_listening_port(&mut self) -> anyhow::Result<PeerIdentifier> {
         let mut cmd = self.extract_cmd().await?;
 
-        let port = cmd.extract_listening_port()?;
+        let (port, announced_ip) = cmd.extract_listening_port()?;
+        let host = match announced_ip {
+            | Some(ip) => ip,
+            | None => self.peer_addr.ip().to_string(),
+        };
+        let peer_id = PeerIdentifier::try_new(&host, port)?;
 
         self.w.write(QueryIO::SimpleString("OK".into())).await?;
 
-        Ok(port)
+        Ok(peer_id)
     }
 
     async fn recv_replconf_capa(&mut self) -> anyhow::Result<Vec<(Bytes, Bytes)>> {
@@ -87,7 +147,10 @@ impl InboundStream {
         self.w.write(QueryIO::SimpleString("OK".into())).await?;
         Ok(capa_val_vec)
     }
-    async fn recv_psync(&mut self) -> anyhow::Result<(ReplicationId, u64, ReplicationRole)> {
+    async fn recv_psync(
+        &mut self,
+        compression: Compression,
+    ) -> anyhow::Result<(ReplicationId, u64, ReplicationRole)> {
         let mut cmd = self.extract_cmd().await?;
         let (inbound_repl_id, offset, role) = cmd.extract_psync()?;
 
@@ -102,7 +165,10 @@ impl InboundStream {
 
         self.w
             .write(QueryIO::SimpleString(
-                format!("FULLRESYNC {id} {self_replid} {self_repl_offset} {self_role}").into(),
+                format!(
+                    "FULLRESYNC {id} {self_replid} {self_repl_offset} {self_role} {compression}"
+                )
+                .into(),
             ))
             .await?;
         self.recv_ok().await?;
@@ -134,9 +200,13 @@ impl InboundStream {
         self.recv_handshake().await?;
 
         let peer_state = self.connected_peer_state();
-        let kill_switch =
-            PeerListener::spawn(self.r, cluster_handler.clone(), peer_state.id().clone());
-        let peer = Peer::new(WriteConnected(Box::new(self.w)), peer_state, kill_switch);
+        let kill_switch = PeerListener::spawn(
+            ReadConnected(self.r),
+            cluster_handler.clone(),
+            peer_state.id().clone(),
+        );
+        let peer = Peer::new(WriteConnected(self.w), peer_state, kill_switch)
+            .with_compression(self.connected_peer_info.compression);
         let _ = cluster_handler.send(ConnectionMessage::AddPeer(peer, None)).await;
         Ok(())
     }
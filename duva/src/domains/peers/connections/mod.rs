@@ -1,3 +1,5 @@
+pub(crate) mod cluster_secret;
 pub(crate) mod connection_types;
 pub mod inbound;
 pub mod outbound;
+pub(crate) mod tls;
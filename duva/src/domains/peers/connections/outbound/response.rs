@@ -1,12 +1,27 @@
 use anyhow::Context;
 
-use crate::domains::{QueryIO, cluster_actors::replication::ReplicationRole};
+use crate::domains::{
+    QueryIO, cluster_actors::replication::ReplicationRole, compression::Compression,
+};
 
 #[derive(Debug, PartialEq)]
 pub enum ConnectionResponse {
     Pong,
+    // * Sent instead of PONG by peers that require the cluster secret
+    Challenge {
+        nonce: String,
+    },
+    Authenticated {
+        proof: String,
+    },
     Ok,
-    FullResync { id: String, repl_id: String, offset: u64, role: ReplicationRole },
+    FullResync {
+        id: String,
+        repl_id: String,
+        offset: u64,
+        role: ReplicationRole,
+        compression: Compression,
+    },
 }
 
 impl TryFrom<String> for ConnectionResponse {
@@ -15,23 +30,33 @@ impl TryFrom<String> for ConnectionResponse {
         match value.to_lowercase().as_str() {
             | "pong" => Ok(ConnectionResponse::Pong),
             | "ok" => Ok(ConnectionResponse::Ok),
+            | var if var.starts_with("challenge ") => {
+                Ok(ConnectionResponse::Challenge { nonce: var["challenge ".len()..].to_string() })
+            },
+            | var if var.starts_with("authenticated ") => Ok(ConnectionResponse::Authenticated {
+                proof: var["authenticated ".len()..].to_string(),
+            }),
 
             | var if var.starts_with("fullresync") => {
-                let [_, id, repl_id, offset, role] = var
-                    .split_whitespace()
-                    .take(5)
-                    .collect::<Vec<_>>()
-                    .as_slice()
-                    .try_into()
-                    .context("Must have command, replication_id and offset")?;
+                let tokens = var.split_whitespace().collect::<Vec<_>>();
+                let [_, id, repl_id, offset, role] = tokens
+                    .get(..5)
+                    .context("Must have command, replication_id and offset")?
+                    .try_into()?;
 
                 let offset = offset.parse::<u64>()?;
+                // * Compression is only used when the peer agreed to it
+                let compression = match tokens.get(5) {
+                    | Some(compression) => compression.parse()?,
+                    | None => Compression::None,
+                };
 
                 Ok(ConnectionResponse::FullResync {
                     id: id.to_string(),
                     repl_id: repl_id.to_string(),
                     offset,
                     role: role.to_string().into(),
+                    compression,
                 })
             },
 
@@ -55,3 +80,30 @@ impl TryFrom<QueryIO> for ConnectionResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_resync_carries_agreed_compression() {
+        let response = ConnectionResponse::try_from(
+            "FULLRESYNC 127.0.0.1:6379 replid 3 leader lz4".to_string(),
+        )
+        .unwrap();
+        let ConnectionResponse::FullResync { offset, compression, .. } = response else {
+            panic!("expected FULLRESYNC");
+        };
+        assert_eq!(offset, 3);
+        assert_eq!(compression, Compression::Lz4);
+
+        // * Peers that didn't agree on compression leave it out
+        let response =
+            ConnectionResponse::try_from("FULLRESYNC 127.0.0.1:6379 replid 3 leader".to_string())
+                .unwrap();
+        assert!(matches!(
+            response,
+            ConnectionResponse::FullResync { compression: Compression::None, .. }
+        ));
+    }
+}
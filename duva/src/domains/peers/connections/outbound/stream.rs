@@ -6,8 +6,9 @@ use crate::domains::cluster_actors::replication::ReplicationId;
 use crate::domains::cluster_actors::replication::ReplicationState;
 use crate::domains::interface::TRead;
 use crate::domains::interface::TWrite;
+use crate::domains::peers::connections::cluster_secret::{ClusterSecret, Prover};
 use crate::domains::peers::connections::connection_types::ConnectedPeerInfo;
-use crate::domains::peers::connections::connection_types::WriteConnected;
+use crate::domains::peers::connections::connection_types::{ReadConnected, WriteConnected};
 use crate::domains::peers::identifier::PeerIdentifier;
 use crate::domains::peers::identifier::TPeerAddress;
 use crate::domains::peers::peer::Peer;
@@ -18,14 +19,12 @@ use anyhow::Context;
 use bytes::Bytes;
 use std::sync::atomic::Ordering;
 use tokio::net::TcpStream;
-use tokio::net::tcp::OwnedReadHalf;
-use tokio::net::tcp::OwnedWriteHalf;
 use tracing::trace;
 
 // The following is used only when the node is in follower mode
 pub(crate) struct OutboundStream {
-    r: OwnedReadHalf,
-    w: OwnedWriteHalf,
+    r: Box<dyn TRead>,
+    w: Box<dyn TWrite>,
     my_repl_info: ReplicationState,
     connected_node_info: Option<ConnectedPeerInfo>,
 }
@@ -39,17 +38,26 @@ impl OutboundStream {
             .await
             .context(format!("Failed to connect to {}", connect_to.cluster_bind_addr()?))?;
 
-        let (read, write) = stream.into_split();
-        Ok(OutboundStream { r: read, w: write, my_repl_info, connected_node_info: None })
+        let (r, w): (Box<dyn TRead>, Box<dyn TWrite>) = match my_repl_info.tls.as_ref() {
+            | Some(tls) => tls.connect(connect_to.host_and_port().0, stream).await?,
+            | None => {
+                let (r, w) = stream.into_split();
+                (Box::new(r), Box::new(w))
+            },
+        };
+        Ok(OutboundStream { r, w, my_repl_info, connected_node_info: None })
     }
     async fn make_handshake(&mut self, self_port: u16) -> anyhow::Result<()> {
         self.w.write(write_array!("PING")).await?;
         let mut ok_count = 0;
+        // * Nonce the accepting peer has to prove the cluster secret over
+        let mut challenge = None;
         let mut connection_info = ConnectedPeerInfo {
             id: Default::default(),
             replid: Default::default(),
             hwm: Default::default(),
             role: Default::default(),
+            compression: Default::default(),
         };
 
         loop {
@@ -57,14 +65,49 @@ impl OutboundStream {
             trace!(?res, "Received handshake response");
             for query in res {
                 match ConnectionResponse::try_from(query)? {
+                    | ConnectionResponse::Pong if self.my_repl_info.cluster_secret.is_some() => {
+                        return Err(anyhow::anyhow!("peer does not require the cluster secret"));
+                    },
                     | ConnectionResponse::Pong => {
-                        let msg = write_array!("REPLCONF", "listening-port", self_port.to_string());
-                        self.w.write(msg).await?
+                        self.w.write(self.listening_port(self_port)).await?
+                    },
+                    | ConnectionResponse::Challenge { nonce } => {
+                        let secret = self
+                            .my_repl_info
+                            .cluster_secret
+                            .as_ref()
+                            .context("peer requires a cluster secret")?;
+                        let own_nonce = ClusterSecret::nonce();
+                        let proof = secret.prove(Prover::Connector, &nonce);
+                        self.w
+                            .write(write_array!("REPLCONF", "auth", proof, own_nonce.clone()))
+                            .await?;
+                        challenge = Some(own_nonce);
+                    },
+                    | ConnectionResponse::Authenticated { proof } => {
+                        let (Some(secret), Some(nonce)) =
+                            (self.my_repl_info.cluster_secret.as_ref(), challenge.as_ref())
+                        else {
+                            return Err(anyhow::anyhow!("peer authenticated without a challenge"));
+                        };
+                        if !secret.verify(Prover::Acceptor, nonce, &proof) {
+                            return Err(anyhow::anyhow!("cluster secret mismatch"));
+                        }
+                        self.w.write(self.listening_port(self_port)).await?
                     },
                     | ConnectionResponse::Ok => {
                         ok_count += 1;
                         let msg = {
                             match ok_count {
+                                | 1 if self.my_repl_info.compression.is_enabled() => {
+                                    Ok(write_array!(
+                                        "REPLCONF",
+                                        "capa",
+                                        self.my_repl_info.compression.to_string(),
+                                        "capa",
+                                        "psync2"
+                                    ))
+                                },
                                 | 1 => Ok(write_array!("REPLCONF", "capa", "psync2")),
                                 // "?" here means the server is undecided about their leader. and -1 is the offset that follower is aware of
                                 | 2 => Ok(write_array!(
@@ -79,11 +122,12 @@ impl OutboundStream {
                         }?;
                         self.w.write(msg).await?
                     },
-                    | ConnectionResponse::FullResync { id, repl_id, offset, role } => {
+                    | ConnectionResponse::FullResync { id, repl_id, offset, role, compression } => {
                         connection_info.replid = ReplicationId::Key(repl_id);
                         connection_info.hwm = offset;
                         connection_info.id = PeerIdentifier(id);
                         connection_info.role = role;
+                        connection_info.compression = compression;
                         self.connected_node_info = Some(connection_info);
 
                         self.reply_with_ok().await?;
@@ -94,6 +138,20 @@ impl OutboundStream {
         }
     }
 
+    // * The accepting peer takes the host from the connection unless this node announces its own
+    fn listening_port(&self, self_port: u16) -> QueryIO {
+        match self.my_repl_info.announce_ip.as_deref() {
+            | Some(ip) => write_array!(
+                "REPLCONF",
+                "listening-port",
+                self_port.to_string(),
+                "ip-address",
+                ip.to_string()
+            ),
+            | None => write_array!("REPLCONF", "listening-port", self_port.to_string()),
+        }
+    }
+
     async fn reply_with_ok(&mut self) -> anyhow::Result<()> {
         self.w.write(QueryIO::SimpleString(Bytes::from("ok"))).await?;
         Ok(())
@@ -119,9 +177,13 @@ impl OutboundStream {
         }
         let peer_state = connection_info.decide_peer_state(&self.my_repl_info.replid);
 
-        let kill_switch =
-            PeerListener::spawn(self.r, cluster_handler.clone(), peer_state.id().clone());
-        let peer = Peer::new(WriteConnected(Box::new(self.w)), peer_state, kill_switch);
+        let kill_switch = PeerListener::spawn(
+            ReadConnected(self.r),
+            cluster_handler.clone(),
+            peer_state.id().clone(),
+        );
+        let peer = Peer::new(WriteConnected(self.w), peer_state, kill_switch)
+            .with_compression(connection_info.compression);
 
         let _ = cluster_handler.send(ConnectionMessage::AddPeer(peer, optional_callback)).await;
         Ok(())
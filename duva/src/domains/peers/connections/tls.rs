@@ -0,0 +1,200 @@
+//! Mutual TLS between peers.
+//!
+//! Every node holds a certificate issued by the cluster CA, valid for the address peers reach it
+//! at. Both ends of a peer connection present their certificate and verify the other's against
+//! the CA, so a node the CA did not vouch for can neither join the cluster nor be joined.
+
+use crate::domains::interface::{TRead, TWrite};
+#[cfg(feature = "tls")]
+use anyhow::Context;
+#[cfg(feature = "tls")]
+use std::fmt::Debug;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+#[cfg(feature = "tls")]
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::crypto::ring;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+#[cfg(feature = "tls")]
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+pub(crate) type PeerHalves = (Box<dyn TRead>, Box<dyn TWrite>);
+
+#[cfg(feature = "tls")]
+#[derive(Clone)]
+pub(crate) struct PeerTls {
+    acceptor: TlsAcceptor,
+    connector: TlsConnector,
+}
+
+#[cfg(feature = "tls")]
+impl PeerTls {
+    /// Reads the node's certificate chain, its private key and the cluster CA, all PEM encoded.
+    pub(crate) fn load(cert_path: &str, key_path: &str, ca_path: &str) -> anyhow::Result<Self> {
+        let read =
+            |path: &str| std::fs::read(path).with_context(|| format!("failed to read {path}"));
+        Self::from_pem(&read(cert_path)?, &read(key_path)?, &read(ca_path)?)
+    }
+
+    pub(crate) fn from_pem(cert: &[u8], key: &[u8], ca: &[u8]) -> anyhow::Result<Self> {
+        let certs = CertificateDer::pem_slice_iter(cert)
+            .collect::<Result<Vec<_>, _>>()
+            .context("invalid peer certificate")?;
+        let key = PrivateKeyDer::from_pem_slice(key).context("invalid peer private key")?;
+        let mut roots = RootCertStore::empty();
+        for ca in CertificateDer::pem_slice_iter(ca) {
+            roots.add(ca.context("invalid cluster CA certificate")?)?;
+        }
+        let roots = Arc::new(roots);
+
+        let provider = Arc::new(ring::default_provider());
+        let verifier =
+            WebPkiClientVerifier::builder_with_provider(roots.clone(), provider.clone()).build()?;
+        let server = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(certs.clone(), key.clone_key())?;
+        let client = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_client_auth_cert(certs, key)?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(server)),
+            connector: TlsConnector::from(Arc::new(client)),
+        })
+    }
+
+    /// Secures a connection a peer opened, once the peer proved its certificate.
+    pub(crate) async fn accept(&self, stream: TcpStream) -> anyhow::Result<PeerHalves> {
+        let stream = self.acceptor.accept(stream).await.context("peer TLS handshake failed")?;
+        Ok(split(stream))
+    }
+
+    /// Secures a connection to the peer at `host`, whose certificate has to be valid for it.
+    pub(crate) async fn connect(
+        &self,
+        host: &str,
+        stream: TcpStream,
+    ) -> anyhow::Result<PeerHalves> {
+        let name = ServerName::try_from(host.to_string())
+            .with_context(|| format!("{host} is not a valid TLS server name"))?;
+        let stream =
+            self.connector.connect(name, stream).await.context("peer TLS handshake failed")?;
+        Ok(split(stream))
+    }
+}
+
+#[cfg(feature = "tls")]
+fn split(stream: impl AsyncRead + AsyncWrite + Send + Sync + Debug + 'static) -> PeerHalves {
+    let (r, w) = tokio::io::split(stream);
+    (Box::new(r), Box::new(w))
+}
+
+#[cfg(feature = "tls")]
+impl Debug for PeerTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PeerTls(..)")
+    }
+}
+
+/// Builds without the `tls` feature can't set up peer TLS, so there is no value of it to use.
+#[cfg(not(feature = "tls"))]
+#[derive(Clone, Debug)]
+pub(crate) enum PeerTls {}
+
+#[cfg(not(feature = "tls"))]
+impl PeerTls {
+    pub(crate) fn load(_cert_path: &str, _key_path: &str, _ca_path: &str) -> anyhow::Result<Self> {
+        anyhow::bail!("peer TLS needs duva built with the tls feature")
+    }
+
+    pub(crate) async fn accept(&self, _stream: TcpStream) -> anyhow::Result<PeerHalves> {
+        match *self {}
+    }
+
+    pub(crate) async fn connect(
+        &self,
+        _host: &str,
+        _stream: TcpStream,
+    ) -> anyhow::Result<PeerHalves> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "tls"))]
+pub(crate) mod tests {
+    use super::*;
+    use crate::domains::QueryIO;
+    use rcgen::{
+        BasicConstraints, CertificateParams, CertifiedIssuer, ExtendedKeyUsagePurpose, IsCa,
+        KeyPair,
+    };
+
+    /// A cluster CA that issues node certificates for 127.0.0.1.
+    pub(crate) struct TestCa(CertifiedIssuer<'static, KeyPair>);
+
+    impl TestCa {
+        pub(crate) fn new() -> Self {
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            Self(CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap())
+        }
+
+        pub(crate) fn peer_tls(&self) -> PeerTls {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["127.0.0.1".to_string()]).unwrap();
+            params.extended_key_usages =
+                vec![ExtendedKeyUsagePurpose::ServerAuth, ExtendedKeyUsagePurpose::ClientAuth];
+            let cert = params.signed_by(&key, &self.0).unwrap();
+            PeerTls::from_pem(
+                cert.pem().as_bytes(),
+                key.serialize_pem().as_bytes(),
+                self.0.pem().as_bytes(),
+            )
+            .unwrap()
+        }
+    }
+
+    /// Whether a peer connecting with `connector` and one accepting with `acceptor` get a message
+    /// across.
+    async fn connects(connector: PeerTls, acceptor: PeerTls) -> bool {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (mut r, _w) = acceptor.accept(stream).await?;
+            anyhow::Ok(r.read_values().await?)
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        if let Ok((_r, mut w)) = connector.connect("127.0.0.1", stream).await {
+            let _ = w.write(QueryIO::SimpleString("PING".into())).await;
+        }
+        accepted.await.unwrap().is_ok_and(|values| values == [QueryIO::SimpleString("PING".into())])
+    }
+
+    #[tokio::test]
+    async fn test_peers_connect_with_certificates_of_the_cluster_ca() {
+        let (ca, other_ca) = (TestCa::new(), TestCa::new());
+
+        assert!(connects(ca.peer_tls(), ca.peer_tls()).await);
+        assert!(!connects(other_ca.peer_tls(), ca.peer_tls()).await);
+        assert!(!connects(ca.peer_tls(), other_ca.peer_tls()).await);
+        assert_eq!(format!("{:?}", ca.peer_tls()), "PeerTls(..)");
+    }
+
+    #[test]
+    fn test_from_pem_rejects_garbage() {
+        assert!(PeerTls::from_pem(b"", b"", b"").is_err());
+    }
+}
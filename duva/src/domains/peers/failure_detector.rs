@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub(crate) const DEFAULT_PHI_THRESHOLD: f64 = 8.0;
+// * Heartbeat intervals kept to estimate the arrival distribution
+const MAX_SAMPLES: usize = 100;
+
+/// Phi-accrual failure detector.
+///
+/// Rather than declaring a peer dead after a fixed timeout, the time since its last heartbeat is
+/// turned into a suspicion level `phi` based on the intervals observed so far: `phi = 1` means a
+/// 10% chance the peer is still alive, `phi = 2` a 1% chance, and so on. Peers with jittery links
+/// build up a wider distribution and are therefore tolerated longer.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct PhiAccrualDetector {
+    intervals: VecDeque<u64>,
+}
+
+impl PhiAccrualDetector {
+    pub(crate) fn record(&mut self, interval: Duration) {
+        if self.intervals.len() == MAX_SAMPLES {
+            self.intervals.pop_front();
+        }
+        self.intervals.push_back(interval.as_millis() as u64);
+    }
+
+    /// Suspicion level after `elapsed` without a heartbeat. `acceptable_pause` is added to the
+    /// expected interval, and a tenth of it bounds the deviation from below so that a perfectly
+    /// regular peer is not suspected on the first late heartbeat.
+    pub(crate) fn phi(&self, elapsed: Duration, acceptable_pause: Duration) -> f64 {
+        let (mean, std_deviation) = self.distribution();
+        let pause = acceptable_pause.as_millis() as f64;
+        let mean = mean + pause;
+        let std_deviation = std_deviation.max(pause / 10.0).max(1.0);
+
+        // * Logistic approximation of the normal CDF
+        let elapsed = elapsed.as_millis() as f64;
+        let y = (elapsed - mean) / std_deviation;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        let phi = if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        };
+        phi.max(0.0)
+    }
+
+    fn distribution(&self) -> (f64, f64) {
+        if self.intervals.is_empty() {
+            return (0.0, 0.0);
+        }
+        let n = self.intervals.len() as f64;
+        let mean = self.intervals.iter().sum::<u64>() as f64 / n;
+        let variance = self.intervals.iter().map(|&i| (i as f64 - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phi_grows_with_silence() {
+        let mut detector = PhiAccrualDetector::default();
+        (0..10).for_each(|_| detector.record(Duration::from_millis(100)));
+        let pause = Duration::from_millis(1000);
+
+        let phis =
+            [0, 500, 1100, 1500, 2000].map(|ms| detector.phi(Duration::from_millis(ms), pause));
+
+        assert!(phis.windows(2).all(|w| w[0] <= w[1]));
+        assert!(phis[0] < 0.1);
+        assert!(phis[4] > DEFAULT_PHI_THRESHOLD);
+    }
+
+    #[test]
+    fn test_jittery_peer_is_suspected_later() {
+        let mut steady = PhiAccrualDetector::default();
+        let mut jittery = PhiAccrualDetector::default();
+        for i in 0..20 {
+            steady.record(Duration::from_millis(500));
+            jittery.record(Duration::from_millis(if i % 2 == 0 { 100 } else { 900 }));
+        }
+        let pause = Duration::from_millis(1000);
+        let elapsed = Duration::from_millis(2200);
+
+        assert!(steady.phi(elapsed, pause) > DEFAULT_PHI_THRESHOLD);
+        assert!(jittery.phi(elapsed, pause) < DEFAULT_PHI_THRESHOLD);
+    }
+}
@@ -0,0 +1,127 @@
+use super::peer::PeerState;
+use crate::prelude::PeerIdentifier;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+// * The node list is sent in full at least every this many heartbeats, even when unchanged, so that a
+// * receiver that lost track of it recovers
+const FULL_SYNC_EVERY: u32 = 10;
+
+/// Digest of a gossiped node list. Covers what receivers act upon, the ids, partitions and roles, so
+/// that advancing match indexes alone do not count as a change.
+pub(crate) fn nodes_digest(nodes: &[PeerState]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for node in nodes {
+        (node.id(), &node.replid, &node.role).hash(&mut hasher);
+    }
+    // * 0 is left for heartbeats that carry no node list at all
+    hasher.finish().max(1)
+}
+
+/// Node list digest last sent to a peer, on the sending side.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct SentNodes {
+    digest: Option<u64>,
+    since_full: u32,
+}
+
+impl SentNodes {
+    /// Whether the node list with `digest` must go out in full, rather than as the digest alone.
+    pub(crate) fn needs_full(&mut self, digest: u64) -> bool {
+        if self.digest == Some(digest) && self.since_full < FULL_SYNC_EVERY {
+            self.since_full += 1;
+            return false;
+        }
+        self.digest = Some(digest);
+        self.since_full = 0;
+        true
+    }
+}
+
+/// Node lists last received in full, by sender, on the receiving side.
+#[derive(Debug, Default)]
+pub(crate) struct GossipViews(HashMap<PeerIdentifier, (u64, Vec<PeerState>)>);
+
+impl GossipViews {
+    /// Node list a heartbeat stands for: the one it carries, or the one last received from `from`
+    /// when it only carries its digest. Empty when the digest is not the one on record.
+    pub(crate) fn resolve(
+        &mut self,
+        from: &PeerIdentifier,
+        digest: u64,
+        nodes: Vec<PeerState>,
+    ) -> Vec<PeerState> {
+        if digest == 0 {
+            return nodes;
+        }
+        if !nodes.is_empty() {
+            self.0.insert(from.clone(), (digest, nodes.clone()));
+            return nodes;
+        }
+        match self.0.get(from) {
+            | Some((known, nodes)) if *known == digest => nodes.clone(),
+            | _ => vec![],
+        }
+    }
+
+    pub(crate) fn forget(&mut self, peer_id: &PeerIdentifier) {
+        self.0.remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
+
+    fn node(port: u16, match_index: u64, role: ReplicationRole) -> PeerState {
+        PeerState::new(
+            &format!("127.0.0.1:{port}"),
+            match_index,
+            ReplicationId::Key("repl".into()),
+            role,
+        )
+    }
+
+    #[test]
+    fn test_digest_ignores_match_indexes_but_not_roles() {
+        let nodes =
+            vec![node(6379, 1, ReplicationRole::Leader), node(6380, 1, ReplicationRole::Follower)];
+        let advanced =
+            vec![node(6379, 9, ReplicationRole::Leader), node(6380, 7, ReplicationRole::Follower)];
+        let failed_over =
+            vec![node(6379, 9, ReplicationRole::Follower), node(6380, 9, ReplicationRole::Leader)];
+
+        assert_eq!(nodes_digest(&nodes), nodes_digest(&advanced));
+        assert_ne!(nodes_digest(&nodes), nodes_digest(&failed_over));
+        assert_ne!(nodes_digest(&nodes), nodes_digest(&nodes[..1]));
+    }
+
+    #[test]
+    fn test_unchanged_node_list_is_sent_in_full_only_now_and_then() {
+        let mut sent = SentNodes::default();
+        assert!(sent.needs_full(7));
+        for _ in 0..FULL_SYNC_EVERY {
+            assert!(!sent.needs_full(7));
+        }
+        assert!(sent.needs_full(7));
+        assert!(sent.needs_full(8));
+    }
+
+    #[test]
+    fn test_digest_only_heartbeats_stand_for_the_last_full_list() {
+        // GIVEN
+        let from = PeerIdentifier::new("127.0.0.1", 6379);
+        let nodes = vec![node(6379, 0, ReplicationRole::Leader)];
+        let mut views = GossipViews::default();
+        assert_eq!(views.resolve(&from, 5, nodes.clone()), nodes);
+
+        // THEN
+        assert_eq!(views.resolve(&from, 5, vec![]), nodes);
+        assert!(views.resolve(&from, 6, vec![]).is_empty());
+        assert!(views.resolve(&from, 0, vec![]).is_empty());
+
+        views.forget(&from);
+        assert!(views.resolve(&from, 5, vec![]).is_empty());
+    }
+}
@@ -6,7 +6,25 @@ use crate::make_smart_pointer;
 pub struct PeerIdentifier(pub String);
 impl PeerIdentifier {
     pub(crate) fn new(host: &str, port: u16) -> Self {
-        parse_address(host).map(|ip| Self(format!("{ip}:{port}"))).unwrap()
+        Self::try_new(host, port).unwrap()
+    }
+
+    pub(crate) fn try_new(host: &str, port: u16) -> anyhow::Result<Self> {
+        Ok(Self(join_host_port(&parse_address(host)?, port)))
+    }
+
+    /// Host without the brackets of an IPv6 literal, and port, the way Redis reports them apart.
+    pub(crate) fn host_and_port(&self) -> (&str, &str) {
+        let (host, port) = self.0.rsplit_once(':').unwrap_or((self.0.as_str(), ""));
+        (host.trim_start_matches('[').trim_end_matches(']'), port)
+    }
+}
+
+/// `host:port`, with an IPv6 host in brackets so the port can be told apart from it.
+pub(crate) fn join_host_port(host: &str, port: u16) -> String {
+    match host.contains(':') && !host.starts_with('[') {
+        | true => format!("[{host}]:{port}"),
+        | false => format!("{host}:{port}"),
     }
 }
 
@@ -18,15 +36,15 @@ pub trait TPeerAddress {
 impl<T: AsRef<str>> TPeerAddress for T {
     fn bind_addr(&self) -> anyhow::Result<String> {
         let (host, port) = extract_host_and_port(self.as_ref())?;
-        Ok(format!("{host}:{port}"))
+        Ok(join_host_port(&host, port))
     }
     fn cluster_bind_addr(&self) -> anyhow::Result<String> {
         let (host, port) = extract_host_and_port(self.as_ref())?;
-        Ok(format!("{}:{}", host, port + 10000))
+        Ok(join_host_port(&host, port + 10000))
     }
 }
 
-fn extract_host_and_port(addr: &str) -> anyhow::Result<(std::net::IpAddr, u16)> {
+fn extract_host_and_port(addr: &str) -> anyhow::Result<(String, u16)> {
     let (host, port_str) =
         addr.rsplit_once(':').ok_or_else(|| anyhow::anyhow!("Invalid address format"))?;
     let host = parse_address(host)?;
@@ -34,40 +52,46 @@ fn extract_host_and_port(addr: &str) -> anyhow::Result<(std::net::IpAddr, u16)>
     Ok((host, port))
 }
 
-fn parse_address(addr: &str) -> anyhow::Result<std::net::IpAddr> {
-    match addr.to_lowercase().as_str() {
-        // IPv4 127.0.0.1 variants
-        | "127.0.0.1" | "localhost" => {
-            Ok(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
-        },
-        // IPv6 127.0.0.1 variants
-        | "::1" | "[::1]" | "0:0:0:0:0:0:0:1" => {
-            Ok(std::net::IpAddr::V6(std::net::Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
-        },
-        // Try to parse anything else as an IP address
-        | other => other.parse().map_err(|_| {
-            anyhow::anyhow!(
-                "Invalid address: {}. Expected a valid IP address or 'localhost'.",
-                other
-            )
-        }),
+/// Canonical form of a host: an IP address, or a DNS name that is kept as it is and resolved
+/// whenever the peer is connected to, so a pod rescheduled under a new IP is found again.
+fn parse_address(addr: &str) -> anyhow::Result<String> {
+    let addr = addr.to_lowercase();
+    let unbracketed = addr.strip_prefix('[').and_then(|addr| addr.strip_suffix(']'));
+    if let Ok(ip) = unbracketed.unwrap_or(&addr).parse::<std::net::IpAddr>() {
+        return Ok(ip.to_string());
+    }
+    match addr.as_str() {
+        | "localhost" => Ok(std::net::Ipv4Addr::LOCALHOST.to_string()),
+        | name if unbracketed.is_none() && is_hostname(name) => Ok(addr),
+        | other => Err(anyhow::anyhow!(
+            "Invalid address: {}. Expected a valid IP address or hostname.",
+            other
+        )),
     }
 }
 
+// * RFC 1123 names; one ending in a numeric label is taken for a mistyped IPv4 address
+fn is_hostname(name: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let valid_label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+    name.len() <= 253
+        && name.split('.').all(valid_label)
+        && !name.rsplit('.').next().is_some_and(|tld| tld.bytes().all(|b| b.is_ascii_digit()))
+}
+
 make_smart_pointer!(PeerIdentifier, String);
 
 impl std::fmt::Display for PeerIdentifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.0
-                .rsplit_once(':')
-                .map(|(host, port)| {
-                    format!("{}:{}", parse_address(host).unwrap(), port.parse::<u16>().unwrap())
-                })
-                .unwrap()
-        )
+        match self.0.bind_addr() {
+            | Ok(addr) => write!(f, "{addr}"),
+            | Err(_) => write!(f, "{}", self.0),
+        }
     }
 }
 
@@ -86,3 +110,30 @@ fn test_peer_identifier2() {
 
     assert!(peer < peer2);
 }
+
+#[test]
+fn test_ipv6_peer_identifier_is_bracketed() {
+    let peer = PeerIdentifier::new("0:0:0:0:0:0:0:1", 6379);
+
+    assert_eq!(peer.0, "[::1]:6379");
+    assert_eq!(peer.to_string(), "[::1]:6379");
+    assert_eq!(peer.host_and_port(), ("::1", "6379"));
+    assert_eq!("[::1]:6379".bind_addr().unwrap(), "[::1]:6379");
+    assert_eq!("::1:6379".bind_addr().unwrap(), "[::1]:6379");
+    assert_eq!("[FE80::1]:6379".cluster_bind_addr().unwrap(), "[fe80::1]:16379");
+}
+
+#[test]
+fn test_hostname_peer_identifier_is_kept_unresolved() {
+    let peer = PeerIdentifier::new("Duva-0.duva.default.svc", 6379);
+
+    assert_eq!(peer.0, "duva-0.duva.default.svc:6379");
+    assert_eq!(peer.host_and_port(), ("duva-0.duva.default.svc", "6379"));
+    assert_eq!(peer.cluster_bind_addr().unwrap(), "duva-0.duva.default.svc:16379");
+    assert_eq!(PeerIdentifier::new("localhost", 6379).0, "127.0.0.1:6379");
+
+    assert!("1.2.3:6379".bind_addr().is_err());
+    assert!("-duva:6379".bind_addr().is_err());
+    assert!("duva_0:6379".bind_addr().is_err());
+    assert!("[duva]:6379".bind_addr().is_err());
+}
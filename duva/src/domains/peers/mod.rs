@@ -1,6 +1,11 @@
+pub(crate) mod banlist;
 pub(crate) mod connections;
+pub(crate) mod failure_detector;
+pub(crate) mod gossip;
 pub mod identifier;
 pub(crate) mod peer;
+pub(crate) mod reconnect;
+pub(crate) mod seeds;
 pub(crate) mod service;
 
 pub(crate) mod command;
@@ -1,19 +1,134 @@
 use super::connections::connection_types::WriteConnected;
+use super::failure_detector::PhiAccrualDetector;
+use super::gossip::SentNodes;
 use super::identifier::TPeerAddress;
 use crate::domains::QueryIO;
+use crate::domains::cluster_actors::hash_ring::DEFAULT_WEIGHT;
 use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
+use crate::domains::compression::Compression;
+use crate::domains::encryption::KeyRing;
 use crate::domains::{IoError, TRead};
 use crate::prelude::PeerIdentifier;
 use crate::types::Callback;
+use bytes::Bytes;
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use std::time::Duration;
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
 
+// * Entries sent to a replica ahead of its acknowledgements
+pub(crate) const MAX_INFLIGHT_ENTRIES: u64 = 1024;
+// * In-flight entries that are not acknowledged for this long are assumed lost and sent again
+const RETRANSMIT_AFTER: Duration = Duration::from_millis(1000);
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct Peer {
     pub(crate) w_conn: WriteConnected,
     pub(crate) listener_kill_trigger: ListeningActorKillTrigger,
     pub(crate) last_seen: Instant,
     state: PeerState,
+    window: ReplicationWindow,
+    // * Election priority the peer last advertised in its heartbeats
+    priority: u8,
+    // * Hash ring weight the peer last advertised in its heartbeats
+    weight: u8,
+    // * Replica the peer last advertised taking the log from, if it is chained behind one
+    upstream: Option<PeerIdentifier>,
+    liveness: PhiAccrualDetector,
+    // * Codec agreed on with the peer for the entries carried by AppendEntries
+    compression: Compression,
+    sent_nodes: SentNodes,
+}
+
+/// A message several peers are sent, serialized at most once for each codec it goes out with.
+#[derive(Debug)]
+pub(crate) struct SharedMessage {
+    io: QueryIO,
+    frames: Vec<(Option<Compression>, Bytes)>,
+}
+
+impl SharedMessage {
+    pub(crate) fn new(io: impl Into<QueryIO>) -> Self {
+        Self { io: io.into(), frames: Vec::new() }
+    }
+
+    /// The message serialized the way `peer` takes it.
+    fn frame_for(&mut self, peer: &Peer) -> Bytes {
+        let codec = peer.codec_for(&self.io);
+        if let Some((_, frame)) = self.frames.iter().find(|(c, _)| *c == codec) {
+            return frame.clone();
+        }
+        let frame = with_codec(self.io.clone(), codec).serialize();
+        self.frames.push((codec, frame.clone()));
+        frame
+    }
+}
+
+/// Serializes the message of every peer, to be sent with [`send_frames`]. Messages that several
+/// peers are sent alike, as AppendEntries to replicas that are equally caught up, are serialized
+/// only once per codec.
+pub(crate) fn frame_each<'a>(
+    sends: impl Iterator<Item = (&'a mut Peer, QueryIO)>,
+) -> Vec<(&'a mut Peer, Bytes)> {
+    let mut messages: Vec<SharedMessage> = Vec::new();
+    sends
+        .map(|(peer, io)| {
+            let message = match messages.iter().position(|message| message.io == io) {
+                | Some(i) => &mut messages[i],
+                | None => {
+                    messages.push(SharedMessage::new(io));
+                    messages.last_mut().unwrap()
+                },
+            };
+            let frame = message.frame_for(peer);
+            (peer, frame)
+        })
+        .collect()
+}
+
+/// Sends `message` to all of `peers` at once.
+pub(crate) async fn broadcast<'a>(
+    peers: impl Iterator<Item = &'a mut Peer>,
+    mut message: SharedMessage,
+) {
+    let framed = peers
+        .map(|peer| {
+            let frame = message.frame_for(peer);
+            (peer, frame)
+        })
+        .collect();
+    send_frames(framed).await;
+}
+
+/// Sends every peer its frame at once.
+pub(crate) async fn send_frames(framed: Vec<(&mut Peer, Bytes)>) {
+    framed
+        .into_iter()
+        .map(|(peer, frame)| async move {
+            let _ = peer.send_frame(&frame).await;
+        })
+        .collect::<FuturesUnordered<_>>()
+        .for_each(|_| async {})
+        .await;
+}
+
+fn with_codec(io: QueryIO, codec: Option<Compression>) -> QueryIO {
+    match (io, codec) {
+        | (QueryIO::AppendEntriesRPC(heartbeat), Some(codec)) => {
+            QueryIO::CompressedAppendEntriesRPC(heartbeat, codec)
+        },
+        | (io, _) => io,
+    }
+}
+
+/// Replication pipeline towards a replica: entries after its match index up to `next_index - 1`
+/// were sent and are waiting to be acknowledged.
+#[derive(Debug, PartialEq, Eq)]
+struct ReplicationWindow {
+    next_index: u64,
+    // * Last time the match index moved, or the window started filling up
+    progressed_at: Instant,
 }
 
 impl Peer {
@@ -22,7 +137,26 @@ impl Peer {
         state: PeerState,
         listener_kill_trigger: ListeningActorKillTrigger,
     ) -> Self {
-        Self { w_conn: w.into(), listener_kill_trigger, last_seen: Instant::now(), state }
+        let window =
+            ReplicationWindow { next_index: state.match_index + 1, progressed_at: Instant::now() };
+        Self {
+            w_conn: w.into(),
+            listener_kill_trigger,
+            last_seen: Instant::now(),
+            state,
+            window,
+            priority: 0,
+            weight: DEFAULT_WEIGHT,
+            upstream: None,
+            liveness: PhiAccrualDetector::default(),
+            compression: Compression::None,
+            sent_nodes: SentNodes::default(),
+        }
+    }
+
+    pub(crate) fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
     }
     pub(crate) fn id(&self) -> &PeerIdentifier {
         &self.state.id
@@ -37,11 +171,66 @@ impl Peer {
         self.state.match_index
     }
     pub(crate) fn set_match_index(&mut self, match_index: u64) {
+        if match_index != self.state.match_index {
+            self.window.progressed_at = Instant::now();
+        }
         self.state.match_index = match_index;
     }
 
+    /// Entries in flight are sent again from the match index, e.g. after the replica rejected them.
+    pub(crate) fn rewind_window(&mut self) {
+        self.window.next_index = self.state.match_index + 1;
+    }
+
+    /// Log index up to which entries were already sent and are either acknowledged or in flight.
+    pub(crate) fn sent_up_to(&mut self) -> u64 {
+        let match_index = self.state.match_index;
+        let in_flight = self.window.next_index > match_index + 1;
+        if !in_flight || self.window.progressed_at.elapsed() >= RETRANSMIT_AFTER {
+            self.window.next_index = match_index + 1;
+        }
+        self.window.next_index - 1
+    }
+
+    pub(crate) fn mark_sent(&mut self, last_index: u64) {
+        if self.window.next_index == self.state.match_index + 1 {
+            self.window.progressed_at = Instant::now();
+        }
+        self.window.next_index = self.window.next_index.max(last_index + 1);
+    }
+
     pub(crate) async fn send(&mut self, io: impl Into<QueryIO> + Send) -> Result<(), IoError> {
-        self.w_conn.write(io.into()).await
+        let io = io.into();
+        let codec = self.codec_for(&io);
+        self.w_conn.write(with_codec(io, codec)).await
+    }
+
+    /// Sends a message shared with other peers, serialized once for every codec it goes out with.
+    pub(crate) async fn send_shared(&mut self, message: &mut SharedMessage) -> Result<(), IoError> {
+        let frame = message.frame_for(self);
+        self.send_frame(&frame).await
+    }
+
+    // * Writes a frame taken from `SharedMessage::frame_for` this peer
+    async fn send_frame(&mut self, frame: &Bytes) -> Result<(), IoError> {
+        self.w_conn.write_bytes(frame).await
+    }
+
+    // * Entries carried by AppendEntries are sent with the codec agreed on with the peer
+    fn codec_for(&self, io: &QueryIO) -> Option<Compression> {
+        match io {
+            | QueryIO::AppendEntriesRPC(heartbeat)
+                if self.compression.is_enabled() && !heartbeat.append_entries.is_empty() =>
+            {
+                Some(self.compression)
+            },
+            | _ => None,
+        }
+    }
+
+    /// Whether the node list with `digest` must be sent in full, see [`SentNodes`].
+    pub(crate) fn needs_full_nodes(&mut self, digest: u64) -> bool {
+        self.sent_nodes.needs_full(digest)
     }
 
     pub(crate) async fn kill(self) -> Box<dyn TRead> {
@@ -60,6 +249,40 @@ impl Peer {
         self.state.role = role;
     }
 
+    pub(crate) fn seen(&mut self, now: Instant) {
+        self.liveness.record(now.saturating_duration_since(self.last_seen));
+        self.last_seen = now;
+    }
+
+    /// Suspicion level that the peer has failed, see [`PhiAccrualDetector`].
+    pub(crate) fn phi(&self, now: Instant, acceptable_pause: Duration) -> f64 {
+        self.liveness.phi(now.saturating_duration_since(self.last_seen), acceptable_pause)
+    }
+
+    pub(crate) fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    pub(crate) fn set_priority(&mut self, priority: u8) {
+        self.priority = priority;
+    }
+
+    pub(crate) fn weight(&self) -> u8 {
+        self.weight
+    }
+
+    pub(crate) fn set_weight(&mut self, weight: u8) {
+        self.weight = weight;
+    }
+
+    pub(crate) fn upstream(&self) -> Option<&PeerIdentifier> {
+        self.upstream.as_ref()
+    }
+
+    pub(crate) fn set_upstream(&mut self, upstream: Option<PeerIdentifier>) {
+        self.upstream = upstream;
+    }
+
     pub(crate) fn role(&self) -> ReplicationRole {
         self.state.role.clone()
     }
@@ -119,8 +342,8 @@ impl PeerState {
         }
     }
 
-    pub(crate) fn from_file(path: &str) -> Vec<Self> {
-        let Some(contents) = Self::read_file_if_valid(path) else {
+    pub(crate) fn from_file(path: &str, keys: &KeyRing) -> Vec<Self> {
+        let Some(contents) = Self::read_file(path, keys) else {
             return vec![];
         };
 
@@ -138,15 +361,10 @@ impl PeerState {
         nodes
     }
 
-    fn read_file_if_valid(path: &str) -> Option<String> {
-        let metadata = std::fs::metadata(path).ok()?;
-        let modified = metadata.modified().ok()?;
-
-        if modified.elapsed().unwrap_or_default().as_secs() > 300 {
-            return None;
-        }
-
-        std::fs::read_to_string(path).ok()
+    // * Taken in however old it is; peers that are gone for good are given up on once rejoining fails
+    fn read_file(path: &str, keys: &KeyRing) -> Option<String> {
+        let contents = std::fs::read(path).ok()?;
+        String::from_utf8(keys.open_file(&contents).ok()?.into_owned()).ok()
     }
 
     fn extract_my_repl_id(contents: &str) -> Option<String> {
@@ -215,7 +433,7 @@ fn test_prioritize_nodes_with_same_replid() {
     write!(temp_file, "{file_content}").expect("Failed to write to temp file");
 
     // Read and prioritize nodes
-    let nodes = PeerState::from_file(temp_file.path().to_str().unwrap());
+    let nodes = PeerState::from_file(temp_file.path().to_str().unwrap(), &KeyRing::default());
 
     // There should be 4 nodes, all with priority 0 (same ID as myself)
     assert_eq!(nodes.len(), 5);
@@ -228,3 +446,23 @@ fn test_prioritize_nodes_with_same_replid() {
         println!("{node:?}");
     }
 }
+
+#[test]
+fn test_parse_node_info_with_ipv6_and_hostname() {
+    let node = PeerState::parse_node_info(
+        "[::1]:6000 myself,0196477d-f227-72f2-81eb-6a3703076de8 0 11 leader",
+    )
+    .unwrap();
+    assert_eq!(node.id(), &PeerIdentifier("[::1]:6000".into()));
+    assert!(node.is_self("[::1]:6000"));
+
+    let node = PeerState::parse_node_info(
+        "duva-1.duva.default.svc:6000 0196477d-f227-72f2-81eb-6a3703076de8 0 11 follower",
+    )
+    .unwrap();
+    assert_eq!(node.id(), &PeerIdentifier("duva-1.duva.default.svc:6000".into()));
+    assert_eq!(
+        node.format(&PeerIdentifier("[::1]:6000".into())),
+        "duva-1.duva.default.svc:6000 0196477d-f227-72f2-81eb-6a3703076de8 0 11 follower"
+    );
+}
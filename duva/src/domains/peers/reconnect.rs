@@ -0,0 +1,168 @@
+use super::peer::PeerState;
+use crate::prelude::PeerIdentifier;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// * Wait before the first attempt; each failed one doubles it, up to RECONNECT_BACKOFF_MAX
+pub(crate) const RECONNECT_BACKOFF_BASE: Duration = Duration::from_millis(500);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+// * Peers still unreachable after this many attempts are given up on, until gossip brings them back
+const RECONNECT_MAX_ATTEMPTS: u32 = 12;
+
+/// State of the link to a known peer, as the last field of `CLUSTER NODES` reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LinkState {
+    Connected,
+    // * Lost and being retried
+    Disconnected,
+    // * Forgotten or departed; never retried
+    Banned,
+}
+
+impl Display for LinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Self::Connected => write!(f, "connected"),
+            | Self::Disconnected => write!(f, "disconnected"),
+            | Self::Banned => write!(f, "banned"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LostPeer {
+    state: PeerState,
+    attempts: u32,
+    next_attempt: Instant,
+    banned: bool,
+}
+
+/// Peers this node lost its connection to. Those that went unresponsive are retried with exponential
+/// backoff and jitter until they are back or given up on; banned ones are only kept to be reported
+/// while their ban lasts.
+#[derive(Debug, Default)]
+pub(crate) struct Reconnects(BTreeMap<PeerIdentifier, LostPeer>);
+
+impl Reconnects {
+    pub(crate) fn track(&mut self, state: PeerState, now: Instant) {
+        self.0.entry(state.id().clone()).or_insert(LostPeer {
+            state,
+            attempts: 0,
+            next_attempt: now + backoff(0),
+            banned: false,
+        });
+    }
+
+    pub(crate) fn ban(&mut self, peer_id: &PeerIdentifier) {
+        if let Some(lost) = self.0.get_mut(peer_id) {
+            lost.banned = true;
+        }
+    }
+
+    /// Drops banned peers whose ban ran out; they stay gone unless they rejoin.
+    pub(crate) fn lift_bans(&mut self, still_banned: impl Fn(&PeerIdentifier) -> bool) {
+        self.0.retain(|peer_id, lost| !lost.banned || still_banned(peer_id));
+    }
+
+    pub(crate) fn forget(&mut self, peer_id: &PeerIdentifier) {
+        self.0.remove(peer_id);
+    }
+
+    /// Peers to attempt again as of `now`, each pushed back by its next backoff.
+    pub(crate) fn due(&mut self, now: Instant) -> Vec<PeerIdentifier> {
+        self.0.retain(|_, lost| lost.banned || lost.attempts < RECONNECT_MAX_ATTEMPTS);
+        self.0
+            .iter_mut()
+            .filter(|(_, lost)| !lost.banned && lost.next_attempt <= now)
+            .map(|(peer_id, lost)| {
+                lost.attempts += 1;
+                lost.next_attempt = now + backoff(lost.attempts);
+                peer_id.clone()
+            })
+            .collect()
+    }
+
+    pub(crate) fn links(&self) -> impl Iterator<Item = (&PeerState, LinkState)> {
+        self.0.values().map(|lost| {
+            (&lost.state, if lost.banned { LinkState::Banned } else { LinkState::Disconnected })
+        })
+    }
+}
+
+// * Drawn from the upper half of the window, so that nodes which lost the same peer do not retry in lockstep
+fn backoff(attempts: u32) -> Duration {
+    let window =
+        RECONNECT_BACKOFF_BASE.saturating_mul(1 << attempts.min(16)).min(RECONNECT_BACKOFF_MAX);
+    window / 2 + (window / 2).mul_f64(rand::random::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
+
+    fn lost(port: u16) -> PeerState {
+        PeerState::new(
+            &format!("127.0.0.1:{port}"),
+            0,
+            ReplicationId::Key("repl".into()),
+            ReplicationRole::Follower,
+        )
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        assert!(backoff(0) <= RECONNECT_BACKOFF_BASE);
+        assert!(backoff(0) >= RECONNECT_BACKOFF_BASE / 2);
+        assert!(backoff(3) >= RECONNECT_BACKOFF_BASE * 4);
+        assert!(backoff(40) <= RECONNECT_BACKOFF_MAX);
+        assert!(backoff(40) >= RECONNECT_BACKOFF_MAX / 2);
+    }
+
+    #[test]
+    fn test_due_retries_lost_peers_until_given_up() {
+        // GIVEN
+        let now = Instant::now();
+        let mut reconnects = Reconnects::default();
+        reconnects.track(lost(6380), now);
+
+        // THEN - not before the first backoff, then once per backoff
+        assert!(reconnects.due(now).is_empty());
+        let later = now + RECONNECT_BACKOFF_BASE;
+        assert_eq!(reconnects.due(later).len(), 1);
+        assert!(reconnects.due(later).is_empty());
+
+        // WHEN every attempt failed
+        let mut at = later;
+        for _ in 1..RECONNECT_MAX_ATTEMPTS {
+            at += RECONNECT_BACKOFF_MAX;
+            assert_eq!(reconnects.due(at).len(), 1);
+        }
+
+        // THEN
+        assert!(reconnects.due(at + RECONNECT_BACKOFF_MAX).is_empty());
+        assert_eq!(reconnects.links().count(), 0);
+    }
+
+    #[test]
+    fn test_banned_peers_are_reported_but_never_retried() {
+        let now = Instant::now();
+        let mut reconnects = Reconnects::default();
+        reconnects.track(lost(6380), now);
+        reconnects.track(lost(6381), now);
+        reconnects.ban(lost(6381).id());
+
+        let due = reconnects.due(now + RECONNECT_BACKOFF_MAX);
+        assert_eq!(due, vec![lost(6380).id().clone()]);
+        let links: Vec<_> = reconnects.links().map(|(_, link)| link).collect();
+        assert_eq!(links, vec![LinkState::Disconnected, LinkState::Banned]);
+
+        // WHEN the ban runs out
+        reconnects.lift_bans(|_| false);
+
+        // THEN
+        assert_eq!(reconnects.links().count(), 1);
+    }
+}
@@ -0,0 +1,94 @@
+use super::identifier::{PeerIdentifier, TPeerAddress};
+use std::str::FromStr;
+use tracing::debug;
+
+/// Addresses a node contacts to join its cluster, from `--cluster_seeds`: comma separated
+/// `host:port` entries, where the host may be a DNS name standing for every address it resolves to,
+/// the way a Kubernetes headless service stands for the pods behind it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ClusterSeeds(Vec<String>);
+
+impl FromStr for ClusterSeeds {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let seeds: Vec<String> =
+            s.split(',').map(str::trim).filter(|seed| !seed.is_empty()).map(String::from).collect();
+        if seeds.is_empty() {
+            anyhow::bail!("no cluster seeds given");
+        }
+        for seed in &seeds {
+            let valid = seed
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+            if !valid {
+                anyhow::bail!("invalid cluster seed '{seed}', expected host:port");
+            }
+        }
+        Ok(Self(seeds))
+    }
+}
+
+impl ClusterSeeds {
+    /// Resolves the seeds anew, as the addresses behind a DNS name change when pods are rescheduled.
+    /// Seeds that do not resolve are left out until they do.
+    pub(crate) async fn resolve(&self) -> Vec<PeerIdentifier> {
+        let mut resolved = Vec::new();
+        for seed in &self.0 {
+            let addrs = match tokio::net::lookup_host(seed.as_str()).await {
+                | Ok(addrs) => addrs,
+                | Err(err) => {
+                    debug!("cluster seed {seed} did not resolve: {err}");
+                    continue;
+                },
+            };
+            for addr in addrs {
+                let Ok(addr) = addr.to_string().bind_addr() else { continue };
+                let peer = PeerIdentifier(addr);
+                if !resolved.contains(&peer) {
+                    resolved.push(peer);
+                }
+            }
+        }
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seeds() {
+        let seeds: ClusterSeeds =
+            "127.0.0.1:6379, duva-headless.default.svc:6379,".parse().unwrap();
+        assert_eq!(
+            seeds,
+            ClusterSeeds(vec![
+                "127.0.0.1:6379".to_string(),
+                "duva-headless.default.svc:6379".to_string()
+            ])
+        );
+        assert!("".parse::<ClusterSeeds>().is_err());
+        assert!("127.0.0.1".parse::<ClusterSeeds>().is_err());
+        assert!(":6379".parse::<ClusterSeeds>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_drops_duplicates_and_unresolvable_seeds() {
+        // GIVEN
+        let seeds: ClusterSeeds =
+            "127.0.0.1:6379,127.0.0.1:6379,127.0.0.1:6380,unresolvable.invalid:6379"
+                .parse()
+                .unwrap();
+
+        // WHEN
+        let resolved = seeds.resolve().await;
+
+        // THEN
+        assert_eq!(
+            resolved,
+            vec![PeerIdentifier("127.0.0.1:6379".into()), PeerIdentifier("127.0.0.1:6380".into())]
+        );
+    }
+}
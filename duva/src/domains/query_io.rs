@@ -1,9 +1,11 @@
 use crate::domains::caches::cache_objects::{CacheValue, TypedValue};
 use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
 use crate::domains::cluster_actors::topology::Topology;
+use crate::domains::compression::Compression;
 use crate::domains::operation_logs::WriteOperation;
 use crate::domains::peers::command::{
-    ElectionVote, HeartBeat, MigrateBatch, MigrationBatchAck, ReplicationAck, RequestVote,
+    ElectionVote, ForwardRequest, ForwardResponse, HeartBeat, InstallSnapshot, MigrateBatch,
+    MigrationBatchAck, ReplicationAck, RequestVote, TimeoutNow,
 };
 use anyhow::{Context, Result};
 use bytes::{Bytes, BytesMut};
@@ -14,7 +16,9 @@ const FILE_PREFIX: char = '\u{0066}';
 const SIMPLE_STRING_PREFIX: char = '+';
 const BULK_STRING_PREFIX: char = '$';
 const ARRAY_PREFIX: char = '*';
+const INTEGER_PREFIX: char = ':';
 const APPEND_ENTRY_RPC_PREFIX: char = '^';
+const COMPRESSED_APPEND_ENTRY_RPC_PREFIX: char = 'z';
 const CLUSTER_HEARTBEAT_PREFIX: char = 'c';
 const TOPOLOGY_CHANGE_PREFIX: char = 't';
 const START_REBALANCE_PREFIX: char = 'T';
@@ -22,14 +26,33 @@ const REPLICATE_PREFIX: char = '#';
 const ACKS_PREFIX: char = '@';
 const REQUEST_VOTE_PREFIX: char = 'v';
 const REQUEST_VOTE_REPLY_PREFIX: char = 'r';
+const PRE_VOTE_PREFIX: char = 'p';
+const PRE_VOTE_REPLY_PREFIX: char = 'P';
 const SESSION_REQUEST_PREFIX: char = '!';
 const MIGRATE_BATCH_PREFIX: char = 'm';
 const MIGRATION_BATCH_ACK_PREFIX: char = 'M';
+const INSTALL_SNAPSHOT_PREFIX: char = 'S';
+const TIMEOUT_NOW_PREFIX: char = 'n';
+const FORWARD_REQUEST_PREFIX: char = 'q';
+const FORWARD_RESPONSE_PREFIX: char = 'Q';
+
+// * RESP3 types
+const MAP_PREFIX: char = '%';
+const DOUBLE_PREFIX: char = ',';
+const PUSH_PREFIX: char = '>';
 
 const ERR_PREFIX: char = '-';
 const NULL_PREFIX: char = '\u{0000}';
 pub(crate) const SERDE_CONFIG: bincode::config::Configuration = bincode::config::standard();
 
+pub const RESP2: u8 = 2;
+pub const RESP3: u8 = 3;
+
+/// Returned when the buffer ends in the middle of a frame, so the caller can wait for more bytes.
+#[derive(Debug, thiserror::Error)]
+#[error("Incomplete frame")]
+pub struct IncompleteFrame;
+
 #[macro_export]
 macro_rules! write_array {
     ($($x:expr),*) => {
@@ -43,6 +66,7 @@ pub enum QueryIO {
     Null,
     SimpleString(Bytes),
     BulkString(Bytes),
+    Integer(i64),
     Array(Vec<QueryIO>),
     SessionRequest {
         request_id: u64,
@@ -50,19 +74,32 @@ pub enum QueryIO {
     },
     Err(Bytes),
 
+    // RESP3 types
+    Map(Vec<(QueryIO, QueryIO)>),
+    Double(f64),
+    Push(Vec<QueryIO>),
+
     // custom types
     File(Bytes),
     AppendEntriesRPC(HeartBeat),
+    // * Sent to peers that negotiated compression; it is read back as `AppendEntriesRPC`
+    CompressedAppendEntriesRPC(HeartBeat, Compression),
     ClusterHeartBeat(HeartBeat),
     WriteOperation(WriteOperation),
     Ack(ReplicationAck),
     RequestVote(RequestVote),
     RequestVoteReply(ElectionVote),
+    PreVote(RequestVote),
+    PreVoteReply(ElectionVote),
 
     TopologyChange(Topology),
     StartRebalance,
     MigrateBatch(MigrateBatch),
     MigrationBatchAck(MigrationBatchAck),
+    InstallSnapshot(InstallSnapshot),
+    TimeoutNow(TimeoutNow),
+    ForwardRequest(ForwardRequest),
+    ForwardResponse(ForwardResponse),
 }
 
 impl QueryIO {
@@ -86,6 +123,7 @@ impl QueryIO {
                 byte_mut.extend_from_slice(b"\r\n");
                 byte_mut.freeze()
             },
+            | QueryIO::Integer(n) => format!("{INTEGER_PREFIX}{n}\r\n").into(),
             | QueryIO::File(f) => {
                 let file_len = f.len() * 2;
                 let mut hex_file = String::with_capacity(file_len + file_len.to_string().len() + 2);
@@ -124,9 +162,43 @@ impl QueryIO {
                 buffer.extend_from_slice(b"\r\n");
                 buffer.freeze()
             },
+            | QueryIO::Map(entries) => {
+                let mut buffer = BytesMut::with_capacity(entries.len() * 64 + 1 + entries.len());
+                buffer.extend_from_slice(format!("{MAP_PREFIX}{}\r\n", entries.len()).as_bytes());
+                for (key, value) in entries {
+                    buffer.extend_from_slice(&key.serialize());
+                    buffer.extend_from_slice(&value.serialize());
+                }
+                buffer.freeze()
+            },
+            | QueryIO::Double(d) => {
+                let repr = match d {
+                    | d if d.is_nan() => "nan".to_string(),
+                    | d if d == f64::INFINITY => "inf".to_string(),
+                    | d if d == f64::NEG_INFINITY => "-inf".to_string(),
+                    | d => d.to_string(),
+                };
+                format!("{DOUBLE_PREFIX}{repr}\r\n").into()
+            },
+            | QueryIO::Push(items) => {
+                let mut buffer = BytesMut::with_capacity(items.len() * 32 + 1 + items.len());
+                buffer.extend_from_slice(format!("{PUSH_PREFIX}{}\r\n", items.len()).as_bytes());
+                for item in items {
+                    buffer.extend_from_slice(&item.serialize());
+                }
+                buffer.freeze()
+            },
             | QueryIO::AppendEntriesRPC(heartbeat) => {
                 serialize_with_bincode(APPEND_ENTRY_RPC_PREFIX, &heartbeat)
             },
+            | QueryIO::CompressedAppendEntriesRPC(mut heartbeat, compression) => {
+                let entries = std::mem::take(&mut heartbeat.append_entries);
+                let entries = bincode::encode_to_vec(&entries, SERDE_CONFIG).unwrap();
+                serialize_with_bincode(
+                    COMPRESSED_APPEND_ENTRY_RPC_PREFIX,
+                    &(heartbeat, compression, compression.compress(&entries)),
+                )
+            },
             | QueryIO::WriteOperation(write_operation) => {
                 serialize_with_bincode(REPLICATE_PREFIX, &write_operation)
             },
@@ -137,6 +209,12 @@ impl QueryIO {
             | QueryIO::RequestVoteReply(request_vote_reply) => {
                 serialize_with_bincode(REQUEST_VOTE_REPLY_PREFIX, &request_vote_reply)
             },
+            | QueryIO::PreVote(request_vote) => {
+                serialize_with_bincode(PRE_VOTE_PREFIX, &request_vote)
+            },
+            | QueryIO::PreVoteReply(pre_vote_reply) => {
+                serialize_with_bincode(PRE_VOTE_REPLY_PREFIX, &pre_vote_reply)
+            },
             | QueryIO::ClusterHeartBeat(heart_beat_message) => {
                 serialize_with_bincode(CLUSTER_HEARTBEAT_PREFIX, &heart_beat_message)
             },
@@ -150,6 +228,34 @@ impl QueryIO {
             | QueryIO::MigrationBatchAck(migration_batch_ack) => {
                 serialize_with_bincode(MIGRATION_BATCH_ACK_PREFIX, &migration_batch_ack)
             },
+            | QueryIO::InstallSnapshot(install_snapshot) => {
+                serialize_with_bincode(INSTALL_SNAPSHOT_PREFIX, &install_snapshot)
+            },
+            | QueryIO::TimeoutNow(timeout_now) => {
+                serialize_with_bincode(TIMEOUT_NOW_PREFIX, &timeout_now)
+            },
+            | QueryIO::ForwardRequest(request) => {
+                serialize_with_bincode(FORWARD_REQUEST_PREFIX, &request)
+            },
+            | QueryIO::ForwardResponse(response) => {
+                serialize_with_bincode(FORWARD_RESPONSE_PREFIX, &response)
+            },
+        }
+    }
+
+    /// Downgrades RESP3-only types so that they can be sent to a RESP2 client.
+    /// Maps are flattened into arrays of alternating keys and values, doubles become bulk strings
+    /// and push frames become plain arrays.
+    pub fn into_resp2(self) -> QueryIO {
+        match self {
+            | QueryIO::Map(entries) => QueryIO::Array(
+                entries.into_iter().flat_map(|(k, v)| [k.into_resp2(), v.into_resp2()]).collect(),
+            ),
+            | QueryIO::Double(d) => QueryIO::BulkString(d.to_string().into()),
+            | QueryIO::Push(items) | QueryIO::Array(items) => {
+                QueryIO::Array(items.into_iter().map(QueryIO::into_resp2).collect())
+            },
+            | other => other,
         }
     }
 
@@ -158,10 +264,7 @@ impl QueryIO {
         T: std::str::FromStr<Err: std::error::Error + Sync + Send + 'static>,
     {
         match self {
-            | QueryIO::BulkString(s) => {
-                let string_value = String::from_utf8(s.to_vec())?;
-                Ok(string_value.parse::<T>()?)
-            },
+            | QueryIO::BulkString(s) => parse_line(&s),
             | _ => Err(anyhow::anyhow!("Expected command to be a bulk string")),
         }
     }
@@ -223,6 +326,9 @@ impl From<QueryIO> for Bytes {
 
 pub fn deserialize(buffer: impl Into<Bytes>) -> Result<(QueryIO, usize)> {
     let buffer: Bytes = buffer.into();
+    if buffer.is_empty() {
+        return Err(IncompleteFrame.into());
+    }
     match buffer[0] as char {
         | SIMPLE_STRING_PREFIX => {
             let (bytes, len) = parse_simple_string(buffer)?;
@@ -234,6 +340,10 @@ pub fn deserialize(buffer: impl Into<Bytes>) -> Result<(QueryIO, usize)> {
             let (bytes, len) = parse_bulk_string(buffer)?;
             Ok((QueryIO::BulkString(bytes), len))
         },
+        | INTEGER_PREFIX => {
+            let (line, len) = parse_simple_string(buffer)?;
+            Ok((QueryIO::Integer(parse_line(&line)?), len))
+        },
         | FILE_PREFIX => {
             let (bytes, len) = parse_file(buffer)?;
             Ok((QueryIO::File(bytes), len))
@@ -243,11 +353,21 @@ pub fn deserialize(buffer: impl Into<Bytes>) -> Result<(QueryIO, usize)> {
             Ok((QueryIO::Err(bytes), len))
         },
         | NULL_PREFIX => Ok((QueryIO::Null, 1)),
+        | MAP_PREFIX => parse_map(buffer),
+        | DOUBLE_PREFIX => parse_double(buffer),
+        | PUSH_PREFIX => {
+            let (items, len) = parse_aggregate(buffer)?;
+            Ok((QueryIO::Push(items), len))
+        },
 
         | APPEND_ENTRY_RPC_PREFIX => {
             let (heartbeat, len) = parse_heartbeat(buffer)?;
             Ok((QueryIO::AppendEntriesRPC(heartbeat), len))
         },
+        | COMPRESSED_APPEND_ENTRY_RPC_PREFIX => {
+            let (heartbeat, _, len) = parse_compressed_heartbeat(buffer)?;
+            Ok((QueryIO::AppendEntriesRPC(heartbeat), len))
+        },
         | CLUSTER_HEARTBEAT_PREFIX => {
             let (heartbeat, len) = parse_heartbeat(buffer)?;
             Ok((QueryIO::ClusterHeartBeat(heartbeat), len))
@@ -256,30 +376,59 @@ pub fn deserialize(buffer: impl Into<Bytes>) -> Result<(QueryIO, usize)> {
         | ACKS_PREFIX => parse_custom_type::<ReplicationAck>(buffer),
         | REQUEST_VOTE_PREFIX => parse_custom_type::<RequestVote>(buffer),
         | REQUEST_VOTE_REPLY_PREFIX => parse_custom_type::<ElectionVote>(buffer),
+        | PRE_VOTE_PREFIX => {
+            let (request_vote, len): (RequestVote, usize) = decode_with_bincode(&buffer)?;
+            Ok((QueryIO::PreVote(request_vote), len + 1))
+        },
+        | PRE_VOTE_REPLY_PREFIX => {
+            let (reply, len): (ElectionVote, usize) = decode_with_bincode(&buffer)?;
+            Ok((QueryIO::PreVoteReply(reply), len + 1))
+        },
         | TOPOLOGY_CHANGE_PREFIX => parse_custom_type::<Topology>(buffer),
         | START_REBALANCE_PREFIX => Ok((QueryIO::StartRebalance, 1)),
         | MIGRATE_BATCH_PREFIX => parse_custom_type::<MigrateBatch>(buffer),
         | MIGRATION_BATCH_ACK_PREFIX => parse_custom_type::<MigrationBatchAck>(buffer),
+        | INSTALL_SNAPSHOT_PREFIX => parse_custom_type::<InstallSnapshot>(buffer),
+        | TIMEOUT_NOW_PREFIX => parse_custom_type::<TimeoutNow>(buffer),
+        | FORWARD_REQUEST_PREFIX => parse_custom_type::<ForwardRequest>(buffer),
+        | FORWARD_RESPONSE_PREFIX => parse_custom_type::<ForwardResponse>(buffer),
         | _ => Err(anyhow::anyhow!("Not a known value type {:?}", buffer)),
     }
 }
 
+/// Like [`deserialize`], but compressed AppendEntries are read back as
+/// `CompressedAppendEntriesRPC` with the codec they were sent with, so that tests can tell it.
+#[cfg(test)]
+pub(crate) fn deserialize_as_sent(buffer: impl Into<Bytes>) -> Result<(QueryIO, usize)> {
+    let buffer: Bytes = buffer.into();
+    if buffer.first() != Some(&(COMPRESSED_APPEND_ENTRY_RPC_PREFIX as u8)) {
+        return deserialize(buffer);
+    }
+    let (heartbeat, compression, len) = parse_compressed_heartbeat(buffer)?;
+    Ok((QueryIO::CompressedAppendEntriesRPC(heartbeat, compression), len))
+}
+
 // +PING\r\n
 pub(crate) fn parse_simple_string(buffer: Bytes) -> Result<(Bytes, usize)> {
-    let (line, len) = read_until_crlf_exclusive(&buffer.slice(1..))
-        .ok_or(anyhow::anyhow!("Invalid simple string"))?;
-    Ok((line.into(), len + 1))
+    let (line, len) = read_until_crlf_exclusive(&buffer.slice(1..)).ok_or(IncompleteFrame)?;
+    Ok((line, len + 1))
 }
 
 fn parse_array(buffer: Bytes) -> Result<(QueryIO, usize)> {
+    let (elements, len) = parse_aggregate(buffer)?;
+    Ok((QueryIO::Array(elements), len))
+}
+
+// *2\r\n... or >2\r\n...
+fn parse_aggregate(buffer: Bytes) -> Result<(Vec<QueryIO>, usize)> {
     let mut offset = 0;
     offset += 1;
 
-    let (count_bytes, count_len) = read_until_crlf_exclusive(&buffer.slice(offset..))
-        .ok_or(anyhow::anyhow!("Invalid array length"))?;
+    let (count_bytes, count_len) =
+        read_until_crlf_exclusive(&buffer.slice(offset..)).ok_or(IncompleteFrame)?;
     offset += count_len;
 
-    let array_len = count_bytes.parse()?;
+    let array_len = parse_line(&count_bytes)?;
 
     let mut elements = Vec::with_capacity(array_len);
 
@@ -289,7 +438,41 @@ fn parse_array(buffer: Bytes) -> Result<(QueryIO, usize)> {
         elements.push(element);
     }
 
-    Ok((QueryIO::Array(elements), offset))
+    Ok((elements, offset))
+}
+
+// %2\r\n+key\r\n+value\r\n...
+fn parse_map(buffer: Bytes) -> Result<(QueryIO, usize)> {
+    let mut offset = 1;
+
+    let (count_bytes, count_len) =
+        read_until_crlf_exclusive(&buffer.slice(offset..)).ok_or(IncompleteFrame)?;
+    offset += count_len;
+
+    let map_len = parse_line(&count_bytes)?;
+    let mut entries = Vec::with_capacity(map_len);
+
+    for _ in 0..map_len {
+        let (key, key_len) = deserialize(buffer.slice(offset..))?;
+        offset += key_len;
+        let (value, value_len) = deserialize(buffer.slice(offset..))?;
+        offset += value_len;
+        entries.push((key, value));
+    }
+
+    Ok((QueryIO::Map(entries), offset))
+}
+
+// ,3.14\r\n
+fn parse_double(buffer: Bytes) -> Result<(QueryIO, usize)> {
+    let (line, len) = read_until_crlf_exclusive(&buffer.slice(1..)).ok_or(IncompleteFrame)?;
+    let value = match std::str::from_utf8(&line)? {
+        | "inf" => f64::INFINITY,
+        | "-inf" => f64::NEG_INFINITY,
+        | "nan" => f64::NAN,
+        | number => number.parse().context("Invalid double")?,
+    };
+    Ok((QueryIO::Double(value), len + 1))
 }
 
 fn parse_session_request(buffer: Bytes) -> Result<(QueryIO, usize)> {
@@ -297,19 +480,22 @@ fn parse_session_request(buffer: Bytes) -> Result<(QueryIO, usize)> {
     // ! to advance '!'
     offset += 1;
 
-    let (count_bytes, count_len) = read_until_crlf_exclusive(&buffer.slice(offset..))
-        .ok_or(anyhow::anyhow!("Invalid array length"))?;
+    let (count_bytes, count_len) =
+        read_until_crlf_exclusive(&buffer.slice(offset..)).ok_or(IncompleteFrame)?;
     offset += count_len;
-    let request_id = count_bytes.parse()?;
+    let request_id = parse_line(&count_bytes)?;
 
     // ! to advance '$'
     offset += 1;
+    if offset > buffer.len() {
+        return Err(IncompleteFrame.into());
+    }
 
-    let (count_bytes, count_len) = read_until_crlf_exclusive(&buffer.slice(offset..))
-        .ok_or(anyhow::anyhow!("Invalid array length"))?;
+    let (count_bytes, count_len) =
+        read_until_crlf_exclusive(&buffer.slice(offset..)).ok_or(IncompleteFrame)?;
     offset += count_len;
 
-    let array_len = count_bytes.parse()?;
+    let array_len = parse_line(&count_bytes)?;
 
     let mut elements = Vec::with_capacity(array_len);
 
@@ -325,28 +511,39 @@ pub fn parse_custom_type<T>(buffer: Bytes) -> Result<(QueryIO, usize)>
 where
     T: bincode::Decode<()> + Into<QueryIO>,
 {
-    let (encoded, len): (T, usize) =
-        bincode::decode_from_slice(&buffer.slice(1..), SERDE_CONFIG)
-            .map_err(|err| anyhow::anyhow!("Failed to decode heartbeat message: {:?}", err))?;
+    let (encoded, len): (T, usize) = decode_with_bincode(&buffer)?;
     Ok((encoded.into(), len + 1))
 }
 
 fn parse_heartbeat(buffer: Bytes) -> Result<(HeartBeat, usize)> {
-    let (encoded, len): (HeartBeat, usize) =
-        bincode::decode_from_slice(&buffer.slice(1..), SERDE_CONFIG)
-            .map_err(|err| anyhow::anyhow!("Failed to decode heartbeat message: {:?}", err))?;
+    let (encoded, len): (HeartBeat, usize) = decode_with_bincode(&buffer)?;
     Ok((encoded, len + 1))
 }
 
+fn parse_compressed_heartbeat(buffer: Bytes) -> Result<(HeartBeat, Compression, usize)> {
+    let ((mut heartbeat, compression, entries), len): ((HeartBeat, Compression, Vec<u8>), usize) =
+        decode_with_bincode(&buffer)?;
+    let entries = compression.decompress(&entries)?;
+    (heartbeat.append_entries, _) = bincode::decode_from_slice(&entries, SERDE_CONFIG)?;
+    Ok((heartbeat, compression, len + 1))
+}
+
+fn decode_with_bincode<T: bincode::Decode<()>>(buffer: &Bytes) -> Result<(T, usize)> {
+    bincode::decode_from_slice(&buffer.slice(1..), SERDE_CONFIG).map_err(|err| match err {
+        // * Large peer messages (e.g. snapshots) may span several reads
+        | bincode::error::DecodeError::UnexpectedEnd { .. } => IncompleteFrame.into(),
+        | err => anyhow::anyhow!("Failed to decode heartbeat message: {:?}", err),
+    })
+}
+
 fn parse_bulk_string(buffer: Bytes) -> Result<(Bytes, usize)> {
-    let (line, len) = read_until_crlf_exclusive(&buffer.slice(1..))
-        .ok_or(anyhow::anyhow!("Invalid bulk string"))?;
-    let content_len: usize = line.parse().context("Invalid bulk string length")?;
+    let (line, len) = read_until_crlf_exclusive(&buffer.slice(1..)).ok_or(IncompleteFrame)?;
+    let content_len: usize = parse_line(&line).context("Invalid bulk string length")?;
 
     let content_start = len + 1;
     let content_end = content_start + content_len;
     if content_end + 2 > buffer.len() {
-        return Err(anyhow::anyhow!("Incomplete bulk string"));
+        return Err(IncompleteFrame.into());
     }
 
     if &buffer[content_end..content_end + 2] != b"\r\n" {
@@ -362,7 +559,7 @@ fn parse_file(buffer: Bytes) -> Result<(Bytes, usize)> {
 
     // Adjust `len` to include the initial line and calculate `bulk_str_len`
     len += 1;
-    let content_len: usize = line.parse()?;
+    let content_len: usize = parse_line(&line)?;
 
     let file_content = &buffer.slice(len..(len + content_len));
 
@@ -374,11 +571,19 @@ fn parse_file(buffer: Bytes) -> Result<(Bytes, usize)> {
     Ok((file, len + content_len))
 }
 
-/// None if crlf not found.
+/// None if crlf not found. The line is a slice of `buffer`, not a copy.
+#[inline]
+pub(super) fn read_until_crlf_exclusive(buffer: &Bytes) -> Option<(Bytes, usize)> {
+    memchr::memmem::find(buffer, b"\r\n").map(|i| (buffer.slice(0..i), i + 2))
+}
+
+// * Parses straight from the frame, e.g. a length or an argument, without taking an owned string
 #[inline]
-pub(super) fn read_until_crlf_exclusive(buffer: &Bytes) -> Option<(String, usize)> {
-    memchr::memmem::find(buffer, b"\r\n")
-        .map(|i| (String::from_utf8_lossy(&buffer.slice(0..i)).to_string(), i + 2))
+fn parse_line<T>(line: &[u8]) -> Result<T>
+where
+    T: std::str::FromStr<Err: std::error::Error + Sync + Send + 'static>,
+{
+    Ok(std::str::from_utf8(line)?.parse::<T>()?)
 }
 
 fn serialize_with_bincode<T: bincode::Encode>(prefix: char, arg: &T) -> Bytes {
@@ -465,12 +670,14 @@ impl From<ReplicationRole> for Bytes {
 #[cfg(test)]
 mod test {
     use crate::domains::caches::cache_objects::CacheEntry;
+    use crate::domains::cluster_actors::forwarding::{ForwardId, ForwardedOp, ForwardedReply};
     use crate::domains::cluster_actors::hash_ring::{BatchId, HashRing};
     use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
     use crate::domains::operation_logs::WriteRequest;
     use crate::domains::peers::command::BannedPeer;
     use crate::domains::peers::identifier::PeerIdentifier;
     use crate::domains::peers::peer::PeerState;
+    use crate::domains::telemetry::TraceContext;
     use uuid::Uuid;
 
     use super::*;
@@ -527,6 +734,21 @@ mod test {
         assert_eq!(value, QueryIO::BulkString("".into()));
     }
 
+    #[test]
+    fn test_deserialize_bulk_string_is_binary_safe_and_borrows_the_buffer() {
+        // GIVEN
+        let buffer = Bytes::from_static(b"$4\r\n\r\n\xff\0\r\n");
+
+        // WHEN
+        let (value, len) = deserialize(buffer.clone()).unwrap();
+
+        // THEN
+        assert_eq!(len, 10);
+        let QueryIO::BulkString(bytes) = value else { panic!("expected a bulk string") };
+        assert_eq!(&bytes[..], b"\r\n\xff\0");
+        assert_eq!(bytes.as_ptr(), buffer[4..].as_ptr());
+    }
+
     #[test]
     fn test_deserialize_array() {
         // GIVEN
@@ -582,6 +804,96 @@ mod test {
         assert_eq!(serialized, Bytes::from("!30\r\n*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n"));
     }
 
+    #[test]
+    fn test_map_to_binary_back_to_map() {
+        // GIVEN
+        let map = QueryIO::Map(vec![
+            (QueryIO::BulkString("server".into()), QueryIO::BulkString("duva".into())),
+            (QueryIO::BulkString("proto".into()), QueryIO::Double(3.0)),
+        ]);
+
+        // WHEN
+        let serialized = map.clone().serialize();
+        let (deserialized, len) = deserialize(serialized.clone()).unwrap();
+
+        // THEN
+        assert_eq!(
+            serialized,
+            Bytes::from("%2\r\n$6\r\nserver\r\n$4\r\nduva\r\n$5\r\nproto\r\n,3\r\n")
+        );
+        assert_eq!(len, serialized.len());
+        assert_eq!(deserialized, map);
+    }
+
+    #[test]
+    fn test_deserialize_double() {
+        // GIVEN
+        let buffer = Bytes::from(",-1.5\r\n,inf\r\n");
+
+        // WHEN
+        let (value, len) = deserialize(buffer.clone()).unwrap();
+        let (infinity, _) = deserialize(buffer.slice(len..)).unwrap();
+
+        // THEN
+        assert_eq!(len, 7);
+        assert_eq!(value, QueryIO::Double(-1.5));
+        assert_eq!(infinity, QueryIO::Double(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_integer_to_binary_back_to_integer() {
+        // GIVEN
+        let integers = [QueryIO::Integer(3), QueryIO::Integer(-42)];
+
+        // WHEN
+        let serialized: Vec<Bytes> = integers.iter().cloned().map(QueryIO::serialize).collect();
+
+        // THEN
+        assert_eq!(serialized, vec![Bytes::from(":3\r\n"), Bytes::from(":-42\r\n")]);
+        for (integer, serialized) in integers.into_iter().zip(serialized) {
+            let len = serialized.len();
+            let (deserialized, consumed) = deserialize(serialized).unwrap();
+            assert_eq!(deserialized, integer);
+            assert_eq!(consumed, len);
+        }
+    }
+
+    #[test]
+    fn test_push_to_binary_back_to_push() {
+        // GIVEN
+        let push = QueryIO::Push(vec![
+            QueryIO::BulkString("invalidate".into()),
+            QueryIO::Array(vec![QueryIO::BulkString("foo".into())]),
+        ]);
+
+        // WHEN
+        let (deserialized, _) = deserialize(push.clone().serialize()).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, push);
+    }
+
+    #[test]
+    fn test_into_resp2_flattens_resp3_types() {
+        // GIVEN
+        let map = QueryIO::Map(vec![(
+            QueryIO::BulkString("proto".into()),
+            QueryIO::Push(vec![QueryIO::Double(2.5)]),
+        )]);
+
+        // WHEN
+        let downgraded = map.into_resp2();
+
+        // THEN
+        assert_eq!(
+            downgraded,
+            QueryIO::Array(vec![
+                QueryIO::BulkString("proto".into()),
+                QueryIO::Array(vec![QueryIO::BulkString("2.5".into())]),
+            ])
+        );
+    }
+
     #[test]
     fn test_parse_file() {
         // GIVEN
@@ -602,6 +914,7 @@ mod test {
             log_index: 1,
             term: 0,
             session_req: None,
+            timestamp: 0,
         });
 
         // WHEN
@@ -637,8 +950,16 @@ mod test {
         let me = PeerIdentifier::new("127.0.0.1", 6035);
         let leader = ReplicationId::Undecided;
         let banned_list = vec![
-            BannedPeer { p_id: PeerIdentifier("localhost:28889".into()), ban_time: 3553 },
-            BannedPeer { p_id: PeerIdentifier("localhost:22888".into()), ban_time: 3556 },
+            BannedPeer {
+                p_id: PeerIdentifier("localhost:28889".into()),
+                ban_time: 3553,
+                duration: Some(60),
+            },
+            BannedPeer {
+                p_id: PeerIdentifier("localhost:22888".into()),
+                ban_time: 3556,
+                duration: None,
+            },
         ];
         let heartbeat = HeartBeat {
             from: me.clone(),
@@ -659,6 +980,7 @@ mod test {
                     log_index: 1,
                     term: 0,
                     session_req: None,
+                    timestamp: 0,
                 },
                 WriteOperation {
                     request: WriteRequest::Set {
@@ -669,6 +991,7 @@ mod test {
                     log_index: 2,
                     term: 1,
                     session_req: None,
+                    timestamp: 0,
                 },
             ],
             cluster_nodes: vec![
@@ -709,7 +1032,12 @@ mod test {
                     ReplicationRole::Follower,
                 ),
             ],
+            nodes_digest: 42,
             hashring: None,
+            priority: 0,
+            weight: 1,
+            upstream: None,
+            trace: None,
         };
         let replicate = QueryIO::AppendEntriesRPC(heartbeat);
 
@@ -722,6 +1050,51 @@ mod test {
         assert_eq!(value, replicate);
     }
 
+    #[test]
+    fn test_compressed_heartbeat_is_read_back_as_append_entries() {
+        // GIVEN
+        let heartbeat = HeartBeat {
+            from: PeerIdentifier("leader".into()),
+            term: 1,
+            hwm: 2,
+            append_entries: (1..=20)
+                .map(|log_index| WriteOperation {
+                    request: WriteRequest::Set {
+                        key: format!("key{log_index}").into(),
+                        value: "value".repeat(20).into(),
+                        expires_at: None,
+                    },
+                    log_index,
+                    term: 1,
+                    session_req: None,
+                    timestamp: 0,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let plain = QueryIO::AppendEntriesRPC(heartbeat.clone()).serialize();
+
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            // WHEN
+            let serialized =
+                QueryIO::CompressedAppendEntriesRPC(heartbeat.clone(), compression).serialize();
+            let (deserialized, len) = deserialize(serialized.clone()).unwrap();
+
+            // THEN
+            assert!(serialized.len() < plain.len());
+            assert_eq!(len, serialized.len());
+            assert_eq!(deserialized, QueryIO::AppendEntriesRPC(heartbeat.clone()));
+            assert_eq!(
+                deserialize_as_sent(serialized).unwrap(),
+                (QueryIO::CompressedAppendEntriesRPC(heartbeat.clone(), compression), len)
+            );
+        }
+        assert_eq!(
+            deserialize_as_sent(plain.clone()).unwrap(),
+            (QueryIO::AppendEntriesRPC(heartbeat), plain.len())
+        );
+    }
+
     #[test]
     fn test_request_vote_to_binary_back_to_request_vote() {
         // GIVEN
@@ -741,6 +1114,28 @@ mod test {
         assert_eq!(deserialized, request_vote);
     }
 
+    #[test]
+    fn test_pre_vote_serde() {
+        // GIVEN
+        let request_vote = RequestVote {
+            term: 2,
+            candidate_id: PeerIdentifier("me".into()),
+            last_log_index: 5,
+            last_log_term: 1,
+        };
+
+        for query in [
+            QueryIO::PreVote(request_vote),
+            QueryIO::PreVoteReply(ElectionVote { term: 1, vote_granted: true }),
+        ] {
+            // WHEN
+            let (deserialized, _) = deserialize(query.clone().serialize()).unwrap();
+
+            // THEN
+            assert_eq!(deserialized, query);
+        }
+    }
+
     #[test]
     fn test_request_vote_reply_to_binary_back_to_request_vote_reply() {
         // GIVEN
@@ -808,7 +1203,12 @@ mod test {
             ban_list: vec![],
             append_entries: vec![],
             cluster_nodes: vec![],
+            nodes_digest: 0,
             hashring: Some(Box::new(ring)),
+            priority: 3,
+            weight: 2,
+            upstream: Some(PeerIdentifier::new("127.0.0.1", 3345)),
+            trace: Some(TraceContext { trace_id: 7, span_id: 3 }),
         };
 
         let query_io = QueryIO::ClusterHeartBeat(heartbeat.clone());
@@ -827,6 +1227,7 @@ mod test {
         assert_eq!(deserialized_ring.get_pnode_count(), ring_to_cmp.get_pnode_count());
 
         assert_eq!(deserialized_ring, ring_to_cmp);
+        assert_eq!(deserialized_heartbeat.trace, heartbeat.trace);
         assert!(!ring_to_cmp.get_virtual_nodes().is_empty());
         assert!(!deserialized_ring.get_virtual_nodes().is_empty());
     }
@@ -837,6 +1238,7 @@ mod test {
         let migrate_batch = MigrateBatch {
             batch_id: BatchId(Uuid::now_v7().to_string()),
             cache_entries: vec![CacheEntry::new("foo", "bar")],
+            trace: Some(TraceContext { trace_id: 7, span_id: 3 }),
         };
         let query_io = QueryIO::MigrateBatch(migrate_batch.clone());
 
@@ -872,4 +1274,66 @@ mod test {
         assert_eq!(deserialized_migration_batch_ack.batch_id, migration_batch_ack.batch_id);
         assert_eq!(deserialized_migration_batch_ack.success, migration_batch_ack.success);
     }
+
+    #[test]
+    fn test_install_snapshot_serde() {
+        // GIVEN
+        let install_snapshot = InstallSnapshot {
+            from: PeerIdentifier::new("127.0.0.1", 6379),
+            term: 3,
+            last_included_index: 42,
+            last_included_term: 2,
+            offset: 0,
+            data: vec![1; 4096],
+            done: true,
+        };
+        let query_io = QueryIO::InstallSnapshot(install_snapshot);
+
+        // WHEN
+        let serialized = query_io.clone().serialize();
+        let truncated = deserialize(serialized.slice(..serialized.len() / 2)).unwrap_err();
+        let (deserialized, len) = deserialize(serialized.clone()).unwrap();
+
+        // THEN
+        assert!(truncated.downcast_ref::<IncompleteFrame>().is_some());
+        assert_eq!(deserialized, query_io);
+        assert_eq!(len, serialized.len());
+    }
+
+    #[test]
+    fn test_timeout_now_serde() {
+        // GIVEN
+        let query_io = QueryIO::TimeoutNow(TimeoutNow {
+            from: PeerIdentifier::new("127.0.0.1", 6379),
+            term: 3,
+        });
+
+        // WHEN
+        let (deserialized, _) = deserialize(query_io.clone().serialize()).unwrap();
+
+        // THEN
+        assert_eq!(deserialized, query_io);
+    }
+
+    #[test]
+    fn test_forward_serde() {
+        // GIVEN
+        let id = ForwardId(Uuid::now_v7().to_string());
+        let request = QueryIO::ForwardRequest(ForwardRequest {
+            id: id.clone(),
+            op: ForwardedOp::MGet(vec!["a".into(), "b".into()]),
+        });
+        let response = QueryIO::ForwardResponse(ForwardResponse {
+            id,
+            reply: ForwardedReply::Values(vec![Some(b"1".to_vec()), None]),
+        });
+
+        // WHEN
+        let (deserialized_request, _) = deserialize(request.clone().serialize()).unwrap();
+        let (deserialized_response, _) = deserialize(response.clone().serialize()).unwrap();
+
+        // THEN
+        assert_eq!(deserialized_request, request);
+        assert_eq!(deserialized_response, response);
+    }
 }
@@ -8,31 +8,34 @@ use super::{
     },
 };
 use crate::domains::saves::snapshot::Metadata;
-use crate::domains::{
-    IoError, caches::cache_objects::CacheEntry, cluster_actors::replication::ReplicationId,
-};
+use crate::domains::saves::status::SaveStatus;
+use crate::domains::{IoError, caches::cache_objects::CacheEntry};
 use tokio::io::AsyncWriteExt;
 
 pub struct SaveActor {
     pub(crate) target: SaveTarget,
     pub(crate) meta: SaveMeta,
+    pub(crate) status: Option<SaveStatus>,
 }
 
 impl SaveActor {
     pub(crate) async fn new(
         target: SaveTarget,
         num_of_shards: usize,
-        repl_id: ReplicationId,
-        current_offset: u64,
+        metadata: Metadata,
     ) -> anyhow::Result<Self> {
-        let meta = SaveMeta::new(num_of_shards, repl_id, current_offset);
-        let mut processor = Self { target, meta };
-        processor.encode_meta().await?;
+        let meta = SaveMeta::new(num_of_shards);
+        let mut processor = Self { target, meta, status: None };
+        processor.encode_meta(metadata).await?;
         Ok(processor)
     }
 
-    pub async fn encode_meta(&mut self) -> anyhow::Result<()> {
-        let metadata = Metadata { repl_id: self.meta.repl_id.clone(), log_idx: self.meta.offset };
+    pub(crate) fn with_status(mut self, status: SaveStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub async fn encode_meta(&mut self, metadata: Metadata) -> anyhow::Result<()> {
         let meta = [encode_header()?, encode_metadata(metadata)?, encode_database_info(0)?];
         self.target.write(&meta.concat()).await?;
         Ok(())
@@ -42,6 +45,9 @@ impl SaveActor {
             | SaveCommand::LocalShardSize { table_size, expiry_size } => {
                 self.meta.total_key_value_table_size += table_size;
                 self.meta.total_expires_table_size += expiry_size;
+                if let Some(status) = &self.status {
+                    status.add_total(table_size);
+                }
                 self.meta.num_of_saved_table_size_actor -= 1;
                 if self.meta.num_of_saved_table_size_actor == 0 {
                     self.target
@@ -73,10 +79,14 @@ impl SaveActor {
 
     async fn encode_chunk_queue(&mut self) -> anyhow::Result<()> {
         while let Some(chunk) = self.meta.chunk_queue.pop_front() {
+            let saved = chunk.len();
             for kvs in chunk {
                 let encoded_chunk = kvs.encode_with_key()?;
                 self.target.write(&encoded_chunk).await?;
             }
+            if let Some(status) = &self.status {
+                status.add_saved(saved);
+            }
         }
         Ok(())
     }
@@ -113,20 +123,16 @@ pub struct SaveMeta {
     pub(crate) total_expires_table_size: usize,
     pub(crate) chunk_queue: VecDeque<Vec<CacheEntry>>,
     pub(crate) num_of_cache_actors: usize,
-    pub(crate) repl_id: ReplicationId,
-    pub(crate) offset: u64,
 }
 
 impl SaveMeta {
-    pub(crate) fn new(num_of_cache_actors: usize, repl_id: ReplicationId, offset: u64) -> Self {
+    pub(crate) fn new(num_of_cache_actors: usize) -> Self {
         Self {
             num_of_saved_table_size_actor: num_of_cache_actors,
             total_key_value_table_size: 0,
             total_expires_table_size: 0,
             chunk_queue: VecDeque::new(),
             num_of_cache_actors,
-            repl_id,
-            offset,
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::domains::caches::cache_objects::{CacheEntry, CacheValue, TypedValue};
 use crate::domains::cluster_actors::replication::ReplicationId;
+use crate::domains::leases::actor::LeaseStates;
 use crate::domains::saves::endec::{
     DATABASE_SECTION_INDICATOR, DATABASE_TABLE_SIZE_INDICATOR,
     EXPIRY_TIME_IN_MILLISECONDS_INDICATOR, EXPIRY_TIME_IN_SECONDS_INDICATOR, HEADER_MAGIC_STRING,
@@ -187,17 +188,32 @@ impl<'a> BytesDecoder<'a, DecoderInit> {
 
 impl<'a> BytesDecoder<'a, HeaderReady> {
     pub fn load_metadata(mut self) -> Result<BytesDecoder<'a, MetadataReady>> {
-        let mut metadata = Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 };
+        let mut metadata = Metadata::default();
         while self.check_indicator(METADATA_SECTION_INDICATOR) {
             let (key, value) = self
                 .try_extract_metadata_key_value()
                 .context("metadata loading: key value extraction failed")?;
 
+            // * Every value but the leases, which carry keys, is text
+            if key == "leases" {
+                metadata.leases = LeaseStates::decode(&value).context("leases parse fail")?;
+                continue;
+            }
+            let value = String::from_utf8(value.to_vec()).context("metadata value decode fail")?;
             match key.as_str() {
                 | "repl-id" => metadata.repl_id = ReplicationId::Key(value),
                 | "repl-offset" => {
                     metadata.log_idx = value.parse().context("repl-offset parse fail")?
                 },
+                | "repl-term" => {
+                    metadata.log_term = value.parse().context("repl-term parse fail")?
+                },
+                | "client-sessions" => {
+                    metadata.sessions = value.parse().context("client-sessions parse fail")?
+                },
+                | "transactions" => {
+                    metadata.transactions = value.parse().context("transactions parse fail")?
+                },
                 | var => {
                     println!("Unknown metadata key: {var}");
                 },
@@ -208,10 +224,10 @@ impl<'a> BytesDecoder<'a, HeaderReady> {
             state: MetadataReady { metadata, header: self.state.0 },
         })
     }
-    pub fn try_extract_metadata_key_value(&mut self) -> anyhow::Result<(String, String)> {
+    pub fn try_extract_metadata_key_value(&mut self) -> anyhow::Result<(String, Bytes)> {
         self.remove_identifier();
         let key_data = self.string_decode().context("key decode fail")?;
-        let value_data = self.string_decode().context("value decode fail")?;
+        let value_data = self.bytes_decode().context("value decode fail")?;
 
         Ok((key_data, value_data))
     }
@@ -341,9 +357,9 @@ impl BytesDecoder<'_, MetadataReady> {
         Ok(StoredDuration::Milliseconds(result))
     }
 
-    pub fn try_extract_key_bytes(&mut self) -> Result<(String, Bytes)> {
+    pub fn try_extract_key_bytes(&mut self) -> Result<(Bytes, Bytes)> {
         self.remove_identifier();
-        let key_data = self.string_decode().context("key decode fail")?;
+        let key_data = self.bytes_decode().context("key decode fail")?;
         let value_data = self.bytes_decode().context("value decode fail")?;
 
         Ok((key_data, value_data))
@@ -479,7 +495,14 @@ mod test {
         let mut bytes_handler = BytesDecoder::<MetadataReady> {
             data,
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
@@ -489,11 +512,11 @@ mod test {
         assert_eq!(db_section.storage.len(), 3);
 
         let cache_entry = &db_section.storage[0];
-        assert_eq!(cache_entry.key(), "foobar");
+        assert_eq!(cache_entry.key(), b"foobar");
         assert_eq!(cache_entry.value, "bazqux");
 
         let cache_entry = &db_section.storage[1];
-        assert_eq!(cache_entry.key(), "foo");
+        assert_eq!(cache_entry.key(), b"foo");
         assert_eq!(cache_entry.value, "bar");
         assert!(cache_entry.expiry().is_some());
         assert_eq!(
@@ -507,13 +530,20 @@ mod test {
         let mut bytes_handler = BytesDecoder::<MetadataReady> {
             data: &[0x00, 0x03, 0x62, 0x61, 0x7A, 0x03, 0x71, 0x75, 0x78],
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
 
         let key_value = bytes_handler.try_key_value().expect("Failed to extract key value expiry");
-        assert_eq!(key_value.key(), "baz");
+        assert_eq!(key_value.key(), b"baz");
         assert_eq!(key_value.value, "qux");
         assert!(key_value.expiry().is_none());
 
@@ -528,14 +558,21 @@ mod test {
                 0x03, 0x71, 0x75, 0x78,
             ],
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
 
         let key_value = bytes_handler.try_key_value().unwrap();
 
-        assert_eq!(key_value.key(), "baz");
+        assert_eq!(key_value.key(), b"baz");
         assert_eq!(key_value.value, "qux");
         assert!(key_value.expiry().is_some());
         assert!(bytes_handler.data.is_empty());
@@ -548,13 +585,20 @@ mod test {
                 0xFD, 0x52, 0xED, 0x2A, 0x66, 0x00, 0x03, 0x62, 0x61, 0x7A, 0x03, 0x71, 0x75, 0x78,
             ],
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
 
         let key_value = bytes_handler.try_key_value().unwrap();
-        assert_eq!(key_value.key(), "baz");
+        assert_eq!(key_value.key(), b"baz");
         assert_eq!(key_value.value, "qux");
         assert!(key_value.expiry().is_some());
     }
@@ -566,7 +610,14 @@ mod test {
                 0xFF, 0x52, 0xED, 0x2A, 0x66, 0x00, 0x03, 0x62, 0x61, 0x7A, 0x03, 0x71, 0x75, 0x78,
             ],
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
@@ -608,7 +659,14 @@ mod test {
         let metadata = bytes_handler.load_metadata().unwrap();
         assert_eq!(
             metadata.state.metadata,
-            Metadata { repl_id: ReplicationId::Undecided, log_idx: Default::default() }
+            Metadata {
+                repl_id: ReplicationId::Undecided,
+                log_idx: Default::default(),
+                log_term: Default::default(),
+                sessions: Default::default(),
+                leases: Default::default(),
+                transactions: Default::default()
+            }
         );
     }
 
@@ -624,7 +682,14 @@ mod test {
         let bytes_handler = BytesDecoder::<MetadataReady> {
             data: data.as_slice(),
             state: MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
@@ -676,11 +741,11 @@ mod test {
         assert_eq!(rdb_file.database[0].storage.len(), 2);
 
         let cache_entry = &rdb_file.database[0].storage[0];
-        assert_eq!(cache_entry.key(), "foo2");
+        assert_eq!(cache_entry.key(), b"foo2");
         assert_eq!(cache_entry.value, "bar2");
 
         let cache_entry = &rdb_file.database[0].storage[1];
-        assert_eq!(cache_entry.key(), "foo");
+        assert_eq!(cache_entry.key(), b"foo");
         assert_eq!(cache_entry.value, "bar");
         assert!(cache_entry.expiry().is_none());
 
@@ -41,14 +41,39 @@ pub(crate) fn encode_metadata(metadata: Metadata) -> Result<Vec<u8>> {
     let mut result = Vec::new();
     result.push(METADATA_SECTION_INDICATOR);
     result.extend_from_slice(&encode_key_bytes(
-        "repl-id",
+        b"repl-id",
         &bytes::Bytes::from(metadata.repl_id.to_string()),
     )?);
     result.push(METADATA_SECTION_INDICATOR);
     result.extend_from_slice(&encode_key_bytes(
-        "repl-offset",
+        b"repl-offset",
         &bytes::Bytes::from(metadata.log_idx.to_string()),
     )?);
+    if metadata.log_term > 0 {
+        result.push(METADATA_SECTION_INDICATOR);
+        result.extend_from_slice(&encode_key_bytes(
+            b"repl-term",
+            &bytes::Bytes::from(metadata.log_term.to_string()),
+        )?);
+    }
+    if !metadata.sessions.is_empty() {
+        result.push(METADATA_SECTION_INDICATOR);
+        result.extend_from_slice(&encode_key_bytes(
+            b"client-sessions",
+            &bytes::Bytes::from(metadata.sessions.to_string()),
+        )?);
+    }
+    if !metadata.leases.is_empty() {
+        result.push(METADATA_SECTION_INDICATOR);
+        result.extend_from_slice(&encode_key_bytes(b"leases", &metadata.leases.encode())?);
+    }
+    if !metadata.transactions.is_empty() {
+        result.push(METADATA_SECTION_INDICATOR);
+        result.extend_from_slice(&encode_key_bytes(
+            b"transactions",
+            &bytes::Bytes::from(metadata.transactions.to_string()),
+        )?);
+    }
     Ok(result)
 }
 pub(crate) fn encode_database_info(index: usize) -> Result<Vec<u8>> {
@@ -74,9 +99,12 @@ pub(crate) fn encode_checksum(checksum: &[u8]) -> Result<Vec<u8>> {
     Ok(result)
 }
 
-fn encode_key_bytes(key: &str, value: &[u8]) -> Result<Vec<u8>> {
+fn encode_key_bytes(key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
     let mut result = Vec::new();
-    let key = encode_string(key.len(), key)?;
+    let key = match std::str::from_utf8(key) {
+        | Ok(key) => encode_string(key.len(), key)?,
+        | Err(_) => encode_bytes(key.len(), key)?,
+    };
     let value = encode_bytes(value.len(), value)?;
     result.extend_from_slice(&key);
     result.extend_from_slice(&value);
@@ -361,7 +389,14 @@ mod test {
 
     #[test]
     fn test_encode_metadata() {
-        let metadata = Metadata { repl_id: ReplicationId::Key("key1".to_string()), log_idx: 123 };
+        let metadata = Metadata {
+            repl_id: ReplicationId::Key("key1".to_string()),
+            log_idx: 123,
+            log_term: Default::default(),
+            sessions: Default::default(),
+            leases: Default::default(),
+            transactions: Default::default(),
+        };
         let encoded = encode_metadata(metadata).unwrap();
         let expected = vec![
             METADATA_SECTION_INDICATOR,
@@ -401,10 +436,10 @@ mod test {
 
     #[test]
     fn test_cache_value_encode_with_binary_data() {
-        // Test with binary data that's not valid UTF-8
+        // Test with a key and data that are not valid UTF-8
         let binary_data = vec![0xFF, 0xFE, 0xFD, 0xFC, 0xFB];
         let value = CacheEntry::new(
-            "binary_key",
+            bytes::Bytes::from_static(b"binary_key\xff"),
             TypedValue::String(bytes::Bytes::from(binary_data.clone())),
         );
         let encoded = value.encode_with_key().unwrap();
@@ -413,13 +448,20 @@ mod test {
         let mut decoder = crate::domains::saves::endec::decoder::BytesDecoder {
             data: &encoded,
             state: crate::domains::saves::endec::decoder::MetadataReady {
-                metadata: Metadata { repl_id: ReplicationId::Undecided, log_idx: 0 },
+                metadata: Metadata {
+                    repl_id: ReplicationId::Undecided,
+                    log_idx: 0,
+                    log_term: Default::default(),
+                    sessions: Default::default(),
+                    leases: Default::default(),
+                    transactions: Default::default(),
+                },
                 header: "".into(),
             },
         };
 
         let decoded_entry = decoder.try_key_value().unwrap();
-        assert_eq!(decoded_entry.key(), "binary_key");
+        assert_eq!(decoded_entry.key(), b"binary_key\xff");
         assert_eq!(decoded_entry.value, binary_data.as_slice());
     }
 }
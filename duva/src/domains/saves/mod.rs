@@ -3,3 +3,4 @@ pub mod command;
 pub mod endec;
 mod service;
 pub mod snapshot;
+pub mod status;
@@ -0,0 +1,108 @@
+//! Serialization of a single value for `DUMP` and `RESTORE`.
+//!
+//! The layout is the one Redis uses: the value type and its RDB encoding, the RDB version it was
+//! written with (2 bytes, little endian) and a CRC-64 of everything before it. Command arguments are
+//! text, so the payload travels hex encoded.
+use super::redis_rdb_loader::read_dumped_value;
+use super::redis_rdb_writer::{crc64, value_type, write_value};
+use crate::domains::caches::cache_objects::TypedValue;
+use anyhow::{Result, anyhow};
+
+// * Same version as the dumps written by EXPORT; payloads from newer versions are refused
+const DUMP_VERSION: u16 = 11;
+
+pub(crate) struct DumpPayload {}
+
+impl DumpPayload {
+    pub(crate) fn encode(value: &TypedValue) -> Option<String> {
+        let mut payload = vec![value_type(value)?];
+        write_value(&mut payload, value);
+        payload.extend_from_slice(&DUMP_VERSION.to_le_bytes());
+        let checksum = crc64(&payload);
+        payload.extend_from_slice(&checksum.to_le_bytes());
+        Some(payload.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    pub(crate) fn decode(payload: &str) -> Result<TypedValue> {
+        let corrupted = || anyhow!("ERR DUMP payload version or checksum are wrong");
+        let payload = hex_decode(payload).ok_or_else(corrupted)?;
+        if payload.len() < 10 {
+            return Err(corrupted());
+        }
+        let (body, checksum) = payload.split_at(payload.len() - 8);
+        let version = u16::from_le_bytes([body[body.len() - 2], body[body.len() - 1]]);
+        if version > DUMP_VERSION || checksum != crc64(body).to_le_bytes() {
+            return Err(corrupted());
+        }
+
+        match read_dumped_value(&body[..body.len() - 2]) {
+            | Ok(Some(value)) => Ok(value),
+            | _ => Err(anyhow!("ERR Bad data format")),
+        }
+    }
+}
+
+fn hex_decode(payload: &str) -> Option<Vec<u8>> {
+    if !payload.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..payload.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(payload.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_dump_and_restore_values() {
+        for value in [
+            TypedValue::String(Bytes::from("bar")),
+            TypedValue::String(Bytes::from("x".repeat(100))),
+            TypedValue::List(vec![Bytes::from("a"), Bytes::from("b")]),
+        ] {
+            let payload = DumpPayload::encode(&value).unwrap();
+            assert_eq!(DumpPayload::decode(&payload).unwrap(), value);
+        }
+        assert_eq!(DumpPayload::encode(&TypedValue::Null), None);
+    }
+
+    #[test]
+    fn test_matches_redis_layout() {
+        // * DUMP of "bar" as Redis 7.2 returns it
+        let payload = DumpPayload::encode(&TypedValue::String(Bytes::from("bar"))).unwrap();
+        assert_eq!(payload, payload_of(b"\x00\x03bar", 11));
+        assert_eq!(&payload[..14], "00036261720b00");
+    }
+
+    fn payload_of(body: &[u8], version: u16) -> String {
+        let mut payload = body.to_vec();
+        payload.extend_from_slice(&version.to_le_bytes());
+        let checksum = crc64(&payload);
+        payload.extend_from_slice(&checksum.to_le_bytes());
+        payload.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn test_rejects_corrupted_or_newer_payloads() {
+        let payload = payload_of(b"\x00\x03bar", DUMP_VERSION);
+        let mut flipped = payload.clone();
+        flipped.replace_range(4..6, "00");
+        let newer = payload_of(b"\x00\x03bar", DUMP_VERSION + 1);
+
+        for payload in [flipped.as_str(), newer.as_str(), "zz", "00", &payload[1..]] {
+            let err = DumpPayload::decode(payload).unwrap_err();
+            assert_eq!(err.to_string(), "ERR DUMP payload version or checksum are wrong");
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsupported_types() {
+        // * A hash holding f => v
+        let payload = payload_of(b"\x04\x01\x01f\x01v", DUMP_VERSION);
+        assert_eq!(DumpPayload::decode(&payload).unwrap_err().to_string(), "ERR Bad data format");
+    }
+}
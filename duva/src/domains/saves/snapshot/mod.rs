@@ -1,6 +1,13 @@
+pub mod dump_payload;
+pub mod redis_rdb_loader;
+pub mod redis_rdb_writer;
 pub mod snapshot_loader;
 use crate::domains::{
-    caches::cache_objects::CacheEntry, cluster_actors::replication::ReplicationId,
+    caches::cache_objects::CacheEntry,
+    cluster_actors::{
+        actor::client_sessions::ClientSessions, replication::ReplicationId, transactions::TxnStates,
+    },
+    leases::actor::LeaseStates,
 };
 
 #[allow(dead_code)]
@@ -30,7 +37,17 @@ impl Snapshot {
     }
 
     pub(crate) fn default_with_repl_id(repl_id: ReplicationId) -> Self {
-        Self { metadata: Metadata { repl_id, log_idx: Default::default() }, ..Default::default() }
+        Self {
+            metadata: Metadata {
+                repl_id,
+                log_idx: Default::default(),
+                log_term: Default::default(),
+                sessions: Default::default(),
+                leases: Default::default(),
+                transactions: Default::default(),
+            },
+            ..Default::default()
+        }
     }
 }
 
@@ -38,6 +55,14 @@ impl Snapshot {
 pub struct Metadata {
     pub(crate) repl_id: ReplicationId,
     pub(crate) log_idx: u64,
+    // * Term of the entry at `log_idx`, 0 for snapshots taken before it was recorded
+    pub(crate) log_term: u64,
+    // * Requests already applied per client, so that retries stay deduplicated after a restart
+    pub(crate) sessions: ClientSessions,
+    // * Filled in by the cache manager when the snapshot is taken
+    pub(crate) leases: LeaseStates,
+    // * Cross-partition transactions the log entries compacted into the snapshot took part in
+    pub(crate) transactions: TxnStates,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,552 @@
+//! Reads dump files written by Redis, so that their keys can be imported.
+//!
+//! Strings and lists are converted into cache entries, whichever encoding Redis stored them with
+//! (plain, integer, LZF compressed, ziplist, quicklist or listpack). Sets, sorted sets and hashes
+//! have no counterpart here; they are skipped and counted by type. Streams and module values cannot
+//! be skipped without decoding them, so a file holding any is rejected.
+use crate::domains::caches::cache_objects::{CacheEntry, CacheValue, TypedValue};
+use anyhow::{Context, Result, anyhow, bail};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub(super) const REDIS_MAGIC_STRING: &[u8] = b"REDIS";
+
+const OPCODE_SLOT_INFO: u8 = 0xF4;
+const OPCODE_FUNCTION2: u8 = 0xF5;
+const OPCODE_FUNCTION_PRE_GA: u8 = 0xF6;
+const OPCODE_MODULE_AUX: u8 = 0xF7;
+const OPCODE_IDLE: u8 = 0xF8;
+const OPCODE_FREQ: u8 = 0xF9;
+pub(super) const OPCODE_AUX: u8 = 0xFA;
+pub(super) const OPCODE_RESIZEDB: u8 = 0xFB;
+pub(super) const OPCODE_EXPIRETIME_MS: u8 = 0xFC;
+const OPCODE_EXPIRETIME: u8 = 0xFD;
+pub(super) const OPCODE_SELECTDB: u8 = 0xFE;
+pub(super) const OPCODE_EOF: u8 = 0xFF;
+
+pub(super) const TYPE_STRING: u8 = 0;
+pub(super) const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_HASH_ZIPMAP: u8 = 9;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_HASH_METADATA: u8 = 24;
+const TYPE_HASH_LISTPACK_EX: u8 = 25;
+
+const QUICKLIST_NODE_PLAIN: u64 = 1;
+
+/// Keys read from a Redis dump, along with how many were left out and why.
+#[derive(Debug, Default)]
+pub(crate) struct RedisRdbImport {
+    pub(crate) entries: Vec<CacheEntry>,
+    // * Reason, usually the Redis type that has no counterpart, to number of keys skipped
+    pub(crate) skipped: BTreeMap<String, usize>,
+}
+
+impl RedisRdbImport {
+    pub(crate) fn skip(&mut self, reason: impl Into<String>, count: usize) {
+        if count > 0 {
+            *self.skipped.entry(reason.into()).or_default() += count;
+        }
+    }
+
+    pub(crate) fn vectorize(&self, imported: usize) -> Vec<String> {
+        let mut report = vec![format!("imported:{imported}")];
+        report
+            .extend(self.skipped.iter().map(|(reason, count)| format!("skipped_{reason}:{count}")));
+        report
+    }
+}
+
+pub(crate) struct RedisRdbLoader {}
+
+impl RedisRdbLoader {
+    pub(crate) fn load_from_filepath(filepath: &Path) -> Result<RedisRdbImport> {
+        let bytes = std::fs::read(filepath)
+            .with_context(|| format!("failed to read {}", filepath.display()))?;
+        Self::load_from_bytes(&bytes)
+    }
+
+    pub(crate) fn load_from_bytes(bytes: &[u8]) -> Result<RedisRdbImport> {
+        let mut reader = RdbReader { data: bytes };
+        let version = reader.read_header()?;
+
+        let mut import = RedisRdbImport::default();
+        let now = Utc::now();
+        let mut expiry: Option<DateTime<Utc>> = None;
+        loop {
+            match reader.take_u8()? {
+                | OPCODE_EOF => break,
+                | OPCODE_AUX => {
+                    reader.read_string()?;
+                    reader.read_string()?;
+                },
+                | OPCODE_SELECTDB => {
+                    reader.read_length()?;
+                },
+                | OPCODE_RESIZEDB => {
+                    reader.read_length()?;
+                    reader.read_length()?;
+                },
+                | OPCODE_SLOT_INFO => {
+                    for _ in 0..3 {
+                        reader.read_length()?;
+                    }
+                },
+                | OPCODE_FUNCTION2 => {
+                    reader.read_string()?;
+                },
+                | OPCODE_IDLE => {
+                    reader.read_length()?;
+                },
+                | OPCODE_FREQ => {
+                    reader.take(1)?;
+                },
+                | OPCODE_EXPIRETIME_MS => {
+                    let millis = i64::from_le_bytes(reader.take_array()?);
+                    expiry =
+                        Some(DateTime::from_timestamp_millis(millis).context("invalid expiry")?);
+                },
+                | OPCODE_EXPIRETIME => {
+                    let secs = u32::from_le_bytes(reader.take_array()?);
+                    expiry =
+                        Some(DateTime::from_timestamp(secs as i64, 0).context("invalid expiry")?);
+                },
+                | OPCODE_MODULE_AUX | OPCODE_FUNCTION_PRE_GA => {
+                    bail!("RDB version {version}: module and function data are not supported");
+                },
+                | value_type => {
+                    let key = reader.read_string()?;
+                    let value = reader.read_value(value_type)?;
+                    let expiry = expiry.take();
+                    let Some(value) = value else {
+                        import.skip(type_name(value_type), 1);
+                        continue;
+                    };
+                    let mut value = CacheValue::new(value);
+                    if let Some(expiry) = expiry {
+                        if expiry <= now {
+                            import.skip("expired", 1);
+                            continue;
+                        }
+                        value = value.with_expiry(expiry);
+                    }
+                    import.entries.push(CacheEntry::new_with_cache_value(key, value));
+                },
+            }
+        }
+        Ok(import)
+    }
+}
+
+/// Reads a single value as Redis serializes it for `DUMP`: its type followed by its encoding.
+pub(super) fn read_dumped_value(body: &[u8]) -> Result<Option<TypedValue>> {
+    let mut reader = RdbReader { data: body };
+    let value_type = reader.take_u8()?;
+    let value = reader.read_value(value_type)?;
+    if !reader.data.is_empty() {
+        bail!("trailing bytes after the value");
+    }
+    Ok(value)
+}
+
+fn type_name(value_type: u8) -> &'static str {
+    match value_type {
+        | TYPE_SET | TYPE_SET_INTSET | TYPE_SET_LISTPACK => "set",
+        | TYPE_ZSET | TYPE_ZSET_2 | TYPE_ZSET_ZIPLIST | TYPE_ZSET_LISTPACK => "zset",
+        | _ => "hash",
+    }
+}
+
+enum Length {
+    Len(u64),
+    // * Strings stored as integers or compressed
+    Encoded(u8),
+}
+
+struct RdbReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> RdbReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.data.len() < n {
+            bail!("unexpected end of RDB file");
+        }
+        let (taken, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn take_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn read_header(&mut self) -> Result<u32> {
+        if self.take(REDIS_MAGIC_STRING.len())? != REDIS_MAGIC_STRING {
+            bail!("not a Redis RDB file");
+        }
+        let version = std::str::from_utf8(self.take(4)?)?.parse().context("invalid RDB version")?;
+        Ok(version)
+    }
+
+    fn read_length_or_encoding(&mut self) -> Result<Length> {
+        let first = self.take_u8()?;
+        Ok(match first >> 6 {
+            | 0b00 => Length::Len((first & 0x3F) as u64),
+            | 0b01 => Length::Len((((first & 0x3F) as u64) << 8) | self.take_u8()? as u64),
+            | 0b10 => match first {
+                | 0x80 => Length::Len(u32::from_be_bytes(self.take_array()?) as u64),
+                | 0x81 => Length::Len(u64::from_be_bytes(self.take_array()?)),
+                | _ => bail!("invalid length encoding {first:#x}"),
+            },
+            | _ => Length::Encoded(first & 0x3F),
+        })
+    }
+
+    fn read_length(&mut self) -> Result<usize> {
+        match self.read_length_or_encoding()? {
+            | Length::Len(len) => Ok(len as usize),
+            | Length::Encoded(_) => bail!("expected a length, found an encoded string"),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Vec<u8>> {
+        match self.read_length_or_encoding()? {
+            | Length::Len(len) => Ok(self.take(len as usize)?.to_vec()),
+            | Length::Encoded(0) => Ok((self.take_u8()? as i8).to_string().into_bytes()),
+            | Length::Encoded(1) => {
+                Ok(i16::from_le_bytes(self.take_array()?).to_string().into_bytes())
+            },
+            | Length::Encoded(2) => {
+                Ok(i32::from_le_bytes(self.take_array()?).to_string().into_bytes())
+            },
+            | Length::Encoded(3) => {
+                let compressed_len = self.read_length()?;
+                let len = self.read_length()?;
+                lzf_decompress(self.take(compressed_len)?, len)
+            },
+            | Length::Encoded(encoding) => bail!("unknown string encoding {encoding}"),
+        }
+    }
+
+    /// Reads a value of the given type. Types with no counterpart are skipped over and give `None`.
+    fn read_value(&mut self, value_type: u8) -> Result<Option<TypedValue>> {
+        let list = |items: Vec<Vec<u8>>| {
+            Some(TypedValue::List(items.into_iter().map(Bytes::from).collect()))
+        };
+        Ok(match value_type {
+            | TYPE_STRING => Some(TypedValue::String(self.read_string()?.into())),
+            | TYPE_LIST => {
+                let len = self.read_length()?;
+                list((0..len).map(|_| self.read_string()).collect::<Result<_>>()?)
+            },
+            | TYPE_LIST_ZIPLIST => list(ziplist_entries(&self.read_string()?)?),
+            | TYPE_LIST_QUICKLIST => {
+                let mut items = vec![];
+                for _ in 0..self.read_length()? {
+                    items.extend(ziplist_entries(&self.read_string()?)?);
+                }
+                list(items)
+            },
+            | TYPE_LIST_QUICKLIST_2 => {
+                let mut items = vec![];
+                for _ in 0..self.read_length()? {
+                    let container = self.read_length()? as u64;
+                    let node = self.read_string()?;
+                    if container == QUICKLIST_NODE_PLAIN {
+                        items.push(node);
+                    } else {
+                        items.extend(listpack_entries(&node)?);
+                    }
+                }
+                list(items)
+            },
+            | TYPE_SET => {
+                let len = self.read_length()?;
+                self.skip_strings(len)?;
+                None
+            },
+            | TYPE_HASH => {
+                let len = self.read_length()?;
+                self.skip_strings(len * 2)?;
+                None
+            },
+            | TYPE_ZSET => {
+                for _ in 0..self.read_length()? {
+                    self.read_string()?;
+                    // * Scores are stored as text, with special lengths for nan and infinities
+                    let len = self.take_u8()?;
+                    if len < 253 {
+                        self.take(len as usize)?;
+                    }
+                }
+                None
+            },
+            | TYPE_ZSET_2 => {
+                for _ in 0..self.read_length()? {
+                    self.read_string()?;
+                    self.take(8)?;
+                }
+                None
+            },
+            | TYPE_HASH_ZIPMAP | TYPE_SET_INTSET | TYPE_ZSET_ZIPLIST | TYPE_HASH_ZIPLIST
+            | TYPE_HASH_LISTPACK | TYPE_ZSET_LISTPACK | TYPE_SET_LISTPACK => {
+                self.read_string()?;
+                None
+            },
+            | TYPE_HASH_METADATA => {
+                self.take(8)?;
+                for _ in 0..self.read_length()? {
+                    self.read_length()?;
+                    self.skip_strings(2)?;
+                }
+                None
+            },
+            | TYPE_HASH_LISTPACK_EX => {
+                self.take(8)?;
+                self.read_string()?;
+                None
+            },
+            | value_type => bail!("value type {value_type} (stream or module) is not supported"),
+        })
+    }
+
+    fn skip_strings(&mut self, n: usize) -> Result<()> {
+        for _ in 0..n {
+            self.read_string()?;
+        }
+        Ok(())
+    }
+}
+
+fn lzf_decompress(input: &[u8], len: usize) -> Result<Vec<u8>> {
+    let mut out: Vec<u8> = Vec::with_capacity(len);
+    let mut i = 0;
+    let byte_at = |i: usize| input.get(i).copied().ok_or_else(|| anyhow!("truncated LZF data"));
+    while i < input.len() {
+        let ctrl = input[i] as usize;
+        i += 1;
+        if ctrl < 32 {
+            // * Literal run of ctrl + 1 bytes
+            let literal = input.get(i..i + ctrl + 1).context("truncated LZF data")?;
+            out.extend_from_slice(literal);
+            i += ctrl + 1;
+            continue;
+        }
+        // * Back reference into the output produced so far
+        let mut run = ctrl >> 5;
+        if run == 7 {
+            run += byte_at(i)? as usize;
+            i += 1;
+        }
+        let distance = ((ctrl & 0x1F) << 8) + byte_at(i)? as usize + 1;
+        i += 1;
+        let start = out.len().checked_sub(distance).context("invalid LZF back reference")?;
+        for k in 0..run + 2 {
+            out.push(out[start + k]);
+        }
+    }
+    if out.len() != len {
+        bail!("LZF data decompressed to {} bytes instead of {len}", out.len());
+    }
+    Ok(out)
+}
+
+fn ziplist_entries(ziplist: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut reader = RdbReader { data: ziplist };
+    // * total bytes, offset of the last entry and number of entries
+    reader.take(10)?;
+    let mut entries = vec![];
+    loop {
+        let prev_len = reader.take_u8()?;
+        if prev_len == 0xFF {
+            break;
+        }
+        if prev_len == 0xFE {
+            reader.take(4)?;
+        }
+        let encoding = reader.take_u8()?;
+        let entry = match encoding {
+            | _ if encoding >> 6 == 0b00 => reader.take((encoding & 0x3F) as usize)?.to_vec(),
+            | _ if encoding >> 6 == 0b01 => {
+                let len = (((encoding & 0x3F) as usize) << 8) | reader.take_u8()? as usize;
+                reader.take(len)?.to_vec()
+            },
+            | 0x80 => {
+                let len = u32::from_be_bytes(reader.take_array()?) as usize;
+                reader.take(len)?.to_vec()
+            },
+            | 0xC0 => i16::from_le_bytes(reader.take_array()?).to_string().into_bytes(),
+            | 0xD0 => i32::from_le_bytes(reader.take_array()?).to_string().into_bytes(),
+            | 0xE0 => i64::from_le_bytes(reader.take_array()?).to_string().into_bytes(),
+            | 0xF0 => {
+                let [a, b, c] = reader.take_array()?;
+                (i32::from_le_bytes([0, a, b, c]) >> 8).to_string().into_bytes()
+            },
+            | 0xFE => (reader.take_u8()? as i8).to_string().into_bytes(),
+            | 0xF1..=0xFD => ((encoding & 0x0F) - 1).to_string().into_bytes(),
+            | _ => bail!("invalid ziplist entry encoding {encoding:#x}"),
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn listpack_entries(listpack: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut reader = RdbReader { data: listpack };
+    // * total bytes and number of entries
+    reader.take(6)?;
+    let mut entries = vec![];
+    loop {
+        let encoding = reader.take_u8()?;
+        let (entry, encoded_len) = match encoding {
+            | 0xFF => break,
+            | _ if encoding >> 7 == 0 => ((encoding & 0x7F).to_string().into_bytes(), 1),
+            | _ if encoding >> 6 == 0b10 => {
+                let len = (encoding & 0x3F) as usize;
+                (reader.take(len)?.to_vec(), 1 + len)
+            },
+            | _ if encoding >> 5 == 0b110 => {
+                let raw = (((encoding & 0x1F) as i32) << 8) | reader.take_u8()? as i32;
+                let value = if raw >= 1 << 12 { raw - (1 << 13) } else { raw };
+                (value.to_string().into_bytes(), 2)
+            },
+            | _ if encoding >> 4 == 0b1110 => {
+                let len = (((encoding & 0x0F) as usize) << 8) | reader.take_u8()? as usize;
+                (reader.take(len)?.to_vec(), 2 + len)
+            },
+            | 0xF0 => {
+                let len = u32::from_le_bytes(reader.take_array()?) as usize;
+                (reader.take(len)?.to_vec(), 5 + len)
+            },
+            | 0xF1 => (i16::from_le_bytes(reader.take_array()?).to_string().into_bytes(), 3),
+            | 0xF2 => {
+                let [a, b, c] = reader.take_array()?;
+                ((i32::from_le_bytes([0, a, b, c]) >> 8).to_string().into_bytes(), 4)
+            },
+            | 0xF3 => (i32::from_le_bytes(reader.take_array()?).to_string().into_bytes(), 5),
+            | 0xF4 => (i64::from_le_bytes(reader.take_array()?).to_string().into_bytes(), 9),
+            | _ => bail!("invalid listpack entry encoding {encoding:#x}"),
+        };
+        // * Each entry ends with its own length, in as many 7 bit groups as it takes
+        let back_len = match encoded_len {
+            | ..=127 => 1,
+            | ..=16382 => 2,
+            | ..=2097150 => 3,
+            | ..=268435454 => 4,
+            | _ => 5,
+        };
+        reader.take(back_len)?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rdb(body: &[u8]) -> Vec<u8> {
+        let mut rdb = b"REDIS0011".to_vec();
+        // * aux field, database selector and table sizes as Redis writes them
+        rdb.extend_from_slice(b"\xFA\x09redis-ver\x057.2.4\xFE\x00\xFB\x03\x01");
+        rdb.extend_from_slice(body);
+        rdb.push(OPCODE_EOF);
+        rdb.extend_from_slice(&[0; 8]);
+        rdb
+    }
+
+    fn value_of(import: &RedisRdbImport, key: &str) -> TypedValue {
+        import.entries.iter().find(|e| e.key() == key.as_bytes()).unwrap().value.value.clone()
+    }
+
+    #[test]
+    fn test_load_strings_with_expiry_and_encodings() {
+        let future = (Utc::now().timestamp_millis() + 60_000).to_le_bytes();
+        let mut body = vec![];
+        body.extend_from_slice(b"\x00\x03foo\x03bar");
+        body.push(OPCODE_EXPIRETIME_MS);
+        body.extend_from_slice(&future);
+        body.extend_from_slice(b"\x00\x03num\xC1\x39\x30");
+        // * "aaaaaaaaaa" compressed with LZF: a literal "a" then a back reference of 9 bytes
+        body.extend_from_slice(b"\x00\x03lzf\xC3\x05\x0A\x00a\xE0\x00\x00");
+        body.push(OPCODE_EXPIRETIME_MS);
+        body.extend_from_slice(&1000i64.to_le_bytes());
+        body.extend_from_slice(b"\x00\x04gone\x01x");
+
+        let import = RedisRdbLoader::load_from_bytes(&rdb(&body)).unwrap();
+
+        assert_eq!(import.entries.len(), 3);
+        assert_eq!(value_of(&import, "foo"), TypedValue::String("bar".into()));
+        assert!(import.entries.iter().find(|e| e.key() == b"foo").unwrap().expiry().is_none());
+        assert_eq!(value_of(&import, "num"), TypedValue::String("12345".into()));
+        assert_eq!(value_of(&import, "lzf"), TypedValue::String("aaaaaaaaaa".into()));
+        assert_eq!(import.skipped, BTreeMap::from([("expired".to_string(), 1)]));
+    }
+
+    #[test]
+    fn test_load_lists_in_every_encoding() {
+        let mut body = vec![];
+        body.extend_from_slice(b"\x01\x04list\x02\x01a\xC0\x07");
+
+        // * quicklist of listpack nodes: "x" and 5
+        let listpack = b"\x0C\x00\x00\x00\x02\x00\x81x\x02\x05\x01\xFF";
+        body.extend_from_slice(b"\x12\x03lp2\x01\x02");
+        body.push(listpack.len() as u8);
+        body.extend_from_slice(listpack);
+
+        // * ziplist holding "y" and the immediate integer 3
+        let ziplist = b"\x10\x00\x00\x00\x0D\x00\x00\x00\x02\x00\x00\x01y\x03\xF4\xFF";
+        body.extend_from_slice(b"\x0A\x02zl");
+        body.push(ziplist.len() as u8);
+        body.extend_from_slice(ziplist);
+
+        let import = RedisRdbLoader::load_from_bytes(&rdb(&body)).unwrap();
+
+        assert_eq!(value_of(&import, "list"), TypedValue::from(vec!["a", "7"]));
+        assert_eq!(value_of(&import, "lp2"), TypedValue::from(vec!["x", "5"]));
+        assert_eq!(value_of(&import, "zl"), TypedValue::from(vec!["y", "3"]));
+    }
+
+    #[test]
+    fn test_unsupported_types_are_skipped_and_reported() {
+        let mut body = vec![];
+        body.extend_from_slice(b"\x02\x03set\x02\x01a\x01b");
+        body.extend_from_slice(b"\x04\x04hash\x01\x01f\x01v");
+        body.extend_from_slice(b"\x10\x02hl\x02\xAB\xCD");
+        body.extend_from_slice(b"\x00\x04kept\x01v");
+
+        let import = RedisRdbLoader::load_from_bytes(&rdb(&body)).unwrap();
+
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!(
+            import.skipped,
+            BTreeMap::from([("hash".to_string(), 2), ("set".to_string(), 1)])
+        );
+        assert_eq!(import.vectorize(1), vec!["imported:1", "skipped_hash:2", "skipped_set:1"]);
+    }
+
+    #[test]
+    fn test_rejects_streams_and_foreign_files() {
+        assert!(RedisRdbLoader::load_from_bytes(&rdb(b"\x15\x01s\x00")).is_err());
+        assert!(RedisRdbLoader::load_from_bytes(b"DUVA0001").is_err());
+        assert!(RedisRdbLoader::load_from_bytes(&rdb(b"\x00\x03foo")).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Writes cache entries as a Redis dump file, so that Redis and its tooling (`redis-check-rdb`,
+//! rdb-tools) can read duva data.
+use super::redis_rdb_loader::{
+    OPCODE_AUX, OPCODE_EOF, OPCODE_EXPIRETIME_MS, OPCODE_RESIZEDB, OPCODE_SELECTDB,
+    REDIS_MAGIC_STRING, TYPE_LIST, TYPE_STRING,
+};
+use crate::domains::caches::cache_objects::{CacheEntry, TypedValue};
+use chrono::Utc;
+
+// * Version written by Redis 7.2, the oldest that reads every opcode used here
+const RDB_VERSION: &[u8] = b"0011";
+const CRC64_JONES_REFLECTED: u64 = 0x95AC_9329_AC4B_C9B5;
+
+pub(crate) struct RedisRdbWriter {}
+
+impl RedisRdbWriter {
+    /// Encodes the entries into database 0 of a dump. Strings and lists are written with their
+    /// plain encodings, which every Redis version since 2.x loads.
+    pub(crate) fn encode(entries: &[CacheEntry]) -> Vec<u8> {
+        let entries: Vec<&CacheEntry> =
+            entries.iter().filter(|entry| !matches!(entry.value.value, TypedValue::Null)).collect();
+        let expires = entries.iter().filter(|entry| entry.expiry().is_some()).count();
+
+        let mut rdb = [REDIS_MAGIC_STRING, RDB_VERSION].concat();
+        for (key, value) in [
+            ("redis-bits", "64".to_string()),
+            ("ctime", Utc::now().timestamp().to_string()),
+            ("duva-ver", env!("CARGO_PKG_VERSION").to_string()),
+        ] {
+            rdb.push(OPCODE_AUX);
+            write_string(&mut rdb, key.as_bytes());
+            write_string(&mut rdb, value.as_bytes());
+        }
+        rdb.push(OPCODE_SELECTDB);
+        write_length(&mut rdb, 0);
+        rdb.push(OPCODE_RESIZEDB);
+        write_length(&mut rdb, entries.len());
+        write_length(&mut rdb, expires);
+
+        for entry in entries {
+            if let Some(expiry) = entry.expiry() {
+                rdb.push(OPCODE_EXPIRETIME_MS);
+                rdb.extend_from_slice(&expiry.timestamp_millis().to_le_bytes());
+            }
+            let Some(value_type) = value_type(&entry.value.value) else { continue };
+            rdb.push(value_type);
+            write_string(&mut rdb, entry.key());
+            write_value(&mut rdb, &entry.value.value);
+        }
+
+        rdb.push(OPCODE_EOF);
+        let checksum = crc64(&rdb);
+        rdb.extend_from_slice(&checksum.to_le_bytes());
+        rdb
+    }
+}
+
+pub(super) fn value_type(value: &TypedValue) -> Option<u8> {
+    match value {
+        | TypedValue::String(_) => Some(TYPE_STRING),
+        | TypedValue::List(_) => Some(TYPE_LIST),
+        | TypedValue::Null => None,
+    }
+}
+
+/// Writes the value in the encoding of its type, as it follows the key in a dump.
+pub(super) fn write_value(rdb: &mut Vec<u8>, value: &TypedValue) {
+    match value {
+        | TypedValue::String(value) => write_string(rdb, value),
+        | TypedValue::List(items) => {
+            write_length(rdb, items.len());
+            for item in items {
+                write_string(rdb, item);
+            }
+        },
+        | TypedValue::Null => {},
+    }
+}
+
+fn write_length(rdb: &mut Vec<u8>, len: usize) {
+    match len {
+        | ..0x40 => rdb.push(len as u8),
+        | ..0x4000 => rdb.extend_from_slice(&(0x4000 | len as u16).to_be_bytes()),
+        | _ if len <= u32::MAX as usize => {
+            rdb.push(0x80);
+            rdb.extend_from_slice(&(len as u32).to_be_bytes());
+        },
+        | _ => {
+            rdb.push(0x81);
+            rdb.extend_from_slice(&(len as u64).to_be_bytes());
+        },
+    }
+}
+
+fn write_string(rdb: &mut Vec<u8>, value: &[u8]) {
+    write_length(rdb, value.len());
+    rdb.extend_from_slice(value);
+}
+
+/// CRC-64 with the Jones polynomial, which Redis appends to its dumps.
+pub(super) fn crc64(data: &[u8]) -> u64 {
+    data.iter().fold(0u64, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u64, |crc, _| {
+            if crc & 1 == 1 { (crc >> 1) ^ CRC64_JONES_REFLECTED } else { crc >> 1 }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::saves::snapshot::redis_rdb_loader::RedisRdbLoader;
+    use chrono::{DateTime, Duration};
+
+    #[test]
+    fn test_crc64_matches_redis() {
+        assert_eq!(crc64(b"123456789"), 0xe9c6d914c4b8d9ca);
+    }
+
+    #[test]
+    fn test_write_length_encodings() {
+        for (len, expected) in
+            [(10, vec![0x0A]), (700, vec![0x42, 0xBC]), (17000, vec![0x80, 0x00, 0x00, 0x42, 0x68])]
+        {
+            let mut rdb = vec![];
+            write_length(&mut rdb, len);
+            assert_eq!(rdb, expected);
+        }
+    }
+
+    #[test]
+    fn test_encoded_dump_loads_back() {
+        let expiry =
+            DateTime::from_timestamp_millis((Utc::now() + Duration::minutes(5)).timestamp_millis())
+                .unwrap();
+        let entries = vec![
+            CacheEntry::new("foo", "bar"),
+            CacheEntry::new("big", "x".repeat(20000).as_str()).with_expiry(expiry),
+            CacheEntry::new("list", vec!["a", "b"]),
+        ];
+
+        let rdb = RedisRdbWriter::encode(&entries);
+
+        assert!(rdb.starts_with(b"REDIS0011"));
+        let (body, checksum) = rdb.split_at(rdb.len() - 8);
+        assert_eq!(checksum, crc64(body).to_le_bytes());
+        let import = RedisRdbLoader::load_from_bytes(&rdb).unwrap();
+        assert_eq!(import.entries, entries);
+        assert!(import.skipped.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use super::Snapshot;
+use crate::domains::encryption::KeyRing;
 use crate::domains::saves::endec::decoder::{BytesDecoder, DecoderInit};
 
 pub(crate) struct SnapshotLoader {}
@@ -8,10 +9,16 @@ pub(crate) struct SnapshotLoader {}
 impl SnapshotLoader {
     // Optimization: OS maps the file pages into VM pages and the data is read from the file only when the VM page is accessed.
     // No extra copy is made between kernel and user space
-    pub(crate) fn load_from_filepath(filepath: &Path) -> anyhow::Result<Snapshot> {
+    // * Encrypted snapshots are decrypted into memory instead
+    pub(crate) fn load_from_filepath(filepath: &Path, keys: &KeyRing) -> anyhow::Result<Snapshot> {
         let file = std::fs::File::open(filepath)?;
         let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
-        Self::load_from_bytes(&mmap)
+        Self::load_from_bytes(&keys.open_file(&mmap)?)
+    }
+    /// The snapshot file as it was encoded, decrypted when sealed.
+    pub(crate) fn read_from_filepath(filepath: &Path, keys: &KeyRing) -> anyhow::Result<Vec<u8>> {
+        let data = std::fs::read(filepath)?;
+        Ok(keys.open_file(&data)?.into_owned())
     }
     pub(crate) fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Snapshot> {
         let decoder: BytesDecoder<DecoderInit> = bytes.into();
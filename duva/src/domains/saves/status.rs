@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Shared view of the snapshot being written, if any, and the outcome of the last one,
+/// reported through `INFO persistence`.
+#[derive(Debug, Clone, Default)]
+pub struct SaveStatus(Arc<Mutex<SaveState>>);
+
+#[derive(Debug, Default)]
+struct SaveState {
+    started_at: Option<Instant>,
+    keys_total: usize,
+    keys_saved: usize,
+    // * Unix time, in seconds, of the last successful save
+    last_save_time: u64,
+    last_save_ok: bool,
+    last_save_duration: Option<u64>,
+}
+
+impl SaveStatus {
+    pub(crate) fn try_start(&self) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        if state.started_at.is_some() {
+            return Err(anyhow::anyhow!("ERR Background save already in progress"));
+        }
+        state.started_at = Some(Instant::now());
+        state.keys_total = 0;
+        state.keys_saved = 0;
+        Ok(())
+    }
+
+    pub(crate) fn add_total(&self, keys: usize) {
+        self.lock().keys_total += keys;
+    }
+
+    pub(crate) fn add_saved(&self, keys: usize) {
+        self.lock().keys_saved += keys;
+    }
+
+    pub(crate) fn finish(&self, ok: bool) {
+        let mut state = self.lock();
+        if let Some(started_at) = state.started_at.take() {
+            state.last_save_duration = Some(started_at.elapsed().as_secs());
+        }
+        state.last_save_ok = ok;
+        if ok {
+            state.last_save_time =
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        }
+    }
+
+    pub(crate) fn vectorize(&self) -> Vec<String> {
+        let state = self.lock();
+        let in_progress = state.started_at.is_some();
+        vec![
+            format!("rdb_bgsave_in_progress:{}", in_progress as u8),
+            format!("rdb_last_save_time:{}", state.last_save_time),
+            format!(
+                "rdb_last_bgsave_status:{}",
+                match state.last_save_duration {
+                    | Some(_) if !state.last_save_ok => "err",
+                    | _ => "ok",
+                }
+            ),
+            format!(
+                "rdb_last_bgsave_time_sec:{}",
+                state.last_save_duration.map_or(-1, |secs| secs as i64)
+            ),
+            format!(
+                "rdb_current_bgsave_keys_saved:{}",
+                if in_progress { state.keys_saved } else { 0 }
+            ),
+            format!(
+                "rdb_current_bgsave_keys_total:{}",
+                if in_progress { state.keys_total } else { 0 }
+            ),
+        ]
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SaveState> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_start_rejects_concurrent_save() {
+        let status = SaveStatus::default();
+        status.try_start().unwrap();
+        assert!(status.try_start().is_err());
+
+        status.finish(true);
+        assert!(status.try_start().is_ok());
+    }
+
+    #[test]
+    fn test_vectorize_reports_progress_and_last_status() {
+        let status = SaveStatus::default();
+        assert!(status.vectorize().contains(&"rdb_last_bgsave_status:ok".to_string()));
+        assert!(status.vectorize().contains(&"rdb_last_bgsave_time_sec:-1".to_string()));
+
+        status.try_start().unwrap();
+        status.add_total(3);
+        status.add_saved(2);
+        let info = status.vectorize();
+        assert!(info.contains(&"rdb_bgsave_in_progress:1".to_string()));
+        assert!(info.contains(&"rdb_current_bgsave_keys_saved:2".to_string()));
+        assert!(info.contains(&"rdb_current_bgsave_keys_total:3".to_string()));
+
+        status.finish(false);
+        let info = status.vectorize();
+        assert!(info.contains(&"rdb_bgsave_in_progress:0".to_string()));
+        assert!(info.contains(&"rdb_last_bgsave_status:err".to_string()));
+        assert!(info.contains(&"rdb_last_save_time:0".to_string()));
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::Metadata;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::Registry;
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::reload;
+
+// * Set once the subscriber is initialized, so that `CONFIG SET` can swap the filter of log lines
+static RELOAD_HANDLE: OnceLock<reload::Handle<LogFilter, Registry>> = OnceLock::new();
+
+/// Which log lines are printed: a default level, overridden per module with `module=level`
+/// directives such as `info,cluster_actors=debug`.
+///
+/// A module matches the targets that contain its path, so `cluster_actors` covers
+/// `duva::domains::cluster_actors::service`. When several directives match, the most specific one
+/// wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn from_level(level: tracing::Level) -> Self {
+        Self { default: LevelFilter::from_level(level), directives: vec![] }
+    }
+
+    /// Wraps the filter so that it can be replaced at runtime through [`reload`] and [`set_level`].
+    pub fn reloadable(self) -> reload::Layer<Self, Registry> {
+        let (layer, handle) = reload::Layer::new(self);
+        let _ = RELOAD_HANDLE.set(handle);
+        layer
+    }
+
+    /// Level of the modules no directive covers.
+    pub(crate) fn level(&self) -> String {
+        level_name(self.default)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let segments = target.split("::").collect::<Vec<_>>();
+        self.directives
+            .iter()
+            .filter(|(module, _)| {
+                let module = module.split("::").collect::<Vec<_>>();
+                segments.windows(module.len()).any(|window| window == module)
+            })
+            .max_by_key(|(module, _)| module.split("::").count())
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+/// Filter of log lines currently in place.
+pub(crate) fn current() -> anyhow::Result<LogFilter> {
+    let handle = RELOAD_HANDLE.get().ok_or(anyhow::anyhow!("log filter is not reloadable"))?;
+    handle.clone_current().ok_or(anyhow::anyhow!("log subscriber is gone"))
+}
+
+/// Replaces the filter of log lines, without restarting the node.
+pub(crate) fn reload(filter: LogFilter) -> anyhow::Result<()> {
+    let handle = RELOAD_HANDLE.get().ok_or(anyhow::anyhow!("log filter is not reloadable"))?;
+    Ok(handle.reload(filter)?)
+}
+
+/// Changes the default level of log lines, keeping the per module directives.
+pub(crate) fn set_level(level: LevelFilter) -> anyhow::Result<()> {
+    reload(LogFilter { default: level, ..current()? })
+}
+
+pub(crate) fn parse_level(level: &str) -> anyhow::Result<LevelFilter> {
+    level.parse().map_err(|_| anyhow::anyhow!("invalid log level {level}"))
+}
+
+fn level_name(level: LevelFilter) -> String {
+    level.to_string().to_lowercase()
+}
+
+impl<S> Filter<S> for LogFilter {
+    fn enabled(&self, metadata: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        *metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.directives.iter().map(|(_, level)| *level).chain([self.default]).max()
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = anyhow::Error;
+
+    /// Comma separated directives; a bare level sets the default, which is `info` when omitted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::from_level(tracing::Level::INFO);
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                | Some((module, level)) if !module.trim().is_empty() => {
+                    filter.directives.push((module.trim().to_string(), parse_level(level.trim())?));
+                },
+                | Some(_) => anyhow::bail!("invalid log directive {directive}"),
+                | None => filter.default = parse_level(directive)?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl Display for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.level())?;
+        for (module, level) in &self.directives {
+            write!(f, ",{module}={}", level_name(*level))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let filter: LogFilter = "warn, cluster_actors=debug,duva::adapters=trace".parse().unwrap();
+
+        assert_eq!(filter.to_string(), "warn,cluster_actors=debug,duva::adapters=trace");
+        assert_eq!("".parse::<LogFilter>().unwrap().to_string(), "info");
+        assert!("cluster_actors=loud".parse::<LogFilter>().is_err());
+        assert!("=debug".parse::<LogFilter>().is_err());
+        assert!("verbose".parse::<LogFilter>().is_err());
+    }
+
+    #[test]
+    fn test_level_for_picks_the_most_specific_module() {
+        let filter: LogFilter =
+            "info,cluster_actors=debug,cluster_actors::replication=error".parse().unwrap();
+
+        assert_eq!(filter.level_for("duva::domains::cluster_actors::service"), LevelFilter::DEBUG);
+        assert_eq!(
+            filter.level_for("duva::domains::cluster_actors::replication::manager"),
+            LevelFilter::ERROR
+        );
+        assert_eq!(filter.level_for("duva::domains::caches"), LevelFilter::INFO);
+        // * Modules match whole path segments only
+        assert_eq!(filter.level_for("duva::domains::cluster_actors_old"), LevelFilter::INFO);
+        assert_eq!(
+            <LogFilter as Filter<Registry>>::max_level_hint(&filter),
+            Some(LevelFilter::DEBUG)
+        );
+    }
+}
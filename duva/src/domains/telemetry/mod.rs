@@ -0,0 +1,85 @@
+pub mod log_filter;
+pub mod otlp;
+
+use std::fmt::Display;
+use std::str::FromStr;
+use tracing_subscriber::Registry;
+use tracing_subscriber::registry::LookupSpan;
+
+/// W3C trace context of a span. Peer messages carry the context of the span they were sent from,
+/// so that the spans a client write opens on replicas and migration targets join its trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct TraceContext {
+    pub(crate) trace_id: u128,
+    pub(crate) span_id: u64,
+}
+
+impl TraceContext {
+    /// Context of the span the caller runs in. None unless spans are exported and the span belongs
+    /// to a trace.
+    pub(crate) fn current() -> Option<Self> {
+        tracing::Span::current()
+            .with_subscriber(|(id, dispatch)| {
+                let registry = dispatch.downcast_ref::<Registry>()?;
+                let span = registry.span(id)?;
+                span.extensions().get::<otlp::TracedSpan>().map(|traced| traced.context)
+            })
+            .flatten()
+    }
+
+    /// Context of a new span under `parent`, or of the root of a new trace.
+    pub(crate) fn child_of(parent: Option<Self>) -> Self {
+        let trace_id = parent.map_or_else(|| rand::random::<u128>().max(1), |p| p.trace_id);
+        Self { trace_id, span_id: rand::random::<u64>().max(1) }
+    }
+}
+
+impl Display for TraceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+}
+
+impl FromStr for TraceContext {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split('-').collect::<Vec<_>>();
+        let [_version, trace_id, span_id, _flags] = parts[..] else {
+            anyhow::bail!("invalid traceparent {s}");
+        };
+        Ok(Self {
+            trace_id: u128::from_str_radix(trace_id, 16)?,
+            span_id: u64::from_str_radix(span_id, 16)?,
+        })
+    }
+}
+
+/// Opens a span that joins the trace of `$parent`, an `Option<TraceContext>` received from another
+/// node, or a disabled one when the message carried none.
+macro_rules! joined_span {
+    ($name:literal, $parent:expr) => {
+        match $parent {
+            | Some(parent) => tracing::debug_span!($name, traceparent = %parent),
+            | None => tracing::Span::none(),
+        }
+    };
+}
+pub(crate) use joined_span;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let context = TraceContext {
+            trace_id: 0x4bf92f3577b34da6a3ce929d0e0e4736,
+            span_id: 0xf067aa0ba902b7,
+        };
+
+        assert_eq!(context.to_string(), "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        assert_eq!(context.to_string().parse::<TraceContext>().unwrap(), context);
+        assert!("00-xyz-01".parse::<TraceContext>().is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use super::TraceContext;
+#[cfg(feature = "otlp")]
+use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceState};
+#[cfg(feature = "otlp")]
+use opentelemetry::{InstrumentationScope, KeyValue};
+#[cfg(feature = "otlp")]
+use opentelemetry_otlp::WithExportConfig;
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::Resource;
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::trace::{SpanData, SpanEvents, SpanExporter, SpanLinks};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "otlp")]
+use std::time::Duration;
+use std::time::SystemTime;
+use tracing::Subscriber;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+#[cfg(feature = "otlp")]
+use tracing::warn;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+#[cfg(feature = "otlp")]
+const EXPORT_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(feature = "otlp")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// * Spans kept while the collector is unreachable; the oldest are dropped past it
+const MAX_BUFFERED_SPANS: usize = 4096;
+// * Field through which a span starts a trace, or joins one started on another node
+const TRACEPARENT: &str = "traceparent";
+
+/// Kept in the extensions of a span that belongs to a trace.
+pub(crate) struct TracedSpan {
+    pub(crate) context: TraceContext,
+    parent_span_id: Option<u64>,
+    start: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ExportedSpan {
+    name: &'static str,
+    context: TraceContext,
+    parent_span_id: Option<u64>,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+/// Collects the spans that belong to a trace for export over OTLP.
+///
+/// A span with a `traceparent` field starts a trace, or joins the one of the given context, and
+/// the spans opened under it belong to the same trace. Spans outside of any trace, such as those
+/// of periodic heartbeats, are not exported.
+#[derive(Debug, Clone, Default)]
+pub struct OtlpLayer {
+    spans: Arc<Mutex<VecDeque<ExportedSpan>>>,
+}
+
+impl OtlpLayer {
+    /// Ships the collected spans once a second to the OTLP/gRPC collector at `endpoint` (host:port),
+    /// attributed to this node as `instance`.
+    #[cfg(feature = "otlp")]
+    pub fn export_to(self, endpoint: String, instance: String) -> anyhow::Result<Self> {
+        let url =
+            if endpoint.contains("://") { endpoint.clone() } else { format!("http://{endpoint}") };
+        let mut exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(url)
+            .with_timeout(REQUEST_TIMEOUT)
+            .build()?;
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_service_name("duva")
+                .with_attribute(KeyValue::new("service.instance.id", instance))
+                .build(),
+        );
+
+        let spans = self.spans.clone();
+        tokio::spawn(async move {
+            let scope = InstrumentationScope::builder("duva").build();
+            let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+            loop {
+                interval.tick().await;
+                let batch = std::mem::take(&mut *spans.lock().unwrap());
+                if batch.is_empty() {
+                    continue;
+                }
+                let len = batch.len();
+                let batch = batch.into_iter().map(|span| span.into_span_data(&scope)).collect();
+                if let Err(err) = exporter.export(batch).await {
+                    warn!("failed to export {len} spans to {endpoint}: {err}");
+                }
+            }
+        });
+        Ok(self)
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    pub fn export_to(self, _endpoint: String, _instance: String) -> anyhow::Result<Self> {
+        anyhow::bail!("otlp_endpoint needs duva built with the otlp feature")
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+
+        let parent = if attrs.metadata().fields().field(TRACEPARENT).is_some() {
+            fields.traceparent.and_then(|traceparent| traceparent.parse().ok())
+        } else {
+            let parent = span.parent().and_then(|parent| {
+                parent.extensions().get::<TracedSpan>().map(|traced| traced.context)
+            });
+            // * Not part of a trace
+            if parent.is_none() {
+                return;
+            }
+            parent
+        };
+        span.extensions_mut().insert(TracedSpan {
+            context: TraceContext::child_of(parent),
+            parent_span_id: parent.map(|parent| parent.span_id),
+            start: SystemTime::now(),
+            attributes: fields.attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        if let Some(traced) = span.extensions_mut().get_mut::<TracedSpan>() {
+            let mut fields = FieldVisitor::default();
+            values.record(&mut fields);
+            traced.attributes.extend(fields.attributes);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(traced) = span.extensions_mut().remove::<TracedSpan>() else { return };
+
+        let mut spans = self.spans.lock().unwrap();
+        if spans.len() >= MAX_BUFFERED_SPANS {
+            spans.pop_front();
+        }
+        spans.push_back(ExportedSpan {
+            name: span.name(),
+            context: traced.context,
+            parent_span_id: traced.parent_span_id,
+            start: traced.start,
+            end: SystemTime::now(),
+            attributes: traced.attributes,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    traceparent: Option<String>,
+    attributes: Vec<(String, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{value:?}"));
+    }
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &Field, value: String) {
+        match field.name() {
+            | TRACEPARENT => self.traceparent = Some(value),
+            | name => self.attributes.push((name.to_string(), value)),
+        }
+    }
+}
+
+#[cfg(feature = "otlp")]
+impl ExportedSpan {
+    fn into_span_data(self, scope: &InstrumentationScope) -> SpanData {
+        SpanData {
+            span_context: SpanContext::new(
+                self.context.trace_id.into(),
+                self.context.span_id.into(),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: self.parent_span_id.map_or(SpanId::INVALID, SpanId::from),
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: self.name.into(),
+            start_time: self.start,
+            end_time: self.end,
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value))
+                .collect(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: scope.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::telemetry::joined_span;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn collected(layer: &OtlpLayer) -> Vec<ExportedSpan> {
+        layer.spans.lock().unwrap().iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_spans_join_the_trace_of_a_remote_parent() {
+        let layer = OtlpLayer::default();
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        let remote = TraceContext { trace_id: 7, span_id: 3 };
+
+        let current = tracing::subscriber::with_default(subscriber, || {
+            let _untraced = tracing::debug_span!("heartbeat").entered();
+            let _joined = joined_span!("append_entries", Some(remote)).entered();
+            let _child = tracing::debug_span!("replicate", index = 1).entered();
+            TraceContext::current()
+        });
+
+        let spans = collected(&layer);
+        assert_eq!(
+            spans.iter().map(|span| span.name).collect::<Vec<_>>(),
+            ["replicate", "append_entries"]
+        );
+        let (child, joined) = (&spans[0], &spans[1]);
+        assert!(spans.iter().all(|span| span.context.trace_id == 7));
+        assert_eq!(joined.parent_span_id, Some(3));
+        assert_eq!(child.parent_span_id, Some(joined.context.span_id));
+        assert_eq!(child.attributes, [("index".to_string(), "1".to_string())]);
+        assert_eq!(current, Some(child.context));
+    }
+
+    #[test]
+    fn test_spans_outside_of_a_trace_are_not_collected() {
+        let layer = OtlpLayer::default();
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+        let current = tracing::subscriber::with_default(subscriber, || {
+            let _disabled = joined_span!("append_entries", None::<TraceContext>).entered();
+            let _untraced = tracing::debug_span!("heartbeat").entered();
+            TraceContext::current()
+        });
+
+        assert!(collected(&layer).is_empty());
+        assert_eq!(current, None);
+    }
+
+    #[test]
+    #[cfg(feature = "otlp")]
+    fn test_into_span_data() {
+        let start = SystemTime::now();
+        let span = ExportedSpan {
+            name: "append_entries",
+            context: TraceContext { trace_id: 7, span_id: 3 },
+            parent_span_id: Some(1),
+            start,
+            end: start + Duration::from_millis(5),
+            attributes: vec![("peer_id".to_string(), "127.0.0.1:6380".to_string())],
+        };
+
+        let data = span.into_span_data(&InstrumentationScope::builder("duva").build());
+
+        assert_eq!(data.span_context.trace_id(), 7.into());
+        assert_eq!(data.span_context.span_id(), 3.into());
+        assert!(data.span_context.is_sampled());
+        assert_eq!(data.parent_span_id, 1.into());
+        assert_eq!(data.name, "append_entries");
+        assert_eq!(
+            data.end_time.duration_since(data.start_time).unwrap(),
+            Duration::from_millis(5)
+        );
+        assert_eq!(data.attributes, [KeyValue::new("peer_id", "127.0.0.1:6380")]);
+    }
+}
@@ -4,33 +4,79 @@ pub mod domains;
 pub mod macros;
 pub mod presentation;
 mod types;
+use adapters::op_logs::disk_based::FileOpLogs;
+use adapters::op_logs::memory_based::MemoryOpLogs;
+use anyhow::Context;
 use anyhow::Result;
+use anyhow::bail;
 pub use config::Environment;
 use domains::IoError;
+use domains::backups::shipper::{BackupShipper, restore_from_backup};
+use domains::backups::{S3Credentials, TBackupSink, open_backup_sink};
 use domains::caches::cache_manager::CacheManager;
+use domains::caches::eviction::MaxMemory;
+use domains::caches::value_compression::ValueCompression;
 use domains::cluster_actors::ClusterActor;
 use domains::cluster_actors::ConnectionMessage;
+use domains::cluster_actors::LazyOption;
+use domains::cluster_actors::actor::topology_writer::TopologyWriter;
+use domains::cluster_actors::consensus::append_budget::AppendEntriesBudget;
+use domains::cluster_actors::consensus::compaction::LogCompaction;
+use domains::cluster_actors::consensus::group_commit::GroupCommit;
+use domains::cluster_actors::consensus::hard_state::HardStateStore;
+use domains::cluster_actors::consensus::min_replicas::MinReplicas;
+use domains::cluster_actors::consensus::pending_writes::PendingWriteLimit;
+use domains::cluster_actors::hash_ring::MigrationThrottle;
+use domains::cluster_actors::mailbox::QueueLimits;
 use domains::cluster_actors::replication::ReplicationId;
 use domains::cluster_actors::replication::ReplicationRole;
 use domains::cluster_actors::replication::ReplicationState;
+use domains::cluster_actors::topology::RoutingTable;
+use domains::cluster_actors::transactions::Transactions;
+use domains::encryption::DecryptError;
+use domains::operation_logs::WriteOperation;
 use domains::operation_logs::interfaces::TWriteAheadLog;
-use domains::saves::snapshot::Snapshot;
+use domains::operation_logs::logger::{LogSnapshot, RecoveryTarget, ReplicatedLogs};
+use domains::operation_logs::replay::{WalReplayStats, replay};
+use domains::peers::connections::cluster_secret::ClusterSecret;
+use domains::peers::connections::tls::PeerTls;
+use domains::peers::seeds::ClusterSeeds;
 use domains::saves::snapshot::snapshot_loader::SnapshotLoader;
+use domains::saves::snapshot::{Metadata, Snapshot};
+use domains::saves::status::SaveStatus;
 use presentation::clients::ClientController;
-use presentation::clients::authenticate;
+use presentation::clients::admission::ConnectionAdmission;
+use presentation::clients::audit::{AuditClient, AuditLog};
+use presentation::clients::info::ServerStats;
+use presentation::clients::keepalive::enable_keepalive;
+use presentation::clients::latency::LatencyTracker;
+use presentation::clients::monitor::Monitor;
+use presentation::clients::pause::ClientPause;
+use presentation::clients::quota::UserQuotas;
+use presentation::clients::registry::ClientRegistry;
+use presentation::clients::shutdown::{ServerShutdown, ShutdownState};
+use presentation::clients::socket::{ClientAddr, ClientSocket};
+use presentation::clients::{authenticate, reject};
 use presentation::clusters::communication_manager::ClusterCommunicationManager;
-use std::fs::File;
-use tokio::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, UnixListener, UnixStream};
+use tokio::signal::unix::{SignalKind, signal};
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::instrument;
+use tracing::warn;
 use uuid::Uuid;
 
 pub use config::ENV;
+pub use presentation::embedded::{DuvaNode, DuvaNodeBuilder};
 pub mod prelude {
+    pub use crate::domains::cluster_actors::LazyOption;
     pub use crate::domains::cluster_actors::actor::heartbeat_scheduler::LEADER_HEARTBEAT_INTERVAL_MAX;
-    pub use crate::domains::cluster_actors::topology::Topology;
+    pub use crate::domains::cluster_actors::replication::ReplicationRole;
+    pub use crate::domains::cluster_actors::topology::{Shard, Topology};
     pub use crate::domains::peers::identifier::PeerIdentifier;
     pub use crate::presentation::clients::AuthRequest;
     pub use crate::presentation::clients::AuthResponse;
@@ -46,11 +92,23 @@ pub mod prelude {
 pub struct StartUpFacade {
     cluster_communication_manager: ClusterCommunicationManager,
     cache_manager: CacheManager,
+    save_status: SaveStatus,
+    wal_replay: WalReplayStats,
+    maxmemory: MaxMemory,
+    server_stats: ServerStats,
+    monitor: Monitor,
+    latency: LatencyTracker,
+    audit: AuditLog,
+    quotas: UserQuotas,
+    clients: ClientRegistry,
+    pause: ClientPause,
+    shutdown: ServerShutdown,
+    routing: tokio::sync::watch::Receiver<RoutingTable>,
 }
 
 impl StartUpFacade {
     // Refactiring : this should run before cluster actor runs
-    fn initialize_with_snapshot() -> Snapshot {
+    fn initialize_with_snapshot() -> Result<Snapshot> {
         let path_str = format!("{}/{}", ENV.dir, ENV.dbfilename);
         let path = std::path::Path::new(path_str.as_str());
 
@@ -59,7 +117,7 @@ impl StartUpFacade {
             ReplicationId::Key(
                 ENV.stored_peer_states
                     .iter()
-                    .find(|p| p.is_self(ENV.bind_addr().as_str()))
+                    .find(|p| p.is_self(ENV.announce_addr().as_str()))
                     .map(|p| p.replid.to_string())
                     .unwrap_or_else(|| Uuid::now_v7().to_string()),
             )
@@ -67,38 +125,236 @@ impl StartUpFacade {
             ReplicationId::Undecided
         };
 
-        if let Ok(true) = path.try_exists()
-            && let Ok(snapshot) = SnapshotLoader::load_from_filepath(path)
+        if let Ok(true) = path.try_exists() {
+            match SnapshotLoader::load_from_filepath(path, &ENV.encryption_keys) {
+                | Ok(snapshot) => return Ok(snapshot),
+                // * Starting empty would discard a snapshot that is only missing its key
+                | Err(err) if err.downcast_ref::<DecryptError>().is_some() => {
+                    return Err(err.context(format!("failed to load snapshot {path_str}")));
+                },
+                | Err(_) => {},
+            }
+        }
+
+        Ok(Snapshot::default_with_repl_id(repl_id_from_topp))
+    }
+
+    /// The snapshot the node starts from, as the log keeps it.
+    fn persisted_log_snapshot(metadata: &Metadata) -> Result<Option<LogSnapshot>> {
+        if metadata.log_idx == 0 {
+            return Ok(None);
+        }
+        let path = format!("{}/{}", ENV.dir, ENV.dbfilename);
+        Ok(Some(LogSnapshot {
+            last_included_index: metadata.log_idx,
+            last_included_term: metadata.log_term,
+            data: SnapshotLoader::read_from_filepath(Path::new(&path), &ENV.encryption_keys)?,
+        }))
+    }
+
+    /// Rebuilds the snapshot and WAL segments of the node from its backup target. Runs before the
+    /// WAL is opened, so that the node starts, and joins the cluster, with the restored state.
+    pub async fn restore_from_backup() -> Result<()> {
+        let target =
+            ENV.backup_target.as_deref().context("restore_from_backup requires a backup_target")?;
+        let restored = restore_from_backup(Self::backup_sink(target)?.as_ref(), &ENV.dir).await?;
+        info!("Restored {restored} files from {target}");
+        Ok(())
+    }
+
+    fn backup_sink(target: &str) -> Result<Arc<dyn TBackupSink>> {
+        open_backup_sink(
+            target,
+            S3Credentials {
+                access_key: ENV.backup_s3_access_key.clone(),
+                secret_key: ENV.backup_s3_secret_key.clone(),
+                region: ENV.backup_s3_region.clone(),
+            },
+        )
+    }
+
+    /// Cuts the WAL back to `target` and returns the entries to replay on top of the snapshot.
+    /// Refused on nodes that join a cluster, as its leader would overwrite the recovered log.
+    fn recover_logs(
+        logs: &mut ReplicatedLogs<impl TWriteAheadLog>,
+        target: RecoveryTarget,
+    ) -> Result<Vec<WriteOperation>> {
+        if ENV.seed_server.is_some()
+            || ENV.stored_peer_states.iter().any(|peer| !peer.is_self(&ENV.announce_addr()))
         {
-            return snapshot;
+            bail!(
+                "point-in-time recovery only runs on a node that starts a cluster of its own, \
+                 without replicaof and without peers in {}",
+                ENV.tpp
+            );
+        }
+        let snapshot_index = logs.last_log_index;
+        let entries = logs.recover_to(target).context("point-in-time recovery failed")?;
+        info!(
+            "Recovering to log index {} by replaying {} entries after the snapshot at {snapshot_index}",
+            logs.last_log_index,
+            entries.len()
+        );
+        Ok(entries)
+    }
+
+    /// Sets the node up the way `ENV` describes: restored from its backup target when asked to,
+    /// with its WAL on disk when `append_only` is set and in memory otherwise.
+    pub async fn open() -> Result<Self> {
+        if ENV.restore_from_backup {
+            Self::restore_from_backup().await?;
         }
 
-        Snapshot::default_with_repl_id(repl_id_from_topp)
+        if ENV.append_only {
+            let local_aof = FileOpLogs::open(ENV.dir.clone(), ENV.encryption_keys.clone())?
+                .with_segment_size(ENV.wal_segment_size)
+                .with_fsync_policy(ENV.append_fsync)
+                .with_compression(ENV.wal_compression);
+            Self::new(local_aof, &ENV.tpp).await
+        } else {
+            Self::new(MemoryOpLogs::default(), &ENV.tpp).await
+        }
     }
 
-    pub fn new(wal: impl TWriteAheadLog, writer: File) -> Self {
-        let snapshot_info = Self::initialize_with_snapshot();
+    pub async fn new(wal: impl TWriteAheadLog, topology_path: impl Into<PathBuf>) -> Result<Self> {
+        let snapshot_info = Self::initialize_with_snapshot()?;
         let (r_id, hwm) = snapshot_info.extract_replication_info();
+        let mut logs = ReplicatedLogs::new(wal, hwm, snapshot_info.metadata.log_term);
+        // * The snapshot stands in for the entries compacted into it, and catches lagging replicas up
+        logs.snapshot = Self::persisted_log_snapshot(&snapshot_info.metadata)?;
+        let entries = match ENV.recover_to {
+            | Some(target) => Self::recover_logs(&mut logs, target)?,
+            | None => logs.entries_after_snapshot(),
+        };
 
-        let replication_state =
-            ReplicationState::new(r_id, ENV.role.clone(), &ENV.host, ENV.port, hwm);
-        let cache_manager = CacheManager::run_cache_actors(replication_state.hwm.clone());
-        tokio::spawn(cache_manager.clone().apply_snapshot(snapshot_info.key_values()));
+        let mut replication_state = ReplicationState::new(
+            r_id,
+            ENV.role.clone(),
+            ENV.announce_host(),
+            ENV.announce_port(),
+            logs.last_log_index,
+        );
+        replication_state.term = logs.last_log_term;
+        replication_state.priority = ENV.election_priority;
+        replication_state.banlist = ENV.banlist.clone();
+        replication_state.weight = ENV.partition_weight;
+        replication_state.upstream = ENV.replicate_from.clone();
+        replication_state.compression = ENV.replication_compression;
+        replication_state.cluster_secret = ENV.cluster_secret.as_deref().map(ClusterSecret::new);
+        replication_state.tls = match (&ENV.peer_tls_cert, &ENV.peer_tls_key, &ENV.peer_tls_ca) {
+            | (Some(cert), Some(key), Some(ca)) => Some(PeerTls::load(cert, key, ca)?),
+            | (None, None, None) => None,
+            | _ => {
+                anyhow::bail!("peer_tls_cert, peer_tls_key and peer_tls_ca must be set together")
+            },
+        };
+        replication_state.announce_ip = ENV.cluster_announce_ip.clone();
+        let cache_manager = CacheManager::run_sharded(
+            replication_state.hwm.clone(),
+            ENV.cache_shards,
+            ENV.cache_hash_seed,
+            ValueCompression::new(ENV.value_compression, ENV.value_compression_threshold),
+        );
+        // * Sessions of the snapshot, brought up to date with the writes replayed on top of it
+        let mut client_sessions = snapshot_info.metadata.sessions.clone();
+        entries.iter().for_each(|op| client_sessions.set_response(op.session_req.clone()));
+        let mut transactions = Transactions::restore(
+            snapshot_info.metadata.transactions.clone(),
+            snapshot_info.metadata.log_idx,
+        );
+        entries.iter().for_each(|op| transactions.track(&op.request, op.log_index));
+
+        // * Connections are accepted once `run` is called, after the replay is done
+        let leases = snapshot_info.metadata.leases.clone();
+        cache_manager.clone().apply_snapshot(snapshot_info.key_values()).await?;
+        cache_manager.restore_leases(leases).await?;
+        let wal_replay = replay(&cache_manager, entries).await;
+        if ENV.recover_to.is_some() {
+            // * Saved right away, so that the recovered state no longer depends on the WAL
+            cache_manager
+                .save_to_file(
+                    &ENV.get_filepath(),
+                    Metadata {
+                        repl_id: replication_state.replid.clone(),
+                        log_idx: logs.last_log_index,
+                        log_term: logs.last_log_term,
+                        sessions: client_sessions.clone(),
+                        leases: Default::default(),
+                        transactions: transactions.snapshot(logs.last_log_index),
+                    },
+                    SaveStatus::default(),
+                    &ENV.encryption_keys,
+                )
+                .await?;
+        }
 
+        // * Joining through a seed replaces the cluster the topology file remembers
+        let stored_peers = match ENV.seed_server {
+            | Some(_) => Vec::new(),
+            | None => ENV.stored_peer_states.clone(),
+        };
         let cluster_actor_handler = ClusterActor::run(
             ENV.ttl_mills,
-            writer,
+            TopologyWriter::spawn(topology_path),
             ENV.hf_mills,
+            ENV.raft_timings.clone(),
             replication_state,
             cache_manager.clone(),
-            wal,
+            logs,
+            LogCompaction::new(
+                ENV.snapshot_threshold,
+                ENV.wal_retained_segments,
+                Some(ENV.get_filepath()),
+            )
+            .with_catchup_lag(ENV.snapshot_catchup_lag)
+            .with_save_policy(ENV.save)
+            .with_encryption(ENV.encryption_keys.clone()),
+            HardStateStore::new(Some(PathBuf::from(format!("{}/raft.state", ENV.dir)))),
+            MinReplicas::new(ENV.min_replicas_to_write, ENV.min_replicas_max_lag),
+            AppendEntriesBudget::new(ENV.append_entries_max_entries, ENV.append_entries_max_bytes),
+            ENV.phi_threshold,
+            ENV.vnode_num,
+            MigrationThrottle::new(
+                ENV.migration_max_batches,
+                ENV.migration_max_bytes_per_sec,
+                ENV.migration_batch_size,
+                ENV.migration_batch_timeout,
+            ),
+            PendingWriteLimit::new(ENV.pending_writes_max, ENV.pending_writes_timeout)
+                .with_consensus_timeout(ENV.consensus_timeout),
+            GroupCommit::new(ENV.group_commit_window, ENV.group_commit_max_entries),
+            ENV.encryption_keys.clone(),
+            QueueLimits::new(ENV.cluster_queue_writes_max, ENV.cluster_queue_reads_max),
+            client_sessions,
+            ENV.client_session_ttl,
+            transactions,
+            stored_peers,
         );
 
-        StartUpFacade {
-            cluster_communication_manager: ClusterCommunicationManager(cluster_actor_handler),
-
+        let cluster_communication_manager = ClusterCommunicationManager(cluster_actor_handler);
+        let routing = cluster_communication_manager.route_subscribe_routing().await?;
+        Ok(StartUpFacade {
+            cluster_communication_manager,
             cache_manager,
-        }
+            save_status: SaveStatus::default(),
+            wal_replay,
+            maxmemory: MaxMemory::new(ENV.maxmemory, ENV.maxmemory_policy),
+            server_stats: ServerStats::default(),
+            monitor: Monitor::default(),
+            latency: LatencyTracker::default(),
+            audit: match ENV.audit_log.as_deref() {
+                | Some(path) => {
+                    AuditLog::open(path, ENV.audit_log_max_size, ENV.audit_log_retained)
+                        .with_context(|| format!("failed to open audit log {path}"))?
+                },
+                | None => AuditLog::default(),
+            },
+            quotas: UserQuotas::new(ENV.user_command_rate, ENV.user_output_rate),
+            clients: ClientRegistry::default(),
+            pause: ClientPause::default(),
+            shutdown: ServerShutdown::default(),
+            routing,
+        })
     }
 
     pub async fn run(self) -> Result<()> {
@@ -107,7 +363,20 @@ impl StartUpFacade {
             self.cluster_communication_manager.clone(),
         ));
 
+        tokio::spawn(self.client_controller().revoke_expired_leases());
+        tokio::spawn(self.client_controller().release_expired_locks());
+        if self.maxmemory.evicts() {
+            tokio::spawn(self.client_controller().evict_over_maxmemory());
+        }
+        if let Some(target) = ENV.backup_target.as_deref() {
+            BackupShipper::new(Self::backup_sink(target)?, &ENV.dir, ENV.dbfilename.clone())
+                .schedule(ENV.backup_interval);
+        }
+
         self.discover_cluster().await?;
+        if let Some(path) = ENV.import.clone() {
+            tokio::spawn(self.client_controller().import_on_startup(path));
+        }
         self.start_receiving_client_streams().await
     }
 
@@ -116,7 +385,9 @@ impl StartUpFacade {
             return self.cluster_communication_manager.route_connect_to_server(seed.clone()).await;
         }
 
-        for peer in ENV.stored_peer_states.iter().filter(|p| !p.is_self(&ENV.bind_addr())) {
+        let stored_peers: Vec<_> =
+            ENV.stored_peer_states.iter().filter(|p| !p.is_self(&ENV.announce_addr())).collect();
+        for peer in stored_peers.iter() {
             if let Err(err) =
                 self.cluster_communication_manager.route_connect_to_server(peer.id().clone()).await
             {
@@ -124,9 +395,46 @@ impl StartUpFacade {
             }
         }
 
+        // * Only a node with no cluster to go back to joins through the seeds
+        if let Some(seeds) = ENV.cluster_seeds.clone()
+            && stored_peers.is_empty()
+        {
+            tokio::spawn(Self::join_through_seeds(
+                self.cluster_communication_manager.clone(),
+                seeds,
+            ));
+        }
+
         Ok(())
     }
 
+    /// Meets the first seed that answers, trying again every `cluster_seeds_interval` ms until
+    /// the node knows a peer, whether through a seed or because a peer reached it first.
+    async fn join_through_seeds(manager: ClusterCommunicationManager, seeds: ClusterSeeds) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_millis(ENV.cluster_seeds_interval));
+        loop {
+            interval.tick().await;
+            match manager.route_cluster_nodes().await {
+                | Ok(nodes) if nodes.len() > 1 => return,
+                | Ok(_) => {},
+                | Err(_) => return,
+            }
+            for seed in seeds.resolve().await {
+                if *seed == ENV.bind_addr() || *seed == ENV.announce_addr() {
+                    continue;
+                }
+                match manager.route_cluster_meet(seed.clone(), LazyOption::Lazy).await {
+                    | Ok(()) => {
+                        info!("joined the cluster through seed {seed}");
+                        return;
+                    },
+                    | Err(err) => debug!("meeting seed {seed} failed: {err}"),
+                }
+            }
+        }
+    }
+
     #[instrument(skip_all)]
     async fn start_accepting_peer_connections(
         peer_bind_addr: String,
@@ -166,28 +474,120 @@ impl StartUpFacade {
     async fn start_receiving_client_streams(self) -> anyhow::Result<()> {
         let listener = TcpListener::bind(ENV.bind_addr()).await?;
         info!("start listening on {}", ENV.bind_addr());
-        let mut handles = Vec::with_capacity(100);
+        let unix_listener = match ENV.unixsocket.as_deref() {
+            | Some(path) => {
+                // * A socket file left behind by a previous run would fail the bind
+                let _ = std::fs::remove_file(path);
+                let unix_listener = UnixListener::bind(path)
+                    .with_context(|| format!("failed to listen on {path}"))?;
+                info!("start listening on {path}");
+                Some(unix_listener)
+            },
+            | None => None,
+        };
+        let mut shutdown = self.cluster_communication_manager.route_subscribe_shutdown().await?;
+        let mut shutdown_state = self.shutdown.subscribe();
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let mut admission = ConnectionAdmission::new(ENV.maxclients, ENV.connection_rate_limit);
 
         //TODO refactor: authentication should be simplified
-        while let Ok((stream, _)) = listener.accept().await {
-            let topology = self.cluster_communication_manager.route_get_topology().await?;
-
-            let is_leader: bool = self.cluster_communication_manager.route_get_role().await?
-                == ReplicationRole::Leader;
-            let Ok((reader, writer)) = authenticate(stream, topology, is_leader).await else {
-                error!("Failed to authenticate client stream");
-                continue;
+        loop {
+            // * Connections wait in the backlog while SHUTDOWN runs, and are served if it fails
+            let serving = *shutdown_state.borrow() == ShutdownState::Serving;
+            tokio::select! {
+                accepted = listener.accept(), if serving => match accepted {
+                    | Ok((stream, addr)) => {
+                        if ENV.tcp_keepalive > 0
+                            && let Err(err) =
+                                enable_keepalive(&stream, Duration::from_secs(ENV.tcp_keepalive))
+                        {
+                            warn!("Failed to enable TCP keepalive for {addr}: {err}");
+                        }
+                        self.serve_client(stream, ClientAddr::Tcp(addr), &mut admission).await?;
+                    },
+                    | Err(_) => break,
+                },
+                accepted = accept_unix(unix_listener.as_ref()), if serving => match accepted {
+                    | Ok(stream) => {
+                        self.serve_client(stream, ClientAddr::Unix, &mut admission).await?;
+                    },
+                    | Err(err) => error!("Failed to accept on the Unix socket: {err}"),
+                },
+                // * The node has left the cluster
+                // * The guard wait_for resolves to is dropped right away, as it is not Send
+                _ = async { shutdown.wait_for(|left| *left).await.is_ok() } => {
+                    info!("Shutting down after leaving the cluster");
+                    // * Gives the reply to CLUSTER LEAVE time to reach the client
+                    tokio::time::sleep(std::time::Duration::from_millis(
+                        prelude::LEADER_HEARTBEAT_INTERVAL_MAX,
+                    ))
+                    .await;
+                    break;
+                },
+                _ = shutdown_state.changed() => {
+                    if *shutdown_state.borrow() == ShutdownState::Done {
+                        // * Gives the reply to SHUTDOWN time to reach the client
+                        tokio::time::sleep(std::time::Duration::from_millis(
+                            prelude::LEADER_HEARTBEAT_INTERVAL_MAX,
+                        ))
+                        .await;
+                        break;
+                    }
+                },
+                _ = sigterm.recv() => {
+                    // * Failures are logged, and the node keeps serving as it would after SHUTDOWN
+                    let _ = self.client_controller().shutdown(None).await;
+                },
             };
+        }
 
-            let observer =
-                self.cluster_communication_manager.route_subscribe_topology_change().await?;
-            let write_handler = writer.run(observer);
+        if let Some(path) = ENV.unixsocket.as_deref() {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(())
+    }
 
-            handles.push(tokio::spawn(
-                reader.handle_client_stream(self.client_controller(), write_handler.clone()),
-            ));
+    /// Admits a client connection, TCP or Unix, and serves it once its handshake is done.
+    async fn serve_client(
+        &self,
+        stream: impl ClientSocket,
+        addr: ClientAddr,
+        admission: &mut ConnectionAdmission,
+    ) -> anyhow::Result<()> {
+        let connected = self.server_stats.connected_clients();
+        if let Err(rejection) = admission.admit(addr.ip(), connected, Instant::now()) {
+            self.server_stats.record_rejected_connection();
+            tokio::spawn(reject(stream, rejection.to_string()));
+            return Ok(());
         }
+        // * Counted from here so that connections still in their handshake count to maxclients
+        let connected = self.server_stats.connect();
 
+        let topology = self.cluster_communication_manager.route_get_topology().await?;
+
+        let is_leader: bool =
+            self.cluster_communication_manager.route_get_role().await? == ReplicationRole::Leader;
+        let (reader, writer) =
+            match authenticate(stream, addr, topology, is_leader, &self.clients).await {
+                | Ok(authenticated) => authenticated,
+                | Err(err) => {
+                    error!("Failed to authenticate client stream");
+                    let client = AuditClient { addr, client_id: None };
+                    self.audit.record("auth", client, "HANDSHAKE", Err(err.to_string()));
+                    return Ok(());
+                },
+            };
+        let client = AuditClient { addr, client_id: Some(reader.client_id) };
+        self.audit.record("auth", client, "HANDSHAKE", Ok(()));
+
+        let observer = self.cluster_communication_manager.route_subscribe_topology_change().await?;
+        let write_handler = writer.run(observer, reader.client.output(), self.server_stats.clone());
+
+        tokio::spawn(reader.handle_client_stream(
+            self.client_controller(),
+            write_handler.clone(),
+            connected,
+        ));
         Ok(())
     }
 
@@ -195,6 +595,26 @@ impl StartUpFacade {
         ClientController {
             cluster_communication_manager: self.cluster_communication_manager.clone(),
             cache_manager: self.cache_manager.clone(),
+            save_status: self.save_status.clone(),
+            wal_replay: self.wal_replay,
+            maxmemory: self.maxmemory.clone(),
+            server_stats: self.server_stats.clone(),
+            monitor: self.monitor.clone(),
+            latency: self.latency.clone(),
+            audit: self.audit.clone(),
+            quotas: self.quotas.clone(),
+            clients: self.clients.clone(),
+            pause: self.pause.clone(),
+            shutdown: self.shutdown.clone(),
+            routing: self.routing.clone(),
         }
     }
 }
+
+/// Next connection on the Unix socket, never resolving when there is none.
+async fn accept_unix(listener: Option<&UnixListener>) -> std::io::Result<UnixStream> {
+    match listener {
+        | Some(listener) => listener.accept().await.map(|(stream, _)| stream),
+        | None => std::future::pending().await,
+    }
+}
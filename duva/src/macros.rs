@@ -60,6 +60,8 @@ macro_rules! from_to {
 #[macro_export]
 macro_rules! env_var {
     (
+        args: $args:expr,
+        vars: $vars:expr,
         defaults: {
             $($name:ident : $type:ty = $default:expr),* $(,)?
         },
@@ -72,10 +74,10 @@ macro_rules! env_var {
         )*
 
         $(
-            let mut $opt_name: Option<String> = std::env::var(stringify!($opt_name)).ok();
+            let mut $opt_name: Option<String> = ($vars)(stringify!($opt_name));
         )*
 
-        let mut args = std::env::args().skip(1);
+        let mut args = $args.into_iter();
         while let Some(arg) = args.next() {
             match arg.as_str() {
                 $(
@@ -1,8 +1,10 @@
 use duva::{
-    ENV, Environment, StartUpFacade,
-    adapters::op_logs::{disk_based::FileOpLogs, memory_based::MemoryOpLogs},
+    ENV, StartUpFacade,
+    domains::telemetry::{log_filter::LogFilter, otlp::OtlpLayer},
 };
+use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -14,22 +16,24 @@ async fn main() -> anyhow::Result<()> {
     } else {
         FmtSpan::CLOSE // Only timing info in production
     };
-    tracing_subscriber::FmtSubscriber::builder()
-        .with_max_level(ENV.log_level)
+    let fmt = tracing_subscriber::fmt::layer()
         .with_span_events(span_events)
-        .init(); // Initialize the subscriber
+        // * Reloaded by CONFIG SET loglevel / log-filter
+        .with_filter(LogFilter::from_level(ENV.log_level).reloadable());
+    // * Traced spans are exported whatever the log level, as they are opened at debug level
+    let otlp = ENV
+        .otlp_endpoint
+        .clone()
+        .map(|endpoint| {
+            OtlpLayer::default().export_to(endpoint, format!("{}:{}", ENV.host, ENV.port))
+        })
+        .transpose()?
+        .map(|layer| {
+            layer.with_filter(filter_fn(|metadata| {
+                metadata.is_span() && *metadata.level() <= tracing::Level::DEBUG
+            }))
+        });
+    tracing_subscriber::registry().with(fmt).with(otlp).init(); // Initialize the subscriber
 
-    let topology_writer = Environment::open_topology_file(ENV.tpp.clone()).await;
-
-    // ! should we support type erasure?
-
-    if ENV.append_only {
-        let local_aof = FileOpLogs::new(ENV.dir.clone())?;
-        let start_up_runner = StartUpFacade::new(local_aof, topology_writer);
-        start_up_runner.run().await
-    } else {
-        let in_memory_aof = MemoryOpLogs::default();
-        let start_up_runner = StartUpFacade::new(in_memory_aof, topology_writer);
-        start_up_runner.run().await
-    }
+    StartUpFacade::open().await?.run().await
 }
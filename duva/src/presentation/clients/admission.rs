@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+// * Past it, addresses whose window has ended are forgotten
+const MAX_TRACKED_ADDRS: usize = 4096;
+
+/// Why a client connection was turned away, sent back to it in place of the handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    MaxClients,
+    RateLimited(IpAddr),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Rejection::MaxClients => write!(f, "ERR max number of clients reached"),
+            | Rejection::RateLimited(ip) => {
+                write!(f, "ERR too many connections from {ip}, try again later")
+            },
+        }
+    }
+}
+
+/// Decides whether a new client connection is served: no more than `maxclients` connections at a
+/// time, and no more than `rate_per_ip` new connections per second from one address.
+#[derive(Debug)]
+pub(crate) struct ConnectionAdmission {
+    maxclients: u64,
+    // * 0 for no limit
+    rate_per_ip: u32,
+    // * Start of the current window of each address and the connections admitted in it
+    windows: HashMap<IpAddr, (Instant, u32)>,
+}
+
+impl ConnectionAdmission {
+    pub(crate) fn new(maxclients: u64, rate_per_ip: u32) -> Self {
+        Self { maxclients, rate_per_ip, windows: HashMap::new() }
+    }
+
+    /// `ip` is None for connections over the Unix socket, which are local and never rate limited.
+    pub(crate) fn admit(
+        &mut self,
+        ip: Option<IpAddr>,
+        connected: u64,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if connected >= self.maxclients {
+            return Err(Rejection::MaxClients);
+        }
+        let Some(ip) = ip.filter(|_| self.rate_per_ip > 0) else {
+            return Ok(());
+        };
+
+        if self.windows.len() >= MAX_TRACKED_ADDRS {
+            self.windows.retain(|_, (start, _)| now.duration_since(*start) < RATE_WINDOW);
+        }
+        let (start, admitted) = self.windows.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            (*start, *admitted) = (now, 0);
+        }
+        if *admitted >= self.rate_per_ip {
+            return Err(Rejection::RateLimited(ip));
+        }
+        *admitted += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit_caps_connected_clients() {
+        let mut admission = ConnectionAdmission::new(2, 0);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+
+        assert_eq!(admission.admit(Some(ip), 1, Instant::now()), Ok(()));
+        assert_eq!(admission.admit(Some(ip), 2, Instant::now()), Err(Rejection::MaxClients));
+    }
+
+    #[test]
+    fn test_admit_limits_new_connections_per_address_and_second() {
+        let mut admission = ConnectionAdmission::new(100, 2);
+        let (ip, other): (IpAddr, IpAddr) =
+            ("127.0.0.1".parse().unwrap(), "127.0.0.2".parse().unwrap());
+        let now = Instant::now();
+
+        assert_eq!(admission.admit(Some(ip), 0, now), Ok(()));
+        assert_eq!(admission.admit(Some(ip), 0, now), Ok(()));
+        assert_eq!(admission.admit(Some(ip), 0, now), Err(Rejection::RateLimited(ip)));
+        assert_eq!(admission.admit(Some(other), 0, now), Ok(()));
+
+        // * A new window starts a second later
+        assert_eq!(admission.admit(Some(ip), 0, now + RATE_WINDOW), Ok(()));
+    }
+
+    #[test]
+    fn test_rejection_message() {
+        assert_eq!(Rejection::MaxClients.to_string(), "ERR max number of clients reached");
+        assert_eq!(
+            Rejection::RateLimited("10.0.0.1".parse().unwrap()).to_string(),
+            "ERR too many connections from 10.0.0.1, try again later"
+        );
+    }
+}
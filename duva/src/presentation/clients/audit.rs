@@ -0,0 +1,167 @@
+use super::socket::ClientAddr;
+use crate::types::json_string;
+use chrono::{SecondsFormat, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+use uuid::Uuid;
+
+/// Append-only record of administrative operations and connection handshakes, one JSON object
+/// per line. Once the file grows past `max_size` bytes it is rotated to `<path>.1`, shifting older
+/// files up to `<path>.<retained>`, past which they are removed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuditLog(Option<Arc<Mutex<AuditFile>>>);
+
+#[derive(Debug)]
+struct AuditFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    retained: usize,
+}
+
+/// Who an audited event came from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AuditClient {
+    pub(crate) addr: ClientAddr,
+    // * Unknown until the handshake has been read
+    pub(crate) client_id: Option<Uuid>,
+}
+
+impl AuditLog {
+    pub(crate) fn open(
+        path: impl AsRef<Path>,
+        max_size: u64,
+        retained: usize,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self(Some(Arc::new(Mutex::new(AuditFile { path, file, size, max_size, retained })))))
+    }
+
+    /// Records `operation` along with its outcome. Administrative operations are rare, so the line
+    /// is written right away rather than batched, and a failure to write it is logged, not returned.
+    pub(crate) fn record(
+        &self,
+        event: &str,
+        client: AuditClient,
+        operation: &str,
+        outcome: Result<(), String>,
+    ) {
+        let Some(file) = &self.0 else { return };
+        let line = format_entry(
+            &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            event,
+            client,
+            operation,
+            &outcome,
+        );
+        let mut file = file.lock().unwrap();
+        if let Err(err) = file.append(line.as_bytes()) {
+            error!("failed to write audit log {}: {err}", file.path.display());
+        }
+    }
+}
+
+impl AuditFile {
+    fn append(&mut self, line: &[u8]) -> std::io::Result<()> {
+        if self.max_size > 0 && self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{n}", self.path.display()));
+        if self.retained == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(rotated(self.retained));
+            for n in (1..self.retained).rev() {
+                if rotated(n).exists() {
+                    std::fs::rename(rotated(n), rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn format_entry(
+    time: &str,
+    event: &str,
+    client: AuditClient,
+    operation: &str,
+    outcome: &Result<(), String>,
+) -> String {
+    let client_id = client.client_id.map_or("null".to_string(), |id| json_string(&id.to_string()));
+    let outcome = match outcome {
+        | Ok(()) => r#""outcome":"ok""#.to_string(),
+        | Err(err) => format!(r#""outcome":"error","error":{}"#, json_string(err)),
+    };
+    format!(
+        "{{\"time\":{},\"event\":{},\"client\":{},\"client_id\":{client_id},\"operation\":{},{outcome}}}\n",
+        json_string(time),
+        json_string(event),
+        json_string(&client.addr.to_string()),
+        json_string(operation),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> AuditClient {
+        AuditClient { addr: ClientAddr::Tcp("127.0.0.1:60866".parse().unwrap()), client_id: None }
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let line = format_entry(
+            "2026-10-17T10:00:00.000Z",
+            "command",
+            client(),
+            "CONFIG SET appendfsync \"no\"",
+            &Err("bad\nvalue".to_string()),
+        );
+
+        assert_eq!(
+            line,
+            concat!(
+                r#"{"time":"2026-10-17T10:00:00.000Z","event":"command","client":"127.0.0.1:60866","#,
+                r#""client_id":null,"operation":"CONFIG SET appendfsync \"no\"","#,
+                r#""outcome":"error","error":"bad\nvalue"}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_rotates_past_max_size_and_keeps_retained_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let audit = AuditLog::open(&path, 200, 2).unwrap();
+
+        for n in 0..8 {
+            audit.record("command", client(), &format!("CLUSTER FORGET 127.0.0.1:{n}"), Ok(()));
+        }
+
+        let read = |p: &Path| std::fs::read_to_string(p).unwrap();
+        let rotated = |n: usize| PathBuf::from(format!("{}.{n}", path.display()));
+        assert!(read(&path).contains("127.0.0.1:7"));
+        assert!(read(&rotated(1)).contains("127.0.0.1:6"));
+        assert!(read(&rotated(2)).contains("127.0.0.1:5"));
+        assert!(!rotated(3).exists());
+        assert!(read(&path).len() <= 200);
+    }
+}
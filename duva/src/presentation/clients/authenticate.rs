@@ -1,14 +1,23 @@
 use crate::{
-    domains::{IoError, TSerdeReadWrite, cluster_actors::topology::Topology},
+    config::ENV,
+    domains::{
+        IoError, TSerdeReadWrite, cluster_actors::topology::Topology, crypto::secrets_match,
+        query_io::RESP2,
+    },
+    presentation::clients::quota::quota_key,
+    presentation::clients::registry::ClientRegistry,
+    presentation::clients::socket::{ClientAddr, ClientSocket},
     presentation::clients::stream::{ClientStreamReader, ClientStreamWriter},
 };
-use tokio::net::TcpStream;
+use bytes::BytesMut;
 use uuid::Uuid;
 
 pub(crate) async fn authenticate(
-    mut stream: TcpStream,
+    mut stream: impl ClientSocket,
+    peer_addr: ClientAddr,
     topology: Topology,
     is_leader: bool,
+    registry: &ClientRegistry,
 ) -> Result<(ClientStreamReader, ClientStreamWriter), IoError> {
     let auth_req: AuthRequest = stream.deserialized_read().await?;
 
@@ -25,20 +34,67 @@ pub(crate) async fn authenticate(
             request_id: auth_req.request_id,
             topology,
             connected_to_leader: is_leader,
+            rejection: None,
         })
         .await?;
 
-    let (r, w) = stream.into_split();
-    let reader = ClientStreamReader { r, client_id };
+    // * Lets clients that reconnect to another node carry over the password they used
+    let authenticated = ENV.requirepass.is_none()
+        || auth_req.password.as_deref().is_some_and(|password| password_matches(None, password));
+
+    let (r, w) = stream.into_halves();
+    let client = registry.register(peer_addr, DEFAULT_USER);
+    client.output().set_limit(ENV.client_output_buffer_limit_normal);
+    let reader = ClientStreamReader {
+        r,
+        client_id,
+        peer_addr,
+        protocol: RESP2,
+        buffer: BytesMut::new(),
+        read_only: false,
+        last_write_index: 0,
+        authenticated,
+        user: DEFAULT_USER.to_string(),
+        quota_key: quota_key(DEFAULT_USER, &peer_addr),
+        client,
+        monitoring: false,
+        subscribed: false,
+    };
     let sender = ClientStreamWriter(w);
 
     Ok((reader, sender))
 }
 
+/// Turns a client connection away with the reason in place of the handshake reply.
+pub(crate) async fn reject(mut stream: impl ClientSocket, reason: String) {
+    let Ok(auth_req) = stream.deserialized_read::<AuthRequest>().await else { return };
+    let _ = stream
+        .serialized_write(AuthResponse {
+            request_id: auth_req.request_id,
+            rejection: Some(reason),
+            ..Default::default()
+        })
+        .await;
+}
+
+// * The only user until ACLs are in place
+pub(crate) const DEFAULT_USER: &str = "default";
+
+/// Whether the credentials match `requirepass`, always false when none is set. The password is
+/// compared in constant time.
+pub(crate) fn password_matches(username: Option<&str>, password: &str) -> bool {
+    let Some(required) = ENV.requirepass.as_deref() else {
+        return false;
+    };
+    username.is_none_or(|username| username == DEFAULT_USER)
+        && secrets_match(password.as_bytes(), required.as_bytes())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, bincode::Decode, bincode::Encode)]
 pub struct AuthRequest {
     pub client_id: Option<String>,
     pub request_id: u64,
+    pub password: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, bincode::Decode, bincode::Encode)]
@@ -47,4 +103,6 @@ pub struct AuthResponse {
     pub request_id: u64,
     pub topology: Topology,
     pub connected_to_leader: bool,
+    // * Set when the connection was turned away, which is closed right after
+    pub rejection: Option<String>,
 }
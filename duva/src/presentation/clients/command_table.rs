@@ -0,0 +1,701 @@
+use crate::domains::QueryIO;
+
+/// Metadata of a command, as reported by `COMMAND INFO` and `COMMAND DOCS`.
+///
+/// Key positions count the command name as 0, and those of subcommands the container name as 0.
+/// A negative last key counts from the end of the arguments.
+#[derive(Debug)]
+pub(crate) struct CommandSpec {
+    pub(crate) name: &'static str,
+    // * Number of arguments including the name, or the negated minimum when it varies
+    pub(crate) arity: i64,
+    pub(crate) flags: &'static [&'static str],
+    pub(crate) first_key: i64,
+    pub(crate) last_key: i64,
+    pub(crate) key_step: i64,
+    pub(crate) group: &'static str,
+    pub(crate) summary: &'static str,
+    pub(crate) syntax: &'static str,
+    pub(crate) subcommands: &'static [CommandSpec],
+}
+
+impl CommandSpec {
+    const fn new(name: &'static str, arity: i64, flags: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            arity,
+            flags,
+            first_key: 0,
+            last_key: 0,
+            key_step: 0,
+            group: "",
+            summary: "",
+            syntax: "",
+            subcommands: &[],
+        }
+    }
+
+    const fn with_keys(self, first_key: i64, last_key: i64, key_step: i64) -> Self {
+        Self { first_key, last_key, key_step, ..self }
+    }
+
+    const fn with_docs(
+        self,
+        group: &'static str,
+        syntax: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self { group, syntax, summary, ..self }
+    }
+
+    const fn with_subcommands(self, subcommands: &'static [CommandSpec]) -> Self {
+        Self { subcommands, ..self }
+    }
+
+    /// Looks up a command, or a subcommand given as `container|subcommand`, ignoring case.
+    pub(crate) fn find(name: &str) -> Option<&'static CommandSpec> {
+        let name = name.to_lowercase();
+        let container = name.split('|').next()?;
+        let spec = COMMANDS.iter().find(|spec| spec.name == container)?;
+        if container == name {
+            return Some(spec);
+        }
+        spec.subcommands.iter().find(|sub| sub.name == name)
+    }
+
+    /// `[name, arity, flags, first key, last key, step, ACL categories, tips, key specs, subcommands]`
+    pub(crate) fn info(&self) -> QueryIO {
+        let strings = |values: &[&str]| {
+            QueryIO::Array(
+                values
+                    .iter()
+                    .map(|value| QueryIO::SimpleString(value.to_string().into()))
+                    .collect(),
+            )
+        };
+        let acl_category = format!("@{}", self.group);
+        QueryIO::Array(vec![
+            QueryIO::BulkString(self.name.to_string().into()),
+            QueryIO::SimpleString(self.arity.to_string().into()),
+            strings(self.flags),
+            QueryIO::SimpleString(self.first_key.to_string().into()),
+            QueryIO::SimpleString(self.last_key.to_string().into()),
+            QueryIO::SimpleString(self.key_step.to_string().into()),
+            strings(&[acl_category.as_str()]),
+            QueryIO::Array(vec![]),
+            QueryIO::Array(vec![]),
+            QueryIO::Array(self.subcommands.iter().map(CommandSpec::info).collect()),
+        ])
+    }
+
+    /// Summary, group and syntax of the command and of its subcommands.
+    pub(crate) fn docs(&self) -> QueryIO {
+        let mut fields = vec![
+            ("summary".to_string().into(), self.summary.to_string().into()),
+            ("group".to_string().into(), self.group.to_string().into()),
+            ("syntax".to_string().into(), self.syntax.to_string().into()),
+        ];
+        if !self.subcommands.is_empty() {
+            fields.push((
+                "subcommands".to_string().into(),
+                QueryIO::Map(
+                    self.subcommands
+                        .iter()
+                        .map(|sub| (sub.name.to_string().into(), sub.docs()))
+                        .collect(),
+                ),
+            ));
+        }
+        QueryIO::Map(fields)
+    }
+}
+
+const CLUSTER: &str = "cluster";
+const CONNECTION: &str = "connection";
+const GENERIC: &str = "generic";
+const LEASE: &str = "lease";
+const SERVER: &str = "server";
+const STRING: &str = "string";
+
+/// Every command `extract_action` accepts.
+pub(crate) static COMMANDS: &[CommandSpec] = &[
+    CommandSpec::new("append", 3, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "APPEND key value",
+        "Appends a string to the value of a key",
+    ),
+    CommandSpec::new("auth", -2, &["fast", "loading", "stale", "no_auth"]).with_docs(
+        CONNECTION,
+        "AUTH [username] password",
+        "Authenticates the connection",
+    ),
+    CommandSpec::new("batch", -4, &["write", "denyoom", "movablekeys"]).with_docs(
+        GENERIC,
+        "BATCH SET key value [; DEL key [key ...] ...]",
+        "Commits SET and DEL operations separated by ';' as one log entry",
+    ),
+    CommandSpec::new("bgsave", 1, &["admin"]).with_docs(
+        SERVER,
+        "BGSAVE",
+        "Saves a snapshot in the background",
+    ),
+    CommandSpec::new("cas", 4, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "CAS key expected value",
+        "Sets a key only if its value is the expected one",
+    ),
+    CommandSpec::new("client", -2, &[])
+        .with_docs(CONNECTION, "CLIENT subcommand", "Commands on client connections")
+        .with_subcommands(&[
+            CommandSpec::new("client|getname", 2, &["loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT GETNAME",
+                "Returns the name of the connection",
+            ),
+            CommandSpec::new("client|id", 2, &["loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT ID",
+                "Returns the id of the connection",
+            ),
+            CommandSpec::new("client|kill", -3, &["admin", "loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT KILL addr | CLIENT KILL [ID client-id] [ADDR addr]",
+                "Closes client connections",
+            ),
+            CommandSpec::new("client|list", 2, &["admin", "loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT LIST",
+                "Lists the client connections of this node",
+            ),
+            CommandSpec::new("client|pause", -3, &["admin", "loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT PAUSE timeout [WRITE | ALL]",
+                "Holds client commands back for a while",
+            ),
+            CommandSpec::new("client|setname", 3, &["loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT SETNAME name",
+                "Names the connection",
+            ),
+            CommandSpec::new("client|unpause", 2, &["admin", "loading", "stale"]).with_docs(
+                CONNECTION,
+                "CLIENT UNPAUSE",
+                "Resumes client commands held back by CLIENT PAUSE",
+            ),
+        ]),
+    CommandSpec::new("cluster", -2, &[])
+        .with_docs(CLUSTER, "CLUSTER subcommand", "Commands on the cluster topology")
+        .with_subcommands(&[
+            CommandSpec::new("cluster|banlist", -2, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER BANLIST [CLEAR [host:port]]",
+                "Lists the banned nodes with the seconds left of their bans, or lifts bans",
+            ),
+            CommandSpec::new("cluster|consensus", 2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER CONSENSUS",
+                "Reports the term, log positions, peer progress and in-flight entries",
+            ),
+            CommandSpec::new("cluster|failover", -2, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER FAILOVER [host:port]",
+                "Hands leadership over to a replica",
+            ),
+            CommandSpec::new("cluster|forget", -3, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER FORGET host:port [PERMANENT]",
+                "Removes a node from the cluster and bans it, for good with PERMANENT",
+            ),
+            CommandSpec::new("cluster|history", -2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER HISTORY [count]",
+                "Lists the most recent topology transitions",
+            ),
+            CommandSpec::new("cluster|info", 2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER INFO",
+                "Reports the state of the cluster",
+            ),
+            CommandSpec::new("cluster|leave", 2, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER LEAVE",
+                "Drains the keys of this node and leaves the cluster",
+            ),
+            CommandSpec::new("cluster|meet", -3, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER MEET host:port [LAZY | EAGER]",
+                "Joins the cluster of another node",
+            ),
+            CommandSpec::new("cluster|migrate", 5, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER MIGRATE selector TO replid",
+                "Moves the keys matching a selector to another partition",
+            ),
+            CommandSpec::new("cluster|nodes", 2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER NODES",
+                "Lists the nodes of the cluster",
+            ),
+            CommandSpec::new("cluster|reshard", -2, &["admin"]).with_docs(
+                CLUSTER,
+                "CLUSTER RESHARD [STATUS]",
+                "Rebalances the hash ring, or reports the progress of a rebalance",
+            ),
+            CommandSpec::new("cluster|shards", 2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER SHARDS",
+                "Lists the partitions with their nodes and hash ranges",
+            ),
+            CommandSpec::new("cluster|subscribe", 2, &["stale"]).with_docs(
+                CLUSTER,
+                "CLUSTER SUBSCRIBE",
+                "Pushes the partitions to the connection now and whenever they change",
+            ),
+        ]),
+    CommandSpec::new("command", -1, &["loading", "stale"])
+        .with_docs(SERVER, "COMMAND [subcommand]", "Reports the metadata of every command")
+        .with_subcommands(&[
+            CommandSpec::new("command|count", 2, &["loading", "stale"]).with_docs(
+                SERVER,
+                "COMMAND COUNT",
+                "Counts the supported commands",
+            ),
+            CommandSpec::new("command|docs", -2, &["loading", "stale"]).with_docs(
+                SERVER,
+                "COMMAND DOCS [command-name ...]",
+                "Reports the documentation of the given commands, or of every command",
+            ),
+            CommandSpec::new("command|getkeys", -3, &["loading", "stale"]).with_docs(
+                SERVER,
+                "COMMAND GETKEYS command [arg ...]",
+                "Extracts the keys of a full command",
+            ),
+            CommandSpec::new("command|info", -2, &["loading", "stale"]).with_docs(
+                SERVER,
+                "COMMAND INFO [command-name ...]",
+                "Reports the metadata of the given commands, or of every command",
+            ),
+        ]),
+    CommandSpec::new("config", -3, &["admin"])
+        .with_docs(SERVER, "CONFIG subcommand", "Reads and changes the configuration")
+        .with_subcommands(&[
+            CommandSpec::new("config|get", 3, &["admin", "loading", "stale"]).with_docs(
+                SERVER,
+                "CONFIG GET parameter",
+                "Reads a configuration parameter",
+            ),
+            CommandSpec::new("config|set", 4, &["admin", "loading", "stale"]).with_docs(
+                SERVER,
+                "CONFIG SET parameter value",
+                "Changes a configuration parameter at runtime",
+            ),
+        ]),
+    CommandSpec::new("debug", -2, &["admin"])
+        .with_docs(SERVER, "DEBUG subcommand", "Debugging commands")
+        .with_subcommands(&[
+            CommandSpec::new("debug|bigkeys", 2, &["admin"]).with_docs(
+                SERVER,
+                "DEBUG BIGKEYS",
+                "Reports the largest keys of each type",
+            ),
+            CommandSpec::new("debug|wal", 3, &["admin"]).with_docs(
+                SERVER,
+                "DEBUG WAL VERIFY",
+                "Checks the on-disk log without modifying it",
+            ),
+        ]),
+    CommandSpec::new("decr", 2, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "DECR key",
+        "Decrements the integer value of a key by one",
+    ),
+    CommandSpec::new("decrby", 3, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "DECRBY key decrement",
+        "Decrements the integer value of a key by a number",
+    ),
+    CommandSpec::new("del", -2, &["write"]).with_keys(1, -1, 1).with_docs(
+        GENERIC,
+        "DEL key [key ...]",
+        "Deletes keys",
+    ),
+    CommandSpec::new("dump", 2, &["readonly"]).with_keys(1, 1, 1).with_docs(
+        GENERIC,
+        "DUMP key",
+        "Serializes the value of a key",
+    ),
+    CommandSpec::new("echo", 2, &["fast"]).with_docs(
+        CONNECTION,
+        "ECHO message",
+        "Returns the given message",
+    ),
+    CommandSpec::new("exists", -2, &["readonly", "fast"]).with_keys(1, -1, 1).with_docs(
+        GENERIC,
+        "EXISTS key [key ...]",
+        "Counts the given keys that exist",
+    ),
+    CommandSpec::new("export", 2, &["admin"]).with_docs(
+        SERVER,
+        "EXPORT path",
+        "Writes the keys of this shard to a Redis dump file",
+    ),
+    CommandSpec::new("get", -2, &["readonly", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "GET key [LINEARIZABLE | index]",
+        "Returns the value of a key, or its value as of a log index",
+    ),
+    CommandSpec::new("hello", -1, &["fast", "loading", "stale"]).with_docs(
+        CONNECTION,
+        "HELLO [protover [AUTH username password]]",
+        "Negotiates the protocol version and reports server properties",
+    ),
+    CommandSpec::new("import", 2, &["write", "denyoom", "admin"]).with_docs(
+        SERVER,
+        "IMPORT path",
+        "Loads the keys of a Redis dump file",
+    ),
+    CommandSpec::new("incr", 2, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "INCR key",
+        "Increments the integer value of a key by one",
+    ),
+    CommandSpec::new("incrby", 3, &["write", "denyoom", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "INCRBY key increment",
+        "Increments the integer value of a key by a number",
+    ),
+    CommandSpec::new("info", -1, &["loading", "stale"]).with_docs(
+        SERVER,
+        "INFO [section]",
+        "Reports information and statistics about the server",
+    ),
+    CommandSpec::new("keys", 2, &["readonly"]).with_docs(
+        GENERIC,
+        "KEYS pattern",
+        "Lists the keys matching a pattern",
+    ),
+    CommandSpec::new("latency", -2, &["admin"])
+        .with_docs(SERVER, "LATENCY subcommand", "Per-command latency histograms")
+        .with_subcommands(&[
+            CommandSpec::new("latency|histogram", -2, &["admin", "loading", "stale"]).with_docs(
+                SERVER,
+                "LATENCY HISTOGRAM [command ...]",
+                "Reports latency percentiles of each phase of the given commands",
+            ),
+            CommandSpec::new("latency|reset", 2, &["admin", "loading", "stale"]).with_docs(
+                SERVER,
+                "LATENCY RESET",
+                "Clears the latency histograms",
+            ),
+        ]),
+    CommandSpec::new("lease", -2, &[])
+        .with_docs(LEASE, "LEASE subcommand", "Leases that delete their keys once they expire")
+        .with_subcommands(&[
+            CommandSpec::new("lease|attach", -4, &["write", "denyoom"])
+                .with_keys(3, -1, 1)
+                .with_docs(LEASE, "LEASE ATTACH id key [key ...]", "Ties keys to a lease"),
+            CommandSpec::new("lease|grant", 3, &["write", "denyoom"]).with_docs(
+                LEASE,
+                "LEASE GRANT ttl",
+                "Creates a lease",
+            ),
+            CommandSpec::new("lease|keepalive", 3, &["write", "fast"]).with_docs(
+                LEASE,
+                "LEASE KEEPALIVE id",
+                "Renews a lease",
+            ),
+            CommandSpec::new("lease|revoke", 3, &["write"]).with_docs(
+                LEASE,
+                "LEASE REVOKE id",
+                "Revokes a lease and deletes its keys",
+            ),
+        ]),
+    CommandSpec::new("lock", 3, &["write", "denyoom"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "LOCK key milliseconds",
+        "Acquires a lock that expires, returning its fencing token",
+    ),
+    CommandSpec::new("memory", -2, &[])
+        .with_docs(SERVER, "MEMORY subcommand", "Memory introspection")
+        .with_subcommands(&[
+            CommandSpec::new("memory|evictionpool", 2, &["readonly"]).with_docs(
+                SERVER,
+                "MEMORY EVICTIONPOOL",
+                "Lists the keys the eviction policy evicts first",
+            ),
+            CommandSpec::new("memory|stats", 2, &["readonly"]).with_docs(
+                SERVER,
+                "MEMORY STATS",
+                "Reports memory usage and compression savings",
+            ),
+        ]),
+    CommandSpec::new("mget", -2, &["readonly", "fast", "movablekeys"])
+        .with_keys(1, -1, 1)
+        .with_docs(STRING, "MGET key [key ...] [LINEARIZABLE]", "Returns the values of keys"),
+    CommandSpec::new("monitor", 1, &["admin", "loading", "stale"]).with_docs(
+        SERVER,
+        "MONITOR",
+        "Streams every command processed by the server",
+    ),
+    CommandSpec::new("object", -2, &[])
+        .with_docs(GENERIC, "OBJECT subcommand", "Key introspection")
+        .with_subcommands(&[
+            CommandSpec::new("object|freq", 3, &["readonly"]).with_keys(2, 2, 1).with_docs(
+                GENERIC,
+                "OBJECT FREQ key",
+                "Counts the reads and writes of a key since it was set",
+            ),
+            CommandSpec::new("object|idletime", 3, &["readonly"]).with_keys(2, 2, 1).with_docs(
+                GENERIC,
+                "OBJECT IDLETIME key",
+                "Seconds since a key was last read or written",
+            ),
+        ]),
+    CommandSpec::new("ping", 1, &["fast", "stale"]).with_docs(CONNECTION, "PING", "Returns PONG"),
+    CommandSpec::new("readonly", 1, &["fast", "loading", "stale"]).with_docs(
+        CLUSTER,
+        "READONLY",
+        "Lets the connection read from replicas",
+    ),
+    CommandSpec::new("readwrite", 1, &["fast", "loading", "stale"]).with_docs(
+        CLUSTER,
+        "READWRITE",
+        "Sends the reads of the connection back to the leader",
+    ),
+    CommandSpec::new("replicaof", 3, &["admin", "stale"]).with_docs(
+        SERVER,
+        "REPLICAOF host port",
+        "Makes this node a replica of another",
+    ),
+    CommandSpec::new("restore", -4, &["write", "denyoom"]).with_keys(1, 1, 1).with_docs(
+        GENERIC,
+        "RESTORE key ttl serialized-value [REPLACE]",
+        "Creates a key from the output of DUMP",
+    ),
+    CommandSpec::new("role", 1, &["fast", "loading", "stale"]).with_docs(
+        SERVER,
+        "ROLE",
+        "Reports the replication role of the node",
+    ),
+    CommandSpec::new("save", 1, &["admin"]).with_docs(SERVER, "SAVE", "Saves a snapshot"),
+    CommandSpec::new("set", -3, &["write", "denyoom"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "SET key value [PX milliseconds]",
+        "Sets the value of a key, optionally with an expiry",
+    ),
+    CommandSpec::new("shutdown", -1, &["admin"]).with_docs(
+        SERVER,
+        "SHUTDOWN [NOSAVE|SAVE]",
+        "Drains writes, saves and flushes state, hands off leadership and stops the node",
+    ),
+    CommandSpec::new("ttl", 2, &["readonly", "fast"]).with_keys(1, 1, 1).with_docs(
+        GENERIC,
+        "TTL key",
+        "Returns the seconds a key has left to live",
+    ),
+    CommandSpec::new("unlink", -2, &["write", "fast"]).with_keys(1, -1, 1).with_docs(
+        GENERIC,
+        "UNLINK key [key ...]",
+        "Deletes keys",
+    ),
+    CommandSpec::new("unlock", 3, &["write", "fast"]).with_keys(1, 1, 1).with_docs(
+        STRING,
+        "UNLOCK key token",
+        "Releases a lock held with the given fencing token",
+    ),
+    CommandSpec::new("wait", 3, &["blocking"]).with_docs(
+        GENERIC,
+        "WAIT numreplicas timeout",
+        "Waits until the last write of the connection reaches replicas",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::caches::cache_objects::TypedValue;
+    use crate::domains::saves::snapshot::dump_payload::DumpPayload;
+    use crate::presentation::clients::request::extract_action;
+    use bytes::Bytes;
+
+    // * A valid invocation of every command and subcommand
+    const EXAMPLES: &[&str] = &[
+        "APPEND k v",
+        "AUTH secret",
+        "AUTH default secret",
+        "BATCH SET k1 v ; DEL k2 k3",
+        "BGSAVE",
+        "CAS k old new",
+        "CLIENT GETNAME",
+        "CLIENT ID",
+        "CLIENT KILL ID 1",
+        "CLIENT LIST",
+        "CLIENT PAUSE 1000 WRITE",
+        "CLIENT SETNAME worker",
+        "CLIENT UNPAUSE",
+        "CLUSTER BANLIST CLEAR 127.0.0.1:6380",
+        "CLUSTER CONSENSUS",
+        "CLUSTER FAILOVER",
+        "CLUSTER FORGET 127.0.0.1:6380 PERMANENT",
+        "CLUSTER HISTORY 5",
+        "CLUSTER INFO",
+        "CLUSTER LEAVE",
+        "CLUSTER MEET 127.0.0.1:6380 eager",
+        "CLUSTER MIGRATE k TO replid",
+        "CLUSTER NODES",
+        "CLUSTER RESHARD STATUS",
+        "CLUSTER SHARDS",
+        "CLUSTER SUBSCRIBE",
+        "COMMAND",
+        "COMMAND COUNT",
+        "COMMAND DOCS get",
+        "COMMAND GETKEYS SET k v",
+        "COMMAND INFO get set",
+        "CONFIG GET dir",
+        "CONFIG SET appendfsync always",
+        "DEBUG BIGKEYS",
+        "DEBUG WAL VERIFY",
+        "DECR k",
+        "DECRBY k 2",
+        "DEL k1 k2",
+        "DUMP k",
+        "ECHO hi",
+        "EXISTS k1 k2",
+        "EXPORT dump.rdb",
+        "GET k",
+        "HELLO 3",
+        "IMPORT dump.rdb",
+        "INCR k",
+        "INCRBY k 2",
+        "INFO",
+        "KEYS *",
+        "LATENCY HISTOGRAM SET",
+        "LATENCY RESET",
+        "LEASE ATTACH 1 k1 k2",
+        "LEASE GRANT 1000",
+        "LEASE KEEPALIVE 1",
+        "LEASE REVOKE 1",
+        "LOCK k 1000",
+        "MEMORY EVICTIONPOOL",
+        "MEMORY STATS",
+        "MGET k1 k2 k3",
+        "MONITOR",
+        "OBJECT FREQ k",
+        "OBJECT IDLETIME k",
+        "PING",
+        "READONLY",
+        "READWRITE",
+        "REPLICAOF 127.0.0.1 6380",
+        "RESTORE k 0 {payload} REPLACE",
+        "ROLE",
+        "SAVE",
+        "SET k v PX 1000",
+        "SHUTDOWN NOSAVE",
+        "TTL k",
+        "UNLINK k1 k2",
+        "UNLOCK k 1",
+        "WAIT 1 100",
+    ];
+
+    fn spec_of(args: &[&str]) -> &'static CommandSpec {
+        let container = CommandSpec::find(args[0]).unwrap();
+        if container.subcommands.is_empty() {
+            return container;
+        }
+        CommandSpec::find(&format!("{}|{}", args[0], args.get(1).unwrap_or(&"")))
+            // * `COMMAND` on its own is not a subcommand
+            .unwrap_or(container)
+    }
+
+    /// Positions of the keys among `args`, which start with the command name.
+    fn key_positions(spec: &CommandSpec, arg_count: usize) -> Vec<usize> {
+        if spec.first_key == 0 {
+            return vec![];
+        }
+        let last_key = match spec.last_key {
+            | last if last < 0 => arg_count as i64 + last,
+            | last => last,
+        };
+        (spec.first_key..=last_key)
+            .step_by(spec.key_step.max(1) as usize)
+            .map(|i| i as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_examples_cover_every_command() {
+        let mut names: Vec<&str> = EXAMPLES
+            .iter()
+            .map(|example| spec_of(&example.split(' ').collect::<Vec<_>>()).name)
+            .collect();
+        names.dedup();
+
+        let specs: Vec<&str> = COMMANDS
+            .iter()
+            .flat_map(|spec| {
+                let subcommands = spec.subcommands.iter().map(|sub| sub.name);
+                // * Containers are covered by their subcommands, `COMMAND` also runs on its own
+                let standalone =
+                    (spec.subcommands.is_empty() || spec.name == "command").then_some(spec.name);
+                standalone.into_iter().chain(subcommands)
+            })
+            .collect();
+        assert_eq!(names, specs);
+    }
+
+    #[test]
+    fn test_metadata_matches_parsed_actions() {
+        let payload = DumpPayload::encode(&TypedValue::from("v")).unwrap();
+        for example in EXAMPLES {
+            let example = example.replace("{payload}", &payload);
+            let args: Vec<&str> = example.split(' ').collect();
+            let spec = spec_of(&args);
+            let arg_bytes: Vec<Bytes> =
+                args[1..].iter().map(|arg| Bytes::copy_from_slice(arg.as_bytes())).collect();
+            let action = extract_action(args[0], &arg_bytes)
+                .unwrap_or_else(|err| panic!("{example} does not parse: {err}"));
+
+            if spec.arity >= 0 {
+                assert_eq!(args.len() as i64, spec.arity, "{example}");
+            } else {
+                assert!(args.len() as i64 >= -spec.arity, "{example}");
+            }
+            if !spec.flags.contains(&"movablekeys") {
+                let keys: Vec<&[u8]> = key_positions(spec, args.len())
+                    .into_iter()
+                    .map(|i| args[i].as_bytes())
+                    .collect();
+                assert_eq!(keys, action.all_keys(), "{example}");
+            }
+            if action.consensus_required() {
+                assert!(spec.flags.contains(&"write"), "{example}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(CommandSpec::find("GET").unwrap().name, "get");
+        assert_eq!(CommandSpec::find("Cluster|Nodes").unwrap().name, "cluster|nodes");
+        assert!(CommandSpec::find("cluster|nope").is_none());
+        assert!(CommandSpec::find("nope").is_none());
+    }
+
+    #[test]
+    fn test_info() {
+        let QueryIO::Array(info) = CommandSpec::find("del").unwrap().info() else { panic!() };
+
+        assert_eq!(info[0], QueryIO::BulkString("del".into()));
+        assert_eq!(info[1], QueryIO::SimpleString("-2".into()));
+        assert_eq!(info[2], QueryIO::Array(vec![QueryIO::SimpleString("write".into())]));
+        assert_eq!(
+            info[3..6],
+            [
+                QueryIO::SimpleString("1".into()),
+                QueryIO::SimpleString("-1".into()),
+                QueryIO::SimpleString("1".into())
+            ]
+        );
+        assert_eq!(info.len(), 10);
+    }
+}
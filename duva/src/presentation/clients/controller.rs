@@ -1,19 +1,73 @@
 use super::request::ClientRequest;
 use crate::config::ENV;
 use crate::domains::QueryIO;
-use crate::domains::caches::cache_manager::CacheManager;
+use crate::domains::caches::cache_manager::{CacheManager, IndexedValueCodec};
 use crate::domains::caches::cache_objects::{CacheEntry, CacheValue, TypedValue};
+use crate::domains::caches::eviction::MaxMemory;
+use crate::domains::cluster_actors::consensus::applied::Applying;
+use crate::domains::cluster_actors::forwarding::{ForwardedOp, ForwardedReply};
+use crate::domains::cluster_actors::replication::{ReplicationId, ReplicationRole};
+use crate::domains::cluster_actors::topology::{RoutingTable, Shard};
+use crate::domains::cluster_actors::transactions::{TxnId, TxnOutcome, partition_requests};
 use crate::domains::cluster_actors::{ClientMessage, ConsensusClientResponse, ConsensusRequest};
-use crate::domains::saves::actor::SaveTarget;
+use crate::domains::leases::lease_partition;
+use crate::domains::operation_logs::WriteRequest;
+use crate::domains::operation_logs::replay::WalReplayStats;
+use crate::domains::query_io::RESP2;
+use crate::domains::saves::snapshot::dump_payload::DumpPayload;
+use crate::domains::saves::snapshot::redis_rdb_loader::RedisRdbLoader;
+use crate::domains::saves::snapshot::redis_rdb_writer::RedisRdbWriter;
+use crate::domains::saves::status::SaveStatus;
+use crate::domains::telemetry::log_filter;
 use crate::prelude::PeerIdentifier;
-use crate::presentation::clients::request::ClientAction;
+use crate::presentation::clients::audit::AuditLog;
+use crate::presentation::clients::command_table::{COMMANDS, CommandSpec};
+use crate::presentation::clients::info::{InfoSection, ServerStats};
+use crate::presentation::clients::latency::LatencyTracker;
+use crate::presentation::clients::monitor::Monitor;
+use crate::presentation::clients::output::OutputSender;
+use crate::presentation::clients::pause::{ClientPause, PauseMode};
+use crate::presentation::clients::quota::UserQuotas;
+use crate::presentation::clients::registry::ClientRegistry;
+use crate::presentation::clients::request::{ClientAction, ReadConsistency, extract_action};
+use crate::presentation::clients::shutdown::ServerShutdown;
 use crate::presentation::clusters::communication_manager::ClusterCommunicationManager;
+use bytes::Bytes;
+use chrono::Utc;
+use futures::future::join_all;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+const LEASE_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+const LOCK_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+const EVICTION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+// * Keys committed per log entry when importing a Redis dump
+const IMPORT_BATCH_SIZE: usize = 1000;
+const SHUTDOWN_DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+// * Writes are held for the rest of a shutdown, which ends well before this
+const SHUTDOWN_WRITE_HOLD: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[derive(Clone, Debug)]
 pub(crate) struct ClientController {
     pub(crate) cache_manager: CacheManager,
     pub(crate) cluster_communication_manager: ClusterCommunicationManager,
+    pub(crate) save_status: SaveStatus,
+    pub(crate) wal_replay: WalReplayStats,
+    pub(crate) maxmemory: MaxMemory,
+    pub(crate) server_stats: ServerStats,
+    pub(crate) monitor: Monitor,
+    pub(crate) latency: LatencyTracker,
+    pub(crate) audit: AuditLog,
+    pub(crate) quotas: UserQuotas,
+    pub(crate) clients: ClientRegistry,
+    pub(crate) pause: ClientPause,
+    pub(crate) shutdown: ServerShutdown,
+    // * Cached key ownership, so grouping keys by partition does not go through the cluster actor
+    pub(crate) routing: tokio::sync::watch::Receiver<RoutingTable>,
 }
 
 impl ClientController {
@@ -25,16 +79,29 @@ impl ClientController {
         let response = match cmd {
             | ClientAction::Ping => QueryIO::SimpleString("PONG".into()),
             | ClientAction::Echo(val) => QueryIO::BulkString(val.into()),
+            | ClientAction::Hello { protover, .. } => {
+                let role = self.cluster_communication_manager.route_get_role().await?;
+                QueryIO::Map(vec![
+                    ("server".to_string().into(), "duva".to_string().into()),
+                    ("version".to_string().into(), env!("CARGO_PKG_VERSION").to_string().into()),
+                    (
+                        "proto".to_string().into(),
+                        QueryIO::Integer(protover.unwrap_or(RESP2).into()),
+                    ),
+                    ("mode".to_string().into(), "cluster".to_string().into()),
+                    ("role".to_string().into(), role.to_string().into()),
+                ])
+            },
             | ClientAction::Set { key, value } => QueryIO::SimpleString(
                 self.cache_manager
-                    .route_set(CacheEntry::new(key, value.as_str()), current_index.unwrap())
+                    .route_set(CacheEntry::new(key, value), current_index.unwrap())
                     .await?
                     .into(),
             ),
             | ClientAction::SetWithExpiry { key, value, expiry } => QueryIO::SimpleString(
                 self.cache_manager
                     .route_set(
-                        CacheEntry::new(key, value.as_str()).with_expiry(expiry),
+                        CacheEntry::new(key, value).with_expiry(expiry),
                         current_index.unwrap(),
                     )
                     .await?
@@ -44,28 +111,47 @@ impl ClientController {
                 self.cache_manager.route_append(key, value).await?.to_string().into(),
             ),
             | ClientAction::Save => {
-                let file_path = ENV.get_filepath();
-                let file = tokio::fs::OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .create(true)
-                    .open(&file_path)
-                    .await?;
-
-                let repl_info =
-                    self.cluster_communication_manager.route_get_replication_state().await?;
-                self.cache_manager
-                    .route_save(
-                        SaveTarget::File(file),
-                        repl_info.replid,
-                        repl_info.hwm.load(Ordering::Acquire),
-                    )
-                    .await?;
-
+                self.save_status.try_start()?;
+                self.save().await?;
                 QueryIO::Null
             },
-            | ClientAction::Get { key } => self.cache_manager.route_get(key).await?.into(),
-            | ClientAction::MGet { keys } => {
+            | ClientAction::BgSave => {
+                self.save_status.try_start()?;
+                let controller = self.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = controller.save().await {
+                        error!("background save failed: {err}");
+                    }
+                });
+                QueryIO::SimpleString("Background saving started".into())
+            },
+            | ClientAction::Shutdown { save } => {
+                self.shutdown(save).await?;
+                QueryIO::SimpleString("OK".into())
+            },
+            | ClientAction::Import { path } => {
+                QueryIO::BulkString(self.import_redis_rdb(&path).await?.join("\r\n").into())
+            },
+            | ClientAction::Export { path } => QueryIO::BulkString(
+                format!("exported:{}", self.export_redis_rdb(&path).await?).into(),
+            ),
+            | ClientAction::Get { key, consistency } => {
+                // * Reads skip the cluster actor, so ownership is checked against the routing table
+                if let Some(replid) = self.routing.borrow().moved_to(&key) {
+                    return Err(anyhow::anyhow!("MOVED {replid}"));
+                }
+                match self.read_index(consistency).await? {
+                    | Some(read_idx) => {
+                        self.cache_manager.route_index_get(key, read_idx).await?.into()
+                    },
+                    | None => self.cache_manager.route_get(key).await?.into(),
+                }
+            },
+            | ClientAction::MGet { keys, consistency } => {
+                self.read_index(consistency).await?;
+                if let Some(groups) = self.spanning_partitions(&keys).await? {
+                    return self.scatter(ForwardedOp::MGet(keys), groups).await;
+                }
                 let res = self.cache_manager.route_mget(keys).await;
                 QueryIO::Array(
                     res.into_iter()
@@ -84,29 +170,95 @@ impl ClientController {
             },
             | ClientAction::Keys { pattern } => {
                 let res = self.cache_manager.route_keys(pattern).await;
-                QueryIO::Array(res.into_iter().map(|s| QueryIO::BulkString(s.into())).collect())
+                QueryIO::Array(res.into_iter().map(QueryIO::BulkString).collect())
             },
             | ClientAction::Config { key, value } => {
                 match (key.to_lowercase().as_str(), value.to_lowercase().as_str()) {
                     | ("get", "dir") => format!("dir {}", ENV.dir).into(),
                     | ("get", "dbfilename") => ENV.dbfilename.clone().into(),
+                    | ("get", "appendfsync") => format!(
+                        "appendfsync {}",
+                        self.cluster_communication_manager.route_fsync_policy().await?
+                    )
+                    .into(),
+                    | ("get", "loglevel") => {
+                        format!("loglevel {}", log_filter::current()?.level()).into()
+                    },
+                    | ("get", "log-filter") => {
+                        format!("log-filter {}", log_filter::current()?).into()
+                    },
+                    | (
+                        "get",
+                        parameter @ ("raft-heartbeat-interval"
+                        | "election-timeout-min"
+                        | "election-timeout-max"),
+                    ) => {
+                        let timings =
+                            self.cluster_communication_manager.route_raft_timings().await?;
+                        format!("{parameter} {}", timings.get(parameter).unwrap_or_default()).into()
+                    },
+                    | ("get", "client-session-ttl") => format!(
+                        "client-session-ttl {}",
+                        self.cluster_communication_manager.route_client_session_stats().await?.ttl
+                    )
+                    .into(),
                     | _ => Err(anyhow::anyhow!("Invalid command"))?,
                 }
             },
+            | ClientAction::ConfigSet { parameter, value } => {
+                match parameter.to_lowercase().as_str() {
+                    | "appendfsync" => {
+                        let policy = value.parse()?;
+                        self.cluster_communication_manager.route_set_fsync_policy(policy).await?;
+                        QueryIO::SimpleString("OK".into())
+                    },
+                    | "loglevel" => {
+                        log_filter::set_level(log_filter::parse_level(&value)?)?;
+                        QueryIO::SimpleString("OK".into())
+                    },
+                    | "log-filter" => {
+                        log_filter::reload(value.parse()?)?;
+                        QueryIO::SimpleString("OK".into())
+                    },
+                    | parameter @ ("raft-heartbeat-interval"
+                    | "election-timeout-min"
+                    | "election-timeout-max") => {
+                        self.cluster_communication_manager
+                            .route_set_raft_timing(parameter.to_string(), value.parse()?)
+                            .await?;
+                        QueryIO::SimpleString("OK".into())
+                    },
+                    | "client-session-ttl" => {
+                        self.cluster_communication_manager
+                            .route_set_client_session_ttl(value.parse()?)
+                            .await?;
+                        QueryIO::SimpleString("OK".into())
+                    },
+                    | _ => Err(anyhow::anyhow!("Unsupported CONFIG parameter: {parameter}"))?,
+                }
+            },
             | ClientAction::Delete { keys } => QueryIO::SimpleString(
                 self.cache_manager.route_delete(keys).await?.to_string().into(),
             ),
-            | ClientAction::Exists { keys } => QueryIO::SimpleString(
-                self.cache_manager.route_exists(keys).await?.to_string().into(),
-            ),
-            | ClientAction::Info => QueryIO::BulkString(
-                self.cluster_communication_manager
-                    .route_get_replication_state()
-                    .await?
-                    .vectorize()
-                    .join("\r\n")
-                    .into(),
+            | ClientAction::Unlink { keys } => QueryIO::SimpleString(
+                self.cache_manager.route_unlink(keys).await?.to_string().into(),
             ),
+            | ClientAction::Exists { keys } => {
+                if let Some(groups) = self.spanning_partitions(&keys).await? {
+                    return self.scatter(ForwardedOp::Exists(keys), groups).await;
+                }
+                QueryIO::SimpleString(
+                    self.cache_manager.route_exists(keys).await?.to_string().into(),
+                )
+            },
+            | ClientAction::Info { section } => {
+                let mut sections = vec![];
+                for section in InfoSection::parse(&section) {
+                    let fields = self.info_section(section).await?;
+                    sections.push(format!("# {}\r\n{}", section.title(), fields.join("\r\n")));
+                }
+                QueryIO::BulkString(sections.join("\r\n\r\n").into())
+            },
             | ClientAction::ClusterInfo => {
                 self.cluster_communication_manager.route_get_cluster_info().await?.into()
             },
@@ -115,16 +267,47 @@ impl ClientController {
                 .route_cluster_nodes()
                 .await?
                 .into_iter()
-                .map(|peer| peer.format(&PeerIdentifier::new(&ENV.host, ENV.port)))
+                .map(|(peer, link)| {
+                    format!(
+                        "{} {link}",
+                        peer.format(&PeerIdentifier::new(ENV.announce_host(), ENV.announce_port()))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            | ClientAction::ClusterShards => self
+                .cluster_communication_manager
+                .route_cluster_shards()
+                .await?
+                .into_iter()
+                .map(|shard| shard.format())
                 .collect::<Vec<_>>()
                 .into(),
-            | ClientAction::ClusterForget(peer_identifier) => {
-                match self.cluster_communication_manager.route_forget_peer(peer_identifier).await {
+            | ClientAction::ClusterForget(peer_identifier, permanent) => {
+                match self
+                    .cluster_communication_manager
+                    .route_forget_peer(peer_identifier, permanent)
+                    .await
+                {
                     | Ok(true) => QueryIO::SimpleString("OK".into()),
                     | Ok(false) => QueryIO::Err("No such peer".into()),
                     | Err(e) => QueryIO::Err(e.to_string().into()),
                 }
             },
+            | ClientAction::ClusterBanList => self
+                .cluster_communication_manager
+                .route_banned_peers()
+                .await?
+                .into_iter()
+                .map(|(peer, left)| match left {
+                    | Some(left) => format!("{peer} {left}"),
+                    | None => format!("{peer} permanent"),
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            | ClientAction::ClusterBanListClear(peer) => QueryIO::SimpleString(
+                self.cluster_communication_manager.route_clear_bans(peer).await?.to_string().into(),
+            ),
             | ClientAction::ClusterMeet(peer_identifier, option) => self
                 .cluster_communication_manager
                 .route_cluster_meet(peer_identifier, option)
@@ -133,14 +316,160 @@ impl ClientController {
             | ClientAction::ClusterReshard => {
                 self.cluster_communication_manager.route_cluster_reshard().await?.into()
             },
+            | ClientAction::ClusterReshardStatus => {
+                self.cluster_communication_manager.route_cluster_reshard_status().await?.into()
+            },
+            | ClientAction::ClusterConsensus => QueryIO::BulkString(
+                self.cluster_communication_manager
+                    .route_cluster_consensus()
+                    .await?
+                    .to_string()
+                    .into(),
+            ),
+            | ClientAction::ClusterHistory(count) => self
+                .cluster_communication_manager
+                .route_cluster_history(count)
+                .await?
+                .into_iter()
+                .map(|event| event.to_string())
+                .collect::<Vec<_>>()
+                .into(),
+            | ClientAction::ClusterMigrate { selector, target } => self
+                .cluster_communication_manager
+                .route_cluster_migrate(selector, target)
+                .await?
+                .into(),
+            | ClientAction::ClusterFailover(target) => {
+                self.cluster_communication_manager.route_cluster_failover(target).await?.into()
+            },
+            | ClientAction::ClusterLeave => {
+                self.cluster_communication_manager.route_cluster_leave().await?.into()
+            },
+            | ClientAction::DebugWalVerify => QueryIO::BulkString(
+                self.cluster_communication_manager.route_verify_wal().await?.to_string().into(),
+            ),
+            | ClientAction::DebugBigKeys => {
+                QueryIO::BulkString(self.cache_manager.route_big_keys().await?.to_string().into())
+            },
+            | ClientAction::MemoryEvictionPool => QueryIO::BulkString(
+                self.cache_manager
+                    .route_eviction_pool(self.maxmemory.policy)
+                    .await
+                    .to_string()
+                    .into(),
+            ),
+            | ClientAction::LatencyHistogram { commands } => {
+                QueryIO::BulkString(self.latency.histograms(&commands).join("\r\n").into())
+            },
+            | ClientAction::CommandInfo { names } if names.is_empty() => {
+                QueryIO::Array(COMMANDS.iter().map(CommandSpec::info).collect())
+            },
+            | ClientAction::CommandInfo { names } => QueryIO::Array(
+                names
+                    .iter()
+                    .map(|name| CommandSpec::find(name).map_or(QueryIO::Null, CommandSpec::info))
+                    .collect(),
+            ),
+            | ClientAction::CommandDocs { names } => {
+                let specs: Vec<&CommandSpec> = if names.is_empty() {
+                    COMMANDS.iter().collect()
+                } else {
+                    names.iter().filter_map(|name| CommandSpec::find(name)).collect()
+                };
+                QueryIO::Map(
+                    specs
+                        .into_iter()
+                        .map(|spec| (spec.name.to_string().into(), spec.docs()))
+                        .collect(),
+                )
+            },
+            | ClientAction::CommandCount => {
+                QueryIO::SimpleString(COMMANDS.len().to_string().into())
+            },
+            | ClientAction::CommandGetKeys { args } => {
+                let command = std::str::from_utf8(&args[0])
+                    .map_err(|_| anyhow::anyhow!("ERR arguments must be valid UTF-8"))?;
+                let action = extract_action(command, &args[1..])?;
+                let keys = action.all_keys();
+                if keys.is_empty() {
+                    return Err(anyhow::anyhow!("ERR The command has no key arguments"));
+                }
+                QueryIO::Array(
+                    keys.into_iter()
+                        .map(|key| QueryIO::BulkString(Bytes::copy_from_slice(key)))
+                        .collect(),
+                )
+            },
+            | ClientAction::LatencyReset => {
+                QueryIO::SimpleString(self.latency.reset().to_string().into())
+            },
+            | ClientAction::ObjectIdleTime { key } => {
+                match self.cache_manager.route_access(key).await? {
+                    | Some(access) => QueryIO::SimpleString(
+                        access.last_access.elapsed().as_secs().to_string().into(),
+                    ),
+                    | None => QueryIO::Null,
+                }
+            },
+            | ClientAction::ObjectFreq { key } => match self.cache_manager.route_access(key).await?
+            {
+                | Some(access) => QueryIO::SimpleString(access.hits.to_string().into()),
+                | None => QueryIO::Null,
+            },
+            | ClientAction::MemoryStats => {
+                let used_memory = self.cache_manager.route_memory_usage().await;
+                let compression = self.cache_manager.route_compression_stats().await;
+                QueryIO::BulkString(format!("used_memory:{used_memory}\r\n{compression}").into())
+            },
             | ClientAction::ReplicaOf(peer_identifier) => {
                 self.cluster_communication_manager.route_replicaof(peer_identifier.clone()).await?;
                 QueryIO::SimpleString("OK".into())
             },
+            // * The connection flags and the monitor and topology subscriptions are kept by the client stream
+            | ClientAction::ReadOnly
+            | ClientAction::ReadWrite
+            | ClientAction::Monitor
+            | ClientAction::ClusterSubscribe
+            | ClientAction::Auth { .. }
+            | ClientAction::ClientId
+            | ClientAction::ClientSetName(_)
+            | ClientAction::ClientGetName => QueryIO::SimpleString("OK".into()),
+            | ClientAction::ClientList => {
+                QueryIO::BulkString(self.clients.list().join("\r\n").into())
+            },
+            | ClientAction::ClientPause { timeout, mode } => {
+                self.pause.pause(mode, Duration::from_millis(timeout));
+                QueryIO::SimpleString("OK".into())
+            },
+            | ClientAction::ClientUnpause => {
+                self.pause.unpause();
+                QueryIO::SimpleString("OK".into())
+            },
+            | ClientAction::ClientKill { filter, legacy } => {
+                let killed = self.clients.kill(&filter);
+                match legacy {
+                    | true if killed == 0 => QueryIO::Err("ERR No such client".into()),
+                    | true => QueryIO::SimpleString("OK".into()),
+                    | false => QueryIO::SimpleString(killed.to_string().into()),
+                }
+            },
+            | ClientAction::Wait { numreplicas, timeout, index } => {
+                let acked = self
+                    .cluster_communication_manager
+                    .route_wait(index.unwrap_or_default(), numreplicas, timeout)
+                    .await?;
+                QueryIO::SimpleString(acked.to_string().into())
+            },
             | ClientAction::Role => {
                 let role = self.cluster_communication_manager.route_get_role();
                 QueryIO::SimpleString(role.await?.to_string().into())
             },
+            | ClientAction::Dump { key } => {
+                match DumpPayload::encode(&self.cache_manager.route_get(key).await?.value) {
+                    | Some(payload) => QueryIO::BulkString(payload.into()),
+                    | None => QueryIO::Null,
+                }
+            },
             | ClientAction::Ttl { key } => {
                 QueryIO::SimpleString(self.cache_manager.route_ttl(key).await?.into())
             },
@@ -168,32 +497,650 @@ impl ClientController {
                     .await?
                     .into(),
             ),
+            | ClientAction::Cas { key, expected, value } => QueryIO::SimpleString(
+                self.cache_manager
+                    .route_cas(key, expected, value, current_index.unwrap())
+                    .await?
+                    .into(),
+            ),
+            | ClientAction::Batch { actions } => QueryIO::SimpleString(
+                self.cache_manager
+                    .route_batch(
+                        actions.into_iter().map(ClientAction::to_write_request).collect(),
+                        current_index.unwrap(),
+                    )
+                    .await?
+                    .into(),
+            ),
+            | ClientAction::Lock { key, expiry, proposed_at } => QueryIO::SimpleString(
+                self.cache_manager
+                    .route_lock(key, expiry, proposed_at, current_index.unwrap())
+                    .await?
+                    .into(),
+            ),
+            | ClientAction::Restore { entry, replace } => {
+                if !self.cache_manager.route_restore(entry, replace).await? {
+                    return Err(anyhow::anyhow!("BUSYKEY Target key name already exists."));
+                }
+                QueryIO::SimpleString(
+                    IndexedValueCodec::encode("OK", current_index.unwrap()).into(),
+                )
+            },
+            | ClientAction::Unlock { key, token } => QueryIO::SimpleString(
+                self.cache_manager.route_unlock(key, token, current_index.unwrap()).await?.into(),
+            ),
+            | ClientAction::LeaseGrant { ttl } => QueryIO::SimpleString(
+                self.cache_manager
+                    .route_lease_grant(ttl, self.lease_partition(), current_index.unwrap())
+                    .await?
+                    .into(),
+            ),
+            | ClientAction::LeaseKeepAlive { id } => QueryIO::SimpleString(
+                self.cache_manager.route_lease_keep_alive(id, current_index.unwrap()).await?.into(),
+            ),
+            | ClientAction::LeaseAttach { id, keys } => QueryIO::SimpleString(
+                self.cache_manager
+                    .route_lease_attach(id, keys, current_index.unwrap())
+                    .await?
+                    .into(),
+            ),
+            | ClientAction::LeaseRevoke { id } => QueryIO::SimpleString(
+                self.cache_manager.route_lease_revoke(id, current_index.unwrap()).await?.into(),
+            ),
         };
 
         Ok(response)
     }
 
-    pub(crate) async fn make_consensus(
+    /// Fails when this node is a replica too far behind its leader to serve `READONLY` reads.
+    pub(crate) async fn check_replica_staleness(&self) -> anyhow::Result<()> {
+        self.cluster_communication_manager.route_check_replica_staleness(ENV.replica_max_lag).await
+    }
+
+    /// Enqueues the write for consensus without waiting for it to commit. Requests sent from the
+    /// same connection land in the leader's log in the order this is called.
+    pub(crate) async fn request_consensus(
         &self,
         request: ClientRequest,
-    ) -> anyhow::Result<(ClientAction, u64)> {
+    ) -> anyhow::Result<PendingWrite> {
+        match &request.action {
+            | ClientAction::LeaseKeepAlive { id } | ClientAction::LeaseRevoke { id } => {
+                self.check_lease_owner(*id, &[])?;
+            },
+            | ClientAction::LeaseAttach { id, keys } => self.check_lease_owner(*id, keys)?,
+            | _ => {},
+        }
+        // * Deletes are let through so that memory can still be freed
+        if self.maxmemory.refuses_writes()
+            && !matches!(request.action, ClientAction::Delete { .. } | ClientAction::Unlink { .. })
+            && self.maxmemory.is_exceeded(self.cache_manager.route_memory_usage().await)
+        {
+            return Err(anyhow::anyhow!("OOM command not allowed when used memory > 'maxmemory'"));
+        }
+        // * Each shard leader commits the deletes of its own keys
+        let deleted = match &request.action {
+            | ClientAction::Delete { keys } => Some((keys, ForwardedOp::Delete(keys.clone()))),
+            | ClientAction::Unlink { keys } => Some((keys, ForwardedOp::Unlink(keys.clone()))),
+            | _ => None,
+        };
+        if let Some((keys, op)) = deleted
+            && let Some(groups) = self.spanning_partitions(keys).await?
+        {
+            let controller = self.clone();
+            return Ok(PendingWrite::Scattered(tokio::spawn(async move {
+                controller.scatter(op, groups).await
+            })));
+        }
+        // * A batch spanning partitions is committed atomically across them
+        if let ClientAction::Batch { actions } = &request.action {
+            let requests: Vec<WriteRequest> =
+                actions.iter().cloned().map(ClientAction::to_write_request).collect();
+            let keys: Vec<Bytes> = requests
+                .iter()
+                .flat_map(WriteRequest::all_keys)
+                .map(Bytes::copy_from_slice)
+                .collect();
+            if let Some(groups) = self.spanning_partitions(&keys).await? {
+                let controller = self.clone();
+                return Ok(PendingWrite::Scattered(tokio::spawn(async move {
+                    controller.commit_across_partitions(requests, groups).await
+                })));
+            }
+        }
+
         let (tx, consensus_res) = tokio::sync::oneshot::channel();
 
         self.cluster_communication_manager
-            .send(ClientMessage::LeaderReqConsensus(ConsensusRequest::new(
-                request.action.clone().to_write_request(),
+            .send_client(ClientMessage::LeaderReqConsensus(ConsensusRequest::new(
+                self.to_write_request(request.action.clone()),
                 tx,
                 Some(request.session_req),
             )))
             .await?;
 
+        Ok(PendingWrite::Proposed(PendingConsensus { action: request.action, consensus_res }))
+    }
+
+    /// Lease grants are stamped with this node's partition, which every replica builds the id from.
+    fn to_write_request(&self, action: ClientAction) -> WriteRequest {
+        match action {
+            | ClientAction::LeaseGrant { ttl } => {
+                WriteRequest::LeaseGrant { ttl_millis: ttl, partition: self.lease_partition() }
+            },
+            | action => action.to_write_request(),
+        }
+    }
+
+    fn lease_partition(&self) -> u16 {
+        lease_partition(&self.routing.borrow().replid)
+    }
+
+    /// A lease is held by the partition that granted it, so requests for it are sent there. Only
+    /// keys of that partition may be attached, as they are deleted by it when the lease is revoked.
+    fn check_lease_owner(&self, id: u64, keys: &[Bytes]) -> anyhow::Result<()> {
+        let routing = self.routing.borrow();
+        if let Some(replid) = routing.lease_owner(id) {
+            return Err(anyhow::anyhow!("MOVED {replid}"));
+        }
+        if keys.iter().any(|key| routing.moved_to(key).is_some()) {
+            return Err(anyhow::anyhow!(
+                "CROSSSLOT Keys attached to a lease must belong to the partition holding it"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Only the leader decides that a lease has expired. The revocation goes through consensus
+    /// so that attached keys are deleted on every replica when the entry is applied.
+    pub(crate) async fn revoke_expired_leases(self) {
+        let mut interval = tokio::time::interval(LEASE_EXPIRY_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !matches!(
+                self.cluster_communication_manager.route_get_role().await,
+                Ok(ReplicationRole::Leader)
+            ) {
+                continue;
+            }
+            let Ok(expired) = self.cache_manager.route_expired_leases().await else {
+                continue;
+            };
+
+            for id in expired {
+                let request = WriteRequest::LeaseRevoke { id };
+                let result = match self.propose(request.clone()).await {
+                    | Ok((idx, _applying)) => self.cache_manager.apply_log(request, idx).await,
+                    | Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    error!("Failed to revoke expired lease {id}: {err}");
+                }
+            }
+        }
+    }
+
+    /// Only the leader decides that a lock has expired. The release goes through consensus, as an
+    /// UNLOCK with the lock's fencing token, so that every replica drops the lock at the same point
+    /// of the log rather than on its own clock. A lock taken again in between has a new token and
+    /// is left alone.
+    pub(crate) async fn release_expired_locks(self) {
+        let mut interval = tokio::time::interval(LOCK_EXPIRY_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !matches!(
+                self.cluster_communication_manager.route_get_role().await,
+                Ok(ReplicationRole::Leader)
+            ) {
+                continue;
+            }
+
+            for (key, token) in self.cache_manager.route_expired_locks(Utc::now()).await {
+                let request = WriteRequest::Unlock { key: key.clone(), token };
+                let result = match self.propose(request.clone()).await {
+                    | Ok((idx, _applying)) => self.cache_manager.apply_log(request, idx).await,
+                    | Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    error!(
+                        "Failed to release expired lock {}: {err}",
+                        String::from_utf8_lossy(&key)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Only the leader evicts. Keys over `maxmemory` are deleted through consensus so that
+    /// replicas drop the same keys when the entry is applied.
+    pub(crate) async fn evict_over_maxmemory(self) {
+        let mut interval = tokio::time::interval(EVICTION_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !matches!(
+                self.cluster_communication_manager.route_get_role().await,
+                Ok(ReplicationRole::Leader)
+            ) {
+                continue;
+            }
+            let keys = self
+                .cache_manager
+                .route_eviction_candidates(self.maxmemory.limit, self.maxmemory.policy)
+                .await;
+            if keys.is_empty() {
+                continue;
+            }
+
+            let evicted = keys.len();
+            let request = WriteRequest::Unlink { keys };
+            let result = match self.propose(request.clone()).await {
+                | Ok((idx, _applying)) => self.cache_manager.apply_log(request, idx).await,
+                | Err(err) => Err(err),
+            };
+            match result {
+                | Ok(()) => self.maxmemory.record_evictions(evicted),
+                | Err(err) => error!("Failed to evict keys over maxmemory: {err}"),
+            }
+        }
+    }
+
+    /// Positions of the given keys grouped by owning partition, when they span more than one.
+    async fn spanning_partitions(
+        &self,
+        keys: &[Bytes],
+    ) -> anyhow::Result<Option<BTreeMap<Option<ReplicationId>, Vec<usize>>>> {
+        let groups = self.routing.borrow().group_keys(keys);
+        Ok((groups.len() > 1).then_some(groups))
+    }
+
+    /// Runs each partition's share of a multi-key command on its shard leader, this node serving
+    /// its own, and merges the replies back in key order.
+    async fn scatter(
+        &self,
+        op: ForwardedOp,
+        groups: BTreeMap<Option<ReplicationId>, Vec<usize>>,
+    ) -> anyhow::Result<QueryIO> {
+        match self.run_scattered(op, groups).await {
+            | ForwardedReply::Values(values) => Ok(QueryIO::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.map_or(QueryIO::Null, |v| QueryIO::BulkString(v.into())))
+                    .collect(),
+            )),
+            | ForwardedReply::Count(count) => Ok(QueryIO::SimpleString(count.to_string().into())),
+            | ForwardedReply::Outcome(_) => Err(anyhow::anyhow!("unexpected transaction outcome")),
+            | ForwardedReply::Err(err) => Err(anyhow::anyhow!(err)),
+        }
+    }
+
+    /// Two-phase commit of a batch whose writes span partitions. Every shard leader involved first
+    /// prepares its share, locking the keys. The outcome is recorded in this partition's log before
+    /// any participant is told to commit, so that participants left hanging can still learn it.
+    async fn commit_across_partitions(
+        &self,
+        requests: Vec<WriteRequest>,
+        groups: BTreeMap<Option<ReplicationId>, Vec<usize>>,
+    ) -> anyhow::Result<QueryIO> {
+        let replid = self.cluster_communication_manager.route_get_replication_state().await?.replid;
+        let txn_id = TxnId::new(replid);
+        let applied = requests.len();
+        let shares = partition_requests(requests, groups);
+
+        let votes = join_all(shares.iter().map(|(owner, requests)| {
+            let prepare = ForwardedOp::Prepare(txn_id.clone(), requests.clone());
+            self.run_on_partition(owner.clone(), prepare)
+        }))
+        .await;
+        let rejection = votes.into_iter().find_map(|vote| match vote {
+            | ForwardedReply::Err(err) => Some(anyhow::anyhow!(err)),
+            | _ => None,
+        });
+        let decision = match rejection {
+            | Some(err) => Err(err),
+            | None => self
+                .propose(WriteRequest::TxnDecision {
+                    txn_id: txn_id.clone(),
+                    outcome: TxnOutcome::Commit,
+                })
+                .await
+                .map(|(index, _)| index),
+        };
+
+        let (acks, result) = match decision {
+            | Ok(index) => {
+                // * Participants that miss their commit ask for the outcome once they time out
+                let acks = join_all(shares.into_iter().map(|(owner, requests)| {
+                    self.run_on_partition(owner, ForwardedOp::Commit(txn_id.clone(), requests))
+                }))
+                .await;
+                (acks, Ok(QueryIO::SimpleString(IndexedValueCodec::encode(applied, index).into())))
+            },
+            | Err(err) => {
+                let acks =
+                    join_all(shares.into_keys().map(|owner| {
+                        self.run_on_partition(owner, ForwardedOp::Abort(txn_id.clone()))
+                    }))
+                    .await;
+                (acks, Err(anyhow::anyhow!("EXECABORT Transaction discarded: {err}")))
+            },
+        };
+
+        // * Once every participant applied the outcome, no one is left to ask for it
+        if acks.iter().all(|ack| !matches!(ack, ForwardedReply::Err(_))) {
+            let controller = self.clone();
+            tokio::spawn(async move {
+                let _ = controller.propose(WriteRequest::TxnEnd { txn_id }).await;
+            });
+        }
+        result
+    }
+
+    /// Runs each partition's share of a multi-key command on its shard leader and merges the
+    /// replies, without turning them into a client reply.
+    pub(crate) async fn run_scattered(
+        &self,
+        op: ForwardedOp,
+        groups: BTreeMap<Option<ReplicationId>, Vec<usize>>,
+    ) -> ForwardedReply {
+        let parts = join_all(groups.into_iter().map(|(owner, positions)| {
+            let part = op.select(&positions);
+            async move { (positions, self.run_on_partition(owner, part).await) }
+        }))
+        .await;
+        ForwardedReply::merge(op.keys().len(), parts)
+    }
+
+    /// Runs the operation on the leader of the given partition, `None` standing for this node's own.
+    async fn run_on_partition(
+        &self,
+        owner: Option<ReplicationId>,
+        op: ForwardedOp,
+    ) -> ForwardedReply {
+        match owner {
+            | None => op.execute(&self.cluster_communication_manager, &self.cache_manager).await,
+            | Some(replid) => self
+                .cluster_communication_manager
+                .route_forward_to_shard(replid, op)
+                .await
+                .unwrap_or_else(|err| ForwardedReply::Err(err.to_string())),
+        }
+    }
+
+    /// Fields of one `INFO` section.
+    async fn info_section(&self, section: InfoSection) -> anyhow::Result<Vec<String>> {
+        let mut info = vec![];
+        match section {
+            | InfoSection::Server => info.extend(self.server_stats.vectorize_server()),
+            | InfoSection::Clients => info.extend(self.server_stats.vectorize_clients()),
+            | InfoSection::Memory => {
+                let used_memory = self.cache_manager.route_memory_usage().await;
+                info.extend(self.maxmemory.vectorize(used_memory));
+            },
+            | InfoSection::Persistence => {
+                info.push(format!("aof_enabled:{}", ENV.append_only as u8));
+                info.extend(self.save_status.vectorize());
+                info.extend(self.wal_replay.vectorize());
+            },
+            | InfoSection::Stats => {
+                info.extend(self.server_stats.vectorize_stats());
+                info.extend(
+                    self.cluster_communication_manager
+                        .route_pending_write_stats()
+                        .await?
+                        .vectorize(),
+                );
+                info.extend(
+                    self.cluster_communication_manager
+                        .route_client_session_stats()
+                        .await?
+                        .vectorize(),
+                );
+                // * Read off the handle rather than asked of the actor, which may be the one backed up
+                info.extend(self.cluster_communication_manager.queue_stats());
+                info.extend(self.cache_manager.shard_stats());
+            },
+            | InfoSection::Replication => {
+                let state =
+                    self.cluster_communication_manager.route_get_replication_state().await?;
+                let links = self.cluster_communication_manager.route_replication_links().await?;
+                let (role, hwm) = (state.role.clone(), state.hwm.load(Ordering::Relaxed));
+                info.extend(state.vectorize());
+                info.extend(links.vectorize(&role, hwm));
+            },
+            | InfoSection::LatencyStats => info.extend(self.latency.vectorize()),
+            | InfoSection::Cluster => {
+                info.push("cluster_enabled:1".to_string());
+                info.extend(
+                    self.cluster_communication_manager
+                        .route_get_cluster_info()
+                        .await?
+                        .split("\r\n")
+                        .map(str::to_string),
+                );
+            },
+        }
+        Ok(info)
+    }
+
+    /// Writes a snapshot to the configured dump file. The caller must have started `save_status`.
+    async fn save(&self) -> anyhow::Result<()> {
+        let res = async {
+            let metadata = self.cluster_communication_manager.route_snapshot_metadata().await?;
+            self.cache_manager
+                .save_to_file(
+                    &ENV.get_filepath(),
+                    metadata,
+                    self.save_status.clone(),
+                    &ENV.encryption_keys,
+                )
+                .await
+        }
+        .await;
+        self.save_status.finish(res.is_ok());
+        res
+    }
+
+    /// Shuts the node down, as `SHUTDOWN` and SIGTERM do: client connections are no longer
+    /// accepted and writes are held back while in-flight writes commit, then a snapshot is saved
+    /// when asked to, the WAL and topology are flushed and a leader hands off to its most
+    /// up-to-date replica. When any of it fails the node goes back to serving clients.
+    pub(crate) async fn shutdown(&self, save: Option<bool>) -> anyhow::Result<()> {
+        if !self.shutdown.begin() {
+            return Err(anyhow::anyhow!("ERR shutdown already in progress"));
+        }
+        info!("Shutting down");
+        self.pause.pause(PauseMode::Write, SHUTDOWN_WRITE_HOLD);
+        let res = self.prepare_shutdown(save.unwrap_or(ENV.save.is_some())).await;
+        match &res {
+            | Ok(()) => self.shutdown.finish(),
+            | Err(err) => {
+                error!("Shutdown aborted: {err}");
+                self.pause.unpause();
+                self.shutdown.abort();
+            },
+        }
+        res
+    }
+
+    async fn prepare_shutdown(&self, save: bool) -> anyhow::Result<()> {
+        self.drain_consensus(Duration::from_secs(ENV.shutdown_timeout)).await?;
+        if save {
+            self.save_status.try_start()?;
+            self.save().await?;
+        }
+        self.cluster_communication_manager.route_flush().await?;
+
+        if self.cluster_communication_manager.route_get_role().await? == ReplicationRole::Leader
+            && let Err(err) = self.cluster_communication_manager.route_cluster_failover(None).await
+        {
+            // * The replicas elect a new leader once this node stops sending heartbeats
+            info!("Shutting down without handing off leadership: {err}");
+        }
+        Ok(())
+    }
+
+    /// Waits for the writes proposed so far to be decided, or for `timeout` to pass.
+    async fn drain_consensus(&self, timeout: Duration) -> anyhow::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let report = self.cluster_communication_manager.route_cluster_consensus().await?;
+            if report.in_flight.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                warn!("Shutting down with {} writes still in flight", report.in_flight.len());
+                return Ok(());
+            }
+            tokio::time::sleep(SHUTDOWN_DRAIN_INTERVAL).await;
+        }
+    }
+
+    /// Pushes the shards of the cluster to the connection behind `sender`, now and again whenever
+    /// they change, until the connection is closed. Pushes are `["topology", [<shard>, ...]]`, with
+    /// shards formatted as `CLUSTER SHARDS` lines, so that clients can route keys by hash range
+    /// without waiting to be redirected.
+    pub(crate) async fn subscribe_topology(
+        &self,
+        sender: OutputSender,
+        protocol: u8,
+    ) -> anyhow::Result<()> {
+        let manager = self.cluster_communication_manager.clone();
+        let mut changes = manager.route_subscribe_topology_change().await?;
+        let mut shards = manager.route_cluster_shards().await?;
+        sender.send(topology_push(&shards, protocol)).await?;
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(RecvError::Closed) = changes.recv().await {
+                    return;
+                }
+                let Ok(current) = manager.route_cluster_shards().await else {
+                    return;
+                };
+                // * Peers coming and going do not always move shards
+                if current == shards {
+                    continue;
+                }
+                if sender.send(topology_push(&current, protocol)).await.is_err() {
+                    return;
+                }
+                shards = current;
+            }
+        });
+        Ok(())
+    }
+
+    /// Loads the keys of a Redis dump that this partition owns. They are committed through the log in
+    /// batches, so that replicas receive them too; keys of other partitions are left to their leaders.
+    /// Returns a report of how many keys were imported and how many were skipped, and why.
+    pub(crate) async fn import_redis_rdb(&self, path: &str) -> anyhow::Result<Vec<String>> {
+        let mut rdb = RedisRdbLoader::load_from_filepath(Path::new(path))?;
+        let entries = std::mem::take(&mut rdb.entries);
+        let keys: Vec<Bytes> = entries.iter().map(|entry| entry.key.clone()).collect();
+        let groups = self.routing.borrow().group_keys(&keys);
+        let owned: HashSet<usize> = groups.get(&None).into_iter().flatten().copied().collect();
+
+        let (owned, others): (Vec<_>, Vec<_>) =
+            entries.into_iter().enumerate().partition(|(i, _)| owned.contains(i));
+        rdb.skip("other_partition", others.len());
+
+        let owned: Vec<CacheEntry> = owned.into_iter().map(|(_, entry)| entry).collect();
+        for batch in owned.chunks(IMPORT_BATCH_SIZE) {
+            let request = WriteRequest::MSet { entries: batch.to_vec() };
+            let (idx, _applying) = self.propose(request.clone()).await?;
+            self.cache_manager.apply_log(request, idx).await?;
+        }
+        Ok(rdb.vectorize(owned.len()))
+    }
+
+    /// Writes the keys this shard holds to a Redis dump at `path`, through a temporary file so that
+    /// an existing dump is only replaced once the new one is complete. Returns the number of keys written.
+    pub(crate) async fn export_redis_rdb(&self, path: &str) -> anyhow::Result<usize> {
+        let entries = self.cache_manager.route_dump_entries().await;
+        let rdb = RedisRdbWriter::encode(&entries);
+
+        let tmp_path = format!("{path}.tmp");
+        tokio::fs::write(&tmp_path, rdb).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(entries.len())
+    }
+
+    pub(crate) async fn import_on_startup(self, path: String) {
+        match self.import_redis_rdb(&path).await {
+            | Ok(report) => info!("Imported {path}: {}", report.join(", ")),
+            | Err(err) => error!("Failed to import {path}: {err}"),
+        }
+    }
+
+    /// Index the local state has to reach before a read of the given consistency can be served.
+    async fn read_index(&self, consistency: ReadConsistency) -> anyhow::Result<Option<u64>> {
+        match consistency {
+            | ReadConsistency::Local => Ok(None),
+            | ReadConsistency::Linearizable => {
+                Ok(Some(self.cluster_communication_manager.route_read_index().await?))
+            },
+        }
+    }
+
+    /// Commits the request through the log. The entry counts as applied once the returned guard
+    /// is dropped.
+    async fn propose(&self, request: WriteRequest) -> anyhow::Result<(u64, Applying)> {
+        let (tx, consensus_res) = tokio::sync::oneshot::channel();
+        self.cluster_communication_manager
+            .send_client(ClientMessage::LeaderReqConsensus(ConsensusRequest::new(
+                request, tx, None,
+            )))
+            .await?;
+
         match consensus_res.await? {
+            | ConsensusClientResponse::LogIndex(idx, applying) => Ok((idx, applying)),
+            | ConsensusClientResponse::AlreadyProcessed { index: idx, .. } => {
+                Ok((idx, Applying::default()))
+            },
+            | ConsensusClientResponse::Err(error_msg) => Err(anyhow::anyhow!(error_msg)),
+        }
+    }
+}
+
+fn topology_push(shards: &[Shard], protocol: u8) -> QueryIO {
+    let push = QueryIO::Push(vec![
+        QueryIO::BulkString("topology".into()),
+        shards.iter().map(Shard::format).collect::<Vec<_>>().into(),
+    ]);
+    if protocol == RESP2 { push.into_resp2() } else { push }
+}
+
+pub(crate) enum PendingWrite {
+    Proposed(PendingConsensus),
+    // * Keys spread over several partitions, each committed by its own shard leader
+    Scattered(tokio::task::JoinHandle<anyhow::Result<QueryIO>>),
+}
+
+pub(crate) struct PendingConsensus {
+    action: ClientAction,
+    consensus_res: tokio::sync::oneshot::Receiver<ConsensusClientResponse>,
+}
+
+impl PendingConsensus {
+    /// The committed action and its log index. The entry counts as applied once the returned guard
+    /// is dropped.
+    pub(crate) async fn wait(self) -> anyhow::Result<(ClientAction, u64, Applying)> {
+        match self.consensus_res.await? {
             | ConsensusClientResponse::AlreadyProcessed { key: keys, index } => {
                 // * Conversion! request has already been processed so we need to convert it to get
-                let action = ClientAction::MGet { keys };
-                Ok((action, index))
+                let action = ClientAction::MGet { keys, consistency: ReadConsistency::Local };
+                Ok((action, index, Applying::default()))
             },
-            | ConsensusClientResponse::LogIndex(idx) => Ok((request.action, idx)),
+            | ConsensusClientResponse::LogIndex(idx, applying) => Ok((self.action, idx, applying)),
+            | ConsensusClientResponse::Err(error_msg) => Err(anyhow::anyhow!(error_msg)),
+        }
+    }
+
+    /// The log index the write was committed at, `None` when its session had it processed already.
+    /// The entry counts as applied once the returned guard is dropped.
+    pub(crate) async fn committed(self) -> anyhow::Result<Option<(u64, Applying)>> {
+        match self.consensus_res.await? {
+            | ConsensusClientResponse::AlreadyProcessed { .. } => Ok(None),
+            | ConsensusClientResponse::LogIndex(idx, applying) => Ok(Some((idx, applying))),
             | ConsensusClientResponse::Err(error_msg) => Err(anyhow::anyhow!(error_msg)),
         }
     }
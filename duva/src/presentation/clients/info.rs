@@ -0,0 +1,193 @@
+use crate::config::ENV;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// A section of the `INFO` reply, titled and named after its Redis counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InfoSection {
+    Server,
+    Clients,
+    Memory,
+    Persistence,
+    Stats,
+    Replication,
+    Cluster,
+    LatencyStats,
+}
+
+impl InfoSection {
+    const ALL: [Self; 8] = [
+        Self::Server,
+        Self::Clients,
+        Self::Memory,
+        Self::Persistence,
+        Self::Stats,
+        Self::Replication,
+        Self::Cluster,
+        Self::LatencyStats,
+    ];
+
+    /// Sections requested by the argument of `INFO`. Unknown sections yield none, as in Redis.
+    pub(crate) fn parse(section: &str) -> Vec<Self> {
+        match section.to_lowercase().as_str() {
+            | "default" | "all" | "everything" => Self::ALL.to_vec(),
+            | "server" => vec![Self::Server],
+            | "clients" => vec![Self::Clients],
+            | "memory" => vec![Self::Memory],
+            | "persistence" => vec![Self::Persistence],
+            | "stats" => vec![Self::Stats],
+            | "replication" => vec![Self::Replication],
+            | "cluster" => vec![Self::Cluster],
+            | "latencystats" => vec![Self::LatencyStats],
+            | _ => vec![],
+        }
+    }
+
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            | Self::Server => "Server",
+            | Self::Clients => "Clients",
+            | Self::Memory => "Memory",
+            | Self::Persistence => "Persistence",
+            | Self::Stats => "Stats",
+            | Self::Replication => "Replication",
+            | Self::Cluster => "Cluster",
+            | Self::LatencyStats => "Latencystats",
+        }
+    }
+}
+
+/// Process-wide counters of client connections and commands, shared by every client connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ServerStats {
+    started_at: Instant,
+    connected_clients: Arc<AtomicU64>,
+    total_connections_received: Arc<AtomicU64>,
+    total_commands_processed: Arc<AtomicU64>,
+    rejected_connections: Arc<AtomicU64>,
+    output_limit_disconnections: Arc<AtomicU64>,
+}
+
+impl Default for ServerStats {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            connected_clients: Arc::default(),
+            total_connections_received: Arc::default(),
+            total_commands_processed: Arc::default(),
+            rejected_connections: Arc::default(),
+            output_limit_disconnections: Arc::default(),
+        }
+    }
+}
+
+impl ServerStats {
+    /// Counts a client connection in until the returned guard is dropped.
+    pub(crate) fn connect(&self) -> ConnectedClient {
+        self.connected_clients.fetch_add(1, Ordering::Relaxed);
+        self.total_connections_received.fetch_add(1, Ordering::Relaxed);
+        ConnectedClient(self.connected_clients.clone())
+    }
+
+    pub(crate) fn connected_clients(&self) -> u64 {
+        self.connected_clients.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_rejected_connection(&self) {
+        self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_output_limit_disconnection(&self) {
+        self.output_limit_disconnections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_command(&self) {
+        self.total_commands_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn vectorize_server(&self) -> Vec<String> {
+        let uptime = self.started_at.elapsed().as_secs();
+        vec![
+            format!("duva_version:{}", env!("CARGO_PKG_VERSION")),
+            "redis_mode:cluster".to_string(),
+            format!("os:{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            format!("arch_bits:{}", usize::BITS),
+            format!("process_id:{}", std::process::id()),
+            format!("tcp_port:{}", ENV.port),
+            format!("uptime_in_seconds:{uptime}"),
+            format!("uptime_in_days:{}", uptime / 86400),
+        ]
+    }
+
+    pub(crate) fn vectorize_clients(&self) -> Vec<String> {
+        vec![
+            format!("connected_clients:{}", self.connected_clients()),
+            format!("maxclients:{}", ENV.maxclients),
+            format!("connection_rate_limit:{}", ENV.connection_rate_limit),
+        ]
+    }
+
+    pub(crate) fn vectorize_stats(&self) -> Vec<String> {
+        vec![
+            format!(
+                "total_connections_received:{}",
+                self.total_connections_received.load(Ordering::Relaxed)
+            ),
+            format!(
+                "total_commands_processed:{}",
+                self.total_commands_processed.load(Ordering::Relaxed)
+            ),
+            format!("rejected_connections:{}", self.rejected_connections.load(Ordering::Relaxed)),
+            format!(
+                "client_output_buffer_limit_disconnections:{}",
+                self.output_limit_disconnections.load(Ordering::Relaxed)
+            ),
+        ]
+    }
+}
+
+/// Keeps a client counted in `connected_clients` while its connection is served.
+pub(crate) struct ConnectedClient(Arc<AtomicU64>);
+
+impl Drop for ConnectedClient {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sections() {
+        assert_eq!(InfoSection::parse("default"), InfoSection::ALL.to_vec());
+        assert_eq!(InfoSection::parse("EVERYTHING"), InfoSection::ALL.to_vec());
+        assert_eq!(InfoSection::parse("Replication"), vec![InfoSection::Replication]);
+        assert!(InfoSection::parse("keyspace").is_empty());
+    }
+
+    #[test]
+    fn test_connected_clients_drop_with_their_guard() {
+        let stats = ServerStats::default();
+        let first = stats.connect();
+        let _second = stats.connect();
+        drop(first);
+        stats.record_command();
+        stats.record_rejected_connection();
+        stats.record_output_limit_disconnection();
+
+        assert_eq!(stats.connected_clients(), 1);
+        assert_eq!(stats.vectorize_clients()[0], "connected_clients:1");
+        assert_eq!(
+            stats.vectorize_stats(),
+            vec![
+                "total_connections_received:2",
+                "total_commands_processed:1",
+                "rejected_connections:1",
+                "client_output_buffer_limit_disconnections:1"
+            ]
+        );
+    }
+}
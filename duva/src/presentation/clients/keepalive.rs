@@ -0,0 +1,92 @@
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+// * Unanswered probes after which the connection is dropped, as Redis does
+const KEEPALIVE_PROBES: u32 = 3;
+
+/// Turns on TCP keepalive for an accepted client connection, so that connections whose other end
+/// went away without closing them are dropped rather than lingering. The first probe is sent after
+/// `interval` without traffic, and the rest a third of it apart.
+pub(crate) fn enable_keepalive(stream: &TcpStream, interval: Duration) -> std::io::Result<()> {
+    sys::enable_keepalive(stream, interval)
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::KEEPALIVE_PROBES;
+    use std::ffi::{c_int, c_void};
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+
+    const SOL_SOCKET: c_int = 1;
+    const SO_KEEPALIVE: c_int = 9;
+    const IPPROTO_TCP: c_int = 6;
+    const TCP_KEEPIDLE: c_int = 4;
+    const TCP_KEEPINTVL: c_int = 5;
+    const TCP_KEEPCNT: c_int = 6;
+
+    unsafe extern "C" {
+        fn setsockopt(
+            socket: c_int,
+            level: c_int,
+            name: c_int,
+            value: *const c_void,
+            len: u32,
+        ) -> c_int;
+    }
+
+    pub(super) fn enable_keepalive(stream: &TcpStream, interval: Duration) -> std::io::Result<()> {
+        let idle = interval.as_secs().clamp(1, c_int::MAX as u64) as c_int;
+        let options = [
+            (SOL_SOCKET, SO_KEEPALIVE, 1),
+            (IPPROTO_TCP, TCP_KEEPIDLE, idle),
+            (IPPROTO_TCP, TCP_KEEPINTVL, (idle / 3).max(1)),
+            (IPPROTO_TCP, TCP_KEEPCNT, KEEPALIVE_PROBES as c_int),
+        ];
+        for (level, name, value) in options {
+            // SAFETY: the descriptor is owned by `stream` for the duration of the call, and the
+            // value points to a live c_int whose size is passed along with it
+            let result = unsafe {
+                setsockopt(
+                    stream.as_raw_fd(),
+                    level,
+                    name,
+                    &value as *const c_int as *const c_void,
+                    size_of::<c_int>() as u32,
+                )
+            };
+            if result != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+// * The probe timings are only set on Linux; elsewhere connections keep the system defaults
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+
+    pub(super) fn enable_keepalive(_: &TcpStream, _: Duration) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_enable_keepalive_on_an_accepted_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        enable_keepalive(&stream, Duration::from_secs(300)).unwrap();
+        enable_keepalive(&stream, Duration::ZERO).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// * Latencies below this many microseconds get a bucket each; past it every doubling is split into
+// * SUB_BUCKETS buckets, which bounds the error of a percentile to 1/SUB_BUCKETS
+const LINEAR_BUCKETS: u64 = 16;
+const SUB_BUCKETS: u64 = 8;
+
+/// Stage of serving a client command whose latency is tracked on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    // * Turning the bytes read off the connection into a request
+    Parse,
+    // * Committing a write through the log; reads skip this phase
+    Consensus,
+    // * Running the command against the cache actors
+    Apply,
+    // * Handing the reply to the connection's writer
+    Reply,
+}
+
+impl Phase {
+    const ALL: [Self; 4] = [Self::Parse, Self::Consensus, Self::Apply, Self::Reply];
+
+    fn name(&self) -> &'static str {
+        match self {
+            | Self::Parse => "parse",
+            | Self::Consensus => "consensus",
+            | Self::Apply => "apply",
+            | Self::Reply => "reply",
+        }
+    }
+}
+
+/// Log-linear histogram of latencies in microseconds.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed: Duration) {
+        let bucket = bucket_of(elapsed.as_micros().min(u64::MAX as u128) as u64);
+        if self.counts.len() <= bucket {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+        self.total += 1;
+    }
+
+    /// Upper bound, in microseconds, of the latency `percentile`% of the samples stay within.
+    fn percentile(&self, percentile: f64) -> u64 {
+        let rank = ((percentile / 100.0 * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return upper_bound_of(bucket);
+            }
+        }
+        0
+    }
+}
+
+fn bucket_of(micros: u64) -> usize {
+    if micros < LINEAR_BUCKETS {
+        return micros as usize;
+    }
+    let exp = 63 - micros.leading_zeros() as u64;
+    let sub = (micros >> (exp - SUB_BUCKETS.trailing_zeros() as u64)) & (SUB_BUCKETS - 1);
+    (LINEAR_BUCKETS + (exp - LINEAR_BUCKETS.trailing_zeros() as u64) * SUB_BUCKETS + sub) as usize
+}
+
+fn upper_bound_of(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+    if bucket < LINEAR_BUCKETS {
+        return bucket;
+    }
+    let exp = (bucket - LINEAR_BUCKETS) / SUB_BUCKETS + LINEAR_BUCKETS.trailing_zeros() as u64;
+    let sub = (bucket - LINEAR_BUCKETS) % SUB_BUCKETS;
+    let width = 1u64 << (exp - SUB_BUCKETS.trailing_zeros() as u64);
+    ((SUB_BUCKETS + sub) * width).saturating_add(width - 1)
+}
+
+/// Latency histograms of every command served by the node, one per phase, shared by every client
+/// connection. Reported by `LATENCY HISTOGRAM` and `INFO latencystats`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LatencyTracker(Arc<Mutex<BTreeMap<String, [Histogram; 4]>>>);
+
+impl LatencyTracker {
+    pub(crate) fn record(&self, command: &str, phase: Phase, elapsed: Duration) {
+        let mut commands = self.0.lock().unwrap();
+        let histograms = match commands.get_mut(command) {
+            | Some(histograms) => histograms,
+            | None => commands.entry(command.to_string()).or_default(),
+        };
+        histograms[phase as usize].record(elapsed);
+    }
+
+    /// Forgets every sample. Returns how many commands had any.
+    pub(crate) fn reset(&self) -> usize {
+        std::mem::take(&mut *self.0.lock().unwrap()).len()
+    }
+
+    /// A line per phase of each of `commands` that has samples, or of every command if none are
+    /// given, e.g. `SET consensus calls=10 p50=120 p99=480 p999=511` in microseconds.
+    pub(crate) fn histograms(&self, commands: &[String]) -> Vec<String> {
+        self.lines(commands, |command, phase, histogram| {
+            format!(
+                "{command} {} calls={} p50={} p99={} p999={}",
+                phase.name(),
+                histogram.total,
+                histogram.percentile(50.0),
+                histogram.percentile(99.0),
+                histogram.percentile(99.9),
+            )
+        })
+    }
+
+    /// `INFO latencystats` fields, named the way Redis reports per-command percentiles.
+    pub(crate) fn vectorize(&self) -> Vec<String> {
+        self.lines(&[], |command, phase, histogram| {
+            format!(
+                "latency_percentiles_usec_{}_{}:p50={},p99={},p99.9={}",
+                command.to_lowercase(),
+                phase.name(),
+                histogram.percentile(50.0),
+                histogram.percentile(99.0),
+                histogram.percentile(99.9),
+            )
+        })
+    }
+
+    fn lines(
+        &self,
+        commands: &[String],
+        line: impl Fn(&str, Phase, &Histogram) -> String,
+    ) -> Vec<String> {
+        let tracked = self.0.lock().unwrap();
+        tracked
+            .iter()
+            .filter(|(command, _)| {
+                commands.is_empty() || commands.iter().any(|c| c.eq_ignore_ascii_case(command))
+            })
+            .flat_map(|(command, histograms)| {
+                Phase::ALL
+                    .iter()
+                    .filter(|phase| histograms[**phase as usize].total > 0)
+                    .map(|phase| line(command, *phase, &histograms[*phase as usize]))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_bound_the_error() {
+        for micros in [0, 1, 15, 16, 17, 100, 1_000, 123_456, u64::MAX / 2] {
+            let upper = upper_bound_of(bucket_of(micros));
+            assert!(upper >= micros, "{micros} -> {upper}");
+            assert!(upper - micros <= micros / SUB_BUCKETS, "{micros} -> {upper}");
+        }
+        assert_eq!(upper_bound_of(bucket_of(u64::MAX)), u64::MAX);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::default();
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(histogram.total, 1000);
+        assert!((500..=500 + 500 / SUB_BUCKETS).contains(&histogram.percentile(50.0)));
+        assert!((990..=990 + 990 / SUB_BUCKETS).contains(&histogram.percentile(99.0)));
+        assert_eq!(Histogram::default().percentile(99.0), 0);
+    }
+
+    #[test]
+    fn test_tracker_reports_phases_with_samples() {
+        let tracker = LatencyTracker::default();
+        tracker.record("SET", Phase::Consensus, Duration::from_micros(10));
+        tracker.record("SET", Phase::Apply, Duration::from_micros(3));
+        tracker.record("GET", Phase::Apply, Duration::from_micros(2));
+
+        assert_eq!(
+            tracker.histograms(&["set".to_string()]),
+            vec![
+                "SET consensus calls=1 p50=10 p99=10 p999=10",
+                "SET apply calls=1 p50=3 p99=3 p999=3"
+            ]
+        );
+        assert_eq!(
+            tracker.vectorize()[0],
+            "latency_percentiles_usec_get_apply:p50=2,p99=2,p99.9=2"
+        );
+        assert_eq!(tracker.reset(), 2);
+        assert!(tracker.histograms(&[]).is_empty());
+    }
+}
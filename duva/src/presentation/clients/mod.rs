@@ -1,8 +1,21 @@
+pub(crate) mod admission;
+pub(crate) mod audit;
 mod authenticate;
+pub(crate) mod command_table;
 pub mod controller;
+pub(crate) mod info;
+pub(crate) mod keepalive;
+pub(crate) mod latency;
+pub(crate) mod monitor;
+pub mod output;
+pub mod pause;
+pub(crate) mod quota;
+pub(crate) mod registry;
 pub mod request;
+pub(crate) mod shutdown;
+pub(crate) mod socket;
 pub mod stream;
 pub use authenticate::AuthRequest;
 pub use authenticate::AuthResponse;
-pub(crate) use authenticate::authenticate;
+pub(crate) use authenticate::{authenticate, reject};
 pub(crate) use controller::ClientController;
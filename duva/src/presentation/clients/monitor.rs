@@ -0,0 +1,140 @@
+use super::output::OutputSender;
+use super::socket::ClientAddr;
+use crate::domains::QueryIO;
+use bytes::Bytes;
+use chrono::Utc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::error::TrySendError;
+
+// * Lines a monitoring connection may fall behind by before the oldest are dropped
+const MONITOR_BUFFER: usize = 1024;
+
+/// Taps the commands clients send, for connections that issued `MONITOR`.
+#[derive(Debug, Clone)]
+pub(crate) struct Monitor(broadcast::Sender<Bytes>);
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self(broadcast::channel(MONITOR_BUFFER).0)
+    }
+}
+
+impl Monitor {
+    /// Streams a command to the monitoring connections, if there are any. Lines are formatted the
+    /// way Redis does, e.g. `1339518083.107412 [127.0.0.1:60866] "SET" "foo" "bar"`. The arguments
+    /// of `AUTH` and the credentials of `HELLO`'s `AUTH` clause are redacted.
+    pub(crate) fn publish(&self, client: ClientAddr, args: &[QueryIO]) {
+        if self.0.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.0.send(format_line(Utc::now().timestamp_micros(), client, args));
+    }
+
+    /// Whether any connection is monitoring, i.e. whether published commands go anywhere.
+    pub(crate) fn is_watched(&self) -> bool {
+        self.0.receiver_count() > 0
+    }
+
+    /// Forwards every command published from now on to the connection behind `sender`, until it is
+    /// closed. Lines that the connection is too slow to take are dropped rather than waited on, so
+    /// a stalled monitor never holds back the commands it watches, and it is closed once the lines
+    /// it has yet to take are over its output limit.
+    pub(crate) fn subscribe(&self, sender: OutputSender) {
+        let mut lines = self.0.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match lines.recv().await {
+                    | Ok(line) => {
+                        if let Err(TrySendError::Closed(_)) =
+                            sender.try_send(QueryIO::SimpleString(line))
+                        {
+                            return;
+                        }
+                    },
+                    | Err(RecvError::Lagged(_)) => continue,
+                    | Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+}
+
+fn format_line(micros: i64, client: ClientAddr, args: &[QueryIO]) -> Bytes {
+    let mut line = format!("{}.{:06} [{client}]", micros / 1_000_000, micros % 1_000_000);
+    let mut is_hello = false;
+    // * Arguments left to redact: every one after AUTH, the username and password after HELLO's AUTH
+    let mut secrets = 0;
+    for (i, arg) in args.iter().enumerate() {
+        let arg = match arg {
+            | _ if secrets > 0 => {
+                secrets -= 1;
+                "(redacted)".to_string()
+            },
+            | QueryIO::BulkString(arg) | QueryIO::SimpleString(arg) => {
+                String::from_utf8_lossy(arg).into_owned()
+            },
+            | other => format!("{other:?}"),
+        };
+        if i == 0 {
+            is_hello = arg.eq_ignore_ascii_case("hello");
+            if arg.eq_ignore_ascii_case("auth") {
+                secrets = usize::MAX;
+            }
+        } else if is_hello && arg.eq_ignore_ascii_case("auth") {
+            secrets = 2;
+        }
+        line.push_str(&format!(" {arg:?}"));
+    }
+    line.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::clients::info::ServerStats;
+    use crate::presentation::clients::output::OutputBuffer;
+
+    #[test]
+    fn test_format_line() {
+        let client = ClientAddr::Tcp("127.0.0.1:60866".parse().unwrap());
+        let args = [QueryIO::BulkString("SET".into()), QueryIO::BulkString("say \"hi\"".into())];
+
+        assert_eq!(
+            format_line(1_339_518_083_107_412, client, &args),
+            Bytes::from(r#"1339518083.107412 [127.0.0.1:60866] "SET" "say \"hi\"""#)
+        );
+
+        let args = [QueryIO::BulkString("auth".into()), QueryIO::BulkString("secret".into())];
+        assert_eq!(
+            format_line(1_339_518_083_107_412, client, &args),
+            Bytes::from(r#"1339518083.107412 [127.0.0.1:60866] "auth" "(redacted)""#)
+        );
+
+        let args = ["HELLO", "3", "AUTH", "default", "secret", "SETNAME", "app"]
+            .map(|arg| QueryIO::BulkString(arg.into()));
+        assert_eq!(
+            format_line(1_339_518_083_107_412, client, &args),
+            Bytes::from(
+                r#"1339518083.107412 [127.0.0.1:60866] "HELLO" "3" "AUTH" "(redacted)" "(redacted)" "SETNAME" "app""#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slow_monitors_drop_lines_instead_of_blocking() {
+        let monitor = Monitor::default();
+        let client = ClientAddr::Tcp("127.0.0.1:60866".parse().unwrap());
+        monitor.publish(client, &[QueryIO::BulkString("PING".into())]);
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        monitor.subscribe(OutputSender::new(tx, OutputBuffer::default(), ServerStats::default()));
+        for _ in 0..3 {
+            monitor.publish(client, &[QueryIO::BulkString("GET".into())]);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let line = rx.recv().await.unwrap();
+        assert!(line.ends_with(b" \"GET\"\r\n"));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use super::info::ServerStats;
+use crate::domains::QueryIO;
+use bytes::Bytes;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Output a connection may have pending before it is closed, formatted as
+/// `<hard bytes> <soft bytes> <soft seconds>`, the way Redis' `client-output-buffer-limit` is.
+/// Connections are closed as soon as they are over the hard limit, or once they have stayed over
+/// the soft limit for the given seconds. A limit of 0 is no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputBufferLimit {
+    pub hard: u64,
+    pub soft: u64,
+    pub soft_seconds: u64,
+}
+
+impl FromStr for OutputBufferLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s.split_whitespace().map(str::parse).collect::<Result<Vec<u64>, _>>()?;
+        let [hard, soft, soft_seconds] = values[..] else {
+            anyhow::bail!("expected <hard bytes> <soft bytes> <soft seconds>, got {s:?}");
+        };
+        Ok(Self { hard, soft, soft_seconds })
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingOutput {
+    limit: OutputBufferLimit,
+    bytes: u64,
+    over_soft_since: Option<Instant>,
+}
+
+/// Bytes handed to a connection's writer that it has yet to write out, checked against the limit
+/// of the connection. Closing it also closes the connection, as `CLIENT KILL` does.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputBuffer {
+    pending: Arc<Mutex<PendingOutput>>,
+    close: Arc<watch::Sender<bool>>,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self { pending: Arc::default(), close: Arc::new(watch::channel(false).0) }
+    }
+}
+
+impl OutputBuffer {
+    pub(crate) fn set_limit(&self, limit: OutputBufferLimit) {
+        self.pending.lock().unwrap().limit = limit;
+    }
+
+    pub(crate) fn pending(&self) -> u64 {
+        self.pending.lock().unwrap().bytes
+    }
+
+    pub(crate) fn close(&self) {
+        self.close.send_replace(true);
+    }
+
+    /// Resolves once the connection is closed.
+    pub(crate) fn closed(&self) -> watch::Receiver<bool> {
+        self.close.subscribe()
+    }
+
+    /// Counts bytes handed to the writer. Returns by when the connection has to catch up with its
+    /// output to be kept, which is now once it is over the hard limit.
+    fn queue(&self, bytes: u64, now: Instant) -> Option<Instant> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.bytes += bytes;
+        let limit = pending.limit;
+        if limit.hard > 0 && pending.bytes > limit.hard {
+            return Some(now);
+        }
+        if limit.soft > 0 && pending.bytes > limit.soft {
+            let since = *pending.over_soft_since.get_or_insert(now);
+            return Some(since + Duration::from_secs(limit.soft_seconds));
+        }
+        pending.over_soft_since = None;
+        None
+    }
+
+    /// Counts bytes the writer has written out, or that were dropped before reaching it.
+    pub(crate) fn written(&self, bytes: u64) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.bytes = pending.bytes.saturating_sub(bytes);
+        if pending.limit.soft == 0 || pending.bytes <= pending.limit.soft {
+            pending.over_soft_since = None;
+        }
+    }
+}
+
+/// Sending half of a connection's writer, which serializes what it is given so that the output
+/// pending on the connection is counted in bytes.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputSender {
+    tx: Sender<Bytes>,
+    output: OutputBuffer,
+    stats: ServerStats,
+}
+
+impl OutputSender {
+    pub(crate) fn new(tx: Sender<Bytes>, output: OutputBuffer, stats: ServerStats) -> Self {
+        Self { tx, output, stats }
+    }
+
+    /// Waits for room in the writer's queue, but no longer than the connection may stay over its
+    /// soft limit. Returns the size of what was sent.
+    pub(crate) async fn send(&self, query_io: QueryIO) -> Result<usize, SendError<Bytes>> {
+        let bytes = query_io.serialize();
+        let len = bytes.len();
+        let now = Instant::now();
+        let sent = match self.output.queue(len as u64, now) {
+            | None => self.tx.send(bytes).await,
+            | Some(deadline) if deadline <= now => return Err(self.over_limit(bytes)),
+            | Some(deadline) => {
+                match tokio::time::timeout_at(deadline.into(), self.tx.send(bytes.clone())).await {
+                    | Ok(sent) => sent,
+                    | Err(_) => return Err(self.over_limit(bytes)),
+                }
+            },
+        };
+        sent.map(|()| len)
+    }
+
+    /// Drops what the writer has no room for rather than waiting, for output that may be lost
+    /// such as `MONITOR` lines.
+    pub(crate) fn try_send(&self, query_io: QueryIO) -> Result<(), TrySendError<Bytes>> {
+        let bytes = query_io.serialize();
+        let len = bytes.len() as u64;
+        let now = Instant::now();
+        if self.output.queue(len, now).is_some_and(|deadline| deadline <= now) {
+            return Err(TrySendError::Closed(self.over_limit(bytes).0));
+        }
+        self.tx.try_send(bytes).inspect_err(|_| self.output.written(len))
+    }
+
+    fn over_limit(&self, bytes: Bytes) -> SendError<Bytes> {
+        let pending = self.output.pending();
+        warn!("Closing client connection with {pending} bytes of output pending");
+        self.stats.record_output_limit_disconnection();
+        self.output.close();
+        SendError(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(
+            "33554432 8388608 60".parse::<OutputBufferLimit>().unwrap(),
+            OutputBufferLimit { hard: 33554432, soft: 8388608, soft_seconds: 60 }
+        );
+        assert!("1 2".parse::<OutputBufferLimit>().is_err());
+        assert!("1 2 x".parse::<OutputBufferLimit>().is_err());
+    }
+
+    #[test]
+    fn test_queue_past_the_hard_limit_closes_right_away() {
+        let output = OutputBuffer::default();
+        output.set_limit(OutputBufferLimit { hard: 100, soft: 0, soft_seconds: 0 });
+        let now = Instant::now();
+
+        assert_eq!(output.queue(60, now), None);
+        assert_eq!(output.queue(60, now), Some(now));
+        output.written(60);
+        assert_eq!(output.pending(), 60);
+    }
+
+    #[test]
+    fn test_queue_over_the_soft_limit_is_given_soft_seconds_to_catch_up() {
+        let output = OutputBuffer::default();
+        output.set_limit(OutputBufferLimit { hard: 0, soft: 100, soft_seconds: 5 });
+        let start = Instant::now();
+        let deadline = start + Duration::from_secs(5);
+
+        assert_eq!(output.queue(150, start), Some(deadline));
+        // * The deadline runs from when the connection first went over
+        assert_eq!(output.queue(10, start + Duration::from_secs(1)), Some(deadline));
+
+        output.written(160);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(output.queue(150, later), Some(later + Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn test_send_over_the_limit_closes_the_connection() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let output = OutputBuffer::default();
+        output.set_limit(OutputBufferLimit { hard: 20, soft: 0, soft_seconds: 0 });
+        let sender = OutputSender::new(tx, output.clone(), ServerStats::default());
+
+        let sent = sender.send(QueryIO::SimpleString("OK".into())).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().len(), sent);
+        output.written(sent as u64);
+
+        assert!(sender.send(QueryIO::BulkString("x".repeat(32).into())).await.is_err());
+        assert!(*output.closed().borrow());
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Commands `CLIENT PAUSE` holds back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseMode {
+    // * Writes only, so that the keyspace stops changing while reads are still served
+    Write,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pause {
+    mode: PauseMode,
+    until: Instant,
+}
+
+/// Holds client commands back until a deadline or `CLIENT UNPAUSE`, so that a leadership transfer
+/// or a reshard can run without writes failing under it. Held commands are served, in order, once
+/// the pause ends.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientPause(Arc<watch::Sender<Option<Pause>>>);
+
+impl Default for ClientPause {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(None).0))
+    }
+}
+
+impl ClientPause {
+    /// Pausing while already paused keeps the later deadline and the stricter mode.
+    pub(crate) fn pause(&self, mode: PauseMode, timeout: Duration) {
+        let until = Instant::now() + timeout;
+        self.0.send_modify(|pause| {
+            *pause = match pause.filter(|pause| pause.until > Instant::now()) {
+                | Some(current) => {
+                    Some(Pause { mode: current.mode.max(mode), until: current.until.max(until) })
+                },
+                | None => Some(Pause { mode, until }),
+            };
+        });
+    }
+
+    pub(crate) fn unpause(&self) {
+        self.0.send_replace(None);
+    }
+
+    /// Whether a command is held back for now.
+    pub(crate) fn holds(&self, is_write: bool) -> bool {
+        Self::deadline(*self.0.borrow(), is_write).is_some()
+    }
+
+    /// Resolves once a command may be served.
+    pub(crate) async fn wait(&self, is_write: bool) {
+        let mut changes = self.0.subscribe();
+        loop {
+            let Some(until) = Self::deadline(*changes.borrow_and_update(), is_write) else {
+                return;
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(until) => return,
+                _ = changes.changed() => continue,
+            }
+        }
+    }
+
+    fn deadline(pause: Option<Pause>, is_write: bool) -> Option<Instant> {
+        let pause = pause?;
+        let held = pause.mode == PauseMode::All || is_write;
+        (held && pause.until > Instant::now()).then_some(pause.until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_pause_holds_only_writes_until_it_expires() {
+        let pause = ClientPause::default();
+        pause.pause(PauseMode::Write, Duration::from_millis(100));
+
+        assert!(pause.holds(true));
+        assert!(!pause.holds(false));
+
+        let started = Instant::now();
+        pause.wait(true).await;
+        assert!(started.elapsed() >= Duration::from_millis(90));
+        assert!(!pause.holds(true));
+    }
+
+    #[tokio::test]
+    async fn test_unpause_releases_held_commands() {
+        let pause = ClientPause::default();
+        pause.pause(PauseMode::All, Duration::from_secs(60));
+        assert!(pause.holds(false));
+
+        let waiting = tokio::spawn({
+            let pause = pause.clone();
+            async move { pause.wait(false).await }
+        });
+        pause.unpause();
+
+        tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_pausing_again_keeps_the_stricter_mode_and_later_deadline() {
+        let pause = ClientPause::default();
+        pause.pause(PauseMode::All, Duration::from_secs(60));
+        pause.pause(PauseMode::Write, Duration::from_secs(1));
+
+        let current = (*pause.0.borrow()).unwrap();
+        assert_eq!(current.mode, PauseMode::All);
+        assert!(current.until > Instant::now() + Duration::from_secs(30));
+    }
+}
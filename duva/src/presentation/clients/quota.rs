@@ -0,0 +1,146 @@
+use super::authenticate::DEFAULT_USER;
+use super::socket::ClientAddr;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// * Quota a user may spend at once after being idle
+const BURST: Duration = Duration::from_secs(1);
+
+/// Commands per second and reply bytes per second each user may use across all of its
+/// connections. A user over quota is throttled by delaying reads from its connections until it is
+/// back within it, rather than by disconnecting them.
+///
+/// Connections of the `default` user, which every connection is until it authenticates as another,
+/// share quotas per client address instead, so that one client cannot throttle all the others.
+#[derive(Debug, Clone)]
+pub(crate) struct UserQuotas {
+    // * 0 for no limit
+    command_rate: u64,
+    output_rate: u64,
+    budgets: Arc<Mutex<HashMap<String, Budget>>>,
+}
+
+/// Instants from which each user's spending is paid for.
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    commands_paid_until: Instant,
+    output_paid_until: Instant,
+}
+
+impl UserQuotas {
+    pub(crate) fn new(command_rate: u64, output_rate: u64) -> Self {
+        Self { command_rate, output_rate, budgets: Default::default() }
+    }
+
+    pub(crate) fn charge_commands(&self, user: &str, commands: u64, now: Instant) {
+        if self.command_rate == 0 {
+            return;
+        }
+        let mut budgets = self.budgets.lock().unwrap();
+        let budget = budgets.entry(user.to_string()).or_insert_with(|| Budget::new(now));
+        spend(&mut budget.commands_paid_until, commands, self.command_rate, now);
+    }
+
+    pub(crate) fn charge_output(&self, user: &str, bytes: u64, now: Instant) {
+        if self.output_rate == 0 {
+            return;
+        }
+        let mut budgets = self.budgets.lock().unwrap();
+        let budget = budgets.entry(user.to_string()).or_insert_with(|| Budget::new(now));
+        spend(&mut budget.output_paid_until, bytes, self.output_rate, now);
+    }
+
+    /// How long reads from the user's connections should be held back to bring it within quota.
+    pub(crate) fn delay(&self, user: &str, now: Instant) -> Duration {
+        let budgets = self.budgets.lock().unwrap();
+        let Some(budget) = budgets.get(user) else {
+            return Duration::ZERO;
+        };
+        budget.commands_paid_until.max(budget.output_paid_until).saturating_duration_since(now)
+    }
+}
+
+/// Key the quotas of a connection authenticated as `user` from `addr` are kept under.
+pub(crate) fn quota_key(user: &str, addr: &ClientAddr) -> String {
+    match addr.ip() {
+        | _ if user != DEFAULT_USER => format!("user:{user}"),
+        | Some(ip) => format!("addr:{ip}"),
+        | None => "addr:unix".to_string(),
+    }
+}
+
+impl Budget {
+    fn new(now: Instant) -> Self {
+        let idle = idle_since(now);
+        Self { commands_paid_until: idle, output_paid_until: idle }
+    }
+}
+
+// * Quota left unused for longer than BURST is not carried over
+fn idle_since(now: Instant) -> Instant {
+    now.checked_sub(BURST).unwrap_or(now)
+}
+
+fn spend(paid_until: &mut Instant, amount: u64, rate: u64, now: Instant) {
+    *paid_until =
+        (*paid_until).max(idle_since(now)) + Duration::from_secs_f64(amount as f64 / rate as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_once_a_user_spends_more_than_a_burst() {
+        let quotas = UserQuotas::new(10, 0);
+        let now = Instant::now() + BURST;
+
+        quotas.charge_commands("default", 10, now);
+        assert_eq!(quotas.delay("default", now), Duration::ZERO);
+
+        quotas.charge_commands("default", 5, now);
+        assert_eq!(quotas.delay("default", now), Duration::from_millis(500));
+        assert_eq!(quotas.delay("default", now + Duration::from_millis(500)), Duration::ZERO);
+        assert_eq!(quotas.delay("other", now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_delay_is_the_larger_of_command_and_output_quota() {
+        let quotas = UserQuotas::new(100, 1000);
+        let now = Instant::now() + BURST;
+
+        quotas.charge_commands("default", 1, now);
+        quotas.charge_output("default", 3000, now);
+
+        assert_eq!(quotas.delay("default", now), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_default_user_is_keyed_by_client_address() {
+        let addr = |addr: &str| ClientAddr::Tcp(addr.parse().unwrap());
+
+        assert_eq!(quota_key(DEFAULT_USER, &addr("10.0.0.1:5000")), "addr:10.0.0.1");
+        assert_eq!(
+            quota_key(DEFAULT_USER, &addr("10.0.0.1:5001")),
+            quota_key(DEFAULT_USER, &addr("10.0.0.1:5000"))
+        );
+        assert_ne!(
+            quota_key(DEFAULT_USER, &addr("10.0.0.2:5000")),
+            quota_key(DEFAULT_USER, &addr("10.0.0.1:5000"))
+        );
+        assert_eq!(quota_key(DEFAULT_USER, &ClientAddr::Unix), "addr:unix");
+        assert_eq!(quota_key("alice", &addr("10.0.0.1:5000")), "user:alice");
+    }
+
+    #[test]
+    fn test_unlimited_quotas_never_delay() {
+        let quotas = UserQuotas::new(0, 0);
+        let now = Instant::now();
+
+        quotas.charge_commands("default", 1_000_000, now);
+        quotas.charge_output("default", 1_000_000, now);
+
+        assert_eq!(quotas.delay("default", now), Duration::ZERO);
+    }
+}
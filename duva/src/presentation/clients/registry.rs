@@ -0,0 +1,228 @@
+use super::output::OutputBuffer;
+use super::request::ClientFilter;
+use super::socket::ClientAddr;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::watch;
+
+/// Client connections of this node, which `CLIENT LIST` reports and `CLIENT KILL` closes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientRegistry {
+    // * Ids are handed out in connection order and never reused
+    last_id: Arc<AtomicU64>,
+    clients: Arc<Mutex<BTreeMap<u64, ClientEntry>>>,
+}
+
+#[derive(Debug)]
+struct ClientEntry {
+    addr: ClientAddr,
+    name: Option<String>,
+    user: String,
+    connected_at: Instant,
+    last_interaction: Instant,
+    // * Lowercase name of the latest command, empty before the first one
+    last_command: String,
+    monitor: bool,
+    // * Receives topology pushes through CLUSTER SUBSCRIBE
+    subscriber: bool,
+    read_only: bool,
+    // * Closing it closes the connection
+    output: OutputBuffer,
+}
+
+impl ClientRegistry {
+    pub(crate) fn register(&self, addr: ClientAddr, user: &str) -> RegisteredClient {
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = Instant::now();
+        let output = OutputBuffer::default();
+        self.clients.lock().unwrap().insert(
+            id,
+            ClientEntry {
+                addr,
+                name: None,
+                user: user.to_string(),
+                connected_at: now,
+                last_interaction: now,
+                last_command: String::new(),
+                monitor: false,
+                subscriber: false,
+                read_only: false,
+                output: output.clone(),
+            },
+        );
+        RegisteredClient { id, registry: self.clone(), output }
+    }
+
+    /// One line per connection, formatted the way Redis does, e.g.
+    /// `id=3 addr=127.0.0.1:60866 name=worker age=12 idle=0 flags=N omem=0 cmd=get user=default`.
+    pub(crate) fn list(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.clients
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, client)| {
+                format!(
+                    "id={id} addr={} name={} age={} idle={} flags={} omem={} cmd={} user={}",
+                    client.addr,
+                    client.name.as_deref().unwrap_or_default(),
+                    now.duration_since(client.connected_at).as_secs(),
+                    now.duration_since(client.last_interaction).as_secs(),
+                    client.flags(),
+                    client.output.pending(),
+                    if client.last_command.is_empty() { "NULL" } else { &client.last_command },
+                    client.user,
+                )
+            })
+            .collect()
+    }
+
+    /// Closes the connections matching `filter`, returning how many there were.
+    pub(crate) fn kill(&self, filter: &ClientFilter) -> usize {
+        let clients = self.clients.lock().unwrap();
+        let mut killed = 0;
+        for (id, client) in clients.iter() {
+            if filter.id.is_none_or(|wanted| wanted == *id)
+                && filter.addr.as_ref().is_none_or(|wanted| *wanted == client.addr.to_string())
+            {
+                client.output.close();
+                killed += 1;
+            }
+        }
+        killed
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut ClientEntry)) {
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+            f(client);
+        }
+    }
+}
+
+impl ClientEntry {
+    fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.monitor {
+            flags.push('O');
+        }
+        if self.subscriber {
+            flags.push('P');
+        }
+        if self.read_only {
+            flags.push('r');
+        }
+        if flags.is_empty() {
+            flags.push('N');
+        }
+        flags
+    }
+}
+
+/// A connection's place in the registry, which it leaves once this is dropped.
+#[derive(Debug)]
+pub(crate) struct RegisteredClient {
+    id: u64,
+    registry: ClientRegistry,
+    output: OutputBuffer,
+}
+
+impl RegisteredClient {
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Resolves once `CLIENT KILL`, or going over its output limit, closes the connection.
+    pub(crate) fn killed(&self) -> watch::Receiver<bool> {
+        self.output.closed()
+    }
+
+    pub(crate) fn output(&self) -> OutputBuffer {
+        self.output.clone()
+    }
+
+    pub(crate) fn record_command(&self, command: &str) {
+        self.registry.update(self.id, |client| {
+            client.last_interaction = Instant::now();
+            client.last_command = command.to_lowercase();
+        });
+    }
+
+    pub(crate) fn name(&self) -> Option<String> {
+        self.registry.clients.lock().unwrap().get(&self.id).and_then(|client| client.name.clone())
+    }
+
+    pub(crate) fn set_name(&self, name: Option<String>) {
+        self.registry.update(self.id, |client| client.name = name);
+    }
+
+    pub(crate) fn set_user(&self, user: &str) {
+        self.registry.update(self.id, |client| client.user = user.to_string());
+    }
+
+    pub(crate) fn set_monitor(&self) {
+        self.registry.update(self.id, |client| client.monitor = true);
+    }
+
+    pub(crate) fn set_subscriber(&self) {
+        self.registry.update(self.id, |client| client.subscriber = true);
+    }
+
+    pub(crate) fn set_read_only(&self, read_only: bool) {
+        self.registry.update(self.id, |client| client.read_only = read_only);
+    }
+}
+
+impl Drop for RegisteredClient {
+    fn drop(&mut self) {
+        self.registry.clients.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(addr: &str) -> ClientAddr {
+        ClientAddr::Tcp(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_list_reports_each_connection_until_it_is_dropped() {
+        let registry = ClientRegistry::default();
+        let first = registry.register(tcp("127.0.0.1:5000"), "default");
+        let second = registry.register(tcp("127.0.0.1:5001"), "default");
+        first.set_name(Some("worker".into()));
+        first.record_command("GET");
+        second.set_monitor();
+
+        assert_eq!(
+            registry.list(),
+            vec![
+                "id=1 addr=127.0.0.1:5000 name=worker age=0 idle=0 flags=N omem=0 cmd=get user=default",
+                "id=2 addr=127.0.0.1:5001 name= age=0 idle=0 flags=O omem=0 cmd=NULL user=default",
+            ]
+        );
+        assert_eq!(first.name().as_deref(), Some("worker"));
+
+        drop(first);
+        assert_eq!(registry.list().len(), 1);
+    }
+
+    #[test]
+    fn test_kill_signals_matching_connections() {
+        let registry = ClientRegistry::default();
+        let first = registry.register(tcp("127.0.0.1:5000"), "default");
+        let second = registry.register(tcp("127.0.0.1:5001"), "default");
+
+        let by_addr = ClientFilter { addr: Some("127.0.0.1:5001".into()), ..Default::default() };
+        assert_eq!(registry.kill(&by_addr), 1);
+        assert!(!*first.killed().borrow());
+        assert!(*second.killed().borrow());
+
+        assert_eq!(registry.kill(&ClientFilter { id: Some(9), addr: None }), 0);
+        assert_eq!(registry.kill(&ClientFilter { id: Some(first.id()), addr: None }), 1);
+        assert!(*first.killed().borrow());
+    }
+}
@@ -2,11 +2,15 @@ use std::str::FromStr;
 
 use crate::domains::{
     QueryIO,
-    cluster_actors::{LazyOption, SessionRequest},
+    caches::cache_objects::CacheEntry,
+    cluster_actors::{KeySelector, LazyOption, SessionRequest, replication::ReplicationId},
     operation_logs::WriteRequest,
     peers::identifier::{PeerIdentifier, TPeerAddress},
+    saves::snapshot::dump_payload::DumpPayload,
 };
+use crate::presentation::clients::pause::PauseMode;
 use anyhow::Context;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 
 #[derive(Clone, Debug)]
@@ -14,29 +18,107 @@ pub enum ClientAction {
     Ping,
     Echo(String),
     Config { key: String, value: String },
-    Get { key: String },
-    MGet { keys: Vec<String> },
-    IndexGet { key: String, index: u64 },
-    Set { key: String, value: String },
-    Append { key: String, value: String },
-    SetWithExpiry { key: String, value: String, expiry: DateTime<Utc> },
-    Keys { pattern: Option<String> },
-    Delete { keys: Vec<String> },
+    ConfigSet { parameter: String, value: String },
+    Get { key: Bytes, consistency: ReadConsistency },
+    MGet { keys: Vec<Bytes>, consistency: ReadConsistency },
+    IndexGet { key: Bytes, index: u64 },
+    // * Keys and values are binary-safe, unlike the other arguments
+    Set { key: Bytes, value: Bytes },
+    Append { key: Bytes, value: Bytes },
+    SetWithExpiry { key: Bytes, value: Bytes, expiry: DateTime<Utc> },
+    Keys { pattern: Option<Bytes> },
+    Delete { keys: Vec<Bytes> },
+    Unlink { keys: Vec<Bytes> },
     Save,
-    Info,
+    BgSave,
+    // * Saves a snapshot first when save is set, or when it is None and a save policy is configured
+    Shutdown { save: Option<bool> },
+    // * Loads the keys of a Redis dump file
+    Import { path: String },
+    // * Writes the keys of this shard to a Redis dump file
+    Export { path: String },
+    Info { section: String },
     ClusterInfo,
     ClusterNodes,
-    ClusterForget(PeerIdentifier),
+    ClusterShards,
+    // * Pushes the shards to the connection now and whenever the topology changes
+    ClusterSubscribe,
+    // * Set for a permanent ban rather than one of the configured duration
+    ClusterForget(PeerIdentifier, bool),
+    ClusterBanList,
+    ClusterBanListClear(Option<PeerIdentifier>),
     ClusterReshard,
+    ClusterReshardStatus,
+    // * Term, log positions, per-peer progress and in-flight entries of the consensus module
+    ClusterConsensus,
+    // * Most recent topology transitions seen by this node, all of them when no count is given
+    ClusterHistory(Option<usize>),
+    ClusterMigrate { selector: KeySelector, target: ReplicationId },
+    ClusterFailover(Option<PeerIdentifier>),
+    ClusterLeave,
+    // * Checks the on-disk log without modifying it
+    DebugWalVerify,
+    // * Reports the largest keys of each type held by this node
+    DebugBigKeys,
+    // * Reports memory held by this node and how much compressing values saves
+    MemoryStats,
+    // * Lists the keys the eviction policy evicts first
+    MemoryEvictionPool,
+    // * Latency percentiles of each phase of the given commands, or of every command
+    LatencyHistogram { commands: Vec<String> },
+    LatencyReset,
+    // * Seconds since the key was last read or written
+    ObjectIdleTime { key: Bytes },
+    // * Reads and writes of the key since it was set
+    ObjectFreq { key: Bytes },
     ReplicaOf(PeerIdentifier),
-    Exists { keys: Vec<String> },
+    Exists { keys: Vec<Bytes> },
     Role,
-    Incr { key: String },
-    Decr { key: String },
-    Ttl { key: String },
+    Incr { key: Bytes },
+    Decr { key: Bytes },
+    Ttl { key: Bytes },
     ClusterMeet(PeerIdentifier, LazyOption),
-    IncrBy { key: String, increment: i64 },
-    DecrBy { key: String, decrement: i64 },
+    IncrBy { key: Bytes, increment: i64 },
+    DecrBy { key: Bytes, decrement: i64 },
+    Cas { key: Bytes, expected: Bytes, value: Bytes },
+    // * Grants and lease expiry are judged against `proposed_at`, so every replica reaches the
+    // * same outcome whenever it applies the entry
+    Lock { key: Bytes, expiry: DateTime<Utc>, proposed_at: DateTime<Utc> },
+    Unlock { key: Bytes, token: u64 },
+    Dump { key: Bytes },
+    Restore { entry: CacheEntry, replace: bool },
+    LeaseGrant { ttl: u64 },
+    LeaseKeepAlive { id: u64 },
+    LeaseAttach { id: u64, keys: Vec<Bytes> },
+    LeaseRevoke { id: u64 },
+    // * `auth` carries the username and password of the AUTH clause, checked before switching
+    Hello { protover: Option<u8>, auth: Option<(String, String)> },
+    // * Only the default user exists, so a username other than `default` never matches
+    Auth { username: Option<String>, password: String },
+    ReadOnly,
+    ReadWrite,
+    // * Streams the commands of every client back on this connection
+    Monitor,
+    // * Id of this connection in the client registry
+    ClientId,
+    // * None clears the name
+    ClientSetName(Option<String>),
+    ClientGetName,
+    ClientList,
+    // * legacy is the `CLIENT KILL addr` form, which replies OK rather than a count
+    ClientKill { filter: ClientFilter, legacy: bool },
+    // * Holds client commands back for timeout milliseconds
+    ClientPause { timeout: u64, mode: PauseMode },
+    ClientUnpause,
+    // * index is the connection's last write, filled in by the client stream
+    Wait { numreplicas: usize, timeout: u64, index: Option<u64> },
+    Batch { actions: Vec<ClientAction> },
+    // * Metadata of the given commands, or of every command when none is given
+    CommandInfo { names: Vec<String> },
+    CommandDocs { names: Vec<String> },
+    CommandCount,
+    // * Keys of a full command line, for clients that route by key
+    CommandGetKeys { args: Vec<Bytes> },
 }
 
 impl ClientAction {
@@ -52,6 +134,7 @@ impl ClientAction {
             },
             | ClientAction::Append { key, value } => WriteRequest::Append { key, value },
             | ClientAction::Delete { keys } => WriteRequest::Delete { keys },
+            | ClientAction::Unlink { keys } => WriteRequest::Unlink { keys },
             | ClientAction::Incr { key } => WriteRequest::Incr { key, delta: 1 },
             | ClientAction::Decr { key } => WriteRequest::Decr { key, delta: 1 },
             | ClientAction::IncrBy { key, increment } => {
@@ -60,6 +143,27 @@ impl ClientAction {
             | ClientAction::DecrBy { key, decrement } => {
                 WriteRequest::Decr { key, delta: decrement }
             },
+            | ClientAction::Cas { key, expected, value } => {
+                WriteRequest::Cas { key, expected, value }
+            },
+            | ClientAction::Lock { key, expiry, proposed_at } => WriteRequest::Lock {
+                key,
+                expires_at: expiry.timestamp_millis() as u64,
+                proposed_at: proposed_at.timestamp_millis() as u64,
+            },
+            | ClientAction::Unlock { key, token } => WriteRequest::Unlock { key, token },
+            | ClientAction::Restore { entry, replace } => WriteRequest::Restore { entry, replace },
+            | ClientAction::LeaseGrant { .. } => {
+                unreachable!(
+                    "lease grants are stamped with the granting partition by the controller"
+                )
+            },
+            | ClientAction::LeaseKeepAlive { id } => WriteRequest::LeaseKeepAlive { id },
+            | ClientAction::LeaseAttach { id, keys } => WriteRequest::LeaseAttach { id, keys },
+            | ClientAction::LeaseRevoke { id } => WriteRequest::LeaseRevoke { id },
+            | ClientAction::Batch { actions } => WriteRequest::Batch {
+                requests: actions.into_iter().map(ClientAction::to_write_request).collect(),
+            },
             | _ => {
                 debug_assert!(false, "to_write_request called on non-write action: {self:?}");
                 unreachable!(
@@ -76,17 +180,156 @@ impl ClientAction {
                 | ClientAction::SetWithExpiry { .. }
                 | ClientAction::Append { .. }
                 | ClientAction::Delete { .. }
+                | ClientAction::Unlink { .. }
                 | ClientAction::Incr { .. }
                 | ClientAction::Decr { .. }
                 | ClientAction::IncrBy { .. }
                 | ClientAction::DecrBy { .. }
+                | ClientAction::Cas { .. }
+                | ClientAction::Lock { .. }
+                | ClientAction::Unlock { .. }
+                | ClientAction::Restore { .. }
+                | ClientAction::LeaseGrant { .. }
+                | ClientAction::LeaseKeepAlive { .. }
+                | ClientAction::LeaseAttach { .. }
+                | ClientAction::LeaseRevoke { .. }
+                | ClientAction::Batch { .. }
         )
     }
+
+    /// Reads of the keyspace, which a `READONLY` connection to a replica serves within the staleness bound.
+    pub fn is_keyspace_read(&self) -> bool {
+        matches!(
+            self,
+            ClientAction::Get { .. }
+                | ClientAction::MGet { .. }
+                | ClientAction::IndexGet { .. }
+                | ClientAction::Keys { .. }
+                | ClientAction::Exists { .. }
+                | ClientAction::Ttl { .. }
+                | ClientAction::Dump { .. }
+        )
+    }
+
+    /// Keys the command reads or writes.
+    pub fn all_keys(&self) -> Vec<&[u8]> {
+        match self {
+            | ClientAction::Get { key, .. }
+            | ClientAction::IndexGet { key, .. }
+            | ClientAction::Set { key, .. }
+            | ClientAction::Append { key, .. }
+            | ClientAction::SetWithExpiry { key, .. }
+            | ClientAction::ObjectIdleTime { key }
+            | ClientAction::ObjectFreq { key }
+            | ClientAction::Incr { key }
+            | ClientAction::Decr { key }
+            | ClientAction::Ttl { key }
+            | ClientAction::IncrBy { key, .. }
+            | ClientAction::DecrBy { key, .. }
+            | ClientAction::Cas { key, .. }
+            | ClientAction::Lock { key, .. }
+            | ClientAction::Unlock { key, .. }
+            | ClientAction::Dump { key } => vec![key],
+            | ClientAction::Restore { entry, .. } => vec![entry.key()],
+            | ClientAction::MGet { keys, .. }
+            | ClientAction::Delete { keys }
+            | ClientAction::Unlink { keys }
+            | ClientAction::Exists { keys }
+            | ClientAction::LeaseAttach { keys, .. } => keys.iter().map(|k| &k[..]).collect(),
+            | ClientAction::Batch { actions } => {
+                actions.iter().flat_map(ClientAction::all_keys).collect()
+            },
+            | _ => vec![],
+        }
+    }
+
+    /// Administrative operations recorded in the audit log, as they would be typed in.
+    pub(crate) fn audit_operation(&self) -> Option<String> {
+        let operation = match self {
+            | ClientAction::ClusterMeet(peer, option) => {
+                format!("CLUSTER MEET {peer} {}", format!("{option:?}").to_uppercase())
+            },
+            | ClientAction::ClusterForget(peer, false) => format!("CLUSTER FORGET {peer}"),
+            | ClientAction::ClusterForget(peer, true) => format!("CLUSTER FORGET {peer} PERMANENT"),
+            | ClientAction::ClusterBanListClear(Some(peer)) => {
+                format!("CLUSTER BANLIST CLEAR {peer}")
+            },
+            | ClientAction::ClusterBanListClear(None) => "CLUSTER BANLIST CLEAR".to_string(),
+            | ClientAction::ClusterReshard => "CLUSTER RESHARD".to_string(),
+            | ClientAction::ClusterMigrate { selector, target } => {
+                format!("CLUSTER MIGRATE {selector} TO {target}")
+            },
+            | ClientAction::ClusterFailover(target) => match target {
+                | Some(target) => format!("CLUSTER FAILOVER {target}"),
+                | None => "CLUSTER FAILOVER".to_string(),
+            },
+            | ClientAction::ClusterLeave => "CLUSTER LEAVE".to_string(),
+            | ClientAction::ReplicaOf(peer) => format!("REPLICAOF {peer}"),
+            | ClientAction::ConfigSet { parameter, value } => {
+                format!("CONFIG SET {parameter} {value}")
+            },
+            | ClientAction::Import { path } => format!("IMPORT {path}"),
+            | ClientAction::Export { path } => format!("EXPORT {path}"),
+            | ClientAction::ClientKill { filter, .. } => format!("CLIENT KILL {filter}"),
+            | ClientAction::ClientPause { timeout, mode } => {
+                format!("CLIENT PAUSE {timeout} {}", format!("{mode:?}").to_uppercase())
+            },
+            | ClientAction::ClientUnpause => "CLIENT UNPAUSE".to_string(),
+            | ClientAction::Shutdown { save } => match save {
+                | Some(true) => "SHUTDOWN SAVE".to_string(),
+                | Some(false) => "SHUTDOWN NOSAVE".to_string(),
+                | None => "SHUTDOWN".to_string(),
+            },
+            | _ => return None,
+        };
+        Some(operation)
+    }
+}
+
+/// Connections `CLIENT KILL` closes: those matching every filter given.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientFilter {
+    pub id: Option<u64>,
+    pub addr: Option<String>,
+}
+
+impl std::fmt::Display for ClientFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let id = self.id.map(|id| format!("ID {id}"));
+        let addr = self.addr.as_ref().map(|addr| format!("ADDR {addr}"));
+        write!(f, "{}", id.into_iter().chain(addr).collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// How fresh a read has to be.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    /// Served from the local cache right away
+    #[default]
+    Local,
+    /// Served once the leader has confirmed its leadership (ReadIndex)
+    Linearizable,
+}
+
+impl ReadConsistency {
+    /// Splits off the trailing `LINEARIZABLE` flag, if any.
+    fn split_flag(args: &[Bytes]) -> (&[Bytes], Self) {
+        match args.split_last() {
+            | Some((last, rest))
+                if !rest.is_empty() && last.eq_ignore_ascii_case(b"LINEARIZABLE") =>
+            {
+                (rest, ReadConsistency::Linearizable)
+            },
+            | _ => (args, ReadConsistency::Local),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ClientRequest {
     pub(crate) action: ClientAction,
+    // * Name the command's latency is tracked under
+    pub(crate) command: String,
     pub(crate) session_req: SessionRequest,
 }
 
@@ -95,19 +338,43 @@ impl ClientRequest {
         value: Vec<QueryIO>,
         session_req: SessionRequest,
     ) -> anyhow::Result<Self> {
-        let mut values = value.into_iter().flat_map(|v| v.unpack_single_entry::<String>());
-        let command = values.next().ok_or(anyhow::anyhow!("Unexpected command format"))?;
-        let (command, args) = (command, values.collect::<Vec<_>>());
+        let values = value
+            .into_iter()
+            .map(|v| match v {
+                | QueryIO::BulkString(bytes) => Ok(bytes),
+                | _ => Err(anyhow::anyhow!("Expected command to be a bulk string")),
+            })
+            .collect::<anyhow::Result<Vec<Bytes>>>()?;
+        let (command, args) =
+            values.split_first().ok_or(anyhow::anyhow!("Unexpected command format"))?;
+        let command = as_utf8(command)?;
+        let command_name = command.to_uppercase();
+        let action = extract_action(command, args)?;
 
-        Ok(ClientRequest {
-            action: extract_action(&command, &args.iter().map(|s| s.as_str()).collect::<Vec<_>>())
-                .map_err(|e| anyhow::anyhow!(e))?,
-            session_req,
-        })
+        Ok(ClientRequest { action, command: command_name, session_req })
     }
 }
 
-pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientAction> {
+fn as_utf8(arg: &[u8]) -> anyhow::Result<&str> {
+    std::str::from_utf8(arg)
+        .map_err(|_| anyhow::anyhow!("(error) ERR arguments must be valid UTF-8"))
+}
+
+fn text(arg: &[u8]) -> anyhow::Result<String> {
+    as_utf8(arg).map(String::from)
+}
+
+fn parse<T>(arg: &[u8]) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    Ok(as_utf8(arg)?.parse()?)
+}
+
+/// Parses a command from its arguments as they were sent. Keys and values are taken as they are,
+/// while arguments read as text must be valid UTF-8.
+pub fn extract_action(action: &str, args: &[Bytes]) -> anyhow::Result<ClientAction> {
     // Check for invalid characters in command parts
     // Command-specific validation
     let cmd = action.to_uppercase();
@@ -135,21 +402,18 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
 
     match cmd.as_str() {
         | "SET" => {
-            if !(args.len() == 2 || (args.len() == 4 && args[2].eq_ignore_ascii_case("PX"))) {
+            if !(args.len() == 2 || (args.len() == 4 && args[2].eq_ignore_ascii_case(b"PX"))) {
                 return Err(anyhow::anyhow!(
                     "(error) ERR wrong number of arguments for 'set' command"
                 ));
             }
             if args.len() == 2 {
-                return Ok(ClientAction::Set {
-                    key: args[0].to_string(),
-                    value: args[1].to_string(),
-                });
+                return Ok(ClientAction::Set { key: args[0].clone(), value: args[1].clone() });
             }
             Ok(ClientAction::SetWithExpiry {
-                key: args[0].to_string(),
-                value: args[1].to_string(),
-                expiry: extract_expiry(args[3])?,
+                key: args[0].clone(),
+                value: args[1].clone(),
+                expiry: extract_expiry(as_utf8(&args[3])?)?,
             })
         },
 
@@ -159,18 +423,17 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
                     "(error) ERR wrong number of arguments for 'append' command"
                 ));
             }
-            Ok(ClientAction::Append { key: args[0].to_string(), value: args[1].to_string() })
+            Ok(ClientAction::Append { key: args[0].clone(), value: args[1].clone() })
         },
 
         | "GET" => {
-            if args.len() == 1 {
-                Ok(ClientAction::Get { key: args[0].to_string() })
+            let (key_args, consistency) = ReadConsistency::split_flag(args);
+            if key_args.len() == 1 {
+                Ok(ClientAction::Get { key: key_args[0].clone(), consistency })
             } else if args.len() == 2 {
-                Ok(ClientAction::IndexGet { key: args[0].to_string(), index: args[1].parse()? })
+                Ok(ClientAction::IndexGet { key: args[0].clone(), index: parse(&args[1])? })
             } else {
-                return Err(anyhow::anyhow!(
-                    "(error) ERR wrong number of arguments for 'get' command"
-                ));
+                Err(anyhow::anyhow!("(error) ERR wrong number of arguments for 'get' command"))
             }
         },
 
@@ -180,121 +443,488 @@ pub fn extract_action(action: &str, args: &[&str]) -> anyhow::Result<ClientActio
             if args[0] == "*" {
                 Ok(ClientAction::Keys { pattern: None })
             } else {
-                Ok(ClientAction::Keys { pattern: Some(args[0].to_string()) })
+                Ok(ClientAction::Keys { pattern: Some(args[0].clone()) })
             }
         },
         | "DEL" => {
             require_non_empty_args()?;
-            Ok(ClientAction::Delete { keys: args.iter().map(|s| s.to_string()).collect() })
+            Ok(ClientAction::Delete { keys: args.to_vec() })
+        },
+        | "UNLINK" => {
+            require_non_empty_args()?;
+            Ok(ClientAction::Unlink { keys: args.to_vec() })
         },
         | "EXISTS" => {
             require_non_empty_args()?;
-            Ok(ClientAction::Exists { keys: args.iter().map(|s| s.to_string()).collect() })
+            Ok(ClientAction::Exists { keys: args.to_vec() })
         },
 
         | "PING" => {
             require_exact_args(0)?;
             Ok(ClientAction::Ping)
         },
+        | "HELLO" => {
+            let (protover, auth) = match args {
+                | [] => (None, None),
+                | [protover] => (Some(protover), None),
+                | [protover, clause, username, password] if clause.eq_ignore_ascii_case(b"AUTH") => {
+                    (Some(protover), Some((text(username)?, text(password)?)))
+                },
+                | [_, clause, ..] if clause.eq_ignore_ascii_case(b"AUTH") => {
+                    return Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'hello' command"
+                    ));
+                },
+                | [_, clause, ..] => {
+                    return Err(anyhow::anyhow!(
+                        "(error) ERR Syntax error in HELLO option '{}'",
+                        String::from_utf8_lossy(clause)
+                    ));
+                },
+            };
+            let protover = protover
+                .map(|v| parse(v))
+                .transpose()
+                .context("(error) ERR Protocol version is not an integer or out of range")?;
+            Ok(ClientAction::Hello { protover, auth })
+        },
+        | "AUTH" => match args {
+            | [password] => Ok(ClientAction::Auth { username: None, password: text(password)? }),
+            | [username, password] => Ok(ClientAction::Auth {
+                username: Some(text(username)?),
+                password: text(password)?,
+            }),
+            | _ => Err(anyhow::anyhow!("(error) ERR wrong number of arguments for 'auth' command")),
+        },
         | "ECHO" => {
             require_exact_args(1)?;
-            Ok(ClientAction::Echo(args[0].to_string()))
+            Ok(ClientAction::Echo(text(&args[0])?))
         },
         | "INFO" => {
-            require_non_empty_args()?;
-            Ok(ClientAction::Info)
+            let section = match args.first() {
+                | Some(arg) => as_utf8(arg)?.to_lowercase(),
+                | None => "default".to_string(),
+            };
+            Ok(ClientAction::Info { section })
         },
 
         | "CLUSTER" => {
             require_non_empty_args()?;
-            match args[0].to_uppercase().as_str() {
+            let sub = |i: usize| -> anyhow::Result<Option<String>> {
+                args.get(i).map(|arg| as_utf8(arg).map(str::to_uppercase)).transpose()
+            };
+            let peer = |i: usize| -> anyhow::Result<PeerIdentifier> {
+                Ok(PeerIdentifier(as_utf8(&args[i])?.bind_addr()?))
+            };
+            match sub(0)?.unwrap_or_default().as_str() {
                 | "NODES" => Ok(ClientAction::ClusterNodes),
+                | "SHARDS" => Ok(ClientAction::ClusterShards),
+                | "SUBSCRIBE" => Ok(ClientAction::ClusterSubscribe),
                 | "INFO" => Ok(ClientAction::ClusterInfo),
-                | "FORGET" => {
-                    if args.len() != 2 {
-                        return Err(anyhow::anyhow!(
-                            "(error) ERR wrong number of arguments for 'cluster forget' command"
-                        ));
-                    }
-                    Ok(ClientAction::ClusterForget(PeerIdentifier(args[1].bind_addr()?)))
+                | "FORGET" => match sub(2)?.as_deref() {
+                    | None if args.len() == 2 => Ok(ClientAction::ClusterForget(peer(1)?, false)),
+                    | Some("PERMANENT") if args.len() == 3 => {
+                        Ok(ClientAction::ClusterForget(peer(1)?, true))
+                    },
+                    | _ => Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'cluster forget' command"
+                    )),
+                },
+                | "BANLIST" => match sub(1)?.as_deref() {
+                    | None => Ok(ClientAction::ClusterBanList),
+                    | Some("CLEAR") if args.len() == 2 => {
+                        Ok(ClientAction::ClusterBanListClear(None))
+                    },
+                    | Some("CLEAR") if args.len() == 3 => {
+                        Ok(ClientAction::ClusterBanListClear(Some(peer(2)?)))
+                    },
+                    | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
                 },
                 | "MEET" => {
                     if args.len() == 2 {
-                        return Ok(ClientAction::ClusterMeet(
-                            PeerIdentifier(args[1].bind_addr()?),
-                            LazyOption::Lazy,
-                        ));
+                        return Ok(ClientAction::ClusterMeet(peer(1)?, LazyOption::Lazy));
                     }
                     if args.len() == 3 {
                         // args[2].parse()? should be either lazy or eager
-                        let lazy_option:LazyOption =FromStr::from_str(args[2]).context(
+                        let lazy_option:LazyOption =FromStr::from_str(as_utf8(&args[2])?).context(
                             "(error) ERR wrong arguments for 'cluster meet' command, expected 'lazy' or 'eager'"
                         )?;
 
-                        Ok(ClientAction::ClusterMeet(
-                            PeerIdentifier(args[1].bind_addr()?),
-                            lazy_option,
-                        ))
+                        Ok(ClientAction::ClusterMeet(peer(1)?, lazy_option))
                     } else {
                         Err(anyhow::anyhow!(
                             "(error) ERR wrong number of arguments for 'cluster meet' command"
                         ))
                     }
                 },
-                | "RESHARD" => Ok(ClientAction::ClusterReshard),
+                | "RESHARD" => match sub(1)?.as_deref() {
+                    | None => Ok(ClientAction::ClusterReshard),
+                    | Some("STATUS") if args.len() == 2 => Ok(ClientAction::ClusterReshardStatus),
+                    | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+                },
+                | "CONSENSUS" => {
+                    require_exact_args(1)?;
+                    Ok(ClientAction::ClusterConsensus)
+                },
+                | "HISTORY" => match args.len() {
+                    | 1 => Ok(ClientAction::ClusterHistory(None)),
+                    | 2 => Ok(ClientAction::ClusterHistory(Some(
+                        parse(&args[1]).context("(error) ERR count is not a valid integer")?,
+                    ))),
+                    | _ => Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'cluster history' command"
+                    )),
+                },
+                | "MIGRATE" => {
+                    if args.len() != 4 || !args[2].eq_ignore_ascii_case(b"TO") {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR wrong number of arguments for 'cluster migrate' command"
+                        ));
+                    }
+                    let selector =
+                        as_utf8(&args[1])?.parse().context("(error) ERR invalid key selector")?;
+                    Ok(ClientAction::ClusterMigrate {
+                        selector,
+                        target: ReplicationId::Key(text(&args[3])?),
+                    })
+                },
+                | "FAILOVER" => match args.len() {
+                    | 1 => Ok(ClientAction::ClusterFailover(None)),
+                    | 2 => Ok(ClientAction::ClusterFailover(Some(peer(1)?))),
+                    | _ => Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'cluster failover' command"
+                    )),
+                },
+                | "LEAVE" => {
+                    if args.len() != 1 {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR wrong number of arguments for 'cluster leave' command"
+                        ));
+                    }
+                    Ok(ClientAction::ClusterLeave)
+                },
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "DEBUG" => {
+            require_non_empty_args()?;
+            match args {
+                | [sub, check]
+                    if sub.eq_ignore_ascii_case(b"WAL")
+                        && check.eq_ignore_ascii_case(b"VERIFY") =>
+                {
+                    Ok(ClientAction::DebugWalVerify)
+                },
+                | [sub] if sub.eq_ignore_ascii_case(b"BIGKEYS") => Ok(ClientAction::DebugBigKeys),
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "MEMORY" => {
+            require_non_empty_args()?;
+            match args {
+                | [sub] if sub.eq_ignore_ascii_case(b"STATS") => Ok(ClientAction::MemoryStats),
+                | [sub] if sub.eq_ignore_ascii_case(b"EVICTIONPOOL") => {
+                    Ok(ClientAction::MemoryEvictionPool)
+                },
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "LATENCY" => {
+            require_non_empty_args()?;
+            match args {
+                | [sub, commands @ ..] if sub.eq_ignore_ascii_case(b"HISTOGRAM") => {
+                    Ok(ClientAction::LatencyHistogram {
+                        commands: commands
+                            .iter()
+                            .map(|c| as_utf8(c).map(str::to_uppercase))
+                            .collect::<anyhow::Result<_>>()?,
+                    })
+                },
+                | [sub] if sub.eq_ignore_ascii_case(b"RESET") => Ok(ClientAction::LatencyReset),
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "OBJECT" => {
+            require_non_empty_args()?;
+            match args {
+                | [sub, key] if sub.eq_ignore_ascii_case(b"IDLETIME") => {
+                    Ok(ClientAction::ObjectIdleTime { key: key.clone() })
+                },
+                | [sub, key] if sub.eq_ignore_ascii_case(b"FREQ") => {
+                    Ok(ClientAction::ObjectFreq { key: key.clone() })
+                },
                 | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
             }
         },
         | "REPLICAOF" => {
             require_exact_args(2)?;
-            Ok(ClientAction::ReplicaOf(PeerIdentifier::new(args[0], args[1].parse()?)))
+            Ok(ClientAction::ReplicaOf(PeerIdentifier::try_new(
+                as_utf8(&args[0])?,
+                parse(&args[1])?,
+            )?))
         },
         | "ROLE" => {
             require_exact_args(0)?;
             Ok(ClientAction::Role)
         },
+        | "READONLY" => {
+            require_exact_args(0)?;
+            Ok(ClientAction::ReadOnly)
+        },
+        | "READWRITE" => {
+            require_exact_args(0)?;
+            Ok(ClientAction::ReadWrite)
+        },
+        | "MONITOR" => {
+            require_exact_args(0)?;
+            Ok(ClientAction::Monitor)
+        },
+        | "CLIENT" => {
+            require_non_empty_args()?;
+            match args {
+                | [sub] if sub.eq_ignore_ascii_case(b"ID") => Ok(ClientAction::ClientId),
+                | [sub] if sub.eq_ignore_ascii_case(b"LIST") => Ok(ClientAction::ClientList),
+                | [sub] if sub.eq_ignore_ascii_case(b"GETNAME") => Ok(ClientAction::ClientGetName),
+                | [sub] if sub.eq_ignore_ascii_case(b"UNPAUSE") => Ok(ClientAction::ClientUnpause),
+                | [sub, timeout, mode @ ..] if sub.eq_ignore_ascii_case(b"PAUSE") => {
+                    let timeout = parse(timeout)
+                        .context("(error) ERR timeout is not an integer or out of range")?;
+                    let mode = match mode {
+                        | [] => PauseMode::All,
+                        | [mode] if mode.eq_ignore_ascii_case(b"ALL") => PauseMode::All,
+                        | [mode] if mode.eq_ignore_ascii_case(b"WRITE") => PauseMode::Write,
+                        | _ => return Err(anyhow::anyhow!("(error) ERR syntax error")),
+                    };
+                    Ok(ClientAction::ClientPause { timeout, mode })
+                },
+                | [sub, name] if sub.eq_ignore_ascii_case(b"SETNAME") => {
+                    if name.iter().any(|c| !c.is_ascii_graphic()) {
+                        return Err(anyhow::anyhow!(
+                            "(error) ERR Client names cannot contain spaces, newlines or special characters."
+                        ));
+                    }
+                    Ok(ClientAction::ClientSetName(
+                        (!name.is_empty()).then(|| text(name)).transpose()?,
+                    ))
+                },
+                | [sub, addr] if sub.eq_ignore_ascii_case(b"KILL") => {
+                    Ok(ClientAction::ClientKill {
+                        filter: ClientFilter { addr: Some(text(addr)?), ..Default::default() },
+                        legacy: true,
+                    })
+                },
+                | [sub, filters @ ..] if sub.eq_ignore_ascii_case(b"KILL") && !filters.is_empty() =>
+                {
+                    let mut filter = ClientFilter::default();
+                    for pair in filters.chunks(2) {
+                        match pair {
+                            | [name, id] if name.eq_ignore_ascii_case(b"ID") => {
+                                filter.id =
+                                    Some(parse(id).context(
+                                        "(error) ERR client-id should be greater than 0",
+                                    )?);
+                            },
+                            | [name, addr] if name.eq_ignore_ascii_case(b"ADDR") => {
+                                filter.addr = Some(text(addr)?);
+                            },
+                            | _ => return Err(anyhow::anyhow!("(error) ERR syntax error")),
+                        }
+                    }
+                    Ok(ClientAction::ClientKill { filter, legacy: false })
+                },
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "CONFIG" if args.first().is_some_and(|sub| sub.eq_ignore_ascii_case(b"SET")) => {
+            require_exact_args(3)?;
+            Ok(ClientAction::ConfigSet { parameter: text(&args[1])?, value: text(&args[2])? })
+        },
         | "CONFIG" => {
             require_exact_args(2)?;
-            Ok(ClientAction::Config { key: args[0].to_string(), value: args[1].to_string() })
+            Ok(ClientAction::Config { key: text(&args[0])?, value: text(&args[1])? })
         },
         | "SAVE" => {
             require_exact_args(0)?;
             Ok(ClientAction::Save)
         },
+        | "BGSAVE" => {
+            require_exact_args(0)?;
+            Ok(ClientAction::BgSave)
+        },
+        | "SHUTDOWN" => {
+            let save = match args {
+                | [] => None,
+                | [mode] if mode.eq_ignore_ascii_case(b"SAVE") => Some(true),
+                | [mode] if mode.eq_ignore_ascii_case(b"NOSAVE") => Some(false),
+                | _ => return Err(anyhow::anyhow!("(error) ERR syntax error")),
+            };
+            Ok(ClientAction::Shutdown { save })
+        },
+        | "IMPORT" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::Import { path: text(&args[0])? })
+        },
+        | "EXPORT" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::Export { path: text(&args[0])? })
+        },
         | "INCR" => {
             require_exact_args(1)?;
-            Ok(ClientAction::Incr { key: args[0].to_string() })
+            Ok(ClientAction::Incr { key: args[0].clone() })
         },
         | "DECR" => {
             require_exact_args(1)?;
-            Ok(ClientAction::Decr { key: args[0].to_string() })
+            Ok(ClientAction::Decr { key: args[0].clone() })
+        },
+        | "WAIT" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::Wait {
+                numreplicas: parse(&args[0])?,
+                timeout: parse(&args[1])?,
+                index: None,
+            })
         },
         | "TTL" => {
             require_exact_args(1)?;
-            Ok(ClientAction::Ttl { key: args[0].to_string() })
+            Ok(ClientAction::Ttl { key: args[0].clone() })
         },
         | "INCRBY" => {
             require_exact_args(2)?;
 
-            let key = args[0].to_string();
-            let increment = args[1].parse()?;
+            let key = args[0].clone();
+            let increment = parse(&args[1])?;
             Ok(ClientAction::IncrBy { key, increment })
         },
         | "DECRBY" => {
             require_exact_args(2)?;
 
-            let key = args[0].to_string();
-            let decrement = args[1].parse()?;
+            let key = args[0].clone();
+            let decrement = parse(&args[1])?;
             Ok(ClientAction::DecrBy { key, decrement })
         },
+        | "CAS" => {
+            require_exact_args(3)?;
+            Ok(ClientAction::Cas {
+                key: args[0].clone(),
+                expected: args[1].clone(),
+                value: args[2].clone(),
+            })
+        },
+        | "LOCK" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::Lock {
+                key: args[0].clone(),
+                expiry: extract_expiry(as_utf8(&args[1])?)?,
+                proposed_at: Utc::now(),
+            })
+        },
+        | "UNLOCK" => {
+            require_exact_args(2)?;
+            Ok(ClientAction::Unlock { key: args[0].clone(), token: parse(&args[1])? })
+        },
+        | "DUMP" => {
+            require_exact_args(1)?;
+            Ok(ClientAction::Dump { key: args[0].clone() })
+        },
+        | "RESTORE" => {
+            if !(args.len() == 3 || (args.len() == 4 && args[3].eq_ignore_ascii_case(b"REPLACE"))) {
+                return Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'restore' command"
+                ));
+            }
+            let ttl: u64 = parse(&args[1])
+                .map_err(|_| anyhow::anyhow!("ERR Invalid TTL value, must be >= 0"))?;
+            let entry = CacheEntry::new(args[0].clone(), DumpPayload::decode(as_utf8(&args[2])?)?);
+            let entry = match ttl {
+                | 0 => entry,
+                | ttl => entry.with_expiry(Utc::now() + chrono::Duration::milliseconds(ttl as i64)),
+            };
+            Ok(ClientAction::Restore { entry, replace: args.len() == 4 })
+        },
+        | "LEASE" => {
+            require_non_empty_args()?;
+            let sub = as_utf8(&args[0])?.to_uppercase();
+            let require_sub_args = |valid: bool| {
+                if !valid {
+                    Err(anyhow::anyhow!(
+                        "(error) ERR wrong number of arguments for 'lease {}' command",
+                        sub.to_lowercase()
+                    ))
+                } else {
+                    Ok(())
+                }
+            };
+            match sub.as_str() {
+                | "GRANT" => {
+                    require_sub_args(args.len() == 2)?;
+                    Ok(ClientAction::LeaseGrant { ttl: parse(&args[1])? })
+                },
+                | "KEEPALIVE" => {
+                    require_sub_args(args.len() == 2)?;
+                    Ok(ClientAction::LeaseKeepAlive { id: parse(&args[1])? })
+                },
+                | "ATTACH" => {
+                    require_sub_args(args.len() > 2)?;
+                    Ok(ClientAction::LeaseAttach { id: parse(&args[1])?, keys: args[2..].to_vec() })
+                },
+                | "REVOKE" => {
+                    require_sub_args(args.len() == 2)?;
+                    Ok(ClientAction::LeaseRevoke { id: parse(&args[1])? })
+                },
+                | _ => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
+        | "BATCH" => {
+            require_non_empty_args()?;
+            // * Operations are separated by a standalone ";" token
+            let actions = args
+                .split(|arg| arg == ";")
+                .map(|op| {
+                    let (op_cmd, op_args) = op
+                        .split_first()
+                        .ok_or(anyhow::anyhow!("(error) ERR empty operation in 'batch' command"))?;
+                    match extract_action(as_utf8(op_cmd)?, op_args)? {
+                        | action @ (ClientAction::Set { .. }
+                        | ClientAction::SetWithExpiry { .. }
+                        | ClientAction::Delete { .. }) => Ok(action),
+                        | _ => Err(anyhow::anyhow!(
+                            "(error) ERR only SET and DEL are allowed in 'batch' command"
+                        )),
+                    }
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(ClientAction::Batch { actions })
+        },
+        | "COMMAND" => {
+            let names = || args[1..].iter().map(|name| text(name)).collect::<anyhow::Result<_>>();
+            let sub = args.first().map(|sub| as_utf8(sub).map(str::to_uppercase)).transpose()?;
+            match sub.as_deref() {
+                | None => Ok(ClientAction::CommandInfo { names: vec![] }),
+                | Some("INFO") => Ok(ClientAction::CommandInfo { names: names()? }),
+                | Some("DOCS") => Ok(ClientAction::CommandDocs { names: names()? }),
+                | Some("COUNT") if args.len() == 1 => Ok(ClientAction::CommandCount),
+                | Some("GETKEYS") if args.len() > 1 => {
+                    Ok(ClientAction::CommandGetKeys { args: args[1..].to_vec() })
+                },
+                | Some(sub @ ("COUNT" | "GETKEYS")) => Err(anyhow::anyhow!(
+                    "(error) ERR wrong number of arguments for 'command {}' command",
+                    sub.to_lowercase()
+                )),
+                | Some(_) => Err(anyhow::anyhow!("(error) ERR unknown subcommand")),
+            }
+        },
         | "MGET" => {
             require_non_empty_args()?;
-            Ok(ClientAction::MGet { keys: args.iter().map(|s| s.to_string()).collect() })
+            let (key_args, consistency) = ReadConsistency::split_flag(args);
+            Ok(ClientAction::MGet { keys: key_args.to_vec(), consistency })
         },
         // Add other commands as needed
         | unknown_cmd => Err(anyhow::anyhow!(
             "(error) ERR unknown command '{unknown_cmd}', with args beginning with {}",
-            args.iter().map(|s| format!("'{s}'")).collect::<Vec<_>>().join(" ")
+            args.iter()
+                .map(|s| format!("'{}'", String::from_utf8_lossy(s)))
+                .collect::<Vec<_>>()
+                .join(" ")
         )),
     }
 }
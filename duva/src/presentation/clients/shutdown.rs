@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// How far the node has got in shutting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShutdownState {
+    Serving,
+    // * Connections are no longer accepted while writes drain and state is flushed
+    Draining,
+    Done,
+}
+
+/// Shutdown progress shared between the client listeners and `SHUTDOWN`, so that the listeners
+/// stop accepting while it runs, start again if it fails and exit once it is done.
+#[derive(Debug, Clone)]
+pub(crate) struct ServerShutdown(Arc<watch::Sender<ShutdownState>>);
+
+impl Default for ServerShutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(ShutdownState::Serving).0))
+    }
+}
+
+impl ServerShutdown {
+    /// Starts draining, returning false when a shutdown is already under way.
+    pub(crate) fn begin(&self) -> bool {
+        self.0.send_if_modified(|state| {
+            let serving = *state == ShutdownState::Serving;
+            if serving {
+                *state = ShutdownState::Draining;
+            }
+            serving
+        })
+    }
+
+    pub(crate) fn abort(&self) {
+        self.0.send_replace(ShutdownState::Serving);
+    }
+
+    pub(crate) fn finish(&self) {
+        self.0.send_replace(ShutdownState::Done);
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ShutdownState> {
+        self.0.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_one_shutdown_runs_at_a_time() {
+        let shutdown = ServerShutdown::default();
+        let state = shutdown.subscribe();
+
+        assert!(shutdown.begin());
+        assert!(!shutdown.begin());
+        assert_eq!(*state.borrow(), ShutdownState::Draining);
+
+        shutdown.abort();
+        assert!(shutdown.begin());
+        shutdown.finish();
+        assert_eq!(*state.borrow(), ShutdownState::Done);
+        assert!(!shutdown.begin());
+    }
+}
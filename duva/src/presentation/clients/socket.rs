@@ -0,0 +1,54 @@
+use crate::config::ENV;
+use crate::domains::interface::{TRead, TWrite};
+use std::fmt::{Debug, Display};
+use std::net::{IpAddr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+
+/// Where a client connection comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClientAddr {
+    Tcp(SocketAddr),
+    // * Connected through `unixsocket`, which there is only one of
+    Unix,
+}
+
+impl ClientAddr {
+    pub(crate) fn ip(&self) -> Option<IpAddr> {
+        match self {
+            | ClientAddr::Tcp(addr) => Some(addr.ip()),
+            | ClientAddr::Unix => None,
+        }
+    }
+}
+
+impl Display for ClientAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | ClientAddr::Tcp(addr) => write!(f, "{addr}"),
+            // * Formatted the way Redis does, as the socket path with port 0
+            | ClientAddr::Unix => write!(f, "{}:0", ENV.unixsocket.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+/// Stream a client connects over, TCP or a Unix domain socket.
+pub(crate) trait ClientSocket:
+    AsyncRead + AsyncWrite + Unpin + Send + Sync + Debug + 'static
+{
+    fn into_halves(self) -> (Box<dyn TRead>, Box<dyn TWrite>);
+}
+
+impl ClientSocket for TcpStream {
+    fn into_halves(self) -> (Box<dyn TRead>, Box<dyn TWrite>) {
+        let (r, w) = self.into_split();
+        (Box::new(r), Box::new(w))
+    }
+}
+
+impl ClientSocket for UnixStream {
+    fn into_halves(self) -> (Box<dyn TRead>, Box<dyn TWrite>) {
+        let (r, w) = self.into_split();
+        (Box::new(r), Box::new(w))
+    }
+}
@@ -1,116 +1,656 @@
+use super::audit::AuditClient;
+use super::authenticate::{DEFAULT_USER, password_matches};
+use super::controller::PendingWrite;
+use super::info::{ConnectedClient, ServerStats};
+use super::latency::Phase;
+use super::output::{OutputBuffer, OutputSender};
+use super::quota::quota_key;
+use super::registry::RegisteredClient;
+use super::request::ClientAction;
+use super::socket::ClientAddr;
 use super::{ClientController, request::ClientRequest};
+use crate::config::ENV;
 use crate::domains::cluster_actors::topology::Topology;
+use crate::domains::query_io::{IncompleteFrame, RESP2, RESP3};
 use crate::domains::{
     IoError, QueryIO,
     cluster_actors::SessionRequest,
+    deserialize,
     interface::{TRead, TWrite},
 };
-use tokio::{
-    net::tcp::{OwnedReadHalf, OwnedWriteHalf},
-    sync::mpsc::Sender,
-};
-use tracing::{error, instrument, trace};
+use bytes::{Bytes, BytesMut};
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::SendError;
+use tracing::{Instrument, debug, error, instrument, trace};
 use uuid::Uuid;
 
 pub struct ClientStreamReader {
-    pub(crate) r: OwnedReadHalf,
+    pub(crate) r: Box<dyn TRead>,
     pub(crate) client_id: Uuid,
+    pub(crate) peer_addr: ClientAddr,
+    // * Protocol negotiated through HELLO. Connections start in RESP2.
+    pub(crate) protocol: u8,
+    // * Bytes read off the socket that do not yet form a complete frame.
+    pub(crate) buffer: BytesMut,
+    // * Set by READONLY: reads on a replica are only served within the staleness bound
+    pub(crate) read_only: bool,
+    // * Log index of the latest write sent on this connection, which WAIT blocks on
+    pub(crate) last_write_index: u64,
+    // * Cleared until AUTH succeeds when `requirepass` is set
+    pub(crate) authenticated: bool,
+    // * User the connection is authenticated as
+    pub(crate) user: String,
+    // * Key of the quotas the connection spends, see `quota_key`
+    pub(crate) quota_key: String,
+    // * Entry of the connection in the client registry, which it leaves once closed
+    pub(crate) client: RegisteredClient,
+    // * Set by MONITOR: the connection only receives from then on, so it is never idle
+    pub(crate) monitoring: bool,
+    // * Set by CLUSTER SUBSCRIBE: topology pushes may be all the connection waits for
+    pub(crate) subscribed: bool,
 }
 
 impl ClientStreamReader {
-    #[instrument(level = tracing::Level::DEBUG, skip(self, handler, sender),fields(client_id= %self.client_id))]
+    #[instrument(level = tracing::Level::DEBUG, skip(self, handler, sender, _connected),fields(client_id= %self.client_id))]
     pub(crate) async fn handle_client_stream(
         mut self,
         handler: ClientController,
-        sender: Sender<QueryIO>,
+        sender: OutputSender,
+        // * Keeps the connection counted in connected_clients until it is closed
+        _connected: ConnectedClient,
     ) {
+        let mut killed = self.client.killed();
         loop {
-            let requests = match self.extract_query().await {
+            // * A user over quota is throttled by leaving its requests unread for a while
+            let delay = handler.quotas.delay(&self.quota_key, Instant::now());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+
+            // * Only time spent waiting for the next request counts, so blocked commands never do
+            let idle_timeout = (ENV.timeout > 0 && !self.monitoring && !self.subscribed)
+                .then(|| Duration::from_secs(ENV.timeout));
+            let extracted = tokio::select! {
+                extracted = self.extract_query(&handler) => extracted,
+                _ = killed.wait_for(|killed| *killed) => return,
+                _ = idle(idle_timeout) => {
+                    debug!("Closing client connection idle for {}s", ENV.timeout);
+                    return;
+                },
+            };
+            let requests = match extracted {
                 | Ok(requests) => requests,
                 | Err(err) => {
                     error!("{}", err);
                     if err.should_break() {
                         return;
                     }
-                    let _ = sender.send(QueryIO::Err(err.to_string().into())).await;
+                    let _ =
+                        self.send(QueryIO::Err(err.to_string().into()), &handler, &sender).await;
                     continue;
                 },
             };
 
-            for req in requests {
-                trace!(?req, "Processing request");
+            handler.quotas.charge_commands(&self.quota_key, requests.len() as u64, Instant::now());
+            if self.dispatch(requests, &handler, &sender).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Dispatches every request parsed from a single read. A request that failed to parse is answered
+    /// with its error in its own place, after the requests ahead of it.
+    ///
+    /// Consecutive writes are proposed back to back so that they share replication rounds, then
+    /// applied in log order. Consecutive reads run concurrently. A read never starts before the
+    /// writes sent ahead of it on the same connection are applied, and responses are always sent
+    /// in request order.
+    async fn dispatch(
+        &mut self,
+        requests: Vec<ParsedRequest>,
+        handler: &ClientController,
+        sender: &OutputSender,
+    ) -> Result<(), SendError<Bytes>> {
+        let mut segment: Vec<(ClientRequest, u8)> = Vec::new();
+        let mut segment_is_write = false;
 
-                let result = if req.action.consensus_required() {
-                    match handler.make_consensus(req).await {
-                        | Ok((action, idx)) => handler.handle(action, Some(idx)).await,
-                        | Err(err) => Err(err),
+        for (args, req) in requests {
+            let mut req = match req {
+                | Ok(req) => req,
+                | Err(err) => {
+                    if self.authenticated {
+                        self.publish(args, handler);
                     }
-                } else {
-                    handler.handle(req.action, None).await
+                    self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender)
+                        .await?;
+                    self.send(QueryIO::Err(err.to_string().into()), handler, sender).await?;
+                    continue;
+                },
+            };
+            trace!(?req, "Processing request");
+            handler.server_stats.record_command();
+            self.client.record_command(&req.command);
+
+            if let ClientAction::Auth { username, password } = &req.action {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                let reply = self.auth(username.as_deref(), password, handler);
+                if !matches!(reply, QueryIO::Err(_)) {
+                    self.publish(args, handler);
+                }
+                self.send(reply, handler, sender).await?;
+                continue;
+            }
+            // * HELLO can authenticate and switch protocols at once, it is rejected as a whole
+            if let ClientAction::Hello { protover, auth: Some((username, password)) } = &req.action
+            {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                let reply = match protover {
+                    | Some(RESP2 | RESP3) => self.auth(Some(username), password, handler),
+                    | _ => QueryIO::Err("NOPROTO unsupported protocol version".into()),
                 };
+                if matches!(reply, QueryIO::Err(_)) {
+                    self.send(reply, handler, sender).await?;
+                    continue;
+                }
+            }
+            if !self.authenticated {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                let err = QueryIO::Err("NOAUTH Authentication required.".into());
+                self.send(err, handler, sender).await?;
+                continue;
+            }
+            self.publish(args, handler);
+
+            // * CLIENT UNPAUSE is what ends a pause, so it is never held back
+            let is_write = req.action.consensus_required();
+            if !matches!(req.action, ClientAction::ClientUnpause) && handler.pause.holds(is_write) {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                handler.pause.wait(is_write).await;
+            }
 
-                let response = match result {
-                    | Ok(res) => res,
-                    | Err(e) => {
-                        error!("{:?}", e);
-                        QueryIO::Err(e.to_string().into())
+            if let ClientAction::Hello { protover, .. } = &mut req.action {
+                match protover {
+                    | Some(version @ (RESP2 | RESP3)) => self.protocol = *version,
+                    | Some(_) => {
+                        self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender)
+                            .await?;
+                        let err = QueryIO::Err("NOPROTO unsupported protocol version".into());
+                        self.send(err, handler, sender).await?;
+                        continue;
                     },
-                };
+                    | None => *protover = Some(self.protocol),
+                }
+            }
 
-                if sender.send(response).await.is_err() {
-                    return;
+            if let ClientAction::Monitor = req.action {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                self.send(QueryIO::SimpleString("OK".into()), handler, sender).await?;
+                handler.monitor.subscribe(sender.clone());
+                self.client.output().set_limit(ENV.client_output_buffer_limit_monitor);
+                self.client.set_monitor();
+                self.monitoring = true;
+                continue;
+            }
+
+            if let ClientAction::ClusterSubscribe = req.action {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                if let Err(err) = handler.subscribe_topology(sender.clone(), self.protocol).await {
+                    self.send(QueryIO::Err(err.to_string().into()), handler, sender).await?;
+                    continue;
+                }
+                self.client.set_subscriber();
+                self.subscribed = true;
+                continue;
+            }
+
+            if let Some(reply) = self.client_command(&req.action) {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                self.send(reply, handler, sender).await?;
+                continue;
+            }
+
+            let read_only = match req.action {
+                | ClientAction::ReadOnly => true,
+                | ClientAction::ReadWrite => false,
+                | _ => self.read_only,
+            };
+            if read_only != self.read_only {
+                // * Requests sent before the flag changed are served under the previous mode
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                self.read_only = read_only;
+                self.client.set_read_only(read_only);
+            }
+
+            if is_write != segment_is_write {
+                self.flush(std::mem::take(&mut segment), segment_is_write, handler, sender).await?;
+                segment_is_write = is_write;
+            }
+            if let ClientAction::Wait { index, .. } = &mut req.action {
+                *index = Some(self.last_write_index);
+            }
+            segment.push((req, self.protocol));
+        }
+
+        self.flush(segment, segment_is_write, handler, sender).await
+    }
+
+    /// Streams a request that got past authentication to the monitoring connections, so that
+    /// unauthenticated clients can neither write to the feed nor leak failed credentials into it.
+    fn publish(&self, args: Option<Vec<QueryIO>>, handler: &ClientController) {
+        if let Some(args) = args {
+            handler.monitor.publish(self.peer_addr, &args);
+        }
+    }
+
+    /// Marks the connection authenticated when the credentials match `requirepass`. A failed attempt
+    /// leaves an authenticated connection as it was.
+    fn auth(
+        &mut self,
+        username: Option<&str>,
+        password: &str,
+        handler: &ClientController,
+    ) -> QueryIO {
+        let outcome = match ENV.requirepass {
+            | None => Err("ERR AUTH called without any password configured for the default user"),
+            | Some(_) if password_matches(username, password) => Ok(()),
+            | Some(_) => Err("WRONGPASS invalid username-password pair or user is disabled."),
+        };
+        if outcome.is_ok() {
+            self.authenticated = true;
+            self.user = username.unwrap_or(DEFAULT_USER).to_string();
+            self.quota_key = quota_key(&self.user, &self.peer_addr);
+            self.client.set_user(&self.user);
+        }
+
+        let client = AuditClient { addr: self.peer_addr, client_id: Some(self.client_id) };
+        handler.audit.record("auth", client, "AUTH", outcome.map_err(str::to_string));
+        match outcome {
+            | Ok(()) => QueryIO::SimpleString("OK".into()),
+            | Err(err) => QueryIO::Err(err.into()),
+        }
+    }
+
+    /// Serves the `CLIENT` subcommands that concern this connection.
+    fn client_command(&self, action: &ClientAction) -> Option<QueryIO> {
+        let reply = match action {
+            | ClientAction::ClientId => QueryIO::SimpleString(self.client.id().to_string().into()),
+            | ClientAction::ClientSetName(name) => {
+                self.client.set_name(name.clone());
+                QueryIO::SimpleString("OK".into())
+            },
+            | ClientAction::ClientGetName => match self.client.name() {
+                | Some(name) => QueryIO::BulkString(name.into()),
+                | None => QueryIO::Null,
+            },
+            | _ => return None,
+        };
+        Some(reply)
+    }
+
+    async fn flush(
+        &mut self,
+        segment: Vec<(ClientRequest, u8)>,
+        is_write: bool,
+        handler: &ClientController,
+        sender: &OutputSender,
+    ) -> Result<(), SendError<Bytes>> {
+        let latency = &handler.latency;
+        if is_write {
+            let mut pending = Vec::with_capacity(segment.len());
+            for (req, protocol) in segment {
+                let (command, proposed_at) = (req.command.clone(), Instant::now());
+                // * Starts the trace the write is followed by across the cluster, until it is answered
+                let span = tracing::debug_span!(
+                    "client_write",
+                    command = %command,
+                    traceparent = tracing::field::Empty
+                );
+                let consensus = handler.request_consensus(req).instrument(span.clone()).await;
+                pending.push((command, proposed_at, span, consensus, protocol));
+            }
+            for (command, proposed_at, _span, consensus, protocol) in pending {
+                let result = match consensus {
+                    | Ok(PendingWrite::Proposed(consensus)) => match consensus.wait().await {
+                        | Ok((action, idx, _applying)) => {
+                            latency.record(&command, Phase::Consensus, proposed_at.elapsed());
+                            self.last_write_index = self.last_write_index.max(idx);
+                            let applied_at = Instant::now();
+                            let result = handler.handle(action, Some(idx)).await;
+                            latency.record(&command, Phase::Apply, applied_at.elapsed());
+                            result
+                        },
+                        | Err(err) => Err(err),
+                    },
+                    // * Committed and applied by the leaders of the partitions involved
+                    | Ok(PendingWrite::Scattered(reply)) => {
+                        let result = reply.await.unwrap_or_else(|err| Err(err.into()));
+                        latency.record(&command, Phase::Consensus, proposed_at.elapsed());
+                        result
+                    },
+                    | Err(err) => Err(err),
+                };
+                self.reply(&command, Self::to_response(result, protocol), handler, sender).await?;
+            }
+        } else {
+            // * One staleness check covers every keyspace read in the segment
+            let staleness =
+                if self.read_only && segment.iter().any(|(req, _)| req.action.is_keyspace_read()) {
+                    handler.check_replica_staleness().await.map_err(|err| err.to_string())
+                } else {
+                    Ok(())
+                };
+            let staleness = &staleness;
+            let client = AuditClient { addr: self.peer_addr, client_id: Some(self.client_id) };
+            let results = join_all(segment.into_iter().map(|(req, protocol)| async move {
+                let applied_at = Instant::now();
+                let audit_operation = req.action.audit_operation();
+                let result = match staleness {
+                    | Err(err) if req.action.is_keyspace_read() => {
+                        Err(anyhow::anyhow!(err.clone()))
+                    },
+                    | _ => handler.handle(req.action, None).await,
+                };
+                latency.record(&req.command, Phase::Apply, applied_at.elapsed());
+                if let Some(operation) = audit_operation {
+                    let outcome = match &result {
+                        | Ok(QueryIO::Err(err)) => Err(String::from_utf8_lossy(err).into_owned()),
+                        | Ok(_) => Ok(()),
+                        | Err(err) => Err(err.to_string()),
+                    };
+                    handler.audit.record("command", client, &operation, outcome);
                 }
+                (req.command, result, protocol)
+            }))
+            .await;
+            for (command, result, protocol) in results {
+                self.reply(&command, Self::to_response(result, protocol), handler, sender).await?;
             }
         }
+        Ok(())
     }
 
-    pub(crate) async fn extract_query(&mut self) -> Result<Vec<ClientRequest>, IoError> {
-        let query_ios = self.r.read_values().await?;
+    /// Hands the reply to the connection's writer, which takes longer when it is backed up, and
+    /// records how long that took.
+    async fn reply(
+        &self,
+        command: &str,
+        response: QueryIO,
+        handler: &ClientController,
+        sender: &OutputSender,
+    ) -> Result<(), SendError<Bytes>> {
+        let replied_at = Instant::now();
+        self.send(response, handler, sender).await?;
+        handler.latency.record(command, Phase::Reply, replied_at.elapsed());
+        Ok(())
+    }
 
-        query_ios
+    /// Hands the reply to the connection's writer and charges the bytes it was serialized to to the
+    /// connection's output quota.
+    async fn send(
+        &self,
+        reply: QueryIO,
+        handler: &ClientController,
+        sender: &OutputSender,
+    ) -> Result<(), SendError<Bytes>> {
+        let sent_at = Instant::now();
+        let sent = sender.send(reply).await?;
+        handler.quotas.charge_output(&self.quota_key, sent as u64, sent_at);
+        Ok(())
+    }
+
+    fn to_response(result: anyhow::Result<QueryIO>, protocol: u8) -> QueryIO {
+        match result {
+            | Ok(res) if protocol == RESP2 => res.into_resp2(),
+            | Ok(res) => res,
+            | Err(e) => {
+                error!("{:?}", e);
+                QueryIO::Err(e.to_string().into())
+            },
+        }
+    }
+
+    /// Parses the requests of the next read, each with its arguments as sent when a connection is
+    /// monitoring, to be published once the request is let through.
+    pub(crate) async fn extract_query(
+        &mut self,
+        handler: &ClientController,
+    ) -> Result<Vec<ParsedRequest>, IoError> {
+        let mut chunk = BytesMut::with_capacity(512);
+        self.r.read_bytes(&mut chunk).await?;
+        // * Only a partial frame left over from the last read is copied, the chunk is parsed as is
+        if self.buffer.is_empty() {
+            self.buffer = chunk;
+        } else {
+            self.buffer.extend_from_slice(&chunk);
+        }
+
+        let parsed_at = Instant::now();
+        let query_ios = parse_frames(&mut self.buffer).map_err(|e| {
+            self.buffer.clear();
+            IoError::Custom(format!("Parsing error: {e:?}"))
+        })?;
+        // * Frames are parsed off the buffer together, so each request takes an even share
+        let frame_parse = parsed_at.elapsed() / query_ios.len().max(1) as u32;
+
+        // * Each request keeps its own outcome, so one that fails to parse leaves the rest served
+        Ok(query_ios
             .into_iter()
             .map(|query_io| {
                 let QueryIO::SessionRequest { request_id, value } = query_io else {
-                    return Err(IoError::Custom("Unexpected command format".to_string()));
+                    return (None, Err(IoError::Custom("Unexpected command format".to_string())));
                 };
+                let args = handler.monitor.is_watched().then(|| value.clone());
                 let session_request = SessionRequest::new(request_id, self.client_id);
 
-                ClientRequest::from_user_input(value, session_request)
+                let parsed_at = Instant::now();
+                let request = ClientRequest::from_user_input(value, session_request)
                     .map_err(|e| IoError::Custom(e.to_string()))
+                    .inspect(|request| {
+                        let elapsed = frame_parse + parsed_at.elapsed();
+                        handler.latency.record(&request.command, Phase::Parse, elapsed);
+                    });
+                (args, request)
             })
-            .collect()
+            .collect())
     }
 }
 
-pub struct ClientStreamWriter(pub(crate) OwnedWriteHalf);
-impl ClientStreamWriter {
-    pub(crate) async fn write(&mut self, query_io: QueryIO) -> Result<(), IoError> {
-        self.0.write(query_io).await
+/// A request as parsed off the connection, with its arguments as sent when it is to be monitored.
+pub(crate) type ParsedRequest = (Option<Vec<QueryIO>>, Result<ClientRequest, IoError>);
+
+/// Resolves once the connection has been idle for `timeout`, never when there is none.
+async fn idle(timeout: Option<Duration>) {
+    match timeout {
+        | Some(timeout) => tokio::time::sleep(timeout).await,
+        | None => std::future::pending().await,
+    }
+}
+
+/// Drains every complete frame from `buffer`, leaving a trailing partial frame in place.
+/// The buffer is frozen once and each frame split off it, so only the partial frame is copied.
+fn parse_frames(buffer: &mut BytesMut) -> anyhow::Result<Vec<QueryIO>> {
+    let mut pending = buffer.split().freeze();
+    let mut frames = Vec::new();
+    while !pending.is_empty() {
+        match deserialize(pending.clone()) {
+            | Ok((query_io, consumed)) => {
+                frames.push(query_io);
+                let _ = pending.split_to(consumed);
+            },
+            | Err(err) if err.downcast_ref::<IncompleteFrame>().is_some() => break,
+            | Err(err) => return Err(err),
+        }
     }
+    buffer.extend_from_slice(&pending);
+    Ok(frames)
+}
 
+pub struct ClientStreamWriter(pub(crate) Box<dyn TWrite>);
+impl ClientStreamWriter {
+    /// Writes what is sent on the returned channel to the connection, until the connection is
+    /// gone or `output` is closed.
     pub(crate) fn run(
         mut self,
         mut topology_observer: tokio::sync::broadcast::Receiver<Topology>,
-    ) -> Sender<QueryIO> {
-        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        output: OutputBuffer,
+        stats: ServerStats,
+    ) -> OutputSender {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Bytes>(100);
+        let mut killed = output.closed();
+        let sender = OutputSender::new(tx, output.clone(), stats);
         tokio::spawn(async move {
-            while let Some(data) = rx.recv().await {
-                if let Err(e) = self.write(data).await {
-                    if e.should_break() {
-                        break;
-                    }
+            loop {
+                let data = tokio::select! {
+                    data = rx.recv() => match data {
+                        | Some(data) => data,
+                        | None => break,
+                    },
+                    _ = killed.wait_for(|killed| *killed) => break,
+                };
+                let written = self.0.write_bytes(&data).await;
+                output.written(data.len() as u64);
+                if let Err(e) = written
+                    && e.should_break()
+                {
+                    break;
                 }
             }
         });
 
         tokio::spawn({
-            let tx = tx.clone();
+            let sender = sender.clone();
             async move {
                 while let Ok(topology) = topology_observer.recv().await {
-                    let _ = tx.send(QueryIO::TopologyChange(topology)).await;
+                    let _ = sender.send(QueryIO::TopologyChange(topology)).await;
                 }
             }
         });
-        tx
+        sender
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frames_keeps_partial_frame() {
+        // GIVEN two pipelined requests where the second is cut mid-frame
+        let first = QueryIO::SessionRequest {
+            request_id: 1,
+            value: vec![QueryIO::BulkString("GET".into()), QueryIO::BulkString("a".into())],
+        }
+        .serialize();
+        let second = QueryIO::SessionRequest {
+            request_id: 2,
+            value: vec![QueryIO::BulkString("GET".into()), QueryIO::BulkString("b".into())],
+        }
+        .serialize();
+        let mut buffer = BytesMut::new();
+        buffer.extend_from_slice(&first);
+        buffer.extend_from_slice(&second[..second.len() - 3]);
+
+        // WHEN
+        let frames = parse_frames(&mut buffer).unwrap();
+
+        // THEN only the complete frame is returned and the tail is kept
+        assert_eq!(frames.len(), 1);
+        assert_eq!(&buffer[..], &second[..second.len() - 3]);
+
+        // WHEN the rest arrives
+        buffer.extend_from_slice(&second[second.len() - 3..]);
+        let frames = parse_frames(&mut buffer).unwrap();
+
+        // THEN
+        assert_eq!(frames.len(), 1);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_request_takes_keys_and_values_as_they_are() {
+        // GIVEN
+        let session_req = SessionRequest::new(1, Uuid::now_v7());
+        let args = |key: Bytes, value: Bytes| {
+            vec![
+                QueryIO::BulkString("SET".into()),
+                QueryIO::BulkString(key),
+                QueryIO::BulkString(value),
+            ]
+        };
+        let value = Bytes::from_static(b"\xff\r\n\x00");
+
+        // WHEN
+        let binary_value =
+            ClientRequest::from_user_input(args("a".into(), value.clone()), session_req.clone());
+        let binary_key = ClientRequest::from_user_input(
+            args(Bytes::from_static(b"\xff\x00"), "b".into()),
+            session_req,
+        );
+
+        // THEN - the value is not skipped over, which would shift the arguments after it
+        assert!(matches!(
+            binary_value.unwrap().action,
+            ClientAction::Set { key, value: set } if key == "a" && set == value
+        ));
+        assert!(matches!(
+            binary_key.unwrap().action,
+            ClientAction::Set { key, .. } if key == b"\xff\x00"[..]
+        ));
+    }
+
+    #[test]
+    fn test_cas_takes_expected_and_new_values_as_they_are() {
+        // GIVEN
+        let args = vec![
+            QueryIO::BulkString("CAS".into()),
+            QueryIO::BulkString("a".into()),
+            QueryIO::BulkString(Bytes::from_static(b"\xfe")),
+            QueryIO::BulkString(Bytes::from_static(b"\xff")),
+        ];
+
+        // WHEN
+        let request =
+            ClientRequest::from_user_input(args, SessionRequest::new(1, Uuid::now_v7())).unwrap();
+
+        // THEN
+        assert!(matches!(
+            request.action,
+            ClientAction::Cas { expected, value, .. } if expected == b"\xfe"[..] && value == b"\xff"[..]
+        ));
+    }
+
+    #[test]
+    fn test_batch_takes_values_of_its_operations_as_they_are() {
+        // GIVEN
+        let arg = |bytes: &'static [u8]| QueryIO::BulkString(Bytes::from_static(bytes));
+        let args = vec![
+            arg(b"BATCH"),
+            arg(b"SET"),
+            arg(b"a"),
+            arg(b"\xfe"),
+            arg(b";"),
+            arg(b"SET"),
+            arg(b"b"),
+            arg(b"\xff\r\n"),
+            arg(b";"),
+            arg(b"DEL"),
+            arg(b"c"),
+        ];
+
+        // WHEN
+        let request =
+            ClientRequest::from_user_input(args, SessionRequest::new(1, Uuid::now_v7())).unwrap();
+
+        // THEN
+        let ClientAction::Batch { actions } = request.action else { panic!() };
+        assert!(matches!(
+            &actions[..],
+            [
+                ClientAction::Set { value: first, .. },
+                ClientAction::Set { value: second, .. },
+                ClientAction::Delete { .. },
+            ] if first == &b"\xfe"[..] && second == &b"\xff\r\n"[..]
+        ));
     }
 }
@@ -1,12 +1,20 @@
-use crate::domains::cluster_actors::topology::Topology;
+use crate::domains::cluster_actors::actor::client_sessions::ClientSessionStats;
+use crate::domains::cluster_actors::actor::heartbeat_scheduler::RaftTimings;
+use crate::domains::cluster_actors::consensus::pending_writes::PendingWriteStats;
+use crate::domains::cluster_actors::consensus::report::ConsensusReport;
+use crate::domains::cluster_actors::forwarding::{ForwardedOp, ForwardedReply};
+use crate::domains::cluster_actors::history::TopologyEvent;
+use crate::domains::cluster_actors::topology::{RoutingTable, Shard, Topology};
+use crate::domains::operation_logs::interfaces::{FsyncPolicy, WalVerification};
+use crate::domains::saves::snapshot::Metadata;
 use crate::{
     domains::{
         cluster_actors::{
-            ClientMessage, ConnectionMessage, LazyOption,
+            ClientMessage, ConnectionMessage, KeySelector, LazyOption,
             actor::ClusterCommandHandler,
-            replication::{ReplicationRole, ReplicationState},
+            replication::{ReplicationId, ReplicationLinks, ReplicationRole, ReplicationState},
         },
-        peers::{identifier::PeerIdentifier, peer::PeerState},
+        peers::{identifier::PeerIdentifier, peer::PeerState, reconnect::LinkState},
     },
     make_smart_pointer,
 };
@@ -17,16 +25,9 @@ pub(crate) struct ClusterCommunicationManager(pub(crate) ClusterCommandHandler);
 make_smart_pointer!(ClusterCommunicationManager, ClusterCommandHandler);
 
 impl ClusterCommunicationManager {
-    pub(crate) async fn route_get_peers(&self) -> anyhow::Result<Vec<PeerIdentifier>> {
-        let (tx, rx) = tokio::sync::oneshot::channel();
-        self.send(ClientMessage::GetPeers(tx.into())).await?;
-        let peers = rx.await?;
-        Ok(peers)
-    }
-
     pub(crate) async fn route_get_topology(&self) -> anyhow::Result<Topology> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.send(ClientMessage::GetTopology(tx.into())).await?;
+        self.send_client(ClientMessage::GetTopology(tx.into())).await?;
         let peers = rx.await?;
         Ok(peers)
     }
@@ -43,7 +44,19 @@ impl ClusterCommunicationManager {
 
     pub(crate) async fn route_get_replication_state(&self) -> anyhow::Result<ReplicationState> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.send(ClientMessage::ReplicationInfo(tx.into())).await?;
+        self.send_client(ClientMessage::ReplicationInfo(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_snapshot_metadata(&self) -> anyhow::Result<Metadata> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SnapshotMetadata(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_replication_links(&self) -> anyhow::Result<ReplicationLinks> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ReplicationLinks(tx.into())).await?;
         Ok(rx.await?)
     }
 
@@ -60,26 +73,51 @@ impl ClusterCommunicationManager {
         //cluster_stats_messages_sent:1483972
         //cluster_stats_messages_received:1483968
         //total_cluster_links_buffer_limit_exceeded:0
-        let known_node_len = self.route_get_peers().await?.len();
-        Ok(format!("cluster_known_nodes:{known_node_len}"))
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::PeerSuspicion(tx.into())).await?;
+        let suspicion = rx.await?;
+        let phis = suspicion
+            .iter()
+            .map(|(peer, phi)| format!("{peer}={phi:.2}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        Ok(format!("cluster_known_nodes:{}\r\ncluster_peer_phi:{phis}", suspicion.len()))
     }
 
     pub(crate) async fn route_forget_peer(
         &self,
         peer_identifier: PeerIdentifier,
+        permanent: bool,
     ) -> anyhow::Result<bool> {
         let (tx, rx) = tokio::sync::oneshot::channel::<Option<()>>();
-        self.send(ClientMessage::ForgetPeer(peer_identifier, tx.into())).await?;
+        self.send_client(ClientMessage::ForgetPeer(peer_identifier, permanent, tx.into())).await?;
         let Some(_) = rx.await? else { return Ok(false) };
         Ok(true)
     }
 
+    pub(crate) async fn route_banned_peers(
+        &self,
+    ) -> anyhow::Result<Vec<(PeerIdentifier, Option<u64>)>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::BannedPeers(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_clear_bans(
+        &self,
+        peer: Option<PeerIdentifier>,
+    ) -> anyhow::Result<usize> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClearBans(peer, tx.into())).await?;
+        Ok(rx.await?)
+    }
+
     pub(crate) async fn route_replicaof(
         &self,
         peer_identifier: PeerIdentifier,
     ) -> anyhow::Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let _ = self.send(ClientMessage::ReplicaOf(peer_identifier, tx.into())).await;
+        let _ = self.send_client(ClientMessage::ReplicaOf(peer_identifier, tx.into())).await;
 
         rx.await?
     }
@@ -90,25 +128,170 @@ impl ClusterCommunicationManager {
         lazy_option: LazyOption,
     ) -> anyhow::Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let _ =
-            self.send(ClientMessage::ClusterMeet(peer_identifier, lazy_option, tx.into())).await;
+        let _ = self
+            .send_client(ClientMessage::ClusterMeet(peer_identifier, lazy_option, tx.into()))
+            .await;
         rx.await?
     }
     pub(crate) async fn route_cluster_reshard(&self) -> anyhow::Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let _ = self.send(ClientMessage::ClusterReshard(tx.into())).await;
+        let _ = self.send_client(ClientMessage::ClusterReshard(tx.into())).await;
+        rx.await?
+    }
+
+    pub(crate) async fn route_cluster_reshard_status(&self) -> anyhow::Result<String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterReshardStatus(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_cluster_consensus(&self) -> anyhow::Result<ConsensusReport> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterConsensus(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_cluster_history(
+        &self,
+        count: Option<usize>,
+    ) -> anyhow::Result<Vec<TopologyEvent>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterHistory(count, tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_cluster_migrate(
+        &self,
+        selector: KeySelector,
+        target: ReplicationId,
+    ) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterMigrate(selector, target, tx.into())).await?;
         rx.await?
     }
 
-    pub(crate) async fn route_cluster_nodes(&self) -> anyhow::Result<Vec<PeerState>> {
+    pub(crate) async fn route_cluster_failover(
+        &self,
+        target: Option<PeerIdentifier>,
+    ) -> anyhow::Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.send(ClientMessage::ClusterNodes(tx.into())).await?;
+        let _ = self.send_client(ClientMessage::ClusterFailover(target, tx.into())).await;
+        rx.await?
+    }
+
+    pub(crate) async fn route_pending_write_stats(&self) -> anyhow::Result<PendingWriteStats> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::PendingWriteStats(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_client_session_stats(&self) -> anyhow::Result<ClientSessionStats> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClientSessionStats(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_set_client_session_ttl(&self, ttl: u64) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SetClientSessionTtl(ttl, tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_fsync_policy(&self) -> anyhow::Result<FsyncPolicy> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::GetFsyncPolicy(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_set_fsync_policy(&self, policy: FsyncPolicy) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SetFsyncPolicy(policy, tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_raft_timings(&self) -> anyhow::Result<RaftTimings> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::GetRaftTimings(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_set_raft_timing(
+        &self,
+        parameter: String,
+        value: u64,
+    ) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SetRaftTiming(parameter, value, tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_verify_wal(&self) -> anyhow::Result<WalVerification> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::VerifyWal(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_flush(&self) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::Flush(tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_cluster_leave(&self) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterLeave(tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_forward_to_shard(
+        &self,
+        to: ReplicationId,
+        op: ForwardedOp,
+    ) -> anyhow::Result<ForwardedReply> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ForwardToShard { to, op, callback: tx.into() }).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_read_index(&self) -> anyhow::Result<u64> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ReadIndex(tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_wait(
+        &self,
+        index: u64,
+        numreplicas: usize,
+        timeout: u64,
+    ) -> anyhow::Result<usize> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::Wait { index, numreplicas, timeout, callback: tx.into() })
+            .await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_check_replica_staleness(&self, max_lag: u64) -> anyhow::Result<()> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::CheckReplicaStaleness(max_lag, tx.into())).await?;
+        rx.await?
+    }
+
+    pub(crate) async fn route_cluster_nodes(&self) -> anyhow::Result<Vec<(PeerState, LinkState)>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterNodes(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_cluster_shards(&self) -> anyhow::Result<Vec<Shard>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::ClusterShards(tx.into())).await?;
         Ok(rx.await?)
     }
 
     pub(crate) async fn route_get_role(&self) -> anyhow::Result<ReplicationRole> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        self.send(ClientMessage::GetRole(tx.into())).await?;
+        self.send_client(ClientMessage::GetRole(tx.into())).await?;
         Ok(rx.await?)
     }
 
@@ -116,7 +299,23 @@ impl ClusterCommunicationManager {
         &self,
     ) -> anyhow::Result<tokio::sync::broadcast::Receiver<Topology>> {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let _ = self.send(ClientMessage::SubscribeToTopologyChange(tx.into())).await;
+        let _ = self.send_client(ClientMessage::SubscribeToTopologyChange(tx.into())).await;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_subscribe_shutdown(
+        &self,
+    ) -> anyhow::Result<tokio::sync::watch::Receiver<bool>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SubscribeToShutdown(tx.into())).await?;
+        Ok(rx.await?)
+    }
+
+    pub(crate) async fn route_subscribe_routing(
+        &self,
+    ) -> anyhow::Result<tokio::sync::watch::Receiver<RoutingTable>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_client(ClientMessage::SubscribeToRouting(tx.into())).await?;
         Ok(rx.await?)
     }
 }
@@ -0,0 +1,176 @@
+use crate::StartUpFacade;
+use crate::config::Environment;
+use crate::domains::caches::cache_objects::{CacheEntry, TypedValue};
+use crate::domains::cluster_actors::consensus::applied::Applying;
+use crate::domains::cluster_actors::forwarding::{ForwardedOp, ForwardedReply};
+use crate::domains::cluster_actors::replication::ReplicationRole;
+use crate::domains::cluster_actors::topology::{Shard, Topology};
+use crate::domains::cluster_actors::{LazyOption, SessionRequest};
+use crate::domains::peers::identifier::{PeerIdentifier, TPeerAddress};
+use crate::presentation::clients::ClientController;
+use crate::presentation::clients::controller::PendingWrite;
+use crate::presentation::clients::request::{ClientAction, ClientRequest};
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+use tracing::error;
+use uuid::Uuid;
+
+/// Sets up a [`DuvaNode`] from the typed settings of an [`Environment`], starting from their
+/// defaults rather than the command line or environment variables of the process, e.g.
+/// `DuvaNode::builder().port(6380).dir("data").append_only(true).start().await?`.
+pub struct DuvaNodeBuilder {
+    config: Environment,
+}
+
+impl DuvaNodeBuilder {
+    /// Adjusts any setting of the node, as its command line flag of the same name would.
+    pub fn configure(mut self, configure: impl FnOnce(&mut Environment)) -> Self {
+        configure(&mut self.config);
+        self
+    }
+
+    pub fn host(self, host: &str) -> Self {
+        self.configure(|config| config.host = host.to_string())
+    }
+
+    // * Peers connect on this port + 10000
+    pub fn port(self, port: u16) -> Self {
+        self.configure(|config| config.port = port)
+    }
+
+    pub fn dir(self, dir: &str) -> Self {
+        self.configure(|config| config.dir = dir.to_string())
+    }
+
+    pub fn append_only(self, append_only: bool) -> Self {
+        self.configure(|config| config.append_only = append_only)
+    }
+
+    // * Topology file the node restarts onto
+    pub fn tpp(self, tpp: &str) -> Self {
+        self.configure(|config| config.tpp = tpp.to_string())
+    }
+
+    /// Has the node follow `leader`, which has to resolve to an address.
+    pub fn replicaof(mut self, leader: &str) -> anyhow::Result<Self> {
+        self.config.seed_server = Some(PeerIdentifier(leader.bind_addr()?));
+        Ok(self)
+    }
+
+    /// Starts the cache and cluster actors of the node in this process, and has it join its
+    /// cluster and listen for peers and remote clients in the background. Only one node runs per
+    /// process, as its configuration is process-wide.
+    pub async fn start(self) -> anyhow::Result<DuvaNode> {
+        self.config.load_topology().install()?;
+        let facade = StartUpFacade::open().await?;
+        let controller = facade.client_controller();
+        tokio::spawn(async move {
+            if let Err(err) = facade.run().await {
+                error!("Embedded node stopped: {err}");
+            }
+        });
+        Ok(DuvaNode { controller, client_id: Uuid::now_v7(), last_request_id: AtomicU64::new(0) })
+    }
+}
+
+/// A duva node embedded in the process that runs it. Its methods reach the cache and cluster
+/// actors directly, without a connection or RESP in between, and are served the way commands of
+/// a client connection are: writes go through consensus, and `CLIENT PAUSE` holds them back.
+#[derive(Debug)]
+pub struct DuvaNode {
+    controller: ClientController,
+    // * Writes are deduplicated per session, as those of a client connection are
+    client_id: Uuid,
+    last_request_id: AtomicU64,
+}
+
+impl DuvaNode {
+    pub fn builder() -> DuvaNodeBuilder {
+        DuvaNodeBuilder { config: Environment::defaults() }
+    }
+
+    pub async fn get(&self, key: impl AsRef<[u8]>) -> anyhow::Result<Option<Bytes>> {
+        match self.controller.cache_manager.route_get(key).await?.value {
+            | TypedValue::Null => Ok(None),
+            | TypedValue::String(value) => Ok(Some(value)),
+            | TypedValue::List(_) => Err(anyhow::anyhow!(
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            )),
+        }
+    }
+
+    pub async fn set(&self, key: impl AsRef<[u8]>, value: impl Into<Bytes>) -> anyhow::Result<()> {
+        let key = Bytes::copy_from_slice(key.as_ref());
+        let value = value.into();
+        let set = ClientAction::Set { key: key.clone(), value: value.clone() };
+        // * A retried write its session had processed already is not applied twice
+        if let Some((index, _applying)) = self.commit("set", set).await? {
+            self.controller.cache_manager.route_set(CacheEntry::new(key, value), index).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns how many of the keys there were. Each shard leader deletes the keys it owns.
+    pub async fn del(&self, keys: &[impl AsRef<[u8]>]) -> anyhow::Result<u64> {
+        let keys: Vec<Bytes> =
+            keys.iter().map(|key| Bytes::copy_from_slice(key.as_ref())).collect();
+        self.controller.pause.wait(true).await;
+        let groups = self.controller.routing.borrow().group_keys(&keys);
+        match self.controller.run_scattered(ForwardedOp::Delete(keys), groups).await {
+            | ForwardedReply::Count(count) => Ok(count),
+            | ForwardedReply::Err(err) => Err(anyhow::anyhow!(err)),
+            | reply => Err(anyhow::anyhow!("unexpected reply to del: {reply:?}")),
+        }
+    }
+
+    /// Receives the topology, hash ring included, every time it changes.
+    pub async fn subscribe(&self) -> anyhow::Result<broadcast::Receiver<Topology>> {
+        self.controller.cluster_communication_manager.route_subscribe_topology_change().await
+    }
+
+    pub async fn role(&self) -> anyhow::Result<ReplicationRole> {
+        self.controller.cluster_communication_manager.route_get_role().await
+    }
+
+    pub async fn cluster_shards(&self) -> anyhow::Result<Vec<Shard>> {
+        self.controller.cluster_communication_manager.route_cluster_shards().await
+    }
+
+    /// Joins the cluster `peer` is part of, as `CLUSTER MEET` does.
+    pub async fn cluster_meet(&self, peer: &str, option: LazyOption) -> anyhow::Result<()> {
+        let peer = PeerIdentifier(peer.bind_addr()?);
+        self.controller.cluster_communication_manager.route_cluster_meet(peer, option).await
+    }
+
+    /// Hands leadership off to `target`, or to the most up-to-date replica when none is given.
+    pub async fn cluster_failover(&self, target: Option<&str>) -> anyhow::Result<()> {
+        let target = target.map(|peer| peer.bind_addr().map(PeerIdentifier)).transpose()?;
+        self.controller.cluster_communication_manager.route_cluster_failover(target).await
+    }
+
+    /// Shuts the node down the way `SHUTDOWN` does.
+    pub async fn shutdown(&self, save: Option<bool>) -> anyhow::Result<()> {
+        self.controller.shutdown(save).await
+    }
+
+    /// Commits the write through consensus, once `CLIENT PAUSE` lets it through. `None` means
+    /// its session had it processed already.
+    async fn commit(
+        &self,
+        command: &str,
+        action: ClientAction,
+    ) -> anyhow::Result<Option<(u64, Applying)>> {
+        self.controller.pause.wait(true).await;
+        let request_id = self.last_request_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let request = ClientRequest {
+            action,
+            command: command.to_string(),
+            session_req: SessionRequest::new(request_id, self.client_id),
+        };
+        match self.controller.request_consensus(request).await? {
+            | PendingWrite::Proposed(consensus) => consensus.committed().await,
+            | PendingWrite::Scattered(_) => Err(anyhow::anyhow!("{command} spans partitions")),
+        }
+    }
+}
@@ -1,14 +1,54 @@
+mod test_config_appendfsync;
+mod test_config_client_session_ttl;
 mod test_config_get_dir;
+mod test_config_log_filter;
+mod test_config_raft_timings;
+mod test_connection_limits;
 mod test_del;
 mod test_exists;
+mod test_hello;
 
 mod test_append;
+mod test_audit_log;
+mod test_auth;
+mod test_backup;
+mod test_batch;
+mod test_bgsave;
+mod test_cache_shards;
+mod test_cas;
+mod test_client;
+mod test_client_output_buffer_limit;
+mod test_client_pause;
+mod test_client_timeout;
+mod test_command;
+mod test_debug_bigkeys;
+mod test_debug_wal_verify;
 mod test_decr;
 mod test_decrby;
+mod test_dump_restore;
+mod test_encryption_at_rest;
+mod test_export;
+mod test_import;
 mod test_incr;
 mod test_incrby;
+mod test_info;
 mod test_keys;
+mod test_latency;
+mod test_lease;
+mod test_lock;
+mod test_maxmemory;
+mod test_monitor;
+mod test_object_introspection;
+mod test_pipeline;
+mod test_point_in_time_recovery;
 mod test_replication_info;
+mod test_save_policy;
 mod test_set_get;
+mod test_shutdown;
 mod test_snapshot_persists_and_recovers_state;
 mod test_ttl;
+mod test_unixsocket;
+mod test_unlink;
+mod test_user_quotas;
+mod test_value_compression;
+mod test_wal_replay;
@@ -0,0 +1,31 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_audit_log_records_admin_operations_with_their_client() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let path = env.dir.path().join("audit.log");
+    let env = env.with_audit_log(&path);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("CONFIG SET appendfsync everysec"), "OK");
+    assert!(h.send_and_get("CONFIG SET appendfsync sometimes").contains("invalid fsync policy"));
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // THEN
+    let audit = std::fs::read_to_string(&path)?;
+    let lines = audit.lines().collect::<Vec<_>>();
+    assert!(lines.iter().any(|line| line.contains(r#""event":"auth""#)), "{audit}");
+    let config_sets = lines
+        .iter()
+        .filter(|line| line.contains(r#""operation":"CONFIG SET appendfsync"#))
+        .collect::<Vec<_>>();
+    assert_eq!(config_sets.len(), 2, "{audit}");
+    assert!(config_sets[0].contains(r#""outcome":"ok""#));
+    assert!(config_sets[0].contains(r#""client":"127.0.0.1:"#));
+    assert!(config_sets[1].contains(r#""outcome":"error""#));
+    assert!(!audit.contains("SET foo"));
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_auth_is_required_before_other_commands() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_requirepass("secret");
+    let path = env.dir.path().join("audit.log");
+    let env = env.with_audit_log(&path);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET foo bar"), "(error) NOAUTH Authentication required.");
+    assert_eq!(
+        h.send_and_get("AUTH wrong"),
+        "(error) WRONGPASS invalid username-password pair or user is disabled."
+    );
+    assert_eq!(
+        h.send_and_get("AUTH admin secret"),
+        "(error) WRONGPASS invalid username-password pair or user is disabled."
+    );
+    assert_eq!(h.send_and_get("PING"), "(error) NOAUTH Authentication required.");
+    assert_eq!(h.send_and_get("AUTH default secret"), "OK");
+
+    // THEN
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+
+    let audit = std::fs::read_to_string(&path)?;
+    let auths =
+        audit.lines().filter(|line| line.contains(r#""operation":"AUTH""#)).collect::<Vec<_>>();
+    // * The first one comes from the readiness check of the server
+    assert_eq!(auths.len(), 4, "{audit}");
+    assert!(auths[1].contains(r#""outcome":"error""#));
+    assert!(auths[3].contains(r#""outcome":"ok""#));
+    assert!(!audit.contains("secret"));
+    Ok(())
+}
+
+#[test]
+fn test_auth_without_requirepass() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    let res = h.send_and_get("AUTH secret");
+
+    // THEN
+    assert_eq!(res, "(error) ERR AUTH called without any password configured for the default user");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    Ok(())
+}
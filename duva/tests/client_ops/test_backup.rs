@@ -0,0 +1,39 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::Duration;
+
+fn run_backup_and_restore(append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let backup = tempfile::TempDir::new()?;
+    let env = ServerEnv::default().with_append_only(append_only).with_backup_target(backup.path());
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("SET foo2 bar2"), "OK");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+
+    // WHEN - the node is lost once the backup was shipped
+    std::thread::sleep(Duration::from_millis(500));
+    let _ = process.terminate();
+    assert!(backup.path().join("MANIFEST").exists());
+    assert!(backup.path().join("dump.rdb").exists());
+
+    // THEN - a node with an empty directory is rebuilt from the backup
+    let restored_env = ServerEnv::default()
+        .with_append_only(append_only)
+        .with_backup_target(backup.path())
+        .with_restore_from_backup(true);
+    let restored = spawn_server_process(&restored_env)?;
+    let mut h = Client::new(restored.port);
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+    assert_eq!(h.send_and_get("GET foo2"), "bar2");
+
+    Ok(())
+}
+
+#[test]
+fn test_backup_and_restore() -> anyhow::Result<()> {
+    run_backup_and_restore(false)?;
+    run_backup_and_restore(true)?;
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+use std::time::Duration;
+
+fn run_batch(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, follower_p] = form_cluster([&mut env, &mut env2]);
+
+    let mut h = Client::new(leader_p.port);
+    let mut h2 = Client::new(follower_p.port);
+    assert_eq!(h.send_and_get("SET c 3"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("BATCH SET a 1 ; SET b 2 PX 10000 ; DEL c"), "(integer) 3");
+
+    // THEN
+    assert_eq!(h.send_and_get("GET a"), "1");
+    assert_eq!(h.send_and_get("GET b"), "2");
+    assert_eq!(h.send_and_get("GET c"), "(nil)");
+
+    // WHEN & THEN - operations other than SET and DEL reject the whole batch
+    assert_eq!(
+        h.send_and_get("BATCH SET a 5 ; INCR a"),
+        "(error) ERR only SET and DEL are allowed in 'batch' command"
+    );
+    assert_eq!(h.send_and_get("GET a"), "1");
+
+    // WHEN & THEN - followers apply the batch from a single log entry
+    std::thread::sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(h2.send_and_get("GET a"), "1");
+    assert_eq!(h2.send_and_get("GET b"), "2");
+    assert_eq!(h2.send_and_get("GET c"), "(nil)");
+
+    Ok(())
+}
+
+#[test]
+fn test_batch() -> anyhow::Result<()> {
+    run_batch(false)?;
+    run_batch(true)?;
+
+    Ok(())
+}
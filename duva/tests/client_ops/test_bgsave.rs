@@ -0,0 +1,43 @@
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_bgsave(env: ServerEnv) -> anyhow::Result<()> {
+    // GIVEN
+    let mut process = spawn_server_process(&env)?;
+
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("SET foo2 bar2"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("BGSAVE"), "Background saving started");
+
+    // THEN
+    let mut info = h.info("persistence");
+    for _ in 0..20 {
+        if info["rdb_bgsave_in_progress"] == "0" {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+        info = h.info("persistence");
+    }
+    assert_eq!(info["rdb_bgsave_in_progress"], "0");
+    assert_eq!(info["rdb_last_bgsave_status"], "ok");
+    assert_ne!(info["rdb_last_save_time"], "0");
+
+    // * the snapshot is loaded on restart
+    let _ = process.terminate();
+    let new_process = spawn_server_process(&env)?;
+    let mut client = Client::new(new_process.port);
+    assert_eq!(client.send_and_get_vec("KEYS *", 2).len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_bgsave() -> anyhow::Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    run_bgsave(ServerEnv::default().with_file_name(format!("test_bgsave_{timestamp}.rdb")))
+}
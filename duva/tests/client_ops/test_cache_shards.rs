@@ -0,0 +1,49 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_cache_shards_are_configurable() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_cache_shards(4, 42);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    for i in 0..50 {
+        assert_eq!(h.send_and_get(format!("SET key{i} {i}")), "OK");
+    }
+
+    // THEN
+    for i in 0..50 {
+        assert_eq!(h.send_and_get(format!("GET key{i}")), i.to_string());
+    }
+    let info = h.info("stats");
+    assert_eq!(info["cache_shards"], "4");
+    assert_eq!(info["cache_hash_seed"], "42");
+    let depths = &info["cache_shard_queue_depths"];
+    assert_eq!(depths.split(',').count(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_keys_survive_restart_with_a_different_shard_count() -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_cache_shards(3, 0);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    for i in 0..20 {
+        assert_eq!(h.send_and_get(format!("SET key{i} {i}")), "OK");
+    }
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+    let _ = process.terminate();
+
+    // WHEN
+    env = env.with_cache_shards(16, 7);
+    process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // THEN
+    for i in 0..20 {
+        assert_eq!(h.send_and_get(format!("GET key{i}")), i.to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_cas(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, follower_p] = form_cluster([&mut env, &mut env2]);
+
+    let mut h = Client::new(leader_p.port);
+    let mut h2 = Client::new(follower_p.port);
+
+    // WHEN & THEN - missing key never matches
+    assert_eq!(h.send_and_get("CAS a 1 2"), "(integer) 0");
+    assert_eq!(h.send_and_get("GET a"), "(nil)");
+
+    // WHEN & THEN - swap only when the current value matches
+    assert_eq!(h.send_and_get("SET a 1"), "OK");
+    assert_eq!(h.send_and_get("CAS a 1 2"), "(integer) 1");
+    assert_eq!(h.send_and_get("CAS a 1 3"), "(integer) 0");
+    assert_eq!(h.send_and_get("GET a"), "2");
+
+    // WHEN & THEN - wrong number of arguments
+    assert_eq!(
+        h.send_and_get("CAS a 1"),
+        "(error) ERR wrong number of arguments for 'cas' command"
+    );
+
+    // WHEN & THEN - followers reach the same outcome when applying the log
+    assert_eq!(h2.send_and_get("GET a"), "2");
+
+    Ok(())
+}
+
+#[test]
+fn test_cas() -> anyhow::Result<()> {
+    run_cas(false)?;
+    run_cas(true)?;
+
+    Ok(())
+}
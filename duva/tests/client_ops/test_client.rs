@@ -0,0 +1,62 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::Duration;
+
+/// Lines of `CLIENT LIST`, read up to the reply of an `ECHO` sent right behind it.
+fn client_list(h: &mut Client) -> anyhow::Result<Vec<String>> {
+    const END: &str = "list-end";
+    h.send(b"CLIENT LIST")?;
+    h.send(format!("ECHO {END}").as_bytes())?;
+    let mut lines = Vec::new();
+    loop {
+        match h.read()? {
+            | line if line == END => return Ok(lines),
+            | line => lines.push(line),
+        }
+    }
+}
+
+#[test]
+fn test_client_list_reports_named_connections() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut worker = Client::new(process.port);
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(worker.send_and_get("CLIENT GETNAME"), "(nil)");
+    assert_eq!(worker.send_and_get("CLIENT SETNAME worker"), "OK");
+    assert_eq!(worker.send_and_get("CLIENT GETNAME"), "worker");
+    let id = worker.send_and_get("CLIENT ID");
+
+    // THEN
+    let id = id.strip_prefix("(integer) ").unwrap();
+    let list = client_list(&mut h)?;
+    let line = list.iter().find(|line| line.contains("name=worker")).unwrap();
+    assert!(line.starts_with(&format!("id={id} addr=127.0.0.1:")), "{line}");
+    assert!(line.contains(" flags=N omem=0 cmd=client user=default"), "{line}");
+    Ok(())
+}
+
+#[test]
+fn test_client_kill_closes_the_connection() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut victim = Client::new(process.port);
+    let mut h = Client::new(process.port);
+    let id = victim.send_and_get("CLIENT ID");
+    let id = id.strip_prefix("(integer) ").unwrap();
+
+    // WHEN
+    let killed = h.send_and_get(format!("CLIENT KILL ID {id}"));
+
+    // THEN
+    assert_eq!(killed, "(integer) 1");
+    std::thread::sleep(Duration::from_millis(200));
+    let list = client_list(&mut h)?;
+    assert!(!list.iter().any(|line| line.starts_with(&format!("id={id} "))), "{list:?}");
+    assert_eq!(h.send_and_get("CLIENT KILL ID 999999"), "(integer) 0");
+    assert_eq!(h.send_and_get("CLIENT KILL 10.0.0.1:5000"), "(error) ERR No such client");
+    Ok(())
+}
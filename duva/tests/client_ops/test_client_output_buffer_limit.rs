@@ -0,0 +1,38 @@
+use crate::common::{Client, ServerEnv, session_request, spawn_server_process};
+use duva::domains::{TRead, TSerdeReadWrite};
+use duva::prelude::{AuthRequest, AuthResponse};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+#[tokio::test]
+async fn test_clients_that_stop_reading_are_closed_past_the_hard_limit() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_client_output_buffer_limit_normal("1048576 0 0");
+    let process = spawn_server_process(&env)?;
+    let mut stream = TcpStream::connect(("127.0.0.1", process.port)).await?;
+    stream.serialized_write(AuthRequest::default()).await?;
+    let response: AuthResponse = stream.deserialized_read().await?;
+    let value = "v".repeat(64 * 1024);
+    stream.write_all(&session_request(response.request_id + 1, vec!["SET", "big", &value])).await?;
+    stream.read_values().await?;
+
+    // WHEN the client sends far more reads than it is going to take replies for
+    for _ in 0..500 {
+        stream.write_all(&session_request(response.request_id + 1, vec!["GET", "big"])).await?;
+    }
+
+    // THEN
+    let mut h = Client::new(process.port);
+    let mut disconnections = String::new();
+    for _ in 0..50 {
+        disconnections = h.info("stats")["client_output_buffer_limit_disconnections"].clone();
+        if disconnections == "1" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(disconnections, "1");
+    assert!(!h.send_and_get("CLIENT LIST").contains(&stream.local_addr()?.to_string()));
+    Ok(())
+}
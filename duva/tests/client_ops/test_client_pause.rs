@@ -0,0 +1,49 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_client_pause_write_defers_writes_until_it_expires() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut admin = Client::new(process.port);
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(admin.send_and_get("CLIENT PAUSE 1000 WRITE"), "OK");
+    let started = Instant::now();
+    assert_eq!(h.send_and_get("GET foo"), "(nil)");
+    let read_after = started.elapsed();
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // THEN the read is served right away and the write once the pause is over
+    assert!(read_after < Duration::from_millis(500), "{read_after:?}");
+    assert!(started.elapsed() >= Duration::from_millis(800), "{:?}", started.elapsed());
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+    Ok(())
+}
+
+#[test]
+fn test_client_unpause_resumes_held_commands() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut admin = Client::new(process.port);
+    let mut h = Client::new(process.port);
+    assert_eq!(admin.send_and_get("CLIENT PAUSE 60000 ALL"), "OK");
+
+    // WHEN
+    let started = Instant::now();
+    let held = std::thread::spawn(move || h.send_and_get("PING"));
+    std::thread::sleep(Duration::from_millis(300));
+    assert_eq!(admin.send_and_get("CLIENT UNPAUSE"), "OK");
+
+    // THEN
+    assert_eq!(held.join().unwrap(), "PONG");
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(300) && elapsed < Duration::from_secs(10),
+        "{elapsed:?}"
+    );
+    Ok(())
+}
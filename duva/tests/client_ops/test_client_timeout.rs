@@ -0,0 +1,24 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::Duration;
+
+#[test]
+fn test_idle_clients_are_closed_but_monitors_are_kept() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_client_timeout(1);
+    let process = spawn_server_process(&env)?;
+    let mut idle = Client::new(process.port);
+    let mut monitor = Client::new(process.port);
+    let idle_id = idle.send_and_get("CLIENT ID").replace("(integer) ", "");
+    let monitor_id = monitor.send_and_get("CLIENT ID").replace("(integer) ", "");
+    assert_eq!(monitor.send_and_get("MONITOR"), "OK");
+
+    // WHEN
+    std::thread::sleep(Duration::from_millis(2500));
+
+    // THEN
+    let mut h = Client::new(process.port);
+    let list = h.send_and_get_vec("CLIENT LIST", 2);
+    assert!(!list.iter().any(|line| line.starts_with(&format!("id={idle_id} "))), "{list:?}");
+    assert!(list.iter().any(|line| line.starts_with(&format!("id={monitor_id} "))), "{list:?}");
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_command_reports_metadata_of_commands() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN & THEN - name, arity, flags and key positions, with the indentation trimmed
+    let info = h.send_and_get_vec("COMMAND INFO mget", 12);
+    assert_eq!(
+        info[..7],
+        ["1) 1) \"mget\"", "2) -2", "3) 1) readonly", "2) fast", "3) movablekeys", "4) 1", "5) -1",]
+    );
+
+    // WHEN & THEN - subcommands are reported under their container
+    let info = h.send_and_get_vec("COMMAND INFO object|freq", 10);
+    assert_eq!(info[0], "1) 1) \"object|freq\"");
+    assert_eq!(info[3..6], ["4) 2", "5) 2", "6) 1"]);
+
+    assert!(h.send_and_get("COMMAND COUNT").starts_with("(integer) "));
+
+    let docs = h.send_and_get_vec("COMMAND DOCS get", 7);
+    assert_eq!(docs[0], "1) \"get\"");
+    assert_eq!(docs[6], "6) \"GET key [LINEARIZABLE | index]\"");
+
+    // WHEN & THEN - keys the server extracts from a full command
+    let keys = h.send_and_get_vec("COMMAND GETKEYS MGET a b LINEARIZABLE", 2);
+    assert_eq!(keys, ["1) \"a\"", "2) \"b\""]);
+    assert_eq!(
+        h.send_and_get("COMMAND GETKEYS PING"),
+        "(error) ERR The command has no key arguments"
+    );
+    Ok(())
+}
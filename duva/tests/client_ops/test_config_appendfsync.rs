@@ -0,0 +1,32 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_config_appendfsync(env: ServerEnv) -> anyhow::Result<()> {
+    // GIVEN
+    let process = spawn_server_process(&env)?;
+
+    sleep(Duration::from_millis(500));
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("CONFIG get appendfsync"), "appendfsync always");
+
+    // WHEN
+    let res = h.send_and_get("CONFIG set appendfsync everysec");
+
+    // THEN
+    assert_eq!(res, "OK");
+    assert_eq!(h.send_and_get("CONFIG get appendfsync"), "appendfsync everysec");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+
+    Ok(())
+}
+
+#[test]
+fn test_config_appendfsync() -> anyhow::Result<()> {
+    for env in [ServerEnv::default(), ServerEnv::default().with_append_only(true)] {
+        run_config_appendfsync(env)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_config_client_session_ttl() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+
+    sleep(Duration::from_millis(500));
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("CONFIG get client-session-ttl"), "client-session-ttl 3600000");
+
+    // WHEN
+    assert_eq!(h.send_and_get("CONFIG set client-session-ttl 60000"), "OK");
+
+    // THEN
+    assert_eq!(h.send_and_get("CONFIG get client-session-ttl"), "client-session-ttl 60000");
+    assert!(h.send_and_get("CONFIG set client-session-ttl soon").starts_with("(error)"));
+    let info = h.info("stats");
+    assert_eq!(info["client_session_ttl"], "60000");
+    assert_eq!(info["duplicate_writes_suppressed"], "0");
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_config_log_filter() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+
+    sleep(Duration::from_millis(500));
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("CONFIG get loglevel"), "loglevel debug");
+
+    // WHEN
+    assert_eq!(h.send_and_get("CONFIG set log-filter info,cluster_actors=debug"), "OK");
+    assert_eq!(h.send_and_get("CONFIG set loglevel warn"), "OK");
+
+    // THEN
+    assert_eq!(h.send_and_get("CONFIG get loglevel"), "loglevel warn");
+    assert_eq!(h.send_and_get("CONFIG get log-filter"), "log-filter warn,cluster_actors=debug");
+    assert!(h.send_and_get("CONFIG set loglevel loud").starts_with("(error)"));
+    assert!(h.send_and_get("CONFIG set log-filter cluster_actors=loud").starts_with("(error)"));
+    assert_eq!(h.send_and_get("CONFIG get log-filter"), "log-filter warn,cluster_actors=debug");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    Ok(())
+}
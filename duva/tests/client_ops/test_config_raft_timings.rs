@@ -0,0 +1,31 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_config_raft_timings() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+
+    sleep(Duration::from_millis(500));
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("CONFIG get raft-heartbeat-interval"), "raft-heartbeat-interval 300");
+
+    // WHEN
+    assert_eq!(h.send_and_get("CONFIG set election-timeout-max 3000"), "OK");
+    assert_eq!(h.send_and_get("CONFIG set election-timeout-min 2000"), "OK");
+    assert_eq!(h.send_and_get("CONFIG set raft-heartbeat-interval 500"), "OK");
+
+    // THEN
+    assert_eq!(h.send_and_get("CONFIG get election-timeout-min"), "election-timeout-min 2000");
+    assert_eq!(h.send_and_get("CONFIG get election-timeout-max"), "election-timeout-max 3000");
+    // the minimum election timeout has to be at least twice the heartbeat interval
+    assert!(h.send_and_get("CONFIG set raft-heartbeat-interval 1500").starts_with("(error)"));
+    assert!(h.send_and_get("CONFIG set election-timeout-max 2100").starts_with("(error)"));
+    assert_eq!(h.send_and_get("CONFIG get raft-heartbeat-interval"), "raft-heartbeat-interval 500");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use duva::domains::TSerdeReadWrite;
+use duva::prelude::{AuthRequest, AuthResponse};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Reason the connection was turned away for, if it was.
+async fn handshake(port: u16) -> anyhow::Result<Option<String>> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    stream.serialized_write(AuthRequest::default()).await?;
+    let response: AuthResponse = stream.deserialized_read().await?;
+    Ok(response.rejection)
+}
+
+#[tokio::test]
+async fn test_connections_past_maxclients_are_rejected() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_connection_limits(1, 0);
+    let process = spawn_server_process(&env)?;
+    // * Lets the server notice that the client of its readiness check is gone
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("PING"), "PONG");
+
+    // WHEN
+    let rejection = handshake(process.port).await?;
+
+    // THEN
+    assert_eq!(rejection.as_deref(), Some("ERR max number of clients reached"));
+    let stats = h.info("default");
+    assert_eq!(stats["connected_clients"], "1");
+    assert_eq!(stats["maxclients"], "1");
+    assert_eq!(stats["rejected_connections"], "1");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connections_past_the_rate_limit_are_rejected() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_connection_limits(100, 2);
+    let process = spawn_server_process(&env)?;
+    // * Starts a new window past the connections of the readiness check
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    // WHEN
+    let mut rejections = vec![];
+    for _ in 0..3 {
+        rejections.push(handshake(process.port).await?);
+    }
+
+    // THEN
+    assert_eq!(
+        rejections,
+        [None, None, Some("ERR too many connections from 127.0.0.1, try again later".into())]
+    );
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(handshake(process.port).await?, None);
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_debug_bigkeys() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get(format!("SET big {}", "v".repeat(1000))), "OK");
+    assert_eq!(h.send_and_get("SET small 1"), "OK");
+    assert_eq!(h.send_and_get("SET medium 0123456789"), "OK");
+
+    // WHEN
+    let report = h.send_and_get_vec("DEBUG BIGKEYS", 8);
+
+    // THEN
+    assert_eq!(report[0], "keys_scanned:3");
+    assert_eq!(report[1], "string_keys:3");
+    assert_eq!(report[3], "list_keys:0");
+    assert!(report[5].starts_with("biggest_string:big ("), "{}", report[5]);
+    assert!(report[6].starts_with("biggest_string:medium ("), "{}", report[6]);
+    assert!(report[7].starts_with("biggest_string:small ("), "{}", report[7]);
+
+    // * The node keeps serving after the scan
+    assert_eq!(h.send_and_get("GET small"), "1");
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_debug_wal_verify() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo 1"), "OK");
+    assert_eq!(h.send_and_get("SET bar 2"), "OK");
+
+    // WHEN
+    let report = h.send_and_get_vec("DEBUG WAL VERIFY", 6);
+
+    // THEN
+    assert_eq!(report[0], "wal_status:ok");
+    assert_eq!(report[1], "wal_segments:1");
+    assert_eq!(report[2], "wal_entries:2");
+    assert_eq!(report[5], "wal_inconsistency:");
+
+    // * The node keeps serving once the log turns out to be corrupted
+    let segment = env.dir.path().join("segment_0.oplog");
+    let mut buf = std::fs::read(&segment)?;
+    *buf.last_mut().unwrap() ^= 0xff;
+    std::fs::write(&segment, buf)?;
+
+    let report = h.send_and_get_vec("DEBUG WAL VERIFY", 6);
+    assert_eq!(report[0], "wal_status:inconsistent");
+    assert_eq!(report[2], "wal_entries:1");
+    assert!(report[5].starts_with("wal_inconsistency:segment_0.oplog at byte "), "{}", report[5]);
+    assert_eq!(h.send_and_get("GET foo"), "1");
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+use std::time::Duration;
+
+fn run_dump_restore(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, follower_p] = form_cluster([&mut env, &mut env2]);
+
+    let mut h = Client::new(leader_p.port);
+    let mut h2 = Client::new(follower_p.port);
+    assert_eq!(h.send_and_get("SET a hello"), "OK");
+
+    // WHEN
+    let payload = h.send_and_get("DUMP a");
+
+    // THEN - missing keys have nothing to dump
+    assert_eq!(h.send_and_get("DUMP missing"), "(nil)");
+
+    // WHEN & THEN - restore under a new key, with a TTL
+    assert_eq!(h.send_and_get(format!("RESTORE b 100000 {payload}")), "OK");
+    assert_eq!(h.send_and_get("GET b"), "hello");
+    assert!(h.send_and_get("TTL b").starts_with("(integer) 9"));
+
+    // WHEN & THEN - an existing key is only overwritten with REPLACE
+    assert_eq!(h.send_and_get("SET c other"), "OK");
+    assert_eq!(
+        h.send_and_get(format!("RESTORE c 0 {payload}")),
+        "(error) BUSYKEY Target key name already exists."
+    );
+    assert_eq!(h.send_and_get("GET c"), "other");
+    assert_eq!(h.send_and_get(format!("RESTORE c 0 {payload} REPLACE")), "OK");
+    assert_eq!(h.send_and_get("GET c"), "hello");
+
+    // WHEN & THEN - corrupted payloads are refused
+    let corrupted = format!("{}00", &payload[..payload.len() - 2]);
+    assert_eq!(
+        h.send_and_get(format!("RESTORE d 0 {corrupted}")),
+        "ERR DUMP payload version or checksum are wrong"
+    );
+
+    // WHEN & THEN - followers apply restores through the log
+    std::thread::sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(h2.send_and_get("GET b"), "hello");
+    assert_eq!(h2.send_and_get("GET c"), "hello");
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_restore() -> anyhow::Result<()> {
+    run_dump_restore(false)?;
+    run_dump_restore(true)?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+const OLD_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+const NEW_KEY: &str = "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100";
+
+fn files_contain(env: &ServerEnv, needle: &[u8]) -> bool {
+    std::fs::read_dir(env.dir.path()).unwrap().any(|entry| {
+        let path = entry.unwrap().path();
+        path.is_file()
+            && std::fs::read(path).unwrap().windows(needle.len()).any(|window| window == needle)
+    })
+}
+
+#[test]
+fn test_snapshot_and_wal_are_encrypted_at_rest() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true).with_encryption_keys(OLD_KEY);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET snapshotted plaintext-in-snapshot"), "OK");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+    assert_eq!(h.send_and_get("SET logged plaintext-in-wal"), "OK");
+    let log_index: u64 = h.info("replication")["high_watermark"].parse()?;
+    let _ = process.terminate();
+
+    // THEN
+    assert!(!files_contain(&env, b"plaintext-in-snapshot"));
+    assert!(!files_contain(&env, b"plaintext-in-wal"));
+
+    // * A rotated key seals new data while the previous one still opens what is on disk.
+    // * Recovering to the last entry replays it from the WAL
+    let env =
+        env.with_encryption_keys(format!("{NEW_KEY},{OLD_KEY}")).with_recover_to_index(log_index);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("GET snapshotted"), "plaintext-in-snapshot");
+    assert_eq!(h.send_and_get("GET logged"), "plaintext-in-wal");
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_export(env: ServerEnv) -> anyhow::Result<()> {
+    // GIVEN
+    let path = env.dir.path().join("export.rdb");
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("SET baz qux px 600000"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get(format!("EXPORT {}", path.display())), "exported:2");
+
+    // THEN
+    let rdb = std::fs::read(&path)?;
+    assert!(rdb.starts_with(b"REDIS0011"));
+
+    let other_env = ServerEnv::default();
+    let other = spawn_server_process(&other_env)?;
+    let mut other_h = Client::new(other.port);
+    let report = other_h.send_and_get(format!("IMPORT {}", path.display()));
+    assert_eq!(report, "imported:2");
+    assert_eq!(other_h.send_and_get("GET foo"), "bar");
+    assert_eq!(other_h.send_and_get("GET baz"), "qux");
+
+    Ok(())
+}
+
+#[test]
+fn test_export() -> anyhow::Result<()> {
+    for env in [ServerEnv::default(), ServerEnv::default().with_append_only(true)] {
+        run_export(env)?;
+    }
+
+    Ok(())
+}
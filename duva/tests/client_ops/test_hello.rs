@@ -0,0 +1,61 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_hello(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(with_append_only);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN & THEN - RESP2 connections get the flattened map
+    let res = h.send_and_get_vec("HELLO", 5);
+    assert_eq!(res[0], "1# \"server\" => \"duva\"");
+    assert_eq!(res[2], "3# \"proto\" => (integer) 2");
+
+    // WHEN & THEN - negotiating RESP3
+    let res = h.send_and_get_vec("HELLO 3", 5);
+    assert_eq!(res[2], "3# \"proto\" => (integer) 3");
+    assert_eq!(res[4], "5# \"role\" => \"leader\"");
+
+    // WHEN & THEN - the negotiated version sticks to the connection
+    let res = h.send_and_get_vec("HELLO", 5);
+    assert_eq!(res[2], "3# \"proto\" => (integer) 3");
+
+    // WHEN & THEN - unsupported version
+    assert_eq!(h.send_and_get("HELLO 4"), "(error) NOPROTO unsupported protocol version");
+
+    Ok(())
+}
+
+#[test]
+fn test_hello() -> anyhow::Result<()> {
+    run_hello(false)?;
+    run_hello(true)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_hello_authenticates_with_the_auth_clause() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_requirepass("secret");
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN & THEN - HELLO alone does not get past requirepass
+    assert_eq!(h.send_and_get("HELLO 3"), "(error) NOAUTH Authentication required.");
+    assert_eq!(
+        h.send_and_get("HELLO 3 AUTH default wrong"),
+        "(error) WRONGPASS invalid username-password pair or user is disabled."
+    );
+    assert_eq!(
+        h.send_and_get("HELLO 4 AUTH default secret"),
+        "(error) NOPROTO unsupported protocol version"
+    );
+    assert_eq!(h.send_and_get("GET foo"), "(error) NOAUTH Authentication required.");
+
+    // WHEN & THEN - authenticating and negotiating at once
+    let res = h.send_and_get_vec("HELLO 3 AUTH default secret", 5);
+    assert_eq!(res[2], "3# \"proto\" => (integer) 3");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    Ok(())
+}
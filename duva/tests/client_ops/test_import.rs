@@ -0,0 +1,41 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+// * A dump as Redis writes it: a string, a list, a hash and an integer encoded string
+fn redis_dump() -> Vec<u8> {
+    let mut rdb = b"REDIS0011\xFA\x09redis-ver\x057.2.4\xFE\x00\xFB\x04\x00".to_vec();
+    rdb.extend_from_slice(b"\x00\x03foo\x03bar");
+    rdb.extend_from_slice(b"\x01\x04list\x02\x01a\x01b");
+    rdb.extend_from_slice(b"\x04\x04hash\x01\x01f\x01v");
+    rdb.extend_from_slice(b"\x00\x03num\xC0\x2A");
+    rdb.push(0xFF);
+    rdb.extend_from_slice(&[0; 8]);
+    rdb
+}
+
+fn run_import(env: ServerEnv) -> anyhow::Result<()> {
+    // GIVEN
+    let path = env.dir.path().join("redis.rdb");
+    std::fs::write(&path, redis_dump())?;
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    let report = h.send_and_get_vec(format!("IMPORT {}", path.display()), 2);
+
+    // THEN
+    assert_eq!(report, vec!["imported:3", "skipped_hash:1"]);
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+    assert_eq!(h.send_and_get("GET num"), "42");
+    assert_eq!(h.send_and_get_vec("KEYS *", 3).len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_import() -> anyhow::Result<()> {
+    for env in [ServerEnv::default(), ServerEnv::default().with_append_only(true)] {
+        run_import(env)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_info_reports_every_section_by_default() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    let info = h.info("default");
+
+    // THEN
+    assert_eq!(info["tcp_port"], env.port.to_string());
+    assert_eq!(info["redis_mode"], "cluster");
+    assert_eq!(info["connected_clients"], "1");
+    assert!(info.contains_key("used_memory_human"));
+    assert_eq!(info["aof_enabled"], "1");
+    assert!(info["total_connections_received"].parse::<u64>()? >= 1);
+    assert!(info["total_commands_processed"].parse::<u64>()? >= 2);
+    assert_eq!(info["role"], "leader");
+    assert_eq!(info["cluster_enabled"], "1");
+    Ok(())
+}
+
+#[test]
+fn test_info_section_headers() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    let res = h.send_and_get_vec("INFO clients", 4);
+
+    // THEN
+    assert_eq!(
+        res,
+        vec!["# Clients", "connected_clients:1", "maxclients:10000", "connection_rate_limit:0"]
+    );
+    assert!(h.info("keyspace").is_empty());
+    Ok(())
+}
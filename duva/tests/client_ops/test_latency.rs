@@ -0,0 +1,34 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_latency_histograms_split_commands_into_phases() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    for i in 0..5 {
+        assert_eq!(h.send_and_get(format!("SET key{i} {i}")), "OK");
+    }
+    assert_eq!(h.send_and_get("GET key0"), "0");
+
+    // THEN
+    let set = h.send_and_get_vec("LATENCY HISTOGRAM set", 4);
+    for (line, phase) in set.iter().zip(["parse", "consensus", "apply", "reply"]) {
+        assert!(line.starts_with(&format!("SET {phase} calls=5 p50=")), "{set:?}");
+    }
+    let get = h.send_and_get_vec("LATENCY HISTOGRAM GET", 3);
+    for (line, phase) in get.iter().zip(["parse", "apply", "reply"]) {
+        assert!(line.starts_with(&format!("GET {phase} calls=1 p50=")), "{get:?}");
+    }
+
+    let info = h.info("latencystats");
+    assert!(info["latency_percentiles_usec_set_consensus"].starts_with("p50="));
+
+    // * LATENCY itself and the INFO and ECHO sent above have samples too
+    let reset: u64 = h.send_and_get("LATENCY RESET").strip_prefix("(integer) ").unwrap().parse()?;
+    assert!(reset >= 2);
+    assert_eq!(h.send_and_get("LATENCY HISTOGRAM SET"), "");
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, form_cluster, spawn_server_process};
+
+fn lease_id(res: String) -> u64 {
+    res.trim_start_matches("(integer) ").parse().unwrap()
+}
+
+fn run_lease(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, follower_p] = form_cluster([&mut env, &mut env2]);
+
+    let mut h = Client::new(leader_p.port);
+    let mut h2 = Client::new(follower_p.port);
+
+    assert_eq!(h.send_and_get("SET a 1"), "OK");
+    assert_eq!(h.send_and_get("SET b 2"), "OK");
+    assert_eq!(h.send_and_get("SET c 3"), "OK");
+
+    // WHEN - keys are attached to a short lease
+    let short_lease = lease_id(h.send_and_get("LEASE GRANT 500"));
+    assert_eq!(h.send_and_get(format!("LEASE ATTACH {short_lease} a b")), "(integer) 1");
+    assert_eq!(h.send_and_get("LEASE ATTACH 9999 c"), "(integer) 0");
+
+    // THEN - attached keys are deleted cluster-wide once the lease expires
+    std::thread::sleep(std::time::Duration::from_millis(800 + LEADER_HEARTBEAT_INTERVAL_MAX));
+    assert_eq!(h.send_and_get("GET a"), "(nil)");
+    assert_eq!(h2.send_and_get("GET b"), "(nil)");
+    assert_eq!(h2.send_and_get("GET c"), "3");
+    assert_eq!(h.send_and_get(format!("LEASE KEEPALIVE {short_lease}")), "(integer) 0");
+
+    // WHEN - a lease is revoked explicitly
+    let lease = lease_id(h.send_and_get("LEASE GRANT 60000"));
+    assert_eq!(h.send_and_get(format!("LEASE KEEPALIVE {lease}")), "(integer) 1");
+    assert_eq!(h.send_and_get(format!("LEASE ATTACH {lease} c")), "(integer) 1");
+
+    // THEN
+    assert_eq!(h.send_and_get(format!("LEASE REVOKE {lease}")), "(integer) 1");
+    assert_eq!(h.send_and_get("GET c"), "(nil)");
+
+    Ok(())
+}
+
+#[test]
+fn test_lease() -> anyhow::Result<()> {
+    run_lease(false)?;
+    run_lease(true)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_lease_survives_restart_from_snapshot() -> anyhow::Result<()> {
+    // GIVEN
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let env = ServerEnv::default().with_file_name(format!("test_lease_restart_{timestamp}.rdb"));
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    assert_eq!(h.send_and_get("SET a 1"), "OK");
+    let lease = lease_id(h.send_and_get("LEASE GRANT 5000"));
+    assert_eq!(h.send_and_get(format!("LEASE ATTACH {lease} a")), "(integer) 1");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+
+    // WHEN
+    let _ = process.terminate();
+    let new_process = spawn_server_process(&env)?;
+    let mut h = Client::new(new_process.port);
+
+    // THEN - the lease is still known and still deletes its keys when it expires
+    assert_eq!(h.send_and_get(format!("LEASE KEEPALIVE {lease}")), "(integer) 1");
+    assert_eq!(h.send_and_get("GET a"), "1");
+    std::thread::sleep(std::time::Duration::from_millis(5500 + LEADER_HEARTBEAT_INTERVAL_MAX));
+    assert_eq!(h.send_and_get("GET a"), "(nil)");
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn fencing_token(res: String) -> u64 {
+    res.trim_start_matches("(integer) ").parse().unwrap()
+}
+
+fn run_lock(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, follower_p] = form_cluster([&mut env, &mut env2]);
+
+    let mut h = Client::new(leader_p.port);
+    let mut h2 = Client::new(follower_p.port);
+
+    // WHEN & THEN - the first caller gets a fencing token, the second one is refused
+    let token = fencing_token(h.send_and_get("LOCK l 5000"));
+    assert!(token > 0);
+    assert_eq!(h.send_and_get("LOCK l 5000"), "(integer) 0");
+
+    // WHEN & THEN - only the holder of the token can release the lock
+    assert_eq!(h.send_and_get(format!("UNLOCK l {}", token + 100)), "(integer) 0");
+    assert_eq!(h.send_and_get(format!("UNLOCK l {token}")), "(integer) 1");
+
+    // WHEN & THEN - re-acquiring hands out a larger token
+    let next_token = fencing_token(h.send_and_get("LOCK l 5000"));
+    assert!(next_token > token);
+
+    // WHEN & THEN - lock is visible on followers once the commit is propagated
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(h2.send_and_get("GET l"), next_token.to_string());
+
+    // WHEN - lease runs out
+    let short_lived = fencing_token(h.send_and_get("LOCK s 300"));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // THEN - the leader released it through the log, so no replica keeps the stale token
+    assert_eq!(h.send_and_get("GET s"), "(nil)");
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(h2.send_and_get("GET s"), "(nil)");
+    assert!(fencing_token(h.send_and_get("LOCK s 5000")) > short_lived);
+
+    Ok(())
+}
+
+#[test]
+fn test_lock() -> anyhow::Result<()> {
+    run_lock(false)?;
+    run_lock(true)?;
+
+    Ok(())
+}
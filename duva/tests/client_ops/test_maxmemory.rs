@@ -0,0 +1,55 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn used_memory(h: &mut Client) -> u64 {
+    h.info("memory")["used_memory"].parse().unwrap()
+}
+
+#[test]
+fn test_noeviction_refuses_writes_over_maxmemory() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_maxmemory(10, "noeviction");
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET key0 0123456789abcdef"), "OK");
+
+    // WHEN
+    let res = h.send_and_get("SET key1 value");
+
+    // THEN
+    assert_eq!(res, "(error) OOM command not allowed when used memory > 'maxmemory'");
+    assert_eq!(h.send_and_get("GET key1"), "(nil)");
+
+    // * Deletes free memory for later writes
+    assert_eq!(h.send_and_get("DEL key0"), "(integer) 1");
+    assert_eq!(h.send_and_get("SET key1 value"), "OK");
+    Ok(())
+}
+
+#[test]
+fn test_allkeys_lru_evicts_least_recently_used_keys() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_maxmemory(1000, "allkeys-lru");
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    let value = "v".repeat(50);
+
+    // WHEN
+    for i in 0..100 {
+        assert_eq!(h.send_and_get(format!("SET key{i} {value}")), "OK");
+    }
+    sleep(Duration::from_secs(1));
+
+    // THEN
+    assert!(used_memory(&mut h) <= 1000);
+    assert_eq!(h.send_and_get("GET key0"), "(nil)");
+    assert_eq!(h.send_and_get("GET key99"), value);
+
+    let info = h.info("memory");
+    assert_eq!(info["maxmemory"], "1000");
+    assert_eq!(info["maxmemory_policy"], "allkeys-lru");
+    let evicted: u64 = info["evicted_keys"].parse()?;
+    assert!(evicted > 0);
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_monitor_streams_commands_of_other_clients() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut monitor = Client::new(process.port);
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(monitor.send_and_get("MONITOR"), "OK");
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+
+    // THEN
+    let set = monitor.read()?;
+    assert!(set.ends_with(r#"] "SET" "foo" "bar""#), "{set}");
+    assert!(set.contains("[127.0.0.1:"), "{set}");
+    let timestamp: f64 = set.split_once(' ').unwrap().0.parse()?;
+    assert!(timestamp > 0.0);
+    assert!(monitor.read()?.ends_with(r#"] "GET" "foo""#));
+    Ok(())
+}
+
+#[test]
+fn test_monitor_only_streams_authenticated_commands_with_credentials_redacted() -> anyhow::Result<()>
+{
+    // GIVEN
+    let env = ServerEnv::default().with_requirepass("secret");
+    let process = spawn_server_process(&env)?;
+    let mut monitor = Client::new(process.port);
+    let mut h = Client::new(process.port);
+    assert_eq!(monitor.send_and_get("AUTH secret"), "OK");
+    assert_eq!(monitor.send_and_get("MONITOR"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET foo bar"), "(error) NOAUTH Authentication required.");
+    assert_eq!(
+        h.send_and_get("HELLO 3 AUTH default wrong"),
+        "(error) WRONGPASS invalid username-password pair or user is disabled."
+    );
+    h.send_and_get_vec("HELLO 3 AUTH default secret", 5);
+    assert_eq!(h.send_and_get("GET foo"), "(nil)");
+
+    // THEN
+    let hello = monitor.read()?;
+    assert!(hello.ends_with(r#"] "HELLO" "3" "AUTH" "(redacted)" "(redacted)""#), "{hello}");
+    assert!(monitor.read()?.ends_with(r#"] "GET" "foo""#));
+    Ok(())
+}
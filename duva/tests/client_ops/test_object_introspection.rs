@@ -0,0 +1,65 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::thread::sleep;
+use std::time::Duration;
+
+#[test]
+fn test_object_idletime_and_freq() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET key value"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("GET key"), "value");
+    assert_eq!(h.send_and_get("GET key"), "value");
+    sleep(Duration::from_millis(1100));
+
+    // THEN - the write and both reads are counted, the OBJECT calls are not
+    assert_eq!(h.send_and_get("OBJECT FREQ key"), "(integer) 3");
+    assert_eq!(h.send_and_get("OBJECT FREQ key"), "(integer) 3");
+    let idle = h.send_and_get("OBJECT IDLETIME key");
+    assert!(idle.strip_prefix("(integer) ").unwrap().parse::<u64>()? >= 1);
+    assert_eq!(h.send_and_get("OBJECT IDLETIME missing"), "(nil)");
+    assert_eq!(h.send_and_get("OBJECT FREQ missing"), "(nil)");
+    Ok(())
+}
+
+#[test]
+fn test_memory_evictionpool_lists_keys_in_eviction_order() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_maxmemory(1_000_000, "allkeys-lru");
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    for key in ["a", "b", "c"] {
+        assert_eq!(h.send_and_get(format!("SET {key} value")), "OK");
+    }
+
+    // WHEN
+    assert_eq!(h.send_and_get("GET a"), "value");
+
+    // THEN
+    let pool = h.send_and_get_vec("MEMORY EVICTIONPOOL", 5);
+    assert_eq!(pool[0], "maxmemory_policy:allkeys-lru");
+    assert_eq!(pool[1], "pool_size:3");
+    assert!(pool[2].starts_with("candidate:b ") && pool[2].ends_with(" freq=1 ttl=-1"));
+    assert!(pool[3].starts_with("candidate:c ") && pool[3].ends_with(" freq=1 ttl=-1"));
+    assert!(pool[4].starts_with("candidate:a ") && pool[4].ends_with(" freq=2 ttl=-1"));
+    Ok(())
+}
+
+#[test]
+fn test_memory_evictionpool_is_empty_without_eviction() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get("SET key value"), "OK");
+
+    // THEN
+    let pool = h.send_and_get_vec("MEMORY EVICTIONPOOL", 2);
+    assert_eq!(pool, vec!["maxmemory_policy:noeviction", "pool_size:0"]);
+    Ok(())
+}
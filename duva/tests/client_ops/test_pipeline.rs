@@ -0,0 +1,38 @@
+use crate::common::{ServerEnv, session_request, spawn_server_process};
+use duva::domains::query_io::QueryIO;
+use duva::domains::{TRead, TSerdeReadWrite};
+use duva::prelude::{AuthRequest, AuthResponse};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+#[tokio::test]
+async fn test_a_request_that_fails_to_parse_is_answered_in_its_place_in_the_pipeline()
+-> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut stream = TcpStream::connect(("127.0.0.1", process.port)).await?;
+    stream.serialized_write(AuthRequest::default()).await?;
+    let response: AuthResponse = stream.deserialized_read().await?;
+
+    // WHEN the three requests are sent in a single write
+    let mut pipeline = Vec::new();
+    pipeline.extend_from_slice(&session_request(response.request_id + 1, vec!["SET", "a", "1"]));
+    pipeline.extend_from_slice(&session_request(response.request_id + 2, vec!["BOGUS"]));
+    pipeline.extend_from_slice(&session_request(response.request_id + 3, vec!["GET", "a"]));
+    stream.write_all(&pipeline).await?;
+
+    // THEN
+    let mut replies = Vec::new();
+    while replies.len() < 3 {
+        replies.extend(stream.read_values().await?);
+    }
+    assert_eq!(replies.len(), 3);
+    assert!(matches!(&replies[0], QueryIO::SimpleString(_)), "{replies:?}");
+    assert!(
+        matches!(&replies[1], QueryIO::Err(err) if String::from_utf8_lossy(err).contains("unknown command 'BOGUS'")),
+        "{replies:?}"
+    );
+    assert_eq!(replies[2], QueryIO::BulkString("1".into()));
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn high_watermark(h: &mut Client) -> u64 {
+    h.info("replication")["high_watermark"].parse().unwrap()
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+// * Writes foo=1 covered by the snapshot, then bar=2 and later bar=3 and baz=4 to the WAL only.
+// * Returns the log index and time right after bar=2
+fn write_history(env: &ServerEnv) -> anyhow::Result<(u64, u64)> {
+    let mut process = spawn_server_process(env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo 1"), "OK");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+    assert_eq!(h.send_and_get("SET bar 2"), "OK");
+    let log_index = high_watermark(&mut h);
+    std::thread::sleep(Duration::from_millis(50));
+    let time = unix_millis();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(h.send_and_get("SET bar 3"), "OK");
+    assert_eq!(h.send_and_get("SET baz 4"), "OK");
+    let _ = process.terminate();
+    Ok((log_index, time))
+}
+
+fn assert_recovered(env: &ServerEnv, log_index: u64) -> anyhow::Result<()> {
+    let process = spawn_server_process(env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("GET foo"), "1");
+    assert_eq!(h.send_and_get("GET bar"), "2");
+    assert_eq!(h.send_and_get("GET baz"), "(nil)");
+    assert_eq!(high_watermark(&mut h), log_index);
+    Ok(())
+}
+
+#[test]
+fn test_recover_to_log_index() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let (log_index, _) = write_history(&env)?;
+
+    // WHEN
+    let env = env.with_recover_to_index(log_index);
+
+    // THEN
+    assert_recovered(&env, log_index)?;
+
+    // * The recovered state is kept once the node restarts normally
+    let env = ServerEnv { recover_to_index: None, ..env };
+    assert_recovered(&env, log_index)
+}
+
+#[test]
+fn test_recover_to_time() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let (log_index, time) = write_history(&env)?;
+
+    // WHEN
+    let env = env.with_recover_to_time(time);
+
+    // THEN
+    assert_recovered(&env, log_index)
+}
@@ -10,13 +10,15 @@ fn run_replication_info(env: ServerEnv) -> anyhow::Result<()> {
     let mut h = Client::new(process.port);
 
     // WHEN
-    let res = h.send_and_get_vec("INFO replication", 4);
+    let res = h.info("replication");
 
     // THEN
-    assert_eq!(res[0], "role:leader");
-    assert!(res[1].starts_with("leader_repl_id:"));
-    assert_eq!(res[2], "high_watermark:0");
-    assert_eq!(res[3], format!("self_identifier:127.0.0.1:{}", env.port));
+    assert_eq!(res["role"], "leader");
+    assert_eq!(res["master_replid"], res["leader_repl_id"]);
+    assert_eq!(res["high_watermark"], "0");
+    assert_eq!(res["master_repl_offset"], "0");
+    assert_eq!(res["self_identifier"], format!("127.0.0.1:{}", env.port));
+    assert_eq!(res["connected_slaves"], "0");
 
     Ok(())
 }
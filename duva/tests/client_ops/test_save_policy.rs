@@ -0,0 +1,38 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn run_snapshot_taken_by_save_policy(env: ServerEnv, writes: usize) -> anyhow::Result<()> {
+    // GIVEN
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    for i in 0..writes {
+        assert_eq!(h.send_and_get(format!("SET key{i} {i}")), "OK");
+    }
+
+    // WHEN - compaction runs on a stable high water mark
+    sleep(Duration::from_secs(3));
+    let _ = process.terminate();
+
+    // THEN - the writes come back from the snapshot rather than the WAL
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    for i in 0..writes {
+        assert_eq!(h.send_and_get(format!("GET key{i}")), i.to_string());
+    }
+    let info = h.info("persistence");
+    assert_eq!(info["wal_replayed_entries"], "0", "{info:?}");
+    Ok(())
+}
+
+#[test]
+fn test_save_policy_snapshots_on_log_growth() -> anyhow::Result<()> {
+    let env = ServerEnv::default().with_append_only(true).with_save("3 0");
+    run_snapshot_taken_by_save_policy(env, 3)
+}
+
+#[test]
+fn test_save_policy_snapshots_after_elapsed_time() -> anyhow::Result<()> {
+    let env = ServerEnv::default().with_append_only(true).with_save("0 1");
+    run_snapshot_taken_by_save_policy(env, 1)
+}
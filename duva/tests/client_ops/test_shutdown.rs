@@ -0,0 +1,68 @@
+use crate::common::{Client, ServerEnv, TestProcessChild, spawn_server_process};
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+fn wait_for_exit(process: &mut TestProcessChild) -> anyhow::Result<ExitStatus> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if let Some(status) = process.try_wait()? {
+            return Ok(status);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    anyhow::bail!("server did not exit")
+}
+
+#[test]
+fn test_shutdown_saves_and_stops_the_node() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("SHUTDOWN SAVE"), "OK");
+
+    // THEN
+    assert!(wait_for_exit(&mut process)?.success());
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+    Ok(())
+}
+
+#[test]
+fn test_shutdown_rejects_unknown_modes() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    let res = h.send_and_get("SHUTDOWN NOW");
+
+    // THEN
+    assert_eq!(res, "(error) ERR syntax error");
+    assert_eq!(h.send_and_get("PING"), "PONG");
+    Ok(())
+}
+
+#[test]
+fn test_sigterm_flushes_the_wal_and_stops_the_node() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    Command::new("kill").args(["-TERM", &process.id().to_string()]).status()?;
+
+    // THEN
+    assert!(wait_for_exit(&mut process)?.success());
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("GET foo"), "bar");
+    Ok(())
+}
@@ -16,7 +16,7 @@ fn run_snapshot_persists_and_recovers_state(env: ServerEnv) -> anyhow::Result<()
     assert_eq!(h.send_and_get_vec("KEYS *", 2), vec!["1) \"foo2\"", "2) \"foo\""]);
 
     // pre load replication info for comparison
-    let old_info = h.send_and_get_vec("INFO replication", 4);
+    let old_info = h.info("replication");
 
     // WHEN
     assert_eq!(h.send_and_get("SAVE"), "(nil)");
@@ -31,7 +31,7 @@ fn run_snapshot_persists_and_recovers_state(env: ServerEnv) -> anyhow::Result<()
     assert_eq!(client.send_and_get_vec("KEYS *", 2), vec!["1) \"foo2\"", "2) \"foo\""]);
 
     // replication info
-    let new_info = client.send_and_get_vec("INFO replication", 4);
+    let new_info = client.info("replication");
 
     // THEN
     assert_eq!(old_info, new_info);
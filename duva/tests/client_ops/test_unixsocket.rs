@@ -0,0 +1,28 @@
+use crate::common::{Client, ServerEnv, session_request, spawn_server_process};
+use duva::domains::query_io::QueryIO;
+use duva::domains::{TRead, TSerdeReadWrite};
+use duva::prelude::{AuthRequest, AuthResponse};
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+
+#[tokio::test]
+async fn test_clients_connect_over_the_unix_socket() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_unixsocket();
+    let process = spawn_server_process(&env)?;
+    let path = env.unixsocket.clone().unwrap();
+
+    // WHEN
+    let mut stream = UnixStream::connect(&path).await?;
+    stream.serialized_write(AuthRequest::default()).await?;
+    let response: AuthResponse = stream.deserialized_read().await?;
+    stream.write_all(&session_request(response.request_id + 1, vec!["PING"])).await?;
+
+    // THEN
+    assert_eq!(response.rejection, None);
+    assert_eq!(stream.read_values().await?, vec![QueryIO::SimpleString("PONG".into())]);
+    let mut h = Client::new(process.port);
+    let list = h.send_and_get("CLIENT LIST");
+    assert!(list.contains(&format!("addr={}:0 ", path.display())), "{list}");
+    Ok(())
+}
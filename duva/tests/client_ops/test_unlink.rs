@@ -0,0 +1,28 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_unlink() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET a b"), "OK");
+    assert_eq!(h.send_and_get("SET c d"), "OK");
+    assert_eq!(h.send_and_get("SET e f"), "OK");
+
+    // WHEN
+    assert_eq!(h.send_and_get("UNLINK a c missing"), "(integer) 2");
+
+    // THEN
+    assert_eq!(h.send_and_get("GET a"), "(nil)");
+    assert_eq!(h.send_and_get("GET c"), "(nil)");
+    assert_eq!(h.send_and_get("GET e"), "f");
+
+    // * The unlink is replayed from the log on restart
+    let _ = process.terminate();
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("GET a"), "(nil)");
+    assert_eq!(h.send_and_get("GET e"), "f");
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_commands_past_the_user_quota_are_delayed() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_user_quotas(10, 0);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // WHEN
+    let started = Instant::now();
+    for _ in 0..30 {
+        assert_eq!(h.send_and_get("PING"), "PONG");
+    }
+
+    // THEN the connection is slowed down to the quota rather than closed
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    Ok(())
+}
+
+#[test]
+fn test_replies_past_the_user_output_quota_are_delayed() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_user_quotas(0, 200);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    let value = "v".repeat(100);
+    assert_eq!(h.send_and_get(format!("SET foo {value}")), "OK");
+
+    // WHEN
+    let started = Instant::now();
+    for _ in 0..6 {
+        assert_eq!(h.send_and_get("GET foo"), value);
+    }
+
+    // THEN
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn memory_stat(h: &mut Client, name: &str) -> String {
+    let stats = h.send_and_get_vec("MEMORY STATS", 8);
+    let line = stats.iter().find(|line| line.starts_with(&format!("{name}:"))).unwrap();
+    line[name.len() + 1..].to_string()
+}
+
+#[test]
+fn test_large_values_are_compressed_in_memory() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_value_compression("lz4", 100);
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    let value = "duva".repeat(1000);
+
+    // WHEN
+    assert_eq!(h.send_and_get(format!("SET large {value}")), "OK");
+    assert_eq!(h.send_and_get("SET small value"), "OK");
+
+    // THEN
+    assert_eq!(h.send_and_get("GET large"), value);
+    assert_eq!(h.send_and_get("GET small"), "value");
+    assert_eq!(memory_stat(&mut h, "value_compression"), "lz4");
+    assert_eq!(memory_stat(&mut h, "compressed_keys"), "1");
+    assert_eq!(memory_stat(&mut h, "compressed_raw_bytes"), value.len().to_string());
+    assert!(memory_stat(&mut h, "compression_ratio").parse::<f64>()? > 10.0);
+    assert!(memory_stat(&mut h, "used_memory").parse::<usize>()? < value.len());
+    Ok(())
+}
+
+#[test]
+fn test_compressed_values_survive_restart() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_value_compression("zstd", 100);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    let value = "duva".repeat(1000);
+    assert_eq!(h.send_and_get(format!("SET large {value}")), "OK");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+    let _ = process.terminate();
+
+    // WHEN - restarted without compression
+    let env = ServerEnv { value_compression: None, ..env };
+    process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // THEN
+    assert_eq!(h.send_and_get("GET large"), value);
+    assert_eq!(memory_stat(&mut h, "compressed_keys"), "0");
+    Ok(())
+}
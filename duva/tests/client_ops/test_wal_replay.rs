@@ -0,0 +1,38 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn high_watermark(h: &mut Client) -> u64 {
+    h.info("replication")["high_watermark"].parse().unwrap()
+}
+
+#[test]
+fn test_wal_is_replayed_on_startup() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(true);
+    let mut process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+    assert_eq!(h.send_and_get("SET snapshotted 1"), "OK");
+    assert_eq!(h.send_and_get("SAVE"), "(nil)");
+    assert_eq!(h.send_and_get("SET logged 2"), "OK");
+    for _ in 0..3 {
+        h.send_and_get("INCR counter");
+    }
+    let log_index = high_watermark(&mut h);
+    let _ = process.terminate();
+
+    // WHEN
+    let process = spawn_server_process(&env)?;
+    let mut h = Client::new(process.port);
+
+    // THEN
+    assert_eq!(h.send_and_get("GET snapshotted"), "1");
+    assert_eq!(h.send_and_get("GET logged"), "2");
+    assert_eq!(h.send_and_get("GET counter"), "3");
+    let info = h.info("persistence");
+    assert_eq!(info["wal_replayed_entries"], "4", "{info:?}");
+    assert_eq!(high_watermark(&mut h), log_index);
+
+    // * New writes follow the replayed ones in the log
+    assert_eq!(h.send_and_get("SET after 3"), "OK");
+    assert_eq!(high_watermark(&mut h), log_index + 1);
+    Ok(())
+}
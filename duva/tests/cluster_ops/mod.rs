@@ -1,9 +1,19 @@
+mod test_cluster_announce;
+mod test_cluster_banlist;
+mod test_cluster_failover;
 mod test_cluster_forget_makes_all_nodes_forget_target_node;
 mod test_cluster_forget_when_wrong_id_given;
+mod test_cluster_history;
 mod test_cluster_known_nodes_increase_when_new_replica_is_added;
 
 mod test_removes_node_when_heartbeat_is_not_received_for_certain_time;
 
 mod test_cluster_meet;
+mod test_cluster_secret;
+mod test_cluster_seeds;
+mod test_cluster_shards;
+mod test_cluster_subscribe;
 mod test_lazy_discovery;
+#[cfg(feature = "tls")]
+mod test_peer_tls;
 mod test_reconnection_on_reboot;
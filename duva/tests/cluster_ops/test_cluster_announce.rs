@@ -0,0 +1,63 @@
+use crate::common::{Client, ServerEnv, form_cluster, spawn_server_process};
+
+#[test]
+fn test_replica_is_known_by_its_announced_ip() -> anyhow::Result<()> {
+    // GIVEN - a replica bound to every interface that peers have to reach through another address
+    let mut env = ServerEnv::default();
+    let mut repl_env = ServerEnv::default().with_announce_ip("0.0.0.0", "127.0.0.2");
+
+    // WHEN
+    let [leader_p, repl_p] = form_cluster([&mut env, &mut repl_env]);
+
+    // THEN
+    let mut h1 = Client::new(leader_p.port);
+    assert_eq!(h1.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
+    let nodes = h1.send_and_get_vec("cluster nodes", 2);
+    let announced = format!("127.0.0.2:{}", repl_p.port);
+    assert!(nodes.iter().any(|node| node.starts_with(&announced)), "{nodes:?}");
+
+    let mut h2 = Client::new(repl_p.port);
+    let nodes = h2.send_and_get_vec("cluster nodes", 2);
+    assert!(nodes.iter().any(|node| node.starts_with(&announced) && node.contains("myself")));
+    assert_eq!(h1.send_and_get("SET x 1"), "OK");
+    let until = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while until > std::time::Instant::now() && h2.send_and_get("GET x") != "1" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(h2.send_and_get("GET x"), "1");
+
+    Ok(())
+}
+
+#[test]
+fn test_cluster_over_ipv6() -> anyhow::Result<()> {
+    // GIVEN - nodes bound to every interface that go by their IPv6 loopback address
+    let env = ServerEnv::default().with_announce_ip("::", "::1");
+    let p1 = spawn_server_process(&env)?;
+
+    // WHEN
+    let repl_env = ServerEnv::default()
+        .with_announce_ip("::", "::1")
+        .with_bind_addr(format!("[::1]:{}", p1.port));
+    let p2 = spawn_server_process(&repl_env)?;
+
+    // THEN
+    let mut h1 = Client::new(p1.port);
+    assert_eq!(h1.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
+    let nodes = h1.send_and_get_vec("cluster nodes", 2);
+    assert!(nodes.iter().any(|node| node.starts_with(&format!("[::1]:{} myself", p1.port))));
+    assert!(nodes.iter().any(|node| node.starts_with(&format!("[::1]:{} ", p2.port))));
+    std::fs::read_to_string(&env.topology_path)?
+        .lines()
+        .for_each(|line| assert!(nodes.contains(&format!("{line} connected"))));
+
+    let mut h2 = Client::new(p2.port);
+    assert_eq!(h1.send_and_get("SET x 1"), "OK");
+    let until = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while until > std::time::Instant::now() && h2.send_and_get("GET x") != "1" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(h2.send_and_get("GET x"), "1");
+
+    Ok(())
+}
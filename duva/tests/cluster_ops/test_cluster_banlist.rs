@@ -0,0 +1,34 @@
+use crate::common::{Client, ServerEnv, form_cluster, spawn_server_process};
+
+#[test]
+fn test_cluster_banlist_survives_restart_until_cleared() -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default();
+    let mut repl_env = ServerEnv::default();
+    let [mut leader_p, repl_p] = form_cluster([&mut env, &mut repl_env]);
+    let mut h = Client::new(leader_p.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get(format!("cluster forget {} permanent", repl_p.bind_addr())), "OK");
+
+    // THEN
+    let banned = format!("{} permanent", repl_p.bind_addr());
+    assert_eq!(h.send_and_get_vec("cluster banlist", 1), vec![banned.clone()]);
+
+    // WHEN the node restarts
+    // * Let the topology file catch up with the ban before the node goes down
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    leader_p.kill()?;
+    leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(leader_p.port);
+
+    // THEN
+    assert_eq!(h.send_and_get_vec("cluster banlist", 1), vec![banned]);
+    assert_eq!(
+        h.send_and_get(format!("cluster banlist clear {}", repl_p.bind_addr())),
+        "(integer) 1"
+    );
+    assert_eq!(h.send_and_get("cluster banlist clear"), "(integer) 0");
+
+    Ok(())
+}
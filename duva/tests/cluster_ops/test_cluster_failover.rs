@@ -0,0 +1,61 @@
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_cluster_failover(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut repl_env = ServerEnv::default().with_append_only(with_append_only);
+    let mut repl_env2 = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, repl_p, _repl_p2] = form_cluster([&mut env, &mut repl_env, &mut repl_env2]);
+
+    let mut leader_h = Client::new(leader_p.port);
+    assert_eq!(leader_h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    assert_eq!(leader_h.send_and_get(format!("cluster failover {}", repl_p.bind_addr())), "OK");
+
+    // THEN - the chosen replica takes over without losing writes
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
+    let mut repl_h = Client::new(repl_p.port);
+    assert_eq!(repl_h.send_and_get("role"), "leader");
+    assert_eq!(leader_h.send_and_get("role"), "follower");
+    assert_eq!(repl_h.send_and_get("GET foo"), "bar");
+    assert_eq!(repl_h.send_and_get("SET foo baz"), "OK");
+
+    Ok(())
+}
+
+fn run_cluster_failover_rejected_on_follower(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut repl_env = ServerEnv::default().with_append_only(with_append_only);
+
+    let [_leader_p, repl_p] = form_cluster([&mut env, &mut repl_env]);
+
+    // WHEN
+    let mut repl_h = Client::new(repl_p.port);
+    let res = repl_h.send_and_get("cluster failover");
+
+    // THEN
+    assert!(res.contains("only the leader can hand off leadership"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cluster_failover() -> anyhow::Result<()> {
+    run_cluster_failover(false)?;
+    run_cluster_failover(true)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_cluster_failover_rejected_on_follower() -> anyhow::Result<()> {
+    run_cluster_failover_rejected_on_follower(false)?;
+    run_cluster_failover_rejected_on_follower(true)?;
+
+    Ok(())
+}
@@ -23,13 +23,13 @@ fn run_cluster_forget_makes_all_nodes_forget_target_node(
     );
 
     // THEN
-    assert_eq!(client_handler.send_and_get_vec("cluster info", 1), vec!["cluster_known_nodes:1"]);
+    assert_eq!(client_handler.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
 
     let mut repl_cli = Client::new(repl_p2.port);
 
     std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
 
-    assert_eq!(repl_cli.send_and_get_vec("cluster info", 1), vec!["cluster_known_nodes:1"]);
+    assert_eq!(repl_cli.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
 
     Ok(())
 }
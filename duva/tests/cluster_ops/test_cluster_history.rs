@@ -0,0 +1,29 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+#[test]
+fn test_cluster_history_records_topology_transitions() -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default();
+    let mut repl_env = ServerEnv::default();
+    let [leader_p, repl_p] = form_cluster([&mut env, &mut repl_env]);
+    let mut h = Client::new(leader_p.port);
+
+    // WHEN
+    assert_eq!(h.send_and_get(format!("cluster forget {}", repl_p.bind_addr())), "OK");
+
+    // THEN
+    let events = h.send_and_get_vec("cluster history 2", 2);
+    assert!(events[0].ends_with(&format!(" node_removed {}", repl_p.bind_addr())), "{events:?}");
+    assert!(events[1].ends_with(&format!(" node_banned {}", repl_p.bind_addr())), "{events:?}");
+
+    let events = h.send_and_get_vec("cluster history 4", 4);
+    assert!(events[0].contains(&format!(" leader_changed {} term=", leader_p.bind_addr())));
+    assert!(events[1].ends_with(&format!(" node_added {}", repl_p.bind_addr())), "{events:?}");
+    let times = events.iter().map(|event| event.split(' ').next().unwrap().parse::<u128>());
+    assert!(times.collect::<Result<Vec<_>, _>>()?.is_sorted());
+
+    let repl_events = Client::new(repl_p.port).send_and_get_vec("cluster history", 2);
+    assert!(repl_events[0].ends_with(&format!(" node_added {}", leader_p.bind_addr())));
+    assert!(repl_events[1].contains(&format!(" leader_changed {} term=", leader_p.bind_addr())));
+    Ok(())
+}
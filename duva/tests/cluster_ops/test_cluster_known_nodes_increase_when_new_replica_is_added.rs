@@ -9,10 +9,7 @@ fn run_cluster_topology_change_when_new_node_added(with_append_only: bool) -> an
     let [leader_p, _repl_p] = form_cluster([&mut env, &mut repl_env]);
 
     let mut client_handler = Client::new(leader_p.port);
-    assert_eq!(
-        client_handler.send_and_get_vec("cluster info", 1),
-        vec!["cluster_known_nodes:1".to_string()]
-    );
+    assert_eq!(client_handler.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
 
     // WHEN -- new replica is added
     let repl_env2 = ServerEnv::default()
@@ -21,16 +18,13 @@ fn run_cluster_topology_change_when_new_node_added(with_append_only: bool) -> an
     let mut _new_repl_p = spawn_server_process(&repl_env2)?;
 
     //THEN
-    assert_eq!(
-        client_handler.send_and_get_vec("cluster info", 1),
-        vec!["cluster_known_nodes:2".to_string()]
-    );
+    assert_eq!(client_handler.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:2");
     let nodes = client_handler.send_and_get_vec("cluster nodes", 3);
     assert_eq!(nodes.len(), 3);
     std::fs::read_to_string(&env.topology_path)
         .unwrap()
         .lines()
-        .for_each(|line| assert!(nodes.contains(&line.to_string())));
+        .for_each(|line| assert!(nodes.contains(&format!("{line} connected"))));
 
     Ok(())
 }
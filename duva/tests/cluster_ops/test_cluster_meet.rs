@@ -28,7 +28,7 @@ fn run_cluster_meet(append_only: bool) -> anyhow::Result<()> {
     // backoff time try for 3 seconds
     let until = std::time::Instant::now() + std::time::Duration::from_secs(3);
     while until > std::time::Instant::now() {
-        let res = client_handler.send_and_get_vec("cluster info", 1);
+        let res = client_handler.send_and_get_vec("cluster info", 2);
         if res.contains(&"cluster_known_nodes:3".to_string()) {
             assert_eq!(client_handler.send_and_get("role"), "leader");
             success_cnt += 1;
@@ -40,7 +40,7 @@ fn run_cluster_meet(append_only: bool) -> anyhow::Result<()> {
     let mut replica_handler = Client::new(env4.port);
     // WHEN query is given to joining replica
     while until > std::time::Instant::now() {
-        let res = replica_handler.send_and_get_vec("cluster info", 1);
+        let res = replica_handler.send_and_get_vec("cluster info", 2);
         if res.contains(&"cluster_known_nodes:3".to_string()) {
             assert_eq!(replica_handler.send_and_get("role"), "follower");
             success_cnt += 1;
@@ -131,6 +131,51 @@ fn run_cluster_meet_with_migration(append_only: bool) -> anyhow::Result<()> {
     // verify that all keys are accessible from both nodes
     assert!(dbg!(keys_accessible_from_node1 + keys_accessible_from_node2) == 1000);
 
+    // Multi-key commands spanning both partitions are split, forwarded and merged by the receiving node
+    let (local, remote) = (node1_keys[0], node2_keys[0]);
+    // Plain reads of keys another partition owns are redirected to it
+    assert!(client_handler1.send_and_get(format!("get {remote}")).starts_with("(error) MOVED "));
+    assert_eq!(
+        client_handler1.send_and_get_vec(format!("mget {local} {remote}"), 2),
+        vec![format!("1) \"{local}\""), format!("2) \"{remote}\"")]
+    );
+    assert_eq!(client_handler1.send_and_get(format!("exists {local} {remote}")), "(integer) 2");
+    assert_eq!(client_handler1.send_and_get(format!("del {local} {remote}")), "(integer) 2");
+    assert_eq!(client_handler2.send_and_get(format!("exists {local} {remote}")), "(integer) 0");
+
+    // A batch spanning both partitions is committed on each of them through two-phase commit
+    assert_eq!(
+        client_handler1.send_and_get(format!("batch set {local} a ; set {remote} b")),
+        "(integer) 2"
+    );
+    assert_eq!(
+        client_handler2.send_and_get_vec(format!("mget {local} {remote}"), 2),
+        vec!["1) \"a\"".to_string(), "2) \"b\"".to_string()]
+    );
+
+    // A lease is held by the partition that granted it and only takes keys of that partition
+    let lease = client_handler1.send_and_get("lease grant 60000");
+    let lease = lease.trim_start_matches("(integer) ");
+    let other_lease = client_handler2.send_and_get("lease grant 60000");
+    assert_ne!(lease, other_lease.trim_start_matches("(integer) "));
+    assert!(
+        client_handler2
+            .send_and_get(format!("lease keepalive {lease}"))
+            .starts_with("(error) MOVED ")
+    );
+    assert!(
+        client_handler1
+            .send_and_get(format!("lease attach {lease} {remote}"))
+            .starts_with("(error) CROSSSLOT")
+    );
+    assert_eq!(
+        client_handler1.send_and_get(format!("lease attach {lease} {local}")),
+        "(integer) 1"
+    );
+    assert_eq!(client_handler1.send_and_get(format!("lease revoke {lease}")), "(integer) 1");
+    assert_eq!(client_handler1.send_and_get(format!("get {local}")), "(nil)");
+    assert_eq!(client_handler2.send_and_get(format!("get {remote}")), "b");
+
     Ok(())
 }
 
@@ -0,0 +1,35 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn known_nodes(client: &mut Client) -> Vec<String> {
+    client
+        .send_and_get_vec("cluster info", 2)
+        .into_iter()
+        .filter(|line| line.starts_with("cluster_known_nodes"))
+        .collect()
+}
+
+#[test]
+fn test_only_peers_with_the_cluster_secret_join() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_cluster_secret("s3cret");
+    let member_env = ServerEnv::default().with_cluster_secret("s3cret");
+    let stray_env = ServerEnv::default().with_cluster_secret("guess");
+    let _process = spawn_server_process(&env)?;
+    let _member = spawn_server_process(&member_env)?;
+    let _stray = spawn_server_process(&stray_env)?;
+    let mut h = Client::new(env.port);
+
+    // WHEN
+    let mut stray = Client::new(stray_env.port);
+    let stray_res = stray.send_and_get(format!("cluster meet 127.0.0.1:{}", env.port));
+    let mut member = Client::new(member_env.port);
+    let member_res = member.send_and_get(format!("cluster meet 127.0.0.1:{}", env.port));
+
+    // THEN
+    assert_ne!(stray_res, "OK");
+    assert_eq!(member_res, "OK");
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    assert_eq!(known_nodes(&mut h), ["cluster_known_nodes:1"]);
+    assert_eq!(known_nodes(&mut stray), ["cluster_known_nodes:0"]);
+    Ok(())
+}
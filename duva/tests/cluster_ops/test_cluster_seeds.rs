@@ -0,0 +1,54 @@
+use crate::common::{Client, ServerEnv, get_available_port, spawn_server_process};
+
+fn known_nodes(client: &mut Client) -> String {
+    client.send_and_get_vec("cluster info", 2)[0].clone()
+}
+
+#[test]
+fn test_node_joins_through_seeds() -> anyhow::Result<()> {
+    // GIVEN - a seed that is not up yet, listed before one reached through its DNS name
+    let env = ServerEnv::default();
+    let p1 = spawn_server_process(&env)?;
+    let seeds = format!("127.0.0.1:{},localhost:{}", get_available_port(), env.port);
+
+    // WHEN
+    let env2 = ServerEnv::default().with_cluster_seeds(seeds);
+    let p2 = spawn_server_process(&env2)?;
+
+    // THEN
+    let mut h1 = Client::new(p1.port);
+    let mut h2 = Client::new(p2.port);
+    let until = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while until > std::time::Instant::now() && known_nodes(&mut h1) != "cluster_known_nodes:1" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(known_nodes(&mut h1), "cluster_known_nodes:1");
+    assert_eq!(known_nodes(&mut h2), "cluster_known_nodes:1");
+    assert_eq!(h2.send_and_get("ROLE"), "leader");
+
+    Ok(())
+}
+
+#[test]
+fn test_first_seed_node_waits_for_the_others() -> anyhow::Result<()> {
+    // GIVEN - every node lists every other, and the first one up finds no one to meet
+    let (port1, port2) = (get_available_port(), get_available_port());
+    let seeds = format!("127.0.0.1:{port1},127.0.0.1:{port2}");
+    let env1 = ServerEnv::default().with_port(port1).with_cluster_seeds(&seeds);
+    let p1 = spawn_server_process(&env1)?;
+    let mut h1 = Client::new(p1.port);
+    assert_eq!(known_nodes(&mut h1), "cluster_known_nodes:0");
+
+    // WHEN
+    let env2 = ServerEnv::default().with_port(port2).with_cluster_seeds(&seeds);
+    let _p2 = spawn_server_process(&env2)?;
+
+    // THEN
+    let until = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while until > std::time::Instant::now() && known_nodes(&mut h1) != "cluster_known_nodes:1" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(known_nodes(&mut h1), "cluster_known_nodes:1");
+
+    Ok(())
+}
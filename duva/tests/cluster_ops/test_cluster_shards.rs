@@ -0,0 +1,33 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_cluster_shards(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(with_append_only);
+    let mut repl_env = ServerEnv::default().with_append_only(with_append_only);
+
+    let [leader_p, repl_p] = form_cluster([&mut env, &mut repl_env]);
+
+    // WHEN
+    let mut h = Client::new(leader_p.port);
+    let shards = h.send_and_get_vec("cluster shards", 1);
+
+    // THEN - one partition led by the leader, with the replica and the whole hash space
+    let fields: Vec<&str> = shards[0].split(' ').collect();
+    assert_eq!(fields.len(), 4);
+    let nodes = h.send_and_get_vec("cluster nodes", 2);
+    let myself = nodes.iter().find(|line| line.contains("myself")).unwrap();
+    assert!(myself.starts_with(fields[1]));
+    assert_eq!(fields[2], repl_p.bind_addr());
+    assert!(fields[3].starts_with("0-"));
+    assert!(fields[3].ends_with(&format!("-{}", u64::MAX)));
+
+    Ok(())
+}
+
+#[test]
+fn test_cluster_shards() -> anyhow::Result<()> {
+    run_cluster_shards(false)?;
+    run_cluster_shards(true)?;
+
+    Ok(())
+}
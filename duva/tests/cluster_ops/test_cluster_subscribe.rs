@@ -0,0 +1,67 @@
+use crate::common::{Client, ServerEnv, session_request, spawn_server_process};
+use duva::domains::query_io::QueryIO;
+use duva::domains::{TRead, TSerdeReadWrite};
+use duva::prelude::{AuthRequest, AuthResponse};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// Shard lines of the next topology push, skipping whatever else the connection receives.
+async fn next_topology_push(stream: &mut TcpStream) -> anyhow::Result<Vec<String>> {
+    loop {
+        for value in stream.read_values().await? {
+            let QueryIO::Array(items) = value else { continue };
+            let [QueryIO::BulkString(kind), QueryIO::Array(shards)] = &items[..] else { continue };
+            if kind.as_ref() != b"topology" {
+                continue;
+            }
+            return Ok(shards
+                .iter()
+                .map(|shard| match shard {
+                    | QueryIO::BulkString(line) => String::from_utf8_lossy(line).into_owned(),
+                    | other => panic!("unexpected shard {other:?}"),
+                })
+                .collect());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cluster_subscribe_pushes_shards_as_the_ring_changes() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let env2 = ServerEnv::default();
+    let process = spawn_server_process(&env)?;
+    let _process2 = spawn_server_process(&env2)?;
+
+    let mut stream = TcpStream::connect(format!("127.0.0.1:{}", process.port)).await?;
+    stream.serialized_write(AuthRequest::default()).await?;
+    let response: AuthResponse = stream.deserialized_read().await?;
+    stream
+        .write_all(&session_request(response.request_id + 1, vec!["CLUSTER", "SUBSCRIBE"]))
+        .await?;
+
+    // THEN - the current shards come first
+    let shards = next_topology_push(&mut stream).await?;
+    assert_eq!(shards.len(), 1);
+    assert!(shards[0].ends_with(&format!("0-{}", u64::MAX)), "{shards:?}");
+    let mut h = Client::new(process.port);
+    let clients = h.send_and_get_vec("CLIENT LIST", 2);
+    assert!(clients.iter().any(|line| line.contains(" flags=P ")), "{clients:?}");
+
+    // WHEN
+    assert_eq!(h.send_and_get(format!("cluster meet 127.0.0.1:{} eager", env2.port)), "OK");
+
+    // THEN - the rebalanced ring is pushed without asking
+    let rebalanced = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let shards = next_topology_push(&mut stream).await?;
+            if shards.len() == 2 {
+                return anyhow::Ok(shards);
+            }
+        }
+    })
+    .await??;
+    assert!(rebalanced.iter().any(|shard| shard.contains(&format!(":{} ", env2.port))));
+    Ok(())
+}
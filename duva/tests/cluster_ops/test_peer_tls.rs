@@ -0,0 +1,53 @@
+use crate::common::{Client, ServerEnv, spawn_server_process};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Writes a certificate for 127.0.0.1, its key and the CA that issued it to `dir`.
+fn issue(ca: &CertifiedIssuer<'static, KeyPair>, dir: &Path, name: &str) -> ServerEnv {
+    let key = KeyPair::generate().unwrap();
+    let mut params = CertificateParams::new(vec!["127.0.0.1".to_string()]).unwrap();
+    params.extended_key_usages =
+        vec![ExtendedKeyUsagePurpose::ServerAuth, ExtendedKeyUsagePurpose::ClientAuth];
+    let cert = params.signed_by(&key, ca).unwrap();
+
+    let [cert_path, key_path, ca_path] =
+        ["crt", "key", "ca"].map(|ext| dir.join(format!("{name}.{ext}")));
+    std::fs::write(&cert_path, cert.pem()).unwrap();
+    std::fs::write(&key_path, key.serialize_pem()).unwrap();
+    std::fs::write(&ca_path, ca.pem()).unwrap();
+    ServerEnv::default().with_peer_tls(cert_path, key_path, ca_path)
+}
+
+fn cluster_ca() -> CertifiedIssuer<'static, KeyPair> {
+    let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap()
+}
+
+#[test]
+fn test_only_peers_with_certificates_of_the_cluster_ca_join() -> anyhow::Result<()> {
+    // GIVEN
+    let dir = TempDir::new()?;
+    let (ca, other_ca) = (cluster_ca(), cluster_ca());
+    let env = issue(&ca, dir.path(), "leader");
+    let leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(env.port);
+
+    // WHEN
+    let replica_env = issue(&ca, dir.path(), "replica").with_bind_addr(leader_p.bind_addr());
+    let _replica = spawn_server_process(&replica_env)?;
+    let stray_env = issue(&other_ca, dir.path(), "stray");
+    let _stray = spawn_server_process(&stray_env)?;
+    let mut stray = Client::new(stray_env.port);
+    let stray_res = stray.send_and_get(format!("cluster meet 127.0.0.1:{}", env.port));
+
+    // THEN - the replica takes the log over TLS, and the stray node stays out
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    assert_eq!(Client::new(replica_env.port).send_and_get("GET foo"), "bar");
+    assert_ne!(stray_res, "OK");
+    Ok(())
+}
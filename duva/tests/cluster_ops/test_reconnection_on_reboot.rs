@@ -47,3 +47,68 @@ fn test_reconnection_on_reboot() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_rejoin_after_reboot_once_leader_is_back() -> anyhow::Result<()> {
+    // GIVEN
+    let mut env1 = ServerEnv::default();
+    let mut env2 = ServerEnv::default().with_append_only(true);
+    let [mut p1, mut p2] = form_cluster([&mut env1, &mut env2]);
+    let mut cli_to_p1 = Client::new(p1.port);
+    cli_to_p1.send_and_get("SET x value1");
+    // * The snapshot keeps the replication id of the shard for when the leader comes back
+    assert_eq!(cli_to_p1.send_and_get("SAVE"), "(nil)");
+    drop(cli_to_p1);
+
+    // WHEN the replica reboots while its leader is down
+    p2.kill()?;
+    p1.kill()?;
+    env2.leader_bind_addr = None;
+    p2 = spawn_server_process(&env2)?;
+
+    // WHEN the leader comes back with no memory of the replica
+    env1.topology_path = env1.dir.path().join("fresh.tp");
+    p1 = spawn_server_process(&env1)?;
+
+    // THEN the replica finds its way back through its retries
+    let mut cli_to_p1 = Client::new(p1.port);
+    let until = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while until > std::time::Instant::now() {
+        if cli_to_p1.send_and_get_vec("cluster info", 2)[0] == "cluster_known_nodes:1" {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(cli_to_p1.send_and_get_vec("cluster info", 2)[0], "cluster_known_nodes:1");
+    assert_eq!(cli_to_p1.send_and_get("ROLE"), "leader");
+    assert_eq!(cli_to_p1.send_and_get("SET y value2"), "OK");
+
+    let mut cli_to_p2 = Client::new(p2.port);
+    assert_eq!(cli_to_p2.send_and_get("ROLE"), "follower");
+    assert_eq!(cli_to_p2.send_and_get("GET x"), "value1");
+    while until > std::time::Instant::now() && cli_to_p2.send_and_get("GET y") != "value2" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(cli_to_p2.send_and_get("GET y"), "value2");
+
+    Ok(())
+}
+
+#[test]
+fn test_standalone_node_leads_after_reboot() -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default();
+    let mut p = spawn_server_process(&env)?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    // WHEN
+    p.kill()?;
+    p = spawn_server_process(&env)?;
+
+    // THEN - its topology file names no other node of its shard to follow
+    let mut cli = Client::new(p.port);
+    assert_eq!(cli.send_and_get("ROLE"), "leader");
+    assert_eq!(cli.send_and_get("SET x value1"), "OK");
+
+    Ok(())
+}
@@ -12,14 +12,19 @@ fn run_removes_node_when_heartbeat_is_not_received_for_certain_time(
     let [leader_p, mut repl_p] = form_cluster([&mut env, &mut repl_env]);
 
     let mut h = Client::new(leader_p.port);
-    assert_eq!(h.send_and_get_vec("cluster info", 1), vec!["cluster_known_nodes:1"]);
+    let info = h.send_and_get_vec("cluster info", 2);
+    assert_eq!(info[0], "cluster_known_nodes:1");
+    assert!(info[1].starts_with(&format!("cluster_peer_phi:{}=", repl_p.bind_addr())));
 
     // WHEN
     repl_p.kill()?;
     sleep(Duration::from_secs(2));
 
     //THEN
-    assert_eq!(h.send_and_get_vec("cluster info", 1), vec!["cluster_known_nodes:0"]);
+    assert_eq!(
+        h.send_and_get_vec("cluster info", 2),
+        vec!["cluster_known_nodes:0", "cluster_peer_phi:"]
+    );
 
     Ok(())
 }
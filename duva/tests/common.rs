@@ -2,6 +2,7 @@
 use bytes::Bytes;
 use duva::domains::query_io::QueryIO;
 use duva::make_smart_pointer;
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
 use std::mem::MaybeUninit;
 use std::net::TcpListener;
@@ -21,6 +22,38 @@ pub struct ServerEnv {
     pub hf: u128,
     pub ttl: u128,
     pub append_only: bool,
+    pub snapshot_threshold: u64,
+    pub snapshot_catchup_lag: u64,
+    pub wal_segment_size: Option<usize>,
+    pub save: Option<String>,
+    // * Limit in bytes and the policy applied once it is exceeded
+    pub maxmemory: Option<(u64, String)>,
+    // * Number of cache shards and the seed of the key hash that picks one
+    pub cache_shards: Option<(usize, u64)>,
+    // * Codec for values held in memory and the length past which they are compressed
+    pub value_compression: Option<(String, usize)>,
+    // * Codec for both WAL entries and replicated entries
+    pub compression: Option<String>,
+    pub backup_target: Option<String>,
+    pub restore_from_backup: bool,
+    pub recover_to_index: Option<u64>,
+    pub recover_to_time: Option<u64>,
+    pub encryption_keys: Option<String>,
+    pub audit_log: Option<String>,
+    pub otlp_endpoint: Option<String>,
+    pub requirepass: Option<String>,
+    pub cluster_secret: Option<String>,
+    // * PEM files of the node certificate, its key and the cluster CA
+    pub peer_tls: Option<(PathBuf, PathBuf, PathBuf)>,
+    pub cluster_seeds: Option<String>,
+    // * Host the node binds and the IP it announces to the cluster instead
+    pub announce_ip: Option<(String, String)>,
+    // * Client connections served at a time and new ones accepted from one address per second
+    pub connection_limits: Option<(u64, u32)>,
+    pub user_quotas: Option<(u64, u64)>,
+    pub client_timeout: Option<u64>,
+    pub unixsocket: Option<PathBuf>,
+    pub client_output_buffer_limit_normal: Option<String>,
     // Owns and cleans the directory.
     pub dir: TempDir,
     pub topology_path: PathBuf,
@@ -38,6 +71,31 @@ impl Default for ServerEnv {
             hf: 100,
             ttl: 1500,
             append_only: false,
+            snapshot_threshold: 10000,
+            snapshot_catchup_lag: 0,
+            wal_segment_size: None,
+            save: None,
+            maxmemory: None,
+            cache_shards: None,
+            value_compression: None,
+            compression: None,
+            backup_target: None,
+            restore_from_backup: false,
+            recover_to_index: None,
+            recover_to_time: None,
+            encryption_keys: None,
+            audit_log: None,
+            otlp_endpoint: None,
+            requirepass: None,
+            cluster_secret: None,
+            peer_tls: None,
+            cluster_seeds: None,
+            announce_ip: None,
+            connection_limits: None,
+            user_quotas: None,
+            client_timeout: None,
+            unixsocket: None,
+            client_output_buffer_limit_normal: None,
             dir,
             topology_path,
         }
@@ -80,6 +138,106 @@ impl ServerEnv {
         self.append_only = append_only;
         self
     }
+    pub fn with_snapshot_threshold(mut self, snapshot_threshold: u64) -> Self {
+        self.snapshot_threshold = snapshot_threshold;
+        self
+    }
+    pub fn with_snapshot_catchup_lag(mut self, snapshot_catchup_lag: u64) -> Self {
+        self.snapshot_catchup_lag = snapshot_catchup_lag;
+        self
+    }
+    pub fn with_wal_segment_size(mut self, wal_segment_size: usize) -> Self {
+        self.wal_segment_size = Some(wal_segment_size);
+        self
+    }
+    pub fn with_save(mut self, save: impl Into<String>) -> Self {
+        self.save = Some(save.into());
+        self
+    }
+    pub fn with_maxmemory(mut self, limit: u64, policy: impl Into<String>) -> Self {
+        self.maxmemory = Some((limit, policy.into()));
+        self
+    }
+    pub fn with_cache_shards(mut self, shards: usize, hash_seed: u64) -> Self {
+        self.cache_shards = Some((shards, hash_seed));
+        self
+    }
+    pub fn with_value_compression(mut self, codec: impl Into<String>, threshold: usize) -> Self {
+        self.value_compression = Some((codec.into(), threshold));
+        self
+    }
+    pub fn with_compression(mut self, compression: impl Into<String>) -> Self {
+        self.compression = Some(compression.into());
+        self
+    }
+    pub fn with_backup_target(mut self, backup_target: impl AsRef<Path>) -> Self {
+        self.backup_target = Some(backup_target.as_ref().to_str().unwrap().to_string());
+        self
+    }
+    pub fn with_restore_from_backup(mut self, restore_from_backup: bool) -> Self {
+        self.restore_from_backup = restore_from_backup;
+        self
+    }
+    pub fn with_recover_to_index(mut self, log_index: u64) -> Self {
+        self.recover_to_index = Some(log_index);
+        self
+    }
+    pub fn with_recover_to_time(mut self, unix_millis: u64) -> Self {
+        self.recover_to_time = Some(unix_millis);
+        self
+    }
+    pub fn with_encryption_keys(mut self, encryption_keys: impl Into<String>) -> Self {
+        self.encryption_keys = Some(encryption_keys.into());
+        self
+    }
+    pub fn with_audit_log(mut self, audit_log: impl AsRef<Path>) -> Self {
+        self.audit_log = Some(audit_log.as_ref().to_str().unwrap().to_string());
+        self
+    }
+    pub fn with_otlp_endpoint(mut self, otlp_endpoint: impl Into<String>) -> Self {
+        self.otlp_endpoint = Some(otlp_endpoint.into());
+        self
+    }
+    pub fn with_requirepass(mut self, requirepass: impl Into<String>) -> Self {
+        self.requirepass = Some(requirepass.into());
+        self
+    }
+    pub fn with_cluster_secret(mut self, cluster_secret: impl Into<String>) -> Self {
+        self.cluster_secret = Some(cluster_secret.into());
+        self
+    }
+    pub fn with_peer_tls(mut self, cert: PathBuf, key: PathBuf, ca: PathBuf) -> Self {
+        self.peer_tls = Some((cert, key, ca));
+        self
+    }
+    pub fn with_cluster_seeds(mut self, cluster_seeds: impl Into<String>) -> Self {
+        self.cluster_seeds = Some(cluster_seeds.into());
+        self
+    }
+    pub fn with_announce_ip(mut self, host: impl Into<String>, ip: impl Into<String>) -> Self {
+        self.announce_ip = Some((host.into(), ip.into()));
+        self
+    }
+    pub fn with_connection_limits(mut self, maxclients: u64, rate_limit: u32) -> Self {
+        self.connection_limits = Some((maxclients, rate_limit));
+        self
+    }
+    pub fn with_user_quotas(mut self, command_rate: u64, output_rate: u64) -> Self {
+        self.user_quotas = Some((command_rate, output_rate));
+        self
+    }
+    pub fn with_client_timeout(mut self, secs: u64) -> Self {
+        self.client_timeout = Some(secs);
+        self
+    }
+    pub fn with_unixsocket(mut self) -> Self {
+        self.unixsocket = Some(self.dir.path().join("duva.sock"));
+        self
+    }
+    pub fn with_client_output_buffer_limit_normal(mut self, limit: impl Into<String>) -> Self {
+        self.client_output_buffer_limit_normal = Some(limit.into());
+        self
+    }
 }
 
 // Let the OS assign a free port dynamically to reduce port conflicts:
@@ -123,6 +281,10 @@ pub fn spawn_server_process(env: &ServerEnv) -> anyhow::Result<TestProcessChild>
         std::thread::sleep(std::time::Duration::from_millis(500));
 
         if let Ok(mut child) = std::panic::catch_unwind(|| Client::new(process.port)) {
+            if let Some(requirepass) = env.requirepass.as_ref() {
+                child.send_and_get(format!("AUTH {requirepass}"));
+            }
+
             // First check: basic connectivity
             let ping_res = child.send_and_get("PING");
             if ping_res != "PONG" {
@@ -228,6 +390,10 @@ pub fn run_server_process(env: &ServerEnv) -> TestProcessChild {
         &env.ttl.to_string(),
         "--append_only",
         &env.append_only.to_string(),
+        "--snapshot_threshold",
+        &env.snapshot_threshold.to_string(),
+        "--snapshot_catchup_lag",
+        &env.snapshot_catchup_lag.to_string(),
         "--dir",
         env.dir.path().to_str().unwrap(),
         "--tpp",
@@ -242,6 +408,89 @@ pub fn run_server_process(env: &ServerEnv) -> TestProcessChild {
     if let Some(file_name) = env.file_name.0.as_ref() {
         command.args(["--dbfilename", file_name]);
     }
+    if let Some(wal_segment_size) = env.wal_segment_size {
+        command.args(["--wal_segment_size", &wal_segment_size.to_string()]);
+    }
+    if let Some(save) = env.save.as_ref() {
+        command.args(["--save", save]);
+    }
+    if let Some((limit, policy)) = env.maxmemory.as_ref() {
+        command.args(["--maxmemory", &limit.to_string(), "--maxmemory_policy", policy]);
+    }
+    if let Some((shards, hash_seed)) = env.cache_shards.as_ref() {
+        command.args([
+            "--cache_shards",
+            &shards.to_string(),
+            "--cache_hash_seed",
+            &hash_seed.to_string(),
+        ]);
+    }
+    if let Some((codec, threshold)) = env.value_compression.as_ref() {
+        command.args([
+            "--value_compression",
+            codec,
+            "--value_compression_threshold",
+            &threshold.to_string(),
+        ]);
+    }
+    if let Some(compression) = env.compression.as_ref() {
+        command.args(["--wal_compression", compression, "--replication_compression", compression]);
+    }
+    if let Some(backup_target) = env.backup_target.as_ref() {
+        command.args(["--backup_target", backup_target, "--backup_interval", "100"]);
+    }
+    if env.restore_from_backup {
+        command.args(["--restore_from_backup", "true"]);
+    }
+    if let Some(log_index) = env.recover_to_index {
+        command.args(["--recover_to_index", &log_index.to_string()]);
+    }
+    if let Some(unix_millis) = env.recover_to_time {
+        command.args(["--recover_to_time", &unix_millis.to_string()]);
+    }
+    if let Some(encryption_keys) = env.encryption_keys.as_ref() {
+        command.args(["--encryption_keys", encryption_keys]);
+    }
+    if let Some(audit_log) = env.audit_log.as_ref() {
+        command.args(["--audit_log", audit_log]);
+    }
+    if let Some(otlp_endpoint) = env.otlp_endpoint.as_ref() {
+        command.args(["--otlp_endpoint", otlp_endpoint]);
+    }
+    if let Some(requirepass) = env.requirepass.as_ref() {
+        command.args(["--requirepass", requirepass]);
+    }
+    if let Some(cluster_secret) = env.cluster_secret.as_ref() {
+        command.args(["--cluster_secret", cluster_secret]);
+    }
+    if let Some((cert, key, ca)) = env.peer_tls.as_ref() {
+        command.arg("--peer_tls_cert").arg(cert);
+        command.arg("--peer_tls_key").arg(key);
+        command.arg("--peer_tls_ca").arg(ca);
+    }
+    if let Some(cluster_seeds) = env.cluster_seeds.as_ref() {
+        command.args(["--cluster_seeds", cluster_seeds, "--cluster_seeds_interval", "200"]);
+    }
+    if let Some((host, ip)) = env.announce_ip.as_ref() {
+        command.args(["--host", host, "--cluster_announce_ip", ip]);
+    }
+    if let Some((maxclients, rate_limit)) = env.connection_limits {
+        command.args(["--maxclients", &maxclients.to_string()]);
+        command.args(["--connection_rate_limit", &rate_limit.to_string()]);
+    }
+    if let Some((command_rate, output_rate)) = env.user_quotas {
+        command.args(["--user_command_rate", &command_rate.to_string()]);
+        command.args(["--user_output_rate", &output_rate.to_string()]);
+    }
+    if let Some(secs) = env.client_timeout {
+        command.args(["--timeout", &secs.to_string()]);
+    }
+    if let Some(path) = &env.unixsocket {
+        command.args(["--unixsocket", path.to_str().unwrap()]);
+    }
+    if let Some(limit) = &env.client_output_buffer_limit_normal {
+        command.args(["--client_output_buffer_limit_normal", limit]);
+    }
 
     TestProcessChild::new(
         command
@@ -310,7 +559,7 @@ impl Client {
         Ok(())
     }
 
-    pub fn read(&mut self) -> Result<String, ()> {
+    pub fn read(&mut self) -> std::io::Result<String> {
         // Initialize reader if it doesn't exist
         if self.reader.is_none() {
             self.reader = Some(BufReader::new(self.child.stdout.take().unwrap()));
@@ -318,7 +567,7 @@ impl Client {
 
         let reader = self.reader.as_mut().unwrap();
         let mut line = String::new();
-        reader.read_line(&mut line).map_err(|_| ())?;
+        reader.read_line(&mut line)?;
         Ok(line.trim().to_string())
     }
 
@@ -373,6 +622,31 @@ impl Client {
         self.read().unwrap_or_default()
     }
 
+    /// Fields of an `INFO` reply by name.
+    pub fn info(&mut self, section: &str) -> HashMap<String, String> {
+        self.fields(format!("INFO {section}"))
+    }
+
+    /// `name:value` fields of a reply by name. The reply spans a varying number of lines, so it is
+    /// read up to the reply of an `ECHO` sent right behind it.
+    pub fn fields(&mut self, command: impl AsRef<[u8]>) -> HashMap<String, String> {
+        const END: &str = "fields-end";
+        self.send(command.as_ref()).unwrap();
+        self.send(format!("ECHO {END}").as_bytes()).unwrap();
+
+        let mut fields = HashMap::new();
+        while let Ok(line) = self.read() {
+            if line == END {
+                break;
+            }
+            // * Section headers and the blank lines between sections carry no field
+            if let Some((name, value)) = line.split_once(':') {
+                fields.insert(name.to_string(), value.to_string());
+            }
+        }
+        fields
+    }
+
     pub fn terminate(&mut self) -> std::io::Result<()> {
         self.child.kill()?;
         let _ = self.child.wait()?;
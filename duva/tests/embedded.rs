@@ -0,0 +1,38 @@
+//! Kept out of `test_mods` as a binary of its own: the configuration of an embedded node is
+//! process-wide, so only one can be started per process.
+use duva::DuvaNode;
+use duva::prelude::ReplicationRole;
+use std::net::TcpListener;
+
+#[tokio::test]
+async fn test_embedded_node_serves_typed_commands() -> anyhow::Result<()> {
+    // GIVEN
+    let dir = tempfile::tempdir()?;
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let node = DuvaNode::builder()
+        .port(port)
+        .dir(dir.path().to_str().unwrap())
+        .tpp(dir.path().join("duva.tp").to_str().unwrap())
+        .start()
+        .await?;
+
+    // WHEN
+    node.set("foo", "bar").await?;
+    node.set("baz", "qux").await?;
+    node.set(b"\xff\x00", "binary").await?;
+
+    // THEN
+    assert_eq!(node.get("foo").await?.as_deref(), Some(&b"bar"[..]));
+    assert_eq!(node.del(&["foo", "missing"]).await?, 1);
+    assert_eq!(node.get("foo").await?, None);
+    assert_eq!(node.get(b"\xff\x00").await?.as_deref(), Some(&b"binary"[..]));
+    assert_eq!(node.role().await?, ReplicationRole::Leader);
+    let shards = node.cluster_shards().await?;
+    assert_eq!(shards.len(), 1);
+    assert_eq!(shards[0].ranges.first().map(|range| range.0), Some(0));
+
+    // * The configuration cannot be swapped for that of a second node
+    assert!(DuvaNode::builder().port(port + 1).start().await.is_err());
+    assert!(DuvaNode::builder().replicaof("not an address").is_err());
+    Ok(())
+}
@@ -1,3 +1,13 @@
+mod test_cluster_consensus;
+mod test_compression;
+mod test_eviction;
+mod test_info_replication;
 mod test_leader_election;
+mod test_linearizable_read;
 mod test_raft_happy_case;
+mod test_readonly_replica_read;
+mod test_snapshot_install;
 mod test_sync;
+#[cfg(feature = "otlp")]
+mod test_trace_export;
+mod test_wait;
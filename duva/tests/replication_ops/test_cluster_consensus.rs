@@ -0,0 +1,31 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+#[test]
+fn test_cluster_consensus_reports_log_and_peer_progress() -> anyhow::Result<()> {
+    // GIVEN
+    let mut leader_env = ServerEnv::default();
+    let mut follower_env = ServerEnv::default();
+    let [leader_p, follower_p] = form_cluster([&mut leader_env, &mut follower_env]);
+
+    let mut h = Client::new(leader_p.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    let leader = h.fields("CLUSTER CONSENSUS");
+    let follower = Client::new(follower_p.port).fields("CLUSTER CONSENSUS");
+
+    // THEN
+    assert_eq!(leader["role"], "leader");
+    assert_eq!(leader["election_state"], "leader");
+    assert_eq!(leader["commit_index"], "1");
+    assert_eq!(leader["last_log_index"], "1");
+    assert_eq!(leader["last_log_term"], leader["term"]);
+    assert_eq!(leader["peers"], "1");
+    assert!(leader["peer"].contains("role=follower shard=same match_index=1"), "{leader:?}");
+    assert_eq!(leader["writes_blocked"], "0");
+
+    assert_eq!(follower["election_state"], "follower");
+    assert_eq!(follower["term"], leader["term"]);
+    assert!(follower["peer"].contains("role=leader shard=same"), "{follower:?}");
+    Ok(())
+}
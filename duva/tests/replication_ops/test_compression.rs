@@ -0,0 +1,41 @@
+use std::{thread::sleep, time::Duration};
+
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_compressed_entries_reach_replicas(compression: &str) -> anyhow::Result<()> {
+    // GIVEN
+    let mut env = ServerEnv::default().with_append_only(true).with_compression(compression);
+    let mut follower_env = ServerEnv::default()
+        .with_append_only(true)
+        .with_compression(compression)
+        .with_file_name("follower_dbfilename");
+
+    let [leader_p, repl_p] = form_cluster([&mut env, &mut follower_env]);
+
+    // WHEN - values large enough to be compressed are written
+    let value = "duva".repeat(256);
+    let mut client_handler = Client::new(leader_p.port);
+    for i in 0..5 {
+        assert_eq!(client_handler.send_and_get(format!("SET key{i} {value}")), "OK");
+    }
+
+    // THEN
+    sleep(Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 15));
+
+    let mut client = Client::new(repl_p.port);
+    for i in 0..5 {
+        assert_eq!(client.send_and_get(format!("GET key{i}")), value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_compressed_entries_reach_replicas() -> anyhow::Result<()> {
+    run_compressed_entries_reach_replicas("lz4")?;
+    run_compressed_entries_reach_replicas("zstd")?;
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+#[test]
+fn test_evictions_are_replicated_as_deletes() -> anyhow::Result<()> {
+    // GIVEN - only the leader has a memory limit
+    let env = ServerEnv::default().with_maxmemory(1000, "allkeys-lru");
+    let leader_p = spawn_server_process(&env)?;
+    let repl_env = ServerEnv::default().with_bind_addr(leader_p.bind_addr());
+    let replica_p = spawn_server_process(&repl_env)?;
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
+
+    // WHEN
+    let mut h = Client::new(leader_p.port);
+    let value = "v".repeat(50);
+    for i in 0..100 {
+        assert_eq!(h.send_and_get(format!("SET key{i} {value}")), "OK");
+    }
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    // THEN - the replica dropped the keys the leader evicted
+    assert_eq!(h.send_and_get("GET key0"), "(nil)");
+    let mut replica = Client::new(replica_p.port);
+    assert_eq!(replica.send_and_get("READONLY"), "OK");
+    assert_eq!(replica.send_and_get("GET key0"), "(nil)");
+    assert_eq!(replica.send_and_get("GET key99"), value);
+    Ok(())
+}
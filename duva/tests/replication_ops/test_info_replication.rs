@@ -0,0 +1,27 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+#[test]
+fn test_info_replication_reports_replica_links() -> anyhow::Result<()> {
+    // GIVEN
+    let mut leader_env = ServerEnv::default();
+    let mut follower_env = ServerEnv::default();
+    let [leader_p, follower_p] = form_cluster([&mut leader_env, &mut follower_env]);
+
+    let mut h = Client::new(leader_p.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN
+    let leader_info = h.info("replication");
+    let follower_info = Client::new(follower_p.port).info("replication");
+
+    // THEN
+    assert_eq!(leader_info["connected_slaves"], "1");
+    let replica = &leader_info["slave0"];
+    assert!(replica.starts_with(&format!("ip=127.0.0.1,port={},state=online", follower_p.port)));
+    assert_eq!(follower_info["role"], "follower");
+    assert_eq!(follower_info["master_replid"], leader_info["master_replid"]);
+    assert_eq!(follower_info["master_host"], "127.0.0.1");
+    assert_eq!(follower_info["master_port"], leader_p.port.to_string());
+    assert_eq!(follower_info["master_link_status"], "up");
+    Ok(())
+}
@@ -82,8 +82,7 @@ fn run_leader_election_twice(with_append_only: bool) -> anyhow::Result<()> {
     let mut processes = vec![];
     for mut f in [follower_p1, follower_p2] {
         let mut handler = Client::new(f.port);
-        let res = handler.send_and_get_vec("info replication", 4);
-        if !res.contains(&"role:leader".to_string()) {
+        if handler.info("replication")["role"] != "leader" {
             processes.push(f);
             continue;
         }
@@ -0,0 +1,32 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_linearizable_read(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut leader_env = ServerEnv::default().with_append_only(with_append_only);
+    let mut follower_env1 = ServerEnv::default().with_append_only(with_append_only);
+    let mut follower_env2 = ServerEnv::default().with_append_only(with_append_only);
+    let [leader_p, _follower_p1, _follower_p2] =
+        form_cluster([&mut leader_env, &mut follower_env1, &mut follower_env2]);
+
+    let mut h = Client::new(leader_p.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+    assert_eq!(h.send_and_get("SET baz qux"), "OK");
+
+    // WHEN - reads confirm leadership with the replicas before being served
+    // THEN
+    assert_eq!(h.send_and_get("GET foo LINEARIZABLE"), "bar");
+    assert_eq!(
+        h.send_and_get_vec("MGET foo baz LINEARIZABLE", 2),
+        vec!["1) \"bar\"", "2) \"qux\""]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_linearizable_read() -> anyhow::Result<()> {
+    run_linearizable_read(false)?;
+    run_linearizable_read(true)?;
+
+    Ok(())
+}
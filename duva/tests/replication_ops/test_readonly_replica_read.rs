@@ -0,0 +1,40 @@
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_readonly_replica_read(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let env = ServerEnv::default().with_append_only(with_append_only);
+    let leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(leader_p.port);
+    h.send_and_get("SET foo bar");
+
+    let repl_env = ServerEnv::default()
+        .with_bind_addr(leader_p.bind_addr())
+        .with_append_only(with_append_only);
+    let replica_process = spawn_server_process(&repl_env)?;
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX));
+
+    // WHEN
+    let mut client_to_repl = Client::new(replica_process.port);
+    assert_eq!(client_to_repl.send_and_get("READONLY"), "OK");
+
+    // THEN - reads are served by the replica
+    assert_eq!(client_to_repl.send_and_get("GET foo"), "bar");
+
+    // THEN - writes are redirected to the leader
+    assert_eq!(
+        client_to_repl.send_and_get("SET foo baz"),
+        format!("(error) MOVED {}", leader_p.bind_addr())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_readonly_replica_read() -> anyhow::Result<()> {
+    run_readonly_replica_read(false)?;
+    run_readonly_replica_read(true)?;
+
+    Ok(())
+}
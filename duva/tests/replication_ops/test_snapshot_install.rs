@@ -0,0 +1,103 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use duva::prelude::LEADER_HEARTBEAT_INTERVAL_MAX;
+
+use crate::common::{Client, ServerEnv, spawn_server_process};
+
+fn run_snapshot_install_on_replica_behind_compacted_log(
+    with_append_only: bool,
+) -> anyhow::Result<()> {
+    // GIVEN - a leader that compacts its log every 5 entries
+    let env = ServerEnv::default().with_append_only(with_append_only).with_snapshot_threshold(5);
+    let leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(leader_p.port);
+
+    for i in 0..10 {
+        h.send_and_get(format!("SET key{i} value{i}"));
+    }
+    // wait for the high water mark to settle and compaction to kick in
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+
+    // WHEN - a replica joins after the log has been compacted
+    let repl_env = ServerEnv::default()
+        .with_bind_addr(leader_p.bind_addr())
+        .with_append_only(with_append_only);
+    let replica_process = spawn_server_process(&repl_env)?;
+
+    // THEN - the replica receives the snapshot
+    let mut client_to_repl = Client::new(replica_process.port);
+    assert_eq!(client_to_repl.send_and_get_vec("KEYS *", 10).len(), 10);
+    assert_eq!(client_to_repl.send_and_get("GET key3"), "value3");
+
+    // THEN - and keeps up with the tail of the log
+    h.send_and_get("SET after snapshot");
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(client_to_repl.send_and_get("GET after"), "snapshot");
+
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_install_on_replica_behind_compacted_log() -> anyhow::Result<()> {
+    run_snapshot_install_on_replica_behind_compacted_log(false)?;
+    run_snapshot_install_on_replica_behind_compacted_log(true)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_install_after_leader_restart() -> anyhow::Result<()> {
+    // GIVEN - a leader that compacted its log, dropping the WAL segments covered, then restarted
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let env = ServerEnv::default()
+        .with_append_only(true)
+        .with_snapshot_threshold(5)
+        .with_wal_segment_size(64)
+        .with_file_name(format!("test_snapshot_restart_{timestamp}.rdb"));
+    let mut leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(leader_p.port);
+    for i in 0..10 {
+        h.send_and_get(format!("SET key{i} value{i}"));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+    let _ = leader_p.terminate();
+    let leader_p = spawn_server_process(&env)?;
+
+    // WHEN - a replica joins, with the compacted entries only in the leader's snapshot
+    let repl_env = ServerEnv::default().with_bind_addr(leader_p.bind_addr());
+    let replica_process = spawn_server_process(&repl_env)?;
+
+    // THEN
+    let mut client_to_repl = Client::new(replica_process.port);
+    assert_eq!(client_to_repl.send_and_get_vec("KEYS *", 10).len(), 10);
+    assert_eq!(client_to_repl.send_and_get("GET key3"), "value3");
+
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_catchup_on_replica_far_behind_log() -> anyhow::Result<()> {
+    // GIVEN - a leader that never compacts on its own but catches up replicas 5 entries behind with a snapshot
+    let env = ServerEnv::default().with_snapshot_catchup_lag(5);
+    let leader_p = spawn_server_process(&env)?;
+    let mut h = Client::new(leader_p.port);
+    for i in 0..20 {
+        h.send_and_get(format!("SET key{i} value{i}"));
+    }
+
+    // WHEN - a replica joins with none of the log
+    let repl_env = ServerEnv::default().with_bind_addr(leader_p.bind_addr());
+    let replica_process = spawn_server_process(&repl_env)?;
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+
+    // THEN - it ends up with the whole state, and keeps up with the tail of the log
+    let mut client_to_repl = Client::new(replica_process.port);
+    assert_eq!(client_to_repl.send_and_get_vec("KEYS *", 20).len(), 20);
+    assert_eq!(client_to_repl.send_and_get("GET key13"), "value13");
+
+    h.send_and_get("SET after snapshot");
+    std::thread::sleep(std::time::Duration::from_millis(LEADER_HEARTBEAT_INTERVAL_MAX + 1));
+    assert_eq!(client_to_repl.send_and_get("GET after"), "snapshot");
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
+    TraceService, TraceServiceServer,
+};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tonic::transport::server::TcpIncoming;
+
+// * Name and trace id of an exported span
+type ExportedSpan = (String, Vec<u8>);
+
+/// Accepts OTLP/gRPC exports and keeps every span.
+#[derive(Clone, Default)]
+struct Collector(Arc<Mutex<Vec<ExportedSpan>>>);
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        request: tonic::Request<ExportTraceServiceRequest>,
+    ) -> Result<tonic::Response<ExportTraceServiceResponse>, tonic::Status> {
+        let spans = request
+            .into_inner()
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource| resource.scope_spans)
+            .flat_map(|scope| scope.spans);
+        self.0.lock().unwrap().extend(spans.map(|span| (span.name, span.trace_id)));
+        Ok(tonic::Response::new(ExportTraceServiceResponse::default()))
+    }
+}
+
+impl Collector {
+    /// Trace ids of the exported spans named `name`.
+    fn trace_ids(&self, name: &str) -> Vec<Vec<u8>> {
+        let spans = self.0.lock().unwrap();
+        spans
+            .iter()
+            .filter(|(span, _)| span == name)
+            .map(|(_, trace_id)| trace_id.clone())
+            .collect()
+    }
+}
+
+fn spawn_collector() -> (String, Collector) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = listener.local_addr().unwrap().to_string();
+    listener.set_nonblocking(true).unwrap();
+    let collector = Collector::default();
+    let service = TraceServiceServer::new(collector.clone());
+    std::thread::spawn(move || {
+        tokio::runtime::Runtime::new().unwrap().block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpIncoming::from(listener))
+                .await
+                .unwrap();
+        })
+    });
+    (endpoint, collector)
+}
+
+#[test]
+fn test_client_write_is_traced_across_leader_and_replica() -> anyhow::Result<()> {
+    // GIVEN
+    let (endpoint, collector) = spawn_collector();
+    let mut leader_env = ServerEnv::default().with_otlp_endpoint(&endpoint);
+    let mut follower_env = ServerEnv::default().with_otlp_endpoint(&endpoint);
+    let [leader_p, _follower_p] = form_cluster([&mut leader_env, &mut follower_env]);
+
+    // WHEN
+    let mut h = Client::new(leader_p.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // THEN
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let writes = collector.trace_ids("client_write");
+        let appends = collector.trace_ids("append_entries");
+        if let Some(trace_id) = writes.first()
+            && appends.contains(trace_id)
+        {
+            break;
+        }
+        assert!(Instant::now() < deadline, "writes: {writes:?}, appends: {appends:?}");
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    Ok(())
+}
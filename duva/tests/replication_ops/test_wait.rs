@@ -0,0 +1,31 @@
+use crate::common::{Client, ServerEnv, form_cluster};
+
+fn run_wait(with_append_only: bool) -> anyhow::Result<()> {
+    // GIVEN
+    let mut leader_env = ServerEnv::default().with_append_only(with_append_only);
+    let mut follower_env1 = ServerEnv::default().with_append_only(with_append_only);
+    let mut follower_env2 = ServerEnv::default().with_append_only(with_append_only);
+    let [leader_p, _follower_p1, _follower_p2] =
+        form_cluster([&mut leader_env, &mut follower_env1, &mut follower_env2]);
+
+    let mut h = Client::new(leader_p.port);
+    assert_eq!(h.send_and_get("SET foo bar"), "OK");
+
+    // WHEN - waiting for both replicas to acknowledge the write
+    // THEN
+    assert_eq!(h.send_and_get("WAIT 2 2000"), "(integer) 2");
+
+    // WHEN - asking for more replicas than the shard has
+    // THEN - the timeout kicks in and the acknowledged count is returned
+    assert_eq!(h.send_and_get("WAIT 3 200"), "(integer) 2");
+
+    Ok(())
+}
+
+#[test]
+fn test_wait() -> anyhow::Result<()> {
+    run_wait(false)?;
+    run_wait(true)?;
+
+    Ok(())
+}